@@ -17,17 +17,34 @@ pub fn example_kernel2x2_dummy_float(kernel: kernel::MutKernel2x2<f32>) {
     *kernel.br -= delta;
 }
 
+#[inline(never)]
+pub fn example_kernel_generic_dummy_float(mut kernel: kernel::MutKernel<f32, 2, 2>) {
+    let value = |kernel: &kernel::MutKernel<f32, 2, 2>, kx, ky| kernel.get(kx, ky).copied().unwrap_or_default();
+    let delta = black_box(value(&kernel, 0, 0) * 0.5 + value(&kernel, 1, 0) * 0.3 + value(&kernel, 0, 1) * 0.2 + value(&kernel, 1, 1) * 0.1);
+    for (kx, ky, sign) in [(0, 0, 1.0), (1, 0, -1.0), (0, 1, 1.0), (1, 1, -1.0)] {
+        if let Some(element) = kernel.get_mut(kx, ky) {
+            *element += sign * delta;
+        }
+    }
+}
+
 fn linkedlist_push_back_benchmark(c: &mut Criterion) {
     let mut group = c.benchmark_group("Kernel2x2_comparison");
     let loops = 1;
     let mut matrix = kernel_2x2_benchmarking_gen_data();
 
 
-    group.bench_with_input(BenchmarkId::new("Kernel Unsafe Dummy", loops), &loops, |b, &_loops| {
+    group.bench_with_input(BenchmarkId::new("Kernel Dummy", loops), &loops, |b, &_loops| {
         b.iter(|| {
             kernel::apply_2x2_kernel_processing(&mut matrix, example_kernel2x2_dummy_float);
         });
     });
+
+    group.bench_with_input(BenchmarkId::new("Kernel Generic 2x2 Dummy", loops), &loops, |b, &_loops| {
+        b.iter(|| {
+            kernel::apply_kernel_processing(&mut matrix, (0, 0), kernel::EdgePolicy::Skip, example_kernel_generic_dummy_float);
+        });
+    });
 }
 
 fn configure_criterion() -> Criterion {
@@ -0,0 +1,24 @@
+//! Textual values of CLI flags and config files, malformed ones have to be rejected with error.
+#![no_main]
+
+use ditherum::{
+    algorithms::{dithering::ChannelDecay, outline::Outline, posterize::ChannelLevels, tiles::TileConstraint},
+    color::ColorRGB,
+    image::ProcessingAlgorithm,
+    palette::{ImageRegion, MinCoverage, PaletteExtractOptions, PaletteSampling}
+};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|text: &str| {
+    let _ = text.parse::<ColorRGB>();
+    let _ = text.parse::<ProcessingAlgorithm>();
+    let _ = text.parse::<PaletteSampling>();
+    let _ = text.parse::<MinCoverage>();
+    let _ = text.parse::<PaletteExtractOptions>();
+    let _ = text.parse::<ImageRegion>();
+    let _ = text.parse::<Outline>();
+    let _ = text.parse::<TileConstraint>();
+    let _ = text.parse::<ChannelLevels>();
+    let _ = text.parse::<ChannelDecay>();
+    let _ = ditherum::config::ProcessingConfig::from_toml_str(text);
+});
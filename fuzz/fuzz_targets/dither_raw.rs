@@ -0,0 +1,17 @@
+//! Raw interleaved buffers with declared dimensions, mismatching lengths have to be rejected with error.
+#![no_main]
+
+use ditherum::{api::PaletteStrategy, image::ProcessingAlgorithm, palette::PaletteRGB, DitherOptions};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // Header: width, height, algorithm index, then pixels
+    let [width, height, algorithm, buf @ ..] = data else {
+        return;
+    };
+    let algorithms = ProcessingAlgorithm::all();
+    let options = DitherOptions::default()
+        .with_algorithm(algorithms[*algorithm as usize % algorithms.len()])
+        .with_palette(PaletteStrategy::Fixed(PaletteRGB::primary_bw()));
+    let _ = ditherum::dither_raw(buf, *width as u32, *height as u32, options);
+});
@@ -0,0 +1,22 @@
+//! Palette images like Lospec PNG strips, decoded the same way as `--palette strip.png`.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+/// Cap only keeps fuzzing fast, palette loading itself accepts images of any size.
+const MAX_PIXELS: u64 = 1 << 16;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(reader) = image::ImageReader::new(std::io::Cursor::new(data)).with_guessed_format() else {
+        return;
+    };
+    let Ok((width, height)) = reader.into_dimensions() else {
+        return;
+    };
+    if width as u64 * height as u64 > MAX_PIXELS {
+        return;
+    }
+    if let Ok(img) = image::load_from_memory(data) {
+        let _ = ditherum::palette::PaletteRGB::from_palette_image(&img.to_rgb8());
+    }
+});
@@ -0,0 +1,8 @@
+//! Palette JSON documents of any schema version, malformed ones have to be rejected with error.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = ditherum::palette::PaletteRGB::load_from_json_reader(data);
+});
@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+
+use image::{GrayImage, RgbImage};
+use palette::{color_difference::EuclideanDistance, FromColor};
+
+use crate::palette::PaletteRGB;
+
+/// Side of square cell sharing single decision, so every pattern spans at least 2x2 pixels.
+const CELL_SIZE: u32 = 2;
+
+/// Either a solid palette color or 50% checkerboard of two palette colors.
+#[derive(Debug, Clone, Copy)]
+struct CellFill {
+    /// Color of pixels with even `x + y`.
+    even_idx: usize,
+
+    /// Color of pixels with odd `x + y`.
+    odd_idx: usize,
+}
+
+/// Applies dithering constrained to solid colors and 50% checkerboards of two palette colors.
+///
+/// # Parameters
+/// - `source_image`: The input `RgbImage` to be dithered.
+/// - `palette`: A `PaletteRGB` containing the target colors for dithering.
+///
+/// # Returns
+/// - A dithered `RgbImage` containing only palette colors.
+///
+/// # Algorithm Details
+/// Image is split into 2x2 cells, each gets the solid palette color or the checkerboard
+/// of two palette colors whose mix, averaged in linear light, is the closest to the cell
+/// mean in Lab space. Checkerboard phase is global, so neighbouring cells of the same mix
+/// join into one regular pattern. There are no isolated stray pixels, which suits knitting
+/// charts, cross-stitch or brick mosaics, where every pixel has to be placed by hand.
+/// Cost grows with square of palette size.
+///
+/// # Example
+/// ```
+/// use ditherum::{algorithms::checkerboard::checkerboard_dithering, palette::PaletteRGB};
+///
+/// let source_image = image::RgbImage::from_pixel(4, 4, image::Rgb([188, 188, 188]));
+/// let processed_image = checkerboard_dithering(source_image, PaletteRGB::black_and_white());
+///
+/// // Perceptual middle gray becomes black and white checkerboard
+/// assert_ne!(processed_image.get_pixel(0, 0), processed_image.get_pixel(1, 0));
+/// assert_eq!(processed_image.get_pixel(0, 0), processed_image.get_pixel(1, 1));
+/// ```
+pub fn checkerboard_dithering(source_image: RgbImage, palette: PaletteRGB) -> RgbImage {
+    let mut target_image = RgbImage::new(source_image.width(), source_image.height());
+    checkerboard_dithering_into(&source_image, &palette, &mut target_image);
+    target_image
+}
+
+/// Same as [`checkerboard_dithering`], but writes result to `target_image`, reallocating it
+/// only if its dimensions differ from the source ones.
+pub fn checkerboard_dithering_into(source_image: &RgbImage, palette: &PaletteRGB, target_image: &mut RgbImage) {
+    crate::image::manip::match_dimensions(target_image, source_image);
+    checkerboard(source_image, palette, |_, _| true, target_image);
+}
+
+/// Applies checkerboard constrained dithering with checkerboards allowed only where mask is mostly white.
+///
+/// Cells whose mean mask value is below half get the closest solid color.
+///
+/// # Panics
+/// Panics if mask dimensions differ from image dimensions.
+pub fn checkerboard_dithering_masked(source_image: RgbImage, palette: PaletteRGB, mask: &GrayImage) -> RgbImage {
+    let mut target_image = RgbImage::new(source_image.width(), source_image.height());
+    checkerboard_dithering_masked_into(&source_image, &palette, mask, &mut target_image);
+    target_image
+}
+
+/// Same as [`checkerboard_dithering_masked`], but writes result to `target_image`, reallocating it
+/// only if its dimensions differ from the source ones.
+///
+/// # Panics
+/// Panics if mask dimensions differ from image dimensions.
+pub fn checkerboard_dithering_masked_into(source_image: &RgbImage, palette: &PaletteRGB, mask: &GrayImage, target_image: &mut RgbImage) {
+    assert_eq!(source_image.dimensions(), mask.dimensions(), "Mask dimensions differ from image dimensions.");
+    crate::image::manip::match_dimensions(target_image, source_image);
+    checkerboard(source_image, palette, |x, y| mean_mask_value(mask, x, y) >= u8::MAX as u32 / 2, target_image);
+}
+
+fn cell_pixels(width: u32, height: u32, cell_x: u32, cell_y: u32) -> impl Iterator<Item = (u32, u32)> {
+    (cell_y..(cell_y + CELL_SIZE).min(height))
+        .flat_map(move |y| (cell_x..(cell_x + CELL_SIZE).min(width)).map(move |x| (x, y)))
+}
+
+fn mean_mask_value(mask: &GrayImage, cell_x: u32, cell_y: u32) -> u32 {
+    let values = cell_pixels(mask.width(), mask.height(), cell_x, cell_y)
+        .map(|(x, y)| mask.get_pixel(x, y).0[0] as u32)
+        .collect::<Vec<_>>();
+    values.iter().sum::<u32>() / values.len() as u32
+}
+
+fn checkerboard<M>(source_image: &RgbImage, palette: &PaletteRGB, allow_mix: M, target_image: &mut RgbImage)
+where
+    M: Fn(u32, u32) -> bool
+{
+    assert!(!palette.is_empty(), "Palette is empty.");
+    let (width, height) = source_image.dimensions();
+
+    let linear_palette = palette.iter()
+        .map(|color| color.to_srgb().into_linear())
+        .collect::<Vec<palette::LinSrgb>>();
+    let fills = (0..palette.len())
+        .flat_map(|even_idx| (even_idx..palette.len()).map(move |odd_idx| CellFill { even_idx, odd_idx }))
+        .map(|fill| {
+            let mixed_color = (linear_palette[fill.even_idx] + linear_palette[fill.odd_idx]) * 0.5;
+            (palette::Lab::from_color(mixed_color), fill)
+        })
+        .collect::<Vec<_>>();
+
+    // Cell means repeat a lot in flat areas and pixel art
+    let mut fills_cache: HashMap<([u8; 3], bool), CellFill> = HashMap::new();
+
+    for cell_y in (0..height).step_by(CELL_SIZE as usize) {
+        for cell_x in (0..width).step_by(CELL_SIZE as usize) {
+            let pixels = cell_pixels(width, height, cell_x, cell_y).collect::<Vec<_>>();
+            let linear_sum = pixels.iter()
+                .map(|&(x, y)| crate::color::ops::rgbu8_to_srgb(*source_image.get_pixel(x, y)).into_linear())
+                .fold(palette::LinSrgb::new(0.0, 0.0, 0.0), |sum, color| sum + color);
+            let mean_color = palette::Srgb::from_linear(linear_sum / pixels.len() as f32);
+            let mean_key = crate::color::ops::srgb_to_rgbu8(mean_color).0;
+            let is_mix_allowed = allow_mix(cell_x, cell_y);
+
+            let fill = *fills_cache.entry((mean_key, is_mix_allowed)).or_insert_with(|| {
+                let mean_lab = palette::Lab::from_color(mean_color.into_linear());
+                fills.iter()
+                    .filter(|(_, fill)| is_mix_allowed || fill.even_idx == fill.odd_idx)
+                    .min_by(|(lab_a, _), (lab_b, _)| mean_lab.distance_squared(*lab_a)
+                        .partial_cmp(&mean_lab.distance_squared(*lab_b))
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                    )
+                    .map(|(_, fill)| *fill)
+                    .unwrap()
+            });
+
+            for (x, y) in pixels {
+                let color_idx = if (x + y) % 2 == 0 { fill.even_idx } else { fill.odd_idx };
+                target_image.put_pixel(x, y, palette[color_idx].to_rgbu8());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_isolated_pixels() {
+        let source_image = crate::image::generate_test_gradient_image(32, 8, image::Rgb([0, 0, 0]), image::Rgb([255, 255, 255]));
+        let processed_image = checkerboard_dithering(source_image, PaletteRGB::black_and_white());
+
+        // Every 2x2 cell is solid or checkerboard
+        for cell_y in (0..8).step_by(2) {
+            for cell_x in (0..32).step_by(2) {
+                let tl = processed_image.get_pixel(cell_x, cell_y);
+                let tr = processed_image.get_pixel(cell_x + 1, cell_y);
+                assert_eq!(tl, processed_image.get_pixel(cell_x + 1, cell_y + 1));
+                assert_eq!(tr, processed_image.get_pixel(cell_x, cell_y + 1));
+            }
+        }
+
+        let colors_count = crate::image::count_image_colors(&processed_image).len();
+        assert_eq!(colors_count, 2);
+    }
+
+    #[test]
+    fn test_black_mask_gives_solid_cells() {
+        let source_image = RgbImage::from_pixel(6, 5, image::Rgb([188, 188, 188]));
+        let black_mask = GrayImage::new(6, 5);
+        let processed_image = checkerboard_dithering_masked(source_image, PaletteRGB::black_and_white(), &black_mask);
+        assert_eq!(crate::image::count_image_colors(&processed_image).len(), 1);
+    }
+}
@@ -0,0 +1,166 @@
+use std::{cmp::Reverse, collections::{BinaryHeap, HashMap}};
+
+use image::RgbImage;
+
+/// Removes small islands of a single color left by dithering.
+///
+/// # Parameters
+/// - `source_image`: Quantized `RgbImage`, islands are edge connected areas of exactly the same color.
+/// - `min_size`: Islands with fewer pixels are removed, `1` leaves image unchanged.
+///
+/// # Returns
+/// - An `RgbImage` of the same dimensions with small islands recolored, no new colors are introduced.
+///
+/// # Algorithm Details
+/// Image is split into 4-connected components of equal color. The smallest component below
+/// `min_size` is merged into the neighbor sharing the longest border with it, ties broken by
+/// larger neighbor, and takes its color. Merging repeats until every component reaches
+/// `min_size` or has no neighbors left, so checkerboard areas consisting of single pixel
+/// islands collapse into solid ones instead of swapping colors.
+///
+/// # Example
+/// ```
+/// use ditherum::algorithms::despeckle::despeckle;
+///
+/// let mut source_image = image::RgbImage::from_pixel(5, 5, image::Rgb([255, 255, 255]));
+/// source_image.put_pixel(2, 2, image::Rgb([0, 0, 0]));
+/// let cleaned_image = despeckle(&source_image, 2);
+///
+/// assert_eq!(cleaned_image.get_pixel(2, 2).0, [255, 255, 255]);
+/// ```
+pub fn despeckle(source_image: &RgbImage, min_size: usize) -> RgbImage {
+    let (width, height) = (source_image.width() as usize, source_image.height() as usize);
+    let (labels, mut sizes) = label_components(source_image);
+    if !sizes.iter().any(|&size| size < min_size) {
+        return source_image.clone();
+    }
+
+    let mut colors = vec![image::Rgb([0, 0, 0]); sizes.len()];
+    for (index, &label) in labels.iter().enumerate() {
+        colors[label] = *source_image.get_pixel((index % width) as u32, (index / width) as u32);
+    }
+
+    // Border length between each pair of neighboring components
+    let mut borders: Vec<HashMap<usize, usize>> = vec![HashMap::new(); sizes.len()];
+    for y in 0..height {
+        for x in 0..width {
+            let label = labels[y * width + x];
+            let neighbors = [
+                (x + 1 < width).then(|| labels[y * width + x + 1]),
+                (y + 1 < height).then(|| labels[(y + 1) * width + x]),
+            ];
+            for neighbor_label in neighbors.into_iter().flatten().filter(|&neighbor_label| neighbor_label != label) {
+                *borders[label].entry(neighbor_label).or_default() += 1;
+                *borders[neighbor_label].entry(label).or_default() += 1;
+            }
+        }
+    }
+
+    // Merged components point to the one they were merged into
+    let mut parents = (0..sizes.len()).collect::<Vec<_>>();
+    let mut queue = sizes.iter()
+        .enumerate()
+        .filter(|(_, &size)| size < min_size)
+        .map(|(label, &size)| Reverse((size, label)))
+        .collect::<BinaryHeap<_>>();
+    while let Some(Reverse((size, label))) = queue.pop() {
+        if parents[label] != label || sizes[label] != size {
+            continue;
+        }
+        let Some(target) = borders[label].iter()
+            .max_by_key(|(&neighbor_label, &length)| (length, sizes[neighbor_label], Reverse(neighbor_label)))
+            .map(|(&neighbor_label, _)| neighbor_label) else {
+            continue;
+        };
+
+        let merged_borders = std::mem::take(&mut borders[label]);
+        for (neighbor_label, length) in merged_borders {
+            borders[neighbor_label].remove(&label);
+            if neighbor_label != target {
+                *borders[neighbor_label].entry(target).or_default() += length;
+                *borders[target].entry(neighbor_label).or_default() += length;
+            }
+        }
+        parents[label] = target;
+        sizes[target] += size;
+        if sizes[target] < min_size {
+            queue.push(Reverse((sizes[target], target)));
+        }
+    }
+
+    let root = |mut label: usize| {
+        while parents[label] != label {
+            label = parents[label];
+        }
+        label
+    };
+    RgbImage::from_fn(width as u32, height as u32, |x, y| colors[root(labels[y as usize * width + x as usize])])
+}
+
+/// Labels 4-connected components of equal color, returns label of each pixel and size of each component.
+fn label_components(source_image: &RgbImage) -> (Vec<usize>, Vec<usize>) {
+    let (width, height) = (source_image.width() as usize, source_image.height() as usize);
+    let mut labels = vec![usize::MAX; width * height];
+    let mut sizes = Vec::new();
+    let mut stack = Vec::new();
+
+    for start in 0..width * height {
+        if labels[start] != usize::MAX {
+            continue;
+        }
+        let label = sizes.len();
+        let color = source_image.get_pixel((start % width) as u32, (start / width) as u32);
+        let mut size = 0;
+        labels[start] = label;
+        stack.push(start);
+        while let Some(index) = stack.pop() {
+            size += 1;
+            let (x, y) = (index % width, index / width);
+            let neighbors = [
+                (x > 0).then(|| index - 1),
+                (x + 1 < width).then(|| index + 1),
+                (y > 0).then(|| index - width),
+                (y + 1 < height).then(|| index + width),
+            ];
+            for neighbor in neighbors.into_iter().flatten() {
+                if labels[neighbor] == usize::MAX
+                    && source_image.get_pixel((neighbor % width) as u32, (neighbor / width) as u32) == color {
+                    labels[neighbor] = label;
+                    stack.push(neighbor);
+                }
+            }
+        }
+        sizes.push(size);
+    }
+
+    (labels, sizes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_despeckle_removes_small_islands() {
+        let white = image::Rgb([255, 255, 255]);
+        let black = image::Rgb([0, 0, 0]);
+        let red = image::Rgb([255, 0, 0]);
+        // Left half white, right half black, speck pair of red in white and a single white speck in black
+        let mut source_image = RgbImage::from_fn(10, 6, |x, _| if x < 5 { white } else { black });
+        source_image.put_pixel(1, 1, red);
+        source_image.put_pixel(2, 1, red);
+        source_image.put_pixel(7, 3, white);
+
+        let cleaned_image = despeckle(&source_image, 3);
+        assert_eq!(*cleaned_image.get_pixel(1, 1), white);
+        assert_eq!(*cleaned_image.get_pixel(2, 1), white);
+        assert_eq!(*cleaned_image.get_pixel(7, 3), black);
+        assert_eq!(crate::image::count_image_colors(&cleaned_image).len(), 2);
+
+        // Islands of 2 pixels survive threshold 2
+        let cleaned_image = despeckle(&source_image, 2);
+        assert_eq!(*cleaned_image.get_pixel(1, 1), red);
+        assert_eq!(*cleaned_image.get_pixel(7, 3), black);
+        assert_eq!(despeckle(&source_image, 1), source_image);
+    }
+}
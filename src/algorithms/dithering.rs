@@ -1,6 +1,218 @@
-use image::RgbImage;
+use std::{fmt::Display, str::FromStr};
+
+use image::{GrayImage, Rgb32FImage, RgbImage};
 use crate::{color, palette::PaletteRGB};
-use crate::algorithms::kernel;
+use crate::algorithms::kernel::{self, ERROR_WEIGHTS_2X2};
+
+pub use crate::algorithms::kernel::DEFAULT_ERROR_OVERSHOOT;
+
+/// Error returned when parsing error decay fails.
+#[derive(Debug, thiserror::Error)]
+#[error("Invalid error decay '{0}', expected factor like '0.9' or per channel factors like '1,0.8,0.8' in range 0-1")]
+pub struct ChannelDecayParseError(pub String);
+
+/// Multipliers of error spread along each channel, red, green, blue or L, a, b in Lab space.
+///
+/// Parsed from single factor of all channels like `0.9` or per channel factors like `1,0.8,0.8`.
+///
+/// # Example
+/// ```
+/// use ditherum::algorithms::dithering::ChannelDecay;
+///
+/// assert_eq!("0.5".parse::<ChannelDecay>().unwrap(), ChannelDecay([0.5, 0.5, 0.5]));
+/// assert_eq!("1,0.8,0.8".parse::<ChannelDecay>().unwrap(), ChannelDecay([1.0, 0.8, 0.8]));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChannelDecay(pub [f32; 3]);
+
+impl Default for ChannelDecay {
+    fn default() -> Self {
+        Self([1.0; 3])
+    }
+}
+
+impl FromStr for ChannelDecay {
+    type Err = ChannelDecayParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ChannelDecayParseError(s.to_string());
+        let factors = s.split(',')
+            .map(|factor| factor.trim().parse::<f32>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| invalid())?;
+        let factors = match factors[..] {
+            [factor] => [factor; 3],
+            [first, second, third] => [first, second, third],
+            _ => return Err(invalid()),
+        };
+
+        if factors.iter().any(|factor| !(0.0..=1.0).contains(factor)) {
+            return Err(invalid());
+        }
+        Ok(Self(factors))
+    }
+}
+
+impl Display for ChannelDecay {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let [first, second, third] = self.0;
+        write!(f, "{first},{second},{third}")
+    }
+}
+
+/// Limits of quantization error accumulated by Floyd-Steinberg diffusion.
+///
+/// Saturated colors missing from palette keep pushing error in the same direction, so
+/// accumulated values run far outside of valid range and the error is released as streaks
+/// long after the color area ends. Clamping accumulated values just beyond the range
+/// prevents it, decay additionally weakens error spread along chosen channels.
+///
+/// # Example
+/// ```
+/// use ditherum::algorithms::dithering::{ChannelDecay, ErrorDiffusion};
+///
+/// let diffusion = ErrorDiffusion::default()
+///     .with_overshoot(Some(0.0))
+///     .with_decay(ChannelDecay([1.0, 0.8, 0.8]));
+/// assert_eq!(diffusion.overshoot, Some(0.0));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ErrorDiffusion {
+    /// Fraction of channel range accumulated values may exceed it by, `None` disables clamping.
+    pub overshoot: Option<f32>,
+
+    /// Multipliers of error spread along each channel.
+    pub decay: ChannelDecay,
+
+    /// How much Sobel edges block error spread in range `0.0..=1.0`, `None` disables edge detection.
+    ///
+    /// Error passed between two pixels is scaled by `1 - attenuation * edge`, where `edge`
+    /// is the stronger edge of them, so text and line art stay crisp.
+    pub edge_attenuation: Option<f32>,
+
+    /// Whether error leaving right and bottom edges wraps to the opposite ones, so that result
+    /// tiles seamlessly, see [`kernel::apply_2x2_kernel_processing_wrapped_with_position`].
+    pub tileable: bool,
+}
+
+impl Default for ErrorDiffusion {
+    fn default() -> Self {
+        Self {
+            overshoot: Some(DEFAULT_ERROR_OVERSHOOT),
+            decay: ChannelDecay::default(),
+            edge_attenuation: None,
+            tileable: false,
+        }
+    }
+}
+
+impl ErrorDiffusion {
+    /// Sets fraction of channel range accumulated values may exceed it by, `None` disables clamping.
+    pub fn with_overshoot(mut self, overshoot: Option<f32>) -> Self {
+        self.overshoot = overshoot;
+        self
+    }
+
+    /// Sets multipliers of error spread along each channel.
+    pub fn with_decay(mut self, decay: ChannelDecay) -> Self {
+        self.decay = decay;
+        self
+    }
+
+    /// Sets how much Sobel edges block error spread, see [`ErrorDiffusion::edge_attenuation`].
+    pub fn with_edge_attenuation(mut self, edge_attenuation: Option<f32>) -> Self {
+        self.edge_attenuation = edge_attenuation;
+        self
+    }
+
+    /// Sets whether error wraps around image edges, see [`ErrorDiffusion::tileable`].
+    pub fn with_tileable(mut self, tileable: bool) -> Self {
+        self.tileable = tileable;
+        self
+    }
+
+    fn edge_map(&self, source_image: &RgbImage) -> Option<EdgeWeights> {
+        self.edge_attenuation.map(|attenuation| EdgeWeights {
+            edge_map: crate::algorithms::edges::sobel_edge_map(source_image),
+            attenuation: attenuation.clamp(0.0, 1.0),
+            wrap: self.tileable,
+        })
+    }
+
+    /// Visits matrix with Floyd-Steinberg 2x2 kernel, wrapping around edges if tileable.
+    fn traverse<T, P>(&self, matrix: &mut [Vec<T>], processing: P)
+    where 
+        T: Clone + Default,
+        P: FnMut(usize, usize, kernel::MutKernel2x2<T>)
+    {
+        if self.tileable {
+            kernel::apply_2x2_kernel_processing_wrapped_with_position(matrix, processing);
+        } else {
+            kernel::apply_2x2_kernel_processing_with_position(matrix, processing);
+        }
+    }
+
+    fn clamp_channel(&self, value: f32, min: f32, max: f32) -> f32 {
+        match self.overshoot {
+            Some(overshoot) => {
+                let margin = (max - min) * overshoot.max(0.0);
+                value.clamp(min - margin, max + margin)
+            },
+            None => value,
+        }
+    }
+
+    pub(crate) fn spread_srgb(&self, pixel: &mut palette::Srgb, quant_error: &palette::Srgb, weight: f32) {
+        let [decay_red, decay_green, decay_blue] = self.decay.0;
+        pixel.red = self.clamp_channel(pixel.red + quant_error.red * weight * decay_red, 0.0, 1.0);
+        pixel.green = self.clamp_channel(pixel.green + quant_error.green * weight * decay_green, 0.0, 1.0);
+        pixel.blue = self.clamp_channel(pixel.blue + quant_error.blue * weight * decay_blue, 0.0, 1.0);
+    }
+
+    fn spread_lab(&self, pixel: &mut palette::Lab, quant_error: &palette::Lab, weight: f32) {
+        let [decay_l, decay_a, decay_b] = self.decay.0;
+        pixel.l = self.clamp_channel(pixel.l + quant_error.l * weight * decay_l, 0.0, 100.0);
+        pixel.a = self.clamp_channel(pixel.a + quant_error.a * weight * decay_a, -128.0, 127.0);
+        pixel.b = self.clamp_channel(pixel.b + quant_error.b * weight * decay_b, -128.0, 127.0);
+    }
+}
+
+/// Sobel edge map scaling error passed between pixels.
+struct EdgeWeights {
+    edge_map: GrayImage,
+    attenuation: f32,
+    wrap: bool,
+}
+
+impl EdgeWeights {
+    /// Returns multiplier of error passed from pixel to its neighbour, neighbours outside of image get none
+    /// unless they wrap to the opposite side.
+    fn between(&self, (x, y): (usize, usize), (neighbour_x, neighbour_y): (usize, usize)) -> f32 {
+        let (width, height) = self.edge_map.dimensions();
+        let (neighbour_x, neighbour_y) = match self.wrap {
+            true => (neighbour_x % width as usize, neighbour_y % height as usize),
+            false => (neighbour_x, neighbour_y),
+        };
+        if neighbour_x >= width as usize || neighbour_y >= height as usize {
+            return 0.0;
+        }
+        let edge = self.edge_map.get_pixel(x as u32, y as u32).0[0]
+            .max(self.edge_map.get_pixel(neighbour_x as u32, neighbour_y as u32).0[0]);
+        1.0 - self.attenuation * edge as f32 / 255.0
+    }
+
+    /// Returns multipliers of error passed to top-right, bottom-left and bottom-right neighbours.
+    fn kernel(edges: Option<&Self>, x: usize, y: usize) -> (f32, f32, f32) {
+        match edges {
+            Some(edges) => (
+                edges.between((x, y), (x + 1, y)),
+                edges.between((x, y), (x, y + 1)),
+                edges.between((x, y), (x + 1, y + 1)),
+            ),
+            None => (1.0, 1.0, 1.0),
+        }
+    }
+}
 
 /// Applies Floyd-Steinberg dithering to an RGB image using a given color palette.
 ///
@@ -21,35 +233,360 @@ use crate::algorithms::kernel;
 ///   *    *   (error distribution)
 /// ```
 pub fn dithering_floyd_steinberg_rgb(source_image: RgbImage, palette: PaletteRGB) -> RgbImage {
-    let (width, height, mut rgb_matrix) = crate::image::manip::rgb_image_to_float_srgb_vec(source_image);
+    let mut target_image = RgbImage::new(source_image.width(), source_image.height());
+    floyd_steinberg_rgb(srgb_matrix(&source_image), &palette, &ErrorDiffusion::default(), None, |_, _| 1.0, &mut target_image);
+    target_image
+}
+
+/// Same as [`dithering_floyd_steinberg_rgb`], but writes result to `target_image`, reallocating it
+/// only if its dimensions differ from the source ones.
+pub fn dithering_floyd_steinberg_rgb_into(source_image: &RgbImage, palette: &PaletteRGB, target_image: &mut RgbImage) {
+    crate::image::manip::match_dimensions(target_image, source_image);
+    floyd_steinberg_rgb(srgb_matrix(source_image), palette, &ErrorDiffusion::default(), None, |_, _| 1.0, target_image);
+}
+
+/// Applies Floyd-Steinberg dithering to an RGB image with strength modulated by a mask.
+///
+/// # Parameters
+/// - `source_image`: The input `RgbImage` to be dithered.
+/// - `palette`: A `PaletteRGB` containing the target colors for dithering.
+/// - `mask`: Dithering strength of each pixel, white gives full dithering, black gives plain thresholding.
+///
+/// # Returns
+/// - A dithered `RgbImage` that approximates the input image using the specified palette.
+///
+/// # Panics
+/// Panics if mask dimensions differ from image dimensions.
+pub fn dithering_floyd_steinberg_rgb_masked(source_image: RgbImage, palette: PaletteRGB, mask: &GrayImage) -> RgbImage {
+    let mut target_image = RgbImage::new(source_image.width(), source_image.height());
+    dithering_floyd_steinberg_rgb_masked_into(&source_image, &palette, mask, &mut target_image);
+    target_image
+}
+
+/// Same as [`dithering_floyd_steinberg_rgb_masked`], but writes result to `target_image`, reallocating it
+/// only if its dimensions differ from the source ones.
+///
+/// # Panics
+/// Panics if mask dimensions differ from image dimensions.
+pub fn dithering_floyd_steinberg_rgb_masked_into(source_image: &RgbImage, palette: &PaletteRGB, mask: &GrayImage, target_image: &mut RgbImage) {
+    assert_eq!(source_image.dimensions(), mask.dimensions(), "Mask dimensions differ from image dimensions.");
+    crate::image::manip::match_dimensions(target_image, source_image);
+    floyd_steinberg_rgb(srgb_matrix(source_image), palette, &ErrorDiffusion::default(), None, |x, y| mask_strength(mask, x, y), target_image);
+}
+
+/// Reads dithering strength in range `0.0..=1.0` from the mask.
+pub(crate) fn mask_strength(mask: &GrayImage, x: usize, y: usize) -> f32 {
+    mask.get_pixel(x as u32, y as u32).0[0] as f32 / 255.0
+}
+
+/// Same as [`dithering_floyd_steinberg_rgb`], but takes floating point image, e.g. loaded from
+/// 16-bit file with [`crate::image::load_image_f32`], so it is not quantized to 8 bits beforehand.
+pub fn dithering_floyd_steinberg_rgb_f32(source_image: &Rgb32FImage, palette: &PaletteRGB) -> RgbImage {
+    let mut target_image = RgbImage::new(source_image.width(), source_image.height());
+    let (_, _, rgb_matrix) = crate::image::manip::rgb32f_image_to_float_srgb_vec(source_image);
+    floyd_steinberg_rgb(rgb_matrix, palette, &ErrorDiffusion::default(), None, |_, _| 1.0, &mut target_image);
+    target_image
+}
+
+fn srgb_matrix(source_image: &RgbImage) -> Vec<Vec<palette::Srgb>> {
+    crate::image::manip::rgb_image_ref_to_float_srgb_vec(source_image).2
+}
+
+fn lab_matrix(source_image: &RgbImage) -> Vec<Vec<palette::Lab>> {
+    crate::image::manip::rgb_image_ref_to_lab_vec(source_image).2
+}
+
+/// Applies Floyd-Steinberg dithering in sRGB space with custom limits of accumulated error,
+/// see [`ErrorDiffusion`]. Dithering strength is modulated by `mask` if given, see
+/// [`dithering_floyd_steinberg_rgb_masked`].
+///
+/// # Panics
+/// Panics if mask dimensions differ from image dimensions.
+pub fn dithering_floyd_steinberg_rgb_diffusion_into(source_image: &RgbImage, palette: &PaletteRGB, diffusion: &ErrorDiffusion, mask: Option<&GrayImage>, target_image: &mut RgbImage) {
+    crate::image::manip::match_dimensions(target_image, source_image);
+    let edges = diffusion.edge_map(source_image);
+    match mask {
+        Some(mask) => {
+            assert_eq!(source_image.dimensions(), mask.dimensions(), "Mask dimensions differ from image dimensions.");
+            floyd_steinberg_rgb(srgb_matrix(source_image), palette, diffusion, edges.as_ref(), |x, y| mask_strength(mask, x, y), target_image);
+        },
+        None => floyd_steinberg_rgb(srgb_matrix(source_image), palette, diffusion, edges.as_ref(), |_, _| 1.0, target_image),
+    }
+}
+
+fn floyd_steinberg_rgb<S>(mut rgb_matrix: Vec<Vec<palette::Srgb>>, palette: &PaletteRGB, diffusion: &ErrorDiffusion, edges: Option<&EdgeWeights>, strength: S, target_image: &mut RgbImage)
+where 
+    S: Fn(usize, usize) -> f32
+{
     let srgb_palette = palette.clone().to_srgb();
 
-    kernel::apply_2x2_kernel_processing(&mut rgb_matrix, |kernel| {
-        let closest_tl_color = color::manip::find_closest_srgb_color(kernel.tl , &srgb_palette);
-        let quant_error = color::manip::srgb_sub(kernel.tl, &closest_tl_color);
+    diffusion.traverse(&mut rgb_matrix, |x, y, kernel| {
+        let closest_tl_color = color::ops::find_closest_srgb_color(kernel.tl , &srgb_palette);
+        let quant_error = color::ops::srgb_mul_scalar(
+            &color::ops::srgb_sub(kernel.tl, &closest_tl_color),
+            strength(x, y)
+        );
         *kernel.tl = closest_tl_color;
     
         // Spread quantisation error over remaining 3 pixels
-        // Keep errors weights low to prevent saturation
-        let (err_weight_tr, err_weight_bl, err_weight_br) = (
-            1.5 / 18.0,
-            2.5 / 18.0,
-            4.2 / 18.0,
-        );
+        let (err_weight_tr, err_weight_bl, err_weight_br) = ERROR_WEIGHTS_2X2;
     
-        *kernel.tr = color::manip::srgb_add(
-            kernel.tr, 
-            &color::manip::srgb_mul_scalar(&quant_error, err_weight_tr)
-        );
-        *kernel.bl = color::manip::srgb_add(
-            kernel.bl, 
-            &color::manip::srgb_mul_scalar(&quant_error, err_weight_bl)
-        );
-        *kernel.br = color::manip::srgb_add(
-            kernel.br, 
-            &color::manip::srgb_mul_scalar(&quant_error, err_weight_br)
-        );
+        let (edge_weight_tr, edge_weight_bl, edge_weight_br) = EdgeWeights::kernel(edges, x, y);
+        diffusion.spread_srgb(kernel.tr, &quant_error, err_weight_tr * edge_weight_tr);
+        diffusion.spread_srgb(kernel.bl, &quant_error, err_weight_bl * edge_weight_bl);
+        diffusion.spread_srgb(kernel.br, &quant_error, err_weight_br * edge_weight_br);
+    });
+
+    crate::image::manip::srgb_vec_into_rgb_image_using_palette(&rgb_matrix, palette, target_image);
+}
+
+/// Applies Floyd-Steinberg dithering to an image in Lab space using a given color palette.
+///
+/// # Parameters
+/// - `source_image`: The input `RgbImage` to be dithered.
+/// - `palette`: A `PaletteRGB` containing the target colors for dithering.
+///
+/// # Returns
+/// - A dithered `RgbImage` that approximates the input image using the specified palette.
+///
+/// # Algorithm Details
+/// Same as [`dithering_floyd_steinberg_rgb`], but the closest color is found using CIEDE2000
+/// color difference and quantization error is spread in perceptually uniform Lab space.
+pub fn dithering_floyd_steinberg_lab(source_image: RgbImage, palette: PaletteRGB) -> RgbImage {
+    let mut target_image = RgbImage::new(source_image.width(), source_image.height());
+    floyd_steinberg_lab(lab_matrix(&source_image), &palette, &ErrorDiffusion::default(), None, None, |_, _| 1.0, &mut target_image);
+    target_image
+}
+
+/// Same as [`dithering_floyd_steinberg_lab`], but writes result to `target_image`, reallocating it
+/// only if its dimensions differ from the source ones.
+pub fn dithering_floyd_steinberg_lab_into(source_image: &RgbImage, palette: &PaletteRGB, target_image: &mut RgbImage) {
+    crate::image::manip::match_dimensions(target_image, source_image);
+    floyd_steinberg_lab(lab_matrix(source_image), palette, &ErrorDiffusion::default(), None, None, |_, _| 1.0, target_image);
+}
+
+/// Applies Floyd-Steinberg dithering in Lab space with strength modulated by a mask.
+///
+/// See [`dithering_floyd_steinberg_rgb_masked`].
+///
+/// # Panics
+/// Panics if mask dimensions differ from image dimensions.
+pub fn dithering_floyd_steinberg_lab_masked(source_image: RgbImage, palette: PaletteRGB, mask: &GrayImage) -> RgbImage {
+    let mut target_image = RgbImage::new(source_image.width(), source_image.height());
+    dithering_floyd_steinberg_lab_masked_into(&source_image, &palette, mask, &mut target_image);
+    target_image
+}
+
+/// Same as [`dithering_floyd_steinberg_lab_masked`], but writes result to `target_image`, reallocating it
+/// only if its dimensions differ from the source ones.
+///
+/// # Panics
+/// Panics if mask dimensions differ from image dimensions.
+pub fn dithering_floyd_steinberg_lab_masked_into(source_image: &RgbImage, palette: &PaletteRGB, mask: &GrayImage, target_image: &mut RgbImage) {
+    assert_eq!(source_image.dimensions(), mask.dimensions(), "Mask dimensions differ from image dimensions.");
+    crate::image::manip::match_dimensions(target_image, source_image);
+    floyd_steinberg_lab(lab_matrix(source_image), palette, &ErrorDiffusion::default(), None, None, |x, y| mask_strength(mask, x, y), target_image);
+}
+
+/// Same as [`dithering_floyd_steinberg_lab`], but takes floating point image, see [`dithering_floyd_steinberg_rgb_f32`].
+pub fn dithering_floyd_steinberg_lab_f32(source_image: &Rgb32FImage, palette: &PaletteRGB) -> RgbImage {
+    let mut target_image = RgbImage::new(source_image.width(), source_image.height());
+    let (_, _, lab_matrix) = crate::image::manip::rgb32f_image_to_lab_vec(source_image);
+    floyd_steinberg_lab(lab_matrix, palette, &ErrorDiffusion::default(), None, None, |_, _| 1.0, &mut target_image);
+    target_image
+}
+
+/// Same as [`dithering_floyd_steinberg_lab`], but the closest color is matched by lightness first
+/// and by chroma second, see [`color::ops::find_closest_lab_color_by_luminance`]. Prevents dark
+/// saturated areas from turning black when palette lacks their dark shades.
+pub fn dithering_floyd_steinberg_lab_luminance(source_image: RgbImage, palette: PaletteRGB, luminance_band: f32) -> RgbImage {
+    let mut target_image = RgbImage::new(source_image.width(), source_image.height());
+    dithering_floyd_steinberg_lab_luminance_into(&source_image, &palette, luminance_band, &mut target_image);
+    target_image
+}
+
+/// Same as [`dithering_floyd_steinberg_lab_luminance`], but writes result to `target_image`, reallocating it
+/// only if its dimensions differ from the source ones.
+pub fn dithering_floyd_steinberg_lab_luminance_into(source_image: &RgbImage, palette: &PaletteRGB, luminance_band: f32, target_image: &mut RgbImage) {
+    crate::image::manip::match_dimensions(target_image, source_image);
+    floyd_steinberg_lab(lab_matrix(source_image), palette, &ErrorDiffusion::default(), None, Some(luminance_band), |_, _| 1.0, target_image);
+}
+
+/// Same as [`dithering_floyd_steinberg_lab_luminance_into`], with strength modulated by a mask,
+/// see [`dithering_floyd_steinberg_rgb_masked`].
+///
+/// # Panics
+/// Panics if mask dimensions differ from image dimensions.
+pub fn dithering_floyd_steinberg_lab_luminance_masked_into(source_image: &RgbImage, palette: &PaletteRGB, luminance_band: f32, mask: &GrayImage, target_image: &mut RgbImage) {
+    assert_eq!(source_image.dimensions(), mask.dimensions(), "Mask dimensions differ from image dimensions.");
+    crate::image::manip::match_dimensions(target_image, source_image);
+    floyd_steinberg_lab(lab_matrix(source_image), palette, &ErrorDiffusion::default(), None, Some(luminance_band), |x, y| mask_strength(mask, x, y), target_image);
+}
+
+/// Applies Floyd-Steinberg dithering in Lab space with custom limits of accumulated error,
+/// see [`ErrorDiffusion`]. Colors are matched by lightness first if `luminance_band` is given,
+/// see [`dithering_floyd_steinberg_lab_luminance`], and dithering strength is modulated by
+/// `mask` if given.
+///
+/// # Panics
+/// Panics if mask dimensions differ from image dimensions.
+pub fn dithering_floyd_steinberg_lab_diffusion_into(source_image: &RgbImage, palette: &PaletteRGB, diffusion: &ErrorDiffusion, luminance_band: Option<f32>, mask: Option<&GrayImage>, target_image: &mut RgbImage) {
+    crate::image::manip::match_dimensions(target_image, source_image);
+    let edges = diffusion.edge_map(source_image);
+    match mask {
+        Some(mask) => {
+            assert_eq!(source_image.dimensions(), mask.dimensions(), "Mask dimensions differ from image dimensions.");
+            floyd_steinberg_lab(lab_matrix(source_image), palette, diffusion, edges.as_ref(), luminance_band, |x, y| mask_strength(mask, x, y), target_image);
+        },
+        None => floyd_steinberg_lab(lab_matrix(source_image), palette, diffusion, edges.as_ref(), luminance_band, |_, _| 1.0, target_image),
+    }
+}
+
+fn floyd_steinberg_lab<S>(mut lab_matrix: Vec<Vec<palette::Lab>>, palette: &PaletteRGB, diffusion: &ErrorDiffusion, edges: Option<&EdgeWeights>, luminance_band: Option<f32>, strength: S, target_image: &mut RgbImage)
+where 
+    S: Fn(usize, usize) -> f32
+{
+    let lab_palette = palette.clone().to_lab();
+
+    diffusion.traverse(&mut lab_matrix, |x, y, kernel| {
+        let (closest_tl_color, quant_error) = match luminance_band {
+            Some(luminance_band) => color::ops::find_closest_lab_color_by_luminance(kernel.tl, &lab_palette, luminance_band),
+            None => color::ops::find_closest_lab_color(kernel.tl , &lab_palette),
+        };
+        let quant_error = color::ops::lab_mul_scalar(&quant_error, strength(x, y));
+        *kernel.tl = closest_tl_color;
+    
+        // Spread quantisation error over remaining 3 pixels
+        let (err_weight_tr, err_weight_bl, err_weight_br) = ERROR_WEIGHTS_2X2;
+    
+        let (edge_weight_tr, edge_weight_bl, edge_weight_br) = EdgeWeights::kernel(edges, x, y);
+        diffusion.spread_lab(kernel.tr, &quant_error, err_weight_tr * edge_weight_tr);
+        diffusion.spread_lab(kernel.bl, &quant_error, err_weight_bl * edge_weight_bl);
+        diffusion.spread_lab(kernel.br, &quant_error, err_weight_br * edge_weight_br);
     });
 
-    crate::image::manip::srgb_vec_to_rgb_image_using_palette(width, height, rgb_matrix, &palette)
+    crate::image::manip::lab_vec_into_rgb_image_using_palette(&lab_matrix, palette, target_image);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::ColorRGB;
+
+    #[test]
+    fn test_black_mask_gives_thresholding() {
+        let source_image = crate::image::generate_test_gradient_image(32, 8, image::Rgb([0, 0, 0]), image::Rgb([255, 255, 255]));
+        let palette = PaletteRGB::black_and_white();
+        let black_mask = GrayImage::new(32, 8);
+        
+        let masked_image = dithering_floyd_steinberg_rgb_masked(source_image.clone(), palette.clone(), &black_mask);
+        let thresholded_image = crate::algorithms::thresholding::thresholding_rgb(source_image.clone(), palette.clone());
+        assert_eq!(masked_image, thresholded_image);
+
+        let white_mask = GrayImage::from_pixel(32, 8, image::Luma([255]));
+        let masked_image = dithering_floyd_steinberg_lab_masked(source_image.clone(), palette.clone(), &white_mask);
+        assert_eq!(masked_image, dithering_floyd_steinberg_lab(source_image, palette));
+    }
+
+    #[test]
+    fn test_floyd_steinberg_matches_raw_core() {
+        let source_image = RgbImage::from_fn(37, 23, |x, y| image::Rgb([(x * 7) as u8, (y * 11) as u8, ((x * y) % 256) as u8]));
+        let palette = PaletteRGB::from(vec![
+            ColorRGB([0, 0, 0]), ColorRGB([255, 255, 255]), ColorRGB([200, 30, 40]), ColorRGB([20, 120, 220]), ColorRGB([90, 90, 90]),
+        ]);
+
+        let mut buffer = source_image.as_raw().clone();
+        let raw_palette = palette.iter().map(|color| color.0).collect::<Vec<_>>();
+        crate::algorithms::raw::dithering_floyd_steinberg_rgb(&mut buffer, 37, 23, &raw_palette);
+
+        assert_eq!(buffer, dithering_floyd_steinberg_rgb(source_image, palette).into_raw());
+    }
+
+    #[test]
+    fn test_error_diffusion_limits() {
+        let source_image = crate::image::generate_test_gradient_image(32, 8, image::Rgb([255, 0, 0]), image::Rgb([0, 0, 255]));
+        let palette = PaletteRGB::black_and_white();
+        let mut target_image = RgbImage::new(0, 0);
+
+        // Fully decayed error is not spread at all
+        let no_spread = ErrorDiffusion::default().with_decay(ChannelDecay([0.0; 3]));
+        dithering_floyd_steinberg_rgb_diffusion_into(&source_image, &palette, &no_spread, None, &mut target_image);
+        assert_eq!(target_image, crate::algorithms::thresholding::thresholding_rgb(source_image.clone(), palette.clone()));
+
+        // Accumulated values stay within range extended by overshoot
+        let diffusion = ErrorDiffusion::default().with_overshoot(Some(0.1));
+        let mut pixel = palette::Srgb::new(1.0, 0.0, 0.5);
+        diffusion.spread_srgb(&mut pixel, &palette::Srgb::new(5.0, -5.0, 0.0), 1.0);
+        assert_eq!((pixel.red, pixel.green, pixel.blue), (1.1, -0.1, 0.5));
+
+        assert!("0.5,1".parse::<ChannelDecay>().is_err());
+        assert!("1.5".parse::<ChannelDecay>().is_err());
+    }
+
+    #[test]
+    fn test_edges_block_error_spread() {
+        let palette = PaletteRGB::black_and_white();
+        let diffusion = ErrorDiffusion::default().with_edge_attenuation(Some(1.0));
+        let mut target_image = RgbImage::new(0, 0);
+
+        // Flat image has no edges, so nothing changes
+        let flat_image = RgbImage::from_pixel(16, 16, image::Rgb([120, 120, 120]));
+        dithering_floyd_steinberg_rgb_diffusion_into(&flat_image, &palette, &diffusion, None, &mut target_image);
+        assert_eq!(target_image, dithering_floyd_steinberg_rgb(flat_image, palette));
+
+        let step_image = RgbImage::from_fn(8, 8, |x, _| if x < 4 { image::Rgb([0, 0, 0]) } else { image::Rgb([255, 255, 255]) });
+        let edges = diffusion.edge_map(&step_image).unwrap();
+        assert_eq!(EdgeWeights::kernel(Some(&edges), 3, 2), (0.0, 0.0, 0.0));
+        assert_eq!(EdgeWeights::kernel(Some(&edges), 0, 2), (1.0, 1.0, 1.0));
+        assert_eq!(EdgeWeights::kernel(Some(&edges), 7, 7), (0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_dithering_into_matches_by_value() {
+        let palette = PaletteRGB::primary_bw();
+        let mut target_image = RgbImage::new(0, 0);
+
+        for end_color in [image::Rgb([255, 128, 0]), image::Rgb([0, 64, 255])] {
+            let source_image = crate::image::generate_test_gradient_image(24, 6, image::Rgb([0, 0, 0]), end_color);
+            dithering_floyd_steinberg_rgb_into(&source_image, &palette, &mut target_image);
+            assert_eq!(target_image, dithering_floyd_steinberg_rgb(source_image.clone(), palette.clone()));
+
+            dithering_floyd_steinberg_lab_into(&source_image, &palette, &mut target_image);
+            assert_eq!(target_image, dithering_floyd_steinberg_lab(source_image, palette.clone()));
+        }
+    }
+
+    #[test]
+    fn test_f32_source_is_not_pre_quantized() {
+        // Level between two 8-bit values, 8-bit source would give single color
+        let level = 127.4 / 255.0;
+        let source_image = Rgb32FImage::from_pixel(32, 32, image::Rgb([level; 3]));
+        let palette = PaletteRGB::from(vec![crate::color::ColorRGB([127; 3]), crate::color::ColorRGB([128; 3])]);
+
+        let quantized_image = image::DynamicImage::ImageRgb32F(source_image.clone()).to_rgb8();
+        let processed_image = dithering_floyd_steinberg_rgb(quantized_image, palette.clone());
+        assert_eq!(crate::image::count_image_colors(&processed_image).len(), 1);
+
+        let processed_image = dithering_floyd_steinberg_rgb_f32(&source_image, &palette);
+        assert_eq!(crate::image::count_image_colors(&processed_image).len(), 2);
+    }
+
+    #[test]
+    fn test_tileable_diffusion_has_no_seams() {
+        let source_image = RgbImage::from_pixel(48, 32, image::Rgb([128, 128, 128]));
+        let palette = PaletteRGB::black_and_white();
+        let white_count = |processed_image: &RgbImage, pixels: &mut dyn Iterator<Item = (u32, u32)>| {
+            pixels.filter(|&(x, y)| processed_image.get_pixel(x, y).0[0] > 0).count()
+        };
+
+        for tileable in [false, true] {
+            let mut target_image = RgbImage::new(0, 0);
+            dithering_floyd_steinberg_rgb_diffusion_into(&source_image, &palette, &ErrorDiffusion::default().with_tileable(tileable), None, &mut target_image);
+
+            // Neighbouring columns and rows across the edges keep the same density as inside
+            let seam_columns = white_count(&target_image, &mut (0..32).flat_map(|y| [(47, y), (0, y)]));
+            let seam_rows = white_count(&target_image, &mut (0..48).flat_map(|x| [(x, 31), (x, 0)]));
+            assert_eq!(seam_columns == 32 && seam_rows == 48, tileable, "{seam_columns} {seam_rows}");
+        }
+    }
 }
@@ -0,0 +1,160 @@
+use image::{GrayImage, RgbImage};
+
+use crate::{
+    algorithms::dithering::{mask_strength, ErrorDiffusion},
+    color,
+    palette::PaletteRGB
+};
+
+/// Size of class matrix tiled over the image.
+const CLASS_MATRIX_SIZE: usize = 8;
+
+/// Knuth's class matrix, pixels are processed in order of their class.
+/// Taken from Knuth, "Digital Halftones by Dot Diffusion", ACM TOG 1987.
+const CLASS_MATRIX: [[u8; CLASS_MATRIX_SIZE]; CLASS_MATRIX_SIZE] = [
+    [34, 48, 40, 32, 29, 15, 23, 31],
+    [42, 58, 56, 53, 21,  5,  7, 10],
+    [50, 62, 61, 45, 13,  1,  2, 18],
+    [38, 46, 54, 37, 25, 17,  9, 26],
+    [28, 14, 22, 30, 35, 49, 41, 33],
+    [20,  4,  6, 11, 43, 59, 57, 52],
+    [12,  0,  3, 19, 51, 63, 60, 44],
+    [24, 16,  8, 27, 39, 47, 55, 36],
+];
+
+/// Weight of error passed to orthogonal neighbours, diagonal ones get half of it.
+const ORTHOGONAL_WEIGHT: f32 = 2.0;
+
+/// Applies Knuth's dot diffusion using a given color palette.
+///
+/// # Parameters
+/// - `source_image`: The input `RgbImage` to be dithered.
+/// - `palette`: A `PaletteRGB` containing the target colors for dithering.
+///
+/// # Returns
+/// - A dithered `RgbImage` that approximates the input image using the specified palette.
+///
+/// # Algorithm Details
+/// Every pixel gets class from 8x8 class matrix tiled over the image. Pixels are quantized
+/// to the closest palette color in order of their class and quantization error is spread
+/// only to those of 8 neighbours which have higher class, orthogonal ones get twice
+/// the weight of diagonal ones. Pixels of the same class never exchange error, so each
+/// class could be processed in parallel, unlike serial error diffusion where every pixel
+/// waits for the previous one. Result is sharper than ordered dithering with no global
+/// pattern, but less smooth than Floyd-Steinberg.
+///
+/// # Example
+/// ```
+/// use ditherum::{algorithms::dot_diffusion::dot_diffusion, palette::PaletteRGB};
+///
+/// let source_image = image::RgbImage::from_pixel(16, 16, image::Rgb([128, 128, 128]));
+/// let processed_image = dot_diffusion(source_image, PaletteRGB::black_and_white());
+///
+/// // Roughly half of the pixels are white
+/// let white_count = processed_image.pixels().filter(|px| px.0 == [255, 255, 255]).count();
+/// assert!((100..=156).contains(&white_count));
+/// ```
+pub fn dot_diffusion(source_image: RgbImage, palette: PaletteRGB) -> RgbImage {
+    let mut target_image = RgbImage::new(source_image.width(), source_image.height());
+    dot_diffusion_into(&source_image, &palette, &mut target_image);
+    target_image
+}
+
+/// Same as [`dot_diffusion`], but writes result to `target_image`, reallocating it
+/// only if its dimensions differ from the source ones.
+pub fn dot_diffusion_into(source_image: &RgbImage, palette: &PaletteRGB, target_image: &mut RgbImage) {
+    crate::image::manip::match_dimensions(target_image, source_image);
+    diffuse_dots(source_image, palette, |_, _| 1.0, target_image);
+}
+
+/// Applies dot diffusion with strength modulated by a mask.
+///
+/// See [`crate::algorithms::dithering::dithering_floyd_steinberg_rgb_masked`].
+///
+/// # Panics
+/// Panics if mask dimensions differ from image dimensions.
+pub fn dot_diffusion_masked(source_image: RgbImage, palette: PaletteRGB, mask: &GrayImage) -> RgbImage {
+    let mut target_image = RgbImage::new(source_image.width(), source_image.height());
+    dot_diffusion_masked_into(&source_image, &palette, mask, &mut target_image);
+    target_image
+}
+
+/// Same as [`dot_diffusion_masked`], but writes result to `target_image`, reallocating it
+/// only if its dimensions differ from the source ones.
+///
+/// # Panics
+/// Panics if mask dimensions differ from image dimensions.
+pub fn dot_diffusion_masked_into(source_image: &RgbImage, palette: &PaletteRGB, mask: &GrayImage, target_image: &mut RgbImage) {
+    assert_eq!(source_image.dimensions(), mask.dimensions(), "Mask dimensions differ from image dimensions.");
+    crate::image::manip::match_dimensions(target_image, source_image);
+    diffuse_dots(source_image, palette, |x, y| mask_strength(mask, x, y), target_image);
+}
+
+fn class_of(x: usize, y: usize) -> u8 {
+    CLASS_MATRIX[y % CLASS_MATRIX_SIZE][x % CLASS_MATRIX_SIZE]
+}
+
+fn diffuse_dots<S>(source_image: &RgbImage, palette: &PaletteRGB, strength: S, target_image: &mut RgbImage)
+where
+    S: Fn(usize, usize) -> f32
+{
+    let srgb_palette = palette.clone().to_srgb();
+    let diffusion = ErrorDiffusion::default();
+    let (width, height, mut rgb_matrix) = crate::image::manip::rgb_image_ref_to_float_srgb_vec(source_image);
+
+    let mut pixels_by_class = vec![Vec::new(); CLASS_MATRIX_SIZE * CLASS_MATRIX_SIZE];
+    for y in 0..height {
+        for x in 0..width {
+            pixels_by_class[class_of(x, y) as usize].push((x, y));
+        }
+    }
+
+    for (x, y) in pixels_by_class.into_iter().flatten() {
+        let current = rgb_matrix[y][x];
+        let closest_color = color::ops::find_closest_srgb_color(&current, &srgb_palette);
+        let quant_error = color::ops::srgb_mul_scalar(&color::ops::srgb_sub(&current, &closest_color), strength(x, y));
+        rgb_matrix[y][x] = closest_color;
+
+        // Only neighbours processed later take error
+        let class = class_of(x, y);
+        let neighbours = (-1isize..=1)
+            .flat_map(|dy| (-1isize..=1).map(move |dx| (dx, dy)))
+            .filter(|&offset| offset != (0, 0))
+            .filter_map(|(dx, dy)| {
+                let (neighbour_x, neighbour_y) = (x.checked_add_signed(dx)?, y.checked_add_signed(dy)?);
+                let is_later = neighbour_x < width && neighbour_y < height && class_of(neighbour_x, neighbour_y) > class;
+                let weight = if dx == 0 || dy == 0 { ORTHOGONAL_WEIGHT } else { ORTHOGONAL_WEIGHT / 2.0 };
+                is_later.then_some((neighbour_x, neighbour_y, weight))
+            })
+            .collect::<Vec<_>>();
+
+        let weights_sum = neighbours.iter().map(|(_, _, weight)| weight).sum::<f32>();
+        for (neighbour_x, neighbour_y, weight) in neighbours {
+            diffusion.spread_srgb(&mut rgb_matrix[neighbour_y][neighbour_x], &quant_error, weight / weights_sum);
+        }
+    }
+
+    crate::image::manip::srgb_vec_into_rgb_image_using_palette(&rgb_matrix, palette, target_image);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_class_matrix_is_permutation() {
+        let mut classes = CLASS_MATRIX.iter().flatten().copied().collect::<Vec<_>>();
+        classes.sort_unstable();
+        assert_eq!(classes, (0..64).collect::<Vec<u8>>());
+
+        // Gradient keeps its tone along the way
+        let source_image = crate::image::generate_test_gradient_image(64, 16, image::Rgb([0, 0, 0]), image::Rgb([255, 255, 255]));
+        let processed_image = dot_diffusion(source_image, PaletteRGB::black_and_white());
+        let white_share = |range: std::ops::Range<u32>| range.clone()
+            .flat_map(|x| (0..16).map(move |y| (x, y)))
+            .filter(|&(x, y)| processed_image.get_pixel(x, y).0 == [255, 255, 255])
+            .count() as f32 / (range.len() * 16) as f32;
+        assert!(white_share(0..16) < 0.3);
+        assert!(white_share(48..64) > 0.7);
+    }
+}
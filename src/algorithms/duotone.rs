@@ -0,0 +1,128 @@
+use image::{GrayImage, RgbImage};
+
+use crate::{
+    algorithms::{dithering::mask_strength, kernel::{self, ERROR_WEIGHTS_2X2}},
+    palette::PaletteRGB
+};
+
+/// Maps image luminance through gradient of palette colors with dithering, like newspaper duotones.
+///
+/// # Parameters
+/// - `source_image`: The input `RgbImage` to be dithered.
+/// - `palette`: Gradient colors, usually 2 or 3 inks, ordered by lightness from shadows to highlights.
+///
+/// # Returns
+/// - A dithered `RgbImage` containing only palette colors.
+///
+/// # Algorithm Details
+/// Palette colors are spread evenly over luminance range, the darkest one for black and the
+/// lightest for white, regardless of their own lightness. Lab lightness of each pixel is
+/// quantized to these stops with Floyd-Steinberg error diffusion, then stop is looked up
+/// in the gradient. Hue of the source is dropped, so the look is defined by inks alone.
+///
+/// # Panics
+/// Panics if palette is empty.
+///
+/// # Example
+/// ```
+/// use ditherum::{algorithms::duotone::duotone_dithering, color::ColorRGB, palette::PaletteRGB};
+///
+/// let source_image = ditherum::image::generate_test_gradient_image(32, 4, image::Rgb([0, 0, 0]), image::Rgb([255, 255, 255]));
+/// let inks = PaletteRGB::from(vec![ColorRGB([20, 30, 90]), ColorRGB([240, 200, 120])]);
+/// let processed_image = duotone_dithering(source_image, inks);
+///
+/// assert_eq!(processed_image.get_pixel(0, 0).0, [20, 30, 90]);
+/// assert_eq!(processed_image.get_pixel(31, 0).0, [240, 200, 120]);
+/// ```
+pub fn duotone_dithering(source_image: RgbImage, palette: PaletteRGB) -> RgbImage {
+    let mut target_image = RgbImage::new(source_image.width(), source_image.height());
+    duotone_dithering_into(&source_image, &palette, &mut target_image);
+    target_image
+}
+
+/// Same as [`duotone_dithering`], but writes result to `target_image`, reallocating it
+/// only if its dimensions differ from the source ones.
+pub fn duotone_dithering_into(source_image: &RgbImage, palette: &PaletteRGB, target_image: &mut RgbImage) {
+    crate::image::manip::match_dimensions(target_image, source_image);
+    duotone(source_image, palette, |_, _| 1.0, target_image);
+}
+
+/// Applies duotone mapping with dithering strength modulated by mask, black areas get
+/// the closest gradient stop without error diffusion.
+///
+/// # Panics
+/// Panics if mask dimensions differ from image dimensions.
+pub fn duotone_dithering_masked(source_image: RgbImage, palette: PaletteRGB, mask: &GrayImage) -> RgbImage {
+    let mut target_image = RgbImage::new(source_image.width(), source_image.height());
+    duotone_dithering_masked_into(&source_image, &palette, mask, &mut target_image);
+    target_image
+}
+
+/// Same as [`duotone_dithering_masked`], but writes result to `target_image`, reallocating it
+/// only if its dimensions differ from the source ones.
+///
+/// # Panics
+/// Panics if mask dimensions differ from image dimensions.
+pub fn duotone_dithering_masked_into(source_image: &RgbImage, palette: &PaletteRGB, mask: &GrayImage, target_image: &mut RgbImage) {
+    assert_eq!(source_image.dimensions(), mask.dimensions(), "Mask dimensions differ from image dimensions.");
+    crate::image::manip::match_dimensions(target_image, source_image);
+    duotone(source_image, palette, |x, y| mask_strength(mask, x, y), target_image);
+}
+
+fn duotone<S>(source_image: &RgbImage, palette: &PaletteRGB, strength: S, target_image: &mut RgbImage)
+where
+    S: Fn(usize, usize) -> f32
+{
+    assert!(!palette.is_empty(), "Palette is empty.");
+    let (width, height) = source_image.dimensions();
+
+    // Gradient stops, palette is kept sorted by lightness
+    let steps = (palette.len() - 1).max(1) as f32;
+    let stop_idx = |luminance: f32| ((luminance * steps).round().max(0.0) as usize).min(palette.len() - 1);
+
+    let mut luminance_matrix = (0..height)
+        .map(|y| (0..width)
+            .map(|x| crate::color::ops::rgbu8_to_lab(*source_image.get_pixel(x, y)).l / 100.0)
+            .collect::<Vec<f32>>()
+        )
+        .collect::<Vec<_>>();
+
+    if width > 1 && height > 1 {
+        kernel::apply_2x2_kernel_processing_with_position(&mut luminance_matrix, |x, y, kernel| {
+            let quantized_luminance = stop_idx(*kernel.tl) as f32 / steps;
+            let quant_error = (*kernel.tl - quantized_luminance) * strength(x, y);
+            *kernel.tl = quantized_luminance;
+
+            let (err_weight_tr, err_weight_bl, err_weight_br) = ERROR_WEIGHTS_2X2;
+            *kernel.tr += quant_error * err_weight_tr;
+            *kernel.bl += quant_error * err_weight_bl;
+            *kernel.br += quant_error * err_weight_br;
+        });
+    }
+
+    for (x, y, pixel) in target_image.enumerate_pixels_mut() {
+        *pixel = palette[stop_idx(luminance_matrix[y as usize][x as usize])].to_rgbu8();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::ColorRGB;
+
+    #[test]
+    fn test_duotone_follows_luminance() {
+        let inks = PaletteRGB::from(vec![ColorRGB([120, 0, 0]), ColorRGB([0, 200, 255])]);
+
+        // Hue is dropped, gray of the same lightness as red gets the same mix
+        let red_image = RgbImage::from_pixel(16, 16, image::Rgb([200, 40, 40]));
+        let lightness = ColorRGB([200, 40, 40]).to_lab().l;
+        let gray_level = (0..=255u8).min_by_key(|&level| ((ColorRGB([level; 3]).to_lab().l - lightness).abs() * 100.0) as u32).unwrap();
+        let gray_image = RgbImage::from_pixel(16, 16, image::Rgb([gray_level; 3]));
+
+        let count_light = |img: RgbImage| duotone_dithering(img, inks.clone()).pixels().filter(|px| px.0 == [0, 200, 255]).count() as i32;
+        let (red_light_count, gray_light_count) = (count_light(red_image), count_light(gray_image));
+        assert!(red_light_count > 0 && red_light_count < 256);
+        assert!((red_light_count - gray_light_count).abs() <= 8, "red={red_light_count}, gray={gray_light_count}");
+    }
+}
@@ -0,0 +1,49 @@
+use image::{GrayImage, RgbImage};
+
+/// Computes Sobel edge map of image lightness.
+///
+/// # Parameters
+/// - `source_image`: The input `RgbImage`.
+///
+/// # Returns
+/// - A `GrayImage` of the same dimensions, white where lightness changes the most.
+///
+/// # Algorithm Details
+/// Lab lightness is convolved with horizontal and vertical 3x3 Sobel kernels, border pixels
+/// are repeated outside of the image. Gradient magnitude is scaled so step from black
+/// to white gives full white.
+///
+/// # Example
+/// ```
+/// use ditherum::algorithms::edges::sobel_edge_map;
+///
+/// let source_image = image::RgbImage::from_fn(8, 8, |x, _| if x < 4 { image::Rgb([0, 0, 0]) } else { image::Rgb([255, 255, 255]) });
+/// let edge_map = sobel_edge_map(&source_image);
+///
+/// assert_eq!(edge_map.get_pixel(0, 0).0, [0]);
+/// assert_eq!(edge_map.get_pixel(4, 4).0, [255]);
+/// ```
+pub fn sobel_edge_map(source_image: &RgbImage) -> GrayImage {
+    let (width, height) = source_image.dimensions();
+    let lightness = source_image.pixels()
+        .map(|pixel| crate::color::ops::rgbu8_to_lab(*pixel).l / 100.0)
+        .collect::<Vec<f32>>();
+    let at = |x: i64, y: i64| {
+        let x = x.clamp(0, width as i64 - 1) as usize;
+        let y = y.clamp(0, height as i64 - 1) as usize;
+        lightness[y * width as usize + x]
+    };
+
+    // Sobel response to black to white step is 4
+    const FULL_STEP_RESPONSE: f32 = 4.0;
+
+    GrayImage::from_fn(width, height, |x, y| {
+        let (x, y) = (x as i64, y as i64);
+        let gradient_x = (at(x + 1, y - 1) + 2.0 * at(x + 1, y) + at(x + 1, y + 1))
+            - (at(x - 1, y - 1) + 2.0 * at(x - 1, y) + at(x - 1, y + 1));
+        let gradient_y = (at(x - 1, y + 1) + 2.0 * at(x, y + 1) + at(x + 1, y + 1))
+            - (at(x - 1, y - 1) + 2.0 * at(x, y - 1) + at(x + 1, y - 1));
+        let magnitude = gradient_x.hypot(gradient_y) / FULL_STEP_RESPONSE;
+        image::Luma([(magnitude.clamp(0.0, 1.0) * 255.0).round() as u8])
+    })
+}
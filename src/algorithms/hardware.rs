@@ -0,0 +1,168 @@
+use image::RgbImage;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    algorithms::tiles::{self, TileConstraint, TilePaletteRules},
+    color::ColorRGB,
+    image::ProcessingAlgorithm,
+    palette::PaletteRGB
+};
+
+/// Channel value of ZX Spectrum colors without BRIGHT attribute.
+const ZX_NORMAL_LEVEL: u8 = 0xd7;
+
+/// Retro machine whose palette and color constraints are enforced during dithering.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, clap::ValueEnum, Serialize, Deserialize)]
+pub enum HardwareProfile {
+    /// Commodore 64 multicolor bitmap: double wide pixels, 4 colors per 4x8 cell, one of them shared by the whole screen
+    #[value(name = "c64")]
+    #[serde(rename = "c64")]
+    C64Multicolor,
+
+    /// ZX Spectrum: 2 colors per 8x8 cell, both normal or both bright
+    #[value(name = "zx")]
+    #[serde(rename = "zx")]
+    ZxSpectrum,
+}
+
+impl HardwareProfile {
+    /// Returns short name used by the CLI `--hardware` flag.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::C64Multicolor => "c64",
+            Self::ZxSpectrum => "zx",
+        }
+    }
+
+    /// Returns the machine palette.
+    pub fn palette(&self) -> PaletteRGB {
+        match self {
+            Self::C64Multicolor => PaletteRGB::from(vec![
+                ColorRGB([0x00, 0x00, 0x00]), ColorRGB([0xff, 0xff, 0xff]), ColorRGB([0x88, 0x00, 0x00]), ColorRGB([0xaa, 0xff, 0xee]),
+                ColorRGB([0xcc, 0x44, 0xcc]), ColorRGB([0x00, 0xcc, 0x55]), ColorRGB([0x00, 0x00, 0xaa]), ColorRGB([0xee, 0xee, 0x77]),
+                ColorRGB([0xdd, 0x88, 0x55]), ColorRGB([0x66, 0x44, 0x00]), ColorRGB([0xff, 0x77, 0x77]), ColorRGB([0x33, 0x33, 0x33]),
+                ColorRGB([0x77, 0x77, 0x77]), ColorRGB([0xaa, 0xff, 0x66]), ColorRGB([0x00, 0x88, 0xff]), ColorRGB([0xbb, 0xbb, 0xbb]),
+            ]),
+            Self::ZxSpectrum => PaletteRGB::from([ZX_NORMAL_LEVEL, u8::MAX].iter()
+                .flat_map(|&level| zx_colors(level))
+                .collect::<Vec<_>>()
+            ),
+        }
+    }
+
+    /// Returns size of attribute cell in output pixels and number of colors allowed within it.
+    pub fn tile_constraint(&self) -> TileConstraint {
+        match self {
+            Self::C64Multicolor => TileConstraint::new(8, 8, 4),
+            Self::ZxSpectrum => TileConstraint::new(8, 8, 2),
+        }
+    }
+
+    /// Returns width of a single machine pixel in output pixels.
+    pub fn pixel_width(&self) -> u32 {
+        match self {
+            Self::C64Multicolor => 2,
+            Self::ZxSpectrum => 1,
+        }
+    }
+
+    /// Dithers image meeting color constraints of the machine, output has source dimensions.
+    ///
+    /// Native resolution (320x200 for C64, 256x192 for ZX Spectrum) is not enforced, resize the
+    /// image beforehand to get a picture displayable on the real machine.
+    ///
+    /// # Example
+    /// ```
+    /// use ditherum::{algorithms::hardware::HardwareProfile, image::ProcessingAlgorithm};
+    ///
+    /// let source_image = ditherum::image::generate_test_gradient_image(32, 16, image::Rgb([255, 0, 0]), image::Rgb([0, 0, 255]));
+    /// let processed_image = HardwareProfile::ZxSpectrum.dither(&source_image, ProcessingAlgorithm::Bayer4);
+    ///
+    /// assert_eq!(processed_image.dimensions(), source_image.dimensions());
+    /// ```
+    pub fn dither(&self, source_image: &RgbImage, algorithm: ProcessingAlgorithm) -> RgbImage {
+        let (width, height) = source_image.dimensions();
+        let pixel_width = self.pixel_width();
+        let machine_width = width.div_ceil(pixel_width);
+        let machine_image = if pixel_width > 1 {
+            image::imageops::resize(source_image, machine_width, height, image::imageops::FilterType::Triangle)
+        } else {
+            source_image.clone()
+        };
+
+        let tile_constraint = self.tile_constraint();
+        let machine_constraint = TileConstraint::new(
+            tile_constraint.tile_width / pixel_width,
+            tile_constraint.tile_height,
+            tile_constraint.colors_per_tile
+        );
+        let mut processed_image = RgbImage::new(0, 0);
+        tiles::tiled_dithering_with_rules_into(&machine_image, &self.palette(), machine_constraint, &self.tile_rules(), algorithm, None, &mut processed_image);
+
+        if pixel_width > 1 {
+            RgbImage::from_fn(width, height, |x, y| *processed_image.get_pixel(x / pixel_width, y))
+        } else {
+            processed_image
+        }
+    }
+
+    /// Returns restrictions of cell sub-palettes.
+    fn tile_rules(&self) -> TilePaletteRules {
+        match self {
+            // Background color register is common for all cells
+            Self::C64Multicolor => TilePaletteRules::default().with_shared_colors(1),
+            // BRIGHT attribute applies to both ink and paper
+            Self::ZxSpectrum => TilePaletteRules::default().with_groups(vec![
+                PaletteRGB::from(zx_colors(ZX_NORMAL_LEVEL)),
+                PaletteRGB::from(zx_colors(u8::MAX)),
+            ]),
+        }
+    }
+}
+
+impl std::fmt::Display for HardwareProfile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// All 8 combinations of channels off or at given level.
+fn zx_colors(level: u8) -> Vec<ColorRGB> {
+    (0..8u8)
+        .map(|bits| ColorRGB([(bits >> 1) & 1, (bits >> 2) & 1, bits & 1].map(|bit| bit * level)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hardware_constraints() {
+        let source_image = crate::image::generate_test_gradient_image(37, 19, image::Rgb([250, 40, 0]), image::Rgb([0, 120, 255]));
+        assert_eq!(HardwareProfile::ZxSpectrum.palette().len(), 15);
+
+        let processed_image = HardwareProfile::ZxSpectrum.dither(&source_image, ProcessingAlgorithm::FloydSteinbergRgb);
+        let bright_palette = PaletteRGB::from(zx_colors(u8::MAX));
+        for tile_y in (0..19).step_by(8) {
+            for tile_x in (0..37).step_by(8) {
+                let tile = image::imageops::crop_imm(&processed_image, tile_x, tile_y, 8, 8).to_image();
+                let colors = crate::image::count_image_colors(&tile).into_keys().map(ColorRGB::from_rgbu8).collect::<Vec<_>>();
+                assert!(colors.len() <= 2);
+                let bright_count = colors.iter().filter(|color| color.0 != [0; 3] && bright_palette.contains(color)).count();
+                assert!(bright_count == 0 || colors.iter().all(|color| bright_palette.contains(color)), "Mixed brightness at {tile_x},{tile_y}");
+            }
+        }
+
+        let processed_image = HardwareProfile::C64Multicolor.dither(&source_image, ProcessingAlgorithm::Bayer4);
+        assert!(processed_image.pixels().step_by(2).zip(processed_image.pixels().skip(1).step_by(2))
+            .take(18)
+            .all(|(left, right)| left == right), "Pixels are not double wide");
+        for tile_y in (0..19).step_by(8) {
+            for tile_x in (0..37).step_by(8) {
+                let tile = image::imageops::crop_imm(&processed_image, tile_x, tile_y, 8, 8).to_image();
+                assert!(crate::image::count_image_colors(&tile).len() <= 4);
+            }
+        }
+    }
+}
@@ -1,3 +1,18 @@
+use alloc::vec::Vec;
+
+/// Weights of quantisation error spread to top-right, bottom-left and bottom-right pixels
+/// by Floyd-Steinberg 2x2 kernel, shared by all error diffusion algorithms.
+/// 
+/// Keep errors weights low to prevent saturation.
+pub(crate) const ERROR_WEIGHTS_2X2: (f32, f32, f32) = (
+    1.5 / 18.0,
+    2.5 / 18.0,
+    4.2 / 18.0,
+);
+
+/// Default fraction of channel range accumulated values may exceed it by.
+pub const DEFAULT_ERROR_OVERSHOOT: f32 = 0.1;
+
 /// Represents a mutable 2x2 kernel over a matrix.
 /// 
 /// This struct provides mutable references to four adjacent elements in a 2x2 region.
@@ -23,6 +38,21 @@ pub fn apply_2x2_kernel_processing<T, P>(matrix: &mut [Vec<T>], mut processing:
 where 
     T: Default,
     P: FnMut(MutKernel2x2<T>)
+{
+    apply_2x2_kernel_processing_with_position(matrix, |_, _, kernel| processing(kernel));
+}
+
+/// Applies a 2x2 kernel-based processing function to a mutable matrix, 
+/// passing position `(x, y)` of the top-left element along with the kernel.
+/// 
+/// See [`apply_2x2_kernel_processing`].
+/// 
+/// # Panics
+/// Panics if the matrix has fewer than two rows or columns.
+pub fn apply_2x2_kernel_processing_with_position<T, P>(matrix: &mut [Vec<T>], mut processing: P)
+where 
+    T: Default,
+    P: FnMut(usize, usize, MutKernel2x2<T>)
 {
     let height = matrix.len();
     assert!(height > 1);
@@ -34,32 +64,301 @@ where
     let mut dummy_br = T::default();
 
     for y in 0..height {
-        let row_is_last = y == (height - 1);
+        let _row_span = tracing::trace_span!("row", y).entered();
+        // Splitting gives disjoint mutable borrows of current and next row
+        let (upper_rows, lower_rows) = matrix.split_at_mut(y + 1);
+        let row = &mut upper_rows[y];
+        let mut next_row = lower_rows.first_mut();
 
         for x in 0..width {
-            let column_is_last = x == (width - 1);
-
-            let tl = (&mut matrix[y][x]) as *mut T;
-
-            let tr = if !column_is_last { &mut matrix[y][x + 1] } else { &mut dummy_tr } as *mut T;
-            
-            let bl = if !row_is_last { &mut matrix[y + 1][x] } else { &mut dummy_bl } as *mut T;
-            
-            let br = if !row_is_last && !column_is_last { &mut matrix[y + 1][x + 1] } else { &mut dummy_br } as *mut T;
-
-            unsafe {
-                let kernel = MutKernel2x2 {
-                    tl: &mut *tl,
-                    tr: &mut *tr,
-                    bl: &mut *bl,
-                    br: &mut *br
-                };
-                processing(kernel);
+            let (row_left, row_right) = row.split_at_mut(x + 1);
+            let tl = &mut row_left[x];
+            let tr = row_right.first_mut().unwrap_or(&mut dummy_tr);
+
+            let (bl, br) = match next_row.as_deref_mut() {
+                Some(next_row) => {
+                    let (next_row_left, next_row_right) = next_row.split_at_mut(x + 1);
+                    (&mut next_row_left[x], next_row_right.first_mut().unwrap_or(&mut dummy_br))
+                },
+                None => (&mut dummy_bl, &mut dummy_br),
+            };
+
+            processing(x, y, MutKernel2x2 { tl, tr, bl, br });
+        }
+    }
+}
+
+/// Same as [`apply_2x2_kernel_processing_with_position`], but kernel cells beyond right and bottom
+/// edges wrap to the opposite side, as if the matrix was a torus.
+/// 
+/// Cells on the opposite side are visited already, so the matrix is processed twice: the first pass
+/// only spreads values across edges into a copy of the matrix, which is then processed by the second
+/// pass. Error diffusion this way gives seamlessly tiling result.
+/// 
+/// # Panics
+/// Panics if the matrix has fewer than two rows or columns.
+pub fn apply_2x2_kernel_processing_wrapped_with_position<T, P>(matrix: &mut [Vec<T>], mut processing: P)
+where 
+    T: Clone + Default,
+    P: FnMut(usize, usize, MutKernel2x2<T>)
+{
+    let mut wrapped_matrix = matrix.to_vec();
+    let mut first_pass_matrix = matrix.to_vec();
+    process_2x2_wrapped(&mut first_pass_matrix, Some(&mut wrapped_matrix), &mut processing);
+    matrix.clone_from_slice(&wrapped_matrix);
+    process_2x2_wrapped(matrix, None, &mut processing);
+}
+
+/// Visits matrix with 2x2 kernel wrapping around edges, cells wrapped to already visited elements
+/// refer to `wrapped` matrix, or to default values if not given.
+fn process_2x2_wrapped<T, P>(matrix: &mut [Vec<T>], mut wrapped: Option<&mut [Vec<T>]>, processing: &mut P)
+where 
+    T: Clone + Default,
+    P: FnMut(usize, usize, MutKernel2x2<T>)
+{
+    let height = matrix.len();
+    assert!(height > 1);
+    let width = matrix[0].len();
+    assert!(width > 1);
+
+    let wrapped_cell = |wrapped: Option<&[Vec<T>]>, x: usize, y: usize| {
+        wrapped.map_or_else(T::default, |wrapped| wrapped[y][x].clone())
+    };
+
+    for y in 0..height {
+        let _row_span = tracing::trace_span!("row", y).entered();
+        let (upper_rows, lower_rows) = matrix.split_at_mut(y + 1);
+        let row = &mut upper_rows[y];
+        let mut next_row = lower_rows.first_mut();
+
+        for x in 0..width {
+            let mut wrapped_tr = None;
+            let mut wrapped_bl = None;
+            let mut wrapped_br = None;
+
+            let (row_left, row_right) = row.split_at_mut(x + 1);
+            let tl = &mut row_left[x];
+            let tr = match row_right.first_mut() {
+                Some(tr) => tr,
+                None => wrapped_tr.insert(wrapped_cell(wrapped.as_deref(), 0, y)),
+            };
+
+            let (bl, br) = match next_row.as_deref_mut() {
+                Some(next_row) if x + 1 < width => {
+                    let (next_row_left, next_row_right) = next_row.split_at_mut(x + 1);
+                    (&mut next_row_left[x], &mut next_row_right[0])
+                },
+                // Bottom-right cell wraps to the start of next row, which is not visited yet
+                Some(next_row) => {
+                    let (next_row_left, next_row_right) = next_row.split_at_mut(x);
+                    (&mut next_row_right[0], &mut next_row_left[0])
+                },
+                None => (
+                    wrapped_bl.insert(wrapped_cell(wrapped.as_deref(), x, 0)),
+                    wrapped_br.insert(wrapped_cell(wrapped.as_deref(), (x + 1) % width, 0)),
+                ),
+            };
+
+            processing(x, y, MutKernel2x2 { tl, tr, bl, br });
+
+            if let Some(wrapped) = wrapped.as_deref_mut() {
+                if let Some(value) = wrapped_tr {
+                    wrapped[y][0] = value;
+                }
+                if let Some(value) = wrapped_bl {
+                    wrapped[0][x] = value;
+                }
+                if let Some(value) = wrapped_br {
+                    wrapped[0][(x + 1) % width] = value;
+                }
             }
         }
     }
 }
 
+/// Policy of handling kernel cells falling outside of the matrix.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum EdgePolicy {
+    /// Cells outside of the matrix are not accessible.
+    #[default]
+    Skip,
+
+    /// Cells outside of the matrix refer to the nearest edge element.
+    Clamp,
+
+    /// Cells outside of the matrix refer to elements from the opposite side.
+    Wrap,
+}
+
+/// Mutable `W`x`H` window over a matrix, placed so that its anchor cell lies on the current element.
+/// 
+/// Cells are accessed one at a time, because with [`EdgePolicy::Clamp`] or [`EdgePolicy::Wrap`]
+/// several cells can refer to the same element.
+pub struct MutKernel<'a, T, const W: usize, const H: usize> {
+    matrix: &'a mut [Vec<T>],
+    x: usize,
+    y: usize,
+    anchor: (usize, usize),
+    edge_policy: EdgePolicy,
+    mirrored: bool,
+}
+
+impl<T, const W: usize, const H: usize> MutKernel<'_, T, W, H> {
+    /// Returns position `(x, y)` of the current element, the one under the anchor cell.
+    pub fn position(&self) -> (usize, usize) {
+        (self.x, self.y)
+    }
+
+    /// Returns element under kernel cell `(kx, ky)`, `None` if the cell is outside of the kernel
+    /// or skipped by edge policy.
+    pub fn get(&self, kx: usize, ky: usize) -> Option<&T> {
+        self.resolve(kx, ky).map(|(x, y)| &self.matrix[y][x])
+    }
+
+    /// Returns mutable element under kernel cell `(kx, ky)`, `None` if the cell is outside of the kernel
+    /// or skipped by edge policy.
+    pub fn get_mut(&mut self, kx: usize, ky: usize) -> Option<&mut T> {
+        self.resolve(kx, ky).map(|(x, y)| &mut self.matrix[y][x])
+    }
+
+    /// Returns `true` if kernel is mirrored horizontally, see [`apply_kernel_processing_serpentine`].
+    pub fn is_mirrored(&self) -> bool {
+        self.mirrored
+    }
+
+    fn resolve(&self, kx: usize, ky: usize) -> Option<(usize, usize)> {
+        if kx >= W || ky >= H {
+            return None;
+        }
+
+        let offset_x = kx as isize - self.anchor.0 as isize;
+        let offset_x = if self.mirrored { -offset_x } else { offset_x };
+        let x = resolve_coordinate(self.x as isize + offset_x, self.matrix[0].len(), self.edge_policy)?;
+        let y = resolve_coordinate(self.y as isize + ky as isize - self.anchor.1 as isize, self.matrix.len(), self.edge_policy)?;
+        Some((x, y))
+    }
+}
+
+/// Maps coordinate into `0..size` range according to edge policy.
+fn resolve_coordinate(coordinate: isize, size: usize, edge_policy: EdgePolicy) -> Option<usize> {
+    if (0..size as isize).contains(&coordinate) {
+        return Some(coordinate as usize);
+    }
+
+    match edge_policy {
+        EdgePolicy::Skip => None,
+        EdgePolicy::Clamp => Some(coordinate.clamp(0, size as isize - 1) as usize),
+        EdgePolicy::Wrap => Some(coordinate.rem_euclid(size as isize) as usize),
+    }
+}
+
+/// Applies a `W`x`H` kernel-based processing function to every element of a mutable matrix.
+/// 
+/// Elements are visited row by row, the kernel is placed so that its `anchor` cell lies on
+/// the visited element. This allows error diffusion matrices of any size to share the same
+/// traversal, e.g. Floyd-Steinberg uses 3x2 kernel anchored at `(1, 0)`.
+/// Every cell access is resolved separately, so for 2x2 kernels [`apply_2x2_kernel_processing`] is faster.
+/// 
+/// # Parameters
+/// - `matrix`: A mutable reference to a 2D vector, all rows of the same length.
+/// - `anchor`: Kernel cell `(kx, ky)` placed on the visited element.
+/// - `edge_policy`: Handling of kernel cells falling outside of the matrix.
+/// - `processing`: A function that takes a `MutKernel<T, W, H>` and modifies the matrix accordingly.
+/// 
+/// # Panics
+/// Panics if the matrix is empty or the anchor lies outside of the kernel.
+/// 
+/// # Example
+/// ```
+/// use ditherum::algorithms::kernel::{apply_kernel_processing, EdgePolicy, MutKernel};
+/// 
+/// // Floyd-Steinberg error diffusion on a single channel
+/// let mut matrix = vec![vec![0.5f32; 4]; 3];
+/// apply_kernel_processing(&mut matrix, (1, 0), EdgePolicy::Skip, |mut kernel: MutKernel<f32, 3, 2>| {
+///     let value = kernel.get_mut(1, 0).unwrap();
+///     let quantized = value.round();
+///     let error = *value - quantized;
+///     *value = quantized;
+///     
+///     for (kx, ky, weight) in [(2, 0, 7.0), (0, 1, 3.0), (1, 1, 5.0), (2, 1, 1.0)] {
+///         if let Some(neighbour) = kernel.get_mut(kx, ky) {
+///             *neighbour += error * weight / 16.0;
+///         }
+///     }
+/// });
+/// assert!(matrix.iter().flatten().all(|&value| value == 0.0 || value == 1.0));
+/// ```
+pub fn apply_kernel_processing<T, P, const W: usize, const H: usize>(
+    matrix: &mut [Vec<T>], 
+    anchor: (usize, usize), 
+    edge_policy: EdgePolicy, 
+    mut processing: P
+)
+where 
+    P: FnMut(MutKernel<T, W, H>)
+{
+    let height = matrix.len();
+    assert!(height > 0);
+    let width = matrix[0].len();
+    assert!(width > 0);
+    assert!(anchor.0 < W && anchor.1 < H, "Anchor outside of the kernel.");
+
+    for y in 0..height {
+        let _row_span = tracing::trace_span!("row", y).entered();
+        for x in 0..width {
+            processing(MutKernel {
+                matrix: &mut *matrix,
+                x,
+                y,
+                anchor,
+                edge_policy,
+                mirrored: false,
+            });
+        }
+    }
+}
+
+/// Same as [`apply_kernel_processing`], but odd rows are visited from right to left with
+/// the kernel mirrored horizontally, so kernel cell right of the anchor refers to the element
+/// on the left of the current one.
+/// 
+/// Serpentine traversal of error diffusion breaks up directional artifacts, because error
+/// is not pushed in the same direction on every row.
+/// 
+/// # Panics
+/// Panics if the matrix is empty or the anchor lies outside of the kernel.
+pub fn apply_kernel_processing_serpentine<T, P, const W: usize, const H: usize>(
+    matrix: &mut [Vec<T>], 
+    anchor: (usize, usize), 
+    edge_policy: EdgePolicy, 
+    mut processing: P
+)
+where 
+    P: FnMut(MutKernel<T, W, H>)
+{
+    let height = matrix.len();
+    assert!(height > 0);
+    let width = matrix[0].len();
+    assert!(width > 0);
+    assert!(anchor.0 < W && anchor.1 < H, "Anchor outside of the kernel.");
+
+    for y in 0..height {
+        let _row_span = tracing::trace_span!("row", y).entered();
+        let mirrored = y % 2 == 1;
+        for step in 0..width {
+            let x = if mirrored { width - 1 - step } else { step };
+            processing(MutKernel {
+                matrix: &mut *matrix,
+                x,
+                y,
+                anchor,
+                edge_policy,
+                mirrored,
+            });
+        }
+    }
+}
+
 #[test]
 fn test_unsafe_kernel_processing_simple() {
     let mut data = vec![vec![0u8; 2]; 2];
@@ -73,3 +372,89 @@ fn test_unsafe_kernel_processing_simple() {
     let expected_data = vec![vec![1, 2], vec![2, 4]];
     assert_eq!(processed_data, expected_data);
 }
+
+#[test]
+fn test_kernel_processing_positions() {
+    let mut data = vec![vec![(0, 0); 3]; 2];
+    apply_2x2_kernel_processing_with_position(&mut data, |x, y, kernel| {
+        *kernel.tl = (x, y);
+    });
+    assert_eq!(data, vec![vec![(0, 0), (1, 0), (2, 0)], vec![(0, 1), (1, 1), (2, 1)]]);
+}
+
+#[test]
+fn test_generic_kernel_matches_2x2() {
+    let mut data_2x2 = vec![vec![1u32; 4]; 3];
+    apply_2x2_kernel_processing(&mut data_2x2, |kernel| {
+        *kernel.tr += *kernel.tl;
+        *kernel.bl += *kernel.tl;
+        *kernel.br += *kernel.tl;
+    });
+
+    let mut data_generic = vec![vec![1u32; 4]; 3];
+    apply_kernel_processing(&mut data_generic, (0, 0), EdgePolicy::Skip, |mut kernel: MutKernel<u32, 2, 2>| {
+        let tl = *kernel.get(0, 0).unwrap();
+        for (kx, ky) in [(1, 0), (0, 1), (1, 1)] {
+            if let Some(value) = kernel.get_mut(kx, ky) {
+                *value += tl;
+            }
+        }
+    });
+
+    assert_eq!(data_generic, data_2x2);
+}
+
+#[test]
+fn test_wrapped_2x2_kernel_processing() {
+    // Every element passes one to its right neighbour, wrapped cells get it as well
+    let mut data = vec![vec![0u32; 3]; 2];
+    apply_2x2_kernel_processing_wrapped_with_position(&mut data, |_, _, kernel| {
+        *kernel.tr += 1;
+    });
+    assert_eq!(data, vec![vec![1, 1, 1], vec![1, 1, 1]]);
+
+    // Bottom-right cell of the last column wraps to the start of the next row
+    let mut data = vec![vec![0u32; 2]; 2];
+    apply_2x2_kernel_processing_wrapped_with_position(&mut data, |x, y, kernel| {
+        if (x, y) == (1, 0) {
+            *kernel.br += 1;
+        }
+    });
+    assert_eq!(data, vec![vec![0, 0], vec![1, 0]]);
+}
+
+#[test]
+fn test_kernel_edge_policies() {
+    let collect_neighbours = |edge_policy| {
+        let mut data = vec![vec![0, 1, 2], vec![3, 4, 5]];
+        let mut neighbours = Vec::new();
+        apply_kernel_processing(&mut data, (1, 1), edge_policy, |kernel: MutKernel<i32, 3, 3>| {
+            if kernel.position() == (0, 0) {
+                neighbours = (0..3).flat_map(|ky| (0..3).map(move |kx| (kx, ky)))
+                    .map(|(kx, ky)| kernel.get(kx, ky).copied())
+                    .collect();
+            }
+        });
+        neighbours
+    };
+
+    assert_eq!(collect_neighbours(EdgePolicy::Skip), vec![None, None, None, None, Some(0), Some(1), None, Some(3), Some(4)]);
+    assert_eq!(collect_neighbours(EdgePolicy::Clamp), [0, 0, 1, 0, 0, 1, 3, 3, 4].map(Some).to_vec());
+    assert_eq!(collect_neighbours(EdgePolicy::Wrap), [5, 3, 4, 2, 0, 1, 5, 3, 4].map(Some).to_vec());
+}
+
+#[test]
+fn test_serpentine_kernel_processing() {
+    let mut data = vec![vec![0usize; 3]; 2];
+    let mut visit_idx = 0;
+    apply_kernel_processing_serpentine(&mut data, (0, 0), EdgePolicy::Skip, |mut kernel: MutKernel<usize, 2, 1>| {
+        visit_idx += 1;
+        *kernel.get_mut(0, 0).unwrap() += visit_idx * 10;
+
+        // Right neighbour in scan direction
+        if let Some(next) = kernel.get_mut(1, 0) {
+            *next += 1;
+        }
+    });
+    assert_eq!(data, vec![vec![10, 21, 31], vec![61, 51, 40]]);
+}
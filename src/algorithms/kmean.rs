@@ -1,10 +1,111 @@
-use std::fmt::Debug;
-use rand::seq::IndexedRandom;
+use alloc::{vec, vec::Vec};
+use core::fmt::Debug;
+use rand::{seq::IndexedRandom, Rng};
 
-const MULTITHREADE_ITEMS_COUNT_THRESHOLD: usize = 50;
-const CONVERGE_THRESHOLD: f32 = 0.05;
-const CONVERGE_ENOUGH_THRESHOLD: f32 = 0.8;
-const ITERATION_MAX_COUNT: usize = 120;
+/// Default maximal distance centroids can move by in converged iteration.
+pub const DEFAULT_CONVERGE_THRESHOLD: f32 = 0.05;
+
+/// Default maximal distance centroids can move by after iterations are exhausted.
+pub const DEFAULT_TOLERANCE_FALLBACK: f32 = 0.8;
+
+/// Default maximal number of iterations.
+pub const DEFAULT_MAX_ITERATIONS: usize = 120;
+
+/// Default minimal number of items assigned by one worker thread.
+pub const DEFAULT_MIN_CHUNK_LEN: usize = 64;
+
+/// Minimal number of distance evaluations worth spawning a worker thread for.
+const MIN_DISTANCES_PER_WORKER: usize = 16_384;
+
+/// Multithreading settings of clusters assignment.
+///
+/// Number of workers follows workload, which is number of items times number of centroids,
+/// so tiny palettes on huge inputs do not spawn threads doing almost nothing. Without `std`
+/// feature assignment is always single threaded.
+///
+/// # Examples
+///
+/// ```
+/// use ditherum::algorithms::kmean::Parallelism;
+///
+/// let parallelism = Parallelism::default().with_threads(Some(4));
+/// assert!(parallelism.workers_count(100_000, 16) <= 4);
+/// assert_eq!(parallelism.workers_count(100, 16), 1);
+/// assert_eq!(Parallelism::disabled().workers_count(100_000, 16), 1);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Parallelism {
+    /// Maximal number of worker threads, `None` uses all logical cores.
+    pub threads: Option<usize>,
+
+    /// Minimal number of items assigned by one worker.
+    pub min_chunk_len: usize,
+
+    /// Whether assignment may use multiple threads at all.
+    pub enabled: bool,
+}
+
+impl Default for Parallelism {
+    fn default() -> Self {
+        Self {
+            threads: None,
+            min_chunk_len: DEFAULT_MIN_CHUNK_LEN,
+            enabled: true,
+        }
+    }
+}
+
+impl Parallelism {
+    /// Single threaded assignment.
+    pub fn disabled() -> Self {
+        Self::default().with_enabled(false)
+    }
+
+    /// Sets maximal number of worker threads, `None` uses all logical cores.
+    pub fn with_threads(mut self, threads: Option<usize>) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    /// Sets minimal number of items assigned by one worker.
+    pub fn with_min_chunk_len(mut self, min_chunk_len: usize) -> Self {
+        self.min_chunk_len = min_chunk_len;
+        self
+    }
+
+    /// Enables or disables multithreading.
+    pub fn with_enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
+    /// Returns number of workers assigning `items_count` items to `centroids_count` centroids,
+    /// at least 1.
+    ///
+    /// Workers are limited by available threads, by `min_chunk_len` items per worker and by
+    /// minimal number of distance evaluations per worker.
+    pub fn workers_count(&self, items_count: usize, centroids_count: usize) -> usize {
+        if !self.enabled {
+            return 1;
+        }
+
+        let threads = self.available_threads();
+        let by_chunk_len = items_count / self.min_chunk_len.max(1);
+        let by_workload = items_count.saturating_mul(centroids_count) / MIN_DISTANCES_PER_WORKER;
+        threads.min(by_chunk_len).min(by_workload).max(1)
+    }
+
+    #[cfg(feature = "std")]
+    fn available_threads(&self) -> usize {
+        // Logical cores = doubled physical cores with hyperthreading
+        self.threads.unwrap_or_else(num_cpus::get)
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn available_threads(&self) -> usize {
+        1
+    }
+}
 
 /// Errors that can occur while finding centroids using the K-means algorithm.
 #[derive(Debug, thiserror::Error)]
@@ -23,6 +124,102 @@ pub enum CentroidsFindError {
     /// The input data is empty.
     #[error("InputEmpty")]
     InputEmpty,
+
+    /// More fixed centroids were given than the requested number of centroids.
+    #[error("TooManyFixedCentroids fixed={fixed}, centroids={centroids}")]
+    TooManyFixedCentroids {
+        fixed: usize,
+        centroids: usize
+    },
+}
+
+/// Convergence settings of K-means clustering.
+///
+/// # Examples
+///
+/// ```
+/// use ditherum::algorithms::kmean::KMeansConfig;
+///
+/// // Never fail, return the last centroids when iterations are exhausted
+/// let config = KMeansConfig::default()
+///     .with_max_iterations(20)
+///     .with_tolerance_fallback(None);
+/// assert_eq!(config.max_iterations, 20);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KMeansConfig {
+    /// Maximal number of iterations.
+    pub max_iterations: usize,
+
+    /// Maximal distance every centroid can move by in the last iteration to consider them converged.
+    pub converge_threshold: f32,
+
+    /// Looser threshold accepted once `max_iterations` are exhausted, exceeding it gives
+    /// [`CentroidsFindError::TooManyIterations`]. `None` accepts the last centroids regardless,
+    /// see [`KMeansResult::converged`].
+    pub tolerance_fallback: Option<f32>,
+
+    /// Multithreading settings of clusters assignment.
+    pub parallelism: Parallelism,
+}
+
+impl Default for KMeansConfig {
+    fn default() -> Self {
+        Self {
+            max_iterations: DEFAULT_MAX_ITERATIONS,
+            converge_threshold: DEFAULT_CONVERGE_THRESHOLD,
+            tolerance_fallback: Some(DEFAULT_TOLERANCE_FALLBACK),
+            parallelism: Parallelism::default(),
+        }
+    }
+}
+
+impl KMeansConfig {
+    /// Sets maximal number of iterations.
+    pub fn with_max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    /// Sets maximal distance centroids can move by in converged iteration.
+    pub fn with_converge_threshold(mut self, converge_threshold: f32) -> Self {
+        self.converge_threshold = converge_threshold;
+        self
+    }
+
+    /// Sets threshold accepted after iterations are exhausted, see [`KMeansConfig::tolerance_fallback`].
+    pub fn with_tolerance_fallback(mut self, tolerance_fallback: Option<f32>) -> Self {
+        self.tolerance_fallback = tolerance_fallback;
+        self
+    }
+
+    /// Sets multithreading settings of clusters assignment.
+    pub fn with_parallelism(mut self, parallelism: Parallelism) -> Self {
+        self.parallelism = parallelism;
+        self
+    }
+}
+
+/// Result of K-means clustering with diagnostics of its quality.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KMeansResult<T> {
+    /// Fixed centroids followed by computed ones.
+    pub centroids: Vec<T>,
+
+    /// Number of input items closest to each of `centroids`.
+    pub sizes: Vec<usize>,
+
+    /// Index of centroid each input item is closest to, in order of input.
+    pub assignments: Vec<usize>,
+
+    /// Number of iterations run, `0` if centroids were taken directly from input.
+    pub iterations: usize,
+
+    /// Sum of squared distances of input items to their closest centroids, lower is better.
+    pub inertia: f32,
+
+    /// Whether centroids moved less than [`KMeansConfig::converge_threshold`] in the last iteration.
+    pub converged: bool,
 }
 
 /// Validates the input data for the K-means clustering algorithm.
@@ -139,46 +336,33 @@ where
 /// # Parameters
 /// * `input` - A slice of data points to be assigned to clusters.
 /// * `centroids` - A slice of current centroid points.
+/// * `workers_count` - Number of threads, see [`Parallelism::workers_count`].
 /// * `distance_measure` - A function or closure that calculates the distance between two points.
 ///
 /// # Returns
 /// A vector of clusters, where each cluster is a vector of data points assigned to one centroid.
 ///
 /// # Multithreading Details
-/// * Utilizes all available CPU cores for concurrent processing.
-/// * Divides the input into `workers_count` chunks for load balancing.
+/// * Divides the input into `workers_count` chunks of nearly equal length.
 /// * Aggregates the results from each thread to form the final clusters.
+#[cfg(feature = "std")]
 fn get_filled_cluster_multithreaded<T, D>(
     input: &[T],
     centroids: &[T],
+    workers_count: usize,
     distance_measure: &D
 ) -> Vec<Vec<T>>
 where
     T: Debug + Copy + Clone + Send + Sync,
     D: Fn(&T, &T) -> f32 + Send + Sync
 {
-    // Use all cores. Logical cores = doubled physical cores with hyperthreading
-    let workers_count = num_cpus::get();
-    let work_len = input.len();
-    let work_chunk_len = work_len / workers_count;
-
-    let ranges = (0..workers_count)
-        .map(|worker_idx| {
-            let from_idx = worker_idx * work_chunk_len;
-            let to_idx = if worker_idx == (workers_count - 1) {
-                work_len
-            } else {
-                from_idx + work_chunk_len
-            };
-            from_idx..to_idx
-        })
-        .collect::<Vec<_>>();
+    let work_chunk_len = input.len().div_ceil(workers_count);
 
     std::thread::scope(|s| {
-        let handlers = ranges.into_iter()
-            .map(|range| {
+        let handlers = input.chunks(work_chunk_len)
+            .map(|input_batch| {
                 s.spawn(move || get_filled_batch_cluster(
-                    &input[range.start..range.end],
+                    input_batch,
                     centroids,
                     distance_measure,
                 ))
@@ -212,37 +396,46 @@ where
 /// This function is the entry point for cluster assignment. It assigns each data point to the closest
 /// centroid by calculating distances using the provided distance measure.
 ///
-/// It automatically selects between multithreaded and single-threaded processing based on the input size
-/// and the number of available CPU cores:
-/// * Uses multithreading if the input length exceeds `MULTITHREADE_ITEMS_COUNT_THRESHOLD` 
-///   and there are multiple CPU cores available.
-/// * Falls back to a single-threaded approach for smaller input sizes or when only one CPU core is present.
+/// It selects between multithreaded and single-threaded processing based on the workload,
+/// see [`Parallelism::workers_count`]:
+/// * Uses multithreading if there is enough work for more than one worker.
+/// * Falls back to a single-threaded approach for small workloads, when only one thread
+///   is available or multithreading is disabled.
+/// * Always single-threaded without `std` feature.
 ///
 /// # Parameters
 /// * `input` - A slice of data points to be assigned to clusters.
 /// * `centroids` - A slice of current centroid points.
+/// * `parallelism` - Multithreading settings.
 /// * `distance_measure` - A function or closure that calculates the distance between two points.
 ///
 /// # Returns
 /// A vector of clusters, where each cluster is a vector of data points assigned to one centroid.
 ///
 /// # Performance
-/// * Uses a multithreaded approach to leverage all CPU cores for larger input sizes.
+/// * Uses a multithreaded approach to leverage CPU cores for larger workloads.
 /// * Efficiently aggregates partial results to form the final clusters.
 fn create_clusters_assignment<T, D>(
     input: &[T],
     centroids: &[T],
+    parallelism: &Parallelism,
     distance_measure: &D
 ) -> Vec<Vec<T>>
 where
     T: Debug + Copy + Clone + Send + Sync,
     D: Fn(&T, &T) -> f32 + Send + Sync
 {
-    if input.len() > MULTITHREADE_ITEMS_COUNT_THRESHOLD && num_cpus::get() > 1 {
-        get_filled_cluster_multithreaded(input, centroids, distance_measure)
-    } else {
-        get_filled_batch_cluster(input, centroids, distance_measure)
+    let workers_count = parallelism.workers_count(input.len(), centroids.len());
+
+    #[cfg(feature = "std")]
+    if workers_count > 1 {
+        return get_filled_cluster_multithreaded(input, centroids, workers_count, distance_measure);
     }
+
+    #[cfg(not(feature = "std"))]
+    let _ = workers_count;
+
+    get_filled_batch_cluster(input, centroids, distance_measure)
 }
 
 /// Checks whether the centroids have converged.
@@ -276,7 +469,7 @@ where
         .map(|(last, recent)| distance_measure(last, recent))
         .collect::<Vec<_>>();
 
-    log::info!("distances={distances:?}");
+    tracing::trace!("distances={distances:?}");
 
     distances.iter()
         .all(|&distance| distance < distance_threshold)
@@ -284,9 +477,14 @@ where
 
 /// Computes new centroids by calculating the mean of each cluster.
 ///
+/// First `fixed_count` centroids are fixed and stay unchanged, as do centroids of empty clusters
+/// until they are re-seeded, see [`reseed_empty_clusters`].
+///
 /// # Parameters
 ///
 /// * `clusters` - A slice of clusters, each represented as a vector of data points.
+/// * `last_centroids` - Centroids used to create the clusters.
+/// * `fixed_count` - Number of leading centroids which are never moved.
 /// * `calculate_mean` - A function or closure that computes the mean of a slice of points.
 ///
 /// # Returns
@@ -294,6 +492,8 @@ where
 /// A vector of new centroids, each computed as the mean of the corresponding cluster.
 fn create_centroids_from_clusters<T, M>(
     clusters: &[Vec<T>],
+    last_centroids: &[T],
+    fixed_count: usize,
     calculate_mean: &M
 ) -> Vec<T>
 where 
@@ -301,10 +501,156 @@ where
     M: Fn(&[T]) -> T
 {
     clusters.iter()
-        .map(|cluster| calculate_mean(cluster))
+        .zip(last_centroids)
+        .enumerate()
+        .map(|(idx, (cluster, last_centroid))| {
+            if idx < fixed_count || cluster.is_empty() {
+                *last_centroid
+            } else {
+                calculate_mean(cluster)
+            }
+        })
+        .collect()
+}
+
+/// Moves centroids of empty clusters to input items farthest from all centroids.
+///
+/// Empty clusters are re-seeded one by one, so each of them takes different item. Fixed
+/// centroids are never moved. If every item lies on some centroid, there is nothing better
+/// to pick and centroid stays in place.
+///
+/// # Parameters
+///
+/// * `input` - A slice of input data points.
+/// * `clusters` - Clusters created from previous centroids.
+/// * `centroids` - Centroids computed from `clusters`, modified in place.
+/// * `fixed_count` - Number of leading centroids which are never moved.
+/// * `distance_measure` - A function or closure to calculate the distance between two points.
+///
+/// # Returns
+///
+/// Number of re-seeded centroids.
+fn reseed_empty_clusters<T, D>(
+    input: &[T],
+    clusters: &[Vec<T>],
+    centroids: &mut [T],
+    fixed_count: usize,
+    distance_measure: &D
+) -> usize
+where 
+    T: Debug + Copy + Clone,
+    D: Fn(&T, &T) -> f32,
+{
+    let empty_cluster_indices = clusters.iter()
+        .enumerate()
+        .skip(fixed_count)
+        .filter(|(_, cluster)| cluster.is_empty())
+        .map(|(idx, _)| idx)
+        .collect::<Vec<_>>();
+    if empty_cluster_indices.is_empty() {
+        return 0;
+    }
+
+    let mut nearest_distances = input.iter()
+        .map(|item| {
+            let closest_centroid_idx = find_closest_centroid_idx(item, centroids, distance_measure);
+            distance_measure(item, &centroids[closest_centroid_idx])
+        })
+        .collect::<Vec<_>>();
+    let mut reseeded_count = 0;
+
+    for cluster_idx in empty_cluster_indices {
+        let farthest = nearest_distances.iter()
+            .enumerate()
+            .max_by(|(_, a_dist), (_, b_dist)| a_dist.total_cmp(b_dist))
+            .filter(|(_, &distance)| distance > 0.0)
+            .map(|(item_idx, _)| item_idx);
+        let Some(farthest_idx) = farthest else {
+            break;
+        };
+
+        let seed = input[farthest_idx];
+        centroids[cluster_idx] = seed;
+        reseeded_count += 1;
+        tracing::debug!("Re-seeded empty cluster {cluster_idx} with {seed:?}.");
+
+        for (item, nearest_distance) in input.iter().zip(nearest_distances.iter_mut()) {
+            *nearest_distance = nearest_distance.min(distance_measure(item, &seed));
+        }
+    }
+
+    reseeded_count
+}
+
+/// Finds index of the closest centroid of each input item, split between threads like
+/// [`create_clusters_assignment`].
+fn create_closest_centroid_indices<T, D>(
+    input: &[T],
+    centroids: &[T],
+    parallelism: &Parallelism,
+    distance_measure: &D
+) -> Vec<usize>
+where
+    T: Debug + Copy + Clone + Send + Sync,
+    D: Fn(&T, &T) -> f32 + Send + Sync
+{
+    let closest_centroid_idx = |item: &T| find_closest_centroid_idx(item, centroids, distance_measure);
+    let workers_count = parallelism.workers_count(input.len(), centroids.len());
+
+    #[cfg(feature = "std")]
+    if workers_count > 1 {
+        let work_chunk_len = input.len().div_ceil(workers_count);
+        return std::thread::scope(|s| {
+            let handlers = input.chunks(work_chunk_len)
+                .map(|input_batch| s.spawn(move || input_batch.iter()
+                    .map(closest_centroid_idx)
+                    .collect::<Vec<_>>()
+                ))
+                .collect::<Vec<_>>();
+            handlers.into_iter()
+                .flat_map(|handler| handler.join().unwrap())
+                .collect()
+        });
+    }
+
+    #[cfg(not(feature = "std"))]
+    let _ = workers_count;
+
+    input.iter()
+        .map(closest_centroid_idx)
         .collect()
 }
 
+/// Assigns input items to the closest centroids, counts them and sums their squared distances.
+///
+/// # Returns
+///
+/// Assignments, sizes of clusters and inertia, see [`KMeansResult`].
+fn clusters_statistics<T, D>(
+    input: &[T],
+    centroids: &[T],
+    parallelism: &Parallelism,
+    distance_measure: &D
+) -> (Vec<usize>, Vec<usize>, f32)
+where
+    T: Debug + Copy + Clone + Send + Sync,
+    D: Fn(&T, &T) -> f32 + Send + Sync
+{
+    if centroids.is_empty() {
+        return (Vec::new(), Vec::new(), 0.0);
+    }
+
+    let assignments = create_closest_centroid_indices(input, centroids, parallelism, distance_measure);
+    let mut sizes = vec![0; centroids.len()];
+    let mut inertia = 0.0;
+    for (item, &centroid_idx) in input.iter().zip(&assignments) {
+        let distance = distance_measure(item, &centroids[centroid_idx]);
+        sizes[centroid_idx] += 1;
+        inertia += distance * distance;
+    }
+    (assignments, sizes, inertia)
+}
+
 /// Performs K-means clustering to find a set of centroids for the input data.
 ///
 /// This function implements a K-means clustering algorithm that repeatedly assigns data
@@ -351,6 +697,7 @@ where
 ///
 ///  println!("Computed centroids: {:?}", centroids);
 /// ```
+#[cfg(feature = "std")]
 pub fn find_centroids<T, D, M>(
     input: &[T], 
     centroids_count: usize,
@@ -363,65 +710,281 @@ where
     D: Fn(&T, &T) -> f32 + Send + Sync,
     M: Fn(&[T]) -> T
 {
-    validate_input(input, centroids_count)?;
+    find_centroids_with_fixed(input, centroids_count, &[], distance_measure, calculate_mean)
+}
+
+/// Performs K-means clustering where some of the centroids are fixed in place.
+///
+/// Fixed centroids take part in assignment, so input points close to them do not pull
+/// remaining centroids, but they are never moved. Remaining `centroids_count - fixed.len()`
+/// centroids are found the same way as in [`find_centroids`].
+///
+/// # Parameters
+///
+/// * `input` - A slice of input data points.
+/// * `centroids_count` - The total number of centroids, including fixed ones.
+/// * `fixed` - Centroids which are kept unchanged.
+/// * `distance_measure` - A closure that computes the distance between two points.
+/// * `calculate_mean` - A closure that computes the mean of a slice of data points.
+///
+/// # Returns
+///
+/// Returns `Ok(Vec<T>)` containing fixed centroids followed by computed ones,
+/// or a [`CentroidsFindError`] if an error occurs.
+///
+/// # Examples
+///
+/// ```
+/// use ditherum::algorithms::kmean::find_centroids_with_fixed;
+///
+/// let input_data: Vec<f32> = vec![1.0, 2.0, 9.0, 7.0, 8.0, 22.0, 24.0, 3.0];
+/// let centroids = find_centroids_with_fixed(
+///     &input_data,
+///     3,
+///     &[0.0],
+///     |a: &f32, b: &f32| (a - b).abs(),
+///     |arr: &[f32]| arr.iter().sum::<f32>() / arr.len() as f32
+/// ).unwrap();
+///
+/// assert_eq!(centroids.len(), 3);
+/// assert_eq!(centroids[0], 0.0);
+/// ```
+#[cfg(feature = "std")]
+pub fn find_centroids_with_fixed<T, D, M>(
+    input: &[T], 
+    centroids_count: usize,
+    fixed: &[T],
+    distance_measure: D,
+    calculate_mean: M
+) -> Result<Vec<T>, CentroidsFindError>
+where 
+    T: Debug + Copy + Clone + Send + Sync,
+    D: Fn(&T, &T) -> f32 + Send + Sync,
+    M: Fn(&[T]) -> T
+{
+    find_centroids_with_rng(input, centroids_count, fixed, &mut rand::rng(), distance_measure, calculate_mean)
+}
+
+/// Performs K-means clustering with initial centroids picked using given random generator.
+///
+/// Same as [`find_centroids_with_fixed`], but available without `std` feature, where there is
+/// no thread local generator. The same seeded generator gives the same centroids.
+///
+/// # Examples
+///
+/// ```
+/// use ditherum::algorithms::kmean::find_centroids_with_rng;
+/// use rand::{rngs::StdRng, SeedableRng};
+///
+/// let input_data: Vec<f32> = vec![1.0, 2.0, 9.0, 7.0, 8.0, 22.0, 24.0, 3.0];
+/// let find = |seed| find_centroids_with_rng(
+///     &input_data,
+///     3,
+///     &[],
+///     &mut StdRng::seed_from_u64(seed),
+///     |a: &f32, b: &f32| (a - b).abs(),
+///     |arr: &[f32]| arr.iter().sum::<f32>() / arr.len() as f32
+/// ).unwrap();
+///
+/// assert_eq!(find(7), find(7));
+/// ```
+pub fn find_centroids_with_rng<T, R, D, M>(
+    input: &[T], 
+    centroids_count: usize,
+    fixed: &[T],
+    rng: &mut R,
+    distance_measure: D,
+    calculate_mean: M
+) -> Result<Vec<T>, CentroidsFindError>
+where 
+    T: Debug + Copy + Clone + Send + Sync,
+    R: Rng + ?Sized,
+    D: Fn(&T, &T) -> f32 + Send + Sync,
+    M: Fn(&[T]) -> T
+{
+    find_centroids_with_parallelism(input, centroids_count, fixed, rng, &Parallelism::default(), distance_measure, calculate_mean)
+}
+
+/// Performs K-means clustering with given multithreading settings.
+///
+/// Same as [`find_centroids_with_rng`], but number of worker threads and their minimal
+/// chunk of input are controlled by `parallelism`.
+///
+/// # Examples
+///
+/// ```
+/// use ditherum::algorithms::kmean::{find_centroids_with_parallelism, Parallelism};
+/// use rand::{rngs::StdRng, SeedableRng};
+///
+/// let input_data: Vec<f32> = (0..1000).map(|v| v as f32).collect();
+/// let centroids = find_centroids_with_parallelism(
+///     &input_data,
+///     4,
+///     &[],
+///     &mut StdRng::seed_from_u64(7),
+///     &Parallelism::default().with_threads(Some(2)),
+///     |a: &f32, b: &f32| (a - b).abs(),
+///     |arr: &[f32]| arr.iter().sum::<f32>() / arr.len() as f32
+/// ).unwrap();
+///
+/// assert_eq!(centroids.len(), 4);
+/// ```
+pub fn find_centroids_with_parallelism<T, R, D, M>(
+    input: &[T], 
+    centroids_count: usize,
+    fixed: &[T],
+    rng: &mut R,
+    parallelism: &Parallelism,
+    distance_measure: D,
+    calculate_mean: M
+) -> Result<Vec<T>, CentroidsFindError>
+where 
+    T: Debug + Copy + Clone + Send + Sync,
+    R: Rng + ?Sized,
+    D: Fn(&T, &T) -> f32 + Send + Sync,
+    M: Fn(&[T]) -> T
+{
+    let config = KMeansConfig::default().with_parallelism(*parallelism);
+    find_clusters(input, centroids_count, fixed, rng, &config, distance_measure, calculate_mean)
+        .map(|result| result.centroids)
+}
+
+/// Performs K-means clustering returning centroids together with diagnostics of clustering.
+///
+/// Same as [`find_centroids_with_parallelism`], but iterations and convergence thresholds
+/// are set by `config`. Clusters which end up empty are re-seeded with input items farthest
+/// from all centroids, so no centroid is wasted on region without data and the mean is never
+/// calculated of an empty cluster.
+///
+/// # Errors
+///
+/// Returns [`CentroidsFindError::TooManyIterations`] only if centroids still move by more than
+/// [`KMeansConfig::tolerance_fallback`] after [`KMeansConfig::max_iterations`]. Centroids
+/// alternating between two states, possible with non-Euclidean `distance_measure`, stop
+/// iterations early with the state of lower inertia.
+///
+/// # Examples
+///
+/// ```
+/// use ditherum::algorithms::kmean::{find_clusters, KMeansConfig};
+/// use rand::{rngs::StdRng, SeedableRng};
+///
+/// let input_data: Vec<f32> = vec![1.0, 2.0, 3.0, 21.0, 22.0];
+/// let result = find_clusters(
+///     &input_data,
+///     2,
+///     &[],
+///     &mut StdRng::seed_from_u64(7),
+///     &KMeansConfig::default(),
+///     |a: &f32, b: &f32| (a - b).abs(),
+///     |arr: &[f32]| arr.iter().sum::<f32>() / arr.len() as f32
+/// ).unwrap();
+///
+/// let mut sizes = result.sizes.clone();
+/// sizes.sort();
+/// assert_eq!(sizes, vec![2, 3]);
+/// assert_eq!(result.assignments[0], result.assignments[2]);
+/// assert_ne!(result.assignments[2], result.assignments[3]);
+/// assert!(result.converged);
+/// assert!((result.inertia - 2.5).abs() < 1e-4);
+/// ```
+pub fn find_clusters<T, R, D, M>(
+    input: &[T], 
+    centroids_count: usize,
+    fixed: &[T],
+    rng: &mut R,
+    config: &KMeansConfig,
+    distance_measure: D,
+    calculate_mean: M
+) -> Result<KMeansResult<T>, CentroidsFindError>
+where 
+    T: Debug + Copy + Clone + Send + Sync,
+    R: Rng + ?Sized,
+    D: Fn(&T, &T) -> f32 + Send + Sync,
+    M: Fn(&[T]) -> T
+{
+    if fixed.len() > centroids_count {
+        return Err(CentroidsFindError::TooManyFixedCentroids { fixed: fixed.len(), centroids: centroids_count });
+    }
+    let free_centroids_count = centroids_count - fixed.len();
+    let parallelism = &config.parallelism;
+    let result_of = |centroids: Vec<T>, iterations: usize, converged: bool| {
+        let (assignments, sizes, inertia) = clusters_statistics(input, &centroids, parallelism, &distance_measure);
+        KMeansResult { centroids, sizes, assignments, iterations, inertia, converged }
+    };
+    if free_centroids_count == 0 {
+        return Ok(result_of(fixed.to_vec(), 0, true));
+    }
+
+    validate_input(input, free_centroids_count)?;
 
     // If the number of input points equals the requested centroids count,
     // return the input data as the centroids.
-    if input.len() == centroids_count {
-        return Ok(input.to_vec());
+    if input.len() == free_centroids_count {
+        return Ok(result_of(fixed.iter().chain(input).copied().collect(), 0, true));
     }
 
-    let mut rng = rand::rng();
-
     let mut last_centroids;
-    let mut centroids = input
-        .choose_multiple(&mut rng, centroids_count)
+    let mut centroids = fixed.iter()
         .copied()
+        .chain(input
+            .choose_multiple(rng, free_centroids_count)
+            .copied()
+        )
         .collect::<Vec<_>>();
     let mut clusters;
+    let mut previous_centroids: Option<Vec<T>> = None;
     let mut iterations_count = 0;
-    // println!("Initial centroids={centroids:?}");
+    let _span = tracing::debug_span!("kmeans", points = input.len(), centroids = centroids_count).entered();
 
     loop {
         iterations_count += 1;
-        log::debug!("Iteration {iterations_count}.");
+        let _iteration_span = tracing::trace_span!("kmeans_iteration", iteration = iterations_count).entered();
 
         // Assign each input point to the nearest centroid.
-        clusters = create_clusters_assignment(input, &centroids, &distance_measure);
-        log::trace!("Clusters: {clusters:?}");
+        clusters = create_clusters_assignment(input, &centroids, parallelism, &distance_measure);
 
         // Compute new centroids as the mean of the clusters.
         last_centroids = centroids;
-        centroids = create_centroids_from_clusters(&clusters, &calculate_mean);
+        centroids = create_centroids_from_clusters(&clusters, &last_centroids, fixed.len(), &calculate_mean);
+        reseed_empty_clusters(input, &clusters, &mut centroids, fixed.len(), &distance_measure);
 
         // Check if the centroids have converged.
         if check_converges(
             &last_centroids, 
             &centroids, 
-            CONVERGE_THRESHOLD,
+            config.converge_threshold,
             &distance_measure
         ) {
-            log::debug!("Found solution after {iterations_count} iterations!");
-            break;
+            tracing::debug!("Found solution after {iterations_count} iterations!");
+            return Ok(result_of(centroids, iterations_count, true));
         }
+
+        // Means do not minimize distances other than Euclidean, so centroids can alternate
+        // between two states forever, the one closer to input is kept
+        if previous_centroids.as_ref().is_some_and(|previous_centroids| {
+            check_converges(previous_centroids, &centroids, config.converge_threshold, &distance_measure)
+        }) {
+            tracing::debug!("Centroids oscillate after {iterations_count} iterations!");
+            let last_result = result_of(last_centroids, iterations_count, false);
+            let result = result_of(centroids, iterations_count, false);
+            return Ok(if last_result.inertia < result.inertia { last_result } else { result });
+        }
+        previous_centroids = Some(last_centroids.clone());
         
-        if iterations_count > ITERATION_MAX_COUNT {
+        if iterations_count >= config.max_iterations {
             // Iterations exhausted, but solution can be good enough
-            if check_converges(
-                &last_centroids, 
-                &centroids, 
-                CONVERGE_ENOUGH_THRESHOLD,
-                &distance_measure
-            ) {
-                log::debug!("Found good enough solution after {iterations_count} iterations!");
-                break;
-            } else {
-                return Err(CentroidsFindError::TooManyIterations);
+            match config.tolerance_fallback {
+                Some(tolerance) if !check_converges(&last_centroids, &centroids, tolerance, &distance_measure) => {
+                    return Err(CentroidsFindError::TooManyIterations);
+                },
+                _ => {
+                    tracing::debug!("Found good enough solution after {iterations_count} iterations!");
+                    return Ok(result_of(centroids, iterations_count, false));
+                },
             }
         }
     }
-
-    Ok(centroids)
 }         
 
 #[cfg(test)]
@@ -442,7 +1005,7 @@ mod tests {
             calculate_mean
         );
 
-        assert!(matches!(centroids, Ok(_)));
+        assert!(centroids.is_ok());
         let centroids = centroids.unwrap();
         assert_eq!(centroids.len(), centroids_count);
     }
@@ -450,23 +1013,112 @@ mod tests {
     #[test]
     fn test_centroid_float_multithreaded() {
         let input_data: Vec<f32> = (-100..100).map(|v| v as f32).collect::<Vec<_>>();
-        assert!(input_data.len() > MULTITHREADE_ITEMS_COUNT_THRESHOLD);
-
         let centroids_count = 5;
+        let parallelism = Parallelism { threads: Some(4), min_chunk_len: 1, enabled: true };
         let distance_measure = |a: &f32, b: &f32| { (a - b).abs() };
         let calculate_mean = |arr: &[f32]| { arr.iter().sum::<f32>() / arr.len() as f32 };
 
-        let centroids = find_centroids(
+        // Workload of 200 items is too small to be split by default
+        assert_eq!(Parallelism::default().workers_count(input_data.len(), centroids_count), 1);
+        let clusters = get_filled_cluster_multithreaded(&input_data, &[-50.0, 50.0], 3, &distance_measure);
+        assert_eq!(clusters.iter().map(Vec::len).sum::<usize>(), input_data.len());
+
+        let centroids = find_centroids_with_parallelism(
             &input_data, 
             centroids_count, 
+            &[],
+            &mut rand::rng(),
+            &parallelism,
             distance_measure, 
             calculate_mean
         );
 
-        assert!(matches!(centroids, Ok(_)));
+        assert!(centroids.is_ok());
         let centroids = centroids.unwrap();
         assert_eq!(centroids.len(), centroids_count);
     }
+
+    #[test]
+    fn test_workers_count_follows_workload() {
+        let parallelism = Parallelism::default().with_threads(Some(16));
+
+        // Tiny palette on huge input needs fewer workers than big palette
+        let tiny_palette_workers = parallelism.workers_count(40_000, 2);
+        assert_eq!(tiny_palette_workers, 4);
+        assert_eq!(parallelism.workers_count(40_000, 64), 16);
+        assert_eq!(parallelism.with_min_chunk_len(10_000).workers_count(40_000, 64), 4);
+        assert_eq!(Parallelism::disabled().workers_count(40_000, 64), 1);
+        assert_eq!(parallelism.workers_count(0, 64), 1);
+    }
+
+    #[test]
+    fn test_empty_cluster_reseeding() {
+        let input_data: Vec<f32> = vec![1.0, 2.0, 10.0, 30.0];
+        let distance_measure = |a: &f32, b: &f32| { (a - b).abs() };
+
+        // Centroid far from data got no items and moves to the farthest ones, one by one
+        let clusters = vec![vec![1.0, 2.0, 10.0, 30.0], vec![], vec![]];
+        let mut centroids = vec![10.75, 100.0, 200.0];
+        assert_eq!(reseed_empty_clusters(&input_data, &clusters, &mut centroids, 0, &distance_measure), 2);
+        assert_eq!(centroids, vec![10.75, 30.0, 1.0]);
+
+        // Fixed centroids stay in place
+        let mut centroids = vec![10.75, 100.0, 200.0];
+        assert_eq!(reseed_empty_clusters(&input_data, &clusters, &mut centroids, 2, &distance_measure), 1);
+        assert_eq!(centroids, vec![10.75, 100.0, 30.0]);
+
+        // Duplicated items leave no empty clusters and sizes cover whole input
+        let input_data: Vec<f32> = [0.0; 20].into_iter().chain([5.0; 20]).chain([50.0, 51.0]).collect();
+        let result = find_clusters(
+            &input_data,
+            3,
+            &[],
+            &mut rand::rng(),
+            &KMeansConfig::default(),
+            distance_measure,
+            |arr: &[f32]| arr.iter().sum::<f32>() / arr.len() as f32
+        ).unwrap();
+        assert!(result.centroids.iter().all(|centroid| centroid.is_finite()));
+        assert_eq!(result.sizes.iter().sum::<usize>(), input_data.len());
+        assert!(result.sizes.iter().all(|&size| size > 0));
+    }
+
+    #[test]
+    fn test_iterations_limit() {
+        let input_data: Vec<f32> = (0..100).map(|v| (v * v % 97) as f32).collect();
+        let distance_measure = |a: &f32, b: &f32| { (a - b).abs() };
+        let calculate_mean = |arr: &[f32]| { arr.iter().sum::<f32>() / arr.len() as f32 };
+        let find = |config: &KMeansConfig| find_clusters(&input_data, 6, &[], &mut rand::rng(), config, distance_measure, calculate_mean);
+
+        // Single iteration without fallback returns what it has
+        let config = KMeansConfig::default().with_max_iterations(1).with_tolerance_fallback(None);
+        let result = find(&config).unwrap();
+        assert_eq!(result.iterations, 1);
+        assert_eq!(result.sizes.iter().sum::<usize>(), input_data.len());
+
+        // Impossible thresholds are all-or-nothing
+        let config = config.with_converge_threshold(-1.0).with_tolerance_fallback(Some(-1.0));
+        assert!(matches!(find(&config), Err(CentroidsFindError::TooManyIterations)));
+
+        let result = find(&KMeansConfig::default()).unwrap();
+        assert!(result.converged);
+        assert!(result.inertia > 0.0);
+        assert!(result.iterations <= DEFAULT_MAX_ITERATIONS);
+    }
+
+    #[test]
+    fn test_centroid_float_with_fixed() {
+        let input_data: Vec<f32> = vec![1.0, 2.0, 3.0, 11.0, 12.0, 13.0];
+        let distance_measure = |a: &f32, b: &f32| { (a - b).abs() };
+        let calculate_mean = |arr: &[f32]| { arr.iter().sum::<f32>() / arr.len() as f32 };
+
+        // Fixed centroid takes over its neighbourhood
+        let centroids = find_centroids_with_fixed(&input_data, 2, &[0.0], distance_measure, calculate_mean).unwrap();
+        assert_eq!(centroids, vec![0.0, 12.0]);
+
+        let result = find_centroids_with_fixed(&input_data, 1, &[0.0, 5.0], distance_measure, calculate_mean);
+        assert!(matches!(result, Err(CentroidsFindError::TooManyFixedCentroids { fixed: 2, centroids: 1 })));
+    }
 }
 
 
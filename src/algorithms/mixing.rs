@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+
+use image::{GrayImage, RgbImage};
+use palette::{color_difference::EuclideanDistance, FromColor};
+
+use crate::{algorithms::{dithering::mask_strength, ordered::bayer_matrix}, palette::PaletteRGB};
+
+/// Size of Bayer matrix realizing mixing ratios, gives 64 ratio steps.
+const MIXING_MATRIX_SIZE: usize = 8;
+
+/// Weight of distance between mixed colors added to mix error, discourages
+/// mixing distant colors into noisy patterns when closer pair is almost as good.
+const MIXING_SPREAD_PENALTY: f32 = 0.1;
+
+/// Two palette colors with share of the second one in a pixel.
+#[derive(Debug, Clone, Copy)]
+struct ColorMix {
+    first_idx: usize,
+    second_idx: usize,
+
+    /// Share of second color in range `0.0..=1.0`.
+    ratio: f32,
+}
+
+/// Applies dithering mixing the best pair of palette colors for each pixel.
+///
+/// # Parameters
+/// - `source_image`: The input `RgbImage` to be dithered.
+/// - `palette`: A `PaletteRGB` containing the target colors for dithering.
+///
+/// # Returns
+/// - A dithered `RgbImage` containing only palette colors.
+///
+/// # Algorithm Details
+/// For each source color every pair of palette colors is considered, the color is projected
+/// onto the segment between them in linear light and the projection ratio is quantized
+/// to 64 steps. Pair whose mix is the closest in Lab space wins, with small penalty for
+/// distance between mixed colors. Ratio is then realized by 8x8 Bayer matrix, so pixel
+/// gets the second color where matrix threshold is below the ratio. There is no error
+/// diffusion, gradients on tiny palettes stay smooth and the pattern is stable between
+/// frames. Cost grows with square of palette size.
+///
+/// # Panics
+/// Panics if palette is empty.
+///
+/// # Example
+/// ```
+/// use ditherum::{algorithms::mixing::two_color_mixing_dithering, palette::PaletteRGB};
+///
+/// let source_image = image::RgbImage::from_pixel(8, 8, image::Rgb([188, 188, 188]));
+/// let processed_image = two_color_mixing_dithering(source_image, PaletteRGB::black_and_white());
+///
+/// let white_count = processed_image.pixels().filter(|px| px.0 == [255, 255, 255]).count();
+/// assert!((24..=40).contains(&white_count));
+/// ```
+pub fn two_color_mixing_dithering(source_image: RgbImage, palette: PaletteRGB) -> RgbImage {
+    let mut target_image = RgbImage::new(source_image.width(), source_image.height());
+    two_color_mixing_dithering_into(&source_image, &palette, &mut target_image);
+    target_image
+}
+
+/// Same as [`two_color_mixing_dithering`], but writes result to `target_image`, reallocating it
+/// only if its dimensions differ from the source ones.
+pub fn two_color_mixing_dithering_into(source_image: &RgbImage, palette: &PaletteRGB, target_image: &mut RgbImage) {
+    crate::image::manip::match_dimensions(target_image, source_image);
+    two_color_mixing(source_image, palette, |_, _| 1.0, target_image);
+}
+
+/// Applies two color mixing dithering with Bayer thresholds scaled by a mask, black areas
+/// get the dominant color of the pair.
+///
+/// # Panics
+/// Panics if mask dimensions differ from image dimensions.
+pub fn two_color_mixing_dithering_masked(source_image: RgbImage, palette: PaletteRGB, mask: &GrayImage) -> RgbImage {
+    let mut target_image = RgbImage::new(source_image.width(), source_image.height());
+    two_color_mixing_dithering_masked_into(&source_image, &palette, mask, &mut target_image);
+    target_image
+}
+
+/// Same as [`two_color_mixing_dithering_masked`], but writes result to `target_image`, reallocating it
+/// only if its dimensions differ from the source ones.
+///
+/// # Panics
+/// Panics if mask dimensions differ from image dimensions.
+pub fn two_color_mixing_dithering_masked_into(source_image: &RgbImage, palette: &PaletteRGB, mask: &GrayImage, target_image: &mut RgbImage) {
+    assert_eq!(source_image.dimensions(), mask.dimensions(), "Mask dimensions differ from image dimensions.");
+    crate::image::manip::match_dimensions(target_image, source_image);
+    two_color_mixing(source_image, palette, |x, y| mask_strength(mask, x, y), target_image);
+}
+
+fn best_mix(source_color: palette::LinSrgb, linear_palette: &[palette::LinSrgb], lab_palette: &[palette::Lab]) -> ColorMix {
+    let source_lab = palette::Lab::from_color(source_color);
+    let ratio_steps = (MIXING_MATRIX_SIZE * MIXING_MATRIX_SIZE) as f32;
+
+    (0..linear_palette.len())
+        .flat_map(|first_idx| (first_idx..linear_palette.len()).map(move |second_idx| (first_idx, second_idx)))
+        .map(|(first_idx, second_idx)| {
+            let (first_color, second_color) = (linear_palette[first_idx], linear_palette[second_idx]);
+            let span = second_color - first_color;
+            let span_length = span.red * span.red + span.green * span.green + span.blue * span.blue;
+            let ratio = if span_length > 0.0 {
+                let offset = source_color - first_color;
+                let projection = (offset.red * span.red + offset.green * span.green + offset.blue * span.blue) / span_length;
+                (projection.clamp(0.0, 1.0) * ratio_steps).round() / ratio_steps
+            } else {
+                0.0
+            };
+
+            let mixed_lab = palette::Lab::from_color(first_color + span * ratio);
+            let spread = lab_palette[first_idx].distance(lab_palette[second_idx]);
+            let error = source_lab.distance(mixed_lab) + MIXING_SPREAD_PENALTY * spread * ((ratio - 0.5).abs() + 0.5);
+            (error, ColorMix { first_idx, second_idx, ratio })
+        })
+        .min_by(|(error_a, _), (error_b, _)| error_a.total_cmp(error_b))
+        .map(|(_, mix)| mix)
+        .unwrap()
+}
+
+fn two_color_mixing<S>(source_image: &RgbImage, palette: &PaletteRGB, strength: S, target_image: &mut RgbImage)
+where
+    S: Fn(usize, usize) -> f32
+{
+    assert!(!palette.is_empty(), "Palette is empty.");
+    let linear_palette = palette.iter()
+        .map(|color| color.to_srgb().into_linear())
+        .collect::<Vec<palette::LinSrgb>>();
+    let lab_palette = palette.iter()
+        .map(|color| color.to_lab())
+        .collect::<Vec<_>>();
+    let matrix = bayer_matrix(MIXING_MATRIX_SIZE);
+    let cells_count = (MIXING_MATRIX_SIZE * MIXING_MATRIX_SIZE) as f32;
+
+    // Flat areas and pixel art repeat the same colors a lot
+    let mut mixes_cache: HashMap<[u8; 3], ColorMix> = HashMap::new();
+
+    for (x, y, source_pixel) in source_image.enumerate_pixels() {
+        let mix = *mixes_cache.entry(source_pixel.0).or_insert_with(|| {
+            let source_color = crate::color::ops::rgbu8_to_srgb(*source_pixel).into_linear();
+            best_mix(source_color, &linear_palette, &lab_palette)
+        });
+
+        let threshold = (matrix[y as usize % MIXING_MATRIX_SIZE][x as usize % MIXING_MATRIX_SIZE] as f32 + 0.5) / cells_count;
+        let threshold = 0.5 + (threshold - 0.5) * strength(x as usize, y as usize);
+        let color_idx = if threshold < mix.ratio { mix.second_idx } else { mix.first_idx };
+        target_image.put_pixel(x, y, palette[color_idx].to_rgbu8());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::ColorRGB;
+
+    #[test]
+    fn test_mixing_uses_neighbouring_colors() {
+        let palette = PaletteRGB::from(vec![ColorRGB([0, 0, 0]), ColorRGB([128, 128, 128]), ColorRGB([255, 255, 255])]);
+
+        // Light gray is mixed from gray and white, black would only add noise
+        let source_image = RgbImage::from_pixel(8, 8, image::Rgb([200, 200, 200]));
+        let processed_image = two_color_mixing_dithering(source_image, palette);
+
+        assert!(processed_image.pixels().all(|px| px.0 != [0, 0, 0]));
+        assert!(processed_image.pixels().any(|px| px.0 == [128, 128, 128]));
+        assert!(processed_image.pixels().any(|px| px.0 == [255, 255, 255]));
+    }
+}
@@ -1,4 +1,43 @@
 pub mod kmean;
 pub mod kernel;
+pub mod raw;
+#[cfg(feature = "std")]
 pub mod thresholding;
-pub mod dithering;
\ No newline at end of file
+#[cfg(feature = "std")]
+pub mod dithering;
+#[cfg(feature = "std")]
+pub mod edges;
+#[cfg(feature = "std")]
+pub mod dot_diffusion;
+#[cfg(feature = "std")]
+pub mod ordered;
+#[cfg(feature = "std")]
+pub mod ostromoukhov;
+#[cfg(feature = "std")]
+pub mod preprocessing;
+#[cfg(feature = "std")]
+pub mod noise;
+#[cfg(feature = "std")]
+pub mod temporal;
+#[cfg(feature = "std")]
+pub mod spatial;
+#[cfg(feature = "std")]
+pub mod checkerboard;
+#[cfg(feature = "std")]
+pub mod duotone;
+#[cfg(feature = "std")]
+pub mod mixing;
+#[cfg(feature = "std")]
+pub mod tiles;
+#[cfg(feature = "std")]
+pub mod hardware;
+#[cfg(feature = "std")]
+pub mod posterize;
+#[cfg(feature = "std")]
+pub mod response;
+#[cfg(feature = "std")]
+pub mod regions;
+#[cfg(feature = "std")]
+pub mod outline;
+#[cfg(feature = "std")]
+pub mod despeckle;
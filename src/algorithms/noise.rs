@@ -0,0 +1,127 @@
+use image::{GrayImage, RgbImage};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+/// Probability distribution of generated noise.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, clap::ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NoiseDistribution {
+    /// Every offset equally probable, grainy look
+    Uniform,
+
+    /// Sum of two uniform offsets, small offsets more probable, less visible grain
+    #[default]
+    Triangular,
+}
+
+/// Random noise added to an image before quantization.
+///
+/// Followed by thresholding it gives random dithering, a cheap alternative
+/// between plain thresholding and error diffusion.
+///
+/// # Example
+/// ```
+/// use ditherum::algorithms::noise::{Noise, NoiseDistribution};
+///
+/// let noise = Noise {
+///     amplitude: 0.2,
+///     distribution: NoiseDistribution::Uniform,
+///     seed: 42,
+/// };
+/// let img = image::RgbImage::from_pixel(4, 4, image::Rgb([128, 128, 128]));
+/// assert_eq!(noise.apply(img.clone()), noise.apply(img));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Noise {
+    /// Maximal offset as a fraction of channel range, `0.0` disables noise.
+    pub amplitude: f32,
+
+    /// Distribution of offsets within `-amplitude..amplitude`.
+    pub distribution: NoiseDistribution,
+
+    /// Seed of the random generator, the same seed gives the same noise.
+    pub seed: u64,
+}
+
+impl Default for Noise {
+    fn default() -> Self {
+        Self {
+            amplitude: 0.0,
+            distribution: NoiseDistribution::default(),
+            seed: 0,
+        }
+    }
+}
+
+impl Noise {
+    /// Adds noise to every pixel of the image.
+    ///
+    /// The same offset is added to all channels of a pixel, so that noise
+    /// changes lightness without introducing color speckles.
+    ///
+    /// # Parameters
+    /// - `source_image`: The input `RgbImage`.
+    ///
+    /// # Returns
+    /// - Noisy `RgbImage` of the same dimensions.
+    pub fn apply(&self, source_image: RgbImage) -> RgbImage {
+        self.apply_with_strength(source_image, |_, _| 1.0)
+    }
+
+    /// Adds noise to every pixel of the image with amplitude modulated by a mask.
+    ///
+    /// # Parameters
+    /// - `source_image`: The input `RgbImage`.
+    /// - `mask`: Noise strength of each pixel, white gives full amplitude, black gives no noise.
+    ///
+    /// # Panics
+    /// Panics if mask dimensions differ from image dimensions.
+    pub fn apply_masked(&self, source_image: RgbImage, mask: &GrayImage) -> RgbImage {
+        assert_eq!(source_image.dimensions(), mask.dimensions(), "Mask dimensions differ from image dimensions.");
+        self.apply_with_strength(source_image, |x, y| mask.get_pixel(x, y).0[0] as f32 / 255.0)
+    }
+
+    fn apply_with_strength<S>(&self, mut source_image: RgbImage, strength: S) -> RgbImage
+    where
+        S: Fn(u32, u32) -> f32
+    {
+        if self.amplitude == 0.0 {
+            return source_image;
+        }
+
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let amplitude = self.amplitude * 255.0;
+
+        source_image.enumerate_pixels_mut()
+            .for_each(|(x, y, pixel)| {
+                let offset = amplitude * strength(x, y) * match self.distribution {
+                    NoiseDistribution::Uniform => rng.random_range(-1.0..1.0),
+                    NoiseDistribution::Triangular => rng.random::<f32>() - rng.random::<f32>(),
+                };
+                pixel.0 = pixel.0.map(|channel| (channel as f32 + offset).round().clamp(0.0, 255.0) as u8);
+            });
+
+        source_image
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_noise_within_amplitude() {
+        let source_image = RgbImage::from_pixel(32, 32, image::Rgb([128, 128, 128]));
+
+        for distribution in [NoiseDistribution::Uniform, NoiseDistribution::Triangular] {
+            let noise = Noise { amplitude: 0.1, distribution, seed: 7 };
+            let noisy_image = noise.apply(source_image.clone());
+
+            assert!(noisy_image.pixels().all(|px| px.0[0] == px.0[1] && px.0[1] == px.0[2]));
+            assert!(noisy_image.pixels().all(|px| px.0[0].abs_diff(128) <= 26));
+            assert!(noisy_image.pixels().any(|px| px.0[0] != 128));
+            assert_ne!(noisy_image, Noise { seed: 8, ..noise }.apply(source_image.clone()));
+        }
+    }
+}
@@ -0,0 +1,312 @@
+use std::collections::HashMap;
+
+use image::{GrayImage, Rgb32FImage, RgbImage};
+
+use crate::{algorithms::dithering::mask_strength, color::{self, ColorRGB}, palette::PaletteRGB};
+
+/// Size of Bayer matrix used by pattern dithering.
+const KNOLL_MATRIX_SIZE: usize = 8;
+
+/// Number of palette colors mixed for each source color in pattern dithering.
+const KNOLL_CANDIDATES_COUNT: usize = 16;
+
+/// Generates Bayer threshold matrix of given size with values in range `0..size*size`.
+///
+/// # Parameters
+/// - `size`: Matrix size, power of two.
+///
+/// # Panics
+/// Panics if `size` is not a power of two.
+///
+/// # Example
+/// ```
+/// use ditherum::algorithms::ordered::bayer_matrix;
+///
+/// assert_eq!(bayer_matrix(2), vec![vec![0, 2], vec![3, 1]]);
+/// ```
+pub fn bayer_matrix(size: usize) -> Vec<Vec<usize>> {
+    assert!(size.is_power_of_two(), "Bayer matrix size should be power of two.");
+
+    let mut matrix = vec![vec![0]];
+    while matrix.len() < size {
+        let half = matrix.len();
+        let mut next_matrix = vec![vec![0; half * 2]; half * 2];
+
+        for (y, row) in matrix.iter().enumerate() {
+            for (x, &value) in row.iter().enumerate() {
+                next_matrix[y][x] = 4 * value;
+                next_matrix[y][x + half] = 4 * value + 2;
+                next_matrix[y + half][x] = 4 * value + 3;
+                next_matrix[y + half][x + half] = 4 * value + 1;
+            }
+        }
+        matrix = next_matrix;
+    }
+
+    matrix
+}
+
+/// Applies ordered dithering using Bayer threshold matrix.
+///
+/// # Parameters
+/// - `source_image`: The input `RgbImage` to be dithered.
+/// - `palette`: A `PaletteRGB` containing the target colors for dithering.
+/// - `matrix_size`: Size of the Bayer matrix, power of two.
+///
+/// # Returns
+/// - A dithered `RgbImage` containing only palette colors.
+///
+/// # Algorithm Details
+/// Each pixel is offset by a threshold taken from the Bayer matrix tiled over the image,
+/// then replaced with the closest palette color. Offsets are scaled by estimated distance
+/// between palette colors, so both small and large palettes get visible but not excessive pattern.
+/// Pixels are processed independently, so no error is propagated between them.
+pub fn ordered_dithering_bayer(mut source_image: RgbImage, palette: PaletteRGB, matrix_size: usize) -> RgbImage {
+    ordered_bayer_in_place(&mut source_image, &palette, matrix_size, |_, _| 1.0);
+    source_image
+}
+
+/// Same as [`ordered_dithering_bayer`], but writes result to `target_image`, reallocating it
+/// only if its dimensions differ from the source ones.
+pub fn ordered_dithering_bayer_into(source_image: &RgbImage, palette: &PaletteRGB, matrix_size: usize, target_image: &mut RgbImage) {
+    copy_into(source_image, target_image);
+    ordered_bayer_in_place(target_image, palette, matrix_size, |_, _| 1.0);
+}
+
+/// Applies ordered dithering using Bayer threshold matrix with strength modulated by a mask.
+///
+/// # Parameters
+/// - `source_image`: The input `RgbImage` to be dithered.
+/// - `palette`: A `PaletteRGB` containing the target colors for dithering.
+/// - `matrix_size`: Size of the Bayer matrix, power of two.
+/// - `mask`: Dithering strength of each pixel, white gives full dithering, black gives plain thresholding.
+///
+/// # Panics
+/// Panics if mask dimensions differ from image dimensions or `matrix_size` is not a power of two.
+pub fn ordered_dithering_bayer_masked(mut source_image: RgbImage, palette: PaletteRGB, matrix_size: usize, mask: &GrayImage) -> RgbImage {
+    assert_eq!(source_image.dimensions(), mask.dimensions(), "Mask dimensions differ from image dimensions.");
+    ordered_bayer_in_place(&mut source_image, &palette, matrix_size, |x, y| mask_strength(mask, x, y));
+    source_image
+}
+
+/// Same as [`ordered_dithering_bayer_masked`], but writes result to `target_image`, reallocating it
+/// only if its dimensions differ from the source ones.
+///
+/// # Panics
+/// Panics if mask dimensions differ from image dimensions or `matrix_size` is not a power of two.
+pub fn ordered_dithering_bayer_masked_into(source_image: &RgbImage, palette: &PaletteRGB, matrix_size: usize, mask: &GrayImage, target_image: &mut RgbImage) {
+    assert_eq!(source_image.dimensions(), mask.dimensions(), "Mask dimensions differ from image dimensions.");
+    copy_into(source_image, target_image);
+    ordered_bayer_in_place(target_image, palette, matrix_size, |x, y| mask_strength(mask, x, y));
+}
+
+/// Applies Knoll pattern dithering, known from Adobe Photoshop.
+///
+/// # Parameters
+/// - `source_image`: The input `RgbImage` to be dithered.
+/// - `palette`: A `PaletteRGB` containing the target colors for dithering.
+///
+/// # Returns
+/// - A dithered `RgbImage` containing only palette colors.
+///
+/// # Algorithm Details
+/// For each source color a mix of 16 palette colors is built: every next color is the one
+/// closest to the source corrected by error accumulated by previous ones, so their average
+/// approximates the source. Mix sorted by lightness is then indexed with 8x8 Bayer matrix
+/// threshold. Unlike plain ordered dithering, any palette gives regular patterns of exactly
+/// the colors blending into the source, which is the typical look of retro game art.
+///
+/// # Example
+/// ```
+/// use ditherum::{algorithms::ordered::pattern_dithering_knoll, palette::PaletteRGB};
+///
+/// let source_image = image::RgbImage::from_pixel(8, 8, image::Rgb([128, 128, 128]));
+/// let processed_image = pattern_dithering_knoll(source_image, PaletteRGB::black_and_white());
+///
+/// let white_count = processed_image.pixels().filter(|px| px.0 == [255, 255, 255]).count();
+/// assert_eq!(white_count, 32);
+/// ```
+pub fn pattern_dithering_knoll(mut source_image: RgbImage, palette: PaletteRGB) -> RgbImage {
+    knoll_in_place(&mut source_image, &palette, |_, _| u8::MAX);
+    source_image
+}
+
+/// Same as [`pattern_dithering_knoll`], but writes result to `target_image`, reallocating it
+/// only if its dimensions differ from the source ones.
+pub fn pattern_dithering_knoll_into(source_image: &RgbImage, palette: &PaletteRGB, target_image: &mut RgbImage) {
+    copy_into(source_image, target_image);
+    knoll_in_place(target_image, palette, |_, _| u8::MAX);
+}
+
+/// Applies Knoll pattern dithering with strength modulated by a mask.
+///
+/// Mask scales error carried between mixed colors, so black areas get plain thresholding.
+///
+/// # Panics
+/// Panics if mask dimensions differ from image dimensions.
+pub fn pattern_dithering_knoll_masked(mut source_image: RgbImage, palette: PaletteRGB, mask: &GrayImage) -> RgbImage {
+    assert_eq!(source_image.dimensions(), mask.dimensions(), "Mask dimensions differ from image dimensions.");
+    knoll_in_place(&mut source_image, &palette, |x, y| mask.get_pixel(x, y).0[0]);
+    source_image
+}
+
+/// Same as [`pattern_dithering_knoll_masked`], but writes result to `target_image`, reallocating it
+/// only if its dimensions differ from the source ones.
+///
+/// # Panics
+/// Panics if mask dimensions differ from image dimensions.
+pub fn pattern_dithering_knoll_masked_into(source_image: &RgbImage, palette: &PaletteRGB, mask: &GrayImage, target_image: &mut RgbImage) {
+    assert_eq!(source_image.dimensions(), mask.dimensions(), "Mask dimensions differ from image dimensions.");
+    copy_into(source_image, target_image);
+    knoll_in_place(target_image, palette, |x, y| mask.get_pixel(x, y).0[0]);
+}
+
+fn knoll_in_place<S>(image: &mut RgbImage, palette: &PaletteRGB, strength: S)
+where
+    S: Fn(u32, u32) -> u8
+{
+    let matrix = bayer_matrix(KNOLL_MATRIX_SIZE);
+    let cells_count = KNOLL_MATRIX_SIZE * KNOLL_MATRIX_SIZE;
+    let srgb_palette = palette.clone().to_srgb();
+    let lightness = palette.iter()
+        .map(|color| color.to_lab().l)
+        .collect::<Vec<_>>();
+
+    // Mix depends only on source color and strength, images usually repeat them a lot
+    let mut mixes_cache: HashMap<([u8; 3], u8), [usize; KNOLL_CANDIDATES_COUNT]> = HashMap::new();
+
+    image.enumerate_pixels_mut()
+        .for_each(|(x, y, pixel)| {
+            let strength = strength(x, y);
+            let mix = mixes_cache.entry((pixel.0, strength))
+                .or_insert_with(|| knoll_mix(color::ops::rgbu8_to_srgb(*pixel), strength as f32 / 255.0, &srgb_palette, &lightness));
+            let threshold = matrix[y as usize % KNOLL_MATRIX_SIZE][x as usize % KNOLL_MATRIX_SIZE];
+            *pixel = palette[mix[threshold * KNOLL_CANDIDATES_COUNT / cells_count]].to_rgbu8();
+        });
+}
+
+/// Returns indices of palette colors whose average approximates source color, sorted by lightness.
+fn knoll_mix(source_color: palette::Srgb, error_multiplier: f32, srgb_palette: &[palette::Srgb], lightness: &[f32]) -> [usize; KNOLL_CANDIDATES_COUNT] {
+    let mut error = palette::Srgb::new(0.0, 0.0, 0.0);
+    let mut mix = [0; KNOLL_CANDIDATES_COUNT];
+
+    for candidate in mix.iter_mut() {
+        let attempt = color::ops::srgb_add(&source_color, &color::ops::srgb_mul_scalar(&error, error_multiplier));
+        let closest_color = color::ops::find_closest_srgb_color(&attempt, srgb_palette);
+        *candidate = srgb_palette.iter()
+            .position(|palette_color| *palette_color == closest_color)
+            .unwrap_or_default();
+        error = color::ops::srgb_add(&error, &color::ops::srgb_sub(&source_color, &closest_color));
+    }
+
+    mix.sort_by(|a, b| lightness[*a].partial_cmp(&lightness[*b]).unwrap_or(std::cmp::Ordering::Equal));
+    mix
+}
+
+/// Pixels are processed independently, so source is copied and dithered in place.
+fn copy_into(source_image: &RgbImage, target_image: &mut RgbImage) {
+    crate::image::manip::match_dimensions(target_image, source_image);
+    target_image.copy_from_slice(source_image);
+}
+
+/// Same as [`ordered_dithering_bayer`], but takes floating point image, e.g. loaded from 16-bit file
+/// with [`crate::image::load_image_f32`], so it is not quantized to 8 bits beforehand.
+///
+/// # Panics
+/// Panics if `matrix_size` is not a power of two.
+pub fn ordered_dithering_bayer_f32(source_image: &Rgb32FImage, palette: &PaletteRGB, matrix_size: usize) -> RgbImage {
+    let bayer = BayerThresholds::new(palette, matrix_size);
+    RgbImage::from_fn(source_image.width(), source_image.height(), |x, y| {
+        let pixel = source_image.get_pixel(x, y);
+        bayer.closest(palette::Srgb::new(pixel[0], pixel[1], pixel[2]), x, y, 1.0)
+    })
+}
+
+/// Bayer matrix scaled to palette colors spacing.
+struct BayerThresholds {
+    matrix: Vec<Vec<usize>>,
+    matrix_size: usize,
+    srgb_palette: Vec<palette::Srgb>,
+    spread: f32,
+}
+
+impl BayerThresholds {
+    fn new(palette: &PaletteRGB, matrix_size: usize) -> Self {
+        Self {
+            matrix: bayer_matrix(matrix_size),
+            matrix_size,
+            srgb_palette: palette.clone().to_srgb(),
+            // Approximate palette colors spacing along each channel
+            spread: 1.0 / (palette.len() as f32).cbrt(),
+        }
+    }
+
+    fn closest(&self, srgb_color: palette::Srgb, x: u32, y: u32, strength: f32) -> image::Rgb<u8> {
+        let cells_count = (self.matrix_size * self.matrix_size) as f32;
+        let threshold = self.matrix[y as usize % self.matrix_size][x as usize % self.matrix_size];
+        let offset = self.spread * ((threshold as f32 + 0.5) / cells_count - 0.5) * strength;
+
+        let offset_color = palette::Srgb::new(
+            srgb_color.red + offset,
+            srgb_color.green + offset,
+            srgb_color.blue + offset
+        );
+        let closest_color = color::ops::find_closest_srgb_color(&offset_color, &self.srgb_palette);
+        ColorRGB::from_srgb(closest_color).to_rgbu8()
+    }
+}
+
+fn ordered_bayer_in_place<S>(image: &mut RgbImage, palette: &PaletteRGB, matrix_size: usize, strength: S)
+where 
+    S: Fn(usize, usize) -> f32
+{
+    let bayer = BayerThresholds::new(palette, matrix_size);
+    image.enumerate_pixels_mut()
+        .for_each(|(x, y, pixel)| {
+            let srgb_color = color::ops::rgbu8_to_srgb(*pixel);
+            *pixel = bayer.closest(srgb_color, x, y, strength(x as usize, y as usize));
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bayer_matrix_contains_all_thresholds() {
+        let size = 8;
+        let mut values = bayer_matrix(size).concat();
+        values.sort();
+        assert_eq!(values, (0..size * size).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_ordered_dithering_gray_mix() {
+        // Mid gray should be represented by black and white mix
+        let source_image = RgbImage::from_pixel(8, 8, image::Rgb([128, 128, 128]));
+        let processed_image = ordered_dithering_bayer(source_image, PaletteRGB::black_and_white(), 4);
+
+        let white_count = processed_image.pixels()
+            .filter(|px| px.0 == [255, 255, 255])
+            .count();
+        assert_eq!(white_count, 32);
+    }
+
+    #[test]
+    fn test_knoll_mix_approximates_color() {
+        let palette = PaletteRGB::primary_bw();
+        let source_image = RgbImage::from_pixel(8, 8, image::Rgb([200, 100, 30]));
+        let processed_image = pattern_dithering_knoll(source_image.clone(), palette.clone());
+
+        let mean_color: [f32; 3] = std::array::from_fn(|channel| {
+            processed_image.pixels().map(|px| px.0[channel] as f32).sum::<f32>() / 64.0
+        });
+        assert!(mean_color.iter().zip([200.0, 100.0, 30.0]).all(|(mean, source)| (mean - source).abs() < 24.0), "{mean_color:?}");
+
+        let black_mask = GrayImage::new(8, 8);
+        assert_eq!(
+            pattern_dithering_knoll_masked(source_image.clone(), palette.clone(), &black_mask),
+            crate::algorithms::thresholding::thresholding_rgb(source_image, palette)
+        );
+    }
+}
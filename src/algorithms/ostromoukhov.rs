@@ -0,0 +1,154 @@
+use image::{GrayImage, RgbImage};
+
+use crate::{
+    algorithms::{dithering::{mask_strength, ErrorDiffusion}, kernel::{self, EdgePolicy, MutKernel}},
+    color,
+    palette::PaletteRGB
+};
+
+/// Weights of error spread to right, down-left and down neighbours for intensities `0..128`,
+/// the upper half mirrors them. Taken from Ostromoukhov, "A Simple and Efficient
+/// Error-Diffusion Algorithm", SIGGRAPH 2001.
+const OSTROMOUKHOV_WEIGHTS: [(u16, u16, u16); 128] = [
+    (13, 0, 5), (13, 0, 5), (21, 0, 10), (7, 0, 4), (8, 0, 5), (47, 3, 28), (23, 3, 13), (15, 3, 8),
+    (22, 6, 11), (43, 15, 20), (7, 3, 3), (501, 224, 211), (249, 116, 103), (165, 80, 67), (123, 62, 49), (489, 256, 191),
+    (81, 44, 31), (483, 272, 181), (60, 35, 22), (53, 32, 19), (237, 148, 83), (471, 304, 161), (3, 2, 1), (481, 314, 185),
+    (354, 226, 155), (1389, 866, 685), (227, 138, 125), (267, 158, 163), (327, 188, 220), (61, 34, 45), (627, 338, 505), (1227, 638, 1075),
+    (20, 10, 19), (1937, 1000, 1767), (977, 520, 855), (657, 360, 551), (71, 40, 57), (2005, 1160, 1539), (337, 200, 247), (2039, 1240, 1425),
+    (257, 160, 171), (691, 440, 437), (1045, 680, 627), (301, 200, 171), (177, 120, 95), (2141, 1480, 1083), (1079, 760, 513), (725, 520, 323),
+    (137, 100, 57), (2209, 1640, 855), (53, 40, 19), (2243, 1720, 741), (565, 440, 171), (759, 600, 209), (1147, 920, 285), (2311, 1880, 513),
+    (97, 80, 19), (335, 280, 57), (1181, 1000, 171), (793, 680, 95), (599, 520, 57), (2413, 2120, 171), (405, 360, 19), (2447, 2200, 57),
+    (11, 10, 0), (158, 151, 3), (178, 179, 7), (1030, 1091, 63), (248, 277, 21), (318, 375, 35), (458, 571, 63), (878, 1159, 147),
+    (5, 7, 1), (172, 181, 37), (97, 76, 22), (72, 41, 17), (119, 47, 29), (4, 1, 1), (4, 1, 1), (4, 1, 1),
+    (4, 1, 1), (4, 1, 1), (4, 1, 1), (4, 1, 1), (4, 1, 1), (4, 1, 1), (65, 18, 17), (95, 29, 26),
+    (185, 62, 53), (30, 11, 9), (35, 14, 11), (85, 37, 28), (55, 26, 19), (80, 41, 29), (155, 86, 59), (5, 3, 2),
+    (5, 3, 2), (5, 3, 2), (5, 3, 2), (5, 3, 2), (5, 3, 2), (5, 3, 2), (5, 3, 2), (5, 3, 2),
+    (5, 3, 2), (5, 3, 2), (5, 3, 2), (5, 3, 2), (305, 176, 119), (155, 86, 59), (105, 56, 39), (80, 41, 29),
+    (65, 32, 23), (55, 26, 19), (335, 152, 113), (85, 37, 28), (115, 48, 37), (35, 14, 11), (355, 136, 109), (30, 11, 9),
+    (365, 128, 107), (185, 62, 53), (25, 8, 7), (95, 29, 26), (385, 112, 103), (65, 18, 17), (395, 104, 101), (4, 1, 1),
+];
+
+/// Applies Ostromoukhov variable-coefficient error diffusion using a given color palette.
+///
+/// # Parameters
+/// - `source_image`: The input `RgbImage` to be dithered.
+/// - `palette`: A `PaletteRGB` containing the target colors for dithering.
+///
+/// # Returns
+/// - A dithered `RgbImage` that approximates the input image using the specified palette.
+///
+/// # Algorithm Details
+/// Each pixel is replaced with the closest palette color in sRGB space and quantization error
+/// is spread to right, down-left and down neighbours with weights depending on source pixel
+/// lightness. Weights were optimized for blue noise spectrum at every intensity, so unlike
+/// fixed Floyd-Steinberg weights they do not produce worm artifacts in highlights and shadows.
+/// Rows are scanned in serpentine order, see [`kernel::apply_kernel_processing_serpentine`],
+/// and accumulated error is limited like in [`ErrorDiffusion::default`].
+///
+/// # Example
+/// ```
+/// use ditherum::{algorithms::ostromoukhov::dithering_ostromoukhov, palette::PaletteRGB};
+///
+/// let source_image = image::RgbImage::from_pixel(16, 16, image::Rgb([128, 128, 128]));
+/// let processed_image = dithering_ostromoukhov(source_image, PaletteRGB::black_and_white());
+///
+/// // Half of the pixels are white
+/// let white_count = processed_image.pixels().filter(|px| px.0 == [255, 255, 255]).count();
+/// assert!((118..=138).contains(&white_count));
+/// ```
+pub fn dithering_ostromoukhov(source_image: RgbImage, palette: PaletteRGB) -> RgbImage {
+    let mut target_image = RgbImage::new(source_image.width(), source_image.height());
+    dithering_ostromoukhov_into(&source_image, &palette, &mut target_image);
+    target_image
+}
+
+/// Same as [`dithering_ostromoukhov`], but writes result to `target_image`, reallocating it
+/// only if its dimensions differ from the source ones.
+pub fn dithering_ostromoukhov_into(source_image: &RgbImage, palette: &PaletteRGB, target_image: &mut RgbImage) {
+    crate::image::manip::match_dimensions(target_image, source_image);
+    ostromoukhov(source_image, palette, |_, _| 1.0, target_image);
+}
+
+/// Applies Ostromoukhov error diffusion with strength modulated by a mask.
+///
+/// See [`crate::algorithms::dithering::dithering_floyd_steinberg_rgb_masked`].
+///
+/// # Panics
+/// Panics if mask dimensions differ from image dimensions.
+pub fn dithering_ostromoukhov_masked(source_image: RgbImage, palette: PaletteRGB, mask: &GrayImage) -> RgbImage {
+    let mut target_image = RgbImage::new(source_image.width(), source_image.height());
+    dithering_ostromoukhov_masked_into(&source_image, &palette, mask, &mut target_image);
+    target_image
+}
+
+/// Same as [`dithering_ostromoukhov_masked`], but writes result to `target_image`, reallocating it
+/// only if its dimensions differ from the source ones.
+///
+/// # Panics
+/// Panics if mask dimensions differ from image dimensions.
+pub fn dithering_ostromoukhov_masked_into(source_image: &RgbImage, palette: &PaletteRGB, mask: &GrayImage, target_image: &mut RgbImage) {
+    assert_eq!(source_image.dimensions(), mask.dimensions(), "Mask dimensions differ from image dimensions.");
+    crate::image::manip::match_dimensions(target_image, source_image);
+    ostromoukhov(source_image, palette, |x, y| mask_strength(mask, x, y), target_image);
+}
+
+/// Returns normalized weights of error spread to right, down-left and down neighbours.
+fn weights(lightness: f32) -> (f32, f32, f32) {
+    let intensity = (lightness.clamp(0.0, 1.0) * 255.0).round() as usize;
+    let (right, down_left, down) = OSTROMOUKHOV_WEIGHTS[intensity.min(255 - intensity)];
+    let sum = (right + down_left + down) as f32;
+    (right as f32 / sum, down_left as f32 / sum, down as f32 / sum)
+}
+
+fn ostromoukhov<S>(source_image: &RgbImage, palette: &PaletteRGB, strength: S, target_image: &mut RgbImage)
+where
+    S: Fn(usize, usize) -> f32
+{
+    let srgb_palette = palette.clone().to_srgb();
+    let diffusion = ErrorDiffusion::default();
+    let (_, _, mut rgb_matrix) = crate::image::manip::rgb_image_ref_to_float_srgb_vec(source_image);
+
+    // Weights follow source lightness, not the one modified by accumulated error
+    let lightness = (0..source_image.height())
+        .map(|y| (0..source_image.width())
+            .map(|x| color::ops::rgbu8_to_lab(*source_image.get_pixel(x, y)).l / 100.0)
+            .collect::<Vec<_>>()
+        )
+        .collect::<Vec<_>>();
+
+    kernel::apply_kernel_processing_serpentine(&mut rgb_matrix, (1, 0), EdgePolicy::Skip, |mut kernel: MutKernel<palette::Srgb, 3, 2>| {
+        let (x, y) = kernel.position();
+        let Some(current) = kernel.get_mut(1, 0) else { return };
+        let closest_color = color::ops::find_closest_srgb_color(current, &srgb_palette);
+        let quant_error = color::ops::srgb_mul_scalar(&color::ops::srgb_sub(current, &closest_color), strength(x, y));
+        *current = closest_color;
+
+        let (weight_right, weight_down_left, weight_down) = weights(lightness[y][x]);
+        for (kx, ky, weight) in [(2, 0, weight_right), (0, 1, weight_down_left), (1, 1, weight_down)] {
+            if let Some(neighbour) = kernel.get_mut(kx, ky) {
+                diffusion.spread_srgb(neighbour, &quant_error, weight);
+            }
+        }
+    });
+
+    crate::image::manip::srgb_vec_into_rgb_image_using_palette(&rgb_matrix, palette, target_image);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weights_are_symmetric() {
+        assert_eq!(weights(0.0), weights(1.0));
+        assert_eq!(weights(0.25), weights(1.0 - 0.25));
+        let (right, down_left, down) = weights(0.5);
+        assert!((right + down_left + down - 1.0).abs() < 1e-6);
+
+        // Highlights keep their density without worms
+        let source_image = RgbImage::from_pixel(32, 32, image::Rgb([235, 235, 235]));
+        let processed_image = dithering_ostromoukhov(source_image, PaletteRGB::black_and_white());
+        let black_count = processed_image.pixels().filter(|px| px.0 == [0, 0, 0]).count();
+        assert!((40..=110).contains(&black_count), "black_count={black_count}");
+    }
+}
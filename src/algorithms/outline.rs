@@ -0,0 +1,152 @@
+use std::{fmt::Display, str::FromStr};
+
+use image::RgbImage;
+
+use crate::color::ColorRGB;
+
+/// Error returned when parsing outline fails.
+#[derive(Debug, thiserror::Error)]
+#[error("Invalid outline '{0}', expected 'COLOR' or 'COLOR:AROUND' like 'black:#ff0000'")]
+pub struct OutlineParseError(pub String);
+
+/// One pixel wide contour drawn over quantized image.
+///
+/// Parsed from `COLOR` outlining borders between all palette regions or `COLOR:AROUND`
+/// outlining only regions of `AROUND` color, both as hex or named colors.
+///
+/// # Example
+/// ```
+/// use ditherum::{algorithms::outline::Outline, color::ColorRGB};
+///
+/// let outline = "black:#ff0000".parse::<Outline>().unwrap();
+/// assert_eq!(outline, Outline::new(ColorRGB([0, 0, 0])).with_around(ColorRGB([255, 0, 0])));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Outline {
+    /// Color of contour pixels.
+    pub color: ColorRGB,
+
+    /// Color of regions being outlined, all regions if `None`.
+    pub around: Option<ColorRGB>,
+}
+
+impl Outline {
+    pub fn new(color: ColorRGB) -> Self {
+        Self { color, around: None }
+    }
+
+    /// Outlines only regions of given color instead of borders between all regions.
+    pub fn with_around(mut self, around: ColorRGB) -> Self {
+        self.around = Some(around);
+        self
+    }
+}
+
+impl FromStr for Outline {
+    type Err = OutlineParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || OutlineParseError(s.to_string());
+
+        let (color, around) = match s.split_once(':') {
+            Some((color, around)) => (color, Some(around)),
+            None => (s, None),
+        };
+        let outline = Self::new(color.trim().parse().map_err(|_| invalid())?);
+        match around {
+            Some(around) => Ok(outline.with_around(around.trim().parse().map_err(|_| invalid())?)),
+            None => Ok(outline),
+        }
+    }
+}
+
+impl Display for Outline {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.around {
+            Some(around) => write!(f, "{}:{}", self.color.to_hex(), around.to_hex()),
+            None => write!(f, "{}", self.color.to_hex()),
+        }
+    }
+}
+
+/// Draws one pixel wide outlines over quantized image.
+///
+/// # Parameters
+/// - `source_image`: Quantized `RgbImage`, regions are areas of exactly the same color.
+/// - `outline`: Color of contour and optional color of outlined regions.
+///
+/// # Returns
+/// - An `RgbImage` of the same dimensions with contour pixels replaced by outline color.
+///
+/// # Algorithm Details
+/// Without `around` color a pixel becomes contour if its right or bottom neighbor has
+/// a different color, so each border between two regions gets single pixel line on its
+/// top-left side. With `around` color pixels of other colors touching the region by edge
+/// become contour, so the region itself is left intact and gets surrounded from outside.
+///
+/// # Example
+/// ```
+/// use ditherum::{algorithms::outline::{draw_outline, Outline}, color::ColorRGB};
+///
+/// let source_image = image::RgbImage::from_fn(6, 1, |x, _| if x < 3 { image::Rgb([255, 255, 255]) } else { image::Rgb([255, 0, 0]) });
+/// let outlined_image = draw_outline(&source_image, &Outline::new(ColorRGB([0, 0, 0])));
+///
+/// assert_eq!(outlined_image.get_pixel(2, 0).0, [0, 0, 0]);
+/// assert_eq!(outlined_image.get_pixel(3, 0).0, [255, 0, 0]);
+/// ```
+pub fn draw_outline(source_image: &RgbImage, outline: &Outline) -> RgbImage {
+    let (width, height) = source_image.dimensions();
+    let outline_pixel = outline.color.to_rgbu8();
+
+    let is_contour = |x: u32, y: u32| {
+        let pixel = source_image.get_pixel(x, y);
+        match outline.around {
+            Some(around) => {
+                let around = around.to_rgbu8();
+                let neighbors = [
+                    x.checked_sub(1).map(|x| (x, y)),
+                    (x + 1 < width).then_some((x + 1, y)),
+                    y.checked_sub(1).map(|y| (x, y)),
+                    (y + 1 < height).then_some((x, y + 1)),
+                ];
+                *pixel != around && neighbors.into_iter().flatten().any(|(x, y)| *source_image.get_pixel(x, y) == around)
+            },
+            None => {
+                (x + 1 < width && source_image.get_pixel(x + 1, y) != pixel)
+                    || (y + 1 < height && source_image.get_pixel(x, y + 1) != pixel)
+            },
+        }
+    };
+
+    RgbImage::from_fn(width, height, |x, y| {
+        if is_contour(x, y) {
+            outline_pixel
+        } else {
+            *source_image.get_pixel(x, y)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outline_around_color() {
+        let red = image::Rgb([255, 0, 0]);
+        let white = image::Rgb([255, 255, 255]);
+        let source_image = RgbImage::from_fn(7, 7, |x, y| if (2..5).contains(&x) && (2..5).contains(&y) { red } else { white });
+        let outline = Outline::new(ColorRGB([0, 0, 0])).with_around(ColorRGB([255, 0, 0]));
+
+        let outlined_image = draw_outline(&source_image, &outline);
+        let outline_pixels = outlined_image.pixels().filter(|px| px.0 == [0, 0, 0]).count();
+        assert_eq!(outline_pixels, 12);
+        assert_eq!(outlined_image.pixels().filter(|px| **px == red).count(), 9);
+        assert_eq!(*outlined_image.get_pixel(1, 1), white);
+
+        for invalid in ["", "notacolor", "black:", "black:notacolor"] {
+            assert!(invalid.parse::<Outline>().is_err(), "{invalid}");
+        }
+        assert_eq!(outline.to_string().parse::<Outline>().unwrap(), outline);
+    }
+}
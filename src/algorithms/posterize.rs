@@ -0,0 +1,184 @@
+use std::{fmt::Display, str::FromStr};
+
+use image::RgbImage;
+
+use crate::{
+    algorithms::{kernel::{self, ERROR_WEIGHTS_2X2}, ordered::bayer_matrix},
+    color,
+    image::ProcessingAlgorithm
+};
+
+/// Error returned when parsing channel levels fails.
+#[derive(Debug, thiserror::Error)]
+#[error("Invalid levels '{0}', expected levels count like '4', per channel counts like '8,8,4' or bits like 'rgb332'")]
+pub struct ChannelLevelsParseError(pub String);
+
+/// Number of levels of red, green and blue channels, like framebuffer pixel format.
+///
+/// Parsed from levels count of all channels like `4`, per channel counts like `8,8,4` or
+/// bits per channel like `rgb332` or `rgb565`.
+///
+/// # Example
+/// ```
+/// use ditherum::algorithms::posterize::ChannelLevels;
+///
+/// assert_eq!("rgb332".parse::<ChannelLevels>().unwrap(), ChannelLevels([8, 8, 4]));
+/// assert_eq!("8,8,4".parse::<ChannelLevels>().unwrap(), ChannelLevels([8, 8, 4]));
+/// assert_eq!("6".parse::<ChannelLevels>().unwrap(), ChannelLevels([6, 6, 6]));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ChannelLevels(pub [u16; 3]);
+
+impl ChannelLevels {
+    /// Returns total number of colors levels can express.
+    pub fn colors_count(&self) -> usize {
+        self.0.iter().map(|&levels| levels as usize).product()
+    }
+
+    /// Rounds channel value in range `0.0..=1.0` to the closest level of given channel.
+    fn quantize(&self, channel: usize, value: f32) -> f32 {
+        let steps = (self.0[channel] - 1) as f32;
+        (value.clamp(0.0, 1.0) * steps).round() / steps
+    }
+
+    fn quantize_srgb(&self, srgb_color: &palette::Srgb) -> palette::Srgb {
+        palette::Srgb::new(
+            self.quantize(0, srgb_color.red),
+            self.quantize(1, srgb_color.green),
+            self.quantize(2, srgb_color.blue)
+        )
+    }
+}
+
+impl FromStr for ChannelLevels {
+    type Err = ChannelLevelsParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ChannelLevelsParseError(s.to_string());
+        let trimmed = s.trim();
+
+        let levels: [u32; 3] = if let Some(bits) = trimmed.strip_prefix("rgb").or_else(|| trimmed.strip_prefix("RGB")) {
+            let bits = bits.chars()
+                .map(|bit| bit.to_digit(10).filter(|bit| (1..=8).contains(bit)).map(|bit| 1 << bit))
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(invalid)?;
+            bits.try_into().map_err(|_| invalid())?
+        } else {
+            let counts = trimmed.split(',')
+                .map(|count| count.trim().parse::<u32>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| invalid())?;
+            match counts[..] {
+                [count] => [count; 3],
+                [red, green, blue] => [red, green, blue],
+                _ => return Err(invalid()),
+            }
+        };
+
+        if levels.iter().any(|levels| !(2..=256).contains(levels)) {
+            return Err(invalid());
+        }
+        Ok(Self(levels.map(|levels| levels as u16)))
+    }
+}
+
+impl Display for ChannelLevels {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{},{},{}", self.0[0], self.0[1], self.0[2])
+    }
+}
+
+/// Reduces each channel to given number of levels with dithering, without any palette.
+///
+/// Meant for fixed-format framebuffers like RGB332 or RGB565, whose palette of all level
+/// combinations would be needlessly large for palette based algorithms.
+///
+/// # Parameters
+/// - `source_image`: The input `RgbImage` to be quantized.
+/// - `levels`: Number of levels of each channel.
+/// - `algorithm`: Thresholding algorithms round to the closest level, Bayer ones apply ordered
+///   dithering and all remaining ones Floyd-Steinberg error diffusion, done per channel.
+///
+/// # Returns
+/// - An `RgbImage` whose channels take only evenly spaced levels.
+///
+/// # Example
+/// ```
+/// use ditherum::{algorithms::posterize::{posterize, ChannelLevels}, image::ProcessingAlgorithm};
+///
+/// let source_image = ditherum::image::generate_test_gradient_image(32, 4, image::Rgb([0, 0, 0]), image::Rgb([255, 255, 255]));
+/// let processed_image = posterize(&source_image, ChannelLevels([2, 2, 2]), ProcessingAlgorithm::Bayer4);
+///
+/// assert!(processed_image.pixels().flat_map(|px| px.0).all(|channel| channel == 0 || channel == 255));
+/// ```
+pub fn posterize(source_image: &RgbImage, levels: ChannelLevels, algorithm: ProcessingAlgorithm) -> RgbImage {
+    let (width, height) = source_image.dimensions();
+
+    match algorithm {
+        ProcessingAlgorithm::Bayer4 | ProcessingAlgorithm::Bayer8 => {
+            let matrix_size = if algorithm == ProcessingAlgorithm::Bayer4 { 4 } else { 8 };
+            let matrix = bayer_matrix(matrix_size);
+            let cells_count = (matrix_size * matrix_size) as f32;
+
+            RgbImage::from_fn(width, height, |x, y| {
+                let threshold = (matrix[y as usize % matrix_size][x as usize % matrix_size] as f32 + 0.5) / cells_count - 0.5;
+                let srgb_color = color::ops::rgbu8_to_srgb(*source_image.get_pixel(x, y));
+                let offset_color = palette::Srgb::new(
+                    srgb_color.red + threshold / (levels.0[0] - 1) as f32,
+                    srgb_color.green + threshold / (levels.0[1] - 1) as f32,
+                    srgb_color.blue + threshold / (levels.0[2] - 1) as f32
+                );
+                color::ops::srgb_to_rgbu8(levels.quantize_srgb(&offset_color))
+            })
+        },
+        ProcessingAlgorithm::ThresholdingRgb | ProcessingAlgorithm::ThresholdingLab => quantize_image(source_image, levels),
+        _ if width < 2 || height < 2 => quantize_image(source_image, levels),
+        _ => {
+            let (_, _, mut rgb_matrix) = crate::image::manip::rgb_image_ref_to_float_srgb_vec(source_image);
+
+            kernel::apply_2x2_kernel_processing(&mut rgb_matrix, |kernel| {
+                let quantized_color = levels.quantize_srgb(kernel.tl);
+                let quant_error = color::ops::srgb_sub(kernel.tl, &quantized_color);
+                *kernel.tl = quantized_color;
+
+                let (err_weight_tr, err_weight_bl, err_weight_br) = ERROR_WEIGHTS_2X2;
+                *kernel.tr = color::ops::srgb_add(kernel.tr, &color::ops::srgb_mul_scalar(&quant_error, err_weight_tr));
+                *kernel.bl = color::ops::srgb_add(kernel.bl, &color::ops::srgb_mul_scalar(&quant_error, err_weight_bl));
+                *kernel.br = color::ops::srgb_add(kernel.br, &color::ops::srgb_mul_scalar(&quant_error, err_weight_br));
+            });
+
+            RgbImage::from_fn(width, height, |x, y| color::ops::srgb_to_rgbu8(rgb_matrix[y as usize][x as usize]))
+        },
+    }
+}
+
+fn quantize_image(source_image: &RgbImage, levels: ChannelLevels) -> RgbImage {
+    RgbImage::from_fn(source_image.width(), source_image.height(), |x, y| {
+        let srgb_color = color::ops::rgbu8_to_srgb(*source_image.get_pixel(x, y));
+        color::ops::srgb_to_rgbu8(levels.quantize_srgb(&srgb_color))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_posterize_uses_only_levels() {
+        let source_image = crate::image::generate_test_gradient_image(40, 6, image::Rgb([0, 30, 255]), image::Rgb([255, 200, 0]));
+        let levels = "rgb332".parse::<ChannelLevels>().unwrap();
+        assert_eq!(levels.colors_count(), 256);
+        let allowed = |channel: usize, value: u8| (0..levels.0[channel])
+            .any(|level| (level as f32 * 255.0 / (levels.0[channel] - 1) as f32).round() as u8 == value);
+
+        for algorithm in ProcessingAlgorithm::all() {
+            let processed_image = posterize(&source_image, levels, *algorithm);
+            assert_eq!(processed_image.dimensions(), source_image.dimensions());
+            assert!(processed_image.pixels().all(|px| (0..3).all(|channel| allowed(channel, px.0[channel]))), "{algorithm}");
+        }
+
+        for invalid in ["", "1", "257", "8,8", "rgb3", "rgb392", "a"] {
+            assert!(invalid.parse::<ChannelLevels>().is_err(), "{invalid}");
+        }
+    }
+}
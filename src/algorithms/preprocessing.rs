@@ -0,0 +1,271 @@
+use image::RgbImage;
+use serde::{Deserialize, Serialize};
+
+/// Rec. 709 luma coefficients used to desaturate colors.
+const LUMA_WEIGHTS: [f32; 3] = [0.2126, 0.7152, 0.0722];
+
+/// Tonal adjustments applied to an image before quantization.
+///
+/// Default value leaves image unchanged. Adjustments are applied in order:
+/// brightness, contrast, saturation, gamma, sharpening, chroma subsampling.
+///
+/// # Example
+/// ```
+/// use ditherum::algorithms::preprocessing::Preprocessing;
+///
+/// let preprocessing = Preprocessing {
+///     contrast: 1.3,
+///     ..Default::default()
+/// };
+/// assert!(!preprocessing.is_identity());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Preprocessing {
+    /// Value added to every channel, range `-1.0..=1.0`, `0.0` keeps image unchanged.
+    pub brightness: f32,
+
+    /// Channels distance from mid gray multiplier, `1.0` keeps image unchanged.
+    pub contrast: f32,
+
+    /// Colors distance from their gray multiplier, `0.0` gives grayscale, `1.0` keeps image unchanged.
+    pub saturation: f32,
+
+    /// Gamma correction, values above `1.0` brighten midtones, `1.0` keeps image unchanged.
+    pub gamma: f32,
+
+    /// Unsharp mask blur radius (Gaussian sigma) in pixels.
+    pub sharpen_radius: f32,
+
+    /// Unsharp mask strength, `0.0` disables sharpening, `0.5..=1.5` is a mild to strong range.
+    pub sharpen_amount: f32,
+
+    /// Keep luma of every pixel but share chroma within 2x2 blocks, see [`chroma_subsample`].
+    pub chroma_subsample: bool,
+}
+
+impl Default for Preprocessing {
+    fn default() -> Self {
+        Self {
+            brightness: 0.0,
+            contrast: 1.0,
+            saturation: 1.0,
+            gamma: 1.0,
+            sharpen_radius: 1.0,
+            sharpen_amount: 0.0,
+            chroma_subsample: false,
+        }
+    }
+}
+
+impl Preprocessing {
+    /// Checks if preprocessing would leave image unchanged.
+    pub fn is_identity(&self) -> bool {
+        self.is_tonal_identity() && !self.is_sharpening() && !self.chroma_subsample
+    }
+
+    fn is_tonal_identity(&self) -> bool {
+        self.brightness == 0.0 && self.contrast == 1.0 && self.saturation == 1.0 && self.gamma == 1.0
+    }
+
+    fn is_sharpening(&self) -> bool {
+        self.sharpen_amount != 0.0 && self.sharpen_radius > 0.0
+    }
+
+    /// Applies adjustments to the image.
+    ///
+    /// # Parameters
+    /// - `source_image`: The input `RgbImage` to be adjusted.
+    ///
+    /// # Returns
+    /// - Adjusted `RgbImage` of the same dimensions.
+    pub fn apply(&self, source_image: RgbImage) -> RgbImage {
+        let adjusted_image = if self.is_tonal_identity() {
+            source_image
+        } else {
+            self.apply_tonal_adjustments(source_image)
+        };
+
+        let sharpened_image = if self.is_sharpening() {
+            unsharp_mask(&adjusted_image, self.sharpen_radius, self.sharpen_amount)
+        } else {
+            adjusted_image
+        };
+
+        if self.chroma_subsample {
+            chroma_subsample(&sharpened_image)
+        } else {
+            sharpened_image
+        }
+    }
+
+    fn apply_tonal_adjustments(&self, mut source_image: RgbImage) -> RgbImage {
+        let inverse_gamma = 1.0 / self.gamma.max(f32::EPSILON);
+
+        source_image.pixels_mut()
+            .for_each(|pixel| {
+                let mut channels = pixel.0.map(|channel| channel as f32 / 255.0);
+
+                channels.iter_mut().for_each(|channel| {
+                    *channel = (*channel + self.brightness - 0.5) * self.contrast + 0.5;
+                });
+
+                let luma = channels.iter()
+                    .zip(LUMA_WEIGHTS)
+                    .map(|(channel, weight)| channel * weight)
+                    .sum::<f32>();
+                channels.iter_mut().for_each(|channel| {
+                    *channel = luma + (*channel - luma) * self.saturation;
+                });
+
+                pixel.0 = channels.map(|channel| {
+                    (channel.clamp(0.0, 1.0).powf(inverse_gamma) * 255.0).round() as u8
+                });
+            });
+
+        source_image
+    }
+}
+
+/// Sharpens image by adding difference between image and its blurred copy.
+///
+/// # Parameters
+/// - `source_image`: The input `RgbImage` to be sharpened.
+/// - `radius`: Blur radius (Gaussian sigma) in pixels, bigger values sharpen coarser details.
+/// - `amount`: Strength of sharpening, `1.0` adds the whole difference.
+///
+/// # Returns
+/// - Sharpened `RgbImage` of the same dimensions.
+pub fn unsharp_mask(source_image: &RgbImage, radius: f32, amount: f32) -> RgbImage {
+    let blurred_image = image::imageops::blur(source_image, radius);
+
+    RgbImage::from_fn(source_image.width(), source_image.height(), |x, y| {
+        let source_px = source_image.get_pixel(x, y);
+        let blurred_px = blurred_image.get_pixel(x, y);
+
+        image::Rgb(std::array::from_fn(|channel| {
+            let source_value = source_px.0[channel] as f32;
+            let blurred_value = blurred_px.0[channel] as f32;
+            (source_value + amount * (source_value - blurred_value)).round().clamp(0.0, 255.0) as u8
+        }))
+    })
+}
+
+/// Keeps luma of every pixel at full resolution, while chroma is averaged over 2x2 blocks.
+///
+/// Matches lower chroma resolution perceived on LED matrices, like 4:2:0 subsampling of YCbCr.
+/// Luma uses Rec. 709 weights, both chroma components are linear in differences of channels
+/// from luma, so averaging the differences averages Cb and Cr exactly. Partial blocks at right
+/// and bottom edges are averaged over pixels they cover.
+///
+/// # Returns
+/// - Recombined `RgbImage` of the same dimensions.
+pub fn chroma_subsample(source_image: &RgbImage) -> RgbImage {
+    const BLOCK_SIZE: u32 = 2;
+    let luma = |px: &image::Rgb<u8>| px.0.iter()
+        .zip(LUMA_WEIGHTS)
+        .map(|(&channel, weight)| channel as f32 * weight)
+        .sum::<f32>();
+
+    let (width, height) = source_image.dimensions();
+    let mut target_image = source_image.clone();
+    for block_y in (0..height).step_by(BLOCK_SIZE as usize) {
+        for block_x in (0..width).step_by(BLOCK_SIZE as usize) {
+            let block_pixels = (block_y..(block_y + BLOCK_SIZE).min(height))
+                .flat_map(|y| (block_x..(block_x + BLOCK_SIZE).min(width)).map(move |x| (x, y)))
+                .collect::<Vec<_>>();
+
+            let mut chroma = [0.0f32; 3];
+            for &(x, y) in &block_pixels {
+                let px = source_image.get_pixel(x, y);
+                let px_luma = luma(px);
+                chroma.iter_mut().zip(px.0).for_each(|(sum, channel)| *sum += channel as f32 - px_luma);
+            }
+            let chroma = chroma.map(|sum| sum / block_pixels.len() as f32);
+
+            for (x, y) in block_pixels {
+                let px_luma = luma(source_image.get_pixel(x, y));
+                target_image.get_pixel_mut(x, y).0 = std::array::from_fn(|channel| {
+                    (px_luma + chroma[channel]).round().clamp(0.0, 255.0) as u8
+                });
+            }
+        }
+    }
+    target_image
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identity_preprocessing() {
+        let source_image = crate::image::generate_test_gradient_image(16, 4, image::Rgb([10, 50, 200]), image::Rgb([250, 120, 0]));
+        assert_eq!(Preprocessing::default().apply(source_image.clone()), source_image);
+    }
+
+    #[test]
+    fn test_adjustments() {
+        let source_image = RgbImage::from_pixel(2, 2, image::Rgb([200, 100, 50]));
+
+        let contrast = Preprocessing { contrast: 1.5, ..Default::default() };
+        let contrasted_px = *contrast.apply(source_image.clone()).get_pixel(0, 0);
+        assert_eq!(contrasted_px.0, [236, 86, 11]);
+
+        let grayscale = Preprocessing { saturation: 0.0, ..Default::default() };
+        let gray_px = *grayscale.apply(source_image.clone()).get_pixel(0, 0);
+        assert!(gray_px.0[0] == gray_px.0[1] && gray_px.0[1] == gray_px.0[2]);
+
+        let gamma = Preprocessing { gamma: 2.2, ..Default::default() };
+        let brightened_px = *gamma.apply(source_image).get_pixel(0, 0);
+        assert!(brightened_px.0.iter().zip([200, 100, 50]).all(|(&adjusted, original)| adjusted > original));
+    }
+
+    #[test]
+    fn test_sharpening_increases_edge_contrast() {
+        let source_image = RgbImage::from_fn(16, 4, |x, _| {
+            if x < 8 { image::Rgb([100, 100, 100]) } else { image::Rgb([150, 150, 150]) }
+        });
+
+        let sharpening = Preprocessing { sharpen_amount: 1.0, ..Default::default() };
+        assert!(!sharpening.is_identity());
+        let sharpened_image = sharpening.apply(source_image.clone());
+
+        // Flat areas stay the same, pixels next to the edge move away from each other
+        assert_eq!(sharpened_image.get_pixel(0, 0), source_image.get_pixel(0, 0));
+        assert!(sharpened_image.get_pixel(7, 0).0[0] < 100);
+        assert!(sharpened_image.get_pixel(8, 0).0[0] > 150);
+    }
+
+    #[test]
+    fn test_chroma_subsample() {
+        // Red and green checkerboard with different lightness, blue from the second block on
+        let source_image = RgbImage::from_fn(5, 3, |x, y| {
+            match (x, (x + y) % 2) {
+                (2.., _) => image::Rgb([40, 40, 200]),
+                (_, 0) => image::Rgb([200, 40, 40]),
+                _ => image::Rgb([40, 160, 40]),
+            }
+        });
+        let subsampling = Preprocessing { chroma_subsample: true, ..Default::default() };
+        assert!(!subsampling.is_identity());
+        let subsampled_image = subsampling.apply(source_image.clone());
+        assert_eq!(subsampled_image.dimensions(), source_image.dimensions());
+
+        // Luma of every pixel is kept, while both pixels of a block get the same chroma
+        let luma = |px: &image::Rgb<u8>| px.0.iter().zip(LUMA_WEIGHTS).map(|(&channel, weight)| channel as f32 * weight).sum::<f32>();
+        for (source_px, subsampled_px) in source_image.pixels().zip(subsampled_image.pixels()) {
+            assert!((luma(source_px) - luma(subsampled_px)).abs() < 1.0);
+        }
+        let chroma = |x, y| {
+            let px = subsampled_image.get_pixel(x, y);
+            px.0.map(|channel| channel as f32 - luma(px))
+        };
+        assert!(chroma(0, 0).iter().zip(chroma(1, 0)).all(|(first, second)| (first - second).abs() < 1.5));
+        assert!(chroma(1, 0).iter().zip(chroma(2, 0)).any(|(first, second)| (first - second).abs() > 10.0));
+
+        // Flat color stays unchanged
+        let flat_image = RgbImage::from_pixel(3, 3, image::Rgb([30, 120, 210]));
+        assert_eq!(chroma_subsample(&flat_image), flat_image);
+    }
+}
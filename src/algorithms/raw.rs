@@ -0,0 +1,168 @@
+//! Quantization of raw interleaved RGB buffers, available without `std` feature.
+//!
+//! Buffers hold `width * height` pixels, 3 bytes each, row after row. Palettes are slices
+//! of `[r, g, b]` colors. Colors are compared by squared RGB distance and error is diffused
+//! with plain floating point arithmetic, so no floating point math library is needed.
+//!
+//! # Example
+//! ```
+//! use ditherum::algorithms::raw;
+//!
+//! let palette = [[0, 0, 0], [255, 255, 255]];
+//! let mut buffer = [128u8; 4 * 2 * 3];
+//! raw::dithering_floyd_steinberg_rgb(&mut buffer, 4, 2, &palette);
+//!
+//! assert!(buffer.chunks_exact(3).all(|px| palette.iter().any(|color| color == px)));
+//! ```
+
+use alloc::vec::Vec;
+
+use crate::algorithms::kernel::{DEFAULT_ERROR_OVERSHOOT, ERROR_WEIGHTS_2X2};
+
+/// Returns squared euclidean distance of RGB channels.
+pub fn distance_squared(a: &[u8; 3], b: &[u8; 3]) -> u32 {
+    a.iter()
+        .zip(b)
+        .map(|(&a, &b)| {
+            let diff = a as i32 - b as i32;
+            (diff * diff) as u32
+        })
+        .sum()
+}
+
+/// Returns index of palette color closest to given color, the first one if there is a tie.
+///
+/// # Panics
+/// Panics if palette is empty.
+pub fn find_closest_color_idx(color: &[u8; 3], palette: &[[u8; 3]]) -> usize {
+    palette.iter()
+        .enumerate()
+        .min_by_key(|(_, palette_color)| distance_squared(color, palette_color))
+        .map(|(idx, _)| idx)
+        .expect("Palette is empty.")
+}
+
+/// Replaces every pixel with the closest palette color, no dithering.
+///
+/// # Panics
+/// Panics if palette is empty or buffer length is not a multiple of 3.
+pub fn thresholding_rgb(buffer: &mut [u8], palette: &[[u8; 3]]) {
+    assert!(!palette.is_empty(), "Palette is empty.");
+    assert!(buffer.len().is_multiple_of(3), "Buffer length {} is not a multiple of 3.", buffer.len());
+
+    for px in buffer.chunks_exact_mut(3) {
+        let color = [px[0], px[1], px[2]];
+        px.copy_from_slice(&palette[find_closest_color_idx(&color, palette)]);
+    }
+}
+
+/// Floyd-Steinberg error diffusion in RGB space.
+///
+/// Same kernel, weights and clamping of accumulated error as `dithering::dithering_floyd_steinberg_rgb`
+/// of `std` builds, so both give the same result. Only two rows of accumulated error are kept, so memory
+/// use is proportional to width. Buffers narrower or lower than 2 pixels are thresholded instead.
+///
+/// # Panics
+/// Panics if palette is empty or buffer length does not match dimensions.
+pub fn dithering_floyd_steinberg_rgb(buffer: &mut [u8], width: usize, height: usize, palette: &[[u8; 3]]) {
+    assert!(!palette.is_empty(), "Palette is empty.");
+    assert_eq!(buffer.len(), width * height * 3, "Buffer length does not match {width}x{height} RGB image.");
+    if width < 2 || height < 2 {
+        thresholding_rgb(buffer, palette);
+        return;
+    }
+
+    // Channels in range 0-1 like sRGB values of std path, so rounding matches too
+    let unit_palette = palette.iter()
+        .map(|color| color.map(unit_channel))
+        .collect::<Vec<_>>();
+    let unit_row = |row: &[u8]| row.chunks_exact(3)
+        .map(|px| [unit_channel(px[0]), unit_channel(px[1]), unit_channel(px[2])])
+        .collect::<Vec<_>>();
+    let (weight_tr, weight_bl, weight_br) = ERROR_WEIGHTS_2X2;
+
+    let mut current_row = unit_row(&buffer[..width * 3]);
+    for y in 0..height {
+        let mut next_row = match y + 1 < height {
+            true => unit_row(&buffer[(y + 1) * width * 3..(y + 2) * width * 3]),
+            false => Vec::new(),
+        };
+
+        for x in 0..width {
+            let closest_idx = find_closest_unit_color_idx(&current_row[x], &unit_palette);
+            let closest_color = unit_palette[closest_idx];
+            let quant_error: [f32; 3] = core::array::from_fn(|channel| current_row[x][channel] - closest_color[channel]);
+
+            if let Some(tr) = current_row.get_mut(x + 1) {
+                spread_error(tr, &quant_error, weight_tr);
+            }
+            if let Some(bl) = next_row.get_mut(x) {
+                spread_error(bl, &quant_error, weight_bl);
+            }
+            if let Some(br) = next_row.get_mut(x + 1) {
+                spread_error(br, &quant_error, weight_br);
+            }
+            buffer[(y * width + x) * 3..][..3].copy_from_slice(&palette[closest_idx]);
+        }
+
+        current_row = next_row;
+    }
+}
+
+fn unit_channel(value: u8) -> f32 {
+    value as f32 / 255.0
+}
+
+/// Returns index of the closest color in range 0-1 by squared euclidean distance, the first one if there is a tie.
+fn find_closest_unit_color_idx(color: &[f32; 3], palette: &[[f32; 3]]) -> usize {
+    let distance_squared = |other: &[f32; 3]| {
+        let [red, green, blue]: [f32; 3] = core::array::from_fn(|channel| color[channel] - other[channel]);
+        red * red + green * green + blue * blue
+    };
+    palette.iter()
+        .enumerate()
+        .map(|(idx, palette_color)| (idx, distance_squared(palette_color)))
+        .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(core::cmp::Ordering::Equal))
+        .map(|(idx, _)| idx)
+        .expect("Palette is empty.")
+}
+
+/// Adds weighted error to pixel, clamping channels [`DEFAULT_ERROR_OVERSHOOT`] beyond valid range.
+fn spread_error(pixel: &mut [f32; 3], quant_error: &[f32; 3], weight: f32) {
+    for (channel, error) in pixel.iter_mut().zip(quant_error) {
+        *channel = (*channel + error * weight).clamp(-DEFAULT_ERROR_OVERSHOOT, 1.0 + DEFAULT_ERROR_OVERSHOOT);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_closest_color_idx() {
+        let palette = [[0, 0, 0], [255, 0, 0], [255, 255, 255]];
+        assert_eq!(find_closest_color_idx(&[200, 30, 20], &palette), 1);
+        assert_eq!(find_closest_color_idx(&[10, 10, 10], &palette), 0);
+
+        let mut buffer = [200, 30, 20, 240, 240, 240];
+        thresholding_rgb(&mut buffer, &palette);
+        assert_eq!(buffer, [255, 0, 0, 255, 255, 255]);
+    }
+
+    #[test]
+    fn test_floyd_steinberg_mixes_palette_colors() {
+        let (width, height) = (32, 16);
+        let palette = [[0, 0, 0], [255, 255, 255]];
+        let mut buffer = vec![160u8; width * height * 3];
+        dithering_floyd_steinberg_rgb(&mut buffer, width, height, &palette);
+
+        assert!(buffer.chunks_exact(3).all(|px| palette.iter().any(|color| color == px)));
+        let white_count = buffer.chunks_exact(3).filter(|px| px[0] == 255).count();
+        assert!(white_count > 0 && white_count < width * height, "white_count={white_count}");
+
+        // Too narrow for the kernel, thresholded instead
+        let mut column = [100, 100, 100, 200, 200, 200];
+        dithering_floyd_steinberg_rgb(&mut column, 1, 2, &palette);
+        assert_eq!(column, [0, 0, 0, 255, 255, 255]);
+    }
+}
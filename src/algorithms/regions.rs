@@ -0,0 +1,103 @@
+use std::collections::HashSet;
+
+use image::{GrayImage, RgbImage};
+
+/// Side of square block classified as a whole.
+const REGION_BLOCK_SIZE: u32 = 8;
+
+/// Lightness standard deviation (Lab L) below which block is flat regardless of its colors.
+const FLAT_LIGHTNESS_DEVIATION: f32 = 1.0;
+
+/// Blur of detected mask, so dithering fades in over a few pixels instead of block edges.
+const MASK_SOFTENING_SIGMA: f32 = 2.0;
+
+/// Default sensitivity of photographic regions detection.
+pub const DEFAULT_HYBRID_SENSITIVITY: f32 = 0.5;
+
+/// Detects photographic regions of image, like photos embedded in a screenshot.
+///
+/// # Parameters
+/// - `source_image`: The input `RgbImage`.
+/// - `sensitivity`: In range `0.0..=1.0`, higher values treat more regions as photographic.
+///
+/// # Returns
+/// - A `GrayImage` mask, white in photographic regions and black in flat and line art ones,
+///   usable with [`crate::image::ImageProcessor::with_mask`].
+///
+/// # Algorithm Details
+/// Image is split into 8x8 blocks. Flat areas have low lightness variance, text and line
+/// art have sharp edges but only a few distinct colors, like ink, background and some
+/// anti-aliasing shades. Block is photographic if its lightness varies and the share
+/// of distinct colors among its pixels reaches `(1 - sensitivity) / 2`. Mask is blurred
+/// slightly, so dithering fades in at region borders.
+///
+/// # Example
+/// ```
+/// use ditherum::algorithms::regions::photographic_mask;
+///
+/// let flat_image = image::RgbImage::from_pixel(16, 16, image::Rgb([40, 90, 200]));
+/// let mask = photographic_mask(&flat_image, 0.5);
+///
+/// assert!(mask.pixels().all(|px| px.0 == [0]));
+/// ```
+pub fn photographic_mask(source_image: &RgbImage, sensitivity: f32) -> GrayImage {
+    let (width, height) = source_image.dimensions();
+    let min_distinct_share = (1.0 - sensitivity.clamp(0.0, 1.0)) / 2.0;
+    let mut mask = GrayImage::new(width, height);
+
+    for block_y in (0..height).step_by(REGION_BLOCK_SIZE as usize) {
+        for block_x in (0..width).step_by(REGION_BLOCK_SIZE as usize) {
+            let pixels = (block_y..(block_y + REGION_BLOCK_SIZE).min(height))
+                .flat_map(|y| (block_x..(block_x + REGION_BLOCK_SIZE).min(width)).map(move |x| (x, y)))
+                .collect::<Vec<_>>();
+
+            let distinct_colors = pixels.iter()
+                .map(|&(x, y)| source_image.get_pixel(x, y).0)
+                .collect::<HashSet<_>>();
+            let lightness = pixels.iter()
+                .map(|&(x, y)| crate::color::ops::rgbu8_to_lab(*source_image.get_pixel(x, y)).l)
+                .collect::<Vec<_>>();
+            let mean_lightness = lightness.iter().sum::<f32>() / lightness.len() as f32;
+            let lightness_deviation = (lightness.iter()
+                .map(|value| (value - mean_lightness).powi(2))
+                .sum::<f32>() / lightness.len() as f32)
+                .sqrt();
+
+            let distinct_share = distinct_colors.len() as f32 / pixels.len() as f32;
+            let is_photographic = lightness_deviation >= FLAT_LIGHTNESS_DEVIATION && distinct_share >= min_distinct_share;
+            if is_photographic {
+                pixels.iter().for_each(|&(x, y)| mask.put_pixel(x, y, image::Luma([u8::MAX])));
+            }
+        }
+    }
+
+    image::imageops::blur(&mask, MASK_SOFTENING_SIGMA)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_photo_next_to_text() {
+        // Left half is noisy gradient like photo, right half is page with text lines
+        let source_image = RgbImage::from_fn(64, 32, |x, y| {
+            if x < 32 {
+                let noise = ((x * 37 + y * 91) % 23) as u8;
+                image::Rgb([60 + x as u8 * 4 + noise, 80 + y as u8 * 3, 120 + noise * 2])
+            } else if y % 6 < 2 && x % 5 != 0 {
+                image::Rgb([20, 20, 20])
+            } else {
+                image::Rgb([245, 245, 240])
+            }
+        });
+        let mask = photographic_mask(&source_image, DEFAULT_HYBRID_SENSITIVITY);
+
+        assert!((0..24).all(|x| mask.get_pixel(x, 16).0[0] > 200));
+        assert!((40..64).all(|x| mask.get_pixel(x, 16).0[0] < 50));
+
+        // Everything but flat areas is photographic with full sensitivity
+        let mask = photographic_mask(&source_image, 1.0);
+        assert!((40..64).all(|x| mask.get_pixel(x, 2).0[0] > 200));
+    }
+}
@@ -0,0 +1,204 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{color::ColorRGB, palette::PaletteRGB};
+
+/// Gamma of sRGB displays, [`DisplayResponse::from_gamma`] with this value keeps colors unchanged.
+pub const SRGB_DISPLAY_GAMMA: f32 = 2.2;
+
+/// Number of entries of each channel of display LUT, one per driven value.
+pub const LUT_SIZE: usize = 256;
+
+/// Black level of [`DisplayPreset::Epaper`], ink is never fully black.
+const EPAPER_BLACK_LEVEL: f32 = 0x28 as f32;
+
+/// White level of [`DisplayPreset::Epaper`], paper looks light gray next to sRGB white.
+const EPAPER_WHITE_LEVEL: f32 = 0xc8 as f32;
+
+/// Error returned when parsing display response fails.
+#[derive(Debug, thiserror::Error)]
+#[error("Invalid display response '{0}', expected preset 'led', 'epaper', 'mac' or display gamma like '2.4'")]
+pub struct DisplayResponseParseError(pub String);
+
+/// Error returned when parsing display LUT fails.
+#[derive(Debug, thiserror::Error)]
+#[error("Invalid display LUT, {0}")]
+pub struct DisplayLutParseError(pub String);
+
+/// Response curve of common panels, see [`DisplayResponse`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, clap::ValueEnum, Serialize, Deserialize)]
+pub enum DisplayPreset {
+    /// LED matrices driven by linear PWM, midtones look brighter than on sRGB displays
+    #[value(name = "led")]
+    #[serde(rename = "led")]
+    Led,
+
+    /// E-paper panels, gray ink and paper compress the contrast
+    #[value(name = "epaper")]
+    #[serde(rename = "epaper")]
+    Epaper,
+
+    /// Legacy displays of gamma 1.8, midtones look brighter
+    #[value(name = "mac")]
+    #[serde(rename = "mac")]
+    Mac,
+}
+
+/// Colors a target display actually shows for driven sRGB values, per channel.
+///
+/// E-paper and LED panels don't display sRGB, so palette colors are matched by their perceived
+/// colors instead, see [`crate::DitherOptions::with_display_response`]. Parsed from preset name
+/// like `epaper` or display gamma like `2.4`, [`DisplayResponse::parse_lut`] reads measured curves.
+///
+/// # Example
+/// ```
+/// use ditherum::{algorithms::response::DisplayResponse, color::ColorRGB};
+///
+/// let led = "led".parse::<DisplayResponse>().unwrap();
+/// assert!(led.apply(ColorRGB([128, 128, 128])).red() > 128);
+/// assert_eq!(led.apply(ColorRGB([255, 0, 255])), ColorRGB([255, 0, 255]));
+///
+/// let dark = "2.8".parse::<DisplayResponse>().unwrap();
+/// assert!(dark.apply(ColorRGB([128, 128, 128])).red() < 128);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DisplayResponse {
+    /// Perceived value of every driven value, per channel.
+    lut: [[u8; LUT_SIZE]; 3],
+}
+
+impl DisplayResponse {
+    /// Creates response of display with given gamma, [`SRGB_DISPLAY_GAMMA`] keeps colors unchanged.
+    pub fn from_gamma(gamma: f32) -> Self {
+        let exponent = gamma.max(f32::EPSILON) / SRGB_DISPLAY_GAMMA;
+        Self::from_curve(|value| value.powf(exponent))
+    }
+
+    /// Creates response from perceived value of every driven value, per channel.
+    pub fn from_lut(lut: [[u8; LUT_SIZE]; 3]) -> Self {
+        Self { lut }
+    }
+
+    /// Creates response of all channels from curve mapping driven values in range `0.0..=1.0` to perceived ones.
+    fn from_curve(curve: impl Fn(f32) -> f32) -> Self {
+        let mut channel = [0; LUT_SIZE];
+        for (driven, perceived) in channel.iter_mut().enumerate() {
+            *perceived = (curve(driven as f32 / 255.0).clamp(0.0, 1.0) * 255.0).round() as u8;
+        }
+        Self { lut: [channel; 3] }
+    }
+
+    /// Parses LUT of 256 lines, line `N` holds perceived value of driven value `N` for all channels
+    /// like `12` or per channel like `12, 10, 14`. Empty lines and `#` comments are skipped.
+    ///
+    /// # Example
+    /// ```
+    /// use ditherum::{algorithms::response::DisplayResponse, color::ColorRGB};
+    ///
+    /// let lut = (0..256).map(|value| format!("{}, {value}, {value}", value / 2)).collect::<Vec<_>>().join("\n");
+    /// let response = DisplayResponse::parse_lut(&format!("# Dim red channel\n{lut}")).unwrap();
+    ///
+    /// assert_eq!(response.apply(ColorRGB([200, 200, 200])), ColorRGB([100, 200, 200]));
+    /// assert!(DisplayResponse::parse_lut("0\n255").is_err());
+    /// ```
+    ///
+    /// # Errors
+    /// Returns [`DisplayLutParseError`] if a line is not 1 or 3 values in range 0-255 or there are not 256 lines.
+    pub fn parse_lut(text: &str) -> Result<Self, DisplayLutParseError> {
+        let mut lut = [[0; LUT_SIZE]; 3];
+        let mut entries = 0;
+        for (line_idx, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let invalid = || DisplayLutParseError(format!("line {} '{line}', expected 1 or 3 values in range 0-255", line_idx + 1));
+            let values = line.split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|value| !value.is_empty())
+                .map(|value| value.parse::<u8>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| invalid())?;
+            let values = match values[..] {
+                [value] => [value; 3],
+                [red, green, blue] => [red, green, blue],
+                _ => return Err(invalid()),
+            };
+            if entries == LUT_SIZE {
+                return Err(DisplayLutParseError(format!("more than {LUT_SIZE} entries")));
+            }
+            for (channel, value) in lut.iter_mut().zip(values) {
+                channel[entries] = value;
+            }
+            entries += 1;
+        }
+
+        if entries != LUT_SIZE {
+            return Err(DisplayLutParseError(format!("{entries} entries, expected {LUT_SIZE}")));
+        }
+        Ok(Self { lut })
+    }
+
+    /// Returns color perceived when display is driven with given color.
+    pub fn apply(&self, color: ColorRGB) -> ColorRGB {
+        ColorRGB(std::array::from_fn(|channel| self.lut[channel][color.0[channel] as usize]))
+    }
+
+    /// Returns palette of colors perceived for palette colors, `reserved` color is left unchanged.
+    ///
+    /// Colors perceived the same are kept once.
+    pub fn perceived_palette(&self, palette: &PaletteRGB, reserved: Option<ColorRGB>) -> PaletteRGB {
+        PaletteRGB::from(palette.iter()
+            .map(|&color| if Some(color) == reserved { color } else { self.apply(color) })
+            .collect::<Vec<_>>()
+        )
+    }
+}
+
+impl From<DisplayPreset> for DisplayResponse {
+    fn from(value: DisplayPreset) -> Self {
+        match value {
+            DisplayPreset::Led => Self::from_curve(|value| palette::Srgb::from_linear(palette::LinSrgb::new(value, value, value)).red),
+            DisplayPreset::Epaper => Self::from_curve(|value| {
+                (EPAPER_BLACK_LEVEL + value * (EPAPER_WHITE_LEVEL - EPAPER_BLACK_LEVEL)) / 255.0
+            }),
+            DisplayPreset::Mac => Self::from_gamma(1.8),
+        }
+    }
+}
+
+impl FromStr for DisplayResponse {
+    type Err = DisplayResponseParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.trim();
+        if let Ok(preset) = <DisplayPreset as clap::ValueEnum>::from_str(trimmed, true) {
+            return Ok(Self::from(preset));
+        }
+        match trimmed.parse::<f32>() {
+            Ok(gamma) if gamma.is_finite() && gamma > 0.0 => Ok(Self::from_gamma(gamma)),
+            _ => Err(DisplayResponseParseError(s.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_response_presets() {
+        let identity = DisplayResponse::from_gamma(SRGB_DISPLAY_GAMMA);
+        assert!((0..=255).all(|value| identity.apply(ColorRGB([value, value, value])) == ColorRGB([value, value, value])));
+
+        let epaper = DisplayResponse::from(DisplayPreset::Epaper);
+        assert_eq!(epaper.apply(ColorRGB([0, 255, 0])), ColorRGB([0x28, 0xc8, 0x28]));
+
+        let palette = PaletteRGB::from(vec![ColorRGB([0, 0, 0]), ColorRGB([255, 0, 255]), ColorRGB([255, 255, 255])]);
+        let perceived = epaper.perceived_palette(&palette, Some(ColorRGB([255, 0, 255])));
+        assert_eq!(perceived.to_vec(), vec![ColorRGB([0x28, 0x28, 0x28]), ColorRGB([255, 0, 255]), ColorRGB([0xc8, 0xc8, 0xc8])]);
+
+        assert!("oled".parse::<DisplayResponse>().is_err());
+        assert!("-1".parse::<DisplayResponse>().is_err());
+    }
+}
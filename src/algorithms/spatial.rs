@@ -0,0 +1,344 @@
+use std::collections::HashMap;
+
+use image::RgbImage;
+
+use crate::{color::ColorRGB, palette::PaletteRGB};
+
+/// Weights of 3x3 filter approximating how the eye blends neighbouring pixels, they sum to 1.
+const FILTER_WEIGHTS_3X3: [[f32; 3]; 3] = [
+    [1.0 / 16.0, 2.0 / 16.0, 1.0 / 16.0],
+    [2.0 / 16.0, 4.0 / 16.0, 2.0 / 16.0],
+    [1.0 / 16.0, 2.0 / 16.0, 1.0 / 16.0],
+];
+
+/// Upper limit of passes over all pixels, optimization usually settles earlier.
+const MAX_ASSIGNMENT_SWEEPS: usize = 12;
+
+/// Rounds of alternating palette refit and assignment sweeps in [`refine_palette`].
+const REFINEMENT_ROUNDS: usize = 4;
+
+/// Keeps rarely used palette colors close to their previous values during refit.
+const PALETTE_REGULARIZATION: f64 = 1e-3;
+
+type Color = [f32; 3];
+
+/// Applies spatial color quantization using a given color palette.
+///
+/// # Parameters
+/// - `source_image`: The input `RgbImage` to be dithered.
+/// - `palette`: A `PaletteRGB` containing the target colors for dithering.
+///
+/// # Returns
+/// - A dithered `RgbImage` containing only palette colors.
+///
+/// # Algorithm Details
+/// Variant of Puzicha et al. spatial color quantization: instead of quantizing pixels
+/// one by one, the whole assignment minimizes difference between blurred result and
+/// blurred source, where 3x3 blur approximates how the eye mixes neighbouring pixels.
+/// Starting from Floyd-Steinberg result, each pixel repeatedly gets the palette color
+/// which lowers the difference the most, until no pixel changes. Slower than error
+/// diffusion, but gives visibly smoother results for palettes of 8-16 colors.
+/// See [`refine_palette`] for optimizing palette colors together with the assignment.
+pub fn spatial_quantization(source_image: RgbImage, palette: PaletteRGB) -> RgbImage {
+    let mut target_image = RgbImage::new(source_image.width(), source_image.height());
+    spatial_quantization_into(&source_image, &palette, &mut target_image);
+    target_image
+}
+
+/// Same as [`spatial_quantization`], but writes result to `target_image`, reallocating it
+/// only if its dimensions differ from the source ones.
+pub fn spatial_quantization_into(source_image: &RgbImage, palette: &PaletteRGB, target_image: &mut RgbImage) {
+    crate::image::manip::match_dimensions(target_image, source_image);
+    let mut state = SpatialState::new(source_image, palette);
+    state.optimize_assignment();
+
+    target_image.pixels_mut()
+        .zip(&state.assignment)
+        .for_each(|(pixel, &color_idx)| *pixel = palette[color_idx].to_rgbu8());
+}
+
+/// Optimizes palette colors together with spatial quantization assignment.
+///
+/// Alternates assignment optimization of [`spatial_quantization`] with least squares refit
+/// of palette colors, so colors move where dithered mixtures approximate the image best,
+/// rather than to cluster centers as in k-means. Colors count never grows, it can shrink
+/// if two colors converge to the same value.
+///
+/// # Example
+/// ```
+/// use ditherum::{algorithms::spatial::refine_palette, palette::PaletteRGB};
+///
+/// let img = ditherum::image::generate_test_gradient_image(32, 8, image::Rgb([0, 0, 0]), image::Rgb([255, 128, 0]));
+/// let palette = PaletteRGB::from_rgbu8_image(&img).try_reduce(4).unwrap();
+/// let refined_palette = refine_palette(&img, &palette);
+/// assert!(refined_palette.len() <= 4);
+/// ```
+pub fn refine_palette(source_image: &RgbImage, palette: &PaletteRGB) -> PaletteRGB {
+    let mut state = SpatialState::new(source_image, palette);
+    for _ in 0..REFINEMENT_ROUNDS {
+        state.optimize_assignment();
+        if !state.refit_palette() {
+            break;
+        }
+    }
+
+    state.palette.iter()
+        .map(|color| ColorRGB(color.map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8)))
+        .collect::<Vec<_>>()
+        .into()
+}
+
+fn to_color(pixel: &image::Rgb<u8>) -> Color {
+    pixel.0.map(|channel| channel as f32 / 255.0)
+}
+
+/// Indices and filter weights of pixels within 3x3 neighbourhood, clipped to image borders.
+fn neighbours(width: usize, height: usize, idx: usize) -> impl Iterator<Item = (usize, f32)> {
+    let (x, y) = (idx % width, idx / width);
+    (0..3).flat_map(move |ky| (0..3).map(move |kx| (kx, ky)))
+        .filter_map(move |(kx, ky)| {
+            let nx = (x + kx).checked_sub(1).filter(|&nx| nx < width)?;
+            let ny = (y + ky).checked_sub(1).filter(|&ny| ny < height)?;
+            Some((ny * width + nx, FILTER_WEIGHTS_3X3[ky][kx]))
+        })
+}
+
+struct SpatialState {
+    width: usize,
+    height: usize,
+    palette: Vec<Color>,
+    assignment: Vec<usize>,
+
+    /// Blurred source image.
+    blurred_source: Vec<Color>,
+
+    /// Blurred result minus blurred source.
+    residual: Vec<Color>,
+}
+
+impl SpatialState {
+    fn new(source_image: &RgbImage, palette: &PaletteRGB) -> Self {
+        assert!(!palette.is_empty(), "Palette is empty.");
+        let (width, height) = (source_image.width() as usize, source_image.height() as usize);
+
+        // Error diffusion kernel needs at least 2x2 image
+        let initial_image = if width > 1 && height > 1 {
+            crate::algorithms::dithering::dithering_floyd_steinberg_rgb(source_image.clone(), palette.clone())
+        } else {
+            crate::algorithms::thresholding::thresholding_rgb(source_image.clone(), palette.clone())
+        };
+        let palette_indices = palette.iter()
+            .enumerate()
+            .rev()
+            .map(|(idx, color)| (color.to_rgbu8(), idx))
+            .collect::<HashMap<_, _>>();
+        let assignment = initial_image.pixels()
+            .map(|pixel| palette_indices[pixel])
+            .collect();
+
+        let source = source_image.pixels().map(to_color).collect::<Vec<_>>();
+        let mut state = Self {
+            width,
+            height,
+            palette: palette.iter().map(|color| to_color(&color.to_rgbu8())).collect(),
+            assignment,
+            blurred_source: Vec::new(),
+            residual: Vec::new(),
+        };
+        state.blurred_source = state.blur(|idx| source[idx]);
+        state.update_residual();
+        state
+    }
+
+    fn blur<F: Fn(usize) -> Color>(&self, color_at: F) -> Vec<Color> {
+        (0..self.width * self.height)
+            .map(|idx| {
+                neighbours(self.width, self.height, idx).fold([0.0; 3], |mut sum, (neighbour_idx, weight)| {
+                    let color = color_at(neighbour_idx);
+                    (0..3).for_each(|channel| sum[channel] += weight * color[channel]);
+                    sum
+                })
+            })
+            .collect()
+    }
+
+    fn update_residual(&mut self) {
+        let blurred_result = self.blur(|idx| self.palette[self.assignment[idx]]);
+        self.residual = blurred_result.iter()
+            .zip(&self.blurred_source)
+            .map(|(result, source)| std::array::from_fn(|channel| result[channel] - source[channel]))
+            .collect();
+    }
+
+    /// Sweeps over pixels until no assignment changes.
+    fn optimize_assignment(&mut self) {
+        for _ in 0..MAX_ASSIGNMENT_SWEEPS {
+            if self.sweep() == 0 {
+                break;
+            }
+        }
+    }
+
+    /// Reassigns every pixel to the color minimizing blurred difference, returns changes count.
+    ///
+    /// Changing pixel color by `d` changes squared difference by `2 d·g + s |d|^2`, where `g`
+    /// is residual filtered around the pixel and `s` sum of squared weights, so the best color
+    /// is the one closest to `current - g / s`.
+    fn sweep(&mut self) -> usize {
+        let mut changes_count = 0;
+
+        for idx in 0..self.width * self.height {
+            let (gradient, weights_squared) = neighbours(self.width, self.height, idx)
+                .fold(([0.0f32; 3], 0.0f32), |(mut gradient, weights_squared), (neighbour_idx, weight)| {
+                    (0..3).for_each(|channel| gradient[channel] += weight * self.residual[neighbour_idx][channel]);
+                    (gradient, weights_squared + weight * weight)
+                });
+
+            let current_color = self.palette[self.assignment[idx]];
+            let desired_color: Color = std::array::from_fn(|channel| current_color[channel] - gradient[channel] / weights_squared);
+            let best_idx = self.palette.iter()
+                .map(|color| (0..3).map(|channel| (color[channel] - desired_color[channel]).powi(2)).sum::<f32>())
+                .enumerate()
+                .min_by(|(_, diff_a), (_, diff_b)| diff_a.partial_cmp(diff_b).unwrap_or(std::cmp::Ordering::Equal))
+                .map(|(color_idx, _)| color_idx)
+                .unwrap();
+
+            if best_idx != self.assignment[idx] && self.palette[best_idx] != current_color {
+                let best_color = self.palette[best_idx];
+                neighbours(self.width, self.height, idx).for_each(|(neighbour_idx, weight)| {
+                    (0..3).for_each(|channel| self.residual[neighbour_idx][channel] += weight * (best_color[channel] - current_color[channel]));
+                });
+                self.assignment[idx] = best_idx;
+                changes_count += 1;
+            }
+        }
+
+        changes_count
+    }
+
+    /// Solves least squares for palette colors with assignment fixed, returns false if it failed.
+    fn refit_palette(&mut self) -> bool {
+        let colors_count = self.palette.len();
+        let mut matrix = vec![vec![0.0f64; colors_count]; colors_count];
+        let mut rhs = vec![[0.0f64; 3]; colors_count];
+
+        for idx in 0..self.width * self.height {
+            let source = self.blurred_source[idx];
+            for (neighbour_a, weight_a) in neighbours(self.width, self.height, idx) {
+                let color_a = self.assignment[neighbour_a];
+                (0..3).for_each(|channel| rhs[color_a][channel] += (weight_a * source[channel]) as f64);
+                for (neighbour_b, weight_b) in neighbours(self.width, self.height, idx) {
+                    matrix[color_a][self.assignment[neighbour_b]] += (weight_a * weight_b) as f64;
+                }
+            }
+        }
+
+        for (color_idx, color) in self.palette.iter().enumerate() {
+            matrix[color_idx][color_idx] += PALETTE_REGULARIZATION;
+            (0..3).for_each(|channel| rhs[color_idx][channel] += PALETTE_REGULARIZATION * color[channel] as f64);
+        }
+
+        match solve_linear_system(matrix, rhs) {
+            Some(solution) => {
+                self.palette = solution.iter()
+                    .map(|color| color.map(|channel| channel.clamp(0.0, 1.0) as f32))
+                    .collect();
+                self.update_residual();
+                true
+            },
+            None => false,
+        }
+    }
+}
+
+/// Gaussian elimination with partial pivoting, solves three right hand sides at once.
+fn solve_linear_system(mut matrix: Vec<Vec<f64>>, mut rhs: Vec<[f64; 3]>) -> Option<Vec<[f64; 3]>> {
+    let size = matrix.len();
+
+    for column in 0..size {
+        let pivot_row = (column..size)
+            .max_by(|&a, &b| matrix[a][column].abs().partial_cmp(&matrix[b][column].abs()).unwrap_or(std::cmp::Ordering::Equal))?;
+        if matrix[pivot_row][column].abs() < f64::EPSILON {
+            return None;
+        }
+        matrix.swap(column, pivot_row);
+        rhs.swap(column, pivot_row);
+
+        let (pivot_rows, lower_rows) = matrix.split_at_mut(column + 1);
+        let pivot = &pivot_rows[column];
+        for (row, lower_row) in lower_rows.iter_mut().enumerate().map(|(offset, lower_row)| (column + 1 + offset, lower_row)) {
+            let factor = lower_row[column] / pivot[column];
+            lower_row.iter_mut()
+                .zip(pivot)
+                .skip(column)
+                .for_each(|(value, pivot_value)| *value -= factor * pivot_value);
+            let pivot_rhs = rhs[column];
+            (0..3).for_each(|channel| rhs[row][channel] -= factor * pivot_rhs[channel]);
+        }
+    }
+
+    let mut solution = vec![[0.0f64; 3]; size];
+    for row in (0..size).rev() {
+        for channel in 0..3 {
+            let known_sum = (row + 1..size).map(|k| matrix[row][k] * solution[k][channel]).sum::<f64>();
+            solution[row][channel] = (rhs[row][channel] - known_sum) / matrix[row][row];
+        }
+    }
+
+    Some(solution)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blurred_error(source_image: &RgbImage, processed_image: &RgbImage) -> f32 {
+        let source = source_image.pixels().map(to_color).collect::<Vec<_>>();
+        let processed = processed_image.pixels().map(to_color).collect::<Vec<_>>();
+        let (width, height) = (source_image.width() as usize, source_image.height() as usize);
+
+        (0..width * height)
+            .map(|idx| {
+                let diff = neighbours(width, height, idx).fold([0.0f32; 3], |mut diff, (neighbour_idx, weight)| {
+                    (0..3).for_each(|channel| diff[channel] += weight * (processed[neighbour_idx][channel] - source[neighbour_idx][channel]));
+                    diff
+                });
+                diff.iter().map(|channel| channel * channel).sum::<f32>()
+            })
+            .sum()
+    }
+
+    #[test]
+    fn test_spatial_quantization_beats_error_diffusion() {
+        let source_image = crate::image::generate_test_gradient_image(48, 12, image::Rgb([0, 0, 0]), image::Rgb([255, 200, 100]));
+        let palette = PaletteRGB::primary_bw();
+
+        let spatial_image = spatial_quantization(source_image.clone(), palette.clone());
+        let diffused_image = crate::algorithms::dithering::dithering_floyd_steinberg_rgb(source_image.clone(), palette.clone());
+        assert!(spatial_image.pixels().all(|px| palette.contains(&ColorRGB::from_rgbu8(*px))));
+
+        let spatial_error = blurred_error(&source_image, &spatial_image);
+        let diffused_error = blurred_error(&source_image, &diffused_image);
+        assert!(spatial_error < diffused_error, "spatial_error={spatial_error}, diffused_error={diffused_error}");
+    }
+
+    #[test]
+    fn test_refine_palette_lowers_error() {
+        let source_image = crate::image::generate_test_gradient_image(32, 8, image::Rgb([20, 40, 60]), image::Rgb([220, 180, 90]));
+        let palette = PaletteRGB::from(vec![ColorRGB([0, 0, 0]), ColorRGB([255, 255, 255]), ColorRGB([255, 0, 0])]);
+        let refined_palette = refine_palette(&source_image, &palette);
+
+        let initial_error = blurred_error(&source_image, &spatial_quantization(source_image.clone(), palette));
+        let refined_error = blurred_error(&source_image, &spatial_quantization(source_image.clone(), refined_palette));
+        assert!(refined_error < initial_error, "refined_error={refined_error}, initial_error={initial_error}");
+    }
+
+    #[test]
+    fn test_solve_linear_system() {
+        let matrix = vec![vec![2.0, 1.0], vec![1.0, 3.0]];
+        let solution = solve_linear_system(matrix, vec![[3.0, 5.0, 0.0], [4.0, 10.0, 0.0]]).unwrap();
+        assert!((solution[0][0] - 1.0).abs() < 1e-9 && (solution[1][0] - 1.0).abs() < 1e-9);
+        assert!((solution[0][1] - 1.0).abs() < 1e-9 && (solution[1][1] - 3.0).abs() < 1e-9);
+        assert!(solve_linear_system(vec![vec![0.0]], vec![[1.0; 3]]).is_none());
+    }
+}
@@ -0,0 +1,110 @@
+use image::RgbImage;
+use palette::color_difference::EuclideanDistance;
+
+use crate::{algorithms::ordered::bayer_matrix, palette::PaletteRGB};
+
+/// Size of Bayer matrix spreading frame phases over neighbouring pixels.
+const PHASE_MATRIX_SIZE: usize = 4;
+
+/// Applies temporal dithering, producing frames whose average approximates the source.
+///
+/// Meant for displays refreshing faster than the eye integrates, e.g. LED matrices or
+/// e-paper partial refresh: showing frames in a loop gives intermediate colors without
+/// spatial pattern of regular dithering.
+///
+/// # Parameters
+/// - `source_image`: The input `RgbImage` to be dithered.
+/// - `palette`: A `PaletteRGB` containing the target colors for dithering.
+/// - `frames_count`: Number of alternating output frames.
+///
+/// # Returns
+/// - `frames_count` images containing only palette colors.
+///
+/// # Algorithm Details
+/// Quantization error is diffused in time instead of space: every pixel gets the palette
+/// color closest to the sum of source colors so far minus colors already shown. Each pixel
+/// starts the sequence at a different frame, taken from Bayer matrix, so neighbouring pixels
+/// do not flicker in sync.
+///
+/// # Panics
+/// Panics if `frames_count` is zero or palette is empty.
+///
+/// # Example
+/// ```
+/// use ditherum::{algorithms::temporal::temporal_dithering, palette::PaletteRGB};
+///
+/// let source_image = image::RgbImage::from_pixel(4, 4, image::Rgb([128, 128, 128]));
+/// let frames = temporal_dithering(&source_image, &PaletteRGB::black_and_white(), 2);
+///
+/// // Every pixel alternates between black and white
+/// assert_eq!(frames.len(), 2);
+/// assert!(frames[0].pixels().zip(frames[1].pixels()).all(|(a, b)| a != b));
+/// ```
+pub fn temporal_dithering(source_image: &RgbImage, palette: &PaletteRGB, frames_count: usize) -> Vec<RgbImage> {
+    assert!(frames_count > 0, "Frames count should be positive.");
+    assert!(!palette.is_empty(), "Palette is empty.");
+
+    let (width, height) = source_image.dimensions();
+    let mut frames = vec![RgbImage::new(width, height); frames_count];
+    let phases = bayer_matrix(PHASE_MATRIX_SIZE);
+    let srgb_palette = palette.iter()
+        .map(|color| (color.to_srgb(), color.to_rgbu8()))
+        .collect::<Vec<_>>();
+
+    for (x, y, pixel) in source_image.enumerate_pixels() {
+        let source_color = crate::color::ops::rgbu8_to_srgb(*pixel);
+        let phase = phases[y as usize % PHASE_MATRIX_SIZE][x as usize % PHASE_MATRIX_SIZE];
+        let mut shown_sum = palette::Srgb::new(0.0, 0.0, 0.0);
+
+        for step in 0..frames_count {
+            let desired_color = crate::color::ops::srgb_sub(
+                &crate::color::ops::srgb_mul_scalar(&source_color, (step + 1) as f32),
+                &shown_sum
+            );
+            let (closest_srgb, closest_rgbu8) = srgb_palette.iter()
+                .min_by(|(srgb_a, _), (srgb_b, _)| desired_color.distance_squared(*srgb_a)
+                    .partial_cmp(&desired_color.distance_squared(*srgb_b))
+                    .unwrap_or(std::cmp::Ordering::Equal)
+                )
+                .unwrap();
+
+            shown_sum = crate::color::ops::srgb_add(&shown_sum, closest_srgb);
+            frames[(phase + step) % frames_count].put_pixel(x, y, *closest_rgbu8);
+        }
+    }
+
+    frames
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mean_abs_error(source_image: &RgbImage, frames: &[RgbImage]) -> f32 {
+        let total_error = source_image.enumerate_pixels()
+            .flat_map(|(x, y, px)| (0..3).map(move |channel| (x, y, channel, px.0[channel])))
+            .map(|(x, y, channel, source_value)| {
+                let shown_mean = frames.iter()
+                    .map(|frame| frame.get_pixel(x, y).0[channel] as f32)
+                    .sum::<f32>() / frames.len() as f32;
+                (shown_mean - source_value as f32).abs()
+            })
+            .sum::<f32>();
+        total_error / (source_image.len() as f32)
+    }
+
+    #[test]
+    fn test_frames_average_approximates_source() {
+        let source_image = crate::image::generate_test_gradient_image(32, 4, image::Rgb([0, 0, 0]), image::Rgb([255, 255, 255]));
+        let palette = PaletteRGB::black_and_white();
+
+        let single_frame = temporal_dithering(&source_image, &palette, 1);
+        assert_eq!(single_frame[0], crate::algorithms::thresholding::thresholding_rgb(source_image.clone(), palette.clone()));
+
+        let frames = temporal_dithering(&source_image, &palette, 4);
+        let single_frame_error = mean_abs_error(&source_image, &single_frame);
+        let frames_error = mean_abs_error(&source_image, &frames);
+        assert!(frames_error < single_frame_error / 3.0, "frames_error={frames_error}, single_frame_error={single_frame_error}");
+        assert!(frames.iter().all(|frame| frame.pixels().all(|px| px.0 == [0, 0, 0] || px.0 == [255, 255, 255])));
+    }
+}
@@ -1,4 +1,4 @@
-use image::RgbImage;
+use image::{Rgb32FImage, RgbImage};
 
 use crate::{color::ColorRGB, palette::PaletteRGB};
 /// Applies thresholding to an image in RGB space by replacing each pixel with the closest color from the palette.
@@ -10,14 +10,25 @@ use crate::{color::ColorRGB, palette::PaletteRGB};
 /// # Returns
 /// An `RgbImage` where each pixel is replaced by the closest color from the palette using RGB distance.
 pub fn thresholding_rgb(mut source_image: RgbImage, palette: PaletteRGB) -> RgbImage {
-    source_image.enumerate_pixels_mut()
-        .for_each(|(_, _, pixel)| {
-            *pixel = palette.find_closest_by_rgb(&ColorRGB::from_rgbu8(*pixel)).to_rgbu8()
-        });
-
+    thresholding_rgb_in_place(&mut source_image, &palette);
     source_image
 }
 
+/// Same as [`thresholding_rgb`], but modifies the image in place.
+pub fn thresholding_rgb_in_place(image: &mut RgbImage, palette: &PaletteRGB) {
+    image.pixels_mut()
+        .for_each(|pixel| *pixel = closest_by_rgb(palette, pixel));
+}
+
+/// Same as [`thresholding_rgb`], but writes result to `target_image`, reallocating it only
+/// if its dimensions differ from the source ones.
+pub fn thresholding_rgb_into(source_image: &RgbImage, palette: &PaletteRGB, target_image: &mut RgbImage) {
+    crate::image::manip::match_dimensions(target_image, source_image);
+    target_image.pixels_mut()
+        .zip(source_image.pixels())
+        .for_each(|(target_pixel, source_pixel)| *target_pixel = closest_by_rgb(palette, source_pixel));
+}
+
 /// Applies thresholding to an image in Lab space by replacing each pixel with the closest color from the palette.
 /// 
 /// # Parameters
@@ -27,10 +38,113 @@ pub fn thresholding_rgb(mut source_image: RgbImage, palette: PaletteRGB) -> RgbI
 /// # Returns
 /// An `RgbImage` where each pixel is replaced by the closest color from the palette using Lab color distance.
 pub fn thresholding_lab(mut source_image: RgbImage, palette: PaletteRGB) -> RgbImage {
-    source_image.enumerate_pixels_mut()
-        .for_each(|(_, _, pixel)| {
-            *pixel = palette.find_closest_by_lab(&ColorRGB::from_rgbu8(*pixel)).to_rgbu8()
+    thresholding_lab_in_place(&mut source_image, &palette);
+    source_image
+}
+
+/// Same as [`thresholding_lab`], but modifies the image in place.
+pub fn thresholding_lab_in_place(image: &mut RgbImage, palette: &PaletteRGB) {
+    image.pixels_mut()
+        .for_each(|pixel| *pixel = closest_by_lab(palette, pixel));
+}
+
+/// Same as [`thresholding_lab`], but writes result to `target_image`, reallocating it only
+/// if its dimensions differ from the source ones.
+pub fn thresholding_lab_into(source_image: &RgbImage, palette: &PaletteRGB, target_image: &mut RgbImage) {
+    crate::image::manip::match_dimensions(target_image, source_image);
+    target_image.pixels_mut()
+        .zip(source_image.pixels())
+        .for_each(|(target_pixel, source_pixel)| *target_pixel = closest_by_lab(palette, source_pixel));
+}
+
+/// Applies thresholding in Lab space matching lightness before hue, see
+/// [`crate::color::ops::find_closest_lab_color_by_luminance`].
+///
+/// # Parameters
+/// - `source_image`: The input `RgbImage` to be processed.
+/// - `palette`: The color palette to use for thresholding.
+/// - `luminance_band`: Lightness tolerance (Lab ΔL) within which colors compete by chroma.
+///
+/// # Returns
+/// An `RgbImage` where each pixel is replaced by the palette color of matching brightness.
+pub fn thresholding_lab_luminance(source_image: RgbImage, palette: PaletteRGB, luminance_band: f32) -> RgbImage {
+    let mut target_image = RgbImage::new(source_image.width(), source_image.height());
+    thresholding_lab_luminance_into(&source_image, &palette, luminance_band, &mut target_image);
+    target_image
+}
+
+/// Same as [`thresholding_lab_luminance`], but writes result to `target_image`, reallocating it only
+/// if its dimensions differ from the source ones.
+pub fn thresholding_lab_luminance_into(source_image: &RgbImage, palette: &PaletteRGB, luminance_band: f32, target_image: &mut RgbImage) {
+    crate::image::manip::match_dimensions(target_image, source_image);
+    let lab_palette = palette.iter().map(ColorRGB::to_lab).collect::<Vec<_>>();
+    target_image.pixels_mut()
+        .zip(source_image.pixels())
+        .for_each(|(target_pixel, source_pixel)| {
+            let lab_color = crate::color::ops::rgbu8_to_lab(*source_pixel);
+            let (closest_color, _) = crate::color::ops::find_closest_lab_color_by_luminance(&lab_color, &lab_palette, luminance_band);
+            *target_pixel = ColorRGB::from_lab(closest_color).to_rgbu8();
         });
+}
 
-    source_image
-}
\ No newline at end of file
+/// Same as [`thresholding_rgb`], but takes floating point image, e.g. loaded from 16-bit file
+/// with [`crate::image::load_image_f32`], so it is not quantized to 8 bits beforehand.
+pub fn thresholding_rgb_f32(source_image: &Rgb32FImage, palette: &PaletteRGB) -> RgbImage {
+    let srgb_palette = palette.clone().to_srgb();
+    RgbImage::from_fn(source_image.width(), source_image.height(), |x, y| {
+        let pixel = source_image.get_pixel(x, y);
+        let srgb_color = palette::Srgb::new(pixel[0], pixel[1], pixel[2]);
+        ColorRGB::from_srgb(crate::color::ops::find_closest_srgb_color(&srgb_color, &srgb_palette)).to_rgbu8()
+    })
+}
+
+/// Same as [`thresholding_lab`], but takes floating point image, see [`thresholding_rgb_f32`].
+pub fn thresholding_lab_f32(source_image: &Rgb32FImage, palette: &PaletteRGB) -> RgbImage {
+    let lab_palette = palette.iter().map(ColorRGB::to_lab).collect::<Vec<_>>();
+    RgbImage::from_fn(source_image.width(), source_image.height(), |x, y| {
+        let pixel = source_image.get_pixel(x, y);
+        let lab_color = crate::color::ops::srgb_to_lab(palette::Srgb::new(pixel[0], pixel[1], pixel[2]));
+        let (closest_color, _) = crate::color::ops::find_closest_lab_color(&lab_color, &lab_palette);
+        ColorRGB::from_lab(closest_color).to_rgbu8()
+    })
+}
+
+fn closest_by_rgb(palette: &PaletteRGB, pixel: &image::Rgb<u8>) -> image::Rgb<u8> {
+    palette.find_closest_by_rgb(&ColorRGB::from_rgbu8(*pixel)).to_rgbu8()
+}
+
+fn closest_by_lab(palette: &PaletteRGB, pixel: &image::Rgb<u8>) -> image::Rgb<u8> {
+    palette.find_closest_by_lab(&ColorRGB::from_rgbu8(*pixel)).to_rgbu8()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_thresholding_into_reuses_target() {
+        let source_image = crate::image::generate_test_gradient_image(16, 4, image::Rgb([0, 0, 0]), image::Rgb([255, 255, 255]));
+        let palette = PaletteRGB::black_and_white();
+        let expected_image = thresholding_lab(source_image.clone(), palette.clone());
+
+        let mut target_image = RgbImage::new(1, 1);
+        thresholding_lab_into(&source_image, &palette, &mut target_image);
+        assert_eq!(target_image, expected_image);
+
+        // Stale content of reused target is fully overwritten
+        let previous_buffer = target_image.as_ptr();
+        thresholding_lab_into(&source_image, &palette, &mut target_image);
+        assert_eq!(target_image, expected_image);
+        assert_eq!(target_image.as_ptr(), previous_buffer);
+    }
+
+    #[test]
+    fn test_thresholding_luminance_keeps_dark_reds_off_black() {
+        let source_image = RgbImage::from_pixel(4, 4, image::Rgb([110, 10, 10]));
+        let palette = PaletteRGB::from(vec![ColorRGB([0, 0, 0]), ColorRGB([230, 90, 90]), ColorRGB([255, 255, 255])]);
+
+        assert_eq!(thresholding_lab(source_image.clone(), palette.clone()).get_pixel(0, 0).0, [0, 0, 0]);
+        let processed_image = thresholding_lab_luminance(source_image, palette, 40.0);
+        assert_eq!(processed_image.get_pixel(0, 0).0, [230, 90, 90]);
+    }
+}
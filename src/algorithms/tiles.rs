@@ -0,0 +1,302 @@
+use std::{collections::HashMap, fmt::Display, str::FromStr};
+
+use image::{GrayImage, RgbImage};
+use palette::color_difference::EuclideanDistance;
+
+use crate::{color::ColorRGB, image::ProcessingAlgorithm, palette::PaletteRGB};
+
+/// Error returned when parsing tile constraint fails.
+#[derive(Debug, thiserror::Error)]
+#[error("Invalid tile constraint '{0}', expected 'WIDTHxHEIGHT:COLORS' like '8x8:4'")]
+pub struct TileConstraintParseError(pub String);
+
+/// Limit of colors used within each tile, like attribute cells of retro hardware.
+///
+/// Parsed from `WIDTHxHEIGHT:COLORS`, e.g. `8x8:4`.
+///
+/// # Example
+/// ```
+/// use ditherum::algorithms::tiles::TileConstraint;
+///
+/// let constraint = "8x8:4".parse::<TileConstraint>().unwrap();
+/// assert_eq!(constraint, TileConstraint::new(8, 8, 4));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TileConstraint {
+    pub tile_width: u32,
+    pub tile_height: u32,
+
+    /// Maximal number of global palette colors used within a tile.
+    pub colors_per_tile: usize,
+}
+
+impl TileConstraint {
+    pub fn new(tile_width: u32, tile_height: u32, colors_per_tile: usize) -> Self {
+        Self { tile_width, tile_height, colors_per_tile }
+    }
+}
+
+impl FromStr for TileConstraint {
+    type Err = TileConstraintParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || TileConstraintParseError(s.to_string());
+
+        let (size, colors_per_tile) = s.split_once(':').ok_or_else(invalid)?;
+        let (tile_width, tile_height) = size.split_once(['x', 'X']).ok_or_else(invalid)?;
+        let constraint = Self::new(
+            tile_width.trim().parse().map_err(|_| invalid())?,
+            tile_height.trim().parse().map_err(|_| invalid())?,
+            colors_per_tile.trim().parse().map_err(|_| invalid())?,
+        );
+
+        if constraint.tile_width == 0 || constraint.tile_height == 0 || constraint.colors_per_tile == 0 {
+            return Err(invalid());
+        }
+        Ok(constraint)
+    }
+}
+
+impl Display for TileConstraint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}x{}:{}", self.tile_width, self.tile_height, self.colors_per_tile)
+    }
+}
+
+/// Dithers image tile by tile, each tile limited to its own sub-palette of the global palette.
+///
+/// Reproduces attribute clash of machines like NES or C64, where a small cell of pixels can
+/// use only a few colors out of the hardware palette.
+///
+/// # Parameters
+/// - `source_image`: The input `RgbImage` to be dithered.
+/// - `palette`: Global `PaletteRGB` sub-palettes are selected from.
+/// - `constraint`: Tile size and colors count allowed within a tile.
+/// - `algorithm`: Algorithm dithering each tile with its sub-palette.
+///
+/// # Returns
+/// - A dithered `RgbImage` with at most `colors_per_tile` colors in every tile.
+///
+/// # Algorithm Details
+/// Sub-palette is picked greedily: starting from an empty set, the palette color reducing
+/// the most summed Lab distance of tile pixels to their closest chosen color is added,
+/// until the limit is reached. Each tile is then dithered independently, so error does
+/// not spread over tile borders. Tiles clipped by image edge to a single row or column
+/// are thresholded, error diffusion needs at least 2x2 pixels.
+///
+/// # Example
+/// ```
+/// use ditherum::{algorithms::tiles::{tiled_dithering, TileConstraint}, image::ProcessingAlgorithm, palette::PaletteRGB};
+///
+/// let source_image = ditherum::image::generate_test_gradient_image(32, 8, image::Rgb([255, 0, 0]), image::Rgb([0, 0, 255]));
+/// let processed_image = tiled_dithering(&source_image, &PaletteRGB::primary_bw(), TileConstraint::new(8, 8, 2), ProcessingAlgorithm::Bayer4);
+///
+/// assert_eq!(processed_image.dimensions(), source_image.dimensions());
+/// ```
+pub fn tiled_dithering(source_image: &RgbImage, palette: &PaletteRGB, constraint: TileConstraint, algorithm: ProcessingAlgorithm) -> RgbImage {
+    let mut target_image = RgbImage::new(source_image.width(), source_image.height());
+    tiled_dithering_into(source_image, palette, constraint, algorithm, None, &mut target_image);
+    target_image
+}
+
+/// Same as [`tiled_dithering`], with optional dithering strength mask, writing result to `target_image`
+/// and reallocating it only if its dimensions differ from the source ones.
+///
+/// # Panics
+/// Panics if mask dimensions differ from image dimensions.
+pub fn tiled_dithering_into(
+    source_image: &RgbImage,
+    palette: &PaletteRGB,
+    constraint: TileConstraint,
+    algorithm: ProcessingAlgorithm,
+    mask: Option<&GrayImage>,
+    target_image: &mut RgbImage
+) {
+    tiled_dithering_with_rules_into(source_image, palette, constraint, &TilePaletteRules::default(), algorithm, mask, target_image);
+}
+
+/// Restrictions of tile sub-palettes beyond colors count, like real hardware has.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TilePaletteRules {
+    /// Number of colors shared by all tiles and counted into their limit, like common background color.
+    /// Shared colors are picked from the palette to minimize error of the whole image.
+    pub shared_colors_count: usize,
+
+    /// Parts of the palette, sub-palette of a tile has to be taken from one of them, like brightness
+    /// groups. Empty for the whole palette.
+    pub groups: Vec<PaletteRGB>,
+}
+
+impl TilePaletteRules {
+    /// Sets number of colors shared by all tiles.
+    pub fn with_shared_colors(mut self, shared_colors_count: usize) -> Self {
+        self.shared_colors_count = shared_colors_count;
+        self
+    }
+
+    /// Sets palette parts a tile sub-palette has to be taken from.
+    pub fn with_groups(mut self, groups: Vec<PaletteRGB>) -> Self {
+        self.groups = groups;
+        self
+    }
+}
+
+/// Same as [`tiled_dithering_into`], with sub-palettes also meeting `rules`.
+///
+/// # Panics
+/// Panics if mask dimensions differ from image dimensions, palette is empty or `shared_colors_count`
+/// exceeds `colors_per_tile`.
+pub fn tiled_dithering_with_rules_into(
+    source_image: &RgbImage,
+    palette: &PaletteRGB,
+    constraint: TileConstraint,
+    rules: &TilePaletteRules,
+    algorithm: ProcessingAlgorithm,
+    mask: Option<&GrayImage>,
+    target_image: &mut RgbImage
+) {
+    assert!(!palette.is_empty(), "Palette is empty.");
+    assert!(rules.shared_colors_count <= constraint.colors_per_tile, "More shared colors than colors per tile.");
+    if let Some(mask) = mask {
+        assert_eq!(source_image.dimensions(), mask.dimensions(), "Mask dimensions differ from image dimensions.");
+    }
+    crate::image::manip::match_dimensions(target_image, source_image);
+
+    let (width, height) = source_image.dimensions();
+    let groups = if rules.groups.is_empty() {
+        vec![palette.clone()]
+    } else {
+        rules.groups.clone()
+    };
+    let tiles = (0..height).step_by(constraint.tile_height as usize)
+        .flat_map(|tile_y| (0..width).step_by(constraint.tile_width as usize).map(move |tile_x| (tile_x, tile_y)))
+        .map(|(tile_x, tile_y)| {
+            let tile_width = constraint.tile_width.min(width - tile_x);
+            let tile_height = constraint.tile_height.min(height - tile_y);
+            (tile_x, tile_y, image::imageops::crop_imm(source_image, tile_x, tile_y, tile_width, tile_height).to_image())
+        })
+        .collect::<Vec<_>>();
+    let tiles_colors = tiles.iter()
+        .map(|(_, _, tile)| weighted_lab_colors(tile))
+        .collect::<Vec<_>>();
+
+    // Shared colors are added one by one, each time the one giving the lowest total error
+    let mut shared = Vec::with_capacity(rules.shared_colors_count);
+    while shared.len() < rules.shared_colors_count {
+        let best_shared_color = palette.iter()
+            .filter(|color| !shared.contains(*color))
+            .map(|color| {
+                let candidate_shared = shared.iter().chain([color]).copied().collect::<Vec<_>>();
+                let total_cost = tiles_colors.iter()
+                    .map(|tile_colors| select_tile_palette(tile_colors, &groups, &candidate_shared, constraint.colors_per_tile).1)
+                    .sum::<f32>();
+                (*color, total_cost)
+            })
+            .min_by(|(_, cost_a), (_, cost_b)| cost_a.partial_cmp(cost_b).unwrap_or(std::cmp::Ordering::Equal))
+            .map(|(color, _)| color);
+        match best_shared_color {
+            Some(color) => shared.push(color),
+            None => break,
+        }
+    }
+
+    let mut processed_tile = RgbImage::new(0, 0);
+    for ((tile_x, tile_y, tile), tile_colors) in tiles.into_iter().zip(tiles_colors) {
+        let (tile_palette, _) = select_tile_palette(&tile_colors, &groups, &shared, constraint.colors_per_tile);
+        let tile_mask = mask.map(|mask| image::imageops::crop_imm(mask, tile_x, tile_y, tile.width(), tile.height()).to_image());
+
+        let tile_algorithm = if tile.width() < 2 || tile.height() < 2 {
+            ProcessingAlgorithm::ThresholdingLab
+        } else {
+            algorithm
+        };
+        tile_algorithm.process_into(&tile, &tile_palette, tile_mask.as_ref(), &mut processed_tile);
+        image::imageops::replace(target_image, &processed_tile, tile_x as i64, tile_y as i64);
+    }
+}
+
+/// Unique colors of tile in Lab space along with their pixels count.
+fn weighted_lab_colors(tile: &RgbImage) -> Vec<(palette::Lab, f32)> {
+    tile.pixels()
+        .fold(HashMap::new(), |mut acc, px| {
+            *acc.entry(*px).or_insert(0usize) += 1;
+            acc
+        })
+        .into_iter()
+        .map(|(px, count)| (crate::color::ops::rgbu8_to_lab(px), count as f32))
+        .collect()
+}
+
+/// Picks sub-palette of the group best covering tile colors, returns it with its cost.
+///
+/// Shared colors are always included, remaining ones are added greedily: each time the group
+/// color reducing the most summed Lab distance of tile pixels to their closest chosen color.
+fn select_tile_palette(tile_colors: &[(palette::Lab, f32)], groups: &[PaletteRGB], shared: &[ColorRGB], colors_count: usize) -> (PaletteRGB, f32) {
+    groups.iter()
+        .map(|group| {
+            let mut chosen = shared.to_vec();
+            let mut closest_distances = tile_colors.iter()
+                .map(|(tile_color, _)| shared.iter()
+                    .map(|color| tile_color.distance_squared(color.to_lab()))
+                    .fold(f32::INFINITY, f32::min)
+                )
+                .collect::<Vec<_>>();
+
+            while chosen.len() < colors_count {
+                let best_color = group.iter()
+                    .filter(|color| !chosen.contains(*color))
+                    .map(|color| {
+                        let lab_color = color.to_lab();
+                        let cost = tile_colors.iter()
+                            .zip(&closest_distances)
+                            .map(|((tile_color, count), closest_distance)| count * closest_distance.min(tile_color.distance_squared(lab_color)))
+                            .sum::<f32>();
+                        (*color, lab_color, cost)
+                    })
+                    .min_by(|(_, _, cost_a), (_, _, cost_b)| cost_a.partial_cmp(cost_b).unwrap_or(std::cmp::Ordering::Equal));
+
+                let Some((color, lab_color, _)) = best_color else {
+                    break;
+                };
+                tile_colors.iter()
+                    .zip(closest_distances.iter_mut())
+                    .for_each(|((tile_color, _), closest_distance)| {
+                        *closest_distance = closest_distance.min(tile_color.distance_squared(lab_color));
+                    });
+                chosen.push(color);
+            }
+
+            let cost = tile_colors.iter()
+                .zip(&closest_distances)
+                .map(|((_, count), closest_distance)| count * closest_distance)
+                .sum::<f32>();
+            (PaletteRGB::from(chosen), cost)
+        })
+        .min_by(|(_, cost_a), (_, cost_b)| cost_a.partial_cmp(cost_b).unwrap_or(std::cmp::Ordering::Equal))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tiles_respect_colors_limit() {
+        let source_image = crate::image::generate_test_gradient_image(37, 19, image::Rgb([255, 0, 0]), image::Rgb([0, 255, 255]));
+        let constraint = TileConstraint::new(8, 8, 2);
+
+        for algorithm in ProcessingAlgorithm::all() {
+            let processed_image = tiled_dithering(&source_image, &PaletteRGB::primary_bw(), constraint, *algorithm);
+            for tile_y in (0..19).step_by(8) {
+                for tile_x in (0..37).step_by(8) {
+                    let tile = image::imageops::crop_imm(&processed_image, tile_x, tile_y, 8, 8).to_image();
+                    assert!(crate::image::count_image_colors(&tile).len() <= 2, "{algorithm} at {tile_x},{tile_y}");
+                }
+            }
+        }
+
+        for invalid in ["", "8x8", "8:2", "0x8:2", "8x8:0", "axb:c"] {
+            assert!(invalid.parse::<TileConstraint>().is_err(), "{invalid}");
+        }
+    }
+}
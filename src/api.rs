@@ -0,0 +1,1279 @@
+//! High-level processing in a single call.
+//!
+//! [`dither_image`] resizes, adjusts, picks palette and dithers an image, the same way
+//! `ditherum dither` does, so that embedders don't have to combine [`PaletteRGB`],
+//! [`ImageProcessor`] and resizing themselves. [`dither_raw`] does the same for frames
+//! already held in memory as raw RGB or RGBA bytes.
+//!
+//! # Example
+//! ```
+//! use ditherum::{dither_image, DitherOptions};
+//! use ditherum::image::ProcessingAlgorithm;
+//!
+//! let img = ditherum::image::generate_test_gradient_image(64, 16, image::Rgb([0, 0, 0]), image::Rgb([255, 128, 0]));
+//! let output = dither_image(img, DitherOptions::default()
+//!     .with_colors(4)
+//!     .with_algorithm(ProcessingAlgorithm::Bayer4)
+//!     .with_quality_metrics(true)
+//! ).unwrap();
+//!
+//! assert_eq!(output.palette.len(), 4);
+//! assert_eq!(output.image.dimensions(), (64, 16));
+//! assert!(output.stats.quality.is_some());
+//! ```
+
+use std::{borrow::Cow, collections::HashMap, hash::Hasher, time::{Duration, Instant}};
+
+use image::{buffer::ConvertBuffer, GrayImage, Rgb32FImage, RgbImage};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    algorithms::{despeckle::despeckle, dithering::ErrorDiffusion, hardware::HardwareProfile, kmean::Parallelism, noise::Noise, outline::{draw_outline, Outline}, posterize::ChannelLevels, preprocessing::Preprocessing, response::DisplayResponse, tiles::TileConstraint},
+    color::ColorRGB,
+    config::{ResizeConfig, Thumbnail},
+    image::{manip, ImageProcessor, ProcessingAlgorithm, ProcessingReport},
+    indexed::IndexedImage,
+    metrics::{mean_brightness, ImageQuality, PaletteUsage},
+    palette::{errors::PaletteError, ImageRegion, PaletteExtractOptions, PaletteRGB, PaletteSampling, ReduceStrategy}
+};
+
+use self::errors::DitherError;
+
+pub mod errors {
+    use crate::palette::errors::PaletteError;
+
+    #[derive(Debug, thiserror::Error)]
+    pub enum DitherError {
+        #[error("Buffer length {length} matches neither RGB nor RGBA {width}x{height} image.")]
+        InvalidBufferLength {
+            width: u32,
+            height: u32,
+            length: usize
+        },
+
+        #[error("Palette error, reason={0}")]
+        PaletteError(PaletteError),
+    }
+
+    impl From<PaletteError> for DitherError {
+        fn from(value: PaletteError) -> Self {
+            Self::PaletteError(value)
+        }
+    }
+}
+
+/// Palette used for dithering.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PaletteStrategy {
+    /// Given palette is used as it is.
+    Fixed(PaletteRGB),
+
+    /// Colors are extracted from the image and reduced to given count.
+    Reduce {
+        colors: usize,
+        sampling: PaletteSampling,
+
+        /// Colors guaranteed to survive reduction.
+        keep: Vec<ColorRGB>,
+
+        /// Minimal CIEDE2000 difference between reduced colors.
+        min_delta_e: f32,
+
+        /// Clustering backend of reduction.
+        strategy: ReduceStrategy,
+    },
+
+    /// The smallest palette whose thresholded image has mean CIEDE2000 error up to `max_error`.
+    Auto {
+        max_error: f32,
+        sampling: PaletteSampling,
+    },
+}
+
+impl Default for PaletteStrategy {
+    fn default() -> Self {
+        Self::Reduce {
+            colors: 8,
+            sampling: PaletteSampling::default(),
+            keep: Vec::new(),
+            min_delta_e: 0.0,
+            strategy: ReduceStrategy::default(),
+        }
+    }
+}
+
+/// Steps of gain binary search fitting the result to brightness budget, see [`DitherOptions::with_brightness_budget`].
+const BRIGHTNESS_BUDGET_STEPS: usize = 8;
+
+/// Settings of [`dither_image`], default value matches `ditherum dither` defaults.
+#[derive(Debug, Clone, Default)]
+pub struct DitherOptions {
+    pub algorithm: ProcessingAlgorithm,
+    pub resize: ResizeConfig,
+    pub preprocessing: Preprocessing,
+    pub noise: Noise,
+    pub palette: PaletteStrategy,
+
+    /// Area of resized image the palette is extracted from, whole image if `None`.
+    pub palette_region: Option<ImageRegion>,
+
+    /// Filtering of colors extracted from the image, e.g. ignored background.
+    pub palette_extract: PaletteExtractOptions,
+    pub transparent_key: Option<ColorRGB>,
+    pub mask: Option<GrayImage>,
+
+    /// Limit of colors within each tile, see [`crate::algorithms::tiles::tiled_dithering`].
+    pub tile_constraint: Option<TileConstraint>,
+
+    /// Retro machine whose palette and constraints are enforced, palette strategy is ignored then.
+    pub hardware: Option<HardwareProfile>,
+
+    /// Levels of each channel replacing palette, see [`crate::algorithms::posterize::posterize`].
+    pub levels: Option<ChannelLevels>,
+
+    /// Lightness tolerance of luminance first color matching, see [`ImageProcessor::with_luminance_matching`].
+    pub luminance_band: Option<f32>,
+
+    /// Colors target display shows for palette colors, see [`DitherOptions::with_display_response`].
+    pub display_response: Option<DisplayResponse>,
+
+    /// Maximal mean brightness of the result in percent, see [`DitherOptions::with_brightness_budget`].
+    pub brightness_budget: Option<f32>,
+
+    /// Limits of error accumulated by Floyd-Steinberg algorithms.
+    pub error_diffusion: ErrorDiffusion,
+
+    /// Sensitivity of photographic regions detection, only they are dithered if set.
+    pub hybrid_sensitivity: Option<f32>,
+
+    /// Block size of pixel art look, see [`DitherOptions::with_pixelate`].
+    pub pixelate: Option<u32>,
+
+    /// Factor of resolution dithering runs at, see [`DitherOptions::with_supersample`].
+    pub supersample: Option<u32>,
+
+    /// Islands with fewer pixels are removed from the result, see [`crate::algorithms::despeckle::despeckle`].
+    pub despeckle: Option<usize>,
+
+    /// Contour drawn over the result, see [`crate::algorithms::outline::draw_outline`].
+    pub outline: Option<Outline>,
+
+    /// Exact output dimensions replacing resizing, see [`DitherOptions::with_thumbnail`].
+    pub thumbnail: Option<Thumbnail>,
+
+    /// Minimal percentage of pixels a palette color has to cover to be kept, see [`DitherOptions::with_prune_palette`].
+    pub prune_palette: Option<f32>,
+
+    /// Threads of palette reduction clustering.
+    pub parallelism: Parallelism,
+
+    /// Measure [`ImageQuality`] of the result, costs two blurs of the image.
+    pub measure_quality: bool,
+
+    /// Collect [`ProcessingReport`] of dithering, see [`ImageProcessor::run_with_report`].
+    pub processing_report: bool,
+}
+
+impl DitherOptions {
+    /// Sets processing algorithm.
+    pub fn with_algorithm(mut self, algorithm: ProcessingAlgorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    /// Sets resizing applied before anything else.
+    pub fn with_resize(mut self, resize: ResizeConfig) -> Self {
+        self.resize = resize;
+        self
+    }
+
+    /// Sets adjustments applied after resizing, before palette extraction.
+    pub fn with_preprocessing(mut self, preprocessing: Preprocessing) -> Self {
+        self.preprocessing = preprocessing;
+        self
+    }
+
+    /// Sets noise added right before quantization.
+    pub fn with_noise(mut self, noise: Noise) -> Self {
+        self.noise = noise;
+        self
+    }
+
+    /// Sets palette strategy.
+    pub fn with_palette(mut self, palette: PaletteStrategy) -> Self {
+        self.palette = palette;
+        self
+    }
+
+    /// Sets area of resized image the palette is extracted from, ignored with [`PaletteStrategy::Fixed`].
+    pub fn with_palette_region(mut self, region: ImageRegion) -> Self {
+        self.palette_region = Some(region);
+        self
+    }
+
+    /// Sets filtering of colors extracted from the image, ignored with [`PaletteStrategy::Fixed`].
+    pub fn with_palette_extract(mut self, palette_extract: PaletteExtractOptions) -> Self {
+        self.palette_extract = palette_extract;
+        self
+    }
+
+    /// Reduces colors extracted from the image to given count, shorthand for [`PaletteStrategy::Reduce`].
+    pub fn with_colors(self, colors: usize) -> Self {
+        self.with_palette(PaletteStrategy::Reduce {
+            colors,
+            sampling: PaletteSampling::default(),
+            keep: Vec::new(),
+            min_delta_e: 0.0,
+            strategy: ReduceStrategy::default(),
+        })
+    }
+
+    /// Sets transparent key color, see [`ImageProcessor::with_transparent_key`].
+    pub fn with_transparent_key(mut self, key: ColorRGB) -> Self {
+        self.transparent_key = Some(key);
+        self
+    }
+
+    /// Sets dithering strength mask, see [`ImageProcessor::with_mask`].
+    pub fn with_mask(mut self, mask: GrayImage) -> Self {
+        self.mask = Some(mask);
+        self
+    }
+
+    /// Sets limit of colors within each tile, see [`ImageProcessor::with_tile_constraint`].
+    pub fn with_tile_constraint(mut self, constraint: TileConstraint) -> Self {
+        self.tile_constraint = Some(constraint);
+        self
+    }
+
+    /// Sets retro machine whose palette and constraints are enforced, see [`ImageProcessor::with_hardware`].
+    pub fn with_hardware(mut self, hardware: HardwareProfile) -> Self {
+        self.hardware = Some(hardware);
+        self
+    }
+
+    /// Sets levels of each channel replacing palette, see [`ImageProcessor::with_levels`].
+    pub fn with_levels(mut self, levels: ChannelLevels) -> Self {
+        self.levels = Some(levels);
+        self
+    }
+
+    /// Sets luminance first color matching, see [`ImageProcessor::with_luminance_matching`].
+    pub fn with_luminance_matching(mut self, luminance_band: f32) -> Self {
+        self.luminance_band = Some(luminance_band);
+        self
+    }
+
+    /// Sets response curve of target display, palette colors are matched by colors it actually shows.
+    /// 
+    /// Dithering runs with perceived palette colors, so that e.g. a gray the panel shows too dark
+    /// is picked for brighter pixels, and they are replaced by the driven palette colors afterwards.
+    /// Palette colors perceived the same are merged into the first of them. Meant for fixed palettes
+    /// of the panel, ignored with hardware profile and levels. Processing report describes perceived colors.
+    /// 
+    /// # Example
+    /// ```
+    /// use ditherum::{dither_image, DitherOptions};
+    /// use ditherum::algorithms::response::{DisplayPreset, DisplayResponse};
+    /// use ditherum::api::PaletteStrategy;
+    /// use ditherum::color::ColorRGB;
+    /// use ditherum::image::ProcessingAlgorithm;
+    /// use ditherum::palette::PaletteRGB;
+    ///
+    /// let img = image::RgbImage::from_pixel(4, 4, image::Rgb([200, 200, 200]));
+    /// let grays = PaletteRGB::from(vec![ColorRGB([0, 0, 0]), ColorRGB([128, 128, 128]), ColorRGB([255, 255, 255])]);
+    /// let options = DitherOptions::default()
+    ///     .with_algorithm(ProcessingAlgorithm::ThresholdingLab)
+    ///     .with_palette(PaletteStrategy::Fixed(grays));
+    ///
+    /// let srgb = dither_image(img.clone(), options.clone()).unwrap();
+    /// let led = dither_image(img, options.with_display_response(DisplayResponse::from(DisplayPreset::Led))).unwrap();
+    ///
+    /// // LED panel shows driven gray much brighter
+    /// assert_eq!(srgb.image.get_pixel(0, 0).0, [255, 255, 255]);
+    /// assert_eq!(led.image.get_pixel(0, 0).0, [128, 128, 128]);
+    /// ```
+    pub fn with_display_response(mut self, response: DisplayResponse) -> Self {
+        self.display_response = Some(response);
+        self
+    }
+
+    /// Sets maximal mean brightness of the result in percent of full drive, like power budget of LED walls.
+    /// 
+    /// Brighter results are dithered again from darkened image, so that palette matching picks darker
+    /// colors, the brightest result within budget is kept. If even the darkest palette colors exceed it,
+    /// result of black image is kept. Transparent key pixels are not darkened, outline and thumbnail
+    /// padding drawn afterwards are not counted, see [`crate::metrics::mean_brightness`].
+    /// 
+    /// # Example
+    /// ```
+    /// use ditherum::{dither_image, DitherOptions};
+    /// use ditherum::api::PaletteStrategy;
+    /// use ditherum::metrics::mean_brightness;
+    /// use ditherum::palette::PaletteRGB;
+    ///
+    /// let img = image::RgbImage::from_pixel(16, 16, image::Rgb([255, 255, 255]));
+    /// let output = dither_image(img, DitherOptions::default()
+    ///     .with_palette(PaletteStrategy::Fixed(PaletteRGB::primary_bw()))
+    ///     .with_brightness_budget(40.0)
+    /// ).unwrap();
+    ///
+    /// let brightness = mean_brightness(&output.image);
+    /// assert!(brightness <= 40.0 && brightness > 20.0);
+    /// ```
+    pub fn with_brightness_budget(mut self, max_brightness: f32) -> Self {
+        self.brightness_budget = Some(max_brightness);
+        self
+    }
+
+    /// Sets limits of accumulated error, see [`ImageProcessor::with_error_diffusion`].
+    pub fn with_error_diffusion(mut self, error_diffusion: ErrorDiffusion) -> Self {
+        self.error_diffusion = error_diffusion;
+        self
+    }
+
+    /// Sets dithering of photographic regions only, see [`ImageProcessor::with_hybrid`].
+    pub fn with_hybrid(mut self, sensitivity: f32) -> Self {
+        self.hybrid_sensitivity = Some(sensitivity);
+        self
+    }
+
+    /// Sets block size of chunky pixel art output.
+    /// 
+    /// Image is downscaled by averaging blocks, quantized with [`ProcessingAlgorithm::ThresholdingLab`]
+    /// regardless of the algorithm set and upscaled back with nearest neighbor. Palette is still extracted
+    /// from full resolution image, mask and hybrid sensitivity are ignored. Block size 1 leaves image as is.
+    pub fn with_pixelate(mut self, block_size: u32) -> Self {
+        self.pixelate = Some(block_size);
+        self
+    }
+
+    /// Sets factor of resolution dithering runs at, trading time for smoother gradients with tiny palettes.
+    /// 
+    /// Image is upscaled by the factor with nearest neighbor, dithered and downscaled back with box filter,
+    /// so each output pixel is the mean of `factor` x `factor` dithered pixels. The result contains mixes
+    /// of palette colors, reported palette stays unchanged. Ignored with pixelation, factor 1 leaves
+    /// processing as is. Processing report describes the upscaled image.
+    pub fn with_supersample(mut self, factor: u32) -> Self {
+        self.supersample = Some(factor);
+        self
+    }
+
+    /// Sets size of single color islands removed from the result, applied before outline.
+    pub fn with_despeckle(mut self, min_size: usize) -> Self {
+        self.despeckle = Some(min_size);
+        self
+    }
+
+    /// Sets contour drawn over the result, outline color is added to reported palette.
+    pub fn with_outline(mut self, outline: Outline) -> Self {
+        self.outline = Some(outline);
+        self
+    }
+
+    /// Sets social media thumbnail of exact dimensions, like OpenGraph `1200x630` image.
+    /// 
+    /// Image is center-cropped to thumbnail aspect ratio and downscaled to fit, replacing dimensions
+    /// and mode of resizing, its filter is kept. Smaller images are not upscaled, the result is centered
+    /// and padded with palette color closest to pad color, which is added to reported palette if missing.
+    /// Quality is measured without padding.
+    /// 
+    /// # Example
+    /// ```
+    /// use ditherum::{dither_image, DitherOptions};
+    /// use ditherum::config::Thumbnail;
+    ///
+    /// let img = ditherum::image::generate_test_gradient_image(400, 300, image::Rgb([0, 0, 0]), image::Rgb([255, 128, 0]));
+    /// let output = dither_image(img, DitherOptions::default()
+    ///     .with_colors(4)
+    ///     .with_thumbnail("1200x630".parse::<Thumbnail>().unwrap())
+    /// ).unwrap();
+    ///
+    /// assert_eq!(output.image.dimensions(), (1200, 630));
+    /// ```
+    pub fn with_thumbnail(mut self, thumbnail: Thumbnail) -> Self {
+        self.thumbnail = Some(thumbnail);
+        self
+    }
+
+    /// Sets minimal percentage of output pixels a palette color has to cover to stay in reported palette.
+    /// 
+    /// Applied last, unused colors are always dropped, 0 drops only them. Pixels of dropped colors
+    /// are replaced by the closest kept color by CIEDE2000, the most used color is never dropped.
+    /// 
+    /// # Example
+    /// ```
+    /// use ditherum::{dither_image, DitherOptions};
+    /// use ditherum::api::PaletteStrategy;
+    /// use ditherum::palette::PaletteRGB;
+    ///
+    /// let img = image::RgbImage::from_pixel(8, 8, image::Rgb([250, 10, 10]));
+    /// let output = dither_image(img, DitherOptions::default()
+    ///     .with_palette(PaletteStrategy::Fixed(PaletteRGB::primary_bw()))
+    ///     .with_prune_palette(0.0)
+    /// ).unwrap();
+    ///
+    /// assert_eq!(output.palette.len(), 1);
+    /// ```
+    pub fn with_prune_palette(mut self, min_percentage: f32) -> Self {
+        self.prune_palette = Some(min_percentage);
+        self
+    }
+
+    /// Sets threads of palette reduction clustering.
+    pub fn with_parallelism(mut self, parallelism: Parallelism) -> Self {
+        self.parallelism = parallelism;
+        self
+    }
+
+    /// Enables measuring quality of the result.
+    pub fn with_quality_metrics(mut self, measure_quality: bool) -> Self {
+        self.measure_quality = measure_quality;
+        self
+    }
+
+    /// Enables collecting [`ProcessingReport`] of dithering phase.
+    pub fn with_processing_report(mut self, processing_report: bool) -> Self {
+        self.processing_report = processing_report;
+        self
+    }
+}
+
+/// Statistics of [`dither_image`] run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DitherStats {
+    /// Quality of the result compared to resized and adjusted image, if requested.
+    pub quality: Option<ImageQuality>,
+
+    /// Time spent on palette extraction and reduction.
+    pub palette_duration: Duration,
+
+    /// Time spent on dithering.
+    pub processing_duration: Duration,
+
+    /// Report of dithering phase, if requested. With pixelation it describes the downscaled image.
+    pub processing: Option<ProcessingReport>,
+}
+
+impl DitherStats {
+    /// Total time of palette picking and dithering, without resizing and adjustments.
+    pub fn total_duration(&self) -> Duration {
+        self.palette_duration + self.processing_duration
+    }
+}
+
+/// Result of [`dither_image`].
+#[derive(Debug, Clone)]
+pub struct DitherOutput {
+    /// Processed image.
+    pub image: RgbImage,
+
+    /// Palette used for processing.
+    pub palette: PaletteRGB,
+
+    pub stats: DitherStats,
+}
+
+impl DitherOutput {
+    /// Converts processed image to palette indices, e.g. for exporters.
+    ///
+    /// Pixels missing from palette, like mixes of supersampling, get the closest palette color,
+    /// see [`IndexedImage::from_rgb_image`].
+    ///
+    /// # Example
+    /// ```
+    /// use ditherum::{dither_image, DitherOptions};
+    ///
+    /// let img = ditherum::image::generate_test_gradient_image(16, 8, image::Rgb([0, 0, 0]), image::Rgb([255, 255, 255]));
+    /// let output = dither_image(img, DitherOptions::default().with_colors(4)).unwrap();
+    /// let indexed = output.to_indexed().unwrap();
+    ///
+    /// assert_eq!(indexed.to_rgb_image(), output.image);
+    /// ```
+    pub fn to_indexed(&self) -> Result<IndexedImage, PaletteError> {
+        IndexedImage::from_rgb_image(&self.image, &self.palette)
+    }
+}
+
+/// Resizes, adjusts, picks palette and dithers the image.
+///
+/// Transparent key color is excluded from palettes extracted from the image. With
+/// [`ProcessingAlgorithm::SpatialQuant`] extracted palette is refined together with dithering,
+/// see [`crate::algorithms::spatial::refine_palette`].
+///
+/// # Errors
+/// Returns [`PaletteError`] if palette cannot be reduced, e.g. image has fewer colors than requested,
+/// or palette region does not overlap the image.
+pub fn dither_image(img: RgbImage, options: DitherOptions) -> Result<DitherOutput, PaletteError> {
+    dither_image_with_precise_source(img, None, options)
+}
+
+/// Same as [`dither_image`], but takes floating point image, e.g. loaded from 16-bit file with
+/// [`crate::image::load_image_f32`].
+///
+/// Palette is picked from the image rounded to 8 bits, dithering works on full precision source,
+/// see [`ImageProcessor::with_precise_source`]. Resizing, adjustments, pixelation, supersampling
+/// and brightness budget work on 8-bit pixels, with any of them the image is rounded first.
+///
+/// # Example
+/// ```
+/// use ditherum::{dither_image_f32, DitherOptions};
+/// use ditherum::api::PaletteStrategy;
+/// use ditherum::image::ProcessingAlgorithm;
+/// use ditherum::palette::PaletteRGB;
+///
+/// let img = image::Rgb32FImage::from_pixel(16, 16, image::Rgb([0.5; 3]));
+/// let output = dither_image_f32(img, DitherOptions::default()
+///     .with_algorithm(ProcessingAlgorithm::FloydSteinbergRgb)
+///     .with_palette(PaletteStrategy::Fixed(PaletteRGB::black_and_white()))
+/// ).unwrap();
+///
+/// assert_eq!(output.image.dimensions(), (16, 16));
+/// assert!(output.image.pixels().any(|px| px.0 == [0, 0, 0]) && output.image.pixels().any(|px| px.0 == [255, 255, 255]));
+/// ```
+///
+/// # Errors
+/// Same as [`dither_image`].
+pub fn dither_image_f32(img: Rgb32FImage, options: DitherOptions) -> Result<DitherOutput, PaletteError> {
+    dither_image_with_precise_source(img.convert(), Some(img), options)
+}
+
+/// Runs [`dither_image`], dithering `precise_source` instead of `img` if no stage needs 8-bit pixels.
+fn dither_image_with_precise_source(img: RgbImage, precise_source: Option<Rgb32FImage>, options: DitherOptions) -> Result<DitherOutput, PaletteError> {
+    let _span = tracing::info_span!("dither_image", width = img.width(), height = img.height()).entered();
+    let resize = match options.thumbnail {
+        Some(thumbnail) => thumbnail.resize_config(img.dimensions(), options.resize.filter),
+        None => options.resize,
+    };
+    let precise_source = precise_source.filter(|_| {
+        resize.is_identity() && options.preprocessing.is_identity() && options.brightness_budget.is_none()
+    });
+    let image = resize.apply(img);
+    let image = if options.preprocessing.is_identity() {
+        image
+    } else {
+        options.preprocessing.apply(image)
+    };
+
+    let palette_start = Instant::now();
+    let palette_span = tracing::debug_span!("palette").entered();
+    let palette_strategy = match (options.hardware, options.levels) {
+        (Some(hardware), _) => PaletteStrategy::Fixed(hardware.palette()),
+        // Palette is not used, colors of the result are reported instead
+        (None, Some(_)) => PaletteStrategy::Fixed(PaletteRGB::from(Vec::<ColorRGB>::new())),
+        (None, None) => options.palette,
+    };
+    let palette_is_extracted = !matches!(palette_strategy, PaletteStrategy::Fixed(_));
+    let palette_source_image = match options.palette_region {
+        Some(region) if palette_is_extracted => Cow::Owned(region.crop(&image)?),
+        _ => Cow::Borrowed(&image),
+    };
+    let extract_palette = |sampling| {
+        let mut palette = PaletteRGB::from_rgbu8_image_filtered(&palette_source_image, sampling, &options.palette_extract);
+        if let Some(key) = options.transparent_key {
+            palette.retain(|color| *color != key);
+        }
+        palette
+    };
+    let palette = match palette_strategy {
+        PaletteStrategy::Fixed(palette) => palette,
+        PaletteStrategy::Reduce { colors, sampling, keep, min_delta_e, strategy } => {
+            extract_palette(sampling).try_reduce_spaced_with_strategy(colors, &keep, min_delta_e, strategy, &options.parallelism)?
+        },
+        PaletteStrategy::Auto { max_error, sampling } => {
+            extract_palette(sampling).reduce_to_quality(&palette_source_image, max_error)?
+        },
+    };
+    let palette = match (options.algorithm, palette_is_extracted) {
+        // Palette and assignment are optimized jointly, kept colors are not guaranteed to survive
+        (ProcessingAlgorithm::SpatialQuant, true) => crate::algorithms::spatial::refine_palette(&image, &palette),
+        _ => palette,
+    };
+    let palette_duration = palette_start.elapsed();
+    drop(palette_span);
+
+    let reference_image = options.measure_quality.then(|| image.clone());
+    let processing_start = Instant::now();
+    let pixelate = options.pixelate.filter(|&block_size| block_size > 1);
+    let (width, height) = image.dimensions();
+    let supersample = options.supersample.filter(|&factor| factor > 1 && pixelate.is_none());
+    let (image, algorithm) = match (pixelate, supersample) {
+        (Some(block_size), _) => (manip::rgb_image_downscale_blocks(&image, block_size), ProcessingAlgorithm::ThresholdingLab),
+        (None, Some(factor)) => (manip::rgb_image_upscale_blocks(&image, factor, width * factor, height * factor), options.algorithm),
+        (None, None) => (image, options.algorithm),
+    };
+    // Taken by the first dithering pass, pixelated or supersampled image no longer matches it
+    let precise_source = std::cell::Cell::new(precise_source.filter(|_| pixelate.is_none() && supersample.is_none()));
+    let perceived_palette = options.display_response.as_ref()
+        .filter(|_| options.hardware.is_none() && options.levels.is_none())
+        .map(|response| (response.perceived_palette(&palette, options.transparent_key), response));
+    let mask = options.mask.filter(|_| pixelate.is_none()).map(|mask| match supersample {
+        Some(factor) => image::imageops::resize(&mask, mask.width() * factor, mask.height() * factor, image::imageops::FilterType::Nearest),
+        None => mask,
+    });
+    let dither = |image: RgbImage| {
+        let mut processor = ImageProcessor::new(image, perceived_palette.as_ref().map_or_else(|| palette.clone(), |(perceived, _)| perceived.clone()))
+            .with_algorithm(algorithm)
+            .with_noise(options.noise)
+            .with_error_diffusion(options.error_diffusion);
+        if let Some(mask) = &mask {
+            processor = processor.with_mask(mask.clone());
+        }
+        if let Some(key) = options.transparent_key {
+            processor = processor.with_transparent_key(key);
+        }
+        if let Some(constraint) = options.tile_constraint {
+            processor = processor.with_tile_constraint(constraint);
+        }
+        if let Some(hardware) = options.hardware {
+            processor = processor.with_hardware(hardware);
+        }
+        if let Some(levels) = options.levels {
+            processor = processor.with_levels(levels);
+        }
+        if let Some(luminance_band) = options.luminance_band {
+            processor = processor.with_luminance_matching(luminance_band);
+        }
+        if let Some(sensitivity) = options.hybrid_sensitivity.filter(|_| pixelate.is_none()) {
+            processor = processor.with_hybrid(sensitivity);
+        }
+        if let Some(precise_source) = precise_source.take() {
+            processor = processor.with_precise_source(precise_source);
+        }
+        let (processed_image, processing) = if options.processing_report {
+            let (processed_image, report) = processor.run_with_report();
+            (processed_image, Some(report))
+        } else {
+            (processor.run(), None)
+        };
+        let processed_image = match &perceived_palette {
+            Some((_, response)) => drive_perceived_colors(processed_image, &palette, response, options.transparent_key),
+            None => processed_image,
+        };
+        (processed_image, processing)
+    };
+    let (processed_image, processing) = match options.brightness_budget {
+        Some(max_brightness) => dither_within_brightness_budget(image, max_brightness, options.transparent_key, dither),
+        None => dither(image),
+    };
+    let processed_image = match (pixelate, supersample) {
+        (Some(block_size), _) => manip::rgb_image_upscale_blocks(&processed_image, block_size, width, height),
+        (None, Some(factor)) => manip::rgb_image_downscale_blocks(&processed_image, factor),
+        (None, None) => processed_image,
+    };
+    let processed_image = match options.despeckle {
+        Some(min_size) => despeckle(&processed_image, min_size),
+        None => processed_image,
+    };
+    let processed_image = match &options.outline {
+        Some(outline) => draw_outline(&processed_image, outline),
+        None => processed_image,
+    };
+    let processing_duration = processing_start.elapsed();
+    let mut palette = match (options.hardware, options.levels) {
+        (None, Some(_)) => PaletteRGB::from_rgbu8_image(&processed_image),
+        _ => palette,
+    };
+    if let Some(outline) = options.outline.filter(|outline| !palette.contains(&outline.color)) {
+        palette.push(outline.color);
+    }
+
+    let quality = reference_image.map(|reference_image| ImageQuality::measure(&reference_image, &processed_image));
+    let processed_image = match options.thumbnail.filter(|thumbnail| thumbnail.dimensions() != processed_image.dimensions()) {
+        Some(thumbnail) => {
+            let pad_color = if palette.is_empty() { thumbnail.pad_color } else { palette.find_closest_by_lab(&thumbnail.pad_color) };
+            if !palette.contains(&pad_color) {
+                palette.push(pad_color);
+            }
+            manip::rgb_image_pad(&processed_image, thumbnail.width, thumbnail.height, pad_color.into())
+        },
+        None => processed_image,
+    };
+    let (processed_image, palette) = match options.prune_palette {
+        Some(min_percentage) => prune_palette(processed_image, palette, min_percentage),
+        None => (processed_image, palette),
+    };
+
+    Ok(DitherOutput {
+        image: processed_image,
+        palette,
+        stats: DitherStats {
+            quality,
+            palette_duration,
+            processing_duration,
+            processing,
+        },
+    })
+}
+
+/// Drops palette colors covering less than `min_percentage` of pixels and replaces their pixels
+/// with the closest kept color, see [`DitherOptions::with_prune_palette`].
+fn prune_palette(mut image: RgbImage, mut palette: PaletteRGB, min_percentage: f32) -> (RgbImage, PaletteRGB) {
+    let usage = PaletteUsage::measure(&image, &palette);
+    let most_used = usage.colors.iter().max_by_key(|usage| usage.pixels).map(|usage| usage.color);
+    let dropped = usage.colors.iter()
+        .filter(|usage| (usage.pixels == 0 || usage.percentage < min_percentage) && Some(usage.color) != most_used)
+        .collect::<Vec<_>>();
+    if dropped.is_empty() {
+        return (image, palette);
+    }
+
+    palette.retain(|color| dropped.iter().all(|usage| usage.color != *color));
+    let replacements = dropped.iter()
+        .filter(|usage| usage.pixels > 0)
+        .map(|usage| (usage.color.to_rgbu8(), palette.find_closest_by_lab(&usage.color).to_rgbu8()))
+        .collect::<HashMap<_, _>>();
+    if !replacements.is_empty() {
+        for px in image.pixels_mut() {
+            if let Some(replacement) = replacements.get(px) {
+                *px = *replacement;
+            }
+        }
+    }
+    (image, palette)
+}
+
+/// Dithers image darkened by binary searched gain until mean brightness of the result fits
+/// `max_brightness`, see [`DitherOptions::with_brightness_budget`].
+fn dither_within_brightness_budget<T>(image: RgbImage, max_brightness: f32, reserved: Option<ColorRGB>, dither: impl Fn(RgbImage) -> (RgbImage, T)) -> (RgbImage, T) {
+    let darken = |gain: f32| {
+        let mut image = image.clone();
+        for px in image.pixels_mut().filter(|px| reserved.is_none_or(|key| key.to_rgbu8() != **px)) {
+            px.0 = px.0.map(|channel| (channel as f32 * gain).round() as u8);
+        }
+        image
+    };
+    let result = dither(image.clone());
+    if mean_brightness(&result.0) <= max_brightness {
+        return result;
+    }
+
+    let (mut min_gain, mut max_gain) = (0.0, 1.0);
+    let mut best = None;
+    for _ in 0..BRIGHTNESS_BUDGET_STEPS {
+        let gain = (min_gain + max_gain) / 2.0;
+        let result = dither(darken(gain));
+        if mean_brightness(&result.0) <= max_brightness {
+            min_gain = gain;
+            best = Some(result);
+        } else {
+            max_gain = gain;
+        }
+    }
+    best.unwrap_or_else(|| dither(darken(0.0)))
+}
+
+/// Replaces perceived colors of the image with palette colors driving them, the first one if
+/// several are perceived the same, see [`DitherOptions::with_display_response`].
+fn drive_perceived_colors(mut image: RgbImage, palette: &PaletteRGB, response: &DisplayResponse, reserved: Option<ColorRGB>) -> RgbImage {
+    let mut driven_colors = HashMap::new();
+    for &color in palette.iter().filter(|&&color| Some(color) != reserved) {
+        driven_colors.entry(response.apply(color).to_rgbu8()).or_insert(color.to_rgbu8());
+    }
+    for px in image.pixels_mut() {
+        if let Some(driven) = driven_colors.get(px) {
+            *px = *driven;
+        }
+    }
+    image
+}
+
+/// Same as [`dither_image`], but takes raw interleaved pixels, row after row.
+///
+/// Format is deduced from buffer length: 3 bytes per pixel for RGB, 4 bytes for RGBA,
+/// alpha is ignored. The buffer is borrowed, so its pixels are copied once into `RgbImage`
+/// the pipeline works on, RGBA pixels lose alpha in the same pass. Callers owning RGB `Vec<u8>`
+/// avoid the copy by wrapping it with `RgbImage::from_raw` and calling [`dither_image`].
+///
+/// # Example
+/// ```
+/// use ditherum::{dither_raw, DitherOptions};
+/// use ditherum::api::PaletteStrategy;
+/// use ditherum::palette::PaletteRGB;
+///
+/// let rgba_frame = [
+///     10, 10, 10, 255,   240, 240, 240, 255,
+///     240, 240, 240, 255,   10, 10, 10, 255,
+/// ];
+/// let options = DitherOptions::default().with_palette(PaletteStrategy::Fixed(PaletteRGB::black_and_white()));
+/// let output = dither_raw(&rgba_frame, 2, 2, options).unwrap();
+///
+/// assert_eq!(output.image.as_raw(), &[0, 0, 0, 255, 255, 255, 255, 255, 255, 0, 0, 0]);
+/// ```
+///
+/// # Errors
+/// Returns [`DitherError::InvalidBufferLength`] if buffer length does not match dimensions
+/// and [`DitherError::PaletteError`] if palette cannot be reduced.
+pub fn dither_raw(buf: &[u8], width: u32, height: u32, options: DitherOptions) -> Result<DitherOutput, DitherError> {
+    let pixels_count = width as usize * height as usize;
+    // The only copy of the buffer, resizing and processing work on owned image
+    let pixels = if buf.len() == pixels_count * 3 {
+        Some(buf.to_vec())
+    } else if buf.len() == pixels_count * 4 {
+        Some(buf.chunks_exact(4).flat_map(|px| &px[..3]).copied().collect())
+    } else {
+        None
+    };
+
+    let image = pixels
+        .and_then(|pixels| RgbImage::from_raw(width, height, pixels))
+        .ok_or(DitherError::InvalidBufferLength { width, height, length: buf.len() })?;
+    Ok(dither_image(image, options)?)
+}
+
+/// Fingerprint of [`dither_image`] output for cache keys, so that pipelines can skip reprocessing
+/// of unchanged inputs.
+///
+/// Hashes image pixels together with every option affecting the result, including fixed palette
+/// colors and mask, so changed settings give a different fingerprint. Threads count and
+/// measurement flags are left out. Hash is stable between runs and platforms, but crate version
+/// is included, so upgrades invalidate cached outputs.
+///
+/// # Example
+/// ```
+/// use ditherum::{api::output_fingerprint, image::ProcessingAlgorithm, DitherOptions};
+///
+/// let img = image::RgbImage::from_pixel(8, 8, image::Rgb([40, 80, 120]));
+/// let options = DitherOptions::default().with_colors(4);
+///
+/// assert_eq!(output_fingerprint(&img, &options), output_fingerprint(&img.clone(), &options.clone()));
+/// assert_ne!(output_fingerprint(&img, &options), output_fingerprint(&img, &options.with_algorithm(ProcessingAlgorithm::Bayer4)));
+/// ```
+pub fn output_fingerprint(img: &RgbImage, options: &DitherOptions) -> u64 {
+    fingerprint_with(options, |hasher| {
+        hasher.write(&img.width().to_le_bytes());
+        hasher.write(&img.height().to_le_bytes());
+        hasher.write(img.as_raw());
+    })
+}
+
+/// Same as [`output_fingerprint`], but for [`dither_image_f32`] output, so that images differing
+/// only below 8-bit precision get different fingerprints.
+///
+/// # Example
+/// ```
+/// use ditherum::{api::{output_fingerprint, output_fingerprint_f32}, DitherOptions};
+///
+/// let options = DitherOptions::default().with_colors(4);
+/// let img = image::Rgb32FImage::from_pixel(8, 8, image::Rgb([0.5, 0.5, 0.5]));
+/// let img_shifted = image::Rgb32FImage::from_pixel(8, 8, image::Rgb([0.5001, 0.5, 0.5]));
+///
+/// assert_ne!(output_fingerprint_f32(&img, &options), output_fingerprint_f32(&img_shifted, &options));
+/// ```
+pub fn output_fingerprint_f32(img: &Rgb32FImage, options: &DitherOptions) -> u64 {
+    fingerprint_with(options, |hasher| {
+        // Marks floating point pixels, so they never collide with 8-bit ones
+        hasher.write(b"f32");
+        hasher.write(&img.width().to_le_bytes());
+        hasher.write(&img.height().to_le_bytes());
+        img.as_raw().iter().for_each(|channel| hasher.write(&channel.to_le_bytes()));
+    })
+}
+
+/// Hashes options affecting the result together with image written by `write_image`.
+fn fingerprint_with(options: &DitherOptions, write_image: impl FnOnce(&mut Fnv1aHasher)) -> u64 {
+    // Destructured, so that new options cannot be forgotten here
+    let DitherOptions {
+        algorithm, resize, preprocessing, noise, palette, palette_region, palette_extract, transparent_key, mask,
+        tile_constraint, hardware, levels, luminance_band, display_response, brightness_budget, error_diffusion, hybrid_sensitivity, pixelate,
+        supersample, despeckle, outline, thumbnail, prune_palette, parallelism: _, measure_quality: _, processing_report: _
+    } = options;
+    let settings = format!(
+        "{algorithm:?}{resize:?}{preprocessing:?}{noise:?}{palette:?}{palette_region:?}{palette_extract:?}{transparent_key:?}\
+        {tile_constraint:?}{hardware:?}{levels:?}{luminance_band:?}{display_response:?}{brightness_budget:?}{error_diffusion:?}{hybrid_sensitivity:?}{pixelate:?}{supersample:?}{despeckle:?}{outline:?}{thumbnail:?}{prune_palette:?}"
+    );
+
+    let mut hasher = Fnv1aHasher::default();
+    hasher.write(env!("CARGO_PKG_VERSION").as_bytes());
+    hasher.write(settings.as_bytes());
+    write_image(&mut hasher);
+    match mask {
+        Some(mask) => {
+            hasher.write(&mask.width().to_le_bytes());
+            hasher.write(&mask.height().to_le_bytes());
+            hasher.write(mask.as_raw());
+        },
+        None => hasher.write(&[0]),
+    }
+    hasher.finish()
+}
+
+/// 64-bit FNV-1a hash, unlike `DefaultHasher` stable between Rust releases.
+struct Fnv1aHasher(u64);
+
+impl Default for Fnv1aHasher {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl std::hash::Hasher for Fnv1aHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ *byte as u64).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+/// Same as [`dither_image`], but runs on blocking thread pool of tokio runtime, so that async
+/// services embedding the crate do not stall their executor. Requires `async` feature.
+///
+/// # Example
+/// ```
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// use ditherum::{dither_image_async, DitherOptions};
+///
+/// let img = ditherum::image::generate_test_gradient_image(32, 8, image::Rgb([0, 0, 0]), image::Rgb([255, 255, 255]));
+/// let output = dither_image_async(img, DitherOptions::default().with_colors(4)).await.unwrap();
+/// assert_eq!(output.palette.len(), 4);
+/// # });
+/// ```
+///
+/// # Errors
+/// Returns the same errors as [`dither_image`].
+///
+/// # Panics
+/// Panics if called outside of tokio runtime, panic of processing is resumed in the caller.
+#[cfg(feature = "async")]
+pub async fn dither_image_async(img: RgbImage, options: DitherOptions) -> Result<DitherOutput, PaletteError> {
+    run_blocking(move || dither_image(img, options)).await
+}
+
+/// Same as [`dither_raw`], but runs on blocking thread pool of tokio runtime, see [`dither_image_async`].
+/// Requires `async` feature.
+///
+/// # Errors
+/// Returns the same errors as [`dither_raw`].
+#[cfg(feature = "async")]
+pub async fn dither_raw_async(buf: Vec<u8>, width: u32, height: u32, options: DitherOptions) -> Result<DitherOutput, DitherError> {
+    run_blocking(move || dither_raw(&buf, width, height, options)).await
+}
+
+/// Runs `task` with [`tokio::task::spawn_blocking`], panic of the task is resumed in the caller.
+#[cfg(feature = "async")]
+async fn run_blocking<T, F>(task: F) -> T
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static
+{
+    match tokio::task::spawn_blocking(task).await {
+        Ok(result) => result,
+        Err(error) => match error.try_into_panic() {
+            Ok(payload) => std::panic::resume_unwind(payload),
+            Err(error) => panic!("Blocking task failed, reason={error}"),
+        },
+    }
+}
+
+/// How colors of source palette are matched to colors of target palette, see [`remap_image`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, clap::ValueEnum, Serialize, Deserialize)]
+pub enum RemapMetric {
+    /// Color at the same position in target palette, palettes need the same length
+    #[value(name = "index")]
+    #[serde(rename = "index")]
+    Index,
+
+    /// The closest target color by CIEDE2000
+    #[default]
+    #[value(name = "nearest")]
+    #[serde(rename = "nearest")]
+    Nearest,
+}
+
+impl RemapMetric {
+    /// Returns short name of the metric, e.g. `index`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Index => "index",
+            Self::Nearest => "nearest",
+        }
+    }
+}
+
+/// Recolors indexed or dithered image from one palette to another, without dithering it again.
+///
+/// Every color of `from_palette` is swapped to its counterpart in `to_palette`, picked by
+/// `metric`, so the dithering pattern stays intact. Image is converted to [`IndexedImage`] first,
+/// pixels missing from `from_palette`, e.g. after lossy compression, are treated as the closest color of it. Useful for porting
+/// game assets between palette revisions.
+///
+/// # Errors
+/// Returns [`PaletteError::PaletteEmpty`] if any of the palettes is empty,
+/// [`PaletteError::LengthMismatch`] if [`RemapMetric::Index`] is used with palettes of different lengths
+/// and [`PaletteError::TooManyColorsForIndices`] if `from_palette` is too large for [`IndexedImage`].
+///
+/// # Example
+/// ```
+/// use ditherum::{api::{remap_image, RemapMetric}, color::ColorRGB, palette::PaletteRGB};
+///
+/// let img = image::RgbImage::from_fn(2, 1, |x, _| if x == 0 { image::Rgb([0, 0, 0]) } else { image::Rgb([255, 255, 255]) });
+/// let from_palette = PaletteRGB::black_and_white();
+/// let to_palette = PaletteRGB::from(vec![ColorRGB([20, 12, 28]), ColorRGB([222, 238, 214])]);
+///
+/// let remapped = remap_image(&img, &from_palette, &to_palette, RemapMetric::Index).unwrap();
+/// assert_eq!(remapped.get_pixel(0, 0).0, [20, 12, 28]);
+/// assert_eq!(remapped.get_pixel(1, 0).0, [222, 238, 214]);
+/// ```
+pub fn remap_image(img: &RgbImage, from_palette: &PaletteRGB, to_palette: &PaletteRGB, metric: RemapMetric) -> Result<RgbImage, PaletteError> {
+    if from_palette.is_empty() || to_palette.is_empty() {
+        return Err(PaletteError::PaletteEmpty);
+    }
+    if metric == RemapMetric::Index && from_palette.len() != to_palette.len() {
+        return Err(PaletteError::LengthMismatch { from: from_palette.len(), to: to_palette.len() });
+    }
+
+    let targets = from_palette.iter()
+        .enumerate()
+        .map(|(idx, color)| match metric {
+            RemapMetric::Index => to_palette[idx].to_rgbu8(),
+            RemapMetric::Nearest => to_palette.find_closest_by_lab(color).to_rgbu8(),
+        })
+        .collect::<Vec<_>>();
+
+    let indexed = IndexedImage::from_rgb_image(img, from_palette)?;
+    let raw = indexed.indices().iter()
+        .flat_map(|idx| targets[idx].0)
+        .collect::<Vec<_>>();
+    Ok(RgbImage::from_raw(img.width(), img.height(), raw).expect("indices match dimensions"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dither_image_fixed_palette_and_key() {
+        let key = ColorRGB([255, 0, 255]);
+        let img = RgbImage::from_fn(8, 8, |x, _| if x < 2 { key.to_rgbu8() } else { image::Rgb([200, 200, 200]) });
+        let options = DitherOptions::default()
+            .with_palette(PaletteStrategy::Fixed(PaletteRGB::black_and_white()))
+            .with_transparent_key(key)
+            .with_resize(ResizeConfig { width: Some(4), filter: crate::image::ResizeFilter::Nearest, ..Default::default() });
+
+        let output = dither_image(img, options).unwrap();
+        assert_eq!(output.image.dimensions(), (4, 4));
+        assert_eq!(output.palette, PaletteRGB::black_and_white());
+        assert_eq!(*output.image.get_pixel(0, 0), key.to_rgbu8());
+        assert!(output.stats.quality.is_none());
+        assert!(output.stats.processing.is_none());
+    }
+
+    #[test]
+    fn test_dither_raw_matches_image() {
+        let img = crate::image::generate_test_gradient_image(16, 4, image::Rgb([0, 0, 0]), image::Rgb([255, 200, 100]));
+        let rgba = img.pixels().flat_map(|px| [px[0], px[1], px[2], 128]).collect::<Vec<_>>();
+        let options = DitherOptions::default()
+            .with_palette(PaletteStrategy::Fixed(PaletteRGB::primary_bw()))
+            .with_algorithm(ProcessingAlgorithm::Bayer4);
+
+        let expected = dither_image(img.clone(), options.clone()).unwrap().image;
+        assert_eq!(dither_raw(img.as_raw(), 16, 4, options.clone()).unwrap().image, expected);
+        assert_eq!(dither_raw(&rgba, 16, 4, options.clone()).unwrap().image, expected);
+        assert!(matches!(
+            dither_raw(&rgba[1..], 16, 4, options),
+            Err(DitherError::InvalidBufferLength { width: 16, height: 4, .. })
+        ));
+    }
+
+    #[test]
+    fn test_dither_image_pixelate() {
+        let img = crate::image::generate_test_gradient_image(30, 10, image::Rgb([0, 0, 0]), image::Rgb([255, 255, 255]));
+        let options = DitherOptions::default()
+            .with_palette(PaletteStrategy::Fixed(PaletteRGB::grayscale(4)))
+            .with_pixelate(8)
+            .with_processing_report(true);
+
+        let output = dither_image(img, options).unwrap();
+        assert_eq!(output.image.dimensions(), (30, 10));
+        for (x, y, px) in output.image.enumerate_pixels() {
+            assert_eq!(px, output.image.get_pixel(x - x % 8, y - y % 8));
+        }
+        let report = output.stats.processing.unwrap();
+        assert_eq!(report.palette_size, 4);
+        assert!(report.unique_colors <= 4);
+    }
+
+    #[test]
+    fn test_remap_image() {
+        let from_palette = PaletteRGB::from(vec![ColorRGB([0, 0, 0]), ColorRGB([128, 128, 128]), ColorRGB([255, 255, 255])]);
+        let to_palette = PaletteRGB::from(vec![ColorRGB([250, 250, 250]), ColorRGB([120, 0, 0]), ColorRGB([5, 5, 5])]);
+        let img = RgbImage::from_fn(4, 1, |x, _| image::Rgb([[0, 0, 0], [128, 128, 128], [255, 255, 255], [130, 126, 128]][x as usize]));
+
+        // Colors are swapped by position, off-palette pixel follows its closest source color
+        let remapped = remap_image(&img, &from_palette, &to_palette, RemapMetric::Index).unwrap();
+        let colors = remapped.pixels().map(|px| ColorRGB(px.0)).collect::<Vec<_>>();
+        let gray_idx = from_palette.iter().position(|color| *color == ColorRGB([128, 128, 128])).unwrap();
+        assert!(colors.iter().all(|color| to_palette.contains(color)));
+        assert_eq!(colors[1], to_palette[gray_idx]);
+        assert_eq!(colors[3], colors[1]);
+
+        let remapped = remap_image(&img, &from_palette, &to_palette, RemapMetric::Nearest).unwrap();
+        assert_eq!(remapped.get_pixel(0, 0).0, [5, 5, 5]);
+        assert_eq!(remapped.get_pixel(2, 0).0, [250, 250, 250]);
+
+        let result = remap_image(&img, &from_palette, &PaletteRGB::black_and_white(), RemapMetric::Index);
+        assert!(matches!(result, Err(PaletteError::LengthMismatch { from: 3, to: 2 })));
+    }
+
+    #[test]
+    fn test_dither_image_not_enough_colors() {
+        let img = RgbImage::from_pixel(4, 4, image::Rgb([10, 20, 30]));
+        let result = dither_image(img, DitherOptions::default().with_colors(4));
+        assert!(matches!(result, Err(PaletteError::NotEnoughColors(_))));
+    }
+
+    #[test]
+    fn test_output_fingerprint() {
+        let img = RgbImage::from_fn(6, 4, |x, y| image::Rgb([x as u8 * 40, y as u8 * 60, 90]));
+        let options = DitherOptions::default().with_colors(4);
+        let fingerprint = output_fingerprint(&img, &options);
+
+        // Measurement, threads and color names do not change the output
+        assert_eq!(output_fingerprint(&img, &options.clone().with_quality_metrics(true).with_parallelism(Parallelism::disabled())), fingerprint);
+        let mut named_palette = PaletteRGB::primary_bw();
+        let fixed_fingerprint = output_fingerprint(&img, &options.clone().with_palette(PaletteStrategy::Fixed(named_palette.clone())));
+        named_palette.set_color_name(ColorRGB([0, 0, 0]), "ink");
+        named_palette.pairwise_distances(crate::palette::DistanceMetric::default());
+        assert_eq!(output_fingerprint(&img, &options.clone().with_palette(PaletteStrategy::Fixed(named_palette))), fixed_fingerprint);
+
+        let mut changed_img = img.clone();
+        changed_img.put_pixel(5, 3, image::Rgb([0, 0, 0]));
+        let transposed_img = RgbImage::from_raw(4, 6, img.as_raw().clone()).unwrap();
+        let changed_fingerprints = [
+            output_fingerprint(&changed_img, &options),
+            output_fingerprint(&transposed_img, &options),
+            output_fingerprint(&img, &options.clone().with_colors(5)),
+            output_fingerprint(&img, &options.clone().with_palette(PaletteStrategy::Fixed(PaletteRGB::black_and_white()))),
+            output_fingerprint(&img, &options.clone().with_palette(PaletteStrategy::Fixed(PaletteRGB::primary_bw()))),
+            output_fingerprint(&img, &options.clone().with_mask(GrayImage::new(6, 4))),
+            output_fingerprint(&img, &options.clone().with_error_diffusion(ErrorDiffusion::default().with_tileable(true))),
+        ];
+        for (idx, changed_fingerprint) in changed_fingerprints.iter().enumerate() {
+            assert_ne!(*changed_fingerprint, fingerprint, "{idx}");
+            assert!(changed_fingerprints[idx + 1..].iter().all(|other| other != changed_fingerprint), "{idx}");
+        }
+    }
+
+    #[test]
+    fn test_dither_thumbnail() {
+        let thumbnail = "2:1".parse::<Thumbnail>().unwrap().with_pad_color(ColorRGB([250, 250, 250]));
+        let palette = PaletteRGB::from(vec![ColorRGB([0, 0, 0]), ColorRGB([255, 255, 255])]);
+        let options = DitherOptions::default()
+            .with_palette(PaletteStrategy::Fixed(palette))
+            .with_thumbnail(thumbnail);
+
+        // Larger image is cropped and downscaled to exact dimensions
+        let img = RgbImage::from_pixel(1500, 1500, image::Rgb([0, 0, 0]));
+        let output = dither_image(img, options.clone()).unwrap();
+        assert_eq!(output.image.dimensions(), (1200, 600));
+        assert!(output.image.pixels().all(|px| px.0 == [0, 0, 0]));
+
+        // Smaller one is cropped to aspect ratio and padded with the closest palette color
+        let img = RgbImage::from_pixel(100, 80, image::Rgb([0, 0, 0]));
+        let output = dither_image(img, options).unwrap();
+        assert_eq!(output.image.dimensions(), (1200, 600));
+        assert_eq!(output.image.get_pixel(0, 0).0, [255, 255, 255]);
+        assert_eq!(output.image.get_pixel(600, 300).0, [0, 0, 0]);
+        let dark_pixels = output.image.pixels().filter(|px| px.0 == [0, 0, 0]).count();
+        assert_eq!(dark_pixels, 100 * 50);
+        assert_eq!(output.palette.len(), 2);
+    }
+
+    #[test]
+    fn test_dither_brightness_budget() {
+        let img = crate::image::generate_test_gradient_image(32, 8, image::Rgb([128, 128, 128]), image::Rgb([255, 255, 255]));
+        let options = DitherOptions::default()
+            .with_palette(PaletteStrategy::Fixed(PaletteRGB::primary_bw()))
+            .with_algorithm(ProcessingAlgorithm::FloydSteinbergRgb)
+            .with_transparent_key(ColorRGB([255, 0, 255]));
+        let unconstrained = dither_image(img.clone(), options.clone()).unwrap();
+        assert!(mean_brightness(&unconstrained.image) > 60.0);
+
+        // Budget above brightness of the result keeps it unchanged
+        let output = dither_image(img.clone(), options.clone().with_brightness_budget(100.0)).unwrap();
+        assert_eq!(output.image, unconstrained.image);
+
+        let output = dither_image(img, options.clone().with_brightness_budget(30.0)).unwrap();
+        let brightness = mean_brightness(&output.image);
+        assert!(brightness <= 30.0 && brightness > 20.0, "brightness {brightness}");
+
+        // Key pixels are not darkened, only black fits the budget of other ones
+        let mut img = RgbImage::from_pixel(8, 8, image::Rgb([255, 255, 255]));
+        img.put_pixel(0, 0, image::Rgb([255, 0, 255]));
+        let output = dither_image(img, options.with_brightness_budget(0.0)).unwrap();
+        assert_eq!(output.image.get_pixel(0, 0).0, [255, 0, 255]);
+        assert!(output.image.pixels().skip(1).all(|px| px.0 == [0, 0, 0]));
+    }
+
+    #[test]
+    fn test_dither_supersample() {
+        let img = crate::image::generate_test_gradient_image(32, 8, image::Rgb([0, 0, 0]), image::Rgb([255, 255, 255]));
+        let options = DitherOptions::default()
+            .with_palette(PaletteStrategy::Fixed(PaletteRGB::primary_bw()))
+            .with_algorithm(ProcessingAlgorithm::FloydSteinbergRgb);
+
+        let plain_output = dither_image(img.clone(), options.clone()).unwrap();
+        let supersampled_output = dither_image(img, options.with_supersample(2)).unwrap();
+        assert_eq!(supersampled_output.image.dimensions(), (32, 8));
+        assert_eq!(supersampled_output.palette.to_vec(), plain_output.palette.to_vec());
+
+        // Every pixel is mean of 2x2 black and white pixels
+        let levels = supersampled_output.image.pixels().map(|px| px.0[0]).collect::<std::collections::BTreeSet<_>>();
+        assert!(levels.len() > 2, "{levels:?}");
+        assert!(levels.iter().all(|level| [0, 64, 128, 191, 255].contains(level)), "{levels:?}");
+    }
+
+    #[test]
+    fn test_dither_prune_palette() {
+        let img = RgbImage::from_fn(20, 10, |x, _| if x == 0 { image::Rgb([0, 0, 250]) } else { image::Rgb([250, 250, 250]) });
+        let options = DitherOptions::default()
+            .with_palette(PaletteStrategy::Fixed(PaletteRGB::primary_bw()))
+            .with_algorithm(ProcessingAlgorithm::ThresholdingLab);
+
+        let output = dither_image(img.clone(), options.clone().with_prune_palette(0.0)).unwrap();
+        let hexes = output.palette.iter().map(ColorRGB::to_hex).collect::<std::collections::BTreeSet<_>>();
+        assert_eq!(hexes, ["#0000ff", "#ffffff"].map(String::from).into());
+
+        // Blue covers 5% of pixels, replaced by the closest kept color
+        let output = dither_image(img, options.with_prune_palette(10.0)).unwrap();
+        assert_eq!(output.palette.iter().map(ColorRGB::to_hex).collect::<Vec<_>>(), vec!["#ffffff"]);
+        assert!(output.image.pixels().all(|px| px.0 == [255, 255, 255]));
+    }
+
+    #[test]
+    fn test_long_operations_are_send() {
+        // Options, results and errors cross threads of async services
+        fn assert_send<T: Send + Sync + 'static>() {}
+        assert_send::<DitherOptions>();
+        assert_send::<DitherOutput>();
+        assert_send::<ImageProcessor>();
+        assert_send::<PaletteRGB>();
+        assert_send::<PaletteError>();
+        assert_send::<DitherError>();
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_dither_async_matches_sync() {
+        fn assert_send_future<F: std::future::Future + Send>(future: F) -> F {
+            future
+        }
+
+        let img = crate::image::generate_test_gradient_image(24, 6, image::Rgb([0, 0, 0]), image::Rgb([255, 128, 0]));
+        let options = DitherOptions::default().with_palette(PaletteStrategy::Fixed(PaletteRGB::primary_bw()));
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+
+        let output = runtime.block_on(assert_send_future(dither_image_async(img.clone(), options.clone()))).unwrap();
+        assert_eq!(output.image, dither_image(img.clone(), options.clone()).unwrap().image);
+
+        let output = runtime.block_on(assert_send_future(dither_raw_async(img.as_raw().clone(), 24, 6, options.clone()))).unwrap();
+        assert_eq!(output.image, dither_image(img, options.clone()).unwrap().image);
+
+        let result = runtime.block_on(dither_raw_async(vec![0; 5], 24, 6, options));
+        assert!(matches!(result, Err(DitherError::InvalidBufferLength { length: 5, .. })));
+    }
+}
@@ -0,0 +1,281 @@
+//! # Ditherer
+//!
+//! HTTP service dithering uploaded images, enabled with the `server` feature.
+//!
+//! ```sh
+//! cargo run --release --features server --bin ditherer -- --bind 127.0.0.1:8080
+//! curl -F image=@photo.jpg -F colors=8 -F algorithm=bayer4 http://127.0.0.1:8080/dither -o dithered.png
+//! curl -F image=@photo.jpg -F colors=8 http://127.0.0.1:8080/palette
+//! ```
+//!
+//! ## Endpoints:
+//! - `POST /dither`: Multipart form with `image` file, responds with dithered PNG image. Optional fields:
+//!   - `algorithm`: Processing algorithm, see `ditherum algorithms`. Defaults to `fs-rgb`.
+//!   - `colors`: Number of colors extracted from the image. Defaults to 8.
+//!   - `palette`: Palette JSON used instead of extracted colors, the same as `ditherum dither --palette` file.
+//!   - `width`, `height`: Output dimensions, aspect ratio is kept if only one is given.
+//! - `POST /palette`: Multipart form with `image` file and optional `colors`, responds with palette JSON.
+//!
+//! Requests with body exceeding `--max-upload` bytes are rejected with `413 Payload Too Large`.
+//! Uploaded image is spooled to a temporary file removed as soon as the request completes.
+//! Errors are returned as JSON `{"error": "..."}` with status code matching the cause.
+
+use std::{collections::HashMap, fs::File, io::{BufReader, Write}, net::SocketAddr, path::Path, str::FromStr};
+
+use axum::{
+    extract::{multipart::MultipartError, DefaultBodyLimit, Multipart},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::post,
+    Json,
+    Router
+};
+use clap::Parser;
+use ditherum::{
+    api::PaletteStrategy,
+    config::ResizeConfig,
+    image::ProcessingAlgorithm,
+    palette::{errors::PaletteError, PaletteRGB},
+    DitherOptions
+};
+use serde::Serialize;
+use tempfile::NamedTempFile;
+
+/// Default limit of request body size, 16 MiB.
+const DEFAULT_MAX_UPLOAD: usize = 16 * 1024 * 1024;
+
+/// Largest output width or height, keeps requested resizing from exhausting memory.
+const MAX_OUTPUT_DIMENSION: u32 = 8192;
+
+/// Default number of colors extracted from uploaded image.
+const DEFAULT_COLORS: usize = 8;
+
+/// Name of multipart field carrying the image file.
+const IMAGE_FIELD: &str = "image";
+
+/// Command-line arguments of the service.
+///
+/// - `--bind`: Address to listen on, port `0` picks a free one. Defaults to `127.0.0.1:8080`.
+/// - `--max-upload`: Limit of request body size in bytes. Defaults to 16 MiB.
+#[derive(Debug, Parser)]
+#[command(version, about = "HTTP service dithering uploaded images")]
+struct Args {
+    /// Address to listen on, port 0 picks a free one
+    #[arg(short, long, value_name = "ADDRESS", default_value = "127.0.0.1:8080")]
+    bind: SocketAddr,
+
+    /// Limit of request body size in bytes, larger uploads are rejected with 413
+    #[arg(long = "max-upload", value_name = "BYTES", default_value_t = DEFAULT_MAX_UPLOAD)]
+    max_upload: usize,
+}
+
+/// Error of request handling, converted to JSON response with matching status code.
+#[derive(Debug, thiserror::Error)]
+enum ServerError {
+    #[error("{0}")]
+    BadRequest(String),
+
+    #[error("Request body exceeds upload limit")]
+    PayloadTooLarge,
+
+    #[error("Image error, reason={0}")]
+    ImageError(#[from] image::ImageError),
+
+    #[error("Palette error, reason={0}")]
+    PaletteError(#[from] PaletteError),
+
+    #[error("Temporary file error, reason={0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Processing task failed, reason={0}")]
+    TaskFailed(#[from] tokio::task::JoinError),
+}
+
+impl ServerError {
+    fn status(&self) -> StatusCode {
+        match self {
+            ServerError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ServerError::PayloadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            ServerError::ImageError(error) => match error {
+                image::ImageError::Unsupported(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                image::ImageError::Decoding(_) => StatusCode::BAD_REQUEST,
+                image::ImageError::Limits(_) => StatusCode::PAYLOAD_TOO_LARGE,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            },
+            ServerError::PaletteError(error) => match error {
+                PaletteError::NotEnoughColors(_) | PaletteError::PaletteEmpty | PaletteError::TooManyFixedColors { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+                PaletteError::JsonParsingFailed(_) | PaletteError::InvalidPaletteDocument(_) | PaletteError::UnsupportedPaletteVersion { .. } => StatusCode::BAD_REQUEST,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            },
+            ServerError::IoError(_) | ServerError::TaskFailed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl From<MultipartError> for ServerError {
+    fn from(value: MultipartError) -> Self {
+        match value.status() {
+            StatusCode::PAYLOAD_TOO_LARGE => ServerError::PayloadTooLarge,
+            _ => ServerError::BadRequest(value.body_text()),
+        }
+    }
+}
+
+/// Body of error response.
+#[derive(Debug, Serialize)]
+struct ErrorBody {
+    error: String,
+}
+
+impl IntoResponse for ServerError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        if status.is_server_error() {
+            tracing::error!(%status, "{}", self);
+        }
+        (status, Json(ErrorBody { error: self.to_string() })).into_response()
+    }
+}
+
+/// Multipart form with image spooled to temporary file and remaining text fields.
+struct Upload {
+    image_file: NamedTempFile,
+    fields: HashMap<String, String>,
+}
+
+impl Upload {
+    /// Receives the whole form, the image file is written to temporary file chunk by chunk.
+    async fn receive(mut multipart: Multipart) -> Result<Self, ServerError> {
+        let mut image_file = None;
+        let mut fields = HashMap::new();
+
+        while let Some(mut field) = multipart.next_field().await? {
+            let name = field.name().unwrap_or_default().to_string();
+            if name == IMAGE_FIELD {
+                let mut file = NamedTempFile::new()?;
+                while let Some(chunk) = field.chunk().await? {
+                    file.write_all(&chunk)?;
+                }
+                file.flush()?;
+                image_file = Some(file);
+            } else {
+                let value = field.text().await?;
+                fields.insert(name, value);
+            }
+        }
+
+        let image_file = image_file.ok_or_else(|| ServerError::BadRequest(format!("Missing '{IMAGE_FIELD}' file field")))?;
+        Ok(Self { image_file, fields })
+    }
+
+    /// Rejects fields other than `allowed` ones, so that typos do not pass silently.
+    fn check_fields(&self, allowed: &[&str]) -> Result<(), ServerError> {
+        match self.fields.keys().find(|name| !allowed.contains(&name.as_str())) {
+            Some(name) => Err(ServerError::BadRequest(format!("Unknown field '{name}', expected one of {allowed:?}"))),
+            None => Ok(()),
+        }
+    }
+
+    /// Parses optional text field.
+    fn field<T>(&self, name: &str) -> Result<Option<T>, ServerError>
+    where
+        T: FromStr,
+        T::Err: std::fmt::Display
+    {
+        self.fields.get(name)
+            .map(|value| value.trim().parse::<T>()
+                .map_err(|e| ServerError::BadRequest(format!("Invalid field '{name}'={value:?}, reason={e}")))
+            )
+            .transpose()
+    }
+}
+
+/// Loads uploaded image, format is guessed from content as file name of upload is arbitrary.
+fn load_upload_image(path: &Path) -> Result<image::RgbImage, ServerError> {
+    let file = File::open(path)?;
+    Ok(ditherum::image::load_image_from_reader(BufReader::new(file))?)
+}
+
+/// Builds [`DitherOptions`] from form fields of `/dither` request.
+fn dither_options(upload: &Upload) -> Result<DitherOptions, ServerError> {
+    upload.check_fields(&["algorithm", "colors", "palette", "width", "height"])?;
+
+    let mut options = DitherOptions::default()
+        .with_algorithm(upload.field::<ProcessingAlgorithm>("algorithm")?.unwrap_or_default());
+
+    options = match (upload.fields.get("palette"), upload.field::<usize>("colors")?) {
+        (Some(_), Some(_)) => return Err(ServerError::BadRequest("Fields 'palette' and 'colors' conflict".to_string())),
+        (Some(palette_json), None) => options.with_palette(PaletteStrategy::Fixed(PaletteRGB::load_from_json_reader(palette_json.as_bytes())?)),
+        (None, colors) => options.with_colors(colors.unwrap_or(DEFAULT_COLORS)),
+    };
+
+    let resize = ResizeConfig {
+        width: upload.field::<u32>("width")?,
+        height: upload.field::<u32>("height")?,
+        ..Default::default()
+    };
+    if let Some(dimension) = [resize.width, resize.height].into_iter().flatten().find(|&dimension| dimension == 0 || dimension > MAX_OUTPUT_DIMENSION) {
+        return Err(ServerError::BadRequest(format!("Output dimension {dimension} out of range 1-{MAX_OUTPUT_DIMENSION}")));
+    }
+    Ok(options.with_resize(resize))
+}
+
+/// Handles `POST /dither`, responds with dithered PNG image.
+async fn dither(multipart: Multipart) -> Result<Response, ServerError> {
+    let upload = Upload::receive(multipart).await?;
+    let options = dither_options(&upload)?;
+
+    let encoded = tokio::task::spawn_blocking(move || -> Result<Vec<u8>, ServerError> {
+        let source_image = load_upload_image(upload.image_file.path())?;
+        let output = ditherum::dither_image(source_image, options)?;
+        let mut encoded = Vec::new();
+        ditherum::image::save_image_to_writer(&mut encoded, &output.image, image::ImageFormat::Png)?;
+        Ok(encoded)
+    }).await??;
+
+    Ok(([(header::CONTENT_TYPE, "image/png")], encoded).into_response())
+}
+
+/// Handles `POST /palette`, responds with palette JSON.
+async fn palette(multipart: Multipart) -> Result<Response, ServerError> {
+    let upload = Upload::receive(multipart).await?;
+    upload.check_fields(&["colors"])?;
+    let colors = upload.field::<usize>("colors")?.unwrap_or(DEFAULT_COLORS);
+
+    let encoded = tokio::task::spawn_blocking(move || -> Result<Vec<u8>, ServerError> {
+        let source_image = load_upload_image(upload.image_file.path())?;
+        let palette = PaletteRGB::from_rgbu8_image(&source_image).try_reduce(colors)?;
+        let mut encoded = Vec::new();
+        palette.save_to_json_writer(&mut encoded)?;
+        Ok(encoded)
+    }).await??;
+
+    Ok(([(header::CONTENT_TYPE, "application/json")], encoded).into_response())
+}
+
+/// Routes of the service with body size limit.
+fn router(max_upload: usize) -> Router {
+    Router::new()
+        .route("/dither", post(dither))
+        .route("/palette", post(palette))
+        .layer(DefaultBodyLimit::max(max_upload))
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .with_writer(std::io::stderr)
+        .init();
+
+    let listener = tokio::net::TcpListener::bind(args.bind).await?;
+    println!("Listening on http://{}", listener.local_addr()?);
+
+    axum::serve(listener, router(args.max_upload))
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await?;
+    Ok(())
+}
@@ -4,6 +4,13 @@
 //! - `dither`: Reduces the number of colors in an image using dithering techniques.
 //! - `palette`: Extracts a color palette from an image.
 //! 
+//! Additionally `algorithms` lists available processing algorithms, `compare` runs
+//! multiple algorithms or palettes on the same image, `info` reports image statistics,
+//! `preview` renders dithered image in the terminal, `run` executes pipeline declared
+//! in a TOML or JSON config file, `artifact` renders chart for cross-stitch or mosaics, `remap`
+//! recolors dithered image from one palette to another and `verify` checks image uses only palette colors.
+//! With `video` feature `video` dithers video through ffmpeg.
+//! 
 //! ## Features
 //! - Reduce colors using a fixed count or a custom palette.
 //! - Extract color palettes with optional reduction.
@@ -14,25 +21,165 @@
 //! # Dithering with color reduction to 16 colors
 //! ditherum dither -i input.png -c 16 -o output.png
 //! 
+//! # Dithering pixel art resized without blurring
+//! ditherum dither -i sprite.png -W 128 -H 128 --filter nearest --resize-mode fit -o output.png
+//! 
+//! # Dithering to 4 colors with contrast bump beforehand
+//! ditherum dither -i input.png -c 4 --contrast 1.3 --gamma 1.1 -o output.png
+//! 
+//! # Downscaling with mild sharpening beforehand
+//! ditherum dither -i input.png -W 160 --sharpen 0.8 --sharpen-radius 1.5 -o output.png
+//! 
+//! # Random dithering: thresholding of image with triangular noise added
+//! ditherum dither -i input.png -a threshold-rgb --noise 0.15 --noise-seed 7 -o output.png
+//! 
+//! # Dithering only where mask is white, black areas stay smooth
+//! ditherum dither -i portrait.png -c 8 --mask face_mask.png -o output.png
+//! 
+//! # Dithering sprite, magenta background stays untouched
+//! ditherum dither -i sprite.png -p console.json --transparent-key "#ff00ff" -o output.png
+//! 
+//! # Dithering with at most 4 palette colors within each 8x8 tile, like attribute cells of retro consoles
+//! ditherum dither -i input.png -p console.json --tiles 8x8:4 -a bayer4 -o output.png
+//! 
+//! # Dithering for ZX Spectrum screen, 2 colors per 8x8 cell, both normal or both bright
+//! ditherum dither -i input.png -W 256 -H 192 --hardware zx -o output.png
+//! 
+//! # Dithering for RGB565 framebuffer, each channel reduced to its levels, no palette needed
+//! ditherum dither -i input.png --levels rgb565 -o output.png
+//! 
+//! # Dithering for 4 gray e-paper panel, palette matched by colors the panel actually shows
+//! ditherum dither -i input.png -p epaper_gray4.json --display-response epaper -o output.png
+//! 
+//! # Dithering for LED wall drawing at most 40% of full white power
+//! ditherum dither -i input.png -p matrix.json --brightness-budget 40 -o output.png
+//! 
 //! # Dithering using a predefined palette
 //! ditherum dither -i input.png -p palette.json -o output.png
+//!
+//! # Dithering using palette downloaded from Lospec as PNG strip
+//! ditherum dither -i input.png -p pico-8-1x.png -o output.png
+//! 
+//! # Dithering once, writing image, C header, palette swatch and JSON report
+//! ditherum dither -i sprite.png -c 16 -o out.png --export-c out.h --swatch-out palette.png --report report.json
+//! 
+//! # Dithering using ordered 8x8 Bayer matrix, see `ditherum algorithms` for all options
+//! ditherum dither -i input.png -a bayer8 -o output.png
+//! 
+//! # Dithering every matched image into output directory using 4 threads
+//! ditherum dither -i "photos/*.jpg" -o out_dir/ -j 4
+//! 
+//! # Re-dithering exported sprites whenever they change, stop with Ctrl+C
+//! ditherum dither -i sprites/ -p console.json -o out_dir/ --watch
+//! 
+//! # Image statistics with recommended palette size, helps choosing -c
+//! ditherum info -i input.png
+//! 
+//! # Dithered image rendered in the terminal, 100 columns wide
+//! ditherum preview -i input.png -p palette.json -W 100
+//! 
+//! # Cross-stitch chart 80 stitches wide with thread numbers, grid CSV and thread counts
+//! ditherum artifact -i photo.jpg -W 80 -p threads.json -o chart.png --csv chart.csv --counts counts.csv
+//! 
+//! # Sprite recolored to new revision of the palette, colors swapped by their position
+//! ditherum remap -i sprite.png --from palette_v1.json --to palette_v2.json --metric index -o sprite_v2.png
+//! 
+//! # Asset CI gate, exits with code 6 and lists offending colors if image has colors outside the palette
+//! ditherum verify -i sprite.png -p console.json
+//! 
+//! # Pipeline declared in config file, see `ditherum::config::ProcessingConfig` for schema
+//! ditherum run pipeline.toml
+//! 
+//! # Video dithered with palette of the first frame, static areas do not shimmer (`video` feature)
+//! ditherum video -i clip.mp4 -W 160 -c 16 --stability 6 -o clip.gif
 //! 
 //! # Extracting a palette from an image
 //! ditherum palette -i input.png -c 8 -o palette.json
 //! 
+//! # Extracting a palette which always contains pure black and white
+//! ditherum palette -i input.png -c 8 --keep "#000000,#ffffff" -o palette.json
+//! 
+//! # Extracting a palette whose colors differ by at least 10 CIEDE2000
+//! ditherum palette -i input.png -c 16 --min-delta-e 10 -o palette.json
+//! 
+//! # Dithering with the smallest palette whose thresholded image has mean CIEDE2000 error up to 4
+//! ditherum dither -i input.png --auto-colors --max-error 4.0 -o output.png
+//! 
+//! # Extracting a palette from a huge photo using 5-bit per channel histogram instead of every pixel
+//! ditherum palette -i photo.jpg --sampling histogram -c 16 -o palette.json
+//! 
+//! # Extracting a palette from the product only, ignoring white background around it
+//! ditherum palette -i product.jpg --roi 120,80,400,300 -c 8 -o palette.json
+//! 
+//! # Extracting a palette from a JPEG photo, colors covering less than 0.1% of pixels are dropped
+//! ditherum palette -i photo.jpg --min-coverage 0.1% -c 16 -o palette.json
+//! 
+//! # Dithering subject shot on green screen, background shades are left out of the palette
+//! ditherum dither -i greenscreen.png --ignore "#00ff00:10" -c 8 -o output.png
+//! 
+//! # Comparing algorithms on two palettes, writes comparison.png contact sheet and comparison.json report
+//! ditherum compare -i input.png -a fs-rgb,fs-lab,bayer4 -p warm.json,cold.json -o comparison.png
+//! 
+//! # Merging palettes, colors closer than 2.5 CIEDE2000 difference are treated as duplicates
+//! ditherum palette -i base.json --merge extra.json,brand.json --tolerance 2.5 -o merged.json
+//! 
 //! # Verbose output
 //! ditherum -v palette -i input.png
+//! 
+//! # Pipeline usage, '-' stands for standard input/output
+//! curl -s https://example.com/image.jpg | ditherum dither -i - -c 8 -o - > output.png
+//! 
+//! # Machine-readable error written to stderr
+//! ditherum --error-format json dither -i missing.png
+//! 
+//! # Timing of palette extraction, k-means iterations and dithered rows as JSON lines
+//! ditherum --trace-json trace.jsonl dither -i photo.jpg -c 16 -o out.png
 //! ```
+//! 
+//! ## Exit Codes
+//! - `0`: Success.
+//! - `1`: Processing failure.
+//! - `2`: Invalid command line arguments.
+//! - `3`: Bad input file, e.g. missing, unreadable or corrupted.
+//! - `4`: Unsupported file format.
+//! - `5`: Palette too small, e.g. fewer colors than requested.
+//! - `6`: Image contains colors outside the palette, reported by `verify`.
 
-use std::{path::PathBuf, time::{SystemTime, UNIX_EPOCH}};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap}, 
+    io::Write, 
+    num::NonZeroUsize, 
+    path::{Path, PathBuf}, 
+    sync::atomic::{AtomicUsize, Ordering}, 
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH}
+};
 
-use anyhow::{Context, Ok};
+use anyhow::{bail, Context, Ok};
 use clap::{Parser, Subcommand, Args};
-use ditherum::{image::ImageProcessor, palette::PaletteRGB};
+use clap::ValueEnum;
+use ditherum::{
+    algorithms::{dithering::{ChannelDecay, ErrorDiffusion, DEFAULT_ERROR_OVERSHOOT}, hardware::HardwareProfile, kmean::Parallelism, noise::{Noise, NoiseDistribution}, outline::Outline, posterize::ChannelLevels, preprocessing::Preprocessing, response::{DisplayLutParseError, DisplayResponse}, tiles::TileConstraint},
+    api::{output_fingerprint, output_fingerprint_f32, remap_image, DitherStats, PaletteStrategy, RemapMetric},
+    color::ColorRGB,
+    config::{errors::ConfigError, OutputFields, OutputTemplate, PaletteSource, ProcessingConfig, ResizeConfig, Thumbnail},
+    image::{ImageProcessor, LoadedImage, ProcessingAlgorithm, ResizeFilter, ResizeMode}, 
+    metadata::ImageMetadata, 
+    metrics::{foreign_colors, ImageQuality, PaletteUsage}, 
+    palette::{errors::PaletteError, ImageRegion, MinCoverage, PaletteExtractOptions, PaletteRGB, PaletteSampling, ReduceStrategy}, 
+    text,
+    DitherOptions,
+    DitherOutput
+};
+use serde::Serialize;
+
+/// Path standing for standard input or standard output.
+const STDIO_PATH: &str = "-";
 
 /// Macro for verbose output.
 /// 
-/// Prints the message only if `verbose` is `true`.
+/// Prints the message only if `verbose` is `true`. Messages go to standard error,
+/// so that standard output stays clean when it is used for `-o -`.
 /// 
 /// # Examples
 /// ```rust
@@ -42,7 +189,7 @@ use ditherum::{image::ImageProcessor, palette::PaletteRGB};
 macro_rules! vprintln {
     ($verbose:expr, $($arg:tt)*) => {
         if $verbose {
-            println!($($arg)*);
+            eprintln!($($arg)*);
         }
     };
 }
@@ -62,208 +209,2590 @@ struct Cli {
 
     /// Additional information about execution process (optional)
     #[arg(short = 'v', long = "verbose", value_name = "VERBOSE_ENABLED", default_value_t = false)]
-    verbose: bool  
+    verbose: bool,
+
+    /// Format of error written to stderr (optional)
+    #[arg(long = "error-format", value_name = "FORMAT", value_enum, default_value_t = ErrorFormat::Text)]
+    error_format: ErrorFormat,
+
+    /// Path to save JSON lines with timing of every closed tracing span, for profiling (optional)
+    #[arg(long = "trace-json", value_name = "TRACE_PATH")]
+    trace_json_path: Option<PathBuf>,
+}
+
+/// Format of error written to stderr.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ErrorFormat {
+    /// Human readable message
+    Text,
+
+    /// Single line JSON object with error kind, exit code and message
+    Json,
+}
+
+/// Category of failure, each one exits with distinct process exit code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum ErrorKind {
+    ProcessingFailure,
+    BadInput,
+    UnsupportedFormat,
+    PaletteTooSmall,
+    ColorsOutsidePalette,
+}
+
+impl ErrorKind {
+    /// Process exit code, `2` is taken by `clap` for invalid arguments.
+    fn exit_code(&self) -> i32 {
+        match self {
+            ErrorKind::ProcessingFailure => 1,
+            ErrorKind::BadInput => 3,
+            ErrorKind::UnsupportedFormat => 4,
+            ErrorKind::PaletteTooSmall => 5,
+            ErrorKind::ColorsOutsidePalette => 6,
+        }
+    }
+
+    /// Classifies error by the first recognized cause in its chain.
+    fn of(error: &anyhow::Error) -> Self {
+        error.chain()
+            .find_map(|cause| {
+                #[cfg(feature = "video")]
+                if let Some(error) = cause.downcast_ref::<ditherum::video::errors::VideoError>() {
+                    use ditherum::video::errors::VideoError;
+                    return Some(match error {
+                        VideoError::IoError(_) | VideoError::InvalidProbeOutput(_) => ErrorKind::BadInput,
+                        _ => ErrorKind::ProcessingFailure,
+                    });
+                }
+
+                if let Some(error) = cause.downcast_ref::<CliError>() {
+                    Some(error.kind)
+                } else if let Some(error) = cause.downcast_ref::<PaletteError>() {
+                    Some(match error {
+                        PaletteError::NotEnoughColors(_) | PaletteError::PaletteEmpty | PaletteError::TooManyFixedColors { .. } => ErrorKind::PaletteTooSmall,
+                        PaletteError::IoError(_) | PaletteError::JsonParsingFailed(_) | PaletteError::RegionOutsideImage { .. } | PaletteError::InvalidPaletteDocument(_)
+                            | PaletteError::NotPaletteImage { .. } | PaletteError::ImageLoadingFailed(_) | PaletteError::LengthMismatch { .. } => ErrorKind::BadInput,
+                        PaletteError::UnsupportedPaletteVersion { .. } => ErrorKind::UnsupportedFormat,
+                        _ => ErrorKind::ProcessingFailure,
+                    })
+                } else if let Some(error) = cause.downcast_ref::<image::ImageError>() {
+                    Some(match error {
+                        image::ImageError::Unsupported(_) => ErrorKind::UnsupportedFormat,
+                        image::ImageError::Decoding(_) | image::ImageError::IoError(_) => ErrorKind::BadInput,
+                        _ => ErrorKind::ProcessingFailure,
+                    })
+                } else if let Some(error) = cause.downcast_ref::<ConfigError>() {
+                    Some(match error {
+                        ConfigError::UnsupportedFormat(_) => ErrorKind::UnsupportedFormat,
+                        ConfigError::TomlSerializationFailed(_) => ErrorKind::ProcessingFailure,
+                        _ => ErrorKind::BadInput,
+                    })
+                } else if cause.is::<std::io::Error>() || cause.is::<serde_json::Error>() || cause.is::<DisplayLutParseError>() {
+                    Some(ErrorKind::BadInput)
+                } else {
+                    None
+                }
+            })
+            .unwrap_or(ErrorKind::ProcessingFailure)
+    }
+}
+
+/// Error with explicitly assigned category, for failures detected by the CLI itself.
+#[derive(Debug, thiserror::Error)]
+#[error("{message}")]
+struct CliError {
+    kind: ErrorKind,
+    message: String,
+}
+
+impl CliError {
+    fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Self { kind, message: message.into() }
+    }
+}
+
+/// Error written to stderr with `--error-format json`.
+#[derive(Debug, Serialize)]
+struct ErrorReport {
+    kind: ErrorKind,
+    exit_code: i32,
+    message: String,
 }
 
 /// Subcommands for selecting the operation mode.
 /// 
 /// - `Dither`: Image dithering and color reduction.
 /// - `Palette`: Color palette extraction.
+/// - `Algorithms`: Listing of processing algorithms.
+/// - `Compare`: Comparison of algorithms and palettes.
+/// - `Info`: Image statistics.
+/// - `Preview`: Dithered image rendered in the terminal.
+/// - `Run`: Pipeline declared in config file.
+/// - `Remap`: Recoloring of dithered image from one palette to another.
+/// - `Verify`: Check that image uses only palette colors.
 #[derive(Debug, Subcommand)]
 enum Mode {
     /// Dither mode for image processing
-    Dither(DitherModeArgs),
+    Dither(Box<DitherModeArgs>),
 
     /// Palette mode for color extraction
     Palette(PaletteModeArgs),  
+
+    /// List available processing algorithms
+    Algorithms,
+
+    /// Compare algorithms and palettes on the same image
+    Compare(CompareModeArgs),
+
+    /// Report image statistics and recommended palette size
+    Info(InfoModeArgs),
+
+    /// Render dithered image in the terminal
+    Preview(PreviewModeArgs),
+
+    /// Run pipeline declared in TOML or JSON config file
+    Run(RunModeArgs),
+
+    /// Chart for cross-stitch, perler beads or brick mosaics
+    Artifact(ArtifactModeArgs),
+
+    /// Recolor indexed or dithered image from one palette to another without dithering again
+    Remap(RemapModeArgs),
+
+    /// Check that image contains only palette colors, lists offending ones otherwise
+    Verify(VerifyModeArgs),
+
+    /// Dither video frames with a fixed palette using ffmpeg
+    #[cfg(feature = "video")]
+    Video(VideoModeArgs),
 }
 
 /// Arguments for `dither` mode.
 /// 
 /// # Required Arguments
-/// - `-i`, `--input`: Path to the input image file.
+/// - `-i`, `--input`: Path to the input image file, directory or glob pattern (batch mode), `-` for stdin.
 /// 
 /// # Optional Arguments
-/// - `-W`, `--output`: Optional width for resizing.
-/// - `-H`, `--width`: Optional height for resizing.
-/// - `-o`, `--height`: Path for the output image. Defaults to an auto-generated name.
+/// - `-W`, `--width`: Optional width for resizing.
+/// - `-H`, `--height`: Optional height for resizing.
+/// - `--filter`: Resampling filter used for resizing. Defaults to `lanczos3`.
+/// - `--resize-mode`: How image is fitted into both width and height. Defaults to `fill`.
+/// - `--brightness`, `--contrast`, `--saturation`, `--gamma`: Adjustments applied before quantization.
+/// - `--sharpen`, `--sharpen-radius`: Unsharp mask amount and radius applied before quantization.
+/// - `--chroma-subsample`: Keep luma of every pixel but share chroma within 2x2 blocks, for LED matrices.
+/// - `--noise`, `--noise-distribution`, `--noise-seed`: Random noise added right before quantization.
+/// - `--mask`: Grayscale image modulating dithering strength per pixel.
+/// - `--transparent-key`: Color of pixels bypassing dithering, reserved if it is a palette entry.
+/// - `--tiles`: Tile size and colors allowed within each tile like `8x8:4`, emulating attribute clash of retro hardware.
+/// - `--hardware`: Retro machine whose palette and color constraints are enforced: `c64` or `zx`. Conflicts with `--palette` and `--tiles`.
+/// - `--levels`: Levels of each channel instead of palette, like `4`, `8,8,4` or `rgb565`. Conflicts with `--palette`, `--tiles` and `--hardware`.
+/// - `--luminance-band`: Match palette colors by lightness first, colors within given Lab ΔL compete by chroma. Applies to thresholding and Floyd-Steinberg.
+/// - `--display-response`: Response of target display: preset `led`, `epaper`, `mac` or display gamma like `2.4`. Palette colors are matched by colors the display shows.
+/// - `--display-lut`: Path to measured display response, 256 lines of perceived value per driven value, for all channels or `R,G,B`. Conflicts with `--display-response`.
+/// - `--brightness-budget`: Maximal mean brightness of the result in percent of full drive, darker palette colors are picked until it fits. For LED walls with limited power.
+/// - `--error-overshoot`: Fraction of channel range error accumulated by Floyd-Steinberg may exceed it by. Defaults to 0.1.
+/// - `--no-error-clamp`: Let Floyd-Steinberg accumulate error without limits. Conflicts with `--error-overshoot`.
+/// - `--error-decay`: Multipliers of error spread by Floyd-Steinberg, like `0.9` or per channel `1,0.8,0.8`. Defaults to 1.
+/// - `--hybrid`: Dither only photographic regions, flat and line art ones get plain thresholding. Optional sensitivity 0-1 defaults to 0.5.
+/// - `--edge-attenuation`: How much Sobel edges block Floyd-Steinberg error spread, 0-1. Keeps text and line art crisp.
+/// - `--tileable`: Floyd-Steinberg error wraps around image edges, so that output tiles seamlessly.
+/// - `--despeckle`: Single color islands with fewer pixels are replaced by the dominant neighbor color.
+/// - `--outline`: One pixel contour `COLOR` between all palette regions or `COLOR:AROUND` around regions of one color only.
+/// - `--pixelate`: Block size of chunky pixel art output, blocks are quantized without dithering. Conflicts with `--algorithm`, `--mask` and `--hybrid`.
+/// - `--supersample`: Factor of resolution dithering runs at before box filter downscaling, smooths gradients of tiny palettes. Conflicts with `--pixelate`, `--export-c` and `--export-svg`.
+/// - `--thumbnail`: Exact output shape like `1:1`, `16:9` or `1200x630`, image is center-cropped and downscaled. Conflicts with `--width`, `--height` and `--resize-mode`.
+/// - `--pad-color`: Color padding images smaller than thumbnail, replaced by the closest palette color. Defaults to black.
+/// - `-o`, `--output`: Path for the output image (output directory in batch mode), `-` for PNG to stdout. Defaults to an auto-generated name.
+///   Template like `out/{stem}_{algo}_{colors}.png` names every output, also in batch mode, fields: `stem`, `ext`, `algo`, `colors`, `width`, `height`.
 /// - `-c`, `--colors`: Number of colors to reduce the image to. Conflicts with `--palette`.
-/// - `-p`, `--palette`: Path to the custom palette file for dithering. Conflicts with `--colors`.
+/// - `--sampling`: Pixels used for palette extraction: `all`, `every:N`, `random:N[:SEED]` or `histogram`. Defaults to `all`.
+/// - `--roi`: Region `x,y,w,h` of resized image the palette is extracted from. Conflicts with `--palette`.
+/// - `--ignore`: Hex colors with optional CIEDE2000 tolerance, e.g. `#00ff00:10`, left out of extracted palette. Conflicts with `--palette`.
+/// - `--min-coverage`: Pixels count like `20` or percentage like `0.5%` a color has to cover to be extracted. Conflicts with `--palette`.
+/// - `--keep`: Comma separated hex colors guaranteed to survive reduction. Conflicts with `--palette`.
+/// - `--min-delta-e`: Minimal CIEDE2000 difference between reduced colors. Conflicts with `--palette`.
+/// - `--reduce-strategy`: Clustering backend of palette reduction: `kmeans` or deterministic `agglomerative`. Conflicts with `--palette`.
+/// - `--auto-colors`: Choose the smallest colors count meeting `--max-error`. Conflicts with `--palette`, `--shared-palette`, `--keep` and `--min-delta-e`.
+/// - `--max-error`: Maximal mean CIEDE2000 error of thresholded image used by `--auto-colors`. Defaults to 4.0.
+/// - `-p`, `--palette`: Path to the custom palette file for dithering, JSON or image like Lospec PNG strip. Conflicts with `--colors`.
 /// - `-r`, `--reduced`: Path to save the reduced palette. Requires `--colors`.
+/// - `--prune-palette`: Drop palette colors covering less than given percentage of output pixels, only unused ones without value.
+/// - `--export-c`: Path for C header with palette and image as palette indices. Single image only.
+/// - `--export-svg`: Path for SVG with palette color regions traced as paths, for laser cutters and plotters. Single image only.
+/// - `--swatch-out`: Path for image with palette swatches. Single image only.
+/// - `--report`: Path for JSON report with palette, timing and quality metrics. Single image only.
+/// - `--usage-report`: Path for JSON with number and percentage of pixels per palette color. Single image only.
+/// - `-a`, `--algorithm`: Processing algorithm, see `ditherum algorithms`. Defaults to `fs-rgb`.
+/// - `-j`, `--jobs`: Number of images processed in parallel in batch mode.
+/// - `--threads`: Maximal number of threads of palette reduction clustering, 1 disables multithreading. Defaults to all logical cores.
+/// - `--shared-palette`: Reduce one palette from all batch images and use it for each of them.
+/// - `--watch`: Keep running and reprocess input whenever it changes. Not supported with stdin.
+/// - `--watch-interval`: Milliseconds between input checks in watch mode. Defaults to 500.
+/// - `--dry-run`: Validate inputs and print planned pipeline with estimated memory, nothing is written.
+/// - `--keep-metadata`: Copy ICC profile, EXIF and text chunks of input image to PNG and JPEG outputs.
+/// - `--no-clobber`: Keep existing output images, batch mode skips them. Conflicts with `--watch`.
+/// - `--force`: Overwrite existing output images, default, overrides `--no-clobber` given earlier.
+/// - `--cache-dir`: Directory of results reused by batch runs when neither input image nor options changed.
 #[derive(Debug, Args)]
 struct DitherModeArgs {
-    /// Input image file path (required)
+    /// Input image file path, directory, quoted glob pattern like "photos/*.jpg" or '-' for stdin (required)
     #[arg(short = 'i', long = "input", value_name = "INPUT_PATH", required = true)]
     input_path: PathBuf,
 
-    /// Desired output image width
-    #[arg(short = 'W', long = "width", value_name = "DESIRED_WIDTH")]
-    width: Option<u32>,
+    #[command(flatten)]
+    resize: ResizeArgs,
 
-    /// Desired output image height
-    #[arg(short = 'H', long = "height", value_name = "DESIRED_HEIGHT")]
-    height: Option<u32>,
+    #[command(flatten)]
+    preprocessing: PreprocessingArgs,
+
+    #[command(flatten)]
+    noise: NoiseArgs,
+
+    /// Grayscale mask image, white areas get full dithering and black ones plain thresholding (optional)
+    #[arg(long = "mask", value_name = "MASK_PATH")]
+    mask_path: Option<PathBuf>,
+
+    /// Transparent key color like "#ff00ff" or "magenta", matching pixels bypass dithering and stay unchanged (optional)
+    #[arg(long = "transparent-key", value_name = "COLOR")]
+    transparent_key: Option<ColorRGB>,
+
+    /// Tile size and colors allowed within each tile like "8x8:4", emulating attribute clash of retro hardware (optional)
+    #[arg(long = "tiles", value_name = "WxH:COLORS")]
+    tile_constraint: Option<TileConstraint>,
+
+    /// Retro machine whose palette and color constraints are enforced (optional, conflicts with --palette and --tiles)
+    #[arg(long = "hardware", value_name = "MACHINE", value_enum, conflicts_with_all = ["palette_path", "tile_constraint"])]
+    hardware: Option<HardwareProfile>,
+
+    /// Levels of each channel used instead of palette like "4", "8,8,4" or "rgb565", for fixed-format framebuffers (optional, conflicts with --palette, --tiles and --hardware)
+    #[arg(long = "levels", value_name = "LEVELS", conflicts_with_all = ["palette_path", "tile_constraint", "hardware"])]
+    levels: Option<ChannelLevels>,
+
+    /// Match palette colors by lightness first, colors within this Lab lightness difference compete by chroma, keeps dark reds off black (optional, thresholding and Floyd-Steinberg only)
+    #[arg(long = "luminance-band", value_name = "DELTA_L")]
+    luminance_band: Option<f32>,
+
+    /// Response of target display: preset "led", "epaper", "mac" or display gamma like "2.4", palette colors are matched by colors the display actually shows (optional)
+    #[arg(long = "display-response", value_name = "PRESET|GAMMA")]
+    display_response: Option<DisplayResponse>,
+
+    /// Path to measured display response, 256 lines of perceived value per driven value like "12" or "12,10,14" for R,G,B (optional, conflicts with --display-response)
+    #[arg(long = "display-lut", value_name = "LUT_PATH", conflicts_with = "display_response")]
+    display_lut_path: Option<PathBuf>,
 
-    /// Output file path (optional)
+    /// Maximal mean brightness of the result in percent of full drive, the image is dithered darker until it fits, e.g. power budget of LED wall (optional)
+    #[arg(long = "brightness-budget", value_name = "PERCENT")]
+    brightness_budget: Option<f32>,
+
+    /// Fraction of channel range the error accumulated by Floyd-Steinberg may exceed it by, prevents streaks on saturated images (optional)
+    #[arg(long = "error-overshoot", value_name = "FRACTION", default_value_t = DEFAULT_ERROR_OVERSHOOT)]
+    error_overshoot: f32,
+
+    /// Let Floyd-Steinberg accumulate error without limits (optional, conflicts with --error-overshoot)
+    #[arg(long = "no-error-clamp", conflicts_with = "error_overshoot")]
+    no_error_clamp: bool,
+
+    /// Multipliers of error spread by Floyd-Steinberg like "0.9" or per channel "1,0.8,0.8", R,G,B for fs-rgb and L,a,b for fs-lab (optional)
+    #[arg(long = "error-decay", value_name = "DECAY", default_value_t = ChannelDecay::default())]
+    error_decay: ChannelDecay,
+
+    /// Dither only photographic regions and threshold flat and line art ones, for screenshots with embedded photos. Optional sensitivity in range 0-1, higher treats more regions as photographic (optional)
+    #[arg(long = "hybrid", value_name = "SENSITIVITY", num_args = 0..=1, default_missing_value = "0.5")]
+    hybrid: Option<f32>,
+
+    /// How much Sobel edges block Floyd-Steinberg error spread in range 0-1, keeps text and line art of screenshots crisp (optional)
+    #[arg(long = "edge-attenuation", value_name = "AMOUNT")]
+    edge_attenuation: Option<f32>,
+
+    /// Wrap Floyd-Steinberg error around image edges, so that dithered texture tiles seamlessly when repeated (optional)
+    #[arg(long = "tileable", default_value_t = false)]
+    tileable: bool,
+
+    /// Single color islands with fewer pixels are replaced by the dominant neighbor color, cleans stray specks for vinyl cutting and embroidery (optional)
+    #[arg(long = "despeckle", value_name = "MIN_SIZE", value_parser = clap::value_parser!(u32).range(1..))]
+    despeckle: Option<u32>,
+
+    /// One pixel contour drawn over the result like "black" between all palette regions or "black:#ff0000" around red regions only, for stickers and coloring books (optional)
+    #[arg(long = "outline", value_name = "COLOR[:AROUND]")]
+    outline: Option<Outline>,
+
+    /// Block size of chunky pixel art output, image is downscaled by it, quantized without dithering and upscaled back with nearest neighbor (optional, conflicts with --algorithm, --mask and --hybrid)
+    #[arg(long = "pixelate", value_name = "BLOCK_SIZE", value_parser = clap::value_parser!(u32).range(1..), conflicts_with_all = ["algorithm", "mask_path", "hybrid"])]
+    pixelate: Option<u32>,
+
+    /// Dither at resolution multiplied by this factor and downscale back with box filter, smoother gradients with tiny palettes for longer processing, output mixes palette colors (optional, conflicts with --pixelate, --export-c and --export-svg)
+    #[arg(long = "supersample", value_name = "FACTOR", value_parser = clap::value_parser!(u32).range(1..=8), conflicts_with_all = ["pixelate", "export_c_path", "export_svg_path"])]
+    supersample: Option<u32>,
+
+    /// Drop palette colors covering less than this percentage of output pixels, pixels of dropped colors get the closest kept one. Without value drops only unused colors (optional)
+    #[arg(long = "prune-palette", value_name = "MIN_PERCENT", num_args = 0..=1, default_missing_value = "0")]
+    prune_palette: Option<f32>,
+
+    /// Social media thumbnail shape like "1:1", "16:9" (longer side 1200) or "1200x630" of OpenGraph image, image is center-cropped and downscaled, smaller ones are padded instead of upscaled (optional, conflicts with --width, --height and --resize-mode)
+    #[arg(long = "thumbnail", value_name = "ASPECT|WxH", conflicts_with_all = ["width", "height", "mode"])]
+    thumbnail: Option<Thumbnail>,
+
+    /// Color of padding around images smaller than thumbnail, replaced by the closest palette color (optional, defaults to black)
+    #[arg(long = "pad-color", value_name = "COLOR", requires = "thumbnail")]
+    pad_color: Option<ColorRGB>,
+
+    /// Output file path, output directory in batch mode, '-' for PNG to stdout or template like "out/{stem}_{algo}_{colors}.png" with fields stem, ext, algo, colors, width and height (optional)
     #[arg(short = 'o', long = "output", value_name = "OUTPUT_PATH")]
     output_path: Option<PathBuf>,
 
+    /// Copy ICC profile, EXIF and text chunks of input image to PNG and JPEG outputs, EXIF orientation is reset as image is rotated upright when loaded (optional, file input only)
+    #[arg(long = "keep-metadata", default_value_t = false)]
+    keep_metadata: bool,
+
+    /// Never overwrite existing output images, single image fails and batch images are skipped (optional, conflicts with --watch)
+    #[arg(long = "no-clobber", default_value_t = false, overrides_with = "force", conflicts_with = "watch")]
+    no_clobber: bool,
+
+    /// Overwrite existing output images, default behavior, overrides --no-clobber given earlier e.g. by shell alias (optional)
+    #[arg(long = "force", default_value_t = false, overrides_with = "no_clobber")]
+    force: bool,
+
+    /// Directory where batch results are stored keyed by input image and options, unchanged images are not processed again in later runs (optional, batch mode only)
+    #[arg(long = "cache-dir", value_name = "CACHE_DIR")]
+    cache_dir: Option<PathBuf>,
+
+    /// Processing algorithm (optional)
+    #[arg(short = 'a', long = "algorithm", value_name = "ALGORITHM", value_enum, default_value_t = ProcessingAlgorithm::FloydSteinbergRgb)]
+    algorithm: ProcessingAlgorithm,
+
+    /// Number of images processed in parallel in batch mode (optional)
+    #[arg(short = 'j', long = "jobs", value_name = "JOBS_COUNT", default_value_t = 1)]
+    jobs: usize,
+
+    /// Maximal number of threads of palette reduction clustering, 1 disables multithreading (optional, defaults to all logical cores)
+    #[arg(long = "threads", value_name = "THREADS_COUNT")]
+    threads: Option<NonZeroUsize>,
+
     /// Number of colors to reduce to (optional, conflicts with --palette)
     #[arg(short = 'c', long = "colors", value_name = "INPUT_PATH", conflicts_with = "palette_path", default_value_t = 8)]
     colors_count: usize,
+
+    /// Pixels used for palette extraction: all, every:N, random:N[:SEED] or histogram (optional)
+    #[arg(long = "sampling", value_name = "SAMPLING", default_value = "all")]
+    sampling: PaletteSampling,
+
+    /// Region "x,y,w,h" of resized image the palette is extracted from, e.g. subject without background (optional, conflicts with --palette)
+    #[arg(long = "roi", value_name = "X,Y,W,H", conflicts_with = "palette_path")]
+    roi: Option<ImageRegion>,
+
+    /// Comma separated hex or CSS named colors with optional tolerance like "#00ff00:10" left out of extracted palette, e.g. green screen (optional, conflicts with --palette)
+    #[arg(long = "ignore", value_name = "COLORS[:DELTA_E]", conflicts_with = "palette_path")]
+    ignore: Option<PaletteExtractOptions>,
+
+    /// Pixels count like "20" or percentage like "0.5%" a color has to cover to be extracted, drops JPEG artifacts and fringes (optional, conflicts with --palette)
+    #[arg(long = "min-coverage", value_name = "PIXELS[%]", conflicts_with = "palette_path")]
+    min_coverage: Option<MinCoverage>,
+
+    /// Comma separated hex or CSS named colors like "#000000,white" guaranteed to survive reduction (optional, conflicts with --palette)
+    #[arg(long = "keep", value_name = "COLORS", value_delimiter = ',', conflicts_with = "palette_path")]
+    keep: Vec<ColorRGB>,
+
+    /// Minimal CIEDE2000 difference between reduced colors, too close ones are merged (optional, conflicts with --palette)
+    #[arg(long = "min-delta-e", value_name = "DELTA_E", default_value_t = 0.0, conflicts_with = "palette_path")]
+    min_delta_e: f32,
+
+    /// Clustering backend of palette reduction, agglomerative is deterministic and suits small palettes (optional, conflicts with --palette)
+    #[arg(long = "reduce-strategy", value_name = "STRATEGY", value_enum, default_value_t = ReduceStrategy::KMeans, conflicts_with = "palette_path")]
+    reduce_strategy: ReduceStrategy,
+
+    /// Choose the smallest colors count whose thresholded image meets --max-error (optional, conflicts with --palette)
+    #[arg(long = "auto-colors", default_value_t = false, conflicts_with_all = ["palette_path", "shared_palette", "keep", "min_delta_e"])]
+    auto_colors: bool,
+
+    /// Maximal mean CIEDE2000 error of thresholded image used by --auto-colors (optional)
+    #[arg(long = "max-error", value_name = "DELTA_E", default_value_t = 4.0, requires = "auto_colors")]
+    max_error: f32,
     
-    /// Path to save the reduced palette (optional, works only with --color)
+    /// Path to save the reduced palette, pruned with --prune-palette (optional, works only with --color)
     #[arg(short = 'r', long = "reduced", value_name = "REDUCED_PALETTE_PATH", requires = "colors_count")]
     reduced_palette_path: Option<PathBuf>,
 
-    /// Path to palette file (optional, conflicts with --color)
+    /// Path to palette file, JSON or image like Lospec PNG strip (optional, conflicts with --color)
     #[arg(short = 'p', long = "palette", value_name = "PALETTE_PATH", conflicts_with = "colors_count")]
     palette_path: Option<PathBuf>,
+
+    /// Path to save C header with palette and image as palette indices (optional)
+    #[arg(long = "export-c", value_name = "HEADER_PATH")]
+    export_c_path: Option<PathBuf>,
+
+    /// Path to save SVG with regions of every palette color traced as path (optional)
+    #[arg(long = "export-svg", value_name = "SVG_PATH")]
+    export_svg_path: Option<PathBuf>,
+
+    /// Path to save image with used palette swatches (optional)
+    #[arg(long = "swatch-out", value_name = "SWATCH_PATH")]
+    swatch_path: Option<PathBuf>,
+
+    /// Path to save JSON report with palette, timing and quality metrics (optional)
+    #[arg(long = "report", value_name = "REPORT_PATH")]
+    report_path: Option<PathBuf>,
+
+    /// Path to save JSON with number and percentage of pixels per palette color (optional)
+    #[arg(long = "usage-report", value_name = "USAGE_PATH")]
+    usage_report_path: Option<PathBuf>,
+
+    /// Reduce a single palette from colors of all batch images and apply it to every image (optional, conflicts with --palette)
+    #[arg(long = "shared-palette", conflicts_with = "palette_path", default_value_t = false)]
+    shared_palette: bool,
+
+    /// Keep running and reprocess input file or changed batch images whenever they are modified (optional)
+    #[arg(long = "watch", default_value_t = false)]
+    watch: bool,
+
+    /// Milliseconds between input modification checks in watch mode (optional)
+    #[arg(long = "watch-interval", value_name = "MILLISECONDS", default_value_t = 500, requires = "watch")]
+    watch_interval: u64,
+
+    /// Validate inputs, print planned sizes, palette, outputs and estimated memory without processing or writing anything (optional, conflicts with --watch)
+    #[arg(long = "dry-run", default_value_t = false, conflicts_with = "watch")]
+    dry_run: bool,
+}
+
+/// Resizing arguments shared by modes processing images.
+#[derive(Debug, Args)]
+struct ResizeArgs {
+    /// Desired image width
+    #[arg(short = 'W', long = "width", value_name = "DESIRED_WIDTH")]
+    width: Option<u32>,
+
+    /// Desired image height
+    #[arg(short = 'H', long = "height", value_name = "DESIRED_HEIGHT")]
+    height: Option<u32>,
+
+    /// Resampling filter, use nearest for pixel art and linear-area for bright details on dark background (optional)
+    #[arg(long = "filter", value_name = "FILTER", value_enum, default_value_t = ResizeFilter::Lanczos3)]
+    filter: ResizeFilter,
+
+    /// How image is fitted when both width and height are given (optional)
+    #[arg(long = "resize-mode", value_name = "RESIZE_MODE", value_enum, default_value_t = ResizeMode::Fill)]
+    mode: ResizeMode,
+}
+
+/// Adjustments applied before quantization, shared by modes processing images.
+#[derive(Debug, Args)]
+struct PreprocessingArgs {
+    /// Brightness offset in range -1.0..=1.0 (optional)
+    #[arg(long = "brightness", value_name = "BRIGHTNESS", default_value_t = 0.0, allow_negative_numbers = true)]
+    brightness: f32,
+
+    /// Contrast multiplier, 1.0 keeps image unchanged (optional)
+    #[arg(long = "contrast", value_name = "CONTRAST", default_value_t = 1.0)]
+    contrast: f32,
+
+    /// Saturation multiplier, 0.0 gives grayscale, 1.0 keeps image unchanged (optional)
+    #[arg(long = "saturation", value_name = "SATURATION", default_value_t = 1.0)]
+    saturation: f32,
+
+    /// Gamma correction, above 1.0 brightens midtones, 1.0 keeps image unchanged (optional)
+    #[arg(long = "gamma", value_name = "GAMMA", default_value_t = 1.0)]
+    gamma: f32,
+
+    /// Unsharp mask amount, 0.0 disables sharpening (optional)
+    #[arg(long = "sharpen", value_name = "AMOUNT", default_value_t = 0.0)]
+    sharpen_amount: f32,
+
+    /// Unsharp mask radius in pixels (optional)
+    #[arg(long = "sharpen-radius", value_name = "RADIUS", default_value_t = 1.0)]
+    sharpen_radius: f32,
+
+    /// Keep luma of every pixel at full resolution but average chroma over 2x2 blocks before palette mapping, for LED matrices with lower perceived chroma resolution (optional)
+    #[arg(long = "chroma-subsample", default_value_t = false)]
+    chroma_subsample: bool,
+}
+
+impl From<&ResizeArgs> for ResizeConfig {
+    fn from(args: &ResizeArgs) -> Self {
+        Self {
+            width: args.width,
+            height: args.height,
+            filter: args.filter,
+            mode: args.mode,
+        }
+    }
+}
+
+impl From<&PreprocessingArgs> for Preprocessing {
+    fn from(args: &PreprocessingArgs) -> Self {
+        Self {
+            brightness: args.brightness,
+            contrast: args.contrast,
+            saturation: args.saturation,
+            gamma: args.gamma,
+            sharpen_radius: args.sharpen_radius,
+            sharpen_amount: args.sharpen_amount,
+            chroma_subsample: args.chroma_subsample,
+        }
+    }
+}
+
+/// Noise added right before quantization, shared by modes processing images.
+#[derive(Debug, Args)]
+struct NoiseArgs {
+    /// Noise amplitude as a fraction of channel range, with threshold algorithms gives random dithering (optional)
+    #[arg(long = "noise", value_name = "AMPLITUDE", default_value_t = 0.0)]
+    amplitude: f32,
+
+    /// Noise distribution (optional)
+    #[arg(long = "noise-distribution", value_name = "DISTRIBUTION", value_enum, default_value_t = NoiseDistribution::Triangular)]
+    distribution: NoiseDistribution,
+
+    /// Noise random generator seed (optional)
+    #[arg(long = "noise-seed", value_name = "SEED", default_value_t = 0)]
+    seed: u64,
+}
+
+impl From<&NoiseArgs> for Noise {
+    fn from(args: &NoiseArgs) -> Self {
+        Self {
+            amplitude: args.amplitude,
+            distribution: args.distribution,
+            seed: args.seed,
+        }
+    }
+}
+
+impl DitherModeArgs {
+    /// Checks if any output other than the processed image is requested.
+    fn has_extra_outputs(&self) -> bool {
+        self.export_c_path.is_some() || self.export_svg_path.is_some() || self.swatch_path.is_some() || self.report_path.is_some()
+            || self.usage_report_path.is_some()
+    }
 }
 
 /// Arguments for `palette` mode.
 /// 
 /// # Required Arguments
-/// - `-i`, `--input`: Path to the input image or palette file.
+/// - `-i`, `--input`: Path to the input image or palette file, `-` for image from stdin.
 /// 
 /// # Optional Arguments
-/// - `-o`, `--output`: Path for the output palette JSON file.
+/// - `-o`, `--output`: Path for the output palette JSON file, `-` for stdout.
 /// - `-c`, `--colors`: Number of colors in the output palette.
+/// - `--sampling`: Pixels used for palette extraction: `all`, `every:N`, `random:N[:SEED]` or `histogram`. Defaults to `all`.
+/// - `--roi`: Region `x,y,w,h` of the image the palette is extracted from, image input only.
+/// - `--ignore`: Hex colors with optional CIEDE2000 tolerance, e.g. `#00ff00:10`, left out of extracted palette, image input only.
+/// - `--min-coverage`: Pixels count like `20` or percentage like `0.5%` a color has to cover to be extracted, image input only.
+/// - `--keep`: Comma separated hex colors guaranteed to survive reduction. Requires `--colors`.
+/// - `--min-delta-e`: Minimal CIEDE2000 difference between reduced colors. Requires `--colors`.
+/// - `--reduce-strategy`: Clustering backend of reduction: `kmeans` or deterministic `agglomerative`. Requires `--colors`.
+/// - `--mapping`: Path for JSON object mapping each color before reduction to the color it collapsed into. Requires `--colors`.
+/// - `--auto-colors`: Choose the smallest colors count meeting `--max-error`, image input only. Conflicts with `--colors`.
+/// - `--max-error`: Maximal mean CIEDE2000 error of thresholded image used by `--auto-colors`. Defaults to 4.0.
+/// - `--merge`: Palette files merged into the palette, before reduction.
+/// - `--remove`: Palette files whose colors are removed from the palette, before reduction.
+/// - `--tolerance`: CIEDE2000 difference up to which colors are considered the same when merging or removing.
+/// - `--dedup`: CIEDE2000 difference up to which colors are clustered into one, before reduction.
 #[derive(Debug, Args)]
 struct PaletteModeArgs {
-    /// Input image or palett file path (required)
+    /// Input image or palett file path, '-' for image from stdin (required)
     #[arg(short = 'i', long = "input", value_name = "INPUT_PATH")]
     input_path: PathBuf,
 
-    /// Output palette JSON file (optional)
+    /// Output palette JSON file, '-' for stdout (optional)
     #[arg(short = 'o', long = "output", value_name = "OUTPUT_PATH")]
     output_path: Option<PathBuf>,
 
     /// Number of colors in output palette (optional)
     #[arg(short = 'c', long = "colors", value_name = "COLORS_COUNT")]
     colors_count: Option<usize>,
-}
 
-fn main() {
-    if cfg!(feature = "logging") {
-        env_logger::init();
-    }
+    /// Pixels used for palette extraction: all, every:N, random:N[:SEED] or histogram (optional)
+    #[arg(long = "sampling", value_name = "SAMPLING", default_value = "all")]
+    sampling: PaletteSampling,
 
-    let cli_args = Cli::parse();
-    log::debug!("Got args: '{:?}'.", cli_args);
+    /// Region "x,y,w,h" of the image the palette is extracted from, e.g. subject without background, image input only (optional)
+    #[arg(long = "roi", value_name = "X,Y,W,H")]
+    roi: Option<ImageRegion>,
 
-    if let Err(e) = run(cli_args) {
-        eprintln!("Error: {}", e);
-        std::process::exit(1);
-    }
-}
+    /// Comma separated hex or CSS named colors with optional tolerance like "#00ff00:10" left out of extracted palette, image input only (optional)
+    #[arg(long = "ignore", value_name = "COLORS[:DELTA_E]")]
+    ignore: Option<PaletteExtractOptions>,
 
-/// Main execution flow handler.
-/// 
-/// Calls the appropriate function based on the selected mode.
-fn run(cli_args: Cli) -> anyhow::Result<()> {
-    let process_start = SystemTime::now().duration_since(UNIX_EPOCH)?;
+    /// Pixels count like "20" or percentage like "0.5%" a color has to cover to be extracted, drops JPEG artifacts and fringes, image input only (optional)
+    #[arg(long = "min-coverage", value_name = "PIXELS[%]")]
+    min_coverage: Option<MinCoverage>,
 
-    match cli_args.mode {
-        Mode::Dither(dither_args) => run_dither(cli_args.verbose, dither_args),
-        Mode::Palette(palette_args) => run_palette(cli_args.verbose, palette_args),
-    }?;
-    
-    let process_end = SystemTime::now().duration_since(UNIX_EPOCH)?;
-    let process_duration = process_end-process_start;
-    vprintln!(cli_args.verbose, "Process done in {} seconds.", process_duration.as_secs());
+    /// Comma separated hex or CSS named colors like "#000000,white" guaranteed to survive reduction (optional, requires --colors)
+    #[arg(long = "keep", value_name = "COLORS", value_delimiter = ',', requires = "colors_count")]
+    keep: Vec<ColorRGB>,
 
-    Ok(())
+    /// Minimal CIEDE2000 difference between reduced colors, too close ones are merged (optional, requires --colors)
+    #[arg(long = "min-delta-e", value_name = "DELTA_E", default_value_t = 0.0, requires = "colors_count")]
+    min_delta_e: f32,
+
+    /// Clustering backend of reduction, agglomerative is deterministic and suits small palettes (optional, requires --colors)
+    #[arg(long = "reduce-strategy", value_name = "STRATEGY", value_enum, requires = "colors_count")]
+    reduce_strategy: Option<ReduceStrategy>,
+
+    /// JSON file mapping each hex color before reduction to the hex color it collapsed into, for remapping indexed assets, with --min-delta-e colors map to the nearest reduced ones (optional, requires --colors)
+    #[arg(long = "mapping", value_name = "MAPPING_PATH", requires = "colors_count")]
+    mapping_path: Option<PathBuf>,
+
+    /// Choose the smallest colors count whose thresholded image meets --max-error, image input only (optional, conflicts with --colors)
+    #[arg(long = "auto-colors", default_value_t = false, conflicts_with = "colors_count")]
+    auto_colors: bool,
+
+    /// Maximal mean CIEDE2000 error of thresholded image used by --auto-colors (optional)
+    #[arg(long = "max-error", value_name = "DELTA_E", default_value_t = 4.0, requires = "auto_colors")]
+    max_error: f32,
+
+    /// Comma separated palette files merged into the palette (optional)
+    #[arg(long = "merge", value_name = "PALETTE_PATHS", value_delimiter = ',')]
+    merge_paths: Vec<PathBuf>,
+
+    /// Comma separated palette files whose colors are removed from the palette (optional)
+    #[arg(long = "remove", value_name = "PALETTE_PATHS", value_delimiter = ',')]
+    remove_paths: Vec<PathBuf>,
+
+    /// CIEDE2000 difference up to which colors are the same when merging or removing, 0 for exact match (optional)
+    #[arg(long = "tolerance", value_name = "DELTA_E", default_value_t = 0.0)]
+    tolerance: f32,
+
+    /// Cluster colors with CIEDE2000 difference up to given value into their centroid (optional)
+    #[arg(long = "dedup", value_name = "DELTA_E")]
+    dedup_delta_e: Option<f32>,
 }
 
-/// Executes the `dither` mode logic.
+/// Arguments for `compare` mode.
 /// 
-/// Resizing, dithering, palette loading/saving
-fn run_dither(verbose: bool, args: DitherModeArgs) -> anyhow::Result<()> {
-    vprintln!(verbose, "Dithering started...");
+/// # Required Arguments
+/// - `-i`, `--input`: Path to the input image file, `-` for stdin.
+/// 
+/// # Optional Arguments
+/// - `-W`, `--width`: Optional width for resizing.
+/// - `-H`, `--height`: Optional height for resizing.
+/// - `--filter`: Resampling filter used for resizing. Defaults to `lanczos3`.
+/// - `--resize-mode`: How image is fitted into both width and height. Defaults to `fill`.
+/// - `--brightness`, `--contrast`, `--saturation`, `--gamma`: Adjustments applied before quantization.
+/// - `--sharpen`, `--sharpen-radius`: Unsharp mask amount and radius applied before quantization.
+/// - `--chroma-subsample`: Keep luma of every pixel but share chroma within 2x2 blocks, for LED matrices.
+/// - `--noise`, `--noise-distribution`, `--noise-seed`: Random noise added right before quantization.
+/// - `--mask`: Grayscale image modulating dithering strength per pixel.
+/// - `--transparent-key`: Color of pixels bypassing dithering, reserved if it is a palette entry.
+/// - `-a`, `--algorithms`: Comma separated algorithms to compare. Defaults to all algorithms.
+/// - `-p`, `--palettes`: Comma separated palette files, each compared in separate row. Conflicts with `--colors`.
+/// - `-c`, `--colors`: Number of colors to reduce the image to. Conflicts with `--palettes`.
+/// - `--sampling`: Pixels used for palette extraction: `all`, `every:N`, `random:N[:SEED]` or `histogram`. Defaults to `all`.
+/// - `--keep`: Comma separated hex colors guaranteed to survive reduction. Conflicts with `--palettes`.
+/// - `--min-delta-e`: Minimal CIEDE2000 difference between reduced colors. Conflicts with `--palettes`.
+/// - `--stamp`: Stamp algorithm name, palette size and timing onto each processed image.
+/// - `-o`, `--output`: Path for the contact sheet image. Defaults to `comparison.png`.
+/// - `-r`, `--report`: Path for the JSON report, `-` for stdout. Defaults to the contact sheet path with `json` extension.
+#[derive(Debug, Args)]
+struct CompareModeArgs {
+    /// Input image file path or '-' for stdin (required)
+    #[arg(short = 'i', long = "input", value_name = "INPUT_PATH", required = true)]
+    input_path: PathBuf,
 
-    vprintln!(verbose, "Opening image {:?}...", args.input_path);
-    let image = ditherum::image::load_image(&args.input_path)?;
-    vprintln!(verbose, "Got image width={}, height={}.", image.width(), image.height());
+    #[command(flatten)]
+    resize: ResizeArgs,
 
-    let image = if args.width.is_some() || args.height.is_some() {
-        vprintln!(verbose, "Attempt to reshape image to {:?}x{:?}...", args.width, args.height);
-        let reshaped_image = ditherum::image::manip::rgb_image_reshape(image, args.width, args.height);
-        vprintln!(verbose, "Got image width={}, height={}.", reshaped_image.width(), reshaped_image.height());
-        reshaped_image
-    } else {
-        image
-    };
+    #[command(flatten)]
+    preprocessing: PreprocessingArgs,
 
-    // Fork for 2 options:
-    // - palette from input
-    // - palette generated (with optional save to file)
-    let palette = if let Some(palette_filepath) = args.palette_path {
-        PaletteRGB::load_from_json(palette_filepath)?
-    } else {
-        let mut tmp_palette = PaletteRGB::from_rgbu8_image(&image);
+    #[command(flatten)]
+    noise: NoiseArgs,
 
-        vprintln!(verbose, "Reducing palette to {} colors started...", args.colors_count);
-        tmp_palette = tmp_palette.try_reduce(args.colors_count)?;
-        vprintln!(verbose, "Reduced palette to {} colors.", tmp_palette.len());
+    /// Grayscale mask image, white areas get full dithering and black ones plain thresholding (optional)
+    #[arg(long = "mask", value_name = "MASK_PATH")]
+    mask_path: Option<PathBuf>,
 
-        tmp_palette
-    };
-    vprintln!(verbose, "\nPalette:\n{}\n", palette.get_ansi_colors_visualization());
+    /// Transparent key color like "#ff00ff" or "magenta", matching pixels bypass dithering and stay unchanged (optional)
+    #[arg(long = "transparent-key", value_name = "COLOR")]
+    transparent_key: Option<ColorRGB>,
 
-    // If palette savepath provided, save it
-    if let Some(palette_savepath) = args.reduced_palette_path {
-        vprintln!(verbose, "Saving palette to {:?}.", palette_savepath);
-        palette.save_to_json(&palette_savepath)?;
-        vprintln!(verbose, "Saved palette image to {:?}.", palette_savepath);
-    }
+    /// Comma separated algorithms to compare, all by default (optional)
+    #[arg(short = 'a', long = "algorithms", value_name = "ALGORITHMS", value_enum, value_delimiter = ',')]
+    algorithms: Vec<ProcessingAlgorithm>,
 
-    // Process image
-    let processed_image = ImageProcessor::new(image, palette)
-        .with_algorithm(ditherum::image::ProcessingAlgorithm::FloydSteinbergRgb)
-        .run();
+    /// Comma separated palette files, each compared in separate row (optional, conflicts with --colors)
+    #[arg(short = 'p', long = "palettes", value_name = "PALETTE_PATHS", value_delimiter = ',', conflicts_with = "colors_count")]
+    palette_paths: Vec<PathBuf>,
 
-    let output_path = args.output_path.unwrap_or_else(|| {
-        PathBuf::from("output.png")
-    });
+    /// Number of colors to reduce to (optional, conflicts with --palettes)
+    #[arg(short = 'c', long = "colors", value_name = "COLORS_COUNT", default_value_t = 8)]
+    colors_count: usize,
 
-    ditherum::image::save_image(&output_path, &processed_image)?;
+    /// Pixels used for palette extraction: all, every:N, random:N[:SEED] or histogram (optional)
+    #[arg(long = "sampling", value_name = "SAMPLING", default_value = "all")]
+    sampling: PaletteSampling,
 
-    vprintln!(verbose, "Saved processed image to {:?}.", output_path);
+    /// Comma separated hex or CSS named colors like "#000000,white" guaranteed to survive reduction (optional, conflicts with --palettes)
+    #[arg(long = "keep", value_name = "COLORS", value_delimiter = ',', conflicts_with = "palette_paths")]
+    keep: Vec<ColorRGB>,
 
-    Ok(())
+    /// Minimal CIEDE2000 difference between reduced colors, too close ones are merged (optional, conflicts with --palettes)
+    #[arg(long = "min-delta-e", value_name = "DELTA_E", default_value_t = 0.0, conflicts_with = "palette_paths")]
+    min_delta_e: f32,
+
+    /// Stamp caption with algorithm name, palette size and timing onto each processed image (optional)
+    #[arg(long = "stamp", default_value_t = false)]
+    stamp: bool,
+
+    /// Contact sheet image path (optional)
+    #[arg(short = 'o', long = "output", value_name = "OUTPUT_PATH", default_value = "comparison.png")]
+    output_path: PathBuf,
+
+    /// JSON report path or '-' for stdout, defaults to contact sheet path with json extension (optional)
+    #[arg(short = 'r', long = "report", value_name = "REPORT_PATH")]
+    report_path: Option<PathBuf>,
 }
 
-/// Executes the `palette` mode logic.
-/// 
-/// Loads the image, extracts the palette, and optionally reduces colors.
-fn run_palette(verbose: bool, args: PaletteModeArgs) -> anyhow::Result<()>  {
-    vprintln!(verbose, "Palette extraction started...");
+/// Report of `compare` mode, saved as JSON.
+#[derive(Debug, Serialize)]
+struct CompareReport {
+    input: PathBuf,
+    width: u32,
+    height: u32,
+    results: Vec<CompareResult>,
+}
 
-    let input_extension = args.input_path.extension().context("file missing etension")?;
-    let mut palette = if input_extension.eq_ignore_ascii_case("json") {
-        PaletteRGB::load_from_json(&args.input_path)?
-    } else {
-        let image = ditherum::image::load_image(&args.input_path)?;
-        vprintln!(verbose, "Image '{:?}' loaded successfully. Pixels count {}.", args.input_path, image.len());
-    
-        PaletteRGB::from_rgbu8_image(&image)
-    };
-    vprintln!(verbose, "Got palette with {} colors.", palette.len());
+/// Single algorithm and palette combination result. 
+/// 
+/// Note: `psnr` of output identical to input is infinite, which is written as `null`.
+#[derive(Debug, Serialize)]
+struct CompareResult {
+    algorithm: &'static str,
+    palette: String,
+    colors_count: usize,
+    duration_ms: f64,
+    #[serde(flatten)]
+    quality: ImageQuality,
+}
 
-    if let Some(output_colors_count) = args.colors_count {
-        vprintln!(verbose, "Reducing palette to {} colors started...", output_colors_count);
-        palette = palette.try_reduce(output_colors_count)?;
-        vprintln!(verbose, "Reduced palette to {} colors.", palette.len());
-    }
+/// Arguments for `info` mode.
+/// 
+/// # Required Arguments
+/// - `-i`, `--input`: Path to the input image file, `-` for stdin.
+/// 
+/// # Optional Arguments
+/// - `-d`, `--dominant`: Number of dominant colors to report. Defaults to 5.
+/// - `--max-error`: Maximal mean CIEDE2000 error of thresholded image used to recommend palette size. Defaults to 4.0.
+/// - `--json`: Print report as JSON instead of text.
+#[derive(Debug, Args)]
+struct InfoModeArgs {
+    /// Input image file path or '-' for stdin (required)
+    #[arg(short = 'i', long = "input", value_name = "INPUT_PATH", required = true)]
+    input_path: PathBuf,
 
-    let output_path = args.output_path.unwrap_or_else(|| {
-        args.input_path.with_extension("json")
-    });
+    /// Number of dominant colors to report (optional)
+    #[arg(short = 'd', long = "dominant", value_name = "COLORS_COUNT", default_value_t = 5)]
+    dominant_count: usize,
 
-    palette.save_to_json(&output_path)?;
-    vprintln!(verbose, "Saved to {:?}.", output_path);
-    vprintln!(verbose, "\nResulting palette:\n{}\n", palette.get_ansi_colors_visualization());
+    /// Maximal mean CIEDE2000 error of thresholded image used to recommend palette size (optional)
+    #[arg(long = "max-error", value_name = "DELTA_E", default_value_t = 4.0)]
+    max_error: f32,
 
-    Ok(())
+    /// Print report as JSON (optional)
+    #[arg(long = "json", default_value_t = false)]
+    json: bool,
 }
+
+/// Arguments for `remap` mode.
+/// 
+/// # Required Arguments
+/// - `-i`, `--input`: Path to the indexed or dithered image file, `-` for stdin.
+/// - `--from`: Path to palette the image uses.
+/// - `--to`: Path to palette the image is recolored to.
+/// 
+/// # Optional Arguments
+/// - `-o`, `--output`: Path for the output image, `-` for PNG to stdout. Defaults to the input name with `_remapped` suffix.
+/// - `--metric`: Matching of colors: `index` swaps colors at the same position, `nearest` picks the closest one by CIEDE2000. Defaults to `nearest`.
+#[derive(Debug, Args)]
+struct RemapModeArgs {
+    /// Indexed or dithered image file path or '-' for stdin (required)
+    #[arg(short = 'i', long = "input", value_name = "INPUT_PATH", required = true)]
+    input_path: PathBuf,
+
+    /// Palette file the image uses (required)
+    #[arg(long = "from", value_name = "PALETTE_PATH", required = true)]
+    from_palette_path: PathBuf,
+
+    /// Palette file the image is recolored to (required)
+    #[arg(long = "to", value_name = "PALETTE_PATH", required = true)]
+    to_palette_path: PathBuf,
+
+    /// Output file path or '-' for PNG to stdout (optional)
+    #[arg(short = 'o', long = "output", value_name = "OUTPUT_PATH")]
+    output_path: Option<PathBuf>,
+
+    /// Matching of colors, index requires palettes of the same length (optional)
+    #[arg(long = "metric", value_name = "METRIC", value_enum, default_value_t = RemapMetric::Nearest)]
+    metric: RemapMetric,
+}
+
+/// Arguments for `verify` mode.
+/// 
+/// # Required Arguments
+/// - `-i`, `--input`: Path to the image file, `-` for stdin.
+/// - `-p`, `--palette`: Path to palette the image has to conform to.
+/// 
+/// # Optional Arguments
+/// - `--tolerance`: CIEDE2000 difference from palette color still accepted, e.g. for lossy formats. Defaults to 0, exact match.
+#[derive(Debug, Args)]
+struct VerifyModeArgs {
+    /// Image file path or '-' for stdin (required)
+    #[arg(short = 'i', long = "input", value_name = "INPUT_PATH", required = true)]
+    input_path: PathBuf,
+
+    /// Palette file the image has to conform to (required)
+    #[arg(short = 'p', long = "palette", value_name = "PALETTE_PATH", required = true)]
+    palette_path: PathBuf,
+
+    /// CIEDE2000 difference from the closest palette color still accepted, 0 requires exact match (optional)
+    #[arg(long = "tolerance", value_name = "DELTA_E", default_value_t = 0.0)]
+    tolerance: f32,
+}
+
+/// Arguments for `preview` mode.
+/// 
+/// # Required Arguments
+/// - `-i`, `--input`: Path to the input image file, `-` for stdin.
+/// 
+/// # Optional Arguments
+/// - `-W`, `--width`: Preview width in terminal columns, smaller images are not upscaled. Defaults to 80.
+/// - `-a`, `--algorithm`: Processing algorithm. Defaults to `fs-rgb`.
+/// - `-c`, `--colors`: Number of colors to reduce the image to. Conflicts with `--palette`.
+/// - `-p`, `--palette`: Path to palette file. Conflicts with `--colors`.
+#[derive(Debug, Args)]
+struct PreviewModeArgs {
+    /// Input image file path or '-' for stdin (required)
+    #[arg(short = 'i', long = "input", value_name = "INPUT_PATH", required = true)]
+    input_path: PathBuf,
+
+    /// Preview width in terminal columns, each row shows two pixel rows (optional)
+    #[arg(short = 'W', long = "width", value_name = "COLUMNS", default_value_t = 80)]
+    width: u32,
+
+    /// Processing algorithm (optional)
+    #[arg(short = 'a', long = "algorithm", value_name = "ALGORITHM", value_enum, default_value_t = ProcessingAlgorithm::FloydSteinbergRgb)]
+    algorithm: ProcessingAlgorithm,
+
+    /// Number of colors to reduce to (optional, conflicts with --palette)
+    #[arg(short = 'c', long = "colors", value_name = "COLORS_COUNT", conflicts_with = "palette_path", default_value_t = 8)]
+    colors_count: usize,
+
+    /// Path to palette file (optional, conflicts with --colors)
+    #[arg(short = 'p', long = "palette", value_name = "PALETTE_PATH", conflicts_with = "colors_count")]
+    palette_path: Option<PathBuf>,
+}
+
+/// Arguments for `run` mode.
+/// 
+/// # Required Arguments
+/// - `CONFIG_PATH`: Path to `.toml` or `.json` pipeline file, relative paths inside are resolved against its directory.
+#[derive(Debug, Args)]
+struct RunModeArgs {
+    /// Pipeline config file path with .toml or .json extension (required)
+    #[arg(value_name = "CONFIG_PATH")]
+    config_path: PathBuf,
+}
+
+/// Arguments for `artifact` mode.
+/// 
+/// # Required Arguments
+/// - `-i`, `--input`: Path to the input image file, `-` for stdin.
+/// - `-o`, `--output`: Path for the chart PNG image.
+/// 
+/// # Optional Arguments
+/// - `-W`, `--width`, `-H`, `--height`, `--filter`, `--resize-mode`: Resizing, one pixel is one stitch, bead or brick.
+/// - `-a`, `--algorithm`: Processing algorithm. Defaults to `checker`, which leaves no stray pixels.
+/// - `-c`, `--colors`: Number of colors to reduce the image to. Conflicts with `--palette`. Defaults to 16.
+/// - `-p`, `--palette`: Path to palette file, e.g. available threads or bricks. Conflicts with `--colors`.
+/// - `--csv`: Path for CSV grid of palette numbers.
+/// - `--counts`: Path for CSV with count of every palette color.
+/// - `--cell-size`: Side of a single chart cell in pixels, 2 to 64. Defaults to 16.
+/// - `--print-chart`: Path for printable PDF with chart scaled to the page and palette legend, requires `pdf` feature.
+/// - `--page`: Paper size of printable chart: `a4`, `a3` or `letter`. Defaults to `a4`, requires `pdf` feature.
+#[derive(Debug, Args)]
+struct ArtifactModeArgs {
+    /// Input image file path or '-' for stdin (required)
+    #[arg(short = 'i', long = "input", value_name = "INPUT_PATH", required = true)]
+    input_path: PathBuf,
+
+    /// Output chart image path (required)
+    #[arg(short = 'o', long = "output", value_name = "CHART_PATH", required = true)]
+    output_path: PathBuf,
+
+    #[command(flatten)]
+    resize: ResizeArgs,
+
+    /// Processing algorithm (optional)
+    #[arg(short = 'a', long = "algorithm", value_name = "ALGORITHM", value_enum, default_value_t = ProcessingAlgorithm::Checkerboard)]
+    algorithm: ProcessingAlgorithm,
+
+    /// Number of colors to reduce to (optional, conflicts with --palette)
+    #[arg(short = 'c', long = "colors", value_name = "COLORS_COUNT", conflicts_with = "palette_path", default_value_t = 16)]
+    colors_count: usize,
+
+    /// Path to palette file, e.g. available threads or bricks (optional, conflicts with --colors)
+    #[arg(short = 'p', long = "palette", value_name = "PALETTE_PATH", conflicts_with = "colors_count")]
+    palette_path: Option<PathBuf>,
+
+    /// Path to save CSV grid of 1-based palette numbers (optional)
+    #[arg(long = "csv", value_name = "CSV_PATH")]
+    csv_path: Option<PathBuf>,
+
+    /// Path to save CSV with count of every palette color (optional)
+    #[arg(long = "counts", value_name = "COUNTS_PATH")]
+    counts_path: Option<PathBuf>,
+
+    /// Side of a single chart cell in pixels, numbers are drawn if they fit (optional)
+    #[arg(long = "cell-size", value_name = "PIXELS", default_value_t = 16, value_parser = clap::value_parser!(u32).range(2..=ditherum::export::MAX_CHART_CELL_SIZE as i64))]
+    cell_size: u32,
+
+    /// Path to save printable PDF with chart scaled to the page and palette legend (optional)
+    #[cfg(feature = "pdf")]
+    #[arg(long = "print-chart", value_name = "PDF_PATH")]
+    print_chart_path: Option<PathBuf>,
+
+    /// Paper size of printable chart (optional)
+    #[cfg(feature = "pdf")]
+    #[arg(long = "page", value_name = "PAGE_SIZE", value_enum, default_value_t = ditherum::export::PageSize::A4)]
+    page_size: ditherum::export::PageSize,
+}
+
+/// Arguments for `video` mode, available with `video` feature.
+/// 
+/// # Required Arguments
+/// - `-i`, `--input`: Path to the input video, anything ffmpeg can decode.
+/// - `-o`, `--output`: Path for the output video or frames pattern like `frames_%04d.png`.
+/// 
+/// # Optional Arguments
+/// - `-W`, `--width`, `-H`, `--height`, `--filter`, `--resize-mode`: Resizing applied to every frame.
+/// - `-a`, `--algorithm`: Processing algorithm. Defaults to `fs-rgb`.
+/// - `-c`, `--colors`: Number of colors reduced from the first frame. Conflicts with `--palette`. Defaults to 8.
+/// - `-p`, `--palette`: Path to palette file. Conflicts with `--colors`.
+/// - `--stability`: Channel difference up to which pixels keep previous output, prevents shimmering.
+#[cfg(feature = "video")]
+#[derive(Debug, Args)]
+struct VideoModeArgs {
+    /// Input video file path (required)
+    #[arg(short = 'i', long = "input", value_name = "INPUT_PATH", required = true)]
+    input_path: PathBuf,
+
+    /// Output video file path or frames pattern, format follows extension (required)
+    #[arg(short = 'o', long = "output", value_name = "OUTPUT_PATH", required = true)]
+    output_path: PathBuf,
+
+    #[command(flatten)]
+    resize: ResizeArgs,
+
+    /// Processing algorithm (optional)
+    #[arg(short = 'a', long = "algorithm", value_name = "ALGORITHM", value_enum, default_value_t = ProcessingAlgorithm::FloydSteinbergRgb)]
+    algorithm: ProcessingAlgorithm,
+
+    /// Number of colors reduced from the first frame (optional, conflicts with --palette)
+    #[arg(short = 'c', long = "colors", value_name = "COLORS_COUNT", conflicts_with = "palette_path", default_value_t = 8)]
+    colors_count: usize,
+
+    /// Path to palette file (optional, conflicts with --colors)
+    #[arg(short = 'p', long = "palette", value_name = "PALETTE_PATH", conflicts_with = "colors_count")]
+    palette_path: Option<PathBuf>,
+
+    /// Pixels whose channels changed at most this much keep previous output (optional)
+    #[arg(long = "stability", value_name = "THRESHOLD")]
+    stability: Option<u8>,
+}
+
+/// Report of `info` mode.
+#[derive(Debug, Serialize)]
+struct ImageInfo {
+    input: PathBuf,
+    width: u32,
+    height: u32,
+    unique_colors: usize,
+    dominant_colors: Vec<DominantColor>,
+    recommended_colors: usize,
+    max_error: f32,
+}
+
+/// Dominant color in hex notation with share of pixels closest to it, in percent.
+#[derive(Debug, Serialize)]
+struct DominantColor {
+    color: String,
+    share: f32,
+}
+
+/// Number of offending colors listed by `verify`, the rest is only counted.
+const VERIFY_LISTED_COLORS: usize = 20;
+
+/// Side length of a single palette swatch written by `--swatch-out`.
+const SWATCH_SIZE: u32 = 32;
+
+/// Spacing around images of `compare` contact sheet.
+const CONTACT_SHEET_GAP: u32 = 8;
+
+/// Background of `compare` contact sheet.
+const CONTACT_SHEET_BACKGROUND: image::Rgb<u8> = image::Rgb([32, 32, 32]);
+
+/// Text color of captions stamped by `compare --stamp`.
+const CAPTION_COLOR: image::Rgb<u8> = image::Rgb([255, 255, 255]);
+
+/// Box color of captions stamped by `compare --stamp`.
+const CAPTION_BACKGROUND: image::Rgb<u8> = image::Rgb([0, 0, 0]);
+
+/// Bytes of `RgbImage` pixel, used by `--dry-run` memory estimate.
+const RGB_PIXEL_BYTES: u64 = 3;
+
+/// Bytes of floating point pixel of error diffusion working buffer, used by `--dry-run` memory estimate.
+const FLOAT_PIXEL_BYTES: u64 = 12;
+
+/// Report written by `dither --report`.
+#[derive(Debug, Serialize)]
+struct DitherReport {
+    input: PathBuf,
+    output: PathBuf,
+    width: u32,
+    height: u32,
+    algorithm: &'static str,
+    palette: Vec<String>,
+    duration_ms: u128,
+    quality: Option<ImageQuality>,
+}
+
+fn main() {
+    let cli_args = Cli::parse();
+    let error_format = cli_args.error_format;
+
+    let result = init_tracing(cli_args.trace_json_path.as_deref()).and_then(|_| {
+        tracing::debug!("Got args: '{:?}'.", cli_args);
+        run(cli_args)
+    });
+    if let Err(e) = result {
+        let kind = ErrorKind::of(&e);
+        match error_format {
+            ErrorFormat::Text => eprintln!("Error: {}", e),
+            ErrorFormat::Json => {
+                let report = ErrorReport {
+                    kind,
+                    exit_code: kind.exit_code(),
+                    message: format!("{:#}", e),
+                };
+                eprintln!("{}", serde_json::to_string(&report).unwrap_or_default());
+            },
+        }
+        std::process::exit(kind.exit_code());
+    }
+}
+
+/// Installs tracing subscriber: log lines filtered by `RUST_LOG` to stderr with `logging` feature
+/// and JSON lines with busy and idle time of every closed span if trace path is given.
+fn init_tracing(trace_json_path: Option<&Path>) -> anyhow::Result<()> {
+    use tracing_subscriber::{filter::LevelFilter, fmt::format::FmtSpan, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
+
+    let log_layer = cfg!(feature = "logging").then(|| tracing_subscriber::fmt::layer()
+        .with_writer(std::io::stderr)
+        .with_filter(EnvFilter::from_default_env())
+    );
+    let trace_layer = match trace_json_path {
+        Some(trace_json_path) => {
+            let trace_file = std::fs::File::create(trace_json_path)
+                .with_context(|| format!("failed to create trace {:?}", trace_json_path))?;
+            Some(tracing_subscriber::fmt::layer()
+                .json()
+                .with_span_events(FmtSpan::CLOSE)
+                .with_writer(std::sync::Mutex::new(trace_file))
+                .with_filter(LevelFilter::TRACE)
+            )
+        },
+        None => None,
+    };
+
+    tracing_subscriber::registry()
+        .with(log_layer)
+        .with(trace_layer)
+        .init();
+    Ok(())
+}
+
+/// Main execution flow handler.
+/// 
+/// Calls the appropriate function based on the selected mode.
+fn run(cli_args: Cli) -> anyhow::Result<()> {
+    let process_start = SystemTime::now().duration_since(UNIX_EPOCH)?;
+
+    match cli_args.mode {
+        Mode::Dither(dither_args) => run_dither(cli_args.verbose, *dither_args),
+        Mode::Palette(palette_args) => run_palette(cli_args.verbose, palette_args),
+        Mode::Algorithms => run_algorithms(),
+        Mode::Compare(compare_args) => run_compare(cli_args.verbose, compare_args),
+        Mode::Info(info_args) => run_info(cli_args.verbose, info_args),
+        Mode::Preview(preview_args) => run_preview(cli_args.verbose, preview_args),
+        Mode::Run(run_args) => run_pipeline(cli_args.verbose, run_args),
+        Mode::Artifact(artifact_args) => run_artifact(cli_args.verbose, artifact_args),
+        Mode::Remap(remap_args) => run_remap(cli_args.verbose, remap_args),
+        Mode::Verify(verify_args) => run_verify(cli_args.verbose, verify_args),
+        #[cfg(feature = "video")]
+        Mode::Video(video_args) => run_video(cli_args.verbose, video_args),
+    }?;
+    
+    let process_end = SystemTime::now().duration_since(UNIX_EPOCH)?;
+    let process_duration = process_end-process_start;
+    vprintln!(cli_args.verbose, "Process done in {} seconds.", process_duration.as_secs());
+
+    Ok(())
+}
+
+/// Executes the `dither` mode logic.
+/// 
+/// Dispatches between single image and batch processing.
+fn run_dither(verbose: bool, mut args: DitherModeArgs) -> anyhow::Result<()> {
+    vprintln!(verbose, "Dithering started...");
+
+    // Palette from input is shared by all processed images
+    let palette = match &args.palette_path {
+        Some(palette_filepath) => Some(PaletteRGB::load(palette_filepath)?),
+        None => None,
+    };
+    let mask = load_mask(verbose, args.mask_path.as_deref())?;
+    if let Some(lut_path) = &args.display_lut_path {
+        args.display_response = Some(load_display_lut(verbose, lut_path)?);
+    }
+    if args.cache_dir.is_some() && !is_batch_input(&args.input_path) {
+        bail!("--cache-dir is only supported in batch mode");
+    }
+
+    if args.dry_run {
+        print_dither_plan(&args, palette.as_ref())
+    } else if args.watch {
+        watch_dither(verbose, &args, palette.as_ref(), mask.as_ref())
+    } else if is_batch_input(&args.input_path) {
+        let input_paths = collect_batch_input_paths(&args.input_path)?;
+        run_dither_batch(verbose, &args, palette.as_ref(), mask.as_ref(), &input_paths)
+    } else {
+        run_dither_single(verbose, &args, palette.as_ref(), mask.as_ref())
+    }
+}
+
+/// Executes the `dither` mode logic for a single image, optionally saving reduced palette.
+fn run_dither_single(
+    verbose: bool, 
+    args: &DitherModeArgs, 
+    palette: Option<&PaletteRGB>, 
+    mask: Option<&image::GrayImage>
+) -> anyhow::Result<()> {
+    let output_path = args.output_path.clone().unwrap_or_else(|| {
+        PathBuf::from("output.png")
+    });
+    let output_template = parse_output_template(&output_path)?;
+    if output_template.is_none() {
+        check_clobber(args, &output_path)?;
+    }
+    let result = dither_input(verbose, args, palette, mask, &args.input_path)?;
+    let output_path = match &output_template {
+        Some(output_template) => render_output_path(output_template, args, &args.input_path, Some(result.palette.len()), result.image.dimensions()),
+        None => output_path,
+    };
+    check_clobber(args, &output_path)?;
+    if output_template.is_some() {
+        create_parent_dir(&output_path)?;
+    }
+    let metadata = read_input_metadata(verbose, args, &args.input_path)?;
+    save_output_image_with_metadata(verbose, &output_path, &result.image, &metadata)?;
+
+    // If palette savepath provided, save it
+    if let Some(palette_savepath) = &args.reduced_palette_path {
+        vprintln!(verbose, "Saving palette to {:?}.", palette_savepath);
+        result.palette.save_to_json(palette_savepath)?;
+        vprintln!(verbose, "Saved palette image to {:?}.", palette_savepath);
+    }
+
+    save_extra_outputs(verbose, args, &result, &output_path)
+}
+
+/// Writes outputs derived from dithering result: C header, palette swatch, report and usage report.
+fn save_extra_outputs(verbose: bool, args: &DitherModeArgs, result: &DitherOutput, output_path: &Path) -> anyhow::Result<()> {
+    // Exporters share palette indices of the result
+    let indexed = (args.export_c_path.is_some() || args.export_svg_path.is_some())
+        .then(|| result.to_indexed())
+        .transpose()?;
+
+    if let (Some(export_c_path), Some(indexed)) = (&args.export_c_path, &indexed) {
+        let name = export_c_path.file_stem().unwrap_or_default().to_string_lossy();
+        let header = ditherum::export::to_c_header(&name, indexed);
+        std::fs::write(export_c_path, header)
+            .with_context(|| format!("failed to write C header {:?}", export_c_path))?;
+        vprintln!(verbose, "Saved C header to {:?}.", export_c_path);
+    }
+
+    if let (Some(export_svg_path), Some(indexed)) = (&args.export_svg_path, &indexed) {
+        let svg = ditherum::export::to_svg(indexed);
+        std::fs::write(export_svg_path, svg)
+            .with_context(|| format!("failed to write SVG {:?}", export_svg_path))?;
+        vprintln!(verbose, "Saved SVG to {:?}.", export_svg_path);
+    }
+
+    if let Some(swatch_path) = &args.swatch_path {
+        ditherum::image::save_image(swatch_path, &result.palette.to_swatch_image(SWATCH_SIZE))?;
+        vprintln!(verbose, "Saved palette swatch to {:?}.", swatch_path);
+    }
+
+    if let Some(report_path) = &args.report_path {
+        let report = DitherReport {
+            input: args.input_path.clone(),
+            output: output_path.to_path_buf(),
+            width: result.image.width(),
+            height: result.image.height(),
+            algorithm: args.algorithm.name(),
+            palette: result.palette.iter().map(ColorRGB::to_hex).collect(),
+            duration_ms: result.stats.total_duration().as_millis(),
+            quality: result.stats.quality,
+        };
+        let report_file = std::fs::File::create(report_path)
+            .with_context(|| format!("failed to create report {:?}", report_path))?;
+        serde_json::to_writer_pretty(report_file, &report)?;
+        vprintln!(verbose, "Saved report to {:?}.", report_path);
+    }
+
+    if let Some(usage_report_path) = &args.usage_report_path {
+        let usage = PaletteUsage::measure(&result.image, &result.palette);
+        let usage_file = std::fs::File::create(usage_report_path)
+            .with_context(|| format!("failed to create usage report {:?}", usage_report_path))?;
+        serde_json::to_writer_pretty(usage_file, &usage)?;
+        vprintln!(verbose, "Saved usage report to {:?}, unused colors: {}.", usage_report_path, usage.unused().count());
+    }
+
+    Ok(())
+}
+
+/// Keeps running `dither` mode, reprocessing input whenever it is modified.
+/// 
+/// Input is polled every `--watch-interval`. File is processed once its modification time
+/// stays the same for one interval, so that images still being written are not picked up.
+/// In batch mode only changed images are reprocessed, unless palette is shared by all of them.
+/// Failures are reported and watching continues.
+fn watch_dither(
+    verbose: bool, 
+    args: &DitherModeArgs, 
+    palette: Option<&PaletteRGB>, 
+    mask: Option<&image::GrayImage>
+) -> anyhow::Result<()> {
+    if is_stdio_path(&args.input_path) {
+        bail!("--watch is not supported with stdin input");
+    }
+    let batch_input = is_batch_input(&args.input_path);
+    let interval = Duration::from_millis(args.watch_interval);
+
+    let mut seen_modified = HashMap::<PathBuf, SystemTime>::new();
+    let mut processed_modified = HashMap::<PathBuf, SystemTime>::new();
+    println!("Watching {:?} for changes...", args.input_path);
+
+    loop {
+        let input_paths = if batch_input {
+            collect_batch_input_paths(&args.input_path).unwrap_or_default()
+        } else {
+            vec![args.input_path.clone()]
+        };
+        let current_modified = input_paths.iter()
+            .filter_map(|input_path| {
+                let modified = std::fs::metadata(input_path).and_then(|metadata| metadata.modified()).ok()?;
+                Some((input_path.clone(), modified))
+            })
+            .collect::<HashMap<_, _>>();
+
+        // Settled since previous check and not processed in this version yet
+        let changed_paths = input_paths.iter()
+            .filter(|input_path| {
+                let modified = current_modified.get(*input_path);
+                modified.is_some() && modified == seen_modified.get(*input_path) && modified != processed_modified.get(*input_path)
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+
+        if !changed_paths.is_empty() {
+            vprintln!(verbose, "Detected changes in {} files.", changed_paths.len());
+            let result = if !batch_input {
+                run_dither_single(verbose, args, palette, mask)
+            } else if args.shared_palette {
+                run_dither_batch(verbose, args, palette, mask, &input_paths)
+            } else {
+                run_dither_batch(verbose, args, palette, mask, &changed_paths)
+            };
+
+            if let Err(e) = result {
+                eprintln!("[failed] {:#}", e);
+            } else if !batch_input {
+                println!("[ok] {:?}", args.input_path);
+            }
+            for changed_path in changed_paths {
+                if let Some(modified) = current_modified.get(&changed_path) {
+                    processed_modified.insert(changed_path, *modified);
+                }
+            }
+        }
+
+        seen_modified = current_modified;
+        std::thread::sleep(interval);
+    }
+}
+
+/// Executes the `dither` mode logic over multiple files.
+/// 
+/// Every image is saved under its original filename in the output directory.
+/// Images are distributed between `--jobs` worker threads, each file status is reported
+/// and processing continues even if some of the files fail. With `--cache-dir` results of images
+/// whose pixels and options did not change since previous run are reused instead of processed.
+fn run_dither_batch(
+    verbose: bool, 
+    args: &DitherModeArgs, 
+    palette: Option<&PaletteRGB>, 
+    mask: Option<&image::GrayImage>,
+    input_paths: &[PathBuf]
+) -> anyhow::Result<()> {
+    let output_path = batch_output_path(args)?;
+    let output_template = parse_output_template(&output_path)?;
+    if output_template.is_none() {
+        std::fs::create_dir_all(&output_path)?;
+    }
+    if let Some(cache_dir) = &args.cache_dir {
+        std::fs::create_dir_all(cache_dir)
+            .with_context(|| format!("failed to create cache directory {:?}", cache_dir))?;
+    }
+
+    let shared_palette = if args.shared_palette {
+        let shared_palette = reduce_shared_palette(verbose, args, input_paths)?;
+
+        if let Some(palette_savepath) = &args.reduced_palette_path {
+            shared_palette.save_to_json(palette_savepath)?;
+            vprintln!(verbose, "Saved shared palette to {:?}.", palette_savepath);
+        }
+        Some(shared_palette)
+    } else {
+        None
+    };
+    let palette = shared_palette.as_ref().or(palette);
+
+    let jobs_count = args.jobs.clamp(1, input_paths.len());
+    vprintln!(verbose, "Batch processing {} images using {} jobs...", input_paths.len(), jobs_count);
+
+    let next_input_idx = AtomicUsize::new(0);
+    let failures_count = AtomicUsize::new(0);
+
+    std::thread::scope(|s| {
+        for _ in 0..jobs_count {
+            s.spawn(|| {
+                while let Some(input_path) = input_paths.get(next_input_idx.fetch_add(1, Ordering::Relaxed)) {
+                    // Safe unwrap, only files are collected
+                    let image_output_path = output_path.join(input_path.file_name().unwrap());
+                    if output_template.is_none() && args.no_clobber && image_output_path.exists() {
+                        println!("[skipped] {:?}: {:?} already exists", input_path, image_output_path);
+                        continue;
+                    }
+
+                    let result = match &args.cache_dir {
+                        Some(cache_dir) => dither_input_cached(verbose, args, palette, mask, input_path, cache_dir),
+                        None => dither_input(verbose, args, palette, mask, input_path).map(|result| (result, false)),
+                    };
+                    let result = result.and_then(|(result, cached)| {
+                        let image_output_path = match &output_template {
+                            Some(output_template) => render_output_path(output_template, args, input_path, Some(result.palette.len()), result.image.dimensions()),
+                            None => image_output_path,
+                        };
+                        if args.no_clobber && image_output_path.exists() {
+                            return Ok((image_output_path, BatchStatus::Exists));
+                        }
+                        create_parent_dir(&image_output_path)?;
+                        let metadata = read_input_metadata(verbose, args, input_path)?;
+                        save_output_image_with_metadata(verbose, &image_output_path, &result.image, &metadata)?;
+                        let status = if cached { BatchStatus::Cached } else { BatchStatus::Dithered };
+                        Ok((image_output_path, status))
+                    });
+                    match result.as_ref().ok() {
+                        Some((image_output_path, BatchStatus::Dithered)) => println!("[ok] {:?} -> {:?}", input_path, image_output_path),
+                        Some((image_output_path, BatchStatus::Cached)) => println!("[cached] {:?} -> {:?}", input_path, image_output_path),
+                        Some((existing_path, BatchStatus::Exists)) => println!("[skipped] {:?}: {:?} already exists", input_path, existing_path),
+                        None => {
+                            failures_count.fetch_add(1, Ordering::Relaxed);
+                            eprintln!("[failed] {:?}: {}", input_path, result.unwrap_err());
+                        },
+                    }
+                }
+            });
+        }
+    });
+
+    let failures_count = failures_count.into_inner();
+    if failures_count > 0 {
+        bail!("{} of {} images failed", failures_count, input_paths.len());
+    }
+
+    Ok(())
+}
+
+/// Outcome of processing one image in batch mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BatchStatus {
+    /// Image was processed and saved.
+    Dithered,
+
+    /// Result of previous run was reused from cache directory and saved.
+    Cached,
+
+    /// Output already exists and was kept due to `--no-clobber`.
+    Exists,
+}
+
+/// Checks arguments supported in batch mode and returns output directory or template.
+fn batch_output_path(args: &DitherModeArgs) -> anyhow::Result<PathBuf> {
+    if args.reduced_palette_path.is_some() && !args.shared_palette {
+        bail!("saving reduced palette in batch mode requires --shared-palette");
+    }
+    if args.has_extra_outputs() {
+        bail!("--export-c, --export-svg, --swatch-out, --report and --usage-report are not supported in batch mode");
+    }
+
+    let output_dir = args.output_path.clone().unwrap_or_else(|| {
+        PathBuf::from("output")
+    });
+    if is_stdio_path(&output_dir) {
+        bail!("writing to stdout is not supported in batch mode");
+    }
+    Ok(output_dir)
+}
+
+/// Parses output template if path contains template fields.
+fn parse_output_template(output_path: &Path) -> anyhow::Result<Option<OutputTemplate>> {
+    if !OutputTemplate::is_template(output_path) {
+        return Ok(None);
+    }
+    let template = output_path.to_str()
+        .context("output template is not valid UTF-8")?
+        .parse::<OutputTemplate>()?;
+    Ok(Some(template))
+}
+
+/// Fills output template fields for processed input, stdin input is named `stdin.png`.
+fn render_output_path(
+    output_template: &OutputTemplate,
+    args: &DitherModeArgs,
+    input_path: &Path,
+    colors: Option<usize>,
+    dimensions: (u32, u32)
+) -> PathBuf {
+    let (stem, ext) = if is_stdio_path(input_path) {
+        (Cow::Borrowed("stdin"), Cow::Borrowed("png"))
+    } else {
+        (
+            input_path.file_stem().unwrap_or_default().to_string_lossy(),
+            input_path.extension().unwrap_or_default().to_string_lossy(),
+        )
+    };
+    output_template.render(&OutputFields {
+        stem: &stem,
+        ext: &ext,
+        algo: args.algorithm.name(),
+        colors,
+        width: dimensions.0,
+        height: dimensions.1,
+    })
+}
+
+/// Creates missing directories of output rendered from template.
+fn create_parent_dir(output_path: &Path) -> anyhow::Result<()> {
+    if let Some(parent_dir) = output_path.parent().filter(|parent_dir| !parent_dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent_dir)?;
+    }
+    Ok(())
+}
+
+/// Refuses to overwrite existing output image with `--no-clobber`.
+fn check_clobber(args: &DitherModeArgs, output_path: &Path) -> anyhow::Result<()> {
+    if args.no_clobber && !is_stdio_path(output_path) && output_path.exists() {
+        let message = format!("output {:?} already exists, use --force to overwrite", output_path);
+        return Err(CliError::new(ErrorKind::ProcessingFailure, message).into());
+    }
+    Ok(())
+}
+
+/// Prints planned `dither` pipeline without processing or writing anything.
+///
+/// Inputs are validated by decoding their headers only, stdin input is read whole. Palette
+/// file and mask are loaded by the caller, palettes extracted from images are not reduced.
+fn print_dither_plan(args: &DitherModeArgs, palette: Option<&PaletteRGB>) -> anyhow::Result<()> {
+    let batch_input = is_batch_input(&args.input_path);
+    let (input_paths, output_path) = if batch_input {
+        (collect_batch_input_paths(&args.input_path)?, batch_output_path(args)?)
+    } else {
+        (vec![args.input_path.clone()], args.output_path.clone().unwrap_or_else(|| PathBuf::from("output.png")))
+    };
+    let output_template = parse_output_template(&output_path)?;
+    let planned_colors = planned_colors_count(args, palette);
+    let resize = ResizeConfig::from(&args.resize);
+
+    // Every input is validated before anything is printed
+    let planned_images = input_paths.iter()
+        .map(|input_path| {
+            let dimensions = if is_stdio_path(input_path) {
+                load_input_image(false, input_path)?.dimensions()
+            } else {
+                image::image_dimensions(input_path)
+                    .with_context(|| format!("failed to read image {:?}", input_path))?
+            };
+            let output_dimensions = match args.thumbnail {
+                Some(thumbnail) => thumbnail.dimensions(),
+                None => resize.output_dimensions(dimensions),
+            };
+            let image_output_path = match &output_template {
+                Some(output_template) => render_output_path(output_template, args, input_path, planned_colors, output_dimensions),
+                // Safe unwrap, only files are collected
+                None if batch_input => output_path.join(input_path.file_name().unwrap()),
+                None => output_path.clone(),
+            };
+            Ok((input_path, dimensions, image_output_path, output_dimensions))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    println!("Dry run, nothing is written.");
+    let algorithm = match args.pixelate {
+        Some(block_size) => format!("{} with {}px blocks", ProcessingAlgorithm::ThresholdingLab.name(), block_size),
+        None => args.algorithm.name().to_string(),
+    };
+    println!("Algorithm: {}", algorithm);
+    println!("Palette: {}", describe_palette_plan(args, palette));
+    if let Some(palette) = palette {
+        println!("{}", palette.get_ansi_colors_visualization());
+    }
+    if let Some(mask_path) = &args.mask_path {
+        println!("Mask: {:?}", mask_path);
+    }
+
+    let mut image_memory = 0;
+    let mut shared_palette_memory = 0;
+    for (input_path, dimensions, image_output_path, output_dimensions) in planned_images {
+        let existing = if image_output_path.exists() {
+            if args.no_clobber { " (exists, skipped)" } else { " (exists, overwritten)" }
+        } else {
+            ""
+        };
+        println!(
+            "{:?} {}x{} -> {:?} {}x{}{}",
+            input_path, dimensions.0, dimensions.1, image_output_path, output_dimensions.0, output_dimensions.1, existing
+        );
+        image_memory = image_memory.max(estimate_dither_memory(dimensions, output_dimensions, args.supersample.unwrap_or(1)));
+        shared_palette_memory += pixels_count(output_dimensions) * RGB_PIXEL_BYTES;
+    }
+
+    let jobs_count = if batch_input { args.jobs.clamp(1, input_paths.len()) } else { 1 };
+    if batch_input {
+        println!("Images: {}, jobs: {}", input_paths.len(), jobs_count);
+    }
+
+    let extra_outputs = [
+        ("reduced palette", &args.reduced_palette_path),
+        ("C header", &args.export_c_path),
+        ("SVG", &args.export_svg_path),
+        ("swatch", &args.swatch_path),
+        ("report", &args.report_path),
+        ("usage report", &args.usage_report_path),
+    ];
+    for (name, path) in extra_outputs.iter().filter_map(|(name, path)| path.as_ref().map(|path| (name, path))) {
+        println!("Output {}: {:?}", name, path);
+    }
+
+    let shared_palette_memory = if batch_input && args.shared_palette { shared_palette_memory } else { 0 };
+    let peak_memory = image_memory * jobs_count as u64 + shared_palette_memory;
+    println!("Estimated peak memory: {:.1} MiB", peak_memory as f64 / (1024.0 * 1024.0));
+    Ok(())
+}
+
+/// Describes palette `dither` mode is going to use.
+fn describe_palette_plan(args: &DitherModeArgs, palette: Option<&PaletteRGB>) -> String {
+    match (args.hardware, args.levels, palette) {
+        (Some(hardware), _, _) => format!("{} colors of {} hardware", hardware.palette().len(), hardware),
+        (None, Some(levels), _) => format!("channel levels {}", levels),
+        // Safe unwrap, palette is loaded from path
+        (None, None, Some(palette)) => format!("{} colors from {:?}", palette.len(), args.palette_path.as_ref().unwrap()),
+        (None, None, None) if args.auto_colors => format!("smallest colors count with mean error below {}, sampling {}", args.max_error, args.sampling),
+        (None, None, None) if args.shared_palette => format!("{} colors reduced from all images, sampling {}", args.colors_count, args.sampling),
+        (None, None, None) => format!("{} colors reduced from each image, sampling {}", args.colors_count, args.sampling),
+    }
+}
+
+/// Colors count of the result known before processing, not known for `--auto-colors` and `--levels`.
+fn planned_colors_count(args: &DitherModeArgs, palette: Option<&PaletteRGB>) -> Option<usize> {
+    match (args.hardware, args.levels, palette) {
+        (Some(hardware), _, _) => Some(hardware.palette().len()),
+        (None, Some(_), _) => None,
+        (None, None, Some(palette)) => Some(palette.len()),
+        (None, None, None) if args.auto_colors => None,
+        (None, None, None) => Some(args.colors_count),
+    }
+}
+
+fn pixels_count(dimensions: (u32, u32)) -> u64 {
+    dimensions.0 as u64 * dimensions.1 as u64
+}
+
+/// Estimates bytes held while dithering single image: source, resized and processed image
+/// and floating point working buffer of error diffusion, the last two grow with supersampling.
+fn estimate_dither_memory(dimensions: (u32, u32), output_dimensions: (u32, u32), supersample: u32) -> u64 {
+    let source_memory = pixels_count(dimensions) * RGB_PIXEL_BYTES;
+    let resized_memory = if dimensions == output_dimensions { 0 } else { pixels_count(output_dimensions) * RGB_PIXEL_BYTES };
+    let working_memory = pixels_count(output_dimensions) * (supersample as u64).pow(2) * (FLOAT_PIXEL_BYTES + RGB_PIXEL_BYTES);
+    source_memory + resized_memory + working_memory
+}
+
+/// Reduces one palette from colors of all batch images, so that every output shares the same colors.
+fn reduce_shared_palette(verbose: bool, args: &DitherModeArgs, input_paths: &[PathBuf]) -> anyhow::Result<PaletteRGB> {
+    vprintln!(verbose, "Collecting shared palette from {} images...", input_paths.len());
+    // Images are loaded one at a time and dropped once sampled, so long frame sequences fit in memory
+    let mut load_error = None;
+    let images = input_paths.iter().map_while(|input_path| {
+        let image = load_prepared_image(verbose, input_path, &ResizeConfig::from(&args.resize), &Preprocessing::from(&args.preprocessing))
+            .and_then(|image| match args.roi {
+                Some(roi) => Ok(roi.crop(&image)?),
+                None => Ok(image),
+            });
+        image.map_err(|e| load_error = Some(e)).ok()
+    });
+
+    let mut shared_palette = PaletteRGB::from_rgbu8_images_filtered(images, args.sampling, &extract_options(&args.ignore, args.min_coverage));
+    if let Some(e) = load_error {
+        return Err(e);
+    }
+    exclude_transparent_key(&mut shared_palette, args.transparent_key);
+    vprintln!(verbose, "Got shared palette with {} colors.", shared_palette.len());
+
+    vprintln!(verbose, "Reducing shared palette to {} colors started...", args.colors_count);
+    let shared_palette = shared_palette.try_reduce_spaced_with_strategy(args.colors_count, &args.keep, args.min_delta_e, args.reduce_strategy, &Parallelism::default().with_threads(args.threads.map(NonZeroUsize::get)))?;
+    vprintln!(verbose, "\nShared palette:\n{}\n", shared_palette.get_ansi_colors_visualization());
+
+    Ok(shared_palette)
+}
+
+/// Loads image, reshapes it if desired width or height is provided and applies adjustments.
+/// 
+/// Adjustments are applied before palette reduction, so that reduced palette matches adjusted colors.
+fn load_prepared_image(
+    verbose: bool, 
+    input_path: &Path, 
+    resize: &ResizeConfig, 
+    preprocessing: &Preprocessing
+) -> anyhow::Result<image::RgbImage> {
+    let image = load_input_image(verbose, input_path)?;
+
+    let image = if resize.is_identity() {
+        image
+    } else {
+        vprintln!(verbose, "Attempt to reshape image to {:?}x{:?} ({:?}, {:?})...", resize.width, resize.height, resize.mode, resize.filter);
+        let reshaped_image = resize.apply(image);
+        vprintln!(verbose, "Got image width={}, height={}.", reshaped_image.width(), reshaped_image.height());
+        reshaped_image
+    };
+
+    let image = if preprocessing.is_identity() {
+        image
+    } else {
+        vprintln!(verbose, "Applying {:?}...", preprocessing);
+        preprocessing.apply(image)
+    };
+
+    Ok(image)
+}
+
+/// Loads image and runs resizing, palette reduction and dithering with options from arguments.
+/// 
+/// Nothing is saved, so that every output can be written from the same result.
+fn dither_input(
+    verbose: bool, 
+    args: &DitherModeArgs, 
+    palette: Option<&PaletteRGB>, 
+    mask: Option<&image::GrayImage>,
+    input_path: &Path
+) -> anyhow::Result<DitherOutput> {
+    let image = load_input_image_keeping_depth(verbose, input_path)?;
+    let options = dither_options(verbose, args, palette, mask);
+    dither_with_options(verbose, image, options)
+}
+
+/// Same as [`dither_input`], but reuses result stored in `cache_dir` by previous runs.
+/// 
+/// Returns whether result was taken from cache. Fresh result is stored for later runs,
+/// failure to store it is only reported, as the result itself is still valid.
+fn dither_input_cached(
+    verbose: bool, 
+    args: &DitherModeArgs, 
+    palette: Option<&PaletteRGB>, 
+    mask: Option<&image::GrayImage>,
+    input_path: &Path,
+    cache_dir: &Path
+) -> anyhow::Result<(DitherOutput, bool)> {
+    let image = load_input_image_keeping_depth(verbose, input_path)?;
+    let options = dither_options(verbose, args, palette, mask);
+    let fingerprint = match &image {
+        LoadedImage::Rgb8(image) => output_fingerprint(image, &options),
+        LoadedImage::Rgb32F(image) => output_fingerprint_f32(image, &options),
+    };
+    let cache_entry = CacheEntry::new(cache_dir, fingerprint);
+
+    if let Some(output) = cache_entry.load() {
+        vprintln!(verbose, "Reused cached result {:?}.", cache_entry.image_path);
+        return Ok((output, true));
+    }
+    let output = dither_with_options(verbose, image, options)?;
+    if let Err(e) = cache_entry.store(&output) {
+        eprintln!("[warning] {:?}: failed to store cached result: {:#}", input_path, e);
+    }
+    Ok((output, false))
+}
+
+/// Result of batch processing stored in cache directory under fingerprint of input image and options.
+/// 
+/// Image and its palette are written to temporary files renamed into place, so that entries
+/// interrupted or written concurrently by other runs are never read partially.
+struct CacheEntry {
+    image_path: PathBuf,
+    palette_path: PathBuf,
+}
+
+impl CacheEntry {
+    fn new(cache_dir: &Path, fingerprint: u64) -> Self {
+        Self {
+            image_path: cache_dir.join(format!("{fingerprint:016x}.png")),
+            palette_path: cache_dir.join(format!("{fingerprint:016x}.json")),
+        }
+    }
+
+    /// Reads stored result, missing or unreadable entry is treated as not cached.
+    fn load(&self) -> Option<DitherOutput> {
+        let palette = PaletteRGB::load_from_json(&self.palette_path).ok()?;
+        let image = ditherum::image::load_image(&self.image_path).ok()?;
+        Some(DitherOutput {
+            image,
+            palette,
+            stats: DitherStats {
+                quality: None,
+                palette_duration: Duration::ZERO,
+                processing_duration: Duration::ZERO,
+                processing: None,
+            },
+        })
+    }
+
+    /// Stores result, palette goes first as image completes the entry.
+    fn store(&self, output: &DitherOutput) -> anyhow::Result<()> {
+        let mut encoded_palette = Vec::new();
+        output.palette.save_to_json_writer(&mut encoded_palette)?;
+        write_replacing(&self.palette_path, &encoded_palette)?;
+
+        let mut encoded_image = Vec::new();
+        ditherum::image::save_image_to_writer(&mut encoded_image, &output.image, image::ImageFormat::Png)?;
+        write_replacing(&self.image_path, &encoded_image)
+    }
+}
+
+/// Writes file through temporary file in the same directory, replacing it at once.
+fn write_replacing(path: &Path, contents: &[u8]) -> anyhow::Result<()> {
+    static NEXT_TEMP_ID: AtomicUsize = AtomicUsize::new(0);
+    let temp_path = path.with_extension(format!("{}-{}.tmp", std::process::id(), NEXT_TEMP_ID.fetch_add(1, Ordering::Relaxed)));
+    std::fs::write(&temp_path, contents)
+        .with_context(|| format!("failed to write {:?}", temp_path))?;
+    std::fs::rename(&temp_path, path).inspect_err(|_| {
+        let _ = std::fs::remove_file(&temp_path);
+    })?;
+    Ok(())
+}
+
+/// Builds [`DitherOptions`] from arguments, `palette` loaded from file and `mask` take precedence
+/// over palette reduction and unmasked processing.
+fn dither_options(
+    verbose: bool, 
+    args: &DitherModeArgs, 
+    palette: Option<&PaletteRGB>, 
+    mask: Option<&image::GrayImage>
+) -> DitherOptions {
+    // Fork for 3 options:
+    // - palette from input
+    // - palette generated with the smallest count meeting max error
+    // - palette generated with fixed count
+    let palette = match palette {
+        Some(palette) => PaletteStrategy::Fixed(palette.clone()),
+        None if args.auto_colors => PaletteStrategy::Auto {
+            max_error: args.max_error,
+            sampling: args.sampling,
+        },
+        None => PaletteStrategy::Reduce {
+            colors: args.colors_count,
+            sampling: args.sampling,
+            keep: args.keep.clone(),
+            min_delta_e: args.min_delta_e,
+            strategy: args.reduce_strategy,
+        },
+    };
+    vprintln!(verbose, "Processing with {:?} palette...", palette);
+
+    let mut options = DitherOptions::default()
+        .with_algorithm(args.algorithm)
+        .with_resize(ResizeConfig::from(&args.resize))
+        .with_preprocessing(Preprocessing::from(&args.preprocessing))
+        .with_noise(Noise::from(&args.noise))
+        .with_palette(palette)
+        .with_quality_metrics(args.report_path.is_some())
+        .with_processing_report(verbose);
+    if let Some(roi) = args.roi {
+        options = options.with_palette_region(roi);
+    }
+    if let Some(constraint) = args.tile_constraint {
+        options = options.with_tile_constraint(constraint);
+    }
+    if let Some(hardware) = args.hardware {
+        options = options.with_hardware(hardware);
+    }
+    if let Some(levels) = args.levels {
+        options = options.with_levels(levels);
+    }
+    if let Some(luminance_band) = args.luminance_band {
+        options = options.with_luminance_matching(luminance_band);
+    }
+    if let Some(response) = &args.display_response {
+        options = options.with_display_response(response.clone());
+    }
+    if let Some(max_brightness) = args.brightness_budget {
+        options = options.with_brightness_budget(max_brightness);
+    }
+    if let Some(sensitivity) = args.hybrid {
+        options = options.with_hybrid(sensitivity);
+    }
+    if let Some(block_size) = args.pixelate {
+        options = options.with_pixelate(block_size);
+    }
+    if let Some(factor) = args.supersample {
+        options = options.with_supersample(factor);
+    }
+    if let Some(min_size) = args.despeckle {
+        options = options.with_despeckle(min_size as usize);
+    }
+    if let Some(outline) = args.outline {
+        options = options.with_outline(outline);
+    }
+    if let Some(min_percentage) = args.prune_palette {
+        options = options.with_prune_palette(min_percentage);
+    }
+    options = options.with_parallelism(Parallelism::default().with_threads(args.threads.map(NonZeroUsize::get)));
+    let error_overshoot = (!args.no_error_clamp).then_some(args.error_overshoot);
+    options = options.with_error_diffusion(ErrorDiffusion::default()
+        .with_overshoot(error_overshoot)
+        .with_decay(args.error_decay)
+        .with_edge_attenuation(args.edge_attenuation)
+        .with_tileable(args.tileable)
+    );
+    if args.ignore.is_some() || args.min_coverage.is_some() {
+        options = options.with_palette_extract(extract_options(&args.ignore, args.min_coverage));
+    }
+    if let Some(mask) = mask {
+        options = options.with_mask(mask.clone());
+    }
+    if let Some(key) = args.transparent_key {
+        options = options.with_transparent_key(key);
+    }
+    if let Some(thumbnail) = args.thumbnail {
+        options = options.with_thumbnail(match args.pad_color {
+            Some(pad_color) => thumbnail.with_pad_color(pad_color),
+            None => thumbnail,
+        });
+    }
+    options
+}
+
+/// Runs dithering and reports timing and palette of the result.
+fn dither_with_options(verbose: bool, image: LoadedImage, options: DitherOptions) -> anyhow::Result<DitherOutput> {
+    let output = match image {
+        LoadedImage::Rgb8(image) => ditherum::dither_image(image, options)?,
+        LoadedImage::Rgb32F(image) => ditherum::dither_image_f32(image, options)?,
+    };
+    vprintln!(verbose, "Got image width={}, height={} in {:?}.", output.image.width(), output.image.height(), output.stats.total_duration());
+    if let Some(report) = &output.stats.processing {
+        vprintln!(verbose, "Palette picked in {:?}.\n{}", output.stats.palette_duration, report);
+    }
+    vprintln!(verbose, "\nPalette:\n{}\n", output.palette.get_ansi_colors_visualization());
+
+    Ok(output)
+}
+
+/// Saves processed image to file or writes it as PNG to standard output.
+fn save_output_image(verbose: bool, output_path: &Path, processed_image: &image::RgbImage) -> anyhow::Result<()> {
+    save_output_image_with_metadata(verbose, output_path, processed_image, &ImageMetadata::default())
+}
+
+/// Same as [`save_output_image`], but embeds metadata copied from input where format allows.
+fn save_output_image_with_metadata(verbose: bool, output_path: &Path, processed_image: &image::RgbImage, metadata: &ImageMetadata) -> anyhow::Result<()> {
+    if is_stdio_path(output_path) {
+        let mut encoded = Vec::new();
+        ditherum::image::save_image_to_writer(&mut encoded, processed_image, image::ImageFormat::Png)?;
+        let mut stdout = std::io::stdout().lock();
+        stdout.write_all(&metadata.embed(&encoded, image::ImageFormat::Png))?;
+        stdout.flush()?;
+        vprintln!(verbose, "Written processed image to stdout.");
+    } else {
+        ditherum::image::save_image_with_metadata(output_path, processed_image, metadata)?;
+        vprintln!(verbose, "Saved processed image to {:?}.", output_path);
+    }
+    Ok(())
+}
+
+/// Reads metadata of input image with `--keep-metadata`, stdin input has been consumed already.
+fn read_input_metadata(verbose: bool, args: &DitherModeArgs, input_path: &Path) -> anyhow::Result<ImageMetadata> {
+    if !args.keep_metadata || is_stdio_path(input_path) {
+        return Ok(ImageMetadata::default());
+    }
+    let metadata = ImageMetadata::read(input_path)?;
+    vprintln!(
+        verbose,
+        "Keeping metadata of {:?}: ICC profile={}, EXIF={}, text chunks={}.",
+        input_path, metadata.icc_profile.is_some(), metadata.exif.is_some(), metadata.text.len()
+    );
+    Ok(metadata)
+}
+
+/// Loads grayscale dithering mask if path is provided.
+fn load_mask(verbose: bool, mask_path: Option<&Path>) -> anyhow::Result<Option<image::GrayImage>> {
+    let Some(mask_path) = mask_path else {
+        return Ok(None);
+    };
+
+    vprintln!(verbose, "Opening mask {:?}...", mask_path);
+    let mask = ditherum::image::load_gray_image(mask_path)
+        .with_context(|| format!("failed to load mask {:?}", mask_path))?;
+    vprintln!(verbose, "Got mask width={}, height={}.", mask.width(), mask.height());
+
+    Ok(Some(mask))
+}
+
+/// Loads measured display response, see [`DisplayResponse::parse_lut`].
+fn load_display_lut(verbose: bool, lut_path: &Path) -> anyhow::Result<DisplayResponse> {
+    vprintln!(verbose, "Opening display LUT {:?}...", lut_path);
+    let text = std::fs::read_to_string(lut_path)
+        .with_context(|| format!("failed to read display LUT {:?}", lut_path))?;
+    let response = DisplayResponse::parse_lut(&text)
+        .with_context(|| format!("failed to parse display LUT {:?}", lut_path))?;
+
+    Ok(response)
+}
+
+/// Removes transparent key from colors extracted from image, so that key pixels do not affect reduced palette.
+fn exclude_transparent_key(palette: &mut PaletteRGB, key: Option<ColorRGB>) {
+    if let Some(key) = key {
+        palette.retain(|color| *color != key);
+    }
+}
+
+/// Checks if path stands for standard input or output.
+fn is_stdio_path(path: &Path) -> bool {
+    path.as_os_str() == STDIO_PATH
+}
+
+/// Loads image from file or from standard input.
+fn load_input_image(verbose: bool, input_path: &Path) -> anyhow::Result<image::RgbImage> {
+    let image = if is_stdio_path(input_path) {
+        vprintln!(verbose, "Reading image from stdin...");
+        ditherum::image::load_image_from_reader(std::io::stdin().lock())?
+    } else {
+        vprintln!(verbose, "Opening image {:?}...", input_path);
+        ditherum::image::load_image(input_path)?
+    };
+    vprintln!(verbose, "Got image width={}, height={}.", image.width(), image.height());
+
+    Ok(image)
+}
+
+/// Same as [`load_input_image`], but files with more than 8 bits per channel keep full precision.
+fn load_input_image_keeping_depth(verbose: bool, input_path: &Path) -> anyhow::Result<LoadedImage> {
+    if is_stdio_path(input_path) {
+        return Ok(LoadedImage::Rgb8(load_input_image(verbose, input_path)?));
+    }
+    vprintln!(verbose, "Opening image {:?}...", input_path);
+    let image = ditherum::image::load_image_keeping_depth(input_path)?;
+    let (width, height) = image.dimensions();
+    vprintln!(verbose, "Got image width={}, height={}{}.", width, height, if matches!(image, LoadedImage::Rgb32F(_)) { " with high bit depth" } else { "" });
+
+    Ok(image)
+}
+
+/// Checks if input path points to multiple images: a directory or a glob pattern.
+fn is_batch_input(input_path: &Path) -> bool {
+    input_path.is_dir() || input_path.to_string_lossy().contains(['*', '?', '['])
+}
+
+/// Collects supported image files from directory or matching glob pattern, sorted by path.
+fn collect_batch_input_paths(input_path: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let candidate_paths = if input_path.is_dir() {
+        std::fs::read_dir(input_path)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()?
+    } else {
+        let pattern = input_path.to_str().context("input pattern is not valid UTF-8")?;
+        glob::glob(pattern)?
+            .collect::<Result<Vec<_>, _>>()?
+    };
+
+    let mut input_paths = candidate_paths.into_iter()
+        .filter(|path| path.is_file() && image::ImageFormat::from_path(path).is_ok())
+        .collect::<Vec<_>>();
+    input_paths.sort();
+
+    if input_paths.is_empty() {
+        return Err(CliError::new(ErrorKind::BadInput, format!("no images found matching {:?}", input_path)).into());
+    }
+
+    Ok(input_paths)
+}
+
+/// Executes the `palette` mode logic.
+/// 
+/// Loads the image, extracts the palette, and optionally reduces colors.
+fn run_palette(verbose: bool, args: PaletteModeArgs) -> anyhow::Result<()>  {
+    vprintln!(verbose, "Palette extraction started...");
+
+    let extract_options = extract_options(&args.ignore, args.min_coverage);
+    let (mut palette, image) = if is_stdio_path(&args.input_path) {
+        let image = crop_to_roi(verbose, load_input_image(verbose, &args.input_path)?, args.roi)?;
+        (PaletteRGB::from_rgbu8_image_filtered(&image, args.sampling, &extract_options), Some(image))
+    } else if args.input_path.extension().ok_or_else(|| CliError::new(ErrorKind::UnsupportedFormat, "file missing etension"))?.eq_ignore_ascii_case("json") {
+        if args.roi.is_some() || args.ignore.is_some() || args.min_coverage.is_some() {
+            return Err(CliError::new(ErrorKind::BadInput, "--roi, --ignore and --min-coverage require image input").into());
+        }
+        (PaletteRGB::load_from_json(&args.input_path)?, None)
+    } else {
+        let image = ditherum::image::load_image(&args.input_path)?;
+        vprintln!(verbose, "Image '{:?}' loaded successfully. Pixels count {}.", args.input_path, image.len());
+        let image = crop_to_roi(verbose, image, args.roi)?;
+    
+        (PaletteRGB::from_rgbu8_image_filtered(&image, args.sampling, &extract_options), Some(image))
+    };
+    vprintln!(verbose, "Got palette with {} colors.", palette.len());
+
+    for merge_path in &args.merge_paths {
+        palette.merge_within(PaletteRGB::load(merge_path)?, args.tolerance);
+        vprintln!(verbose, "Merged {:?}, got palette with {} colors.", merge_path, palette.len());
+    }
+
+    for remove_path in &args.remove_paths {
+        palette.remove_within(&PaletteRGB::load(remove_path)?, args.tolerance);
+        vprintln!(verbose, "Removed colors of {:?}, got palette with {} colors.", remove_path, palette.len());
+    }
+    if palette.is_empty() {
+        return Err(CliError::new(ErrorKind::PaletteTooSmall, "no colors left in palette").into());
+    }
+
+    if let Some(dedup_delta_e) = args.dedup_delta_e {
+        palette.dedup_within(dedup_delta_e);
+        vprintln!(verbose, "Clustered nearly identical colors, got palette with {} colors.", palette.len());
+    }
+
+    if let Some(output_colors_count) = args.colors_count {
+        vprintln!(verbose, "Reducing palette to {} colors started...", output_colors_count);
+        let strategy = args.reduce_strategy.unwrap_or_default();
+        let mapping;
+        (palette, mapping) = if args.min_delta_e > 0.0 {
+            // Spacing merges and re-clusters reduced colors, so source colors map to the nearest survivors
+            let source_palette = args.mapping_path.as_ref().map(|_| palette.clone());
+            let reduced_palette = palette.try_reduce_spaced_with_strategy(output_colors_count, &args.keep, args.min_delta_e, strategy, &Parallelism::default())?;
+            let mapping = source_palette.map(|source_palette| source_palette.nearest_color_mapping(&reduced_palette));
+            (reduced_palette, mapping)
+        } else {
+            let (reduced_palette, mapping) = palette.try_reduce_with_mapping(output_colors_count, &args.keep, strategy, &Parallelism::default())?;
+            (reduced_palette, Some(mapping))
+        };
+        vprintln!(verbose, "Reduced palette to {} colors.", palette.len());
+
+        if let (Some(mapping_path), Some(mapping)) = (&args.mapping_path, mapping) {
+            let mapping = mapping
+                .into_iter()
+                .map(|(source, target)| (source.to_hex(), target.to_hex()))
+                .collect::<BTreeMap<_, _>>();
+            let mapping_file = std::fs::File::create(mapping_path)
+                .with_context(|| format!("failed to create mapping {:?}", mapping_path))?;
+            serde_json::to_writer_pretty(mapping_file, &mapping)?;
+            vprintln!(verbose, "Saved reduction mapping to {:?}.", mapping_path);
+        }
+    }
+
+    if args.auto_colors {
+        let image = image.context("--auto-colors requires image input")?;
+        vprintln!(verbose, "Reducing palette to mean error {} started...", args.max_error);
+        palette = palette.reduce_to_quality(&image, args.max_error)?;
+        vprintln!(verbose, "Reduced palette to {} colors.", palette.len());
+    }
+
+    // Palette of image from stdin goes to stdout by default
+    let output_path = args.output_path.unwrap_or_else(|| {
+        if is_stdio_path(&args.input_path) {
+            PathBuf::from(STDIO_PATH)
+        } else {
+            args.input_path.with_extension("json")
+        }
+    });
+
+    if is_stdio_path(&output_path) {
+        palette.save_to_json_writer(std::io::stdout().lock())?;
+        vprintln!(verbose, "Written to stdout.");
+    } else {
+        palette.save_to_json(&output_path)?;
+        vprintln!(verbose, "Saved to {:?}.", output_path);
+    }
+    vprintln!(verbose, "\nResulting palette:\n{}\n", palette.get_ansi_colors_visualization());
+
+    Ok(())
+}
+
+/// Combines `--ignore` and `--min-coverage` filtering of extracted colors.
+fn extract_options(ignore: &Option<PaletteExtractOptions>, min_coverage: Option<MinCoverage>) -> PaletteExtractOptions {
+    let options = ignore.clone().unwrap_or_default();
+    match min_coverage {
+        Some(min_coverage) => options.with_min_coverage(min_coverage),
+        None => options,
+    }
+}
+
+/// Narrows image to region of interest, if given, so that only its colors make the palette.
+fn crop_to_roi(verbose: bool, image: image::RgbImage, roi: Option<ImageRegion>) -> anyhow::Result<image::RgbImage> {
+    let Some(roi) = roi else {
+        return Ok(image);
+    };
+
+    let cropped_image = roi.crop(&image)?;
+    vprintln!(verbose, "Palette region {} covers {}x{} pixels.", roi, cropped_image.width(), cropped_image.height());
+    Ok(cropped_image)
+}
+
+/// Executes the `algorithms` mode logic.
+/// 
+/// Prints names of available algorithms along with short description.
+fn run_algorithms() -> anyhow::Result<()> {
+    for algorithm in ProcessingAlgorithm::all() {
+        println!("{:<16}{}", algorithm.name(), algorithm.description());
+    }
+
+    Ok(())
+}
+/// Executes the `info` mode logic.
+/// 
+/// Dominant colors are k-means centroids of the image colors, ordered by share of pixels closest to them.
+/// Recommended palette size is the smallest one whose thresholded image meets `--max-error`.
+fn run_info(verbose: bool, args: InfoModeArgs) -> anyhow::Result<()> {
+    let image = load_input_image(verbose, &args.input_path)?;
+    let pixels_count = (image.width() as usize * image.height() as usize).max(1);
+    let unique_colors = ditherum::image::count_image_colors(&image).len();
+    vprintln!(verbose, "Got {} unique colors.", unique_colors);
+
+    // Histogram keeps clustering fast on photos with many unique colors
+    let sampled_palette = PaletteRGB::from_rgbu8_image_sampled(&image, PaletteSampling::Histogram);
+
+    vprintln!(verbose, "Finding {} dominant colors...", args.dominant_count);
+    let dominant_palette = sampled_palette.clone().try_reduce(args.dominant_count.min(sampled_palette.len()))?;
+    let dominant_counts = ditherum::image::count_image_colors(
+        &ditherum::algorithms::thresholding::thresholding_rgb(image.clone(), dominant_palette.clone())
+    );
+    let mut dominant_colors = dominant_palette.iter()
+        .map(|color| DominantColor {
+            color: color.to_hex(),
+            share: 100.0 * dominant_counts.get(&color.to_rgbu8()).copied().unwrap_or(0) as f32 / pixels_count as f32,
+        })
+        .collect::<Vec<_>>();
+    dominant_colors.sort_by(|a, b| b.share.total_cmp(&a.share));
+
+    vprintln!(verbose, "Searching recommended palette size...");
+    let recommended_colors = sampled_palette.reduce_to_quality(&image, args.max_error)?.len();
+
+    let info = ImageInfo {
+        input: args.input_path,
+        width: image.width(),
+        height: image.height(),
+        unique_colors,
+        dominant_colors,
+        recommended_colors,
+        max_error: args.max_error,
+    };
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&info)?);
+    } else {
+        println!("Image: {:?}", info.input);
+        println!("Dimensions: {}x{}", info.width, info.height);
+        println!("Unique colors: {}", info.unique_colors);
+        println!("Dominant colors:");
+        for dominant in &info.dominant_colors {
+            let (r, g, b) = ColorRGB::from_hex(&dominant.color)?.tuple();
+            println!("  \x1b[48;2;{};{};{}m  \x1b[0m {} {:>5.1}%", r, g, b, dominant.color, dominant.share);
+        }
+        println!("Recommended palette size: {} (mean CIEDE2000 error up to {})", info.recommended_colors, info.max_error);
+    }
+
+    Ok(())
+}
+
+/// Executes the `preview` mode logic.
+/// 
+/// Image is shrunk to preview width before processing, so that dithering pattern
+/// is visible at terminal resolution. Palette is listed below the image.
+fn run_preview(verbose: bool, args: PreviewModeArgs) -> anyhow::Result<()> {
+    let image = load_input_image(verbose, &args.input_path)?;
+    let width = args.width.clamp(1, image.width().max(1));
+    let image = ditherum::image::manip::rgb_image_resize(image, Some(width), None, ResizeMode::Fill, ResizeFilter::Triangle);
+    vprintln!(verbose, "Resized to width={}, height={}.", image.width(), image.height());
+
+    let palette = if let Some(palette_path) = &args.palette_path {
+        PaletteRGB::load(palette_path)?
+    } else {
+        vprintln!(verbose, "Reducing palette to {} colors started...", args.colors_count);
+        PaletteRGB::from_rgbu8_image(&image).try_reduce(args.colors_count)?
+    };
+
+    let processed_image = ImageProcessor::new(image, palette.clone())
+        .with_algorithm(args.algorithm)
+        .run();
+
+    print!("{}", ditherum::image::get_ansi_image_visualization(&processed_image));
+    println!();
+    print!("{}", palette.get_ansi_colors_visualization());
+
+    Ok(())
+}
+
+/// Executes the `remap` mode logic.
+fn run_remap(verbose: bool, args: RemapModeArgs) -> anyhow::Result<()> {
+    let image = load_input_image(verbose, &args.input_path)?;
+    let from_palette = PaletteRGB::load(&args.from_palette_path)
+        .with_context(|| format!("failed to load palette {:?}", args.from_palette_path))?;
+    let to_palette = PaletteRGB::load(&args.to_palette_path)
+        .with_context(|| format!("failed to load palette {:?}", args.to_palette_path))?;
+
+    vprintln!(verbose, "Remapping {} colors to {} colors by {}...", from_palette.len(), to_palette.len(), args.metric.name());
+    let remapped_image = remap_image(&image, &from_palette, &to_palette, args.metric)?;
+
+    let output_path = args.output_path.unwrap_or_else(|| {
+        if is_stdio_path(&args.input_path) {
+            PathBuf::from(STDIO_PATH)
+        } else {
+            let stem = args.input_path.file_stem().unwrap_or_default().to_string_lossy();
+            args.input_path.with_file_name(format!("{stem}_remapped.png"))
+        }
+    });
+    save_output_image(verbose, &output_path, &remapped_image)
+}
+
+/// Executes the `verify` mode logic.
+/// 
+/// Offending colors are listed on standard output, the most frequent first, and the
+/// process fails with [`ErrorKind::ColorsOutsidePalette`].
+fn run_verify(verbose: bool, args: VerifyModeArgs) -> anyhow::Result<()> {
+    let image = load_input_image(verbose, &args.input_path)?;
+    let palette = PaletteRGB::load(&args.palette_path)
+        .with_context(|| format!("failed to load palette {:?}", args.palette_path))?;
+
+    vprintln!(verbose, "Verifying image against {} colors with tolerance {}...", palette.len(), args.tolerance);
+    let foreign = foreign_colors(&image, &palette, args.tolerance)?;
+    if foreign.is_empty() {
+        println!("{:?}: all colors within palette", args.input_path);
+        return Ok(());
+    }
+
+    for foreign_color in foreign.iter().take(VERIFY_LISTED_COLORS) {
+        let (x, y) = foreign_color.first_position;
+        println!(
+            "{} {} pixels, first at ({x}, {y}), closest {} \u{0394}E {:.2}",
+            foreign_color.color.to_hex(), foreign_color.pixels, foreign_color.closest.to_hex(), foreign_color.delta_e
+        );
+    }
+    if foreign.len() > VERIFY_LISTED_COLORS {
+        println!("... and {} more", foreign.len() - VERIFY_LISTED_COLORS);
+    }
+
+    let pixels = foreign.iter().map(|foreign_color| foreign_color.pixels).sum::<usize>();
+    Err(CliError::new(
+        ErrorKind::ColorsOutsidePalette,
+        format!("{} colors in {} pixels of {:?} are outside the palette", foreign.len(), pixels, args.input_path)
+    ).into())
+}
+
+/// Executes the `run` mode logic.
+/// 
+/// Images are processed in declared order, the first failure stops the pipeline.
+/// Missing output directories are created.
+fn run_pipeline(verbose: bool, args: RunModeArgs) -> anyhow::Result<()> {
+    let config = ProcessingConfig::load(&args.config_path)
+        .with_context(|| format!("failed to load pipeline {:?}", args.config_path))?;
+    vprintln!(verbose, "Running pipeline {:?} with {} images...", args.config_path, config.images.len());
+
+    let mask = load_mask(verbose, config.mask.as_deref())?;
+
+    // Palette shared by all images: loaded from file or reduced from all of them
+    let shared_palette = match &config.palette {
+        PaletteSource::File { path } => Some(PaletteRGB::load(path)
+            .with_context(|| format!("failed to load palette {:?}", path))?),
+        PaletteSource::Reduce { colors, sampling, keep, min_delta_e, strategy, shared: true, output } => {
+            vprintln!(verbose, "Collecting shared palette from {} images...", config.images.len());
+            let images = config.images.iter()
+                .map(|job| load_prepared_image(verbose, &job.input, &config.resize, &config.preprocessing))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            let mut palette = PaletteRGB::from_rgbu8_images_sampled(&images, *sampling);
+            exclude_transparent_key(&mut palette, config.transparent_key);
+            let palette = palette.try_reduce_spaced_with_strategy(*colors, keep, *min_delta_e, *strategy, &Parallelism::default())?;
+
+            if let Some(palette_savepath) = output {
+                palette.save_to_json(palette_savepath)?;
+                vprintln!(verbose, "Saved shared palette to {:?}.", palette_savepath);
+            }
+            Some(palette)
+        },
+        PaletteSource::Reduce { output: Some(_), .. } if config.images.len() > 1 => {
+            bail!("saving reduced palette of multiple images requires shared palette");
+        },
+        _ => None,
+    };
+
+    for job in config.images.iter() {
+        let image = load_input_image(verbose, &job.input)?;
+
+        let palette = match (&shared_palette, &config.palette) {
+            (Some(palette), _) => PaletteStrategy::Fixed(palette.clone()),
+            (None, PaletteSource::Reduce { colors, sampling, keep, min_delta_e, strategy, .. }) => PaletteStrategy::Reduce {
+                colors: *colors,
+                sampling: *sampling,
+                keep: keep.clone(),
+                min_delta_e: *min_delta_e,
+                strategy: *strategy,
+            },
+            (None, PaletteSource::Auto { max_error, sampling }) => PaletteStrategy::Auto {
+                max_error: *max_error,
+                sampling: *sampling,
+            },
+            (None, PaletteSource::File { .. }) => unreachable!("file palette is always loaded upfront"),
+        };
+        let mut options = DitherOptions::default()
+            .with_algorithm(config.algorithm)
+            .with_resize(config.resize)
+            .with_preprocessing(config.preprocessing)
+            .with_noise(config.noise)
+            .with_palette(palette);
+        if let Some(mask) = &mask {
+            options = options.with_mask(mask.clone());
+        }
+        if let Some(key) = config.transparent_key {
+            options = options.with_transparent_key(key);
+        }
+
+        let output = ditherum::dither_image(image, options)?;
+        vprintln!(verbose, "\nPalette:\n{}\n", output.palette.get_ansi_colors_visualization());
+        if let (None, PaletteSource::Reduce { output: Some(palette_savepath), .. }) = (&shared_palette, &config.palette) {
+            output.palette.save_to_json(palette_savepath)?;
+            vprintln!(verbose, "Saved reduced palette to {:?}.", palette_savepath);
+        }
+
+        if let Some(output_dir) = job.output.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(output_dir)?;
+        }
+        save_output_image(verbose, &job.output, &output.image)?;
+        println!("[ok] {:?} -> {:?}", job.input, job.output);
+    }
+
+    Ok(())
+}
+
+/// Executes the `artifact` mode logic.
+/// 
+/// Dithers the image and renders it as chart with legend, optionally with CSV grid and color counts.
+fn run_artifact(verbose: bool, args: ArtifactModeArgs) -> anyhow::Result<()> {
+    let image = load_input_image(verbose, &args.input_path)?;
+    let resize = ResizeConfig::from(&args.resize);
+    // Checked before dithering, so oversized input fails fast
+    let (chart_width, chart_height) = resize.output_dimensions(image.dimensions());
+    if let Err(e) = ditherum::export::check_chart_dimensions(chart_width, chart_height) {
+        bail!("{e} Use -W/-H to set number of chart cells.");
+    }
+    let palette = match &args.palette_path {
+        Some(palette_path) => PaletteStrategy::Fixed(PaletteRGB::load(palette_path)?),
+        None => PaletteStrategy::Reduce {
+            colors: args.colors_count,
+            sampling: PaletteSampling::default(),
+            keep: Vec::new(),
+            min_delta_e: 0.0,
+            strategy: ReduceStrategy::default(),
+        },
+    };
+
+    let output = ditherum::dither_image(image, DitherOptions::default()
+        .with_resize(resize)
+        .with_algorithm(args.algorithm)
+        .with_palette(palette)
+    )?;
+    vprintln!(verbose, "Charting {}x{} cells using {} colors...", output.image.width(), output.image.height(), output.palette.len());
+
+    let indexed = output.to_indexed()?;
+    let chart = ditherum::export::to_chart_image(&indexed, args.cell_size)?;
+    save_output_image(verbose, &args.output_path, &chart)?;
+
+    if let Some(csv_path) = &args.csv_path {
+        std::fs::write(csv_path, ditherum::export::to_chart_csv(&indexed))
+            .with_context(|| format!("failed to write CSV grid {:?}", csv_path))?;
+        vprintln!(verbose, "Saved CSV grid to {:?}.", csv_path);
+    }
+
+    if let Some(counts_path) = &args.counts_path {
+        std::fs::write(counts_path, ditherum::export::to_color_counts_csv(&indexed))
+            .with_context(|| format!("failed to write color counts {:?}", counts_path))?;
+        vprintln!(verbose, "Saved color counts to {:?}.", counts_path);
+    }
+
+    #[cfg(feature = "pdf")]
+    if let Some(print_chart_path) = &args.print_chart_path {
+        std::fs::write(print_chart_path, ditherum::export::to_chart_pdf(&indexed, args.page_size)?)
+            .with_context(|| format!("failed to write printable chart {:?}", print_chart_path))?;
+        vprintln!(verbose, "Saved {} printable chart to {:?}.", args.page_size.name(), print_chart_path);
+    }
+
+    Ok(())
+}
+
+/// Executes the `video` mode logic.
+/// 
+/// Palette is loaded or reduced from the first frame and used for the whole video,
+/// frames are streamed from decoder to encoder one at a time.
+#[cfg(feature = "video")]
+fn run_video(verbose: bool, args: VideoModeArgs) -> anyhow::Result<()> {
+    use ditherum::video::{ffmpeg, FrameDitherer};
+
+    let mut reader = ffmpeg::FrameReader::open(&args.input_path)
+        .with_context(|| format!("failed to open video {:?}", args.input_path))?;
+    let frame_rate = reader.info().frame_rate.clone();
+    vprintln!(verbose, "Decoding {:?}, {:?}...", args.input_path, reader.info());
+
+    let resize = ResizeConfig::from(&args.resize);
+    let Some(first_frame) = reader.next().transpose()? else {
+        return Err(CliError::new(ErrorKind::BadInput, format!("video {:?} has no frames", args.input_path)).into());
+    };
+    let first_frame = resize.apply(first_frame);
+
+    let palette = if let Some(palette_path) = &args.palette_path {
+        PaletteRGB::load(palette_path)?
+    } else {
+        vprintln!(verbose, "Reducing palette of the first frame to {} colors started...", args.colors_count);
+        PaletteRGB::from_rgbu8_image(&first_frame).try_reduce(args.colors_count)?
+    };
+
+    let mut ditherer = FrameDitherer::new(palette).with_algorithm(args.algorithm);
+    if let Some(threshold) = args.stability {
+        ditherer = ditherer.with_temporal_stability(threshold);
+    }
+
+    let mut writer = ffmpeg::FrameWriter::create(&args.output_path, first_frame.width(), first_frame.height(), &frame_rate)
+        .with_context(|| format!("failed to create video {:?}", args.output_path))?;
+    writer.write_frame(ditherer.dither_frame(&first_frame))?;
+
+    let mut frames_count = 1;
+    for frame in reader {
+        let frame = resize.apply(frame?);
+        writer.write_frame(ditherer.dither_frame(&frame))?;
+        frames_count += 1;
+    }
+    writer.finish()?;
+
+    vprintln!(verbose, "Dithered {} frames into {:?}.", frames_count, args.output_path);
+    Ok(())
+}
+
+/// Executes the `compare` mode logic.
+/// 
+/// Runs every algorithm with every palette on the same image, measuring time and quality.
+/// Results are laid out in contact sheet: one row per palette, starting with original image.
+fn run_compare(verbose: bool, args: CompareModeArgs) -> anyhow::Result<()> {
+    vprintln!(verbose, "Comparison started...");
+
+    let image = load_prepared_image(verbose, &args.input_path, &ResizeConfig::from(&args.resize), &Preprocessing::from(&args.preprocessing))?;
+    let mask = load_mask(verbose, args.mask_path.as_deref())?;
+    let algorithms = if args.algorithms.is_empty() {
+        ProcessingAlgorithm::all().to_vec()
+    } else {
+        args.algorithms.clone()
+    };
+
+    // Each palette is compared in separate row
+    let palettes = if args.palette_paths.is_empty() {
+        vprintln!(verbose, "Reducing palette to {} colors started...", args.colors_count);
+        let mut palette = PaletteRGB::from_rgbu8_image_sampled(&image, args.sampling);
+        exclude_transparent_key(&mut palette, args.transparent_key);
+        let palette = palette.try_reduce_spaced(args.colors_count, &args.keep, args.min_delta_e)?;
+        vec![(format!("{} colors", palette.len()), palette)]
+    } else {
+        args.palette_paths.iter()
+            .map(|palette_path| {
+                let palette = PaletteRGB::load(palette_path)?;
+                let label = palette_path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+                Ok((label, palette))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?
+    };
+
+    let mut results = Vec::with_capacity(palettes.len() * algorithms.len());
+    // Each palette is laid out in separate row, after the original image
+    let mut sheet_tiles = Vec::with_capacity(palettes.len() * (algorithms.len() + 1));
+
+    for (palette_label, palette) in &palettes {
+        sheet_tiles.push(("original".to_string(), image.clone()));
+
+        for &algorithm in &algorithms {
+            let start = Instant::now();
+            let mut processor = ImageProcessor::new(image.clone(), palette.clone())
+                .with_algorithm(algorithm)
+                .with_noise(Noise::from(&args.noise));
+            if let Some(mask) = &mask {
+                processor = processor.with_mask(mask.clone());
+            }
+            if let Some(key) = args.transparent_key {
+                processor = processor.with_transparent_key(key);
+            }
+            let mut processed_image = processor.run();
+            let duration = start.elapsed();
+            let quality = ImageQuality::measure(&image, &processed_image);
+            vprintln!(verbose, "{} with {}: {:?}, {:?}.", algorithm, palette_label, duration, quality);
+            if args.stamp {
+                let caption = format!("{}\n{} colors {:.1} ms", algorithm, palette.len(), duration.as_secs_f64() * 1000.0);
+                text::draw_caption(&mut processed_image, &caption, CAPTION_COLOR, CAPTION_BACKGROUND, 1);
+            }
+
+            let label = if palettes.len() > 1 {
+                format!("{} {}", algorithm, palette_label)
+            } else {
+                algorithm.to_string()
+            };
+            sheet_tiles.push((label, processed_image));
+
+            results.push(CompareResult {
+                algorithm: algorithm.name(),
+                palette: palette_label.clone(),
+                colors_count: palette.len(),
+                duration_ms: duration.as_secs_f64() * 1000.0,
+                quality,
+            });
+        }
+    }
+
+    let sheet = ditherum::image::contact_sheet(&sheet_tiles, algorithms.len() + 1, CONTACT_SHEET_GAP, CONTACT_SHEET_BACKGROUND);
+    ditherum::image::save_image(&args.output_path, &sheet)?;
+    vprintln!(verbose, "Saved contact sheet to {:?}.", args.output_path);
+
+    let report = CompareReport {
+        input: args.input_path.clone(),
+        width: image.width(),
+        height: image.height(),
+        results,
+    };
+    let report_path = args.report_path.unwrap_or_else(|| args.output_path.with_extension("json"));
+
+    if is_stdio_path(&report_path) {
+        serde_json::to_writer_pretty(std::io::stdout().lock(), &report)?;
+        println!();
+    } else {
+        serde_json::to_writer_pretty(std::fs::File::create(&report_path)?, &report)?;
+        vprintln!(verbose, "Saved report to {:?}.", report_path);
+    }
+
+    Ok(())
+}
+
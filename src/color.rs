@@ -1,9 +1,30 @@
-use std::ops::Deref;
+use std::{fmt, ops::{Add, Deref, Mul, Sub}, str::FromStr};
 
 use palette::{color_difference::Ciede2000, FromColor};
 use serde::{Deserialize, Serialize};
 
+/// Error returned when parsing color from text fails.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ColorParseError {
+    #[error("expected color like '#ff00ff' or '#f0f', got '{0}'")]
+    InvalidHex(String),
+
+    #[error("expected color like '#ff00ff', '#f0f' or name like 'steelblue', got '{0}'")]
+    UnknownColor(String),
+}
+
 /// Represents an RGB color with three 8-bit components.
+///
+/// Can be parsed from hex notation or CSS color name, see [`named`], and formatted as hex notation.
+///
+/// # Example
+/// ```
+/// use ditherum::color::ColorRGB;
+///
+/// let color: ColorRGB = "#ff8800".parse().unwrap();
+/// assert_eq!(color, ColorRGB([255, 136, 0]));
+/// assert_eq!(color.to_string(), "#ff8800");
+/// ```
 #[derive(Debug, Hash, Copy, Clone, Eq, PartialEq, Deserialize, Serialize)]
 pub struct ColorRGB(pub [u8; 3]);
 
@@ -48,6 +69,36 @@ impl ColorRGB {
         Self::from(lab)
     }
 
+    /// Parses color in `#RRGGBB` or `#RGB` hex notation, leading `#` is optional.
+    pub fn from_hex(hex: &str) -> Result<Self, ColorParseError> {
+        let invalid = || ColorParseError::InvalidHex(hex.to_string());
+        let digits = hex.strip_prefix('#').unwrap_or(hex);
+        if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+
+        let parse_channel = |channel_digits: &str| u8::from_str_radix(channel_digits, 16).map_err(|_| invalid());
+        match digits.len() {
+            6 => Ok(Self([
+                parse_channel(&digits[0..2])?,
+                parse_channel(&digits[2..4])?,
+                parse_channel(&digits[4..6])?,
+            ])),
+            // Each digit is repeated, so "#f80" is "#ff8800"
+            3 => Ok(Self([
+                parse_channel(&digits[0..1])? * 0x11,
+                parse_channel(&digits[1..2])? * 0x11,
+                parse_channel(&digits[2..3])? * 0x11,
+            ])),
+            _ => Err(invalid()),
+        }
+    }
+
+    /// Formats color in lowercase `#rrggbb` hex notation.
+    pub fn to_hex(&self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.red(), self.green(), self.blue())
+    }
+
     /// Converts to `image::Rgb<u8>`.
     pub fn to_rgbu8(&self) -> image::Rgb<u8> {
         (*self).into()
@@ -90,6 +141,22 @@ impl ColorRGB {
         ])
     }
 
+    /// Linearly interpolates between two colors, `t` is clamped to `0.0..=1.0`.
+    ///
+    /// # Example
+    /// ```
+    /// use ditherum::color::ColorRGB;
+    ///
+    /// let color = ColorRGB([0, 0, 0]).lerp(&ColorRGB([100, 200, 255]), 0.25);
+    /// assert_eq!(color, ColorRGB([25, 50, 64]));
+    /// ```
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        ColorRGB(std::array::from_fn(|idx| {
+            ((1.0 - t) * self[idx] as f32 + t * other[idx] as f32).round().clamp(0.0, 255.0) as u8
+        }))
+    }
+
     /// Computes the squared Euclidean distance in RGB space.
     pub fn dist_squared_by_rgb(&self, other: &Self) -> u32 {
         self.0.iter()
@@ -125,6 +192,49 @@ impl PartialOrd for ColorRGB {
     }
 }
 
+/// Formats color in `#rrggbb` hex notation.
+impl fmt::Display for ColorRGB {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_hex())
+    }
+}
+
+/// Parses color in `#RRGGBB` or `#RGB` hex notation or CSS color name, see [`named`].
+impl FromStr for ColorRGB {
+    type Err = ColorParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_hex(s).or_else(|_| named::lookup(s).ok_or_else(|| ColorParseError::UnknownColor(s.to_string())))
+    }
+}
+
+/// Saturating addition of channels, same as [`ColorRGB::saturating_add`].
+impl Add for ColorRGB {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        self.saturating_add(&rhs)
+    }
+}
+
+/// Saturating subtraction of channels, same as [`ColorRGB::saturating_sub`].
+impl Sub for ColorRGB {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.saturating_sub(&rhs)
+    }
+}
+
+/// Multiplication of channels by a scalar with clamping, same as [`ColorRGB::saturating_mul_scalar`].
+impl Mul<f32> for ColorRGB {
+    type Output = Self;
+
+    fn mul(self, rhs: f32) -> Self::Output {
+        self.saturating_mul_scalar(rhs)
+    }
+}
+
 /// Allows treating `ColorRGB` as a slice of three `u8` values.
 impl Deref for ColorRGB {
     type Target = [u8; 3];
@@ -183,27 +293,38 @@ impl From<ColorRGB> for palette::Lab {
     }
 }
 
-pub mod manip {
-    use palette::color_difference::{Ciede2000, EuclideanDistance};
+/// Canonical color math on `palette` color types used by processing algorithms.
+pub mod ops {
+    use palette::{color_difference::{Ciede2000, EuclideanDistance}, FromColor};
 
     use super::ColorRGB;
 
+    /// Converts `image::Rgb<u8>` to `palette::Srgb`.
     pub fn rgbu8_to_srgb(src: image::Rgb<u8>) -> palette::Srgb {
         ColorRGB::from(src).to_srgb()
     }
 
+    /// Converts `image::Rgb<u8>` to `palette::Lab`.
     pub fn rgbu8_to_lab(src: image::Rgb<u8>) -> palette::Lab {
         ColorRGB::from(src).to_lab()
     }
 
+    /// Converts `palette::Srgb` to `palette::Lab`, without quantization to 8 bits.
+    pub fn srgb_to_lab(src: palette::Srgb) -> palette::Lab {
+        palette::Lab::from_color(src)
+    }
+
+    /// Converts `palette::Srgb` to `image::Rgb<u8>`, clamping values.
     pub fn srgb_to_rgbu8(src: palette::Srgb) -> image::Rgb<u8> {
         ColorRGB::from(src).to_rgbu8()
     }
 
+    /// Converts `palette::Lab` to `image::Rgb<u8>`, clamping values.
     pub fn lab_to_rgbu8(src: palette::Lab) -> image::Rgb<u8> {
         ColorRGB::from(src).to_rgbu8()
     }
 
+    /// Adds Lab colors component-wise.
     pub fn lab_add(left: &palette::Lab, right: &palette::Lab) -> palette::Lab {
         palette::Lab::new(
             left.l + right.l,
@@ -212,12 +333,14 @@ pub mod manip {
         )
     }
 
+    /// Adds Lab color to another one in place.
     pub fn lab_mut_add(left: &mut palette::Lab, right: &palette::Lab) {
         left.l += right.l;
         left.a += right.a;
         left.b += right.b;
     }
 
+    /// Subtracts Lab colors component-wise.
     pub fn lab_sub(left: &palette::Lab, right: &palette::Lab) -> palette::Lab {
         palette::Lab::new(
             left.l - right.l,
@@ -226,6 +349,7 @@ pub mod manip {
         )
     }
 
+    /// Multiplies Lab color components by a scalar.
     pub fn lab_mul_scalar(left: &palette::Lab, scalar: f32) -> palette::Lab {
         palette::Lab::new(
             left.l * scalar,
@@ -233,7 +357,26 @@ pub mod manip {
             left.b * scalar
         )
     }
+
+    /// Computes Lab mean of colors, black for empty slice.
+    pub fn lab_mean(colors: &[palette::Lab]) -> palette::Lab {
+        if colors.is_empty() {
+            return palette::Lab::new(0.0, 0.0, 0.0);
+        }
+
+        let mut sum = colors.iter()
+            .fold(palette::Lab::new(0.0, 0.0, 0.0), |mut acc, color| {
+                lab_mut_add(&mut acc, color);
+                acc
+            });
+        let count = colors.len() as f32;
+        sum.l /= count;
+        sum.a /= count;
+        sum.b /= count;
+        sum
+    }
     
+    /// Adds Srgb colors component-wise, values are not clamped.
     pub fn srgb_add(left: &palette::Srgb, right: &palette::Srgb) -> palette::Srgb {
         palette::Srgb::new(
             left.red + right.red,
@@ -242,6 +385,7 @@ pub mod manip {
         )
     }
 
+    /// Subtracts Srgb colors component-wise, values are not clamped.
     pub fn srgb_sub(left: &palette::Srgb, right: &palette::Srgb) -> palette::Srgb {
         palette::Srgb::new(
             left.red - right.red,
@@ -250,6 +394,7 @@ pub mod manip {
         )
     }
 
+    /// Multiplies Srgb color components by a scalar, values are not clamped.
     pub fn srgb_mul_scalar(left: &palette::Srgb, scalar: f32) -> palette::Srgb {
         palette::Srgb::new(
             left.red * scalar,
@@ -258,28 +403,10 @@ pub mod manip {
         )
     }
 
-    pub fn mix_color_channel(
-        mix_factor: f32, 
-        from_value: u8,
-        to_value: u8
-    ) -> u8 {
-        let mix_factor = mix_factor.clamp(0.0, 1.0);
-        let mixed_value = (1.0 - mix_factor) * (from_value as f32) + mix_factor * (to_value as f32);
-        mixed_value.round().clamp(0.0, 255.0) as u8 
-    }
-    
-    pub fn mix_rgb_colors(
-        mix_factor: f32, 
-        from_color: image::Rgb<u8>,
-        to_color: image::Rgb<u8>
-    ) -> image::Rgb<u8> {
-        image::Rgb([
-            mix_color_channel(mix_factor, from_color[0], to_color[0]),
-            mix_color_channel(mix_factor, from_color[1], to_color[1]),
-            mix_color_channel(mix_factor, from_color[2], to_color[2])
-        ])
-    }
-
+    /// Finds palette color closest by CIEDE2000 difference, returns it with quantization error.
+    /// 
+    /// # Panics
+    /// Panics if palette is empty.
     pub fn find_closest_lab_color(lab_color: &palette::Lab, palette: &[palette::Lab]) -> (palette::Lab, palette::Lab) {
         let (_, &closest_palette_color) = palette.iter()
             .map(|palette_color| {
@@ -294,7 +421,39 @@ pub mod manip {
         let quant_err = lab_sub(lab_color, &closest_palette_color);
         (closest_palette_color, quant_err)
     }
-    
+
+    /// Finds palette color matching lightness first and chroma second, returns it with quantization error.
+    ///
+    /// Candidates are palette colors whose lightness differs from the pixel at most `luminance_band`
+    /// more than the best lightness match, the one with the closest a/b components wins. This keeps
+    /// dark saturated pixels dark when palette lacks their hue, instead of collapsing them to black.
+    ///
+    /// # Panics
+    /// Panics if palette is empty.
+    pub fn find_closest_lab_color_by_luminance(lab_color: &palette::Lab, palette: &[palette::Lab], luminance_band: f32) -> (palette::Lab, palette::Lab) {
+        let lightness_diff = |palette_color: &palette::Lab| (lab_color.l - palette_color.l).abs();
+        let chroma_diff = |palette_color: &palette::Lab| (lab_color.a - palette_color.a).powi(2) + (lab_color.b - palette_color.b).powi(2);
+
+        let best_lightness_diff = palette.iter()
+            .map(lightness_diff)
+            .min_by(|diff_a, diff_b| diff_a.total_cmp(diff_b))
+            .unwrap();
+
+        let closest_palette_color = *palette.iter()
+            .filter(|palette_color| lightness_diff(palette_color) <= best_lightness_diff + luminance_band.max(0.0))
+            .min_by(|color_a, color_b| chroma_diff(color_a).total_cmp(&chroma_diff(color_b))
+                .then(lightness_diff(color_a).total_cmp(&lightness_diff(color_b)))
+            )
+            .unwrap();
+
+        let quant_err = lab_sub(lab_color, &closest_palette_color);
+        (closest_palette_color, quant_err)
+    }
+
+    /// Finds palette color closest by Euclidean distance.
+    /// 
+    /// # Panics
+    /// Panics if palette is empty.
     pub fn find_closest_srgb_color(srgb_color: &palette::Srgb, palette: &[palette::Srgb]) -> palette::Srgb {
         let (_, &closest_palette_color) = palette.iter()
             .map(|palette_color| {
@@ -308,21 +467,283 @@ pub mod manip {
     
         closest_palette_color
     }
-    
+
     #[test]
-    fn test_channel_mix() {
-        let mix_factor = 0.25;
-        let from_value = 0;
-        let to_value = 100;
-        let result = mix_color_channel(mix_factor, from_value, to_value);
-        assert_eq!(result, 25);
+    fn test_lab_mean() {
+        let colors = [palette::Lab::new(10.0, -20.0, 4.0), palette::Lab::new(30.0, 20.0, 0.0)];
+        assert_eq!(lab_mean(&colors), palette::Lab::new(20.0, 0.0, 2.0));
+        assert_eq!(lab_mean(&[]), palette::Lab::new(0.0, 0.0, 0.0));
     }
 }
 
+/// CSS named colors, which are the X11 colors with a few web additions, like `"steelblue"`.
+///
+/// # Example
+/// ```
+/// use ditherum::color::{named, ColorRGB};
+///
+/// assert_eq!(named::lookup("RebeccaPurple"), Some(ColorRGB([102, 51, 153])));
+/// assert_eq!(named::name_of(&ColorRGB([70, 130, 180])), Some("steelblue"));
+/// ```
+pub mod named {
+    use super::ColorRGB;
+
+    /// All CSS color names with their values, ordered by name.
+    pub const COLORS: &[(&str, [u8; 3])] = &[
+        ("aliceblue", [240, 248, 255]),
+        ("antiquewhite", [250, 235, 215]),
+        ("aqua", [0, 255, 255]),
+        ("aquamarine", [127, 255, 212]),
+        ("azure", [240, 255, 255]),
+        ("beige", [245, 245, 220]),
+        ("bisque", [255, 228, 196]),
+        ("black", [0, 0, 0]),
+        ("blanchedalmond", [255, 235, 205]),
+        ("blue", [0, 0, 255]),
+        ("blueviolet", [138, 43, 226]),
+        ("brown", [165, 42, 42]),
+        ("burlywood", [222, 184, 135]),
+        ("cadetblue", [95, 158, 160]),
+        ("chartreuse", [127, 255, 0]),
+        ("chocolate", [210, 105, 30]),
+        ("coral", [255, 127, 80]),
+        ("cornflowerblue", [100, 149, 237]),
+        ("cornsilk", [255, 248, 220]),
+        ("crimson", [220, 20, 60]),
+        ("cyan", [0, 255, 255]),
+        ("darkblue", [0, 0, 139]),
+        ("darkcyan", [0, 139, 139]),
+        ("darkgoldenrod", [184, 134, 11]),
+        ("darkgray", [169, 169, 169]),
+        ("darkgreen", [0, 100, 0]),
+        ("darkgrey", [169, 169, 169]),
+        ("darkkhaki", [189, 183, 107]),
+        ("darkmagenta", [139, 0, 139]),
+        ("darkolivegreen", [85, 107, 47]),
+        ("darkorange", [255, 140, 0]),
+        ("darkorchid", [153, 50, 204]),
+        ("darkred", [139, 0, 0]),
+        ("darksalmon", [233, 150, 122]),
+        ("darkseagreen", [143, 188, 143]),
+        ("darkslateblue", [72, 61, 139]),
+        ("darkslategray", [47, 79, 79]),
+        ("darkslategrey", [47, 79, 79]),
+        ("darkturquoise", [0, 206, 209]),
+        ("darkviolet", [148, 0, 211]),
+        ("deeppink", [255, 20, 147]),
+        ("deepskyblue", [0, 191, 255]),
+        ("dimgray", [105, 105, 105]),
+        ("dimgrey", [105, 105, 105]),
+        ("dodgerblue", [30, 144, 255]),
+        ("firebrick", [178, 34, 34]),
+        ("floralwhite", [255, 250, 240]),
+        ("forestgreen", [34, 139, 34]),
+        ("fuchsia", [255, 0, 255]),
+        ("gainsboro", [220, 220, 220]),
+        ("ghostwhite", [248, 248, 255]),
+        ("gold", [255, 215, 0]),
+        ("goldenrod", [218, 165, 32]),
+        ("gray", [128, 128, 128]),
+        ("green", [0, 128, 0]),
+        ("greenyellow", [173, 255, 47]),
+        ("grey", [128, 128, 128]),
+        ("honeydew", [240, 255, 240]),
+        ("hotpink", [255, 105, 180]),
+        ("indianred", [205, 92, 92]),
+        ("indigo", [75, 0, 130]),
+        ("ivory", [255, 255, 240]),
+        ("khaki", [240, 230, 140]),
+        ("lavender", [230, 230, 250]),
+        ("lavenderblush", [255, 240, 245]),
+        ("lawngreen", [124, 252, 0]),
+        ("lemonchiffon", [255, 250, 205]),
+        ("lightblue", [173, 216, 230]),
+        ("lightcoral", [240, 128, 128]),
+        ("lightcyan", [224, 255, 255]),
+        ("lightgoldenrodyellow", [250, 250, 210]),
+        ("lightgray", [211, 211, 211]),
+        ("lightgreen", [144, 238, 144]),
+        ("lightgrey", [211, 211, 211]),
+        ("lightpink", [255, 182, 193]),
+        ("lightsalmon", [255, 160, 122]),
+        ("lightseagreen", [32, 178, 170]),
+        ("lightskyblue", [135, 206, 250]),
+        ("lightslategray", [119, 136, 153]),
+        ("lightslategrey", [119, 136, 153]),
+        ("lightsteelblue", [176, 196, 222]),
+        ("lightyellow", [255, 255, 224]),
+        ("lime", [0, 255, 0]),
+        ("limegreen", [50, 205, 50]),
+        ("linen", [250, 240, 230]),
+        ("magenta", [255, 0, 255]),
+        ("maroon", [128, 0, 0]),
+        ("mediumaquamarine", [102, 205, 170]),
+        ("mediumblue", [0, 0, 205]),
+        ("mediumorchid", [186, 85, 211]),
+        ("mediumpurple", [147, 112, 219]),
+        ("mediumseagreen", [60, 179, 113]),
+        ("mediumslateblue", [123, 104, 238]),
+        ("mediumspringgreen", [0, 250, 154]),
+        ("mediumturquoise", [72, 209, 204]),
+        ("mediumvioletred", [199, 21, 133]),
+        ("midnightblue", [25, 25, 112]),
+        ("mintcream", [245, 255, 250]),
+        ("mistyrose", [255, 228, 225]),
+        ("moccasin", [255, 228, 181]),
+        ("navajowhite", [255, 222, 173]),
+        ("navy", [0, 0, 128]),
+        ("oldlace", [253, 245, 230]),
+        ("olive", [128, 128, 0]),
+        ("olivedrab", [107, 142, 35]),
+        ("orange", [255, 165, 0]),
+        ("orangered", [255, 69, 0]),
+        ("orchid", [218, 112, 214]),
+        ("palegoldenrod", [238, 232, 170]),
+        ("palegreen", [152, 251, 152]),
+        ("paleturquoise", [175, 238, 238]),
+        ("palevioletred", [219, 112, 147]),
+        ("papayawhip", [255, 239, 213]),
+        ("peachpuff", [255, 218, 185]),
+        ("peru", [205, 133, 63]),
+        ("pink", [255, 192, 203]),
+        ("plum", [221, 160, 221]),
+        ("powderblue", [176, 224, 230]),
+        ("purple", [128, 0, 128]),
+        ("rebeccapurple", [102, 51, 153]),
+        ("red", [255, 0, 0]),
+        ("rosybrown", [188, 143, 143]),
+        ("royalblue", [65, 105, 225]),
+        ("saddlebrown", [139, 69, 19]),
+        ("salmon", [250, 128, 114]),
+        ("sandybrown", [244, 164, 96]),
+        ("seagreen", [46, 139, 87]),
+        ("seashell", [255, 245, 238]),
+        ("sienna", [160, 82, 45]),
+        ("silver", [192, 192, 192]),
+        ("skyblue", [135, 206, 235]),
+        ("slateblue", [106, 90, 205]),
+        ("slategray", [112, 128, 144]),
+        ("slategrey", [112, 128, 144]),
+        ("snow", [255, 250, 250]),
+        ("springgreen", [0, 255, 127]),
+        ("steelblue", [70, 130, 180]),
+        ("tan", [210, 180, 140]),
+        ("teal", [0, 128, 128]),
+        ("thistle", [216, 191, 216]),
+        ("tomato", [255, 99, 71]),
+        ("turquoise", [64, 224, 208]),
+        ("violet", [238, 130, 238]),
+        ("wheat", [245, 222, 179]),
+        ("white", [255, 255, 255]),
+        ("whitesmoke", [245, 245, 245]),
+        ("yellow", [255, 255, 0]),
+        ("yellowgreen", [154, 205, 50]),
+    ];
+
+    /// Returns color of given name, case and spaces are ignored, so `"Steel Blue"` works too.
+    pub fn lookup(name: &str) -> Option<ColorRGB> {
+        let normalized = name.chars()
+            .filter(|c| !c.is_whitespace())
+            .map(|c| c.to_ascii_lowercase())
+            .collect::<String>();
+
+        COLORS.binary_search_by(|(color_name, _)| (*color_name).cmp(normalized.as_str()))
+            .ok()
+            .map(|idx| ColorRGB(COLORS[idx].1))
+    }
+
+    /// Returns name of color matching exactly, the first alphabetically if color has aliases,
+    /// e.g. `"aqua"` rather than `"cyan"`.
+    pub fn name_of(color: &ColorRGB) -> Option<&'static str> {
+        COLORS.iter()
+            .find(|(_, value)| *value == color.0)
+            .map(|(name, _)| *name)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_named_colors_table() {
+            assert_eq!(COLORS.len(), 148);
+            assert!(COLORS.windows(2).all(|pair| pair[0].0 < pair[1].0), "Table not sorted");
+
+            assert_eq!(lookup("steelblue"), Some(ColorRGB([70, 130, 180])));
+            assert_eq!(lookup(" Steel Blue "), Some(ColorRGB([70, 130, 180])));
+            assert_eq!(lookup("steelblu"), None);
+            assert_eq!(name_of(&ColorRGB([0, 255, 255])), Some("aqua"));
+            assert_eq!(name_of(&ColorRGB([1, 2, 3])), None);
+            assert!(COLORS.iter().all(|(name, value)| lookup(name) == Some(ColorRGB(*value))));
+        }
+    }
+}
+
+/// Former home of color math, use [`ops`] and [`ColorRGB`] methods instead.
+#[deprecated(note = "use `color::ops` functions and `ColorRGB::lerp` instead")]
+pub mod manip {
+    pub use super::ops::*;
+
+    use super::ColorRGB;
+
+    #[deprecated(note = "use `ColorRGB::lerp` instead")]
+    pub fn mix_color_channel(
+        mix_factor: f32, 
+        from_value: u8,
+        to_value: u8
+    ) -> u8 {
+        ColorRGB([from_value; 3]).lerp(&ColorRGB([to_value; 3]), mix_factor)[0]
+    }
+    
+    #[deprecated(note = "use `ColorRGB::lerp` instead")]
+    pub fn mix_rgb_colors(
+        mix_factor: f32, 
+        from_color: image::Rgb<u8>,
+        to_color: image::Rgb<u8>
+    ) -> image::Rgb<u8> {
+        ColorRGB::from(from_color).lerp(&ColorRGB::from(to_color), mix_factor).to_rgbu8()
+    }
+}
+
+#[test]
+#[allow(deprecated)]
+fn test_channel_mix() {
+    let mix_factor = 0.25;
+    let from_value = 0;
+    let to_value = 100;
+    let result = manip::mix_color_channel(mix_factor, from_value, to_value);
+    assert_eq!(result, 25);
+}
+
+#[test]
+fn test_hex_round_trip() {
+    let color = ColorRGB([255, 136, 0]);
+    assert_eq!(color.to_hex(), "#ff8800");
+    assert_eq!(ColorRGB::from_hex(&color.to_hex()), Ok(color));
+    assert_eq!(ColorRGB::from_hex("FF8800"), Ok(color));
+    assert_eq!(ColorRGB::from_hex("#f80"), Ok(color));
+
+    for invalid in ["", "#", "#ff880", "#ff88000", "#gg8800", "#ff88é", "+f8800"] {
+        assert_eq!(ColorRGB::from_hex(invalid), Err(ColorParseError::InvalidHex(invalid.to_string())));
+    }
+}
+
+#[test]
+fn test_operators_saturate() {
+    let color = ColorRGB([200, 100, 10]);
+    assert_eq!(color + ColorRGB([100, 100, 100]), ColorRGB([255, 200, 110]));
+    assert_eq!(color - ColorRGB([100, 100, 100]), ColorRGB([100, 0, 0]));
+    assert_eq!(color * 1.5, ColorRGB([255, 150, 15]));
+    assert_eq!(color * -1.0, ColorRGB([0, 0, 0]));
+
+    assert_eq!(color.lerp(&ColorRGB([0, 0, 0]), 0.0), color);
+    assert_eq!(color.lerp(&ColorRGB([0, 0, 0]), 2.0), ColorRGB([0, 0, 0]));
+}
+
 #[test]
 fn test_convertion_to_lab() {
     let color = ColorRGB([255, 0, 0]);
-    let lab_color = palette::Lab::from(color.clone());
-    let recreated_color = ColorRGB::from(lab_color.clone());
+    let lab_color = palette::Lab::from(color);
+    let recreated_color = ColorRGB::from(lab_color);
     assert_eq!(color, recreated_color, "Failed! color={color:?}, lab_color={lab_color:?}, recreated_color={recreated_color:?}.");
 }
\ No newline at end of file
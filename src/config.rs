@@ -0,0 +1,684 @@
+//! Declarative processing pipelines loaded from TOML or JSON files.
+//!
+//! [`ProcessingConfig`] describes the same processing as `ditherum dither` arguments:
+//! resizing, preprocessing, palette source, algorithm and input/output pairs. Relative
+//! paths are resolved against directory of the config file.
+//!
+//! # Example
+//! ```
+//! use ditherum::config::{PaletteSource, ProcessingConfig};
+//! use ditherum::image::ProcessingAlgorithm;
+//!
+//! let config = ProcessingConfig::from_toml_str(r##"
+//!     algorithm = "bayer4"
+//!
+//!     [[images]]
+//!     input = "photo.jpg"
+//!     output = "photo_dithered.png"
+//!
+//!     [resize]
+//!     width = 160
+//!
+//!     [palette]
+//!     source = "reduce"
+//!     colors = 4
+//!     keep = ["#000000"]
+//! "##).unwrap();
+//!
+//! assert_eq!(config.algorithm, ProcessingAlgorithm::Bayer4);
+//! assert_eq!(config.resize.width, Some(160));
+//! assert!(matches!(config.palette, PaletteSource::Reduce { colors: 4, .. }));
+//! ```
+
+use std::{fmt::Display, fs, path::{Path, PathBuf}, str::FromStr};
+
+use image::RgbImage;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    algorithms::{noise::Noise, preprocessing::Preprocessing},
+    color::ColorRGB,
+    image::{ProcessingAlgorithm, ResizeFilter, ResizeMode},
+    palette::{PaletteSampling, ReduceStrategy}
+};
+
+use self::errors::ConfigError;
+
+pub mod errors {
+    #[derive(Debug, thiserror::Error)]
+    pub enum ConfigError {
+        #[error("I/O error, reason={0}")]
+        IoError(std::io::Error),
+
+        #[error("TOML parsing failed, reason={0}")]
+        TomlParsingFailed(toml::de::Error),
+
+        #[error("TOML serialization failed, reason={0}")]
+        TomlSerializationFailed(toml::ser::Error),
+
+        #[error("JSON parsing failed, reason={0}")]
+        JsonParsingFailed(serde_json::error::Error),
+
+        #[error("Unsupported config file '{0}', expected '.toml' or '.json' extension.")]
+        UnsupportedFormat(String),
+
+        #[error("Config declares no images.")]
+        NoImages,
+    }
+
+    impl From<std::io::Error> for ConfigError {
+        fn from(value: std::io::Error) -> Self {
+            Self::IoError(value)
+        }
+    }
+
+    impl From<toml::de::Error> for ConfigError {
+        fn from(value: toml::de::Error) -> Self {
+            Self::TomlParsingFailed(value)
+        }
+    }
+
+    impl From<toml::ser::Error> for ConfigError {
+        fn from(value: toml::ser::Error) -> Self {
+            Self::TomlSerializationFailed(value)
+        }
+    }
+
+    impl From<serde_json::error::Error> for ConfigError {
+        fn from(value: serde_json::error::Error) -> Self {
+            Self::JsonParsingFailed(value)
+        }
+    }
+}
+
+/// Whole processing pipeline, every image is processed with the same settings.
+///
+/// Missing sections take default values, which match `ditherum dither` defaults.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProcessingConfig {
+    /// Processing algorithm.
+    pub algorithm: ProcessingAlgorithm,
+
+    /// Color left untouched by dithering and excluded from extracted palettes.
+    #[serde(with = "hex_color_option", skip_serializing_if = "Option::is_none")]
+    pub transparent_key: Option<ColorRGB>,
+
+    /// Grayscale mask image, white areas get full dithering and black ones plain thresholding.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mask: Option<PathBuf>,
+
+    /// Processed images with their outputs.
+    pub images: Vec<ImageJob>,
+
+    /// Resizing applied right after loading.
+    pub resize: ResizeConfig,
+
+    /// Adjustments applied after resizing, before palette extraction.
+    pub preprocessing: Preprocessing,
+
+    /// Noise added right before quantization.
+    pub noise: Noise,
+
+    /// Source of palette used for quantization.
+    pub palette: PaletteSource,
+}
+
+/// Single image processed by the pipeline.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ImageJob {
+    /// Input image path.
+    pub input: PathBuf,
+
+    /// Output image path, format is deduced from extension.
+    pub output: PathBuf,
+}
+
+/// Desired image dimensions, image is left unchanged if neither width nor height is given.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ResizeConfig {
+    /// Desired width, computed from aspect ratio if missing.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+
+    /// Desired height, computed from aspect ratio if missing.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+
+    /// Resampling filter.
+    pub filter: ResizeFilter,
+
+    /// How image is fitted when both width and height are given.
+    pub mode: ResizeMode,
+}
+
+impl ResizeConfig {
+    /// Checks if resizing would leave image unchanged.
+    pub fn is_identity(&self) -> bool {
+        self.width.is_none() && self.height.is_none()
+    }
+
+    /// Dimensions of image with given dimensions after resizing.
+    pub fn output_dimensions(&self, dimensions: (u32, u32)) -> (u32, u32) {
+        if self.is_identity() {
+            return dimensions;
+        }
+        crate::image::manip::resized_dimensions(dimensions, self.width, self.height, self.mode)
+    }
+
+    /// Resizes image to desired dimensions.
+    pub fn apply(&self, source_image: RgbImage) -> RgbImage {
+        if self.is_identity() {
+            return source_image;
+        }
+        crate::image::manip::rgb_image_resize(source_image, self.width, self.height, self.mode, self.filter)
+    }
+}
+
+/// Longer side of thumbnail given by aspect ratio, width recommended for OpenGraph images.
+pub const THUMBNAIL_LONG_SIDE: u32 = 1200;
+
+/// Error returned when parsing thumbnail format fails.
+#[derive(Debug, thiserror::Error)]
+#[error("Invalid thumbnail format '{0}', expected aspect ratio like '16:9' or dimensions like '1200x630'")]
+pub struct ThumbnailFormatError(pub String);
+
+/// Social media thumbnail of exact dimensions, see [`crate::DitherOptions::with_thumbnail`].
+///
+/// Parsed from aspect ratio like `1:1` or `16:9`, whose longer side is [`THUMBNAIL_LONG_SIDE`],
+/// or from dimensions like `1200x630` of OpenGraph image. Image is center-cropped to the aspect
+/// ratio and downscaled to fit. Smaller images are not upscaled, they are centered and padded
+/// with palette color closest to `pad_color` instead.
+///
+/// # Example
+/// ```
+/// use ditherum::config::Thumbnail;
+///
+/// assert_eq!("16:9".parse::<Thumbnail>().unwrap().dimensions(), (1200, 675));
+/// assert_eq!("1200x630".parse::<Thumbnail>().unwrap().dimensions(), (1200, 630));
+/// assert_eq!("1:1".parse::<Thumbnail>().unwrap().fitted_dimensions((3000, 2000)), (1200, 1200));
+/// // Smaller image is only cropped
+/// assert_eq!("1:1".parse::<Thumbnail>().unwrap().fitted_dimensions((800, 400)), (400, 400));
+/// assert!("16:0".parse::<Thumbnail>().is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Thumbnail {
+    pub width: u32,
+    pub height: u32,
+
+    /// Color of padding around images smaller than thumbnail, replaced by the closest palette color.
+    pub pad_color: ColorRGB,
+}
+
+impl Thumbnail {
+    /// Creates thumbnail of given dimensions padded with black.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width: width.max(1),
+            height: height.max(1),
+            pad_color: ColorRGB([0, 0, 0]),
+        }
+    }
+
+    /// Sets color of padding around images smaller than thumbnail.
+    pub fn with_pad_color(mut self, pad_color: ColorRGB) -> Self {
+        self.pad_color = pad_color;
+        self
+    }
+
+    /// Dimensions of the thumbnail.
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Dimensions of image with given dimensions, center-cropped to thumbnail aspect ratio and downscaled to fit.
+    pub fn fitted_dimensions(&self, dimensions: (u32, u32)) -> (u32, u32) {
+        let (width, height) = (dimensions.0 as u64, dimensions.1 as u64);
+        let (thumbnail_width, thumbnail_height) = (self.width as u64, self.height as u64);
+        // Aspect ratios compared by cross multiplication, exact for any dimensions
+        let (cropped_width, cropped_height) = if width * thumbnail_height > height * thumbnail_width {
+            (height * thumbnail_width / thumbnail_height, height)
+        } else {
+            (width, width * thumbnail_height / thumbnail_width)
+        };
+        if cropped_width >= thumbnail_width || cropped_height >= thumbnail_height {
+            return self.dimensions();
+        }
+        ((cropped_width as u32).max(1), (cropped_height as u32).max(1))
+    }
+
+    /// Resizing of image with given dimensions into the thumbnail, using given filter.
+    pub fn resize_config(&self, dimensions: (u32, u32), filter: ResizeFilter) -> ResizeConfig {
+        let (width, height) = self.fitted_dimensions(dimensions);
+        ResizeConfig {
+            width: Some(width),
+            height: Some(height),
+            filter,
+            mode: ResizeMode::Fill,
+        }
+    }
+}
+
+impl FromStr for Thumbnail {
+    type Err = ThumbnailFormatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ThumbnailFormatError(s.to_string());
+        let parse_pair = |separator: char| -> Result<Option<(u32, u32)>, ThumbnailFormatError> {
+            let Some((first, second)) = s.split_once(separator) else {
+                return Ok(None);
+            };
+            let first = first.trim().parse::<u32>().map_err(|_| error())?;
+            let second = second.trim().parse::<u32>().map_err(|_| error())?;
+            if first == 0 || second == 0 {
+                return Err(error());
+            }
+            Ok(Some((first, second)))
+        };
+
+        if let Some((width, height)) = parse_pair('x')? {
+            return Ok(Self::new(width, height));
+        }
+        let (aspect_width, aspect_height) = parse_pair(':')?.ok_or_else(error)?;
+        let long_side = THUMBNAIL_LONG_SIDE as f64;
+        let (width, height) = if aspect_width >= aspect_height {
+            (long_side, long_side * aspect_height as f64 / aspect_width as f64)
+        } else {
+            (long_side * aspect_width as f64 / aspect_height as f64, long_side)
+        };
+        Ok(Self::new(width.round() as u32, height.round() as u32))
+    }
+}
+
+impl Display for Thumbnail {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}x{}", self.width, self.height)
+    }
+}
+
+/// Source of palette used for quantization, selected with `source` key.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "source", rename_all = "lowercase", deny_unknown_fields)]
+pub enum PaletteSource {
+    /// Palette loaded from JSON file.
+    File {
+        path: PathBuf
+    },
+
+    /// Palette extracted from image and reduced to given colors count.
+    Reduce {
+        colors: usize,
+
+        #[serde(default)]
+        sampling: PaletteSampling,
+
+        /// Colors guaranteed to survive reduction.
+        #[serde(default, with = "hex_colors", skip_serializing_if = "Vec::is_empty")]
+        keep: Vec<ColorRGB>,
+
+        /// Minimal CIEDE2000 difference between reduced colors.
+        #[serde(default)]
+        min_delta_e: f32,
+
+        /// Clustering backend of reduction.
+        #[serde(default)]
+        strategy: ReduceStrategy,
+
+        /// Reduce one palette from all images instead of one palette per image.
+        #[serde(default)]
+        shared: bool,
+
+        /// Path to save the reduced palette, requires `shared` if multiple images are processed.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        output: Option<PathBuf>,
+    },
+
+    /// Smallest palette whose thresholded image has mean CIEDE2000 error up to `max_error`.
+    Auto {
+        #[serde(default = "default_max_error")]
+        max_error: f32,
+
+        #[serde(default)]
+        sampling: PaletteSampling,
+    },
+}
+
+impl Default for PaletteSource {
+    fn default() -> Self {
+        Self::Reduce {
+            colors: 8,
+            sampling: PaletteSampling::default(),
+            keep: Vec::new(),
+            min_delta_e: 0.0,
+            strategy: ReduceStrategy::default(),
+            shared: false,
+            output: None,
+        }
+    }
+}
+
+fn default_max_error() -> f32 {
+    4.0
+}
+
+impl ProcessingConfig {
+    /// Parses config from TOML text, paths are left as they are.
+    pub fn from_toml_str(s: &str) -> Result<Self, ConfigError> {
+        Ok(toml::from_str(s)?)
+    }
+
+    /// Parses config from JSON text, paths are left as they are.
+    pub fn from_json_str(s: &str) -> Result<Self, ConfigError> {
+        Ok(serde_json::from_str(s)?)
+    }
+
+    /// Serializes config to TOML text.
+    pub fn to_toml_string(&self) -> Result<String, ConfigError> {
+        Ok(toml::to_string(self)?)
+    }
+
+    /// Serializes config to pretty JSON text.
+    pub fn to_json_string(&self) -> Result<String, ConfigError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Loads config from `.toml` or `.json` file.
+    ///
+    /// Relative paths are resolved against directory of the config file, so that
+    /// pipeline works regardless of current working directory.
+    pub fn load<P>(path: P) -> Result<Self, ConfigError>
+    where
+        P: AsRef<Path>
+    {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)?;
+        let mut config = match ConfigFormat::of(path)? {
+            ConfigFormat::Toml => Self::from_toml_str(&content)?,
+            ConfigFormat::Json => Self::from_json_str(&content)?,
+        };
+
+        if config.images.is_empty() {
+            return Err(ConfigError::NoImages);
+        }
+        if let Some(base_dir) = path.parent() {
+            config.resolve_paths(base_dir);
+        }
+        Ok(config)
+    }
+
+    /// Saves config to `.toml` or `.json` file, format is deduced from extension.
+    pub fn save<P>(&self, path: P) -> Result<(), ConfigError>
+    where
+        P: AsRef<Path>
+    {
+        let path = path.as_ref();
+        let content = match ConfigFormat::of(path)? {
+            ConfigFormat::Toml => self.to_toml_string()?,
+            ConfigFormat::Json => self.to_json_string()?,
+        };
+        fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// Joins every relative path of config with base directory.
+    pub fn resolve_paths(&mut self, base_dir: &Path) {
+        let resolve = |path: &mut PathBuf| {
+            if path.is_relative() {
+                *path = base_dir.join(&*path);
+            }
+        };
+
+        for job in self.images.iter_mut() {
+            resolve(&mut job.input);
+            resolve(&mut job.output);
+        }
+        if let Some(mask) = self.mask.as_mut() {
+            resolve(mask);
+        }
+        match &mut self.palette {
+            PaletteSource::File { path } => resolve(path),
+            PaletteSource::Reduce { output: Some(output), .. } => resolve(output),
+            _ => {},
+        }
+    }
+}
+
+/// Error returned when parsing output template fails.
+#[derive(Debug, thiserror::Error)]
+#[error("Invalid output template '{0}', expected fields {{stem}}, {{ext}}, {{algo}}, {{colors}}, {{width}} or {{height}}")]
+pub struct OutputTemplateError(pub String);
+
+/// Output path with fields filled for each processed image, like `out/{stem}_{algo}_{colors}.png`.
+///
+/// Supported fields are `{stem}` and `{ext}` of input file name, `{algo}` name of processing
+/// algorithm, `{colors}` count of palette colors and `{width}`, `{height}` of processed image.
+///
+/// # Example
+/// ```
+/// use ditherum::config::{OutputFields, OutputTemplate};
+///
+/// let template = "out/{stem}_{algo}_{colors}.png".parse::<OutputTemplate>().unwrap();
+/// let fields = OutputFields { stem: "photo", ext: "jpg", algo: "bayer4", colors: Some(8), width: 160, height: 90 };
+///
+/// assert_eq!(template.render(&fields), std::path::PathBuf::from("out/photo_bayer4_8.png"));
+/// assert!("out/{name}.png".parse::<OutputTemplate>().is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputTemplate(String);
+
+/// Values of [`OutputTemplate`] fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutputFields<'a> {
+    /// Input file name without extension.
+    pub stem: &'a str,
+
+    /// Input file extension.
+    pub ext: &'a str,
+
+    /// Name of processing algorithm.
+    pub algo: &'a str,
+
+    /// Palette colors count, `{colors}` is left unfilled if not known yet.
+    pub colors: Option<usize>,
+
+    /// Processed image width.
+    pub width: u32,
+
+    /// Processed image height.
+    pub height: u32,
+}
+
+impl OutputTemplate {
+    const FIELDS: [&'static str; 6] = ["stem", "ext", "algo", "colors", "width", "height"];
+
+    /// Checks if path contains template fields and has to be rendered for each image.
+    pub fn is_template(path: &Path) -> bool {
+        path.to_string_lossy().contains('{')
+    }
+
+    /// Fills fields of the template.
+    pub fn render(&self, fields: &OutputFields) -> PathBuf {
+        let mut rendered = String::with_capacity(self.0.len());
+        let mut rest = self.0.as_str();
+        // Safe unwraps, braces are validated when parsing
+        while let Some(start) = rest.find('{') {
+            let end = start + rest[start..].find('}').unwrap();
+            rendered.push_str(&rest[..start]);
+            match &rest[start + 1..end] {
+                "stem" => rendered.push_str(fields.stem),
+                "ext" => rendered.push_str(fields.ext),
+                "algo" => rendered.push_str(fields.algo),
+                "colors" => match fields.colors {
+                    Some(colors) => rendered.push_str(&colors.to_string()),
+                    None => rendered.push_str("{colors}"),
+                },
+                "width" => rendered.push_str(&fields.width.to_string()),
+                "height" => rendered.push_str(&fields.height.to_string()),
+                _ => unreachable!(),
+            }
+            rest = &rest[end + 1..];
+        }
+        rendered.push_str(rest);
+        PathBuf::from(rendered)
+    }
+}
+
+impl FromStr for OutputTemplate {
+    type Err = OutputTemplateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut rest = s;
+        while let Some(start) = rest.find(['{', '}']) {
+            let end = match rest[start..].find('}') {
+                Some(end) if rest[start..].starts_with('{') => start + end,
+                _ => return Err(OutputTemplateError(s.to_string())),
+            };
+            if !Self::FIELDS.contains(&&rest[start + 1..end]) {
+                return Err(OutputTemplateError(s.to_string()));
+            }
+            rest = &rest[end + 1..];
+        }
+        Ok(Self(s.to_string()))
+    }
+}
+
+impl Display for OutputTemplate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Supported config file formats.
+enum ConfigFormat {
+    Toml,
+    Json,
+}
+
+impl ConfigFormat {
+    fn of(path: &Path) -> Result<Self, ConfigError> {
+        let extension = path.extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("toml") => Ok(Self::Toml),
+            Some("json") => Ok(Self::Json),
+            _ => Err(ConfigError::UnsupportedFormat(path.display().to_string())),
+        }
+    }
+}
+
+/// Serializes colors as hex strings like `"#ff00ff"`, more readable in configs than arrays.
+mod hex_colors {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use crate::color::ColorRGB;
+
+    pub fn serialize<S: Serializer>(colors: &[ColorRGB], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(colors.iter().map(ColorRGB::to_hex))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<ColorRGB>, D::Error> {
+        Vec::<String>::deserialize(deserializer)?
+            .iter()
+            .map(|hex| hex.parse().map_err(serde::de::Error::custom))
+            .collect()
+    }
+}
+
+/// Optional color serialized as hex string, see [`hex_colors`].
+mod hex_color_option {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use crate::color::ColorRGB;
+
+    pub fn serialize<S: Serializer>(color: &Option<ColorRGB>, serializer: S) -> Result<S::Ok, S::Error> {
+        match color {
+            Some(color) => serializer.serialize_some(&color.to_hex()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<ColorRGB>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|hex| hex.parse().map_err(serde::de::Error::custom))
+            .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_round_trip() {
+        let config = ProcessingConfig {
+            algorithm: ProcessingAlgorithm::FloydSteinbergLab,
+            transparent_key: Some(ColorRGB([255, 0, 255])),
+            images: vec![ImageJob { input: "a.png".into(), output: "out/a.png".into() }],
+            resize: ResizeConfig { width: Some(64), filter: ResizeFilter::Nearest, ..Default::default() },
+            palette: PaletteSource::Reduce {
+                colors: 6,
+                sampling: PaletteSampling::Random { samples: 100, seed: 3 },
+                keep: vec![ColorRGB([0, 0, 0])],
+                min_delta_e: 5.0,
+                strategy: ReduceStrategy::Agglomerative,
+                shared: true,
+                output: Some("palette.json".into()),
+            },
+            ..Default::default()
+        };
+
+        let toml_text = config.to_toml_string().unwrap();
+        assert!(toml_text.contains("\"#ff00ff\""), "{toml_text}");
+        assert_eq!(ProcessingConfig::from_toml_str(&toml_text).unwrap(), config);
+        assert_eq!(ProcessingConfig::from_json_str(&config.to_json_string().unwrap()).unwrap(), config);
+    }
+
+    #[test]
+    fn test_config_defaults_and_errors() {
+        let config = ProcessingConfig::from_toml_str("[[images]]\ninput = \"a.png\"\noutput = \"b.png\"\n").unwrap();
+        assert_eq!(config.algorithm, ProcessingAlgorithm::FloydSteinbergRgb);
+        assert_eq!(config.palette, PaletteSource::default());
+        assert!(config.resize.is_identity());
+
+        let config = ProcessingConfig::from_json_str(r#"{"palette": {"source": "auto"}}"#).unwrap();
+        assert_eq!(config.palette, PaletteSource::Auto { max_error: 4.0, sampling: PaletteSampling::All });
+
+        assert!(ProcessingConfig::from_toml_str("algoritm = \"fs-rgb\"").is_err());
+        assert!(ProcessingConfig::from_toml_str("algorithm = \"unknown\"").is_err());
+        assert!(ProcessingConfig::from_toml_str("[palette]\nsource = \"reduce\"\ncolors = 4\nkeep = [\"#zz0000\"]").is_err());
+    }
+
+    #[test]
+    fn test_config_resolve_paths() {
+        let mut config = ProcessingConfig {
+            images: vec![ImageJob { input: "a.png".into(), output: "/tmp/b.png".into() }],
+            palette: PaletteSource::File { path: "palette.json".into() },
+            ..Default::default()
+        };
+        config.resolve_paths(Path::new("pipelines"));
+
+        assert_eq!(config.images[0].input, Path::new("pipelines/a.png"));
+        assert_eq!(config.images[0].output, Path::new("/tmp/b.png"));
+        assert_eq!(config.palette, PaletteSource::File { path: "pipelines/palette.json".into() });
+    }
+
+    #[test]
+    fn test_output_template() {
+        let template = "{stem}/{width}x{height}.{ext}".parse::<OutputTemplate>().unwrap();
+        let fields = OutputFields { stem: "a", ext: "png", algo: "fs-rgb", colors: None, width: 3, height: 4 };
+        assert_eq!(template.render(&fields), Path::new("a/3x4.png"));
+
+        let template = "{stem}_{colors}.png".parse::<OutputTemplate>().unwrap();
+        assert_eq!(template.render(&fields), Path::new("a_{colors}.png"));
+        assert_eq!(template.to_string(), "{stem}_{colors}.png");
+
+        for invalid in ["{stem", "stem}", "{}", "{{stem}}", "{Stem}.png"] {
+            assert!(invalid.parse::<OutputTemplate>().is_err(), "{invalid}");
+        }
+        assert!(OutputTemplate::is_template(Path::new("out/{stem}.png")));
+        assert!(!OutputTemplate::is_template(Path::new("out/stem.png")));
+    }
+}
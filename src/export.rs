@@ -0,0 +1,626 @@
+//! Exporting processed images to formats consumed outside of image editors.
+
+use std::{collections::HashMap, fmt::Write};
+
+use image::{Rgb, RgbImage};
+
+use crate::{color::ColorRGB, indexed::IndexedImage, text};
+
+use self::errors::ExportError;
+
+pub mod errors {
+    #[derive(Debug, thiserror::Error)]
+    pub enum ExportError {
+        #[error("Chart of {width}x{height} cells exceeds limit of {max} cells per side.")]
+        ChartTooLarge {
+            width: u32,
+            height: u32,
+            max: u32,
+        },
+
+        #[cfg(feature = "pdf")]
+        #[error("Failed to write PDF: {0}")]
+        Pdf(#[from] printpdf::Error),
+    }
+}
+
+/// Number of pixel indices written in one line of C array.
+const C_INDICES_PER_LINE: usize = 16;
+
+/// Every this many cells chart grid line is drawn bold, as in printed cross-stitch charts.
+const CHART_MAJOR_GRID_STEP: u32 = 10;
+
+const CHART_BACKGROUND_COLOR: Rgb<u8> = Rgb([255, 255, 255]);
+const CHART_MINOR_GRID_COLOR: Rgb<u8> = Rgb([160, 160, 160]);
+const CHART_MAJOR_GRID_COLOR: Rgb<u8> = Rgb([0, 0, 0]);
+const CHART_TEXT_COLOR: Rgb<u8> = Rgb([0, 0, 0]);
+
+/// Gap around chart and legend parts.
+const CHART_MARGIN: u32 = 8;
+
+/// Largest number of chart cells per side, more is unreadable on paper and a huge image to render.
+pub const MAX_CHART_CELLS_PER_SIDE: u32 = 500;
+
+/// Largest side of a single chart cell in pixels, bigger values are clamped.
+pub const MAX_CHART_CELL_SIZE: u32 = 64;
+
+/// Generates C header with palette and image stored as palette indices, row by row.
+///
+/// Identifiers are prefixed with `name` turned into valid C identifier. Indices are
+/// `uint8_t` for palettes up to 256 colors, `uint16_t` otherwise.
+///
+/// # Example
+/// ```
+/// use ditherum::{export, indexed::IndexedImage, palette::PaletteRGB};
+///
+/// let img = image::RgbImage::from_pixel(2, 1, image::Rgb([255, 255, 255]));
+/// let indexed = IndexedImage::from_rgb_image(&img, &PaletteRGB::black_and_white()).unwrap();
+/// let header = export::to_c_header("my sprite", &indexed);
+/// assert!(header.contains("#define MY_SPRITE_WIDTH 2"));
+/// assert!(header.contains("static const uint8_t my_sprite_palette[2][3]"));
+/// ```
+pub fn to_c_header(name: &str, indexed: &IndexedImage) -> String {
+    let identifier = c_identifier(name);
+    let macro_prefix = identifier.to_ascii_uppercase();
+    let palette = indexed.palette();
+    let index_type = if palette.len() <= 256 { "uint8_t" } else { "uint16_t" };
+
+    let pixel_indices = indexed.indices().iter().collect::<Vec<_>>();
+
+    let mut header = String::new();
+    // Writing to String never fails
+    let _ = writeln!(header, "// Generated by ditherum");
+    let _ = writeln!(header, "#ifndef {macro_prefix}_H");
+    let _ = writeln!(header, "#define {macro_prefix}_H\n");
+    let _ = writeln!(header, "#include <stdint.h>\n");
+    let _ = writeln!(header, "#define {macro_prefix}_WIDTH {}", indexed.width());
+    let _ = writeln!(header, "#define {macro_prefix}_HEIGHT {}", indexed.height());
+    let _ = writeln!(header, "#define {macro_prefix}_PALETTE_SIZE {}\n", palette.len());
+
+    let _ = writeln!(header, "static const uint8_t {identifier}_palette[{}][3] = {{", palette.len());
+    for color in palette.iter() {
+        let [r, g, b] = color.0;
+        let _ = writeln!(header, "    {{0x{r:02x}, 0x{g:02x}, 0x{b:02x}}},");
+    }
+    let _ = writeln!(header, "}};\n");
+
+    let _ = writeln!(header, "static const {index_type} {identifier}_pixels[{}] = {{", pixel_indices.len());
+    for line in pixel_indices.chunks(C_INDICES_PER_LINE) {
+        let line = line.iter()
+            .map(|idx| idx.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let _ = writeln!(header, "    {line},");
+    }
+    let _ = writeln!(header, "}};\n");
+    let _ = writeln!(header, "#endif // {macro_prefix}_H");
+
+    header
+}
+
+/// Generates CSV grid of 1-based palette numbers, one line per image row, as used by [`to_chart_image`].
+///
+/// # Example
+/// ```
+/// use ditherum::{export, indexed::IndexedImage, palette::PaletteRGB};
+///
+/// let palette = PaletteRGB::black_and_white();
+/// let img = image::RgbImage::from_fn(3, 2, |x, y| palette[((x + y) % 2) as usize].to_rgbu8());
+/// let indexed = IndexedImage::from_rgb_image(&img, &palette).unwrap();
+/// assert_eq!(export::to_chart_csv(&indexed), "1,2,1\n2,1,2\n");
+/// ```
+pub fn to_chart_csv(indexed: &IndexedImage) -> String {
+    let indices = indexed.indices().iter().collect::<Vec<_>>();
+    indices.chunks(indexed.width().max(1) as usize)
+        .map(|row| {
+            let line = row.iter()
+                .map(|idx| (idx + 1).to_string())
+                .collect::<Vec<_>>()
+                .join(",");
+            line + "\n"
+        })
+        .collect()
+}
+
+/// Generates CSV with number, hex color and pixels count of every palette color.
+///
+/// # Example
+/// ```
+/// use ditherum::{export, indexed::IndexedImage, palette::PaletteRGB};
+///
+/// let palette = PaletteRGB::black_and_white();
+/// let img = image::RgbImage::from_pixel(2, 2, palette[0].to_rgbu8());
+/// let indexed = IndexedImage::from_rgb_image(&img, &palette).unwrap();
+/// assert_eq!(export::to_color_counts_csv(&indexed), format!("number,color,count\n1,{},4\n2,{},0\n", palette[0].to_hex(), palette[1].to_hex()));
+/// ```
+pub fn to_color_counts_csv(indexed: &IndexedImage) -> String {
+    let counts = indexed.color_counts();
+    let mut csv = String::from("number,color,count\n");
+    for (idx, (color, count)) in indexed.palette().iter().zip(counts).enumerate() {
+        // Writing to String never fails
+        let _ = writeln!(csv, "{},{},{}", idx + 1, color.to_hex(), count);
+    }
+    csv
+}
+
+/// Generates SVG with one path per palette color, pixels of the color traced as rectangles.
+///
+/// Horizontal runs of the same color are merged with identical runs in following rows,
+/// so solid areas become few rectangles instead of a path per pixel. Paths get `color-N` ids
+/// with 1-based palette numbers, for laser cutters and plotters treating them as layers.
+/// Colors missing from the image get no path.
+///
+/// # Example
+/// ```
+/// use ditherum::{export, indexed::IndexedImage, palette::PaletteRGB};
+///
+/// let img = image::RgbImage::from_pixel(4, 3, image::Rgb([255, 255, 255]));
+/// let svg = export::to_svg(&IndexedImage::from_rgb_image(&img, &PaletteRGB::black_and_white()).unwrap());
+/// assert!(svg.contains(r#"viewBox="0 0 4 3""#));
+/// assert!(svg.contains(r##"fill="#ffffff" d="M0 0h4v3h-4z""##));
+/// ```
+pub fn to_svg(indexed: &IndexedImage) -> String {
+    let (width, height) = indexed.dimensions();
+    let palette = indexed.palette();
+    let indices = indexed.indices().iter().collect::<Vec<_>>();
+
+    // Rectangles of every color as (x, y, width, height), open ones keyed by run
+    let mut rects = vec![Vec::new(); palette.len()];
+    let mut open_rects = HashMap::<(u32, u32, usize), u32>::new();
+    for (y, row) in (0..height).zip(indices.chunks(width.max(1) as usize)) {
+        let mut row_rects = HashMap::new();
+        let mut run_start = 0;
+        for x in 1..=width {
+            if x == width || row[x as usize] != row[run_start as usize] {
+                let run = (run_start, x, row[run_start as usize]);
+                row_rects.insert(run, open_rects.remove(&run).unwrap_or(y));
+                run_start = x;
+            }
+        }
+        for ((x0, x1, color_idx), y0) in open_rects.drain() {
+            rects[color_idx].push((x0, y0, x1 - x0, y - y0));
+        }
+        open_rects = row_rects;
+    }
+    for ((x0, x1, color_idx), y0) in open_rects {
+        rects[color_idx].push((x0, y0, x1 - x0, height - y0));
+    }
+
+    let mut svg = String::new();
+    // Writing to String never fails
+    let _ = writeln!(svg, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}" shape-rendering="crispEdges">"#
+    );
+    for (idx, (color, color_rects)) in palette.iter().zip(rects.iter_mut()).enumerate() {
+        if color_rects.is_empty() {
+            continue;
+        }
+        color_rects.sort_unstable_by_key(|&(x, y, _, _)| (y, x));
+        let path = color_rects.iter()
+            .map(|(x, y, w, h)| format!("M{x} {y}h{w}v{h}h-{w}z"))
+            .collect::<String>();
+        let _ = writeln!(svg, r#"  <path id="color-{}" fill="{}" d="{path}"/>"#, idx + 1, color.to_hex());
+    }
+    let _ = writeln!(svg, "</svg>");
+
+    svg
+}
+
+/// Renders chart for cross-stitch, perler beads or brick mosaics: every pixel becomes a cell.
+///
+/// Cells are filled with their color and labeled with 1-based palette number when the label
+/// fits in `cell_size`. Grid lines separate cells, every 10th is bold. Legend below the grid
+/// lists swatch, number, hex code and count of every palette color.
+///
+/// `cell_size` is clamped to `2..=`[`MAX_CHART_CELL_SIZE`].
+///
+/// # Errors
+/// Returns [`ExportError::ChartTooLarge`] if any side exceeds [`MAX_CHART_CELLS_PER_SIDE`] cells,
+/// see [`check_chart_dimensions`].
+///
+/// # Example
+/// ```
+/// use ditherum::{export, indexed::IndexedImage, palette::PaletteRGB};
+///
+/// let img = image::RgbImage::from_pixel(12, 4, image::Rgb([0, 0, 0]));
+/// let indexed = IndexedImage::from_rgb_image(&img, &PaletteRGB::black_and_white()).unwrap();
+/// let chart = export::to_chart_image(&indexed, 16).unwrap();
+/// assert!(chart.width() >= 12 * 16 && chart.height() >= 4 * 16);
+/// ```
+pub fn to_chart_image(indexed: &IndexedImage, cell_size: u32) -> Result<RgbImage, ExportError> {
+    let (width, height) = indexed.dimensions();
+    check_chart_dimensions(width, height)?;
+    let cell_size = cell_size.clamp(2, MAX_CHART_CELL_SIZE);
+    let palette = indexed.palette();
+    let counts = indexed.color_counts();
+
+    let legend_lines = palette.iter()
+        .zip(&counts)
+        .enumerate()
+        .map(|(idx, (color, count))| format!("{:>3}  {}  {}", idx + 1, color.to_hex(), count))
+        .collect::<Vec<_>>();
+    let legend_row_height = text::GLYPH_HEIGHT + 2 * text::GLYPH_SPACING + 4;
+    let legend_width = legend_lines.iter()
+        .map(|line| legend_row_height + CHART_MARGIN + text::text_size(line, 1).0)
+        .max()
+        .unwrap_or(0);
+
+    let grid_width = width * cell_size + 1;
+    let grid_height = height * cell_size + 1;
+    let mut chart = RgbImage::from_pixel(
+        2 * CHART_MARGIN + grid_width.max(legend_width),
+        3 * CHART_MARGIN + grid_height + palette.len() as u32 * legend_row_height,
+        CHART_BACKGROUND_COLOR
+    );
+
+    // Cells with labels
+    let (label_width, label_height) = text::text_size(&palette.len().to_string(), 1);
+    let are_labels_fitting = label_width + 2 <= cell_size && label_height + 2 <= cell_size;
+    for (pixel_idx, color_idx) in indexed.indices().iter().enumerate() {
+        let cell_x = CHART_MARGIN + (pixel_idx as u32 % width) * cell_size;
+        let cell_y = CHART_MARGIN + (pixel_idx as u32 / width) * cell_size;
+        let color = palette[color_idx];
+        fill_rect(&mut chart, cell_x, cell_y, cell_size, cell_size, color.to_rgbu8());
+
+        if are_labels_fitting {
+            let label = (color_idx + 1).to_string();
+            let (text_width, text_height) = text::text_size(&label, 1);
+            text::draw_text(
+                &mut chart,
+                cell_x + (cell_size - text_width) / 2 + 1,
+                cell_y + (cell_size - text_height) / 2 + 1,
+                &label,
+                contrasting_color(&color),
+                1
+            );
+        }
+    }
+
+    // Grid lines, major ones drawn last to stay on top
+    let is_major_line = |line: u32, last_line: u32| line.is_multiple_of(CHART_MAJOR_GRID_STEP) || line == last_line;
+    for is_major in [false, true] {
+        let line_color = if is_major { CHART_MAJOR_GRID_COLOR } else { CHART_MINOR_GRID_COLOR };
+        for column in (0..=width).filter(|&column| is_major_line(column, width) == is_major) {
+            fill_rect(&mut chart, CHART_MARGIN + column * cell_size, CHART_MARGIN, 1, grid_height, line_color);
+        }
+        for row in (0..=height).filter(|&row| is_major_line(row, height) == is_major) {
+            fill_rect(&mut chart, CHART_MARGIN, CHART_MARGIN + row * cell_size, grid_width, 1, line_color);
+        }
+    }
+
+    // Legend
+    let swatch_size = legend_row_height - 2;
+    for (idx, (line, color)) in legend_lines.iter().zip(palette.iter()).enumerate() {
+        let row_y = 2 * CHART_MARGIN + grid_height + idx as u32 * legend_row_height;
+        fill_rect(&mut chart, CHART_MARGIN, row_y, swatch_size, swatch_size, CHART_MAJOR_GRID_COLOR);
+        fill_rect(&mut chart, CHART_MARGIN + 1, row_y + 1, swatch_size - 2, swatch_size - 2, color.to_rgbu8());
+        text::draw_text(&mut chart, CHART_MARGIN + swatch_size + CHART_MARGIN, row_y + (swatch_size - text::GLYPH_HEIGHT) / 2, line, CHART_TEXT_COLOR, 1);
+    }
+
+    Ok(chart)
+}
+
+/// Checks that image of given dimensions can be charted, one cell per pixel.
+///
+/// # Errors
+/// Returns [`ExportError::ChartTooLarge`] if any side exceeds [`MAX_CHART_CELLS_PER_SIDE`] cells.
+pub fn check_chart_dimensions(width: u32, height: u32) -> Result<(), ExportError> {
+    if width > MAX_CHART_CELLS_PER_SIDE || height > MAX_CHART_CELLS_PER_SIDE {
+        return Err(ExportError::ChartTooLarge { width, height, max: MAX_CHART_CELLS_PER_SIDE });
+    }
+    Ok(())
+}
+
+/// Paper size of printable chart, see [`to_chart_pdf`].
+#[cfg(feature = "pdf")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, clap::ValueEnum)]
+pub enum PageSize {
+    /// 210x297 mm
+    #[default]
+    #[value(name = "a4")]
+    A4,
+
+    /// 297x420 mm, for large charts
+    #[value(name = "a3")]
+    A3,
+
+    /// 8.5x11 in
+    #[value(name = "letter")]
+    Letter,
+}
+
+#[cfg(feature = "pdf")]
+impl PageSize {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::A4 => "a4",
+            Self::A3 => "a3",
+            Self::Letter => "letter",
+        }
+    }
+
+    /// Portrait width and height in PDF points, 1/72 of inch.
+    pub fn dimensions(&self) -> (f32, f32) {
+        match self {
+            Self::A4 => (595.28, 841.89),
+            Self::A3 => (841.89, 1190.55),
+            Self::Letter => (612.0, 792.0),
+        }
+    }
+}
+
+/// Margin of printable chart pages in PDF points.
+#[cfg(feature = "pdf")]
+const PDF_MARGIN: f32 = 36.0;
+
+/// Height of legend row of printable chart in PDF points.
+#[cfg(feature = "pdf")]
+const PDF_LEGEND_ROW_HEIGHT: f32 = 16.0;
+
+/// Cells smaller than this many PDF points get no numbers.
+#[cfg(feature = "pdf")]
+const PDF_MIN_LABELED_CELL: f32 = 7.0;
+
+/// Renders printable PDF chart: [`to_chart_image`] drawn as vectors scaled to fit the page,
+/// available with `pdf` feature.
+///
+/// First page holds the grid of cells labeled with 1-based palette numbers when they are
+/// large enough to read, page is turned to landscape for wide images. Legend with swatch,
+/// number, hex code and count of every palette color follows on as many pages as it needs.
+/// Text uses standard Helvetica font, so no font is embedded.
+///
+/// # Errors
+/// Returns [`ExportError::ChartTooLarge`] if any side exceeds [`MAX_CHART_CELLS_PER_SIDE`] cells
+/// and [`ExportError::Pdf`] if document cannot be written.
+///
+/// # Example
+/// ```
+/// use ditherum::{export::{self, PageSize}, indexed::IndexedImage, palette::PaletteRGB};
+///
+/// let img = image::RgbImage::from_pixel(12, 4, image::Rgb([0, 0, 0]));
+/// let indexed = IndexedImage::from_rgb_image(&img, &PaletteRGB::black_and_white()).unwrap();
+/// let pdf = export::to_chart_pdf(&indexed, PageSize::A4).unwrap();
+/// assert!(pdf.starts_with(b"%PDF-"));
+/// ```
+#[cfg(feature = "pdf")]
+pub fn to_chart_pdf(indexed: &IndexedImage, page_size: PageSize) -> Result<Vec<u8>, ExportError> {
+    use printpdf::{BuiltinFont, Line, Mm, PdfDocument, Point, Pt, Rect};
+
+    let (width, height) = indexed.dimensions();
+    check_chart_dimensions(width, height)?;
+    let palette = indexed.palette();
+    let indices = indexed.indices().iter().collect::<Vec<_>>();
+    let counts = indexed.color_counts();
+
+    let (portrait_width, portrait_height) = page_size.dimensions();
+    let (page_width, page_height) = if width > height { (portrait_height, portrait_width) } else { (portrait_width, portrait_height) };
+    let cell = ((page_width - 2.0 * PDF_MARGIN) / width.max(1) as f32).min((page_height - 2.0 * PDF_MARGIN) / height.max(1) as f32);
+    let grid_left = (page_width - cell * width as f32) / 2.0;
+    let grid_top = page_height - PDF_MARGIN;
+    let cell_origin = |x: u32, y: u32| (grid_left + x as f32 * cell, grid_top - (y + 1) as f32 * cell);
+
+    // Layout is computed in points, printpdf takes millimeters
+    let mm = |points: f32| Mm::from(Pt(points));
+    let rect = |left: f32, bottom: f32, rect_width: f32, rect_height: f32| Rect::new(mm(left), mm(bottom), mm(left + rect_width), mm(bottom + rect_height));
+    let line = |from: (f32, f32), to: (f32, f32)| Line {
+        points: vec![(Point::new(mm(from.0), mm(from.1)), false), (Point::new(mm(to.0), mm(to.1)), false)],
+        is_closed: false,
+    };
+    let fill = |color: Rgb<u8>| {
+        let [r, g, b] = color.0.map(|channel| channel as f32 / 255.0);
+        printpdf::Color::Rgb(printpdf::Rgb::new(r, g, b, None))
+    };
+
+    let (document, chart_page, chart_layer) = PdfDocument::new("Chart", mm(page_width), mm(page_height), "Chart");
+    let font = document.add_builtin_font(BuiltinFont::Helvetica)?;
+    let chart = document.get_page(chart_page).get_layer(chart_layer);
+
+    // Cells, runs of one color are filled as single rectangle
+    for (color_idx, color) in palette.iter().enumerate() {
+        chart.set_fill_color(fill(color.to_rgbu8()));
+        for (y, row) in (0..height).zip(indices.chunks(width.max(1) as usize)) {
+            let mut x = 0;
+            while x < width {
+                if row[x as usize] != color_idx {
+                    x += 1;
+                    continue;
+                }
+                let run_start = x;
+                while x < width && row[x as usize] == color_idx {
+                    x += 1;
+                }
+                let (left, bottom) = cell_origin(run_start, y);
+                chart.add_rect(rect(left, bottom, (x - run_start) as f32 * cell, cell));
+            }
+        }
+    }
+
+    if cell >= PDF_MIN_LABELED_CELL {
+        let font_size = cell * 0.5;
+        for (pixel_idx, &color_idx) in indices.iter().enumerate() {
+            let (left, bottom) = cell_origin(pixel_idx as u32 % width, pixel_idx as u32 / width);
+            let label = (color_idx + 1).to_string();
+            // Helvetica digits are 0.556 of font size wide
+            let text_width = label.len() as f32 * 0.556 * font_size;
+            chart.set_fill_color(fill(contrasting_color(&palette[color_idx])));
+            chart.use_text(label, font_size, mm(left + (cell - text_width) / 2.0), mm(bottom + (cell - 0.7 * font_size) / 2.0), &font);
+        }
+    }
+
+    // Grid lines, major ones drawn last to stay on top
+    let is_major_line = |line: u32, last_line: u32| line.is_multiple_of(CHART_MAJOR_GRID_STEP) || line == last_line;
+    let (grid_right, grid_bottom) = (grid_left + width as f32 * cell, grid_top - height as f32 * cell);
+    for is_major in [false, true] {
+        let (line_color, line_width) = if is_major { (CHART_MAJOR_GRID_COLOR, 0.8) } else { (CHART_MINOR_GRID_COLOR, 0.2) };
+        chart.set_outline_color(fill(line_color));
+        chart.set_outline_thickness(line_width);
+        for column in (0..=width).filter(|&column| is_major_line(column, width) == is_major) {
+            let x = grid_left + column as f32 * cell;
+            chart.add_line(line((x, grid_bottom), (x, grid_top)));
+        }
+        for row in (0..=height).filter(|&row| is_major_line(row, height) == is_major) {
+            let y = grid_top - row as f32 * cell;
+            chart.add_line(line((grid_left, y), (grid_right, y)));
+        }
+    }
+
+    // Legend pages
+    let rows_per_page = (((portrait_height - 2.0 * PDF_MARGIN) / PDF_LEGEND_ROW_HEIGHT) as usize).max(1);
+    let legend_rows = palette.iter().zip(&counts).enumerate().collect::<Vec<_>>();
+    for page_rows in legend_rows.chunks(rows_per_page) {
+        let (legend_page, legend_layer) = document.add_page(mm(portrait_width), mm(portrait_height), "Legend");
+        let legend = document.get_page(legend_page).get_layer(legend_layer);
+        legend.set_outline_color(fill(CHART_MAJOR_GRID_COLOR));
+        legend.set_outline_thickness(0.5);
+        for (row, (idx, (color, count))) in page_rows.iter().enumerate() {
+            let bottom = portrait_height - PDF_MARGIN - (row + 1) as f32 * PDF_LEGEND_ROW_HEIGHT;
+            let swatch_size = PDF_LEGEND_ROW_HEIGHT - 4.0;
+            legend.set_fill_color(fill(color.to_rgbu8()));
+            legend.add_rect(rect(PDF_MARGIN, bottom, swatch_size, swatch_size).with_mode(printpdf::path::PaintMode::FillStroke));
+            legend.set_fill_color(fill(CHART_TEXT_COLOR));
+            legend.use_text(
+                format!("{:>3}   {}   {}", idx + 1, color.to_hex(), count),
+                10.0,
+                mm(PDF_MARGIN + swatch_size + 8.0),
+                mm(bottom + 2.0),
+                &font
+            );
+        }
+    }
+
+    Ok(document.save_to_bytes()?)
+}
+
+fn fill_rect(img: &mut RgbImage, x: u32, y: u32, width: u32, height: u32, color: Rgb<u8>) {
+    for py in y..(y + height).min(img.height()) {
+        for px in x..(x + width).min(img.width()) {
+            img.put_pixel(px, py, color);
+        }
+    }
+}
+
+/// Black or white, whichever is readable on given background.
+fn contrasting_color(background: &ColorRGB) -> Rgb<u8> {
+    if background.to_lab().l > 55.0 { Rgb([0, 0, 0]) } else { Rgb([255, 255, 255]) }
+}
+
+/// Turns arbitrary name into C identifier: invalid characters become `_`, leading digit is prefixed with `_`.
+fn c_identifier(name: &str) -> String {
+    let identifier = name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect::<String>();
+
+    match identifier.chars().next() {
+        None => "image".to_string(),
+        Some(c) if c.is_ascii_digit() => format!("_{identifier}"),
+        Some(_) => identifier,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::palette::PaletteRGB;
+
+    use super::*;
+
+    #[test]
+    fn test_c_identifier() {
+        assert_eq!(c_identifier("hero-sprite.v2"), "hero_sprite_v2");
+        assert_eq!(c_identifier("8bit"), "_8bit");
+        assert_eq!(c_identifier(""), "image");
+    }
+
+    #[test]
+    fn test_c_header_indices() {
+        let palette = PaletteRGB::black_and_white();
+        let img = RgbImage::from_fn(3, 1, |x, _| match x {
+            0 => image::Rgb([0, 0, 0]),
+            1 => image::Rgb([255, 255, 255]),
+            _ => image::Rgb([250, 250, 250]),
+        });
+        let black_idx = palette.iter().position(|color| color.0 == [0, 0, 0]).unwrap();
+        let white_idx = 1 - black_idx;
+
+        let header = to_c_header("img", &IndexedImage::from_rgb_image(&img, &palette).unwrap());
+        assert!(header.contains(&format!("    {black_idx}, {white_idx}, {white_idx},\n")), "{header}");
+        assert!(header.contains("static const uint8_t img_pixels[3]"));
+    }
+
+    #[test]
+    fn test_svg_rects_cover_image() {
+        let palette = PaletteRGB::black_and_white();
+        let black = palette.iter().find(|color| color.0 == [0, 0, 0]).unwrap().to_rgbu8();
+        // Black square with hole in the middle on white background
+        let img = RgbImage::from_fn(5, 5, |x, y| {
+            let is_square = (1..4).contains(&x) && (1..4).contains(&y);
+            if is_square && (x, y) != (2, 2) { black } else { image::Rgb([255, 255, 255]) }
+        });
+
+        let svg = to_svg(&IndexedImage::from_rgb_image(&img, &palette).unwrap());
+        let area = svg.lines()
+            .filter_map(|line| line.split(" d=\"").nth(1))
+            .flat_map(|path| path.trim_end_matches("\"/>").split('z').filter(|rect| !rect.is_empty()).collect::<Vec<_>>())
+            .map(|rect| {
+                let (_, size) = rect.split_once('h').unwrap();
+                let (w, h) = size.split_once('v').unwrap();
+                w.parse::<u32>().unwrap() * h.split_once('h').unwrap().0.parse::<u32>().unwrap()
+            })
+            .sum::<u32>();
+        assert_eq!(area, 25);
+        assert!(svg.contains("M1 1h3v1h-3zM1 2h1v1h-1zM3 2h1v1h-1zM1 3h3v1h-3z"), "{svg}");
+    }
+
+    #[cfg(feature = "pdf")]
+    #[test]
+    fn test_chart_pdf_structure() {
+        let palette = PaletteRGB::grayscale(60);
+        let img = RgbImage::from_fn(80, 20, |x, y| palette[((x + y) % 60) as usize].to_rgbu8());
+
+        let pdf = to_chart_pdf(&IndexedImage::from_rgb_image(&img, &palette).unwrap(), PageSize::A4).unwrap();
+        let document = printpdf::lopdf::Document::load_mem(&pdf).unwrap();
+        let pages = document.get_pages();
+        let media_box = |page_number| document.get_dictionary(pages[&page_number]).unwrap()
+            .get(b"MediaBox").unwrap()
+            .as_array().unwrap()
+            .iter()
+            .map(|value| value.as_float().unwrap())
+            .collect::<Vec<_>>();
+
+        // Wide image turns chart page to landscape, 60 legend rows need second legend page
+        assert_eq!(pages.len(), 3);
+        let is_close = |page_number, (width, height): (f32, f32)| {
+            let media_box = media_box(page_number);
+            (media_box[2] - width).abs() < 0.1 && (media_box[3] - height).abs() < 0.1
+        };
+        assert!(is_close(1, (841.89, 595.28)), "{:?}", media_box(1));
+        assert!(is_close(2, (595.28, 841.89)), "{:?}", media_box(2));
+
+        let oversized = IndexedImage::from_rgb_image(&RgbImage::new(1, MAX_CHART_CELLS_PER_SIDE + 1), &palette).unwrap();
+        assert!(matches!(to_chart_pdf(&oversized, PageSize::A4), Err(ExportError::ChartTooLarge { .. })));
+    }
+
+    #[test]
+    fn test_chart_cells_and_grid() {
+        let palette = PaletteRGB::black_and_white();
+        let black = palette.iter().find(|color| color.0 == [0, 0, 0]).unwrap().to_rgbu8();
+        let img = RgbImage::from_fn(12, 3, |x, _| if x < 6 { black } else { image::Rgb([255, 255, 255]) });
+        let cell_size = 8;
+        let indexed = IndexedImage::from_rgb_image(&img, &palette).unwrap();
+        let chart = to_chart_image(&indexed, cell_size).unwrap();
+
+        // Cell corner away from label and grid keeps its color
+        let cell_px = |x: u32, y: u32| *chart.get_pixel(CHART_MARGIN + x * cell_size + 1, CHART_MARGIN + y * cell_size + 1);
+        assert_eq!(cell_px(0, 0), black);
+        assert_eq!(cell_px(11, 2), image::Rgb([255, 255, 255]));
+
+        // Major line after 10 cells, minor ones elsewhere
+        assert_eq!(*chart.get_pixel(CHART_MARGIN + 10 * cell_size, CHART_MARGIN + 3), CHART_MAJOR_GRID_COLOR);
+        assert_eq!(*chart.get_pixel(CHART_MARGIN + 3 * cell_size, CHART_MARGIN + 3), CHART_MINOR_GRID_COLOR);
+        assert_eq!(indexed.color_counts().iter().sum::<usize>(), 36);
+
+        let oversized = IndexedImage::from_rgb_image(&RgbImage::new(MAX_CHART_CELLS_PER_SIDE + 1, 1), &palette).unwrap();
+        assert!(matches!(to_chart_image(&oversized, cell_size), Err(ExportError::ChartTooLarge { .. })));
+    }
+}
@@ -1,15 +1,293 @@
-use std::{collections::HashMap, path::Path};
+use std::{collections::HashMap, fmt::Display, io::{Cursor, Read, Write}, path::Path, str::FromStr, time::{Duration, Instant}};
 
-use image::{ImageResult, RgbImage};
+use clap::ValueEnum;
+use image::{buffer::ConvertBuffer, DynamicImage, GrayImage, ImageDecoder, ImageFormat, ImageResult, Rgb32FImage, RgbImage};
+use serde::{Deserialize, Serialize};
 
-use crate::{algorithms::{dithering, thresholding}, palette::PaletteRGB};
+use crate::{
+    algorithms::{checkerboard, dithering::{self, ErrorDiffusion}, dot_diffusion, duotone, mixing, noise::Noise, ordered, ostromoukhov, preprocessing::Preprocessing, spatial, thresholding, tiles::{self, TileConstraint}, hardware::HardwareProfile, posterize::{self, ChannelLevels}, regions}, 
+    color::ColorRGB, 
+    indexed::IndexedImage,
+    metadata::ImageMetadata,
+    metrics,
+    palette::{errors::PaletteError, ImageRegion, PaletteRGB},
+    text
+};
+
+/// Grid to which area processed around [`ImageProcessor::with_region`] is aligned, multiple of
+/// ordered dithering matrix sizes, so that patterns line up with processing of whole image.
+const REGION_ALIGNMENT: u32 = 8;
 
 /// Defines different image processing algorithms.
-#[derive(Debug)]
+/// 
+/// Each algorithm has a short name used by the CLI `-a/--algorithm` flag,
+/// see [`ProcessingAlgorithm::name`]. The same name is used when serialized.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, clap::ValueEnum, Serialize, Deserialize)]
 pub enum ProcessingAlgorithm {
+    /// Closest palette color by RGB distance, no dithering
+    #[value(name = "threshold-rgb")]
+    #[serde(rename = "threshold-rgb")]
     ThresholdingRgb,
+
+    /// Closest palette color by CIEDE2000 Lab difference, no dithering
+    #[value(name = "threshold-lab")]
+    #[serde(rename = "threshold-lab")]
     ThresholdingLab,
+
+    /// Floyd-Steinberg error diffusion in sRGB space
+    #[default]
+    #[value(name = "fs-rgb")]
+    #[serde(rename = "fs-rgb")]
     FloydSteinbergRgb,
+
+    /// Floyd-Steinberg error diffusion in Lab space
+    #[value(name = "fs-lab")]
+    #[serde(rename = "fs-lab")]
+    FloydSteinbergLab,
+
+    /// Ostromoukhov error diffusion with weights varying by intensity, no worms in highlights and shadows
+    #[value(name = "ostromoukhov")]
+    #[serde(rename = "ostromoukhov")]
+    Ostromoukhov,
+
+    /// Knuth dot diffusion spreading error by class matrix order, parallel friendly
+    #[value(name = "dot-diffusion")]
+    #[serde(rename = "dot-diffusion")]
+    DotDiffusion,
+
+    /// Ordered dithering using 4x4 Bayer matrix
+    #[value(name = "bayer4")]
+    #[serde(rename = "bayer4")]
+    Bayer4,
+
+    /// Ordered dithering using 8x8 Bayer matrix
+    #[value(name = "bayer8")]
+    #[serde(rename = "bayer8")]
+    Bayer8,
+
+    /// Knoll pattern dithering mixing palette colors with 8x8 Bayer matrix, retro game art look
+    #[value(name = "knoll")]
+    #[serde(rename = "knoll")]
+    Knoll,
+
+    /// Solid colors and 50% checkerboards of two colors only, no stray pixels
+    #[value(name = "checker")]
+    #[serde(rename = "checker")]
+    Checkerboard,
+
+    /// Spatial color quantization minimizing blurred difference, slow but smooth
+    #[value(name = "spatial")]
+    #[serde(rename = "spatial")]
+    SpatialQuant,
+
+    /// Duotone mapping luminance through gradient of 2-3 palette inks, hue is dropped
+    #[value(name = "duotone")]
+    #[serde(rename = "duotone")]
+    Duotone,
+
+    /// Best pair of palette colors per pixel mixed by 8x8 Bayer matrix, smooth gradients on tiny palettes
+    #[value(name = "mix")]
+    #[serde(rename = "mix")]
+    TwoColorMix,
+}
+
+/// Error returned when parsing unknown algorithm name.
+#[derive(Debug, thiserror::Error)]
+#[error("Unknown algorithm '{0}'")]
+pub struct UnknownAlgorithmError(pub String);
+
+impl ProcessingAlgorithm {
+    /// Returns all available algorithms.
+    pub fn all() -> &'static [Self] {
+        <Self as ValueEnum>::value_variants()
+    }
+
+    /// Returns short name of the algorithm, e.g. `fs-rgb`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::ThresholdingRgb => "threshold-rgb",
+            Self::ThresholdingLab => "threshold-lab",
+            Self::FloydSteinbergRgb => "fs-rgb",
+            Self::FloydSteinbergLab => "fs-lab",
+            Self::Ostromoukhov => "ostromoukhov",
+            Self::DotDiffusion => "dot-diffusion",
+            Self::Bayer4 => "bayer4",
+            Self::Bayer8 => "bayer8",
+            Self::Knoll => "knoll",
+            Self::Checkerboard => "checker",
+            Self::SpatialQuant => "spatial",
+            Self::Duotone => "duotone",
+            Self::TwoColorMix => "mix",
+        }
+    }
+
+    /// Returns one line description of the algorithm.
+    pub fn description(&self) -> String {
+        self.to_possible_value()
+            .and_then(|value| value.get_help().map(ToString::to_string))
+            .unwrap_or_default()
+    }
+
+    /// Quantizes `source_image` with this algorithm, writing result to `target_image`.
+    ///
+    /// Target is reallocated only if its dimensions differ from the source ones, so the same
+    /// target can be reused frame to frame. Mask modulates dithering strength and is ignored
+    /// by thresholding and spatial quantization.
+    ///
+    /// # Example
+    /// ```
+    /// use ditherum::{image::ProcessingAlgorithm, palette::PaletteRGB};
+    ///
+    /// let palette = PaletteRGB::black_and_white();
+    /// let mut target_image = image::RgbImage::new(0, 0);
+    /// for level in [64, 192] {
+    ///     let frame = image::RgbImage::from_pixel(8, 8, image::Rgb([level; 3]));
+    ///     ProcessingAlgorithm::Bayer4.process_into(&frame, &palette, None, &mut target_image);
+    ///     assert_eq!(target_image.dimensions(), (8, 8));
+    /// }
+    /// ```
+    ///
+    /// # Panics
+    /// Panics if mask dimensions differ from image dimensions.
+    pub fn process_into(&self, source_image: &RgbImage, palette: &PaletteRGB, mask: Option<&GrayImage>, target_image: &mut RgbImage) {
+        match (self, mask) {
+            (Self::ThresholdingRgb, _) => thresholding::thresholding_rgb_into(source_image, palette, target_image),
+            (Self::ThresholdingLab, _) => thresholding::thresholding_lab_into(source_image, palette, target_image),
+            (Self::FloydSteinbergRgb, None) => dithering::dithering_floyd_steinberg_rgb_into(source_image, palette, target_image),
+            (Self::FloydSteinbergRgb, Some(mask)) => dithering::dithering_floyd_steinberg_rgb_masked_into(source_image, palette, mask, target_image),
+            (Self::FloydSteinbergLab, None) => dithering::dithering_floyd_steinberg_lab_into(source_image, palette, target_image),
+            (Self::FloydSteinbergLab, Some(mask)) => dithering::dithering_floyd_steinberg_lab_masked_into(source_image, palette, mask, target_image),
+            (Self::Ostromoukhov, None) => ostromoukhov::dithering_ostromoukhov_into(source_image, palette, target_image),
+            (Self::Ostromoukhov, Some(mask)) => ostromoukhov::dithering_ostromoukhov_masked_into(source_image, palette, mask, target_image),
+            (Self::DotDiffusion, None) => dot_diffusion::dot_diffusion_into(source_image, palette, target_image),
+            (Self::DotDiffusion, Some(mask)) => dot_diffusion::dot_diffusion_masked_into(source_image, palette, mask, target_image),
+            (Self::Bayer4, None) => ordered::ordered_dithering_bayer_into(source_image, palette, 4, target_image),
+            (Self::Bayer4, Some(mask)) => ordered::ordered_dithering_bayer_masked_into(source_image, palette, 4, mask, target_image),
+            (Self::Bayer8, None) => ordered::ordered_dithering_bayer_into(source_image, palette, 8, target_image),
+            (Self::Bayer8, Some(mask)) => ordered::ordered_dithering_bayer_masked_into(source_image, palette, 8, mask, target_image),
+            (Self::Knoll, None) => ordered::pattern_dithering_knoll_into(source_image, palette, target_image),
+            (Self::Knoll, Some(mask)) => ordered::pattern_dithering_knoll_masked_into(source_image, palette, mask, target_image),
+            (Self::Checkerboard, None) => checkerboard::checkerboard_dithering_into(source_image, palette, target_image),
+            (Self::Checkerboard, Some(mask)) => checkerboard::checkerboard_dithering_masked_into(source_image, palette, mask, target_image),
+            (Self::SpatialQuant, _) => spatial::spatial_quantization_into(source_image, palette, target_image),
+            (Self::Duotone, None) => duotone::duotone_dithering_into(source_image, palette, target_image),
+            (Self::Duotone, Some(mask)) => duotone::duotone_dithering_masked_into(source_image, palette, mask, target_image),
+            (Self::TwoColorMix, None) => mixing::two_color_mixing_dithering_into(source_image, palette, target_image),
+            (Self::TwoColorMix, Some(mask)) => mixing::two_color_mixing_dithering_masked_into(source_image, palette, mask, target_image),
+        }
+    }
+
+    /// Same as [`ProcessingAlgorithm::process_into`], but palette colors are matched by lightness first
+    /// and by chroma second, see [`crate::color::ops::find_closest_lab_color_by_luminance`].
+    /// 
+    /// Thresholding and Floyd-Steinberg algorithms both switch to Lab space variants, remaining
+    /// ones ignore `luminance_band`.
+    pub fn process_luminance_into(&self, source_image: &RgbImage, palette: &PaletteRGB, luminance_band: f32, mask: Option<&GrayImage>, target_image: &mut RgbImage) {
+        match (self, mask) {
+            (Self::ThresholdingRgb | Self::ThresholdingLab, _) => thresholding::thresholding_lab_luminance_into(source_image, palette, luminance_band, target_image),
+            (Self::FloydSteinbergRgb | Self::FloydSteinbergLab, None) => dithering::dithering_floyd_steinberg_lab_luminance_into(source_image, palette, luminance_band, target_image),
+            (Self::FloydSteinbergRgb | Self::FloydSteinbergLab, Some(mask)) => dithering::dithering_floyd_steinberg_lab_luminance_masked_into(source_image, palette, luminance_band, mask, target_image),
+            _ => self.process_into(source_image, palette, mask, target_image),
+        }
+    }
+
+    /// Processes floating point image, e.g. loaded with [`load_image_f32`] from 16-bit file.
+    /// 
+    /// Thresholding, Floyd-Steinberg and Bayer algorithms work on full precision source,
+    /// remaining ones get the image converted to 8 bits first. Error diffusion needs at least
+    /// 2x2 pixels, so single row or column is thresholded, as in [`ImageProcessor::run`].
+    pub fn process_f32(&self, source_image: &Rgb32FImage, palette: &PaletteRGB) -> RgbImage {
+        let is_degenerate = source_image.width() < 2 || source_image.height() < 2;
+        match self {
+            Self::ThresholdingRgb => thresholding::thresholding_rgb_f32(source_image, palette),
+            Self::ThresholdingLab => thresholding::thresholding_lab_f32(source_image, palette),
+            Self::FloydSteinbergRgb if is_degenerate => thresholding::thresholding_rgb_f32(source_image, palette),
+            Self::FloydSteinbergLab | Self::Ostromoukhov if is_degenerate => thresholding::thresholding_lab_f32(source_image, palette),
+            Self::FloydSteinbergRgb => dithering::dithering_floyd_steinberg_rgb_f32(source_image, palette),
+            Self::FloydSteinbergLab => dithering::dithering_floyd_steinberg_lab_f32(source_image, palette),
+            Self::Bayer4 => ordered::ordered_dithering_bayer_f32(source_image, palette, 4),
+            Self::Bayer8 => ordered::ordered_dithering_bayer_f32(source_image, palette, 8),
+            Self::Ostromoukhov | Self::DotDiffusion | Self::Knoll | Self::Checkerboard | Self::SpatialQuant | Self::Duotone | Self::TwoColorMix => {
+                let source_image = image::DynamicImage::ImageRgb32F(source_image.clone()).to_rgb8();
+                let mut target_image = RgbImage::new(source_image.width(), source_image.height());
+                self.process_into(&source_image, palette, None, &mut target_image);
+                target_image
+            }
+        }
+    }
+}
+
+impl FromStr for ProcessingAlgorithm {
+    type Err = UnknownAlgorithmError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        <Self as ValueEnum>::from_str(s, true)
+            .map_err(|_| UnknownAlgorithmError(s.to_string()))
+    }
+}
+
+impl Display for ProcessingAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Filter used for resampling when resizing an image.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, clap::ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResizeFilter {
+    /// Nearest neighbor, keeps hard edges of pixel art
+    Nearest,
+
+    /// Linear filter
+    Triangle,
+
+    /// Cubic filter
+    #[value(name = "catmullrom")]
+    CatmullRom,
+
+    /// Lanczos with window 3, sharp and smooth
+    #[default]
+    Lanczos3,
+
+    /// Average of covered area in linear light, no dark halos around bright details when downscaling
+    #[value(name = "linear-area")]
+    #[serde(rename = "linear-area")]
+    LinearArea,
+}
+
+impl From<ResizeFilter> for image::imageops::FilterType {
+    /// Converts to filter of `image` crate, [`ResizeFilter::LinearArea`] has no counterpart and
+    /// maps to the closest [`image::imageops::FilterType::Triangle`].
+    fn from(value: ResizeFilter) -> Self {
+        match value {
+            ResizeFilter::Nearest => Self::Nearest,
+            ResizeFilter::Triangle | ResizeFilter::LinearArea => Self::Triangle,
+            ResizeFilter::CatmullRom => Self::CatmullRom,
+            ResizeFilter::Lanczos3 => Self::Lanczos3,
+        }
+    }
+}
+
+/// Defines how an image is fitted into desired dimensions.
+/// 
+/// Matters only if both width and height are given, otherwise
+/// missing dimension is computed from the aspect ratio.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, clap::ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResizeMode {
+    /// Keep aspect ratio, result fits within dimensions and may be smaller in one of them
+    Fit,
+
+    /// Keep aspect ratio, result covers dimensions and overflow is cropped
+    #[default]
+    Fill,
+
+    /// Keep aspect ratio, result fits within dimensions and is padded with black to exact size
+    Exact,
+
+    /// Ignore aspect ratio, result is stretched to dimensions
+    Stretch,
 }
 
 /// Represents an image processor that applies a specified algorithm to an image.
@@ -18,10 +296,76 @@ pub struct ImageProcessor {
     source_image: RgbImage,
     palette: PaletteRGB,
     algorithm: ProcessingAlgorithm,
+    preprocessing: Preprocessing,
+    noise: Noise,
+    mask: Option<GrayImage>,
+    transparent_key: Option<ColorRGB>,
+    tile_constraint: Option<TileConstraint>,
+    hardware: Option<HardwareProfile>,
+    levels: Option<ChannelLevels>,
+    luminance_band: Option<f32>,
+    error_diffusion: ErrorDiffusion,
+    hybrid_sensitivity: Option<f32>,
+    region: Option<ImageRegion>,
+    precise_source: Option<Rgb32FImage>,
+}
+
+/// Statistics of [`ImageProcessor::run_with_report`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessingReport {
+    /// Time spent on preprocessing, masks and noise.
+    pub preparation_duration: Duration,
+
+    /// Time spent on dithering itself.
+    pub dithering_duration: Duration,
+
+    /// Number of colors in the palette passed to processor.
+    pub palette_size: usize,
+
+    /// Number of distinct colors in the result.
+    pub unique_colors: usize,
+
+    /// Mean CIEDE2000 color difference between source and processed image.
+    pub mean_delta_e: f32,
+
+    /// Number of pixels that received each palette color.
+    pub usage: metrics::PaletteUsage,
+}
+
+impl ProcessingReport {
+    /// Total time of preparation and dithering.
+    pub fn total_duration(&self) -> Duration {
+        self.preparation_duration + self.dithering_duration
+    }
+}
+
+impl Display for ProcessingReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Preparation: {:?}", self.preparation_duration)?;
+        writeln!(f, "Dithering: {:?}", self.dithering_duration)?;
+        writeln!(f, "Palette size: {}", self.palette_size)?;
+        writeln!(f, "Unique output colors: {}", self.unique_colors)?;
+        writeln!(f, "Mean \u{0394}E: {:.2}", self.mean_delta_e)?;
+        write!(f, "Unused palette colors: {}", self.usage.unused().count())
+    }
+}
+
+/// Opens an image file and rotates it upright according to its EXIF orientation.
+fn open_oriented<P>(path: P) -> ImageResult<DynamicImage>
+where
+    P: AsRef<Path>
+{
+    let mut decoder = image::ImageReader::open(path)?.into_decoder()?;
+    let orientation = decoder.orientation()?;
+    let mut img = DynamicImage::from_decoder(decoder)?;
+    img.apply_orientation(orientation);
+    Ok(img)
 }
 
 /// Loads an image from a given file path.
 /// 
+/// Image is rotated upright according to its EXIF orientation, e.g. photos taken by phones.
+/// 
 /// # Parameters
 /// - `path`: Path to the image file.
 /// 
@@ -31,10 +375,129 @@ pub fn load_image<P>(path: P) -> ImageResult<RgbImage>
 where 
     P: AsRef<Path>
 {
-    let img = image::open(path)?;
+    let img = open_oriented(path)?;
     Ok(img.to_rgb8())
 }
 
+/// Loads an image from a given file path keeping full precision of 16-bit and floating point files.
+/// 
+/// Channels are gamma encoded sRGB in range `0.0..=1.0`, see [`ProcessingAlgorithm::process_f32`].
+/// 
+/// # Parameters
+/// - `path`: Path to the image file.
+/// 
+/// # Returns
+/// A `Result` containing the loaded `Rgb32FImage` or an error.
+pub fn load_image_f32<P>(path: P) -> ImageResult<Rgb32FImage> 
+where 
+    P: AsRef<Path>
+{
+    let img = open_oriented(path)?;
+    Ok(img.into_rgb32f())
+}
+
+/// Image loaded with [`load_image_keeping_depth`].
+#[derive(Debug, Clone)]
+pub enum LoadedImage {
+    /// File with 8 bits per channel.
+    Rgb8(RgbImage),
+
+    /// File with more bits per channel, e.g. 16-bit PNG or TIFF, kept in full precision.
+    Rgb32F(Rgb32FImage),
+}
+
+impl LoadedImage {
+    /// Returns width and height of the image.
+    pub fn dimensions(&self) -> (u32, u32) {
+        match self {
+            Self::Rgb8(img) => img.dimensions(),
+            Self::Rgb32F(img) => img.dimensions(),
+        }
+    }
+}
+
+/// Loads an image from a given file path, keeping full precision only of files with more than
+/// 8 bits per channel, see [`load_image_f32`].
+/// 
+/// # Parameters
+/// - `path`: Path to the image file.
+/// 
+/// # Returns
+/// A `Result` containing the [`LoadedImage`] or an error.
+pub fn load_image_keeping_depth<P>(path: P) -> ImageResult<LoadedImage> 
+where 
+    P: AsRef<Path>
+{
+    let img = open_oriented(path)?;
+    let color = img.color();
+    if color.bits_per_pixel() > 8 * color.channel_count() as u16 {
+        Ok(LoadedImage::Rgb32F(img.into_rgb32f()))
+    } else {
+        Ok(LoadedImage::Rgb8(img.into_rgb8()))
+    }
+}
+
+/// Loads an image from a given file path and converts it to grayscale, e.g. to be used as a mask.
+/// 
+/// # Parameters
+/// - `path`: Path to the image file.
+/// 
+/// # Returns
+/// A `Result` containing the loaded `GrayImage` or an error.
+pub fn load_gray_image<P>(path: P) -> ImageResult<GrayImage> 
+where 
+    P: AsRef<Path>
+{
+    let img = open_oriented(path)?;
+    Ok(img.to_luma8())
+}
+
+/// Loads an image from a reader, e.g. standard input. Image format is guessed from the content.
+/// 
+/// Image is rotated upright according to its EXIF orientation, like in [`load_image`].
+/// 
+/// # Parameters
+/// - `reader`: Source of encoded image bytes, read until the end.
+/// 
+/// # Returns
+/// A `Result` containing the loaded `RgbImage` or an error.
+pub fn load_image_from_reader<R>(mut reader: R) -> ImageResult<RgbImage> 
+where 
+    R: Read
+{
+    let mut buffer = Vec::new();
+    reader.read_to_end(&mut buffer)?;
+    let mut decoder = image::ImageReader::new(Cursor::new(&buffer))
+        .with_guessed_format()?
+        .into_decoder()?;
+    let orientation = decoder.orientation()?;
+    let mut img = DynamicImage::from_decoder(decoder)?;
+    img.apply_orientation(orientation);
+    Ok(img.to_rgb8())
+}
+
+/// Saves an `RgbImage` to the writer, e.g. standard output, using given format.
+/// 
+/// Image is encoded in memory first, so the writer does not need to be seekable.
+/// 
+/// # Parameters
+/// - `writer`: Destination of encoded image bytes.
+/// - `img`: Reference to the image to be saved.
+/// - `format`: Output image format, e.g. `ImageFormat::Png`.
+/// 
+/// # Returns
+/// A `Result` indicating success or failure.
+pub fn save_image_to_writer<W>(mut writer: W, img: &RgbImage, format: ImageFormat) -> ImageResult<()>
+where 
+    W: Write
+{
+    let mut buffer = Cursor::new(Vec::new());
+    img.write_to(&mut buffer, format)?;
+    writer.write_all(buffer.get_ref())?;
+    writer.flush()?;
+    Ok(())
+}
+
 /// Saves an `RgbImage` to the specified file path.
 /// 
 /// # Parameters
@@ -50,6 +513,31 @@ where
     img.save(path)
 }
 
+/// Saves an `RgbImage` to the specified file path with metadata copied from the source image.
+/// 
+/// Metadata is embedded only where the format allows, see [`ImageMetadata::embed`].
+/// 
+/// # Parameters
+/// - `path`: Destination file path, format is deduced from extension.
+/// - `img`: Reference to the image to be saved.
+/// - `metadata`: ICC profile, EXIF and text to be embedded.
+/// 
+/// # Returns
+/// A `Result` indicating success or failure.
+pub fn save_image_with_metadata<P>(path: P, img: &RgbImage, metadata: &ImageMetadata) -> ImageResult<()>
+where 
+    P: AsRef<Path>
+{
+    if metadata.is_empty() {
+        return save_image(path, img);
+    }
+    let format = ImageFormat::from_path(&path)?;
+    let mut buffer = Cursor::new(Vec::new());
+    img.write_to(&mut buffer, format)?;
+    std::fs::write(path, metadata.embed(buffer.get_ref(), format))?;
+    Ok(())
+}
+
 /// Generates a horizontal gradient image.
 /// 
 /// # Parameters
@@ -76,9 +564,12 @@ pub fn generate_test_gradient_image(
 
     let mut img = RgbImage::new(width, height);
 
+    let from_color = ColorRGB::from(from_color);
+    let to_color = ColorRGB::from(to_color);
+
     for x in 0..width {
         let mix_factor = (x as f32) / (width - 1) as f32;
-        let pixel_color = super::color::manip::mix_rgb_colors(mix_factor, from_color, to_color);
+        let pixel_color = from_color.lerp(&to_color, mix_factor).to_rgbu8();
         (0..height).for_each(|y| {
             *img.get_pixel_mut(x, y) = pixel_color;
         });
@@ -87,6 +578,95 @@ pub fn generate_test_gradient_image(
     img
 }
 
+/// Renders image as ANSI true-color text using upper half-block characters.
+/// 
+/// Each character cell shows two vertically adjacent pixels: the top one as foreground
+/// and the bottom one as background, so `width`x`height` image takes `width` columns and
+/// `height / 2` rows (rounded up, missing bottom pixel of odd height is left default).
+/// 
+/// # Example
+/// ```
+/// let img = image::RgbImage::from_pixel(4, 3, image::Rgb([255, 0, 0]));
+/// let text = ditherum::image::get_ansi_image_visualization(&img);
+/// assert_eq!(text.lines().count(), 2);
+/// ```
+pub fn get_ansi_image_visualization(img: &RgbImage) -> String {
+    let mut text = String::new();
+
+    for y in (0..img.height()).step_by(2) {
+        for x in 0..img.width() {
+            let [top_r, top_g, top_b] = img.get_pixel(x, y).0;
+            text += &format!("\x1b[38;2;{};{};{}m", top_r, top_g, top_b);
+            if let Some(bottom_px) = img.get_pixel_checked(x, y + 1) {
+                let [bottom_r, bottom_g, bottom_b] = bottom_px.0;
+                text += &format!("\x1b[48;2;{};{};{}m", bottom_r, bottom_g, bottom_b);
+            }
+            text += "\u{2580}\x1b[0m";
+        }
+        text += "\n";
+    }
+
+    text
+}
+
+/// Lays out labeled images in a grid, label is drawn under each image with [`crate::text`] bitmap font.
+/// 
+/// # Parameters
+/// - `images`: Labels with images, placed row by row. Cells take size of the largest image.
+/// - `columns`: Images count in a row, at least 1.
+/// - `gap`: Spacing around cells and between image and its label.
+/// - `bg`: Background color, labels are white on dark background and black on light one.
+/// 
+/// Tiles narrower than 160 pixels get labels of font scale 1, other ones scale 2. Labels longer
+/// than the cell are truncated.
+/// 
+/// # Example
+/// ```
+/// let tile = image::RgbImage::from_pixel(40, 30, image::Rgb([255, 0, 0]));
+/// let images = vec![("a".to_string(), tile.clone()), ("b".to_string(), tile.clone()), ("c".to_string(), tile)];
+/// let sheet = ditherum::image::contact_sheet(&images, 2, 8, image::Rgb([32, 32, 32]));
+///
+/// // Two rows of two cells, each row with 7 pixels high label and its gap
+/// assert_eq!(sheet.dimensions(), (8 + 2 * (40 + 8), 8 + 2 * (30 + 7 + 8 + 8)));
+/// ```
+pub fn contact_sheet(images: &[(String, RgbImage)], columns: usize, gap: u32, bg: image::Rgb<u8>) -> RgbImage {
+    let columns_count = columns.clamp(1, images.len().max(1));
+    let rows_count = images.len().div_ceil(columns_count);
+    let tile_width = images.iter().map(|(_, tile)| tile.width()).max().unwrap_or(0);
+    let tile_height = images.iter().map(|(_, tile)| tile.height()).max().unwrap_or(0);
+
+    // Small tiles get small labels
+    let label_scale = if tile_width < 160 { 1 } else { 2 };
+    let label_height = text::GLYPH_HEIGHT * label_scale + gap;
+    let cell_width = tile_width + gap;
+    let cell_height = tile_height + label_height + gap;
+    let label_color = if ColorRGB::from(bg).to_lab().l < 50.0 {
+        image::Rgb([255, 255, 255])
+    } else {
+        image::Rgb([0, 0, 0])
+    };
+
+    let mut sheet = RgbImage::from_pixel(
+        gap + columns_count as u32 * cell_width, 
+        gap + rows_count as u32 * cell_height,
+        bg
+    );
+
+    // Labels longer than tile are truncated
+    let max_label_chars = ((tile_width + text::GLYPH_SPACING * label_scale) / ((text::GLYPH_WIDTH + text::GLYPH_SPACING) * label_scale)) as usize;
+
+    for (idx, (label, tile)) in images.iter().enumerate() {
+        let x = gap + (idx % columns_count) as u32 * cell_width;
+        let y = gap + (idx / columns_count) as u32 * cell_height;
+        image::imageops::replace(&mut sheet, tile, x as i64, y as i64);
+
+        let label = label.chars().take(max_label_chars).collect::<String>();
+        text::draw_text(&mut sheet, x, y + tile_height + gap / 2, &label, label_color, label_scale);
+    }
+
+    sheet
+}
+
 pub fn count_image_colors(src_img: &image::RgbImage) -> HashMap<image::Rgb<u8>, usize> {
     src_img.enumerate_pixels()
         .map(|(_, _, px)| px)
@@ -102,55 +682,447 @@ impl ImageProcessor {
         Self {
             source_image,
             palette,
-            algorithm: ProcessingAlgorithm::ThresholdingRgb
+            algorithm: ProcessingAlgorithm::ThresholdingRgb,
+            preprocessing: Preprocessing::default(),
+            noise: Noise::default(),
+            mask: None,
+            transparent_key: None,
+            tile_constraint: None,
+            hardware: None,
+            levels: None,
+            luminance_band: None,
+            error_diffusion: ErrorDiffusion::default(),
+            hybrid_sensitivity: None,
+            region: None,
+            precise_source: None,
         }
     }
 
+    /// Creates a new `ImageProcessor` instance with floating point image, e.g. loaded with
+    /// [`load_image_f32`] from 16-bit file, see [`ImageProcessor::with_precise_source`].
+    pub fn new_f32(source_image: Rgb32FImage, palette: PaletteRGB) -> Self {
+        Self::new(source_image.convert(), palette).with_precise_source(source_image)
+    }
+
     /// Sets the processing algorithm.
     pub fn with_algorithm(mut self, algorithm: ProcessingAlgorithm) -> Self {
         self.algorithm = algorithm;
         self
     }
 
+    /// Sets adjustments applied to the image before quantization.
+    pub fn with_preprocessing(mut self, preprocessing: Preprocessing) -> Self {
+        self.preprocessing = preprocessing;
+        self
+    }
+
+    /// Sets noise added to the image right before quantization, after preprocessing.
+    /// 
+    /// Combined with thresholding algorithms it gives random dithering.
+    pub fn with_noise(mut self, noise: Noise) -> Self {
+        self.noise = noise;
+        self
+    }
+
+    /// Sets grayscale mask modulating dithering strength per pixel.
+    /// 
+    /// White pixels get full dithering, black ones plain thresholding, e.g. faces can stay
+    /// smooth while background is dithered. Mask scales error diffusion, ordered dithering
+    /// offsets and noise. It is resized to the image dimensions if they differ.
+    pub fn with_mask(mut self, mask: GrayImage) -> Self {
+        self.mask = Some(mask);
+        self
+    }
+
+    /// Sets transparent key color.
+    /// 
+    /// Source pixels matching the key exactly bypass processing and keep the key color,
+    /// they neither spread nor receive quantization error. If the key is a palette entry,
+    /// it is reserved for these pixels, so other pixels never map to it.
+    pub fn with_transparent_key(mut self, key: ColorRGB) -> Self {
+        self.transparent_key = Some(key);
+        self
+    }
+
+    /// Limits colors used within each tile, see [`tiles::tiled_dithering`].
+    pub fn with_tile_constraint(mut self, constraint: TileConstraint) -> Self {
+        self.tile_constraint = Some(constraint);
+        self
+    }
+
+    /// Enforces palette and color constraints of retro machine, see [`HardwareProfile::dither`].
+    /// 
+    /// Machine palette replaces the processor palette, mask and tile constraint are ignored.
+    pub fn with_hardware(mut self, hardware: HardwareProfile) -> Self {
+        self.palette = hardware.palette();
+        self.hardware = Some(hardware);
+        self
+    }
+
+    /// Reduces each channel to given levels instead of using palette, see [`posterize::posterize`].
+    /// 
+    /// Processor palette, mask and tile constraint are ignored.
+    pub fn with_levels(mut self, levels: ChannelLevels) -> Self {
+        self.levels = Some(levels);
+        self
+    }
+
+    /// Matches palette colors by lightness before hue, colors within `luminance_band` (Lab ΔL)
+    /// of the best lightness match compete by chroma, see [`ProcessingAlgorithm::process_luminance_into`].
+    /// 
+    /// Keeps dark reds from mapping to black when palette lacks them. Ignored with hardware
+    /// profile, levels and tile constraint.
+    pub fn with_luminance_matching(mut self, luminance_band: f32) -> Self {
+        self.luminance_band = Some(luminance_band);
+        self
+    }
+
+    /// Sets limits of error accumulated by Floyd-Steinberg algorithms, see [`ErrorDiffusion`].
+    /// 
+    /// Ignored by other algorithms and with hardware profile, levels and tile constraint.
+    pub fn with_error_diffusion(mut self, error_diffusion: ErrorDiffusion) -> Self {
+        self.error_diffusion = error_diffusion;
+        self
+    }
+
+    /// Dithers only photographic regions, flat and line art ones get plain thresholding,
+    /// see [`regions::photographic_mask`].
+    /// 
+    /// Detected mask is combined with the one set by [`ImageProcessor::with_mask`].
+    pub fn with_hybrid(mut self, sensitivity: f32) -> Self {
+        self.hybrid_sensitivity = Some(sensitivity);
+        self
+    }
+
+    /// Processes only pixels within `region`, the rest of the result is untouched source image,
+    /// e.g. for editors re-processing only the area under the brush.
+    /// 
+    /// Area around the region aligned to 8 pixel grid is processed, so that ordered patterns
+    /// match processing of the whole image, error diffusion starts at area borders though.
+    /// Region exceeding the image is clipped, region outside of it leaves the image unchanged.
+    /// 
+    /// # Example
+    /// ```
+    /// use ditherum::{image::ImageProcessor, palette::{ImageRegion, PaletteRGB}};
+    ///
+    /// let source_image = image::RgbImage::from_pixel(32, 32, image::Rgb([200, 40, 40]));
+    /// let region = ImageRegion { x: 8, y: 8, width: 4, height: 4 };
+    /// let processed_image = ImageProcessor::new(source_image.clone(), PaletteRGB::black_and_white())
+    ///     .with_region(region)
+    ///     .run();
+    ///
+    /// assert_eq!(processed_image.get_pixel(0, 0), source_image.get_pixel(0, 0));
+    /// assert_ne!(processed_image.get_pixel(9, 9), source_image.get_pixel(9, 9));
+    /// ```
+    pub fn with_region(mut self, region: ImageRegion) -> Self {
+        self.region = Some(region);
+        self
+    }
+
+    /// Sets full precision copy of the source image, e.g. loaded with [`load_image_f32`] from 16-bit file,
+    /// quantized without rounding to 8 bits first, see [`ProcessingAlgorithm::process_f32`].
+    /// 
+    /// Preprocessing, noise, masks, transparent key, hybrid mode, luminance matching, custom error
+    /// diffusion, hardware profile, levels and tile constraint work on 8-bit pixels, with any of
+    /// them or with dimensions differing from the source image the copy is ignored.
+    pub fn with_precise_source(mut self, source_image: Rgb32FImage) -> Self {
+        self.precise_source = Some(source_image);
+        self
+    }
+
     /// Executes the selected algorithm and processes the image.
     pub fn run(self) -> RgbImage {
-        match self.algorithm {
-            ProcessingAlgorithm::ThresholdingRgb => thresholding::thresholding_rgb(self.source_image, self.palette),
-            ProcessingAlgorithm::ThresholdingLab => thresholding::thresholding_lab(self.source_image, self.palette),
-            ProcessingAlgorithm::FloydSteinbergRgb => dithering::dithering_floyd_steinberg_rgb(self.source_image, self.palette),
+        let mut processed_image = RgbImage::new(0, 0);
+        self.run_into(&mut processed_image);
+        processed_image
+    }
+
+    /// Same as [`ImageProcessor::run`], but returns result as palette indices.
+    ///
+    /// With channel levels the palette consists of colors of the result. Pixels left out of
+    /// processing, e.g. transparent key or outside of region, get the closest palette color.
+    ///
+    /// # Example
+    /// ```
+    /// use ditherum::{image::ImageProcessor, palette::PaletteRGB};
+    ///
+    /// let source_image = ditherum::image::generate_test_gradient_image(16, 8, image::Rgb([0, 0, 0]), image::Rgb([255, 255, 255]));
+    /// let indexed = ImageProcessor::new(source_image, PaletteRGB::black_and_white()).run_indexed().unwrap();
+    ///
+    /// assert_eq!(indexed.dimensions(), (16, 8));
+    /// assert_eq!(indexed.palette().len(), 2);
+    /// ```
+    ///
+    /// # Errors
+    /// Returns [`PaletteError::TooManyColorsForIndices`] if palette does not fit [`IndexedImage`] indices.
+    pub fn run_indexed(self) -> Result<IndexedImage, PaletteError> {
+        let palette = self.palette.clone();
+        let is_levels = self.levels.is_some();
+        let processed_image = self.run();
+        let palette = if is_levels { PaletteRGB::from_rgbu8_image(&processed_image) } else { palette };
+        IndexedImage::from_rgb_image(&processed_image, &palette)
+    }
+
+    /// Same as [`ImageProcessor::run`], but also returns [`ProcessingReport`] with durations of phases
+    /// and statistics of the result.
+    ///
+    /// Measuring costs a copy of the source image and a color difference per pixel.
+    ///
+    /// # Example
+    /// ```
+    /// use ditherum::{image::ImageProcessor, palette::PaletteRGB};
+    ///
+    /// let source_image = ditherum::image::generate_test_gradient_image(16, 8, image::Rgb([0, 0, 0]), image::Rgb([255, 255, 255]));
+    /// let (processed_image, report) = ImageProcessor::new(source_image, PaletteRGB::black_and_white()).run_with_report();
+    ///
+    /// assert_eq!(processed_image.dimensions(), (16, 8));
+    /// assert_eq!(report.palette_size, 2);
+    /// assert!(report.unique_colors <= 2);
+    /// assert_eq!(report.usage.colors.len(), 2);
+    /// ```
+    pub fn run_with_report(self) -> (RgbImage, ProcessingReport) {
+        let reference_image = self.source_image.clone();
+        let palette = self.palette.clone();
+        let mut processed_image = RgbImage::new(0, 0);
+        let (preparation_duration, dithering_duration) = self.run_into_timed(&mut processed_image);
+
+        let report = ProcessingReport {
+            preparation_duration,
+            dithering_duration,
+            palette_size: palette.len(),
+            unique_colors: count_image_colors(&processed_image).len(),
+            mean_delta_e: metrics::mean_delta_e(&reference_image, &processed_image),
+            usage: metrics::PaletteUsage::measure(&processed_image, &palette),
+        };
+        (processed_image, report)
+    }
+
+    /// Same as [`ImageProcessor::run`], but writes result to `target_image`, see [`ProcessingAlgorithm::process_into`].
+    pub fn run_into(self, target_image: &mut RgbImage) {
+        self.run_into_timed(target_image);
+    }
+
+    /// Runs processing into `target_image`, returns durations of preparation and dithering.
+    fn run_into_timed(mut self, target_image: &mut RgbImage) -> (Duration, Duration) {
+        match self.region.take() {
+            Some(region) => self.run_region_into_timed(region, target_image),
+            None => self.run_image_into_timed(target_image),
         }
     }
+
+    /// Processes area around `region` aligned to [`REGION_ALIGNMENT`] grid and composites
+    /// the region back into the source image.
+    fn run_region_into_timed(mut self, region: ImageRegion, target_image: &mut RgbImage) -> (Duration, Duration) {
+        let (width, height) = self.source_image.dimensions();
+        let mut composited_image = std::mem::take(&mut self.source_image);
+        let Some(region) = region.clipped(width, height) else {
+            *target_image = composited_image;
+            return (Duration::ZERO, Duration::ZERO);
+        };
+
+        let area_x = region.x / REGION_ALIGNMENT * REGION_ALIGNMENT;
+        let area_y = region.y / REGION_ALIGNMENT * REGION_ALIGNMENT;
+        let area_width = ((region.x + region.width).next_multiple_of(REGION_ALIGNMENT) - area_x).min(width - area_x);
+        let area_height = ((region.y + region.height).next_multiple_of(REGION_ALIGNMENT) - area_y).min(height - area_y);
+
+        self.source_image = image::imageops::crop_imm(&composited_image, area_x, area_y, area_width, area_height).to_image();
+        self.mask = self.mask.map(|mask| {
+            let mask = fit_mask(mask, width, height);
+            image::imageops::crop_imm(&mask, area_x, area_y, area_width, area_height).to_image()
+        });
+        self.precise_source = self.precise_source
+            .filter(|precise_source| precise_source.dimensions() == (width, height))
+            .map(|precise_source| image::imageops::crop_imm(&precise_source, area_x, area_y, area_width, area_height).to_image());
+
+        let mut processed_area = RgbImage::new(0, 0);
+        let durations = self.run_image_into_timed(&mut processed_area);
+        let processed_region = image::imageops::crop_imm(&processed_area, region.x - area_x, region.y - area_y, region.width, region.height).to_image();
+        image::imageops::replace(&mut composited_image, &processed_region, region.x as i64, region.y as i64);
+        *target_image = composited_image;
+        durations
+    }
+
+    /// Runs processing of the whole source image into `target_image`.
+    fn run_image_into_timed(self, target_image: &mut RgbImage) -> (Duration, Duration) {
+        let preparation_start = Instant::now();
+        let (width, height) = self.source_image.dimensions();
+        let _span = tracing::debug_span!("processing", algorithm = self.algorithm.name(), width, height).entered();
+        let key_pixels = self.transparent_key.map(|key| {
+            self.source_image.pixels()
+                .map(|px| ColorRGB::from_rgbu8(*px) == key)
+                .collect::<Vec<_>>()
+        });
+
+        let mut palette = self.palette;
+        if let Some(key) = self.transparent_key {
+            // Key color is reserved for key pixels, unless nothing else is left
+            if palette.iter().any(|color| *color != key) {
+                palette.retain(|color| *color != key);
+            }
+        }
+
+        let is_precise_source_usable = self.preprocessing.is_identity()
+            && self.noise.amplitude == 0.0
+            && self.mask.is_none()
+            && self.transparent_key.is_none()
+            && self.hybrid_sensitivity.is_none()
+            && self.luminance_band.is_none()
+            && self.error_diffusion == ErrorDiffusion::default()
+            && self.hardware.is_none()
+            && self.levels.is_none()
+            && self.tile_constraint.is_none();
+        let precise_source = self.precise_source
+            .filter(|precise_source| is_precise_source_usable && precise_source.dimensions() == (width, height));
+
+        let source_image = self.preprocessing.apply(self.source_image);
+        let mask = self.mask.map(|mask| fit_mask(mask, width, height));
+
+        let mask = match (self.hybrid_sensitivity, mask) {
+            (Some(sensitivity), mask) => {
+                let mut detected_mask = regions::photographic_mask(&source_image, sensitivity);
+                if let Some(mask) = mask {
+                    detected_mask.pixels_mut()
+                        .zip(mask.pixels())
+                        .for_each(|(detected_px, mask_px)| detected_px.0[0] = (detected_px.0[0] as u16 * mask_px.0[0] as u16 / 255) as u8);
+                }
+                Some(detected_mask)
+            },
+            (None, mask) => mask,
+        };
+
+        // Key pixels do not spread error, so they are masked out
+        let mask = match (&key_pixels, mask) {
+            (Some(key_pixels), mask) => {
+                let mut mask = mask.unwrap_or_else(|| GrayImage::from_pixel(width, height, image::Luma([255])));
+                mask.pixels_mut()
+                    .zip(key_pixels)
+                    .filter(|(_, &is_key)| is_key)
+                    .for_each(|(mask_px, _)| mask_px.0[0] = 0);
+                Some(mask)
+            },
+            (None, mask) => mask,
+        };
+
+        let source_image = match &mask {
+            Some(mask) => self.noise.apply_masked(source_image, mask),
+            None => self.noise.apply(source_image),
+        };
+
+        // Error diffusion kernels need at least 2x2 pixels, single row or column is thresholded
+        let algorithm = match self.algorithm {
+            ProcessingAlgorithm::FloydSteinbergRgb if width < 2 || height < 2 => ProcessingAlgorithm::ThresholdingRgb,
+            ProcessingAlgorithm::FloydSteinbergLab | ProcessingAlgorithm::Ostromoukhov if width < 2 || height < 2 => ProcessingAlgorithm::ThresholdingLab,
+            algorithm => algorithm,
+        };
+
+        let preparation_duration = preparation_start.elapsed();
+
+        let dithering_start = Instant::now();
+        if let Some(precise_source) = precise_source {
+            *target_image = algorithm.process_f32(&precise_source, &palette);
+        } else {
+            match (self.hardware, self.levels, self.tile_constraint) {
+                (Some(hardware), _, _) => *target_image = hardware.dither(&source_image, algorithm),
+                (None, Some(levels), _) => *target_image = posterize::posterize(&source_image, levels, algorithm),
+                (None, None, Some(constraint)) => tiles::tiled_dithering_into(&source_image, &palette, constraint, algorithm, mask.as_ref(), target_image),
+                (None, None, None) => match (algorithm, self.luminance_band) {
+                    (ProcessingAlgorithm::FloydSteinbergRgb, None) => {
+                        dithering::dithering_floyd_steinberg_rgb_diffusion_into(&source_image, &palette, &self.error_diffusion, mask.as_ref(), target_image);
+                    },
+                    (ProcessingAlgorithm::FloydSteinbergRgb | ProcessingAlgorithm::FloydSteinbergLab, luminance_band) => {
+                        dithering::dithering_floyd_steinberg_lab_diffusion_into(&source_image, &palette, &self.error_diffusion, luminance_band, mask.as_ref(), target_image);
+                    },
+                    (_, Some(luminance_band)) => algorithm.process_luminance_into(&source_image, &palette, luminance_band, mask.as_ref(), target_image),
+                    (_, None) => algorithm.process_into(&source_image, &palette, mask.as_ref(), target_image),
+                },
+            }
+        }
+
+        if let (Some(key), Some(key_pixels)) = (self.transparent_key, key_pixels) {
+            target_image.pixels_mut()
+                .zip(key_pixels)
+                .filter(|(_, is_key)| *is_key)
+                .for_each(|(px, _)| *px = key.to_rgbu8());
+        }
+        (preparation_duration, dithering_start.elapsed())
+    }
+}
+
+/// Resizes mask to image dimensions if they differ.
+fn fit_mask(mask: GrayImage, width: u32, height: u32) -> GrayImage {
+    if mask.dimensions() == (width, height) {
+        mask
+    } else {
+        image::imageops::resize(&mask, width, height, image::imageops::FilterType::Triangle)
+    }
 }
 
 pub mod manip {
     use image::DynamicImage;
-    use palette::white_point::D65;
+    use palette::{color_difference::EuclideanDistance, white_point::D65};
 
     use crate::color;
 
     use super::*;
     
+    /// Reallocates `target_image` to dimensions of `source_image` if they differ, otherwise keeps its buffer.
+    pub fn match_dimensions(target_image: &mut RgbImage, source_image: &RgbImage) {
+        if target_image.dimensions() != source_image.dimensions() {
+            *target_image = RgbImage::new(source_image.width(), source_image.height());
+        }
+    }
+
     /// Converts an `RgbImage` to a 2D vector of `palette::Srgb`.
     pub fn rgb_image_to_float_srgb_vec(source_image: RgbImage) -> (usize, usize, Vec<Vec<palette::Srgb>>) {
+        rgb_image_ref_to_float_srgb_vec(&source_image)
+    }
+
+    /// Converts a borrowed `RgbImage` to a 2D vector of `palette::Srgb`, leaving the image to the caller.
+    pub fn rgb_image_ref_to_float_srgb_vec(source_image: &RgbImage) -> (usize, usize, Vec<Vec<palette::Srgb>>) {
         let (width, height) = (source_image.width() as usize, source_image.height() as usize);
         let mut lab_image = vec![vec![palette::Srgb::new(0.0, 0.0, 0.0); width]; height];
         
         source_image.enumerate_pixels()
             .for_each(|(x, y, rgb_pixel)| {
-                lab_image[y as usize][x as usize] = color::manip::rgbu8_to_srgb(*rgb_pixel)
+                lab_image[y as usize][x as usize] = color::ops::rgbu8_to_srgb(*rgb_pixel)
             });
 
         (width, height, lab_image)
     }
 
+    /// Converts an `Rgb32FImage` to a 2D vector of `palette::Srgb`, without quantization to 8 bits.
+    pub fn rgb32f_image_to_float_srgb_vec(source_image: &Rgb32FImage) -> (usize, usize, Vec<Vec<palette::Srgb>>) {
+        let (width, height) = (source_image.width() as usize, source_image.height() as usize);
+        let srgb_image = source_image.rows()
+            .map(|row| row.map(|pixel| palette::Srgb::new(pixel[0], pixel[1], pixel[2])).collect())
+            .collect();
+
+        (width, height, srgb_image)
+    }
+
+    /// Converts an `Rgb32FImage` to a 2D vector of `palette::Lab<D65, f32>`, without quantization to 8 bits.
+    pub fn rgb32f_image_to_lab_vec(source_image: &Rgb32FImage) -> (usize, usize, Vec<Vec<palette::Lab<D65,f32>>>) {
+        let (width, height, srgb_image) = rgb32f_image_to_float_srgb_vec(source_image);
+        let lab_image = srgb_image.into_iter()
+            .map(|row| row.into_iter().map(color::ops::srgb_to_lab).collect())
+            .collect();
+
+        (width, height, lab_image)
+    }
+
     /// Converts an `RgbImage` to a 2D vector of `palette::Lab<D65, f32>`.
     pub fn rgb_image_to_lab_vec(source_image: RgbImage) -> (usize, usize, Vec<Vec<palette::Lab<D65,f32>>>) {
+        rgb_image_ref_to_lab_vec(&source_image)
+    }
+
+    /// Converts a borrowed `RgbImage` to a 2D vector of `palette::Lab<D65, f32>`, leaving the image to the caller.
+    pub fn rgb_image_ref_to_lab_vec(source_image: &RgbImage) -> (usize, usize, Vec<Vec<palette::Lab<D65,f32>>>) {
         let (width, height) = (source_image.width() as usize, source_image.height() as usize);
         let mut lab_image = vec![vec![palette::Lab::new(0.0, 0.0, 0.0); width]; height];
         
         source_image.enumerate_pixels()
             .for_each(|(x, y, rgb_pixel)| {
-                lab_image[y as usize][x as usize] = color::manip::rgbu8_to_lab(*rgb_pixel)
+                lab_image[y as usize][x as usize] = color::ops::rgbu8_to_lab(*rgb_pixel)
             });
 
         (width, height, lab_image)
@@ -160,7 +1132,7 @@ pub mod manip {
     pub fn lab_vec_to_rgb_image(width: usize, height: usize, lab_vec: Vec<Vec<palette::Lab>>) -> RgbImage {
         RgbImage::from_fn(width as u32, height as u32, |x, y| {
             let lab_color = &lab_vec[y as usize][x as usize];
-            color::manip::lab_to_rgbu8(*lab_color)
+            color::ops::lab_to_rgbu8(*lab_color)
         })
     }
 
@@ -168,24 +1140,64 @@ pub mod manip {
     pub fn srgb_vec_to_rgb_image(width: usize, height: usize, rgb_vec: Vec<Vec<palette::Srgb>>) -> RgbImage {
         RgbImage::from_fn(width as u32, height as u32, |x, y| {
             let srgb_color = &rgb_vec[y as usize][x as usize];
-            color::manip::srgb_to_rgbu8(*srgb_color)
+            color::ops::srgb_to_rgbu8(*srgb_color)
         })
     }
 
     /// Converts a 2D vector of `palette::Srgb` to an `RgbImage` ensuring palette coherency.
     pub fn srgb_vec_to_rgb_image_using_palette(width: usize, height: usize, rgb_vec: Vec<Vec<palette::Srgb>>, palette: &PaletteRGB) -> RgbImage {
-        RgbImage::from_fn(width as u32, height as u32, |x, y| {
-            let srgb_color = &rgb_vec[y as usize][x as usize];
-            palette.find_closest_by_srgb(srgb_color).into()
-        })
+        let mut target_image = RgbImage::new(width as u32, height as u32);
+        srgb_vec_into_rgb_image_using_palette(&rgb_vec, palette, &mut target_image);
+        target_image
+    }
+
+    /// Same as [`srgb_vec_to_rgb_image_using_palette`], but writes to existing image of matching dimensions.
+    pub fn srgb_vec_into_rgb_image_using_palette(rgb_vec: &[Vec<palette::Srgb>], palette: &PaletteRGB, target_image: &mut RgbImage) {
+        target_image.enumerate_pixels_mut()
+            .for_each(|(x, y, pixel)| {
+                let srgb_color = &rgb_vec[y as usize][x as usize];
+                *pixel = palette.find_closest_by_srgb(srgb_color).into();
+            });
+    }
+
+    /// Converts a 2D vector of `palette::Lab` to an `RgbImage` ensuring palette coherency.
+    pub fn lab_vec_to_rgb_image_using_palette(width: usize, height: usize, lab_vec: Vec<Vec<palette::Lab>>, palette: &PaletteRGB) -> RgbImage {
+        let mut target_image = RgbImage::new(width as u32, height as u32);
+        lab_vec_into_rgb_image_using_palette(&lab_vec, palette, &mut target_image);
+        target_image
+    }
+
+    /// Same as [`lab_vec_to_rgb_image_using_palette`], but writes to existing image of matching dimensions.
+    pub fn lab_vec_into_rgb_image_using_palette(lab_vec: &[Vec<palette::Lab>], palette: &PaletteRGB, target_image: &mut RgbImage) {
+        let lab_palette = palette.iter()
+            .map(|color| (color.to_lab(), *color))
+            .collect::<Vec<_>>();
+
+        // Processed values are palette colors already, cheap euclidean distance is enough to pick them
+        target_image.enumerate_pixels_mut()
+            .for_each(|(x, y, pixel)| {
+                let lab_color = &lab_vec[y as usize][x as usize];
+                let (_, closest_color) = lab_palette.iter()
+                    .min_by(|(lab_a, _), (lab_b, _)| lab_color.distance_squared(*lab_a)
+                        .partial_cmp(&lab_color.distance_squared(*lab_b))
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                    )
+                    .unwrap();
+                *pixel = closest_color.to_rgbu8();
+            });
     }
 
     /// Converts an `RgbImage` to a new size while preserving aspect ratio.
+    /// 
+    /// Same as [`rgb_image_resize`] using [`ResizeMode::Fill`] and [`ResizeFilter::Lanczos3`].
     pub fn rgb_image_reshape(src_img: RgbImage, width: Option<u32>, height: Option<u32>) -> RgbImage {
-        let dyn_img = DynamicImage::from(src_img);
+        rgb_image_resize(src_img, width, height, ResizeMode::Fill, ResizeFilter::Lanczos3)
+    }
 
-        let (original_width, original_height) = (dyn_img.width(), dyn_img.height());
-        let (new_width, new_height) = match (width, height) {
+    /// Completes desired dimensions, missing one is computed from the aspect ratio of original dimensions.
+    fn desired_dimensions(original_dimensions: (u32, u32), width: Option<u32>, height: Option<u32>) -> (u32, u32) {
+        let (original_width, original_height) = original_dimensions;
+        match (width, height) {
             (Some(w), Some(h)) => (w, h),
             (None, None) => (original_width, original_height),
             (None, Some(h)) => {
@@ -196,14 +1208,361 @@ pub mod manip {
                 let h = (w as f32 * original_height as f32 / original_width as f32).round() as u32;
                 (w, h)
             },
-        };
+        }
+    }
 
-        dyn_img.resize_to_fill(
-            new_width, 
-            new_height, 
-            image::imageops::FilterType::Lanczos3
-        ).into()
+    /// Computes dimensions of image resized by [`rgb_image_resize`] without resizing it.
+    ///
+    /// # Example
+    /// ```
+    /// use ditherum::image::{manip, ResizeMode};
+    ///
+    /// assert_eq!(manip::resized_dimensions((300, 200), Some(150), None, ResizeMode::Fill), (150, 100));
+    /// assert_eq!(manip::resized_dimensions((300, 200), Some(100), Some(100), ResizeMode::Fit), (100, 67));
+    /// assert_eq!(manip::resized_dimensions((300, 200), Some(100), Some(100), ResizeMode::Exact), (100, 100));
+    /// ```
+    pub fn resized_dimensions(original_dimensions: (u32, u32), width: Option<u32>, height: Option<u32>, mode: ResizeMode) -> (u32, u32) {
+        let (original_width, original_height) = original_dimensions;
+        let (new_width, new_height) = desired_dimensions(original_dimensions, width, height);
+        match mode {
+            // Same rounding as `DynamicImage::resize`
+            ResizeMode::Fit if original_width > 0 && original_height > 0 => {
+                let ratio = f64::min(new_width as f64 / original_width as f64, new_height as f64 / original_height as f64);
+                (
+                    ((original_width as f64 * ratio).round() as u32).max(1),
+                    ((original_height as f64 * ratio).round() as u32).max(1),
+                )
+            },
+            _ => (new_width, new_height),
+        }
     }
+
+    /// Converts an `RgbImage` to a new size using given mode and resampling filter.
+    /// 
+    /// # Parameters
+    /// - `src_img`: Image to be resized.
+    /// - `width`, `height`: Desired dimensions, missing one is computed from the aspect ratio.
+    /// - `mode`: How image is fitted into desired dimensions.
+    /// - `filter`: Resampling filter, use `ResizeFilter::Nearest` for pixel art.
+    pub fn rgb_image_resize(
+        src_img: RgbImage, 
+        width: Option<u32>, 
+        height: Option<u32>, 
+        mode: ResizeMode, 
+        filter: ResizeFilter
+    ) -> RgbImage {
+        if filter == ResizeFilter::LinearArea {
+            return rgb_image_resize_linear_area(&src_img, width, height, mode);
+        }
+        let dyn_img = DynamicImage::from(src_img);
+        let (new_width, new_height) = desired_dimensions((dyn_img.width(), dyn_img.height()), width, height);
+
+        let filter = filter.into();
+        match mode {
+            ResizeMode::Fit => dyn_img.resize(new_width, new_height, filter).into(),
+            ResizeMode::Fill => dyn_img.resize_to_fill(new_width, new_height, filter).into(),
+            ResizeMode::Stretch => dyn_img.resize_exact(new_width, new_height, filter).into(),
+            ResizeMode::Exact => {
+                let fitted_img = dyn_img.resize(new_width, new_height, filter).to_rgb8();
+                rgb_image_pad(&fitted_img, new_width, new_height, image::Rgb([0, 0, 0]))
+            },
+        }
+    }
+
+    /// Same as [`rgb_image_resize`] with [`ResizeFilter::LinearArea`].
+    fn rgb_image_resize_linear_area(src_img: &RgbImage, width: Option<u32>, height: Option<u32>, mode: ResizeMode) -> RgbImage {
+        let (original_width, original_height) = src_img.dimensions();
+        let (new_width, new_height) = resized_dimensions((original_width, original_height), width, height, mode);
+        if original_width == 0 || original_height == 0 || new_width == 0 || new_height == 0 {
+            return RgbImage::new(new_width, new_height);
+        }
+        match mode {
+            ResizeMode::Fit | ResizeMode::Stretch => rgb_image_area_average(src_img, new_width, new_height),
+            ResizeMode::Fill => {
+                // Source is cropped to desired aspect ratio first, so that no area is averaged twice
+                let (cropped_width, cropped_height) = if original_width as u64 * new_height as u64 > original_height as u64 * new_width as u64 {
+                    (((original_height as u64 * new_width as u64 / new_height as u64) as u32).max(1), original_height)
+                } else {
+                    (original_width, ((original_width as u64 * new_height as u64 / new_width as u64) as u32).max(1))
+                };
+                let cropped_img = image::imageops::crop_imm(
+                    src_img,
+                    (original_width - cropped_width) / 2,
+                    (original_height - cropped_height) / 2,
+                    cropped_width,
+                    cropped_height
+                ).to_image();
+                rgb_image_area_average(&cropped_img, new_width, new_height)
+            },
+            ResizeMode::Exact => {
+                let (fitted_width, fitted_height) = resized_dimensions((original_width, original_height), width, height, ResizeMode::Fit);
+                let fitted_img = rgb_image_area_average(src_img, fitted_width, fitted_height);
+                rgb_image_pad(&fitted_img, new_width, new_height, image::Rgb([0, 0, 0]))
+            },
+        }
+    }
+
+    /// Resizes an image to exact dimensions, each pixel is the mean of source area it covers, in linear light.
+    /// 
+    /// Partially covered source pixels are weighted by their overlap. Unlike Lanczos in gamma space there
+    /// is no ringing and thin bright details on dark background keep their brightness instead of getting
+    /// dark halos. Meant for downscaling, upscaled image is blocky like with nearest neighbor.
+    /// 
+    /// # Example
+    /// ```
+    /// use ditherum::image::manip;
+    ///
+    /// // Single pixel wide white line on black averages to 50% linear light, not 50% sRGB value
+    /// let img = image::RgbImage::from_fn(2, 2, |x, _| if x == 0 { image::Rgb([255, 255, 255]) } else { image::Rgb([0, 0, 0]) });
+    /// let downscaled = manip::rgb_image_area_average(&img, 1, 1);
+    /// assert_eq!(downscaled.get_pixel(0, 0).0, [188, 188, 188]);
+    /// ```
+    pub fn rgb_image_area_average(src_img: &RgbImage, width: u32, height: u32) -> RgbImage {
+        let (src_width, src_height) = src_img.dimensions();
+        if src_width == 0 || src_height == 0 || width == 0 || height == 0 {
+            return RgbImage::new(width, height);
+        }
+        let linear_pixels = src_img.pixels()
+            .map(|px| color::ops::rgbu8_to_srgb(*px).into_linear())
+            .collect::<Vec<palette::LinSrgb>>();
+
+        // Separable: rows are averaged horizontally, then columns vertically
+        let column_weights = area_weights(src_width, width);
+        let row_weights = area_weights(src_height, height);
+        let mut horizontal_pass = Vec::with_capacity(width as usize * src_height as usize);
+        for src_row in linear_pixels.chunks_exact(src_width as usize) {
+            horizontal_pass.extend(column_weights.iter().map(|weights| weighted_sum(weights, |src_x| src_row[src_x])));
+        }
+
+        RgbImage::from_fn(width, height, |x, y| {
+            let linear_color = weighted_sum(&row_weights[y as usize], |src_y| horizontal_pass[src_y * width as usize + x as usize]);
+            color::ops::srgb_to_rgbu8(palette::Srgb::from_linear(linear_color))
+        })
+    }
+
+    /// Source pixels covered by each of `dst_len` output pixels, with weights of their overlap summing to 1.
+    fn area_weights(src_len: u32, dst_len: u32) -> Vec<Vec<(usize, f32)>> {
+        let scale = src_len as f64 / dst_len as f64;
+        (0..dst_len)
+            .map(|dst_idx| {
+                let (start, end) = (dst_idx as f64 * scale, (dst_idx + 1) as f64 * scale);
+                let first_idx = start.floor() as usize;
+                let last_idx = (end.ceil() as usize).min(src_len as usize);
+                (first_idx..last_idx)
+                    .map(|src_idx| {
+                        let overlap = end.min(src_idx as f64 + 1.0) - start.max(src_idx as f64);
+                        (src_idx, (overlap / scale) as f32)
+                    })
+                    .filter(|&(_, weight)| weight > 0.0)
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn weighted_sum<F>(weights: &[(usize, f32)], color_at: F) -> palette::LinSrgb
+    where
+        F: Fn(usize) -> palette::LinSrgb
+    {
+        weights.iter().fold(palette::LinSrgb::new(0.0, 0.0, 0.0), |sum, &(idx, weight)| sum + color_at(idx) * weight)
+    }
+
+    /// Downscales an image by averaging each `block_size` x `block_size` block into a single pixel.
+    /// 
+    /// Partial blocks at right and bottom edges are averaged over pixels they cover.
+    pub fn rgb_image_downscale_blocks(src_img: &RgbImage, block_size: u32) -> RgbImage {
+        let block_size = block_size.max(1);
+        let (width, height) = src_img.dimensions();
+        RgbImage::from_fn(width.div_ceil(block_size), height.div_ceil(block_size), |block_x, block_y| {
+            let (x0, y0) = (block_x * block_size, block_y * block_size);
+            let (x1, y1) = ((x0 + block_size).min(width), (y0 + block_size).min(height));
+            let mut sums = [0u32; 3];
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    let pixel = src_img.get_pixel(x, y);
+                    sums.iter_mut().zip(pixel.0).for_each(|(sum, channel)| *sum += channel as u32);
+                }
+            }
+            let count = (x1 - x0) * (y1 - y0);
+            image::Rgb(sums.map(|sum| ((sum + count / 2) / count) as u8))
+        })
+    }
+
+    /// Upscales an image with nearest neighbor, each pixel becomes `block_size` x `block_size` block.
+    /// 
+    /// Result is cropped to `width` x `height`, reverting [`rgb_image_downscale_blocks`] dimensions.
+    pub fn rgb_image_upscale_blocks(src_img: &RgbImage, block_size: u32, width: u32, height: u32) -> RgbImage {
+        let block_size = block_size.max(1);
+        RgbImage::from_fn(width, height, |x, y| {
+            let block_x = (x / block_size).min(src_img.width() - 1);
+            let block_y = (y / block_size).min(src_img.height() - 1);
+            *src_img.get_pixel(block_x, block_y)
+        })
+    }
+
+    /// Centers image on canvas of given dimensions filled with `color`, larger image is cropped around its center.
+    pub fn rgb_image_pad(src_img: &RgbImage, width: u32, height: u32, color: image::Rgb<u8>) -> RgbImage {
+        let mut padded_img = RgbImage::from_pixel(width, height, color);
+        image::imageops::replace(
+            &mut padded_img,
+            src_img,
+            (width as i64 - src_img.width() as i64) / 2,
+            (height as i64 - src_img.height() as i64) / 2
+        );
+        padded_img
+    }
+}
+
+#[test]
+fn test_degenerate_dimensions() {
+    for (width, height) in [(0, 0), (1, 1), (1, 7), (7, 1), (0, 5)] {
+        for algorithm in ProcessingAlgorithm::all() {
+            let source_image = RgbImage::from_pixel(width, height, image::Rgb([120, 60, 200]));
+            let processed_image = ImageProcessor::new(source_image, PaletteRGB::primary_bw())
+                .with_algorithm(*algorithm)
+                .run();
+            assert_eq!(processed_image.dimensions(), (width, height), "{algorithm}");
+        }
+    }
+}
+
+#[test]
+fn test_run_with_report() {
+    let mut source_image = RgbImage::from_pixel(6, 4, image::Rgb([0, 0, 0]));
+    source_image.put_pixel(2, 1, image::Rgb([255, 255, 255]));
+    let (processed_image, report) = ImageProcessor::new(source_image.clone(), PaletteRGB::primary_bw()).run_with_report();
+
+    // Colors already in palette pass unchanged
+    assert_eq!(processed_image, source_image);
+    assert_eq!(report.palette_size, PaletteRGB::primary_bw().len());
+    assert_eq!(report.unique_colors, 2);
+    assert_eq!(report.mean_delta_e, 0.0);
+    assert_eq!(report.total_duration(), report.preparation_duration + report.dithering_duration);
+}
+
+#[test]
+fn test_run_region() {
+    let source_image = generate_test_gradient_image(37, 23, image::Rgb::<u8>([0,0,0]), image::Rgb::<u8>([255,255,255]));
+    let processor = || ImageProcessor::new(source_image.clone(), PaletteRGB::black_and_white()).with_algorithm(ProcessingAlgorithm::Bayer4);
+    let whole_image = processor().run();
+    let region = ImageRegion { x: 5, y: 7, width: 30, height: 20 };
+    let processed_image = processor().with_region(region).run();
+
+    assert_eq!(processed_image.dimensions(), source_image.dimensions());
+    for (x, y, px) in processed_image.enumerate_pixels() {
+        // Region is clipped to the image, ordered pattern matches whole image processing
+        let expected_px = if (5..35).contains(&x) && y >= 7 { whole_image.get_pixel(x, y) } else { source_image.get_pixel(x, y) };
+        assert_eq!(px, expected_px, "({x}, {y})");
+    }
+
+    let outside_region = ImageRegion { x: 40, y: 0, width: 4, height: 4 };
+    assert_eq!(processor().with_region(outside_region).run(), source_image);
+}
+
+#[test]
+fn test_resized_dimensions_match_resize() {
+    let source_image = generate_test_gradient_image(37, 23, image::Rgb::<u8>([0,0,0]), image::Rgb::<u8>([255,255,255]));
+    for mode in [ResizeMode::Fit, ResizeMode::Fill, ResizeMode::Stretch, ResizeMode::Exact] {
+        for (width, height) in [(Some(10), None), (None, Some(50)), (Some(20), Some(20)), (Some(7), Some(30))] {
+            let resized_image = manip::rgb_image_resize(source_image.clone(), width, height, mode, ResizeFilter::Nearest);
+            assert_eq!(manip::resized_dimensions(source_image.dimensions(), width, height, mode), resized_image.dimensions(), "{mode:?} {width:?}x{height:?}");
+        }
+    }
+}
+
+#[test]
+fn test_load_applies_exif_orientation() {
+    // Little endian TIFF header with single IFD entry: orientation rotated by 90 degrees clockwise
+    let exif = vec![0x49, 0x49, 42, 0, 8, 0, 0, 0, 1, 0, 0x12, 0x01, 3, 0, 1, 0, 0, 0, 6, 0, 0, 0, 0, 0, 0, 0];
+    let metadata = ImageMetadata { exif: Some(exif), ..Default::default() };
+    let mut encoded = Cursor::new(Vec::new());
+    RgbImage::from_pixel(8, 2, image::Rgb([200, 100, 50])).write_to(&mut encoded, ImageFormat::Jpeg).unwrap();
+
+    let oriented_image = load_image_from_reader(Cursor::new(metadata.embed(encoded.get_ref(), ImageFormat::Jpeg))).unwrap();
+    assert_eq!(oriented_image.dimensions(), (2, 8));
+    let plain_image = load_image_from_reader(Cursor::new(encoded.into_inner())).unwrap();
+    assert_eq!(plain_image.dimensions(), (8, 2));
+}
+
+#[test]
+fn test_blocks_roundtrip() {
+    let source_image = generate_test_gradient_image(10, 7, image::Rgb::<u8>([0,0,0]), image::Rgb::<u8>([255,255,255]));
+
+    let blocks_image = manip::rgb_image_downscale_blocks(&source_image, 4);
+    assert_eq!(blocks_image.dimensions(), (3, 2));
+
+    let upscaled_image = manip::rgb_image_upscale_blocks(&blocks_image, 4, 10, 7);
+    assert_eq!(upscaled_image.dimensions(), (10, 7));
+    assert_eq!(upscaled_image.get_pixel(3, 3), blocks_image.get_pixel(0, 0));
+    assert_eq!(upscaled_image.get_pixel(9, 6), blocks_image.get_pixel(2, 1));
+}
+
+#[test]
+fn test_resize_modes_dimensions() {
+    let source_image = generate_test_gradient_image(40, 20, image::Rgb::<u8>([0,0,0]), image::Rgb::<u8>([255,255,255]));
+
+    let expected_dimensions = [
+        (ResizeMode::Fit, (30, 15)),
+        (ResizeMode::Fill, (30, 30)),
+        (ResizeMode::Exact, (30, 30)),
+        (ResizeMode::Stretch, (30, 30)),
+    ];
+    for (mode, dimensions) in expected_dimensions {
+        let resized_image = manip::rgb_image_resize(source_image.clone(), Some(30), Some(30), mode, ResizeFilter::Nearest);
+        assert_eq!(resized_image.dimensions(), dimensions, "{mode:?}");
+    }
+
+    // Nearest filter does not introduce new colors
+    let resized_image = manip::rgb_image_resize(source_image.clone(), Some(80), None, ResizeMode::Fill, ResizeFilter::Nearest);
+    assert_eq!(resized_image.dimensions(), (80, 40));
+    assert!(count_image_colors(&resized_image).keys().all(|px| source_image.pixels().any(|src_px| src_px == px)));
+}
+
+#[test]
+fn test_transparent_key_bypasses_processing() {
+    let key = ColorRGB([255, 0, 255]);
+    let mut source_image = generate_test_gradient_image(16, 8, image::Rgb::<u8>([0,0,0]), image::Rgb::<u8>([255,255,255]));
+    (0..8).for_each(|y| source_image.put_pixel(3, y, key.to_rgbu8()));
+
+    let palette = PaletteRGB::black_and_white();
+    let mut palette_with_key = palette.clone();
+    palette_with_key.push(key);
+
+    for algorithm in ProcessingAlgorithm::all() {
+        let processed_image = ImageProcessor::new(source_image.clone(), palette_with_key.clone())
+            .with_algorithm(*algorithm)
+            .with_transparent_key(key)
+            .run();
+
+        // Only key pixels have key color, remaining ones use remaining palette colors
+        for (x, _, px) in processed_image.enumerate_pixels() {
+            assert_eq!(ColorRGB::from_rgbu8(*px) == key, x == 3, "{algorithm} at x={x}");
+        }
+    }
+}
+
+#[test]
+fn test_writer_and_reader_roundtrip() {
+    let source_image = generate_test_gradient_image(
+        16, 
+        4, 
+        image::Rgb::<u8>([0,0,0]), 
+        image::Rgb::<u8>([255,0,255]), 
+    );
+
+    let mut encoded = Vec::new();
+    save_image_to_writer(&mut encoded, &source_image, ImageFormat::Png).unwrap();
+    let decoded_image = load_image_from_reader(encoded.as_slice()).unwrap();
+    assert_eq!(source_image, decoded_image);
+}
+
+#[test]
+fn test_algorithm_names_roundtrip() {
+    for algorithm in ProcessingAlgorithm::all() {
+        let parsed_algorithm = algorithm.name().parse::<ProcessingAlgorithm>().unwrap();
+        assert_eq!(*algorithm, parsed_algorithm);
+        assert!(!algorithm.description().is_empty());
+    }
+    assert!("not-an-algorithm".parse::<ProcessingAlgorithm>().is_err());
 }
 
 #[test]
@@ -221,4 +1580,102 @@ fn test_processing_gradient_image() {
         .run();
     assert_eq!(processing_result.width(), width);
     assert_eq!(processing_result.height(), height);
-}
\ No newline at end of file
+}
+#[test]
+fn test_process_f32_all_algorithms() {
+    let source_image = image::DynamicImage::ImageRgb8(
+        generate_test_gradient_image(16, 8, image::Rgb::<u8>([0,0,0]), image::Rgb::<u8>([255,255,255]))
+    ).into_rgb32f();
+    let palette = PaletteRGB::black_and_white();
+
+    for algorithm in ProcessingAlgorithm::value_variants() {
+        let processed_image = algorithm.process_f32(&source_image, &palette);
+        assert_eq!(processed_image.dimensions(), source_image.dimensions());
+        assert!(processed_image.pixels().all(|px| px.0 == [0, 0, 0] || px.0 == [255, 255, 255]), "{algorithm}");
+    }
+
+    // Single row or column is thresholded instead of panicking in error diffusion kernel
+    for (width, height) in [(1, 5), (5, 1)] {
+        let line_image = Rgb32FImage::from_pixel(width, height, image::Rgb([0.3; 3]));
+        for algorithm in ProcessingAlgorithm::value_variants() {
+            assert_eq!(algorithm.process_f32(&line_image, &palette).dimensions(), (width, height), "{algorithm}");
+        }
+    }
+}
+
+#[test]
+fn test_processor_precise_source() {
+    // Level between 8-bit steps, rounding it shifts share of white pixels
+    let source_image = Rgb32FImage::from_pixel(64, 32, image::Rgb([0.4992; 3]));
+    let palette = PaletteRGB::black_and_white();
+    let algorithm = ProcessingAlgorithm::FloydSteinbergRgb;
+
+    let precise_image = ImageProcessor::new_f32(source_image.clone(), palette.clone())
+        .with_algorithm(algorithm)
+        .run();
+    assert_eq!(precise_image, algorithm.process_f32(&source_image, &palette));
+
+    // Noise works on 8-bit pixels, so precise source is ignored
+    let rounded_image: RgbImage = source_image.convert();
+    let noise = Noise { amplitude: 0.1, ..Noise::default() };
+    let noisy_image = ImageProcessor::new_f32(source_image, palette.clone())
+        .with_algorithm(algorithm)
+        .with_noise(noise)
+        .run();
+    let rounded_noisy_image = ImageProcessor::new(rounded_image, palette)
+        .with_algorithm(algorithm)
+        .with_noise(noise)
+        .run();
+    assert_eq!(noisy_image, rounded_noisy_image);
+}
+
+#[test]
+fn test_contact_sheet() {
+    let images = vec![
+        ("wide".to_string(), RgbImage::from_pixel(20, 10, image::Rgb([255, 0, 0]))),
+        ("tall".to_string(), RgbImage::from_pixel(10, 30, image::Rgb([0, 0, 255]))),
+        ("third".to_string(), RgbImage::from_pixel(20, 30, image::Rgb([0, 255, 0]))),
+    ];
+    let bg = image::Rgb([250, 250, 250]);
+    let sheet = contact_sheet(&images, 2, 4, bg);
+
+    // Cells take size of the largest image, the third one starts next row
+    let cell_height = 30 + text::GLYPH_HEIGHT + 4 + 4;
+    assert_eq!(sheet.dimensions(), (4 + 2 * 24, 4 + 2 * cell_height));
+    assert_eq!(sheet.get_pixel(4, 4).0, [255, 0, 0]);
+    assert_eq!(sheet.get_pixel(4 + 24, 4).0, [0, 0, 255]);
+    assert_eq!(sheet.get_pixel(4, 4 + cell_height).0, [0, 255, 0]);
+    assert_eq!(*sheet.get_pixel(4 + 20, 4), bg);
+
+    // Dark labels on light background
+    let label_colors = count_image_colors(&image::imageops::crop_imm(&sheet, 4, 4 + 30, 20, text::GLYPH_HEIGHT + 4).to_image());
+    assert!(label_colors.contains_key(&image::Rgb([0, 0, 0])));
+
+    assert_eq!(contact_sheet(&[], 3, 4, bg).dimensions(), (4 + 4, 4));
+}
+
+#[test]
+fn test_resize_linear_area() {
+    // Thin bright lines on dark background
+    let source_image = RgbImage::from_fn(90, 60, |x, _| if x % 9 == 4 { image::Rgb([255, 255, 255]) } else { image::Rgb([40, 40, 40]) });
+
+    for mode in [ResizeMode::Fit, ResizeMode::Fill, ResizeMode::Stretch, ResizeMode::Exact] {
+        for (width, height) in [(Some(10), None), (None, Some(50)), (Some(20), Some(20)), (Some(7), Some(30))] {
+            let resized_image = manip::rgb_image_resize(source_image.clone(), width, height, mode, ResizeFilter::LinearArea);
+            assert_eq!(manip::resized_dimensions(source_image.dimensions(), width, height, mode), resized_image.dimensions(), "{mode:?} {width:?}x{height:?}");
+        }
+    }
+
+    // No ringing darker than background, unlike Lanczos
+    let lanczos_image = manip::rgb_image_resize(source_image.clone(), Some(40), None, ResizeMode::Fill, ResizeFilter::Lanczos3);
+    assert!(lanczos_image.pixels().any(|px| px.0[0] < 40));
+    let area_image = manip::rgb_image_resize(source_image.clone(), Some(40), None, ResizeMode::Fill, ResizeFilter::LinearArea);
+    assert!(area_image.pixels().all(|px| px.0[0] >= 40));
+
+    // Mean light is preserved, sRGB mean would be much darker
+    let mean_linear = |img: &RgbImage| img.pixels().map(|px| crate::color::ops::rgbu8_to_srgb(*px).into_linear().red).sum::<f32>() / img.pixels().len() as f32;
+    let downscaled_image = manip::rgb_image_area_average(&source_image, 10, 6);
+    assert!((mean_linear(&downscaled_image) - mean_linear(&source_image)).abs() < 0.005);
+
+    assert_eq!(manip::rgb_image_resize(RgbImage::new(0, 0), Some(4), Some(4), ResizeMode::Fill, ResizeFilter::LinearArea).dimensions(), (4, 4));
+}
@@ -0,0 +1,241 @@
+//! Images stored as palette indices, see [`IndexedImage`].
+
+use std::collections::HashMap;
+
+use image::{Rgb, RgbImage};
+
+use crate::{color::ColorRGB, palette::{errors::PaletteError, PaletteRGB}};
+
+/// Largest palette indices of [`IndexedImage`] can address.
+pub const MAX_PALETTE_SIZE: usize = u16::MAX as usize + 1;
+
+/// Palette index of every pixel, row by row, in the narrowest type fitting the palette.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PaletteIndices {
+    /// Indices of palettes up to 256 colors.
+    U8(Vec<u8>),
+
+    /// Indices of palettes up to [`MAX_PALETTE_SIZE`] colors.
+    U16(Vec<u16>),
+}
+
+impl PaletteIndices {
+    /// Creates empty indices fitting palette of `palette_size` colors.
+    fn for_palette_size(palette_size: usize, capacity: usize) -> Result<Self, PaletteError> {
+        match palette_size {
+            0..=256 => Ok(Self::U8(Vec::with_capacity(capacity))),
+            257..=MAX_PALETTE_SIZE => Ok(Self::U16(Vec::with_capacity(capacity))),
+            _ => Err(PaletteError::TooManyColorsForIndices(palette_size)),
+        }
+    }
+
+    /// Appends index, which has to fit the chosen type.
+    fn push(&mut self, index: usize) {
+        match self {
+            Self::U8(indices) => indices.push(index as u8),
+            Self::U16(indices) => indices.push(index as u16),
+        }
+    }
+
+    /// Number of indices.
+    pub fn len(&self) -> usize {
+        match self {
+            Self::U8(indices) => indices.len(),
+            Self::U16(indices) => indices.len(),
+        }
+    }
+
+    /// Checks if there are no indices.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns index at given position, `None` if out of bounds.
+    pub fn get(&self, position: usize) -> Option<usize> {
+        match self {
+            Self::U8(indices) => indices.get(position).map(|&index| index as usize),
+            Self::U16(indices) => indices.get(position).map(|&index| index as usize),
+        }
+    }
+
+    /// Iterates over indices, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        let (narrow, wide) = match self {
+            Self::U8(indices) => (Some(indices.iter()), None),
+            Self::U16(indices) => (None, Some(indices.iter())),
+        };
+        narrow.into_iter().flatten().map(|&index| index as usize)
+            .chain(wide.into_iter().flatten().map(|&index| index as usize))
+    }
+}
+
+/// Image stored as palette indices together with the palette.
+///
+/// Quantized images use few colors, so exporters and remapping work on indices instead of
+/// matching pixel colors against palette again and again. Indices are `u8` for palettes up to
+/// 256 colors and `u16` up to [`MAX_PALETTE_SIZE`] colors.
+///
+/// # Example
+/// ```
+/// use ditherum::{indexed::IndexedImage, palette::PaletteRGB};
+///
+/// let palette = PaletteRGB::black_and_white();
+/// let img = image::RgbImage::from_fn(3, 2, |x, y| palette[((x + y) % 2) as usize].to_rgbu8());
+///
+/// let indexed = IndexedImage::from_rgb_image(&img, &palette).unwrap();
+/// assert_eq!(indexed.indices().iter().collect::<Vec<_>>(), vec![0, 1, 0, 1, 0, 1]);
+/// assert_eq!(indexed.to_rgb_image(), img);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexedImage {
+    width: u32,
+    height: u32,
+    indices: PaletteIndices,
+    palette: PaletteRGB,
+}
+
+impl IndexedImage {
+    /// Creates image from palette indices given row by row.
+    ///
+    /// # Errors
+    /// Returns [`PaletteError::TooManyColorsForIndices`] if palette exceeds [`MAX_PALETTE_SIZE`],
+    /// [`PaletteError::IndexOutOfRange`] if any index does not point into palette and
+    /// [`PaletteError::IndicesLengthMismatch`] if number of indices does not match dimensions.
+    pub fn new<I>(width: u32, height: u32, indices: I, palette: PaletteRGB) -> Result<Self, PaletteError>
+    where
+        I: IntoIterator<Item = usize>
+    {
+        let pixels_count = width as usize * height as usize;
+        let mut stored_indices = PaletteIndices::for_palette_size(palette.len(), pixels_count)?;
+        for index in indices {
+            if index >= palette.len() {
+                return Err(PaletteError::IndexOutOfRange { index, size: palette.len() });
+            }
+            stored_indices.push(index);
+        }
+        if stored_indices.len() != pixels_count {
+            return Err(PaletteError::IndicesLengthMismatch { width, height, length: stored_indices.len() });
+        }
+
+        Ok(Self { width, height, indices: stored_indices, palette })
+    }
+
+    /// Converts quantized image to palette indices.
+    ///
+    /// Colors repeated in palette get their first position. Pixels missing from palette,
+    /// e.g. transparent key, get index of the closest palette color by CIEDE2000.
+    ///
+    /// # Errors
+    /// Returns [`PaletteError::PaletteEmpty`] if palette is empty and image is not, and
+    /// [`PaletteError::TooManyColorsForIndices`] if palette exceeds [`MAX_PALETTE_SIZE`].
+    pub fn from_rgb_image(img: &RgbImage, palette: &PaletteRGB) -> Result<Self, PaletteError> {
+        if palette.is_empty() && !img.is_empty() {
+            return Err(PaletteError::PaletteEmpty);
+        }
+
+        let mut palette_indices = palette.iter()
+            .enumerate()
+            .rev()
+            .map(|(index, color)| (color.to_rgbu8(), index))
+            .collect::<HashMap<Rgb<u8>, usize>>();
+        let indices = img.pixels()
+            .map(|px| match palette_indices.get(px) {
+                Some(&index) => index,
+                None => {
+                    let index = palette_indices[&palette.find_closest_by_lab(&ColorRGB::from_rgbu8(*px)).to_rgbu8()];
+                    palette_indices.insert(*px, index);
+                    index
+                },
+            })
+            .collect::<Vec<_>>();
+
+        Self::new(img.width(), img.height(), indices, palette.clone())
+    }
+
+    /// Converts indices back to colors of the palette.
+    pub fn to_rgb_image(&self) -> RgbImage {
+        let colors = self.palette.iter().map(ColorRGB::to_rgbu8).collect::<Vec<_>>();
+        let raw = self.indices.iter()
+            .flat_map(|index| colors[index].0)
+            .collect::<Vec<_>>();
+        RgbImage::from_raw(self.width, self.height, raw).expect("indices match dimensions")
+    }
+
+    /// Width in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Height in pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Width and height in pixels.
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Palette index of every pixel, row by row.
+    pub fn indices(&self) -> &PaletteIndices {
+        &self.indices
+    }
+
+    /// Palette indices point into.
+    pub fn palette(&self) -> &PaletteRGB {
+        &self.palette
+    }
+
+    /// Returns palette index of pixel.
+    ///
+    /// # Panics
+    /// Panics if position is outside of the image.
+    pub fn index_at(&self, x: u32, y: u32) -> usize {
+        assert!(x < self.width && y < self.height, "Position ({x}, {y}) outside of {}x{} image.", self.width, self.height);
+        self.indices.get(y as usize * self.width as usize + x as usize).expect("indices match dimensions")
+    }
+
+    /// Counts pixels of every palette color, in palette order.
+    pub fn color_counts(&self) -> Vec<usize> {
+        let mut counts = vec![0; self.palette.len()];
+        self.indices.iter().for_each(|index| counts[index] += 1);
+        counts
+    }
+
+    /// Splits image into indices and palette.
+    pub fn into_parts(self) -> (PaletteIndices, PaletteRGB) {
+        (self.indices, self.palette)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_indexed_round_trip_and_storage() {
+        let palette = PaletteRGB::from((0..300).map(|idx| ColorRGB([(idx % 256) as u8, (idx / 256) as u8, 0])).collect::<Vec<_>>());
+        let img = RgbImage::from_fn(20, 15, |x, y| palette[(y * 20 + x) as usize].to_rgbu8());
+
+        let indexed = IndexedImage::from_rgb_image(&img, &palette).unwrap();
+        assert!(matches!(indexed.indices(), PaletteIndices::U16(_)));
+        assert_eq!(indexed.index_at(3, 2), 43);
+        assert_eq!(indexed.to_rgb_image(), img);
+
+        // Off-palette pixel gets the closest color, small palette fits bytes
+        let mut img = RgbImage::from_pixel(2, 2, image::Rgb([0, 0, 0]));
+        img.put_pixel(1, 1, image::Rgb([240, 240, 240]));
+        let indexed = IndexedImage::from_rgb_image(&img, &PaletteRGB::black_and_white()).unwrap();
+        assert!(matches!(indexed.indices(), PaletteIndices::U8(_)));
+        assert_eq!(indexed.color_counts().iter().sum::<usize>(), 4);
+        assert_eq!(indexed.to_rgb_image().get_pixel(1, 1).0, [255, 255, 255]);
+    }
+
+    #[test]
+    fn test_indexed_invalid_indices() {
+        let palette = PaletteRGB::black_and_white();
+        assert!(matches!(IndexedImage::new(2, 1, [0, 2], palette.clone()), Err(PaletteError::IndexOutOfRange { index: 2, size: 2 })));
+        assert!(matches!(IndexedImage::new(2, 2, [0, 1], palette.clone()), Err(PaletteError::IndicesLengthMismatch { length: 2, .. })));
+        assert!(matches!(IndexedImage::from_rgb_image(&RgbImage::new(1, 1), &PaletteRGB::from(Vec::<ColorRGB>::new())), Err(PaletteError::PaletteEmpty)));
+    }
+}
@@ -0,0 +1,181 @@
+//! Conversions between image buffers and [`ndarray`] arrays, enabled with the `ndarray` feature.
+//!
+//! Images are `(height, width, 3)` arrays, masks and palette indices `(height, width)` arrays,
+//! palettes `(colors_count, 3)` arrays, the same shapes NumPy and OpenCV use. `RgbImage` and
+//! `GrayImage` are `image` crate buffers shared with `imageproc`, so they are converted by
+//! functions, crate types implement `From`/`TryFrom`.
+//!
+//! # Example
+//! ```
+//! use ditherum::{interop, palette::PaletteRGB};
+//! use ndarray::{Array2, Array3};
+//!
+//! let array = Array3::<u8>::from_shape_fn((2, 4, 3), |(y, _, _)| if y == 0 { 0 } else { 250 });
+//! let img = interop::array3_to_rgb_image(array.view()).unwrap();
+//! assert_eq!(img.get_pixel(3, 1).0, [250, 250, 250]);
+//! assert_eq!(interop::rgb_image_to_array3(img), array);
+//!
+//! let palette = PaletteRGB::try_from(Array2::<u8>::zeros((1, 3)).view()).unwrap();
+//! assert_eq!(Array2::from(&palette), Array2::<u8>::zeros((1, 3)));
+//! ```
+
+use image::{GrayImage, RgbImage};
+use ndarray::{Array2, Array3, ArrayView2, ArrayView3};
+
+use crate::{api::DitherOutput, color::ColorRGB, indexed::IndexedImage, palette::PaletteRGB};
+
+use self::errors::InteropError;
+
+pub mod errors {
+    #[derive(Debug, thiserror::Error)]
+    pub enum InteropError {
+        #[error("Invalid array shape {shape:?}, expected {expected}.")]
+        InvalidShape {
+            shape: Vec<usize>,
+            expected: &'static str,
+        },
+
+        #[error("Array of {width}x{height} pixels exceeds image dimensions limit.")]
+        TooLarge {
+            width: usize,
+            height: usize,
+        },
+    }
+}
+
+/// Converts image to `(height, width, 3)` array, reusing its buffer.
+pub fn rgb_image_to_array3(img: RgbImage) -> Array3<u8> {
+    let (width, height) = (img.width() as usize, img.height() as usize);
+    // Safe unwrap, RgbImage raw buffer is always height * width * 3 long
+    Array3::from_shape_vec((height, width, 3), img.into_raw()).unwrap()
+}
+
+/// Converts `(height, width, 3)` array of any memory layout to image.
+///
+/// # Errors
+/// Returns [`InteropError::InvalidShape`] if there are not 3 channels and [`InteropError::TooLarge`]
+/// if dimensions exceed `u32`.
+pub fn array3_to_rgb_image(array: ArrayView3<u8>) -> Result<RgbImage, InteropError> {
+    let (height, width, channels) = array.dim();
+    if channels != 3 {
+        return Err(InteropError::InvalidShape { shape: array.shape().to_vec(), expected: "(height, width, 3)" });
+    }
+    let (img_width, img_height) = image_dimensions(width, height)?;
+
+    // Iteration follows logical order, so transposed or sliced views are fine too
+    let buffer = array.iter().copied().collect::<Vec<_>>();
+    Ok(RgbImage::from_raw(img_width, img_height, buffer).expect("Buffer matches dimensions."))
+}
+
+/// Converts grayscale image, like dithering mask, to `(height, width)` array, reusing its buffer.
+pub fn gray_image_to_array2(img: GrayImage) -> Array2<u8> {
+    let (width, height) = (img.width() as usize, img.height() as usize);
+    // Safe unwrap, GrayImage raw buffer is always height * width long
+    Array2::from_shape_vec((height, width), img.into_raw()).unwrap()
+}
+
+/// Converts `(height, width)` array of any memory layout to grayscale image.
+///
+/// # Errors
+/// Returns [`InteropError::TooLarge`] if dimensions exceed `u32`.
+pub fn array2_to_gray_image(array: ArrayView2<u8>) -> Result<GrayImage, InteropError> {
+    let (height, width) = array.dim();
+    let (img_width, img_height) = image_dimensions(width, height)?;
+
+    let buffer = array.iter().copied().collect::<Vec<_>>();
+    Ok(GrayImage::from_raw(img_width, img_height, buffer).expect("Buffer matches dimensions."))
+}
+
+/// Checks that array dimensions fit image ones.
+fn image_dimensions(width: usize, height: usize) -> Result<(u32, u32), InteropError> {
+    match (u32::try_from(width), u32::try_from(height)) {
+        (Ok(width), Ok(height)) => Ok((width, height)),
+        _ => Err(InteropError::TooLarge { width, height }),
+    }
+}
+
+/// Palette as `(colors_count, 3)` array.
+impl From<&PaletteRGB> for Array2<u8> {
+    fn from(value: &PaletteRGB) -> Self {
+        Array2::from_shape_fn((value.len(), 3), |(idx, channel)| value[idx][channel])
+    }
+}
+
+/// Palette from `(colors_count, 3)` array, rows are colors.
+impl TryFrom<ArrayView2<'_, u8>> for PaletteRGB {
+    type Error = InteropError;
+
+    fn try_from(value: ArrayView2<'_, u8>) -> Result<Self, Self::Error> {
+        if value.ncols() != 3 {
+            return Err(InteropError::InvalidShape { shape: value.shape().to_vec(), expected: "(colors_count, 3)" });
+        }
+        Ok(PaletteRGB::from(value.rows()
+            .into_iter()
+            .map(|row| ColorRGB([row[0], row[1], row[2]]))
+            .collect::<Vec<_>>()
+        ))
+    }
+}
+
+/// Palette indices as `(height, width)` array, wide enough for every palette size.
+impl From<&IndexedImage> for Array2<u16> {
+    fn from(value: &IndexedImage) -> Self {
+        let (width, height) = (value.width() as usize, value.height() as usize);
+        Array2::from_shape_vec((height, width), value.indices().iter().map(|index| index as u16).collect())
+            .expect("Indices match dimensions.")
+    }
+}
+
+/// Processed image as `(height, width, 3)` array.
+impl From<DitherOutput> for Array3<u8> {
+    fn from(value: DitherOutput) -> Self {
+        rgb_image_to_array3(value.image)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::s;
+
+    use super::*;
+
+    #[test]
+    fn test_array_round_trips() {
+        let img = crate::image::generate_test_gradient_image(5, 3, image::Rgb([0, 20, 40]), image::Rgb([255, 200, 100]));
+        let array = rgb_image_to_array3(img.clone());
+        assert_eq!(array.dim(), (3, 5, 3));
+        assert_eq!(array[[2, 4, 1]], img.get_pixel(4, 2).0[1]);
+        assert_eq!(array3_to_rgb_image(array.view()).unwrap(), img);
+
+        // Non-contiguous views keep logical order
+        let flipped = array.slice(s![.., ..;-1, ..]);
+        let flipped_img = array3_to_rgb_image(flipped).unwrap();
+        assert_eq!(flipped_img.get_pixel(0, 1), img.get_pixel(4, 1));
+
+        let mut mask = GrayImage::new(4, 2);
+        mask.put_pixel(3, 1, image::Luma([200]));
+        let mask_array = gray_image_to_array2(mask.clone());
+        assert_eq!(mask_array[[1, 3]], 200);
+        assert_eq!(array2_to_gray_image(mask_array.t()).unwrap().dimensions(), (2, 4));
+        assert_eq!(array2_to_gray_image(mask_array.view()).unwrap(), mask);
+
+        let rgba = Array3::<u8>::zeros((2, 2, 4));
+        assert!(matches!(array3_to_rgb_image(rgba.view()), Err(InteropError::InvalidShape { .. })));
+    }
+
+    #[test]
+    fn test_palette_and_indices_conversions() {
+        let palette = PaletteRGB::black_and_white();
+        let array = Array2::from(&palette);
+        assert_eq!(array.dim(), (2, 3));
+        assert_eq!(PaletteRGB::try_from(array.view()).unwrap().to_vec(), palette.to_vec());
+        assert!(PaletteRGB::try_from(Array2::<u8>::zeros((2, 4)).view()).is_err());
+
+        let img = RgbImage::from_fn(3, 2, |x, y| palette[((x + y) % 2) as usize].to_rgbu8());
+        let indexed = IndexedImage::from_rgb_image(&img, &palette).unwrap();
+        let indices = Array2::<u16>::from(&indexed);
+        assert_eq!(indices.dim(), (2, 3));
+        assert_eq!(indices[[1, 0]], 1);
+        assert_eq!(indices[[1, 1]], 0);
+    }
+}
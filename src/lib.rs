@@ -1,3 +1,7 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
 /// To organize things
 /// 
 /// ## Colors used
@@ -12,7 +16,44 @@
 /// self:
 /// - ColorRGB ([u8; 3] same as image::Rgb<u8> but can be easly serialized) 
 ///
+/// ## Features
+/// 
+/// Without default `std` feature the crate is `no_std + alloc` and contains only
+/// [`algorithms::kernel`], [`algorithms::kmean`] and [`algorithms::raw`]. Optional `video`
+/// feature adds `video` module dithering frame sequences, `async` feature adds tokio based
+/// wrappers like `dither_image_async`, `ndarray` feature adds `interop` module converting
+/// images and palettes to and from `ndarray` arrays, `pdf` feature adds printable charts
+/// `export::to_chart_pdf`.
 pub mod algorithms;
+#[cfg(feature = "std")]
 pub mod image;
+#[cfg(feature = "std")]
 pub mod color;
+#[cfg(feature = "std")]
 pub mod palette;
+#[cfg(feature = "std")]
+pub mod metrics;
+#[cfg(feature = "std")]
+pub mod metadata;
+#[cfg(feature = "std")]
+pub mod text;
+#[cfg(feature = "std")]
+pub mod config;
+#[cfg(feature = "std")]
+pub mod indexed;
+#[cfg(feature = "std")]
+pub mod export;
+#[cfg(feature = "std")]
+pub mod api;
+#[cfg(feature = "video")]
+pub mod video;
+#[cfg(feature = "ndarray")]
+pub mod interop;
+
+#[cfg(feature = "std")]
+pub use api::{dither_image, dither_image_f32, dither_raw, DitherOptions, DitherOutput};
+#[cfg(feature = "async")]
+pub use api::{dither_image_async, dither_raw_async};
+
+#[cfg(feature = "python")]
+mod python;
@@ -0,0 +1,314 @@
+//! Metadata carried over from input to output images.
+//!
+//! Dithering works on decoded pixels only, so ICC profile, EXIF and text chunks of the input
+//! are lost unless they are copied explicitly. [`ImageMetadata`] reads them from encoded input
+//! and embeds them into encoded output where the format allows: PNG keeps all of them, JPEG
+//! keeps ICC profile and EXIF, other formats are written without metadata.
+//!
+//! # Example
+//! ```
+//! use ditherum::metadata::ImageMetadata;
+//!
+//! let metadata = ImageMetadata {
+//!     text: vec![("Author".to_string(), "Gieneq".to_string())],
+//!     ..Default::default()
+//! };
+//! let mut png = std::io::Cursor::new(Vec::new());
+//! image::RgbImage::new(2, 2).write_to(&mut png, image::ImageFormat::Png).unwrap();
+//!
+//! let png = metadata.embed(png.get_ref(), image::ImageFormat::Png);
+//! assert_eq!(ImageMetadata::read_from_memory(&png).unwrap(), metadata);
+//! ```
+
+use std::{io::Cursor, path::Path};
+
+use image::{ImageDecoder, ImageFormat, ImageResult};
+
+/// PNG file signature.
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+/// Name of ICC profile written to PNG `iCCP` chunk.
+const PNG_ICC_PROFILE_NAME: &[u8] = b"ICC Profile";
+
+/// Identifier of EXIF JPEG `APP1` segment.
+const JPEG_EXIF_ID: &[u8] = b"Exif\0\0";
+
+/// Identifier of ICC profile JPEG `APP2` segments.
+const JPEG_ICC_ID: &[u8] = b"ICC_PROFILE\0";
+
+/// Maximal payload of JPEG segment, its length field counts itself too.
+const JPEG_SEGMENT_MAX_LEN: usize = u16::MAX as usize - 2;
+
+/// EXIF tag of image orientation.
+const EXIF_ORIENTATION_TAG: u16 = 0x0112;
+
+/// Metadata of encoded image.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImageMetadata {
+    /// Embedded ICC color profile.
+    pub icc_profile: Option<Vec<u8>>,
+
+    /// Raw EXIF data starting with TIFF header. Orientation is reset to upright, because
+    /// images are rotated according to it when loaded, see [`crate::image::load_image`].
+    pub exif: Option<Vec<u8>>,
+
+    /// Keyword and text pairs of PNG `tEXt` chunks.
+    pub text: Vec<(String, String)>,
+}
+
+impl ImageMetadata {
+    /// Reads metadata of image file.
+    pub fn read<P>(path: P) -> ImageResult<Self>
+    where
+        P: AsRef<Path>
+    {
+        Self::read_from_memory(&std::fs::read(path)?)
+    }
+
+    /// Reads metadata of encoded image, format is guessed from the content.
+    pub fn read_from_memory(bytes: &[u8]) -> ImageResult<Self> {
+        let mut decoder = image::ImageReader::new(Cursor::new(bytes))
+            .with_guessed_format()?
+            .into_decoder()?;
+        let mut metadata = Self {
+            icc_profile: decoder.icc_profile()?,
+            exif: decoder.exif_metadata()?,
+            text: Vec::new(),
+        };
+
+        if bytes.starts_with(&PNG_SIGNATURE) {
+            for (chunk_type, data) in png_chunks(bytes) {
+                match &chunk_type {
+                    b"eXIf" if metadata.exif.is_none() => metadata.exif = Some(data.to_vec()),
+                    b"tEXt" => {
+                        if let Some(separator) = data.iter().position(|&byte| byte == 0) {
+                            metadata.text.push((latin1_to_string(&data[..separator]), latin1_to_string(&data[separator + 1..])));
+                        }
+                    },
+                    _ => {},
+                }
+            }
+        }
+
+        if let Some(exif) = &mut metadata.exif {
+            reset_exif_orientation(exif);
+        }
+        Ok(metadata)
+    }
+
+    /// Checks if there is no metadata to embed.
+    pub fn is_empty(&self) -> bool {
+        self.icc_profile.is_none() && self.exif.is_none() && self.text.is_empty()
+    }
+
+    /// Embeds metadata into encoded image of given format.
+    ///
+    /// PNG gets `iCCP`, `eXIf` and `tEXt` chunks, JPEG gets `APP1` EXIF and `APP2` ICC profile
+    /// segments. Other formats, EXIF not fitting into single JPEG segment and text which cannot
+    /// be encoded as Latin-1 are left out.
+    pub fn embed(&self, encoded: &[u8], format: ImageFormat) -> Vec<u8> {
+        match format {
+            ImageFormat::Png if encoded.starts_with(&PNG_SIGNATURE) => self.embed_png(encoded),
+            ImageFormat::Jpeg if encoded.starts_with(&[0xFF, 0xD8]) => self.embed_jpeg(encoded),
+            _ => encoded.to_vec(),
+        }
+    }
+
+    /// Inserts chunks right after `IHDR`, `iCCP` has to precede image data.
+    fn embed_png(&self, encoded: &[u8]) -> Vec<u8> {
+        // Signature and IHDR chunk of 13 bytes with length, type and CRC
+        let header_len = PNG_SIGNATURE.len() + 12 + 13;
+        let mut png = encoded[..header_len].to_vec();
+
+        if let Some(icc_profile) = &self.icc_profile {
+            let mut data = PNG_ICC_PROFILE_NAME.to_vec();
+            data.extend([0, 0]);
+            data.extend(zlib_stored(icc_profile));
+            write_png_chunk(&mut png, b"iCCP", &data);
+        }
+        if let Some(exif) = &self.exif {
+            write_png_chunk(&mut png, b"eXIf", exif);
+        }
+        for (keyword, text) in &self.text {
+            let (Some(keyword), Some(text)) = (string_to_latin1(keyword), string_to_latin1(text)) else {
+                continue;
+            };
+            if keyword.is_empty() || keyword.len() > 79 || keyword.contains(&0) || text.contains(&0) {
+                continue;
+            }
+            let data = [keyword, vec![0], text].concat();
+            write_png_chunk(&mut png, b"tEXt", &data);
+        }
+
+        png.extend_from_slice(&encoded[header_len..]);
+        png
+    }
+
+    /// Inserts segments after `SOI` and `APP0` JFIF header, which has to come first.
+    fn embed_jpeg(&self, encoded: &[u8]) -> Vec<u8> {
+        let mut header_len = 2;
+        if encoded.get(2..4) == Some(&[0xFF, 0xE0]) {
+            header_len += 2 + u16::from_be_bytes([encoded[4], encoded[5]]) as usize;
+        }
+        let mut jpeg = encoded[..header_len].to_vec();
+
+        if let Some(exif) = self.exif.as_ref().filter(|exif| JPEG_EXIF_ID.len() + exif.len() <= JPEG_SEGMENT_MAX_LEN) {
+            write_jpeg_segment(&mut jpeg, 0xE1, &[JPEG_EXIF_ID, exif].concat());
+        }
+        if let Some(icc_profile) = &self.icc_profile {
+            // Profile is split into numbered segments, each prefixed with its sequence number and count
+            let chunks = icc_profile.chunks(JPEG_SEGMENT_MAX_LEN - JPEG_ICC_ID.len() - 2).collect::<Vec<_>>();
+            if chunks.len() <= u8::MAX as usize {
+                for (chunk_idx, chunk) in chunks.iter().enumerate() {
+                    let data = [JPEG_ICC_ID, &[chunk_idx as u8 + 1, chunks.len() as u8], chunk].concat();
+                    write_jpeg_segment(&mut jpeg, 0xE2, &data);
+                }
+            }
+        }
+
+        jpeg.extend_from_slice(&encoded[header_len..]);
+        jpeg
+    }
+}
+
+/// Iterates over types and data of PNG chunks, stops at the first malformed one.
+fn png_chunks(png: &[u8]) -> impl Iterator<Item = ([u8; 4], &[u8])> {
+    let mut rest = &png[PNG_SIGNATURE.len()..];
+    std::iter::from_fn(move || {
+        let length = u32::from_be_bytes(rest.get(..4)?.try_into().ok()?) as usize;
+        let chunk_type: [u8; 4] = rest.get(4..8)?.try_into().ok()?;
+        let data = rest.get(8..8 + length)?;
+        rest = rest.get(12 + length..)?;
+        Some((chunk_type, data))
+    })
+}
+
+fn write_png_chunk(png: &mut Vec<u8>, chunk_type: &[u8; 4], data: &[u8]) {
+    png.extend((data.len() as u32).to_be_bytes());
+    png.extend(chunk_type);
+    png.extend(data);
+    png.extend(crc32(&[chunk_type.as_slice(), data].concat()).to_be_bytes());
+}
+
+fn write_jpeg_segment(jpeg: &mut Vec<u8>, marker: u8, data: &[u8]) {
+    jpeg.extend([0xFF, marker]);
+    jpeg.extend((data.len() as u16 + 2).to_be_bytes());
+    jpeg.extend(data);
+}
+
+/// Sets EXIF orientation tag of the first IFD to upright, if present.
+fn reset_exif_orientation(exif: &mut [u8]) {
+    let little_endian = match exif.get(..4) {
+        Some([0x49, 0x49, 42, 0]) => true,
+        Some([0x4D, 0x4D, 0, 42]) => false,
+        _ => return,
+    };
+    let read_u16 = |bytes: &[u8]| if little_endian { u16::from_le_bytes([bytes[0], bytes[1]]) } else { u16::from_be_bytes([bytes[0], bytes[1]]) };
+    let read_u32 = |bytes: &[u8]| {
+        let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        if little_endian { u32::from_le_bytes(bytes) } else { u32::from_be_bytes(bytes) }
+    };
+
+    let Some(ifd_offset) = exif.get(4..8).map(|bytes| read_u32(bytes) as usize) else {
+        return;
+    };
+    let Some(entries_count) = exif.get(ifd_offset..ifd_offset + 2).map(read_u16) else {
+        return;
+    };
+
+    for entry_idx in 0..entries_count as usize {
+        let entry_offset = ifd_offset + 2 + 12 * entry_idx;
+        let Some(entry) = exif.get_mut(entry_offset..entry_offset + 12) else {
+            return;
+        };
+        // SHORT value is stored in the first 2 bytes of value field
+        if read_u16(&entry[0..2]) == EXIF_ORIENTATION_TAG && read_u16(&entry[2..4]) == 3 {
+            let upright = if little_endian { 1u16.to_le_bytes() } else { 1u16.to_be_bytes() };
+            entry[8..10].copy_from_slice(&upright);
+            return;
+        }
+    }
+}
+
+/// Wraps data into zlib stream of uncompressed deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut stream = vec![0x78, 0x01];
+    let mut blocks = data.chunks(u16::MAX as usize).peekable();
+    if blocks.peek().is_none() {
+        stream.extend([1, 0, 0, 0xFF, 0xFF]);
+    }
+    while let Some(block) = blocks.next() {
+        let is_final = blocks.peek().is_none();
+        stream.push(is_final as u8);
+        stream.extend((block.len() as u16).to_le_bytes());
+        stream.extend((!(block.len() as u16)).to_le_bytes());
+        stream.extend(block);
+    }
+    stream.extend(adler32(data).to_be_bytes());
+    stream
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    const MOD_ADLER: u32 = 65521;
+    let (a, b) = data.iter().fold((1u32, 0u32), |(a, b), &byte| {
+        let a = (a + byte as u32) % MOD_ADLER;
+        (a, (b + a) % MOD_ADLER)
+    });
+    (b << 16) | a
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(u32::MAX, |crc, &byte| {
+        (0..8).fold(crc ^ byte as u32, |crc, _| {
+            if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 }
+        })
+    })
+}
+
+fn latin1_to_string(bytes: &[u8]) -> String {
+    bytes.iter().map(|&byte| byte as char).collect()
+}
+
+fn string_to_latin1(s: &str) -> Option<Vec<u8>> {
+    s.chars().map(|c| u8::try_from(c).ok()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_embed_and_read_metadata() {
+        // Little endian TIFF header with single IFD entry: orientation rotated by 90 degrees
+        let exif = vec![0x49, 0x49, 42, 0, 8, 0, 0, 0, 1, 0, 0x12, 0x01, 3, 0, 1, 0, 0, 0, 6, 0, 0, 0, 0, 0, 0, 0];
+        let metadata = ImageMetadata {
+            icc_profile: Some((0..70_000).map(|idx| (idx % 251) as u8).collect()),
+            exif: Some(exif.clone()),
+            text: vec![("Title".to_string(), "Dithered".to_string())],
+        };
+        let img = image::RgbImage::from_pixel(4, 3, image::Rgb([10, 20, 30]));
+
+        for format in [ImageFormat::Png, ImageFormat::Jpeg] {
+            let mut encoded = Cursor::new(Vec::new());
+            img.write_to(&mut encoded, format).unwrap();
+            let embedded = metadata.embed(encoded.get_ref(), format);
+
+            let decoded = image::load_from_memory(&embedded).unwrap();
+            assert_eq!(decoded.width(), 4, "{format:?}");
+            let read_metadata = ImageMetadata::read_from_memory(&embedded).unwrap();
+            assert_eq!(read_metadata.icc_profile, metadata.icc_profile, "{format:?}");
+            // Orientation is reset, image is already upright
+            assert_eq!(read_metadata.exif.as_ref().unwrap()[18], 1, "{format:?}");
+        }
+
+        // Truncated EXIF with valid header but no IFD offset is kept as is
+        let truncated = ImageMetadata { exif: Some(vec![0x49, 0x49, 42, 0, 8]), ..ImageMetadata::default() };
+        let mut encoded = Cursor::new(Vec::new());
+        img.write_to(&mut encoded, ImageFormat::Png).unwrap();
+        let embedded = truncated.embed(encoded.get_ref(), ImageFormat::Png);
+        assert_eq!(ImageMetadata::read_from_memory(&embedded).unwrap().exif, truncated.exif);
+
+        assert_eq!(crc32(b"IEND"), 0xAE42_6082);
+        assert!(ImageMetadata::default().is_empty());
+    }
+}
@@ -0,0 +1,316 @@
+use std::collections::HashMap;
+
+use image::RgbImage;
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::{color::ColorRGB, palette::{errors::PaletteError, PaletteRGB}};
+
+/// Blur applied before measuring perceived difference, roughly mimics viewing from a distance.
+const PERCEIVED_BLUR_SIGMA: f32 = 1.0;
+
+/// Quality of processed image compared to the reference one.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ImageQuality {
+    /// Mean CIEDE2000 color difference of corresponding pixels.
+    pub mean_delta_e: f32,
+
+    /// Mean CIEDE2000 color difference of slightly blurred images. Dithering patterns blend
+    /// when viewed from a distance, so this is usually a better measure of dithering quality.
+    pub perceived_mean_delta_e: f32,
+
+    /// Peak signal-to-noise ratio in decibels, infinite for identical images.
+    pub psnr: f32,
+}
+
+impl ImageQuality {
+    /// Measures quality of processed image compared to the reference one.
+    ///
+    /// # Panics
+    /// Panics if images have different dimensions.
+    pub fn measure(reference: &RgbImage, processed: &RgbImage) -> Self {
+        let reference_blurred = image::imageops::blur(reference, PERCEIVED_BLUR_SIGMA);
+        let processed_blurred = image::imageops::blur(processed, PERCEIVED_BLUR_SIGMA);
+
+        Self {
+            mean_delta_e: mean_delta_e(reference, processed),
+            perceived_mean_delta_e: mean_delta_e(&reference_blurred, &processed_blurred),
+            psnr: psnr(reference, processed),
+        }
+    }
+}
+
+/// Computes mean CIEDE2000 color difference between corresponding pixels of two images.
+///
+/// # Panics
+/// Panics if images have different dimensions.
+pub fn mean_delta_e(reference: &RgbImage, processed: &RgbImage) -> f32 {
+    assert_eq!(reference.dimensions(), processed.dimensions(), "Images dimensions differ.");
+    if reference.is_empty() {
+        return 0.0;
+    }
+
+    let delta_e_sum = reference.pixels()
+        .zip(processed.pixels())
+        .filter(|(reference_px, processed_px)| reference_px != processed_px)
+        .map(|(reference_px, processed_px)| {
+            ColorRGB::from_rgbu8(*reference_px).dist_by_lab(&ColorRGB::from_rgbu8(*processed_px)) as f64
+        })
+        .sum::<f64>();
+
+    (delta_e_sum / (reference.width() * reference.height()) as f64) as f32
+}
+
+/// Computes peak signal-to-noise ratio in decibels between two images.
+///
+/// # Panics
+/// Panics if images have different dimensions.
+pub fn psnr(reference: &RgbImage, processed: &RgbImage) -> f32 {
+    assert_eq!(reference.dimensions(), processed.dimensions(), "Images dimensions differ.");
+
+    let squared_error_sum = reference.as_raw().iter()
+        .zip(processed.as_raw().iter())
+        .map(|(&a, &b)| (a as f64 - b as f64).powi(2))
+        .sum::<f64>();
+    let mse = squared_error_sum / reference.as_raw().len().max(1) as f64;
+
+    if mse == 0.0 {
+        f32::INFINITY
+    } else {
+        (10.0 * (255.0f64.powi(2) / mse).log10()) as f32
+    }
+}
+
+/// Computes mean of all channel values in percent of full drive, proportional to power drawn
+/// by LED panels showing the image. Empty image has zero brightness.
+///
+/// # Example
+/// ```
+/// use ditherum::metrics::mean_brightness;
+///
+/// let img = image::RgbImage::from_fn(2, 1, |x, _| if x == 0 { image::Rgb([255, 255, 255]) } else { image::Rgb([255, 0, 0]) });
+/// assert!((mean_brightness(&img) - 200.0 / 3.0).abs() < 1e-3);
+/// ```
+pub fn mean_brightness(img: &RgbImage) -> f32 {
+    let channels_sum = img.as_raw().iter().map(|&channel| channel as u64).sum::<u64>();
+    (channels_sum as f64 * 100.0 / (img.as_raw().len().max(1) as f64 * 255.0)) as f32
+}
+
+/// Number of output pixels assigned to a single palette color.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ColorUsage {
+    /// Palette color, serialized as `#rrggbb`.
+    #[serde(serialize_with = "serialize_hex")]
+    pub color: ColorRGB,
+
+    /// Number of pixels having this color.
+    pub pixels: usize,
+
+    /// Share of all pixels in percent.
+    pub percentage: f32,
+}
+
+/// How many pixels of processed image received each palette color.
+///
+/// Colors follow palette order and unused ones are kept with zero count,
+/// so that entries not worth keeping in the palette are easy to spot.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PaletteUsage {
+    /// Usage of every palette color, in palette order.
+    pub colors: Vec<ColorUsage>,
+
+    /// Number of pixels with colors outside of the palette, e.g. outline or padding.
+    pub other_pixels: usize,
+}
+
+impl PaletteUsage {
+    /// Counts pixels of `processed` image per color of `palette`.
+    ///
+    /// # Example
+    /// ```
+    /// use ditherum::{metrics::PaletteUsage, palette::PaletteRGB};
+    ///
+    /// let img = image::RgbImage::from_pixel(4, 4, image::Rgb([0, 0, 0]));
+    /// let usage = PaletteUsage::measure(&img, &PaletteRGB::black_and_white());
+    ///
+    /// assert_eq!(usage.colors.iter().map(|usage| usage.pixels).sum::<usize>(), 16);
+    /// assert_eq!(usage.unused().count(), 1);
+    /// ```
+    pub fn measure(processed: &RgbImage, palette: &PaletteRGB) -> Self {
+        let mut counts = crate::image::count_image_colors(processed);
+        let total_pixels = (processed.width() as usize * processed.height() as usize).max(1);
+
+        let colors = palette.iter()
+            .map(|color| {
+                let pixels = counts.remove(&color.to_rgbu8()).unwrap_or(0);
+                ColorUsage {
+                    color: *color,
+                    pixels,
+                    percentage: (pixels as f64 * 100.0 / total_pixels as f64) as f32,
+                }
+            })
+            .collect();
+
+        Self {
+            colors,
+            other_pixels: counts.values().sum(),
+        }
+    }
+
+    /// Palette colors not assigned to any pixel.
+    pub fn unused(&self) -> impl Iterator<Item = ColorRGB> + '_ {
+        self.colors.iter()
+            .filter(|usage| usage.pixels == 0)
+            .map(|usage| usage.color)
+    }
+}
+
+/// Color of image not matching any palette color, see [`foreign_colors`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ForeignColor {
+    /// Offending color, serialized as `#rrggbb`.
+    #[serde(serialize_with = "serialize_hex")]
+    pub color: ColorRGB,
+
+    /// Number of pixels having this color.
+    pub pixels: usize,
+
+    /// Position `(x, y)` of the first pixel having this color in row order.
+    pub first_position: (u32, u32),
+
+    /// Palette color closest by CIEDE2000, serialized as `#rrggbb`.
+    #[serde(serialize_with = "serialize_hex")]
+    pub closest: ColorRGB,
+
+    /// CIEDE2000 difference from the closest palette color.
+    pub delta_e: f32,
+}
+
+/// Finds colors of image differing from every palette color by more than `tolerance` CIEDE2000,
+/// 0 accepts exact matches only. Colors are sorted by number of pixels, the most frequent first.
+///
+/// # Example
+/// ```
+/// use ditherum::{metrics::foreign_colors, palette::PaletteRGB};
+///
+/// let mut img = image::RgbImage::from_pixel(4, 4, image::Rgb([0, 0, 0]));
+/// img.put_pixel(2, 1, image::Rgb([250, 250, 250]));
+///
+/// let foreign = foreign_colors(&img, &PaletteRGB::black_and_white(), 0.0).unwrap();
+/// assert_eq!(foreign.len(), 1);
+/// assert_eq!(foreign[0].first_position, (2, 1));
+/// assert!(foreign_colors(&img, &PaletteRGB::black_and_white(), 2.0).unwrap().is_empty());
+/// ```
+///
+/// # Errors
+/// Returns [`PaletteError::PaletteEmpty`] if palette has no colors.
+pub fn foreign_colors(img: &RgbImage, palette: &PaletteRGB, tolerance: f32) -> Result<Vec<ForeignColor>, PaletteError> {
+    if palette.is_empty() {
+        return Err(PaletteError::PaletteEmpty);
+    }
+
+    // Index of foreign color for every distinct pixel value, `None` for matching ones
+    let mut verdicts = HashMap::new();
+    let mut foreign = Vec::<ForeignColor>::new();
+    for (x, y, px) in img.enumerate_pixels() {
+        let index = *verdicts.entry(*px).or_insert_with(|| {
+            let color = ColorRGB::from_rgbu8(*px);
+            (!palette.contains_within(&color, tolerance)).then(|| {
+                let closest = palette.find_closest_by_lab(&color);
+                foreign.push(ForeignColor {
+                    color,
+                    pixels: 0,
+                    first_position: (x, y),
+                    closest,
+                    delta_e: color.dist_by_lab(&closest),
+                });
+                foreign.len() - 1
+            })
+        });
+        if let Some(index) = index {
+            foreign[index].pixels += 1;
+        }
+    }
+
+    foreign.sort_by_key(|foreign_color| std::cmp::Reverse(foreign_color.pixels));
+    Ok(foreign)
+}
+
+fn serialize_hex<S>(color: &ColorRGB, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer
+{
+    serializer.serialize_str(&color.to_hex())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identical_images_quality() {
+        let img = crate::image::generate_test_gradient_image(32, 8, image::Rgb([0, 0, 0]), image::Rgb([255, 128, 0]));
+        let quality = ImageQuality::measure(&img, &img);
+        assert_eq!(quality.mean_delta_e, 0.0);
+        assert_eq!(quality.perceived_mean_delta_e, 0.0);
+        assert!(quality.psnr.is_infinite());
+    }
+
+    #[test]
+    fn test_dithering_perceived_better_than_pixelwise() {
+        let gray_img = RgbImage::from_pixel(16, 16, image::Rgb([128, 128, 128]));
+        let checker_img = RgbImage::from_fn(16, 16, |x, y| {
+            if (x + y) % 2 == 0 { image::Rgb([0, 0, 0]) } else { image::Rgb([255, 255, 255]) }
+        });
+
+        let quality = ImageQuality::measure(&gray_img, &checker_img);
+        assert!(quality.perceived_mean_delta_e < quality.mean_delta_e);
+        assert!(quality.psnr > 0.0);
+    }
+
+    #[test]
+    fn test_palette_usage() {
+        let img = RgbImage::from_fn(4, 4, |x, _| {
+            match x {
+                0 => image::Rgb([255, 255, 255]),
+                1 => image::Rgb([255, 0, 0]),
+                _ => image::Rgb([0, 0, 0]),
+            }
+        });
+        let palette = PaletteRGB::from(vec![
+            ColorRGB::from_rgbu8(image::Rgb([0, 0, 0])),
+            ColorRGB::from_rgbu8(image::Rgb([0, 0, 255])),
+            ColorRGB::from_rgbu8(image::Rgb([255, 255, 255])),
+        ]);
+
+        let usage = PaletteUsage::measure(&img, &palette);
+        let pixels_of = |rgb: [u8; 3]| usage.colors.iter()
+            .find(|usage| usage.color.to_rgbu8() == image::Rgb(rgb))
+            .unwrap();
+        assert_eq!(pixels_of([0, 0, 0]).pixels, 8);
+        assert_eq!(pixels_of([0, 0, 0]).percentage, 50.0);
+        assert_eq!(pixels_of([255, 255, 255]).pixels, 4);
+        assert_eq!(usage.other_pixels, 4);
+        assert_eq!(usage.unused().map(|color| color.to_hex()).collect::<Vec<_>>(), vec!["#0000ff"]);
+    }
+
+    #[test]
+    fn test_foreign_colors() {
+        let img = RgbImage::from_fn(4, 2, |x, _| match x {
+            0 => image::Rgb([255, 0, 0]),
+            1 => image::Rgb([0, 0, 0]),
+            _ => image::Rgb([0, 0, 8]),
+        });
+
+        let foreign = foreign_colors(&img, &PaletteRGB::black_and_white(), 0.0).unwrap();
+        let summary = foreign.iter().map(|foreign| (foreign.color.to_hex(), foreign.pixels, foreign.first_position)).collect::<Vec<_>>();
+        assert_eq!(summary, vec![("#000008".to_string(), 4, (2, 0)), ("#ff0000".to_string(), 2, (0, 0))]);
+        assert_eq!(foreign[0].closest.to_hex(), "#000000");
+
+        // Near black passes with tolerance, red does not
+        let foreign = foreign_colors(&img, &PaletteRGB::black_and_white(), 5.0).unwrap();
+        assert_eq!(foreign.len(), 1);
+        assert!(foreign[0].delta_e > 5.0);
+
+        assert!(foreign_colors(&img, &PaletteRGB::from(Vec::<ColorRGB>::new()), 0.0).is_err());
+    }
+}
@@ -1,91 +1,1256 @@
 use std::{
+    borrow::Borrow, 
     collections::{HashMap, HashSet}, 
     fs::File, 
     io::{
         BufReader, 
-        BufWriter
+        BufWriter,
+        Read,
+        Write
     }, ops::{
         Deref, 
         DerefMut
     }, 
     path::Path, 
+    str::FromStr, 
+    sync::{
+        Arc, 
+        Mutex
+    }, 
     vec
 };
 use errors::PaletteError;
-use palette::color_difference::{
-    Ciede2000, 
-    EuclideanDistance
+use palette::{
+    color_difference::{
+        Ciede2000, 
+        EuclideanDistance
+    }, 
+    FromColor
+};
+use rand::{
+    rngs::StdRng, 
+    Rng, 
+    SeedableRng
 };
 use serde::{
     Serialize, 
     Deserialize
 };
 use crate::{
-    algorithms::kmean, 
+    algorithms::{
+        kmean::{
+            self,
+            Parallelism
+        }, 
+        thresholding
+    }, 
     color::{
         self, 
         ColorRGB
+    }, 
+    metrics
+};
+
+/// Maximal number of merge and refill passes of spaced palette reduction.
+const SPACING_MAX_PASSES: usize = 8;
+
+/// Maximal palette size for which pairwise distances are cached, matrix grows quadratically.
+const PAIRWISE_SHORTCUT_MAX_COLORS: usize = 1024;
+
+/// Bits per channel kept by histogram sampling.
+const HISTOGRAM_CHANNEL_BITS: u32 = 5;
+
+/// Lab lightness difference between successive rounds of harmony hues.
+const HARMONY_LIGHTNESS_STEP: f32 = 18.0;
+
+/// Version of palette JSON document written by [`PaletteRGB::save_to_json`].
+///
+/// 1. Bare array of colors.
+/// 2. Object with `version` and `colors` array, entries can carry metadata, see [`PaletteEntry`].
+pub const PALETTE_JSON_VERSION: u32 = 2;
+
+/// Maximal number of colors of image read as palette, more colors suggest a picture.
+const PALETTE_IMAGE_MAX_COLORS: usize = 256;
+
+/// Random candidates drawn per requested color before random palette generation gives up.
+const RANDOM_PALETTE_ATTEMPTS_PER_COLOR: usize = 2000;
+
+pub mod errors {
+    use crate::algorithms::kmean::CentroidsFindError;
+
+    use super::ImageRegion;
+
+    #[derive(Debug, thiserror::Error)]
+    pub enum PaletteError {
+        #[error("Not enough colors to be converted to: {0}.")]
+        NotEnoughColors(usize),
+
+        #[error("Faild to convert, reason={0}")]
+        ConvertionErrot(CentroidsFindError),
+
+        #[error("I/O error, reason={0}")]
+        IoError(std::io::Error),
+
+        #[error("JSON parsing failed, reason={0}")]
+        JsonParsingFailed(serde_json::error::Error),
+
+        #[error("PaletteEmpty")]
+        PaletteEmpty,
+
+        #[error("Invalid sampling '{0}', expected 'all', 'every:N', 'random:N[:SEED]' or 'histogram'.")]
+        InvalidSampling(String),
+
+        #[error("Invalid ignored colors '{0}', expected hex colors with optional tolerance like '#00ff00:10'.")]
+        InvalidExtractOptions(String),
+
+        #[error("Invalid coverage '{0}', expected pixels count like '20' or percentage like '0.5%'.")]
+        InvalidCoverage(String),
+
+        #[error("Invalid region '{0}', expected 'x,y,width,height'.")]
+        InvalidRegion(String),
+
+        #[error("Region {region} does not overlap image of size {width}x{height}.")]
+        RegionOutsideImage {
+            region: ImageRegion,
+            width: u32,
+            height: u32
+        },
+
+        #[error("Only {generated} of {requested} random colors meet the constraints.")]
+        RandomConstraintsUnsatisfied {
+            requested: usize,
+            generated: usize
+        },
+
+        #[error("Palette JSON version {version} is not supported, newest supported is {supported}.")]
+        UnsupportedPaletteVersion {
+            version: u64,
+            supported: u32
+        },
+
+        #[error("Invalid palette JSON, {0}.")]
+        InvalidPaletteDocument(String),
+
+        #[error("Image {width}x{height} has more than 256 colors, it does not look like palette image.")]
+        NotPaletteImage {
+            width: u32,
+            height: u32
+        },
+
+        #[error("Image loading failed, reason={0}")]
+        ImageLoadingFailed(image::ImageError),
+
+        #[error("Too many fixed colors {fixed} for target colors count {target}.")]
+        TooManyFixedColors {
+            fixed: usize,
+            target: usize
+        },
+
+        #[error("Palettes have different lengths {from} and {to}.")]
+        LengthMismatch {
+            from: usize,
+            to: usize
+        },
+
+        #[error("Palette of {0} colors does not fit 16-bit indices.")]
+        TooManyColorsForIndices(usize),
+
+        #[error("Index {index} is outside palette of {size} colors.")]
+        IndexOutOfRange {
+            index: usize,
+            size: usize
+        },
+
+        #[error("Got {length} indices for {width}x{height} image.")]
+        IndicesLengthMismatch {
+            width: u32,
+            height: u32,
+            length: usize
+        },
+    }
+
+    impl From<CentroidsFindError> for PaletteError {
+        fn from(value: CentroidsFindError) -> Self {
+            Self::ConvertionErrot(value)
+        }
+    }
+
+    impl From<std::io::Error> for PaletteError {
+        fn from(value: std::io::Error) -> Self {
+            Self::IoError(value)
+        }
+    }
+
+    impl From<image::ImageError> for PaletteError {
+        fn from(value: image::ImageError) -> Self {
+            Self::ImageLoadingFailed(value)
+        }
+    }
+
+    impl From<serde_json::error::Error> for PaletteError {
+        fn from(value: serde_json::error::Error) -> Self {
+            Self::JsonParsingFailed(value)
+        }
+    }
+}
+
+/// Strategy of picking image pixels when extracting a palette.
+///
+/// Collecting every pixel of large photos allocates huge set of unique colors, sampling
+/// keeps extraction fast while preserving colors distribution well enough for reduction.
+///
+/// Parsed from `all`, `every:N`, `random:N`, `random:N:SEED` or `histogram`.
+///
+/// # Example
+/// ```
+/// use ditherum::palette::PaletteSampling;
+///
+/// assert_eq!("every:4".parse::<PaletteSampling>().unwrap(), PaletteSampling::EveryNth(4));
+/// assert_eq!("random:1000".parse::<PaletteSampling>().unwrap(), PaletteSampling::Random { samples: 1000, seed: 0 });
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum PaletteSampling {
+    /// Every pixel is collected.
+    #[default]
+    All,
+
+    /// Every N-th pixel in row-major order is collected.
+    EveryNth(usize),
+
+    /// Given number of randomly picked pixels is collected, the same seed picks the same pixels.
+    Random {
+        samples: usize,
+        seed: u64
+    },
+
+    /// Pixels are binned by 5 most significant bits of each channel, mean color of each
+    /// occupied bin is collected. Gives at most 32768 colors.
+    Histogram,
+}
+
+impl FromStr for PaletteSampling {
+    type Err = self::errors::PaletteError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || self::errors::PaletteError::InvalidSampling(s.to_string());
+
+        let parts = s.split(':').collect::<Vec<_>>();
+        let sampling = match parts[..] {
+            [name] if name.eq_ignore_ascii_case("all") => Some(Self::All),
+            [name] if name.eq_ignore_ascii_case("histogram") => Some(Self::Histogram),
+            [name, step] if name.eq_ignore_ascii_case("every") => step.parse()
+                .ok()
+                .filter(|&step| step > 0)
+                .map(Self::EveryNth),
+            [name, samples] if name.eq_ignore_ascii_case("random") => samples.parse()
+                .ok()
+                .map(|samples| Self::Random { samples, seed: 0 }),
+            [name, samples, seed] if name.eq_ignore_ascii_case("random") => samples.parse()
+                .ok()
+                .zip(seed.parse().ok())
+                .map(|(samples, seed)| Self::Random { samples, seed }),
+            _ => None,
+        };
+
+        sampling.ok_or_else(invalid)
+    }
+}
+
+impl TryFrom<String> for PaletteSampling {
+    type Error = self::errors::PaletteError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<PaletteSampling> for String {
+    fn from(value: PaletteSampling) -> Self {
+        value.to_string()
+    }
+}
+
+impl std::fmt::Display for PaletteSampling {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::All => write!(f, "all"),
+            Self::EveryNth(step) => write!(f, "every:{step}"),
+            Self::Random { samples, seed } => write!(f, "random:{samples}:{seed}"),
+            Self::Histogram => write!(f, "histogram"),
+        }
+    }
+}
+
+impl PaletteSampling {
+    /// Returns pixels of image picked by this strategy, histogram bins are not merged here.
+    fn sample_pixels<'a>(&self, img: &'a image::RgbImage) -> Box<dyn Iterator<Item = image::Rgb<u8>> + 'a> {
+        match *self {
+            Self::All | Self::Histogram => Box::new(img.pixels().copied()),
+            Self::EveryNth(step) => Box::new(img.pixels().copied().step_by(step.max(1))),
+            Self::Random { samples, seed } => {
+                if img.is_empty() {
+                    return Box::new(std::iter::empty());
+                }
+                let mut rng = StdRng::seed_from_u64(seed);
+                Box::new((0..samples).map(move |_| {
+                    *img.get_pixel(rng.random_range(0..img.width()), rng.random_range(0..img.height()))
+                }))
+            },
+        }
+    }
+}
+
+/// Minimal share of image a color has to cover to be kept in extracted palette.
+///
+/// Parsed from pixels count like `20` or percentage like `0.5%`.
+///
+/// # Example
+/// ```
+/// use ditherum::palette::MinCoverage;
+///
+/// assert_eq!("20".parse::<MinCoverage>().unwrap(), MinCoverage::Pixels(20));
+/// assert_eq!("0.5%".parse::<MinCoverage>().unwrap(), MinCoverage::Percent(0.5));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MinCoverage {
+    /// Number of pixels, sampled ones if sampling is used.
+    Pixels(usize),
+
+    /// Percentage of all, or sampled, pixels.
+    Percent(f32),
+}
+
+impl MinCoverage {
+    /// Returns minimal number of pixels out of `total_count` a color has to cover.
+    pub fn min_pixels(&self, total_count: usize) -> usize {
+        match *self {
+            Self::Pixels(count) => count,
+            Self::Percent(percent) => (total_count as f64 * percent as f64 / 100.0).ceil() as usize,
+        }
+    }
+}
+
+impl FromStr for MinCoverage {
+    type Err = self::errors::PaletteError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || self::errors::PaletteError::InvalidCoverage(s.to_string());
+
+        match s.trim().strip_suffix('%') {
+            Some(percent) => percent.trim().parse::<f32>()
+                .ok()
+                .filter(|percent| (0.0..=100.0).contains(percent))
+                .map(Self::Percent)
+                .ok_or_else(invalid),
+            None => s.trim().parse::<usize>()
+                .map(Self::Pixels)
+                .map_err(|_| invalid()),
+        }
+    }
+}
+
+impl std::fmt::Display for MinCoverage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Pixels(count) => write!(f, "{count}"),
+            Self::Percent(percent) => write!(f, "{percent}%"),
+        }
+    }
+}
+
+/// Filtering of colors collected during palette extraction, before reduction.
+///
+/// Parsed from comma separated hex colors with optional CIEDE2000 tolerance after colon,
+/// e.g. `#00ff00:10` or `#00ff00,#0000ff`.
+///
+/// # Example
+/// ```
+/// use ditherum::{color::ColorRGB, palette::{PaletteExtractOptions, PaletteRGB, PaletteSampling}};
+///
+/// // Subject on green screen
+/// let mut img = image::RgbImage::from_pixel(8, 8, image::Rgb([0, 255, 0]));
+/// img.put_pixel(4, 4, image::Rgb([200, 40, 40]));
+/// img.put_pixel(5, 4, image::Rgb([10, 250, 10]));
+///
+/// let options = "#00ff00:10".parse::<PaletteExtractOptions>().unwrap();
+/// let palette = PaletteRGB::from_rgbu8_image_filtered(&img, PaletteSampling::All, &options);
+/// assert_eq!(palette.to_vec(), vec![ColorRGB([200, 40, 40])]);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PaletteExtractOptions {
+    /// Colors left out of the palette, e.g. green screen background.
+    pub exclude: Vec<ColorRGB>,
+
+    /// CIEDE2000 difference up to which colors are considered the same as excluded ones,
+    /// `0.0` excludes exact matches only.
+    pub tolerance: f32,
+
+    /// Colors covering less of the image are dropped, e.g. JPEG artifacts and antialiasing fringes.
+    pub min_coverage: Option<MinCoverage>,
+}
+
+impl PaletteExtractOptions {
+    /// Sets colors left out of the palette along with tolerance of matching them.
+    pub fn with_exclude(mut self, exclude: Vec<ColorRGB>, tolerance: f32) -> Self {
+        self.exclude = exclude;
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Sets minimal share of image a color has to cover to be kept.
+    pub fn with_min_coverage(mut self, min_coverage: MinCoverage) -> Self {
+        self.min_coverage = Some(min_coverage);
+        self
+    }
+
+    /// Removes colors not meeting the options from extracted palette.
+    fn apply(&self, palette: &mut PaletteRGB) {
+        if !self.exclude.is_empty() {
+            palette.remove_within(&PaletteRGB::from(self.exclude.clone()), self.tolerance);
+        }
+    }
+}
+
+impl FromStr for PaletteExtractOptions {
+    type Err = self::errors::PaletteError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || self::errors::PaletteError::InvalidExtractOptions(s.to_string());
+
+        let (colors, tolerance) = match s.rsplit_once(':') {
+            Some((colors, tolerance)) => (colors, tolerance.trim().parse::<f32>().map_err(|_| invalid())?),
+            None => (s, 0.0),
+        };
+        if tolerance.is_nan() || tolerance < 0.0 {
+            return Err(invalid());
+        }
+
+        let exclude = colors.split(',')
+            .map(|color| color.trim().parse::<ColorRGB>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| invalid())?;
+
+        Ok(Self::default().with_exclude(exclude, tolerance))
+    }
+}
+
+/// Rectangular area of an image, e.g. subject of a photo the palette is extracted from.
+///
+/// Parsed from `x,y,width,height`.
+///
+/// # Example
+/// ```
+/// use ditherum::palette::ImageRegion;
+///
+/// let region = "10,20,64,32".parse::<ImageRegion>().unwrap();
+/// assert_eq!(region, ImageRegion { x: 10, y: 20, width: 64, height: 32 });
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ImageRegion {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl FromStr for ImageRegion {
+    type Err = self::errors::PaletteError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let values = s.split(',')
+            .map(|value| value.trim().parse::<u32>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| self::errors::PaletteError::InvalidRegion(s.to_string()))?;
+
+        match values[..] {
+            [x, y, width, height] if width > 0 && height > 0 => Ok(Self { x, y, width, height }),
+            _ => Err(self::errors::PaletteError::InvalidRegion(s.to_string())),
+        }
+    }
+}
+
+impl TryFrom<String> for ImageRegion {
+    type Error = self::errors::PaletteError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<ImageRegion> for String {
+    fn from(value: ImageRegion) -> Self {
+        value.to_string()
+    }
+}
+
+impl std::fmt::Display for ImageRegion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{},{},{},{}", self.x, self.y, self.width, self.height)
+    }
+}
+
+impl ImageRegion {
+    /// Copies part of image covered by the region, region exceeding image is clipped.
+    ///
+    /// # Errors
+    /// Returns [`PaletteError::RegionOutsideImage`] if region does not overlap the image.
+    pub fn crop(&self, img: &image::RgbImage) -> Result<image::RgbImage, PaletteError> {
+        let (img_width, img_height) = img.dimensions();
+        let region = self.clipped(img_width, img_height)
+            .ok_or(PaletteError::RegionOutsideImage { region: *self, width: img_width, height: img_height })?;
+
+        Ok(image::imageops::crop_imm(img, region.x, region.y, region.width, region.height).to_image())
+    }
+
+    /// Returns part of the region lying within image of given dimensions, `None` if they do not overlap.
+    pub fn clipped(&self, img_width: u32, img_height: u32) -> Option<Self> {
+        let width = self.width.min(img_width.saturating_sub(self.x));
+        let height = self.height.min(img_height.saturating_sub(self.y));
+        (width > 0 && height > 0).then_some(Self { width, height, ..*self })
+    }
+}
+
+/// Clustering backend of palette reduction, see [`PaletteRGB::try_reduce_with_strategy`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, clap::ValueEnum, Serialize, Deserialize)]
+pub enum ReduceStrategy {
+    /// K-means clustering in Lab space, randomly initialized
+    #[default]
+    #[value(name = "kmeans")]
+    #[serde(rename = "kmeans")]
+    KMeans,
+
+    /// Deterministic merging of the closest pair of colors, for already small palettes
+    #[value(name = "agglomerative")]
+    #[serde(rename = "agglomerative")]
+    Agglomerative,
+}
+
+impl ReduceStrategy {
+    /// Returns short name of the strategy, e.g. `kmeans`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::KMeans => "kmeans",
+            Self::Agglomerative => "agglomerative",
+        }
+    }
+}
+
+/// Rule of picking hues around the color wheel, see [`PaletteRGB::harmony`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, clap::ValueEnum, Serialize, Deserialize)]
+pub enum HarmonyKind {
+    /// Seed hue and the opposite one
+    #[value(name = "complementary")]
+    #[serde(rename = "complementary")]
+    Complementary,
+
+    /// Three hues evenly spaced by 120 degrees
+    #[value(name = "triadic")]
+    #[serde(rename = "triadic")]
+    Triadic,
+
+    /// Seed hue and its neighbours 30 degrees apart
+    #[value(name = "analogous")]
+    #[serde(rename = "analogous")]
+    Analogous,
+
+    /// Seed hue and both neighbours of the opposite one
+    #[value(name = "split-complementary")]
+    #[serde(rename = "split-complementary")]
+    SplitComplementary,
+}
+
+impl HarmonyKind {
+    /// Returns short name of the harmony, e.g. `triadic`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Complementary => "complementary",
+            Self::Triadic => "triadic",
+            Self::Analogous => "analogous",
+            Self::SplitComplementary => "split-complementary",
+        }
+    }
+
+    /// Returns hue offsets in degrees from the seed hue.
+    fn hue_offsets(&self) -> &'static [f32] {
+        match self {
+            Self::Complementary => &[0.0, 180.0],
+            Self::Triadic => &[0.0, 120.0, 240.0],
+            Self::Analogous => &[0.0, 30.0, -30.0],
+            Self::SplitComplementary => &[0.0, 150.0, 210.0],
+        }
+    }
+}
+
+impl std::fmt::Display for HarmonyKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Constraints of randomly generated palette, see [`PaletteRGB::random`].
+///
+/// # Example
+/// ```
+/// use ditherum::palette::{PaletteRGB, RandomPaletteOptions};
+///
+/// let options = RandomPaletteOptions::default()
+///     .with_lightness(30.0, 80.0)
+///     .with_min_delta_e(15.0)
+///     .with_seed(7);
+/// let palette = PaletteRGB::random(6, &options).unwrap();
+///
+/// assert_eq!(palette.len(), 6);
+/// assert_eq!(palette, PaletteRGB::random(6, &options).unwrap());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RandomPaletteOptions {
+    /// Minimal Lab lightness of generated colors.
+    pub min_lightness: f32,
+
+    /// Maximal Lab lightness of generated colors.
+    pub max_lightness: f32,
+
+    /// Minimal CIEDE2000 difference between every two generated colors.
+    pub min_delta_e: f32,
+
+    /// The same seed and constraints generate the same palette.
+    pub seed: u64,
+}
+
+impl Default for RandomPaletteOptions {
+    fn default() -> Self {
+        Self {
+            min_lightness: 0.0,
+            max_lightness: 100.0,
+            min_delta_e: 0.0,
+            seed: 0,
+        }
+    }
+}
+
+impl RandomPaletteOptions {
+    /// Sets range of Lab lightness, `0.0..=100.0`.
+    pub fn with_lightness(mut self, min_lightness: f32, max_lightness: f32) -> Self {
+        self.min_lightness = min_lightness;
+        self.max_lightness = max_lightness;
+        self
+    }
+
+    /// Sets minimal CIEDE2000 difference between every two colors.
+    pub fn with_min_delta_e(mut self, min_delta_e: f32) -> Self {
+        self.min_delta_e = min_delta_e;
+        self
+    }
+
+    /// Sets seed of random generator.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+}
+
+/// Metric used to measure distance between palette colors.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum DistanceMetric {
+    /// Euclidean distance of RGB channels.
+    Rgb,
+
+    /// CIEDE2000 color difference.
+    #[default]
+    Ciede2000,
+}
+
+/// Symmetric matrix of distances between every pair of palette colors.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DistanceMatrix {
+    size: usize,
+
+    /// Upper triangle without diagonal, row by row.
+    distances: Vec<f32>,
+}
+
+impl DistanceMatrix {
+    fn new(colors: &[ColorRGB], metric: DistanceMetric) -> Self {
+        // Lab conversion done once per color instead of once per pair
+        let lab_colors = colors.iter()
+            .map(ColorRGB::to_lab)
+            .collect::<Vec<_>>();
+
+        let distances = (0..colors.len())
+            .flat_map(|i| (i + 1..colors.len()).map(move |j| (i, j)))
+            .map(|(i, j)| match metric {
+                DistanceMetric::Rgb => colors[i].dist_by_rgb(&colors[j]),
+                DistanceMetric::Ciede2000 => lab_colors[i].difference(lab_colors[j]),
+            })
+            .collect();
+
+        Self { size: colors.len(), distances }
+    }
+
+    /// Returns number of colors.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns distance between colors at indices `i` and `j`.
+    /// 
+    /// # Panics
+    /// Panics if any index is out of bounds.
+    pub fn get(&self, i: usize, j: usize) -> f32 {
+        assert!(i < self.size && j < self.size, "Index out of bounds.");
+        match i.cmp(&j) {
+            std::cmp::Ordering::Equal => 0.0,
+            std::cmp::Ordering::Less => self.distances[self.triangle_index(i, j)],
+            std::cmp::Ordering::Greater => self.distances[self.triangle_index(j, i)],
+        }
+    }
+
+    /// Returns indices and distance of the closest pair of distinct colors, `None` for fewer than two colors.
+    pub fn closest_pair(&self) -> Option<(usize, usize, f32)> {
+        (0..self.size)
+            .flat_map(|i| (i + 1..self.size).map(move |j| (i, j)))
+            .zip(self.distances.iter())
+            .map(|((i, j), &distance)| (i, j, distance))
+            .min_by(|(_, _, a), (_, _, b)| a.total_cmp(b))
+    }
+
+    /// Returns matrix of colors reordered so that new index `k` is old index `order[k]`.
+    fn permuted(&self, order: &[usize]) -> Self {
+        let distances = (0..self.size)
+            .flat_map(|i| (i + 1..self.size).map(move |j| (i, j)))
+            .map(|(i, j)| self.get(order[i], order[j]))
+            .collect();
+        Self { size: self.size, distances }
+    }
+
+    fn triangle_index(&self, i: usize, j: usize) -> usize {
+        // Rows before `i` have `size - 1`, `size - 2`, ... elements
+        i * (2 * self.size - i - 1) / 2 + (j - i - 1)
+    }
+}
+
+/// Distance matrices computed for current palette colors, cleared whenever colors are mutated.
+#[derive(Debug, Default)]
+struct DistancesCache(Mutex<Vec<(DistanceMetric, Arc<DistanceMatrix>)>>);
+
+impl DistancesCache {
+    fn get_or_compute(&self, colors: &[ColorRGB], metric: DistanceMetric) -> Arc<DistanceMatrix> {
+        let mut cache = self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some((_, matrix)) = cache.iter().find(|(cached_metric, _)| *cached_metric == metric) {
+            return matrix.clone();
+        }
+
+        let matrix = Arc::new(DistanceMatrix::new(colors, metric));
+        cache.push((metric, matrix.clone()));
+        matrix
+    }
+
+    /// Reorders cached matrices along with palette colors, see [`DistanceMatrix::permuted`].
+    fn permute(&mut self, order: &[usize]) {
+        let cache = self.0.get_mut().unwrap_or_else(|poisoned| poisoned.into_inner());
+        for (_, matrix) in cache.iter_mut() {
+            *matrix = Arc::new(matrix.permuted(order));
+        }
+    }
+
+    fn clear(&mut self) {
+        self.0.get_mut()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clear();
+    }
+}
+
+impl Clone for DistancesCache {
+    fn clone(&self) -> Self {
+        let cache = self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        Self(Mutex::new(cache.clone()))
+    }
+}
+
+/// Palette color together with its metadata, the rich form of palette JSON entry.
+///
+/// Serialized as object like `{"rgb": [135, 206, 235], "name": "sky", "count": 120, "locked": true, "order": 1}`,
+/// only `rgb` is required.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PaletteEntry {
+    #[serde(rename = "rgb")]
+    pub color: ColorRGB,
+
+    /// Human-readable name, e.g. paint or thread name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// Number of source pixels, or other items, the color stands for.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub count: Option<usize>,
+
+    /// Locked colors survive palette reduction unchanged.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub locked: bool,
+
+    /// Index the color is pinned at instead of lightness order, see [`PaletteRGB::sort`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub order: Option<usize>,
+}
+
+impl PaletteEntry {
+    /// Creates entry of plain color without metadata.
+    pub fn new(color: ColorRGB) -> Self {
+        Self { color, name: None, count: None, locked: false, order: None }
+    }
+
+    /// Sets human-readable name.
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Sets source count.
+    pub fn with_count(mut self, count: usize) -> Self {
+        self.count = Some(count);
+        self
+    }
+
+    /// Sets whether color survives palette reduction unchanged.
+    pub fn with_locked(mut self, locked: bool) -> Self {
+        self.locked = locked;
+        self
+    }
+
+    /// Pins color at palette index, e.g. when hardware gives index 0 meaning of transparency.
+    pub fn with_order(mut self, order: usize) -> Self {
+        self.order = Some(order);
+        self
+    }
+
+    /// Checks if entry carries anything besides the color.
+    fn has_metadata(&self) -> bool {
+        self.name.is_some() || self.count.is_some() || self.locked || self.order.is_some()
+    }
+}
+
+/// Colors of palette before reduction mapped to colors they collapsed into, see
+/// [`PaletteRGB::try_reduce_with_mapping`].
+pub type ReductionMapping = HashMap<ColorRGB, ColorRGB>;
+
+/// Ordered set of colors, the simple view of palette, with optional metadata of each color.
+///
+/// Serialized as JSON array of `[r, g, b]` colors, files wrap it in versioned document, see
+/// [`PALETTE_JSON_VERSION`]. Entries can also be hex or CSS color names
+/// like `"steelblue"`, or [`PaletteEntry`] objects with name, count and locked flag. Metadata
+/// is kept with colors and saved back, so it survives round-trips, see [`PaletteRGB::entries`].
+#[derive(Clone)]
+pub struct PaletteRGB(Vec<ColorRGB>, DistancesCache, HashMap<ColorRGB, PaletteEntry>);
+
+/// Prints colors only, metadata and cached distances are left out so that output is stable.
+impl std::fmt::Debug for PaletteRGB {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("PaletteRGB").field(&self.0).finish()
+    }
+}
+
+/// Palettes are equal if they have the same colors in the same order, names are ignored.
+impl PartialEq for PaletteRGB {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl Eq for PaletteRGB {}
+
+impl PaletteRGB {
+    
+    /// Returns distances between every pair of palette colors.
+    /// 
+    /// The matrix is computed once and cached until palette colors are mutated, so that
+    /// repeated spacing checks of reduction and deduplication do not recompute CIEDE2000
+    /// for every pair. Matrix indices match palette indices.
+    /// 
+    /// # Example
+    /// ```
+    /// use ditherum::palette::{DistanceMetric, PaletteRGB};
+    /// 
+    /// let palette = PaletteRGB::black_and_white();
+    /// let distances = palette.pairwise_distances(DistanceMetric::Rgb);
+    /// assert_eq!(distances.get(0, 1), (3.0f32 * 255.0 * 255.0).sqrt());
+    /// assert_eq!(distances.closest_pair().map(|(i, j, _)| (i, j)), Some((0, 1)));
+    /// ```
+    pub fn pairwise_distances(&self, metric: DistanceMetric) -> Arc<DistanceMatrix> {
+        self.1.get_or_compute(&self.0, metric)
+    }
+
+    /// Returns CIEDE2000 difference of colors at two palette indices, read from cached distance
+    /// matrix unless palette is too big for it.
+    fn ciede2000_between(&self) -> impl Fn(usize, usize) -> f32 + '_ {
+        let matrix = (self.len() <= PAIRWISE_SHORTCUT_MAX_COLORS).then(|| self.pairwise_distances(DistanceMetric::Ciede2000));
+        move |i, j| match &matrix {
+            Some(matrix) => matrix.get(i, j),
+            None => self.0[i].dist_by_lab(&self.0[j]),
+        }
+    }
+
+    /// Clusters colors at `indices`, visited in given order, and returns Lab mean of each cluster.
+    /// 
+    /// Each color joins the first cluster whose first member is within `delta_e`, so distances
+    /// are read from cached matrix, or starts a new cluster.
+    fn cluster_within(&self, indices: &[usize], delta_e: f32) -> Vec<ColorRGB> {
+        let distance = self.ciede2000_between();
+        // Clusters as (first member index, Lab sum, members count)
+        let mut clusters: Vec<(usize, palette::Lab, usize)> = Vec::new();
+        for &idx in indices {
+            let lab_color = self.0[idx].to_lab();
+            match clusters.iter_mut().find(|(first_idx, _, _)| distance(*first_idx, idx) <= delta_e) {
+                Some((_, lab_sum, count)) => {
+                    color::ops::lab_mut_add(lab_sum, &lab_color);
+                    *count += 1;
+                },
+                None => clusters.push((idx, lab_color, 1)),
+            }
+        }
+
+        clusters.into_iter()
+            .map(|(_, lab_sum, count)| ColorRGB::from_lab(color::ops::lab_mul_scalar(&lab_sum, 1.0 / count as f32)))
+            .collect()
+    }
+
+    /// Checks if any two colors have CIEDE2000 difference up to `delta_e`, 
+    /// assumes they do for palettes too big for the distance matrix.
+    fn has_colors_within(&self, delta_e: f32) -> bool {
+        if self.len() > PAIRWISE_SHORTCUT_MAX_COLORS {
+            return true;
+        }
+
+        self.pairwise_distances(DistanceMetric::Ciede2000)
+            .closest_pair()
+            .is_some_and(|(_, _, distance)| distance <= delta_e)
+    }
+
+    /// Sorts colors by Lab lightness, darkest first.
+    /// 
+    /// Same order as sorting by [`ColorRGB`] ordering, but lightness is computed once per color.
+    /// Cached [`PaletteRGB::pairwise_distances`] are reordered along with colors.
+    /// Colors with [`PaletteEntry::order`] are pinned at that index and the rest is sorted around
+    /// them, so indices meaningful to hardware survive loading, reduction and merging. Pinned
+    /// index beyond palette length puts the color last, colors pinned at the same index keep their order.
+    /// 
+    /// # Example
+    /// ```
+    /// use ditherum::{color::ColorRGB, palette::{PaletteEntry, PaletteRGB}};
+    ///
+    /// let palette = PaletteRGB::from_entries(vec![
+    ///     PaletteEntry::new(ColorRGB([0, 0, 0])),
+    ///     PaletteEntry::new(ColorRGB([255, 255, 255])),
+    ///     PaletteEntry::new(ColorRGB([255, 0, 255])).with_name("transparent").with_order(0),
+    /// ]);
+    ///
+    /// assert_eq!(palette.to_vec(), vec![ColorRGB([255, 0, 255]), ColorRGB([0, 0, 0]), ColorRGB([255, 255, 255])]);
+    /// ```
+    pub fn sort(&mut self) {
+        let (mut pinned_indices, unpinned_indices): (Vec<_>, Vec<_>) = self.iter()
+            .enumerate()
+            .map(|(idx, color)| (self.2.get(color).and_then(|entry| entry.order), idx))
+            .partition(|(order, _)| order.is_some());
+        let mut lightness_indices = unpinned_indices.into_iter()
+            .map(|(_, idx)| (self.0[idx].to_lab().l, idx))
+            .collect::<Vec<_>>();
+        lightness_indices.sort_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+        let mut sorted_indices = lightness_indices.into_iter()
+            .map(|(_, idx)| idx)
+            .collect::<Vec<_>>();
+        pinned_indices.sort_by_key(|(order, _)| *order);
+        for (order, idx) in pinned_indices {
+            let index = order.unwrap_or_default().min(sorted_indices.len());
+            sorted_indices.insert(index, idx);
+        }
+        if sorted_indices.iter().enumerate().all(|(position, &idx)| position == idx) {
+            return;
+        }
+
+        // Cached distances are reordered instead of computed again for sorted colors
+        self.0 = sorted_indices.iter().map(|&idx| self.0[idx]).collect();
+        self.1.permute(&sorted_indices);
+    }
+
+    /// Returns colors together with their metadata, in palette order.
+    ///
+    /// # Example
+    /// ```
+    /// use ditherum::{color::ColorRGB, palette::{PaletteEntry, PaletteRGB}};
+    ///
+    /// let palette = PaletteRGB::from_entries(vec![
+    ///     PaletteEntry::new(ColorRGB([0, 0, 0])).with_name("ink").with_locked(true),
+    ///     PaletteEntry::new(ColorRGB([250, 245, 230])).with_name("paper"),
+    /// ]);
+    ///
+    /// let entries = palette.entries();
+    /// assert_eq!(entries[0].name.as_deref(), Some("ink"));
+    /// assert_eq!(palette.locked_colors(), vec![ColorRGB([0, 0, 0])]);
+    /// ```
+    pub fn entries(&self) -> Vec<PaletteEntry> {
+        self.iter()
+            .map(|color| self.2.get(color).cloned().unwrap_or_else(|| PaletteEntry::new(*color)))
+            .collect()
+    }
+
+    /// Creates palette of entries colors keeping their metadata, sorted like any other palette.
+    pub fn from_entries(entries: Vec<PaletteEntry>) -> Self {
+        let mut palette = PaletteRGB::from(entries.iter().map(|entry| entry.color).collect::<Vec<_>>());
+        for entry in entries {
+            palette.set_entry(entry);
+        }
+        // Pinned orders are known only now
+        palette.sort();
+        palette
+    }
+
+    /// Sets metadata of entry color, whether or not the color is in the palette.
+    pub fn set_entry(&mut self, entry: PaletteEntry) {
+        if entry.has_metadata() {
+            self.2.insert(entry.color, entry);
+        } else {
+            self.2.remove(&entry.color);
+        }
+    }
+
+    /// Returns human-readable name given to the color, if any.
+    pub fn color_name(&self, color: &ColorRGB) -> Option<&str> {
+        self.2.get(color).and_then(|entry| entry.name.as_deref())
+    }
+
+    /// Gives human-readable name to the color, saved together with it to JSON.
+    pub fn set_color_name(&mut self, color: ColorRGB, name: impl Into<String>) {
+        self.2.entry(color)
+            .or_insert_with(|| PaletteEntry::new(color))
+            .name = Some(name.into());
+    }
+
+    /// Returns colors locked against reduction, in palette order.
+    pub fn locked_colors(&self) -> Vec<ColorRGB> {
+        self.iter()
+            .filter(|color| self.2.get(color).is_some_and(|entry| entry.locked))
+            .copied()
+            .collect()
+    }
+
+    /// Copies metadata of colors present in this palette from `source`, existing metadata is kept.
+    /// Colors are sorted again if any of them got pinned order.
+    fn with_metadata_of(mut self, source: &HashMap<ColorRGB, PaletteEntry>) -> Self {
+        for color in self.0.iter() {
+            if let Some(entry) = source.get(color) {
+                self.2.entry(*color).or_insert_with(|| entry.clone());
+            }
+        }
+        if self.2.values().any(|entry| entry.order.is_some()) {
+            self.sort();
+        }
+        self
+    }
+
+    /// Names colors exactly matching CSS named colors, already named colors are left as they are.
+    ///
+    /// # Example
+    /// ```
+    /// use ditherum::{color::ColorRGB, palette::PaletteRGB};
+    ///
+    /// let palette = PaletteRGB::black_and_white().with_css_names();
+    /// assert_eq!(palette.color_name(&ColorRGB([255, 255, 255])), Some("white"));
+    /// ```
+    pub fn with_css_names(mut self) -> Self {
+        for color in self.0.iter() {
+            if let Some(name) = color::named::name_of(color) {
+                let entry = self.2.entry(*color).or_insert_with(|| PaletteEntry::new(*color));
+                entry.name.get_or_insert_with(|| name.to_string());
+            }
+        }
+        self
     }
-};
 
-pub mod errors {
-    use crate::algorithms::kmean::CentroidsFindError;
+    /// Extracts a palette from an image by collecting unique pixel colors.
+    pub fn from_rgbu8_image(img: &image::RgbImage) -> Self {
+        Self::from_rgbu8_image_sampled(img, PaletteSampling::All)
+    }
 
-    #[derive(Debug, thiserror::Error)]
-    pub enum PaletteError {
-        #[error("Not enough colors to be converted to: {0}.")]
-        NotEnoughColors(usize),
+    /// Extracts a palette from an image by collecting unique colors of sampled pixels.
+    ///
+    /// # Example
+    /// ```
+    /// use ditherum::palette::{PaletteRGB, PaletteSampling};
+    ///
+    /// let img = ditherum::image::generate_test_gradient_image(256, 4, image::Rgb([0, 0, 0]), image::Rgb([255, 255, 255]));
+    ///
+    /// let palette = PaletteRGB::from_rgbu8_image_sampled(&img, PaletteSampling::Histogram);
+    /// assert_eq!(palette.len(), 32);
+    /// ```
+    pub fn from_rgbu8_image_sampled(img: &image::RgbImage, sampling: PaletteSampling) -> Self {
+        Self::from_rgbu8_images_sampled([img], sampling)
+    }
 
-        #[error("Faild to convert, reason={0}")]
-        ConvertionErrot(CentroidsFindError),
+    /// Extracts a palette from region of an image only, so that the subject colors are not
+    /// outnumbered by a dominating background, e.g. product shot on white.
+    ///
+    /// # Errors
+    /// Returns [`PaletteError::RegionOutsideImage`] if region does not overlap the image.
+    ///
+    /// # Example
+    /// ```
+    /// use ditherum::palette::{ImageRegion, PaletteRGB};
+    ///
+    /// let mut img = image::RgbImage::from_pixel(64, 64, image::Rgb([255, 255, 255]));
+    /// img.put_pixel(40, 40, image::Rgb([200, 0, 0]));
+    ///
+    /// let region = ImageRegion { x: 32, y: 32, width: 16, height: 16 };
+    /// let palette = PaletteRGB::from_image_region(&img, region).unwrap();
+    /// assert_eq!(palette.len(), 2);
+    /// ```
+    pub fn from_image_region(img: &image::RgbImage, region: ImageRegion) -> Result<Self, PaletteError> {
+        Self::from_image_region_sampled(img, region, PaletteSampling::All)
+    }
 
-        #[error("I/O error, reason={0}")]
-        IoError(std::io::Error),
+    /// Extracts a palette from region of an image by collecting unique colors of sampled pixels.
+    ///
+    /// # Errors
+    /// Returns [`PaletteError::RegionOutsideImage`] if region does not overlap the image.
+    pub fn from_image_region_sampled(img: &image::RgbImage, region: ImageRegion, sampling: PaletteSampling) -> Result<Self, PaletteError> {
+        Ok(Self::from_rgbu8_image_sampled(&region.crop(img)?, sampling))
+    }
 
-        #[error("JSON parsing failed, reason={0}")]
-        JsonParsingFailed(serde_json::error::Error),
+    /// Extracts a palette from an image by collecting unique colors of sampled pixels, leaving out
+    /// colors filtered by `options`.
+    pub fn from_rgbu8_image_filtered(img: &image::RgbImage, sampling: PaletteSampling, options: &PaletteExtractOptions) -> Self {
+        Self::from_rgbu8_images_filtered([img], sampling, options)
+    }
 
-        #[error("PaletteEmpty")]
-        PaletteEmpty,
+    /// Extracts a single palette from multiple images by collecting unique colors of sampled pixels,
+    /// leaving out colors filtered by `options`.
+    pub fn from_rgbu8_images_filtered<I, B>(images: I, sampling: PaletteSampling, options: &PaletteExtractOptions) -> Self
+    where 
+        I: IntoIterator<Item = B>,
+        B: Borrow<image::RgbImage>
+    {
+        let _span = tracing::debug_span!("palette_extraction", sampling = %sampling).entered();
+        let mut palette = match options.min_coverage {
+            None => Self::from_rgbu8_images_sampled(images, sampling),
+            Some(min_coverage) => {
+                let color_counts = Self::sampled_color_counts(images, sampling);
+                let min_count = min_coverage.min_pixels(color_counts.values().sum());
+                Self::from(color_counts.into_iter()
+                    .filter(|(_, count)| *count >= min_count)
+                    .map(|(color, _)| color)
+                    .collect::<Vec<_>>()
+                )
+            },
+        };
+        options.apply(&mut palette);
+        palette
     }
 
-    impl From<CentroidsFindError> for PaletteError {
-        fn from(value: CentroidsFindError) -> Self {
-            Self::ConvertionErrot(value)
+    /// Collects colors of sampled pixels along with number of pixels of each of them.
+    fn sampled_color_counts<I, B>(images: I, sampling: PaletteSampling) -> HashMap<ColorRGB, usize>
+    where 
+        I: IntoIterator<Item = B>,
+        B: Borrow<image::RgbImage>
+    {
+        if sampling == PaletteSampling::Histogram {
+            return Self::histogram_color_counts(images).into_iter().collect();
         }
+
+        images.into_iter()
+            .fold(HashMap::new(), |mut acc, img| {
+                sampling.sample_pixels(img.borrow())
+                    .for_each(|px| *acc.entry(ColorRGB::from_rgbu8(px)).or_insert(0) += 1);
+                acc
+            })
     }
 
-    impl From<std::io::Error> for PaletteError {
-        fn from(value: std::io::Error) -> Self {
-            Self::IoError(value)
-        }
+    /// Extracts a single palette from multiple images by collecting unique pixel colors of all of them.
+    /// 
+    /// Useful to obtain one palette shared by sprite sheets or video frames, so that
+    /// every image dithered with the reduced palette uses the same colors.
+    ///
+    /// # Example
+    /// ```
+    /// use ditherum::{image::generate_test_gradient_image, palette::PaletteRGB};
+    /// 
+    /// let frames = [
+    ///     generate_test_gradient_image(16, 4, image::Rgb([0, 0, 0]), image::Rgb([255, 0, 0])),
+    ///     generate_test_gradient_image(16, 4, image::Rgb([0, 0, 0]), image::Rgb([0, 0, 255])),
+    /// ];
+    /// 
+    /// let shared_palette = PaletteRGB::from_rgbu8_images(&frames)
+    ///     .try_reduce(4)
+    ///     .unwrap();
+    /// assert_eq!(shared_palette.len(), 4);
+    /// ```
+    pub fn from_rgbu8_images<I, B>(images: I) -> Self
+    where 
+        I: IntoIterator<Item = B>,
+        B: Borrow<image::RgbImage>
+    {
+        Self::from_rgbu8_images_sampled(images, PaletteSampling::All)
     }
 
-    impl From<serde_json::error::Error> for PaletteError {
-        fn from(value: serde_json::error::Error) -> Self {
-            Self::JsonParsingFailed(value)
+    /// Extracts a single palette from multiple images by collecting unique colors of sampled pixels.
+    ///
+    /// Each image is sampled separately, histogram bins are shared by all images.
+    pub fn from_rgbu8_images_sampled<I, B>(images: I, sampling: PaletteSampling) -> Self
+    where 
+        I: IntoIterator<Item = B>,
+        B: Borrow<image::RgbImage>
+    {
+        if sampling == PaletteSampling::Histogram {
+            return Self::from_histogram(images);
         }
+
+        let palette_set = images.into_iter()
+            .fold(HashSet::new(), |mut acc, img| {
+                acc.extend(sampling.sample_pixels(img.borrow()));
+                acc
+            });
+
+        // Sorting included
+        Self::from(palette_set)
     }
-}
 
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
-pub struct PaletteRGB(Vec<ColorRGB>);
+    /// Bins pixels by most significant bits of channels and collects mean color of each occupied bin.
+    fn from_histogram<I, B>(images: I) -> Self
+    where 
+        I: IntoIterator<Item = B>,
+        B: Borrow<image::RgbImage>
+    {
+        Self::from(Self::histogram_color_counts(images).into_iter()
+            .map(|(color, _)| color)
+            .collect::<Vec<_>>()
+        )
+    }
 
-impl PaletteRGB {
-    
-    /// Extracts a palette from an image by collecting unique pixel colors.
-    pub fn from_rgbu8_image(img: &image::RgbImage) -> Self {
-        let mut palette_set = HashSet::new();
+    /// Returns mean color and pixels count of each occupied histogram bin.
+    fn histogram_color_counts<I, B>(images: I) -> Vec<(ColorRGB, usize)>
+    where 
+        I: IntoIterator<Item = B>,
+        B: Borrow<image::RgbImage>
+    {
+        let shift = 8 - HISTOGRAM_CHANNEL_BITS;
+        let bin_index = |px: &image::Rgb<u8>| {
+            px.0.iter().fold(0, |acc, &channel| (acc << HISTOGRAM_CHANNEL_BITS) | (channel >> shift) as usize)
+        };
 
-        for y in 0..img.height() {
-            for x in 0..img.width() {
-                let pixel = img.get_pixel(x, y);
-                palette_set.insert(*pixel);
+        // Bins as (channels sum, pixels count)
+        let mut bins = vec![([0u64; 3], 0u64); 1 << (3 * HISTOGRAM_CHANNEL_BITS)];
+        for img in images {
+            for px in img.borrow().pixels() {
+                let (sum, count) = &mut bins[bin_index(px)];
+                sum.iter_mut().zip(px.0).for_each(|(acc, channel)| *acc += channel as u64);
+                *count += 1;
             }
         }
 
-        // Sorting included
-        Self::from(palette_set)
+        bins.into_iter()
+            .filter(|(_, count)| *count > 0)
+            .map(|(sum, count)| (ColorRGB(sum.map(|channel_sum| ((channel_sum + count / 2) / count) as u8)), count as usize))
+            .collect()
     }
 
     /// Returns a palette containing only black and white.
@@ -138,7 +1303,99 @@ impl PaletteRGB {
             })
             .collect::<Vec<_>>();
 
-        PaletteRGB(colors)
+        PaletteRGB(colors, DistancesCache::default(), HashMap::new())
+    }
+
+    /// Returns a palette of `count` colors following color harmony of `seed` color.
+    ///
+    /// Hues are rotated in LCh space keeping seed lightness and chroma, so colors look
+    /// equally vivid. When `count` exceeds number of harmony hues, further rounds repeat
+    /// the hues alternately lighter and darker. Seed color is always included. Colors
+    /// rotated out of sRGB gamut are clipped.
+    ///
+    /// # Panics
+    /// Panics if `count` is 0.
+    ///
+    /// # Example
+    /// ```
+    /// use ditherum::{color::ColorRGB, palette::{HarmonyKind, PaletteRGB}};
+    ///
+    /// let seed = ColorRGB([200, 60, 40]);
+    /// let palette = PaletteRGB::harmony(seed, HarmonyKind::Triadic, 6);
+    ///
+    /// assert_eq!(palette.len(), 6);
+    /// assert!(palette.contains(&seed));
+    /// ```
+    pub fn harmony(seed: ColorRGB, kind: HarmonyKind, count: usize) -> Self {
+        assert!(count > 0, "Harmony palette requires at least one color.");
+
+        let seed_lch = palette::Lch::from_color(seed.to_lab());
+        let hue_offsets = kind.hue_offsets();
+        let colors = (0..count)
+            .map(|idx| {
+                if idx == 0 {
+                    return seed;
+                }
+
+                let (offset_idx, round) = (idx % hue_offsets.len(), idx / hue_offsets.len());
+                // Rounds go +1, -1, +2, -2... lightness steps, bouncing back if out of range
+                let steps = round.div_ceil(2) as f32 * if round % 2 == 1 { 1.0 } else { -1.0 };
+                let lightness = match seed_lch.l + steps * HARMONY_LIGHTNESS_STEP {
+                    lightness if (0.0..=100.0).contains(&lightness) => lightness,
+                    _ => seed_lch.l - steps * HARMONY_LIGHTNESS_STEP,
+                };
+                let lch = palette::Lch::new(lightness.clamp(0.0, 100.0), seed_lch.chroma, seed_lch.hue + hue_offsets[offset_idx]);
+                ColorRGB::from_lab(palette::Lab::from_color(lch))
+            })
+            .collect::<Vec<_>>();
+
+        PaletteRGB::from(colors)
+    }
+
+    /// Returns a palette of `count` random colors meeting constraints of `options`.
+    ///
+    /// Candidates are drawn uniformly from Lab space within the lightness range, those out of
+    /// sRGB gamut or too close to already accepted colors are rejected. Generation is
+    /// seeded, so batch of variations can be reproduced by seeds alone.
+    ///
+    /// # Errors
+    /// Returns [`PaletteError::RandomConstraintsUnsatisfied`] if lightness range is invalid or
+    /// `count` colors spaced by minimal difference could not be found.
+    pub fn random(count: usize, options: &RandomPaletteOptions) -> Result<Self, PaletteError> {
+        let unsatisfied = |generated: usize| PaletteError::RandomConstraintsUnsatisfied { requested: count, generated };
+        let (min_lightness, max_lightness) = (options.min_lightness.max(0.0), options.max_lightness.min(100.0));
+        if min_lightness > max_lightness {
+            return Err(unsatisfied(0));
+        }
+
+        let mut rng = StdRng::seed_from_u64(options.seed);
+        let mut colors: Vec<ColorRGB> = Vec::with_capacity(count);
+        let mut attempts = 0;
+        while colors.len() < count {
+            if attempts >= count * RANDOM_PALETTE_ATTEMPTS_PER_COLOR {
+                return Err(unsatisfied(colors.len()));
+            }
+            attempts += 1;
+
+            let lab = palette::Lab::new(
+                rng.random_range(min_lightness..=max_lightness),
+                rng.random_range(-128.0..=127.0),
+                rng.random_range(-128.0..=127.0)
+            );
+            let srgb = palette::Srgb::from_color(lab);
+            if [srgb.red, srgb.green, srgb.blue].iter().any(|channel| !(0.0..=1.0).contains(channel)) {
+                continue;
+            }
+
+            let color = ColorRGB::from_srgb(srgb);
+            let lightness = color.to_lab().l;
+            let in_range = (min_lightness..=max_lightness).contains(&lightness);
+            if in_range && colors.iter().all(|accepted| accepted != &color && accepted.dist_by_lab(&color) >= options.min_delta_e) {
+                colors.push(color);
+            }
+        }
+
+        Ok(PaletteRGB::from(colors))
     }
 
     pub fn with_black_and_white(mut self) -> Self {
@@ -181,6 +1438,10 @@ impl PaletteRGB {
     /// In this example, the palette is reduced to 2 colors while maintaining the color balance
     /// using a clustering algorithm to find the best fitting centroids.
     pub fn try_reduce(self, target_colors_count: usize) -> Result<Self, self::errors::PaletteError> {
+        if !self.locked_colors().is_empty() {
+            return self.try_reduce_with_fixed(target_colors_count, &[]);
+        }
+
         match self.len().cmp(&target_colors_count) {
 
             // Cannot obtain bigger pallete than the input pallet size
@@ -206,6 +1467,379 @@ impl PaletteRGB {
         }
     }
 
+    /// Attempts to reduce the palette while guaranteeing that specified colors survive.
+    ///
+    /// Fixed colors (e.g. pure black, white or brand colors) are kept exactly and take part
+    /// in clustering, so remaining `target_colors_count - fixed.len()` slots are filled by
+    /// K-means centroids of colors not covered by fixed ones. Fixed colors do not need to be
+    /// present in the palette. Locked colors of the palette are fixed too, see [`PaletteEntry`].
+    /// Metadata of colors which survive reduction is kept.
+    ///
+    /// # Arguments
+    /// - `target_colors_count`: Number of colors in resulting palette, including fixed ones.
+    /// - `fixed`: Colors guaranteed to be present in resulting palette.
+    ///
+    /// # Errors
+    /// - `PaletteError::TooManyFixedColors`: Returned when there are more distinct fixed colors
+    ///   than the target number of colors.
+    /// - `PaletteError::NotEnoughColors`: Returned when palette together with fixed colors has
+    ///   fewer colors than requested.
+    ///
+    /// # Example
+    /// ```
+    /// use ditherum::{color::ColorRGB, palette::PaletteRGB};
+    ///
+    /// let palette = PaletteRGB::grayscale(16);
+    /// let fixed = [ColorRGB([255, 0, 0])];
+    ///
+    /// let reduced_palette = palette.try_reduce_with_fixed(3, &fixed).unwrap();
+    /// assert_eq!(reduced_palette.len(), 3);
+    /// assert!(reduced_palette.contains(&ColorRGB([255, 0, 0])));
+    /// ```
+    pub fn try_reduce_with_fixed(self, target_colors_count: usize, fixed: &[ColorRGB]) -> Result<Self, self::errors::PaletteError> {
+        self.try_reduce_with_parallelism(target_colors_count, fixed, &Parallelism::default())
+    }
+
+    /// Same as [`PaletteRGB::try_reduce_with_fixed`], but clustering threads are controlled
+    /// by `parallelism`, see [`Parallelism`].
+    ///
+    /// # Errors
+    /// Same as [`PaletteRGB::try_reduce_with_fixed`].
+    pub fn try_reduce_with_parallelism(self, target_colors_count: usize, fixed: &[ColorRGB], parallelism: &Parallelism) -> Result<Self, self::errors::PaletteError> {
+        self.try_reduce_with_strategy(target_colors_count, fixed, ReduceStrategy::KMeans, parallelism)
+    }
+
+    /// Attempts to reduce the palette like [`PaletteRGB::try_reduce_with_strategy`], returning also
+    /// which reduced color each of the original colors collapsed into.
+    ///
+    /// Mapping follows cluster membership of clustering, not the nearest reduced color, so
+    /// indexed assets and documentation tables are remapped the same way colors were merged.
+    /// Fixed colors map to themselves. To map colors to the nearest ones instead, see
+    /// [`PaletteRGB::nearest_color_mapping`].
+    ///
+    /// # Errors
+    /// Same as [`PaletteRGB::try_reduce_with_fixed`].
+    ///
+    /// # Example
+    /// ```
+    /// use ditherum::{algorithms::kmean::Parallelism, color::ColorRGB, palette::{PaletteRGB, ReduceStrategy}};
+    ///
+    /// let palette = PaletteRGB::from(vec![ColorRGB([0, 0, 0]), ColorRGB([8, 8, 8]), ColorRGB([255, 255, 255])]);
+    /// let (reduced_palette, mapping) = palette
+    ///     .try_reduce_with_mapping(2, &[], ReduceStrategy::Agglomerative, &Parallelism::default())
+    ///     .unwrap();
+    ///
+    /// assert_eq!(mapping.len(), 3);
+    /// assert_eq!(mapping[&ColorRGB([0, 0, 0])], mapping[&ColorRGB([8, 8, 8])]);
+    /// assert!(reduced_palette.contains(&mapping[&ColorRGB([255, 255, 255])]));
+    /// ```
+    pub fn try_reduce_with_mapping(mut self, target_colors_count: usize, fixed: &[ColorRGB], strategy: ReduceStrategy, parallelism: &Parallelism) -> Result<(Self, ReductionMapping), self::errors::PaletteError> {
+        let fixed_colors = fixed.iter()
+            .copied()
+            .chain(self.locked_colors())
+            .collect::<HashSet<_>>();
+        let metadata = std::mem::take(&mut self.2);
+        if fixed_colors.len() > target_colors_count {
+            return Err(self::errors::PaletteError::TooManyFixedColors {
+                fixed: fixed_colors.len(),
+                target: target_colors_count
+            });
+        }
+
+        let mut mapping = self.0.iter()
+            .filter(|color| fixed_colors.contains(color))
+            .map(|&color| (color, color))
+            .collect::<ReductionMapping>();
+        let free_colors = self.0.into_iter()
+            .filter(|color| !fixed_colors.contains(color))
+            .collect::<HashSet<_>>();
+        let free_target_colors_count = target_colors_count - fixed_colors.len();
+
+        match free_colors.len().cmp(&free_target_colors_count) {
+            std::cmp::Ordering::Less => Err(self::errors::PaletteError::NotEnoughColors(free_colors.len() + fixed_colors.len())),
+            std::cmp::Ordering::Equal => {
+                mapping.extend(free_colors.iter().map(|&color| (color, color)));
+                let palette = PaletteRGB::from(fixed_colors.into_iter().chain(free_colors).collect::<Vec<_>>()).with_metadata_of(&metadata);
+                Ok((palette, mapping))
+            },
+            std::cmp::Ordering::Greater => {
+                // Sets are sorted, so deterministic strategies do not depend on hashing
+                let mut fixed_colors = fixed_colors.into_iter().collect::<Vec<_>>();
+                fixed_colors.sort_unstable_by_key(|color| color.0);
+                let mut free_colors = free_colors.into_iter().collect::<Vec<_>>();
+                free_colors.sort_unstable_by_key(|color| color.0);
+
+                let fixed_lab_colors = fixed_colors.iter()
+                    .map(|&color| palette::Lab::from(color))
+                    .collect::<Vec<_>>();
+                let free_lab_colors = free_colors.iter()
+                    .map(|&color| palette::Lab::from(color))
+                    .collect::<Vec<_>>();
+
+                let (new_lab_colors, assignments) = match strategy {
+                    ReduceStrategy::KMeans => {
+                        let result = find_lab_colors_clusters_with_fixed(
+                            &free_lab_colors,
+                            target_colors_count,
+                            &fixed_lab_colors,
+                            parallelism
+                        )?;
+                        (result.centroids, result.assignments)
+                    },
+                    ReduceStrategy::Agglomerative => merge_lab_colors_agglomerative(
+                        &free_lab_colors,
+                        target_colors_count,
+                        &fixed_lab_colors
+                    ),
+                };
+
+                // Fixed colors are inserted directly, Lab round trip could shift them
+                let new_colors = fixed_colors.into_iter()
+                    .chain(new_lab_colors.into_iter()
+                        .skip(fixed_lab_colors.len())
+                        .map(ColorRGB::from)
+                    )
+                    .collect::<Vec<_>>();
+                // Centroids rounded to the same color are deduplicated, so their colors stay in palette
+                mapping.extend(free_colors.into_iter().zip(assignments).map(|(color, centroid_idx)| (color, new_colors[centroid_idx])));
+                Ok((PaletteRGB::from(new_colors).with_metadata_of(&metadata), mapping))
+            },
+        }
+    }
+
+    /// Maps each color of the palette to the closest color of `reduced_palette` by CIEDE2000.
+    ///
+    /// Colors present in both palettes map to themselves. Unlike mapping of
+    /// [`PaletteRGB::try_reduce_with_mapping`], this does not follow how colors were clustered,
+    /// so it suits palettes reduced in several passes, like [`PaletteRGB::try_reduce_spaced`].
+    ///
+    /// # Panics
+    /// Panics if `reduced_palette` is empty while this palette is not.
+    pub fn nearest_color_mapping(&self, reduced_palette: &PaletteRGB) -> ReductionMapping {
+        self.iter()
+            .map(|color| {
+                let target = if reduced_palette.contains(color) {
+                    *color
+                } else {
+                    reduced_palette.find_closest_by_lab(color)
+                };
+                (*color, target)
+            })
+            .collect()
+    }
+
+    /// Attempts to reduce the palette by merging the closest pair of colors until target count is left.
+    ///
+    /// Unlike K-means, result is deterministic and every color of small palette is taken
+    /// into account, but cost grows with square of palette size. Merged colors are replaced
+    /// by their mean weighted by number of merged source colors, colors merged into fixed
+    /// ones are absorbed by them. Fixed and locked colors are kept as in
+    /// [`PaletteRGB::try_reduce_with_fixed`].
+    ///
+    /// # Errors
+    /// Same as [`PaletteRGB::try_reduce_with_fixed`].
+    ///
+    /// # Example
+    /// ```
+    /// use ditherum::{color::ColorRGB, palette::PaletteRGB};
+    ///
+    /// let palette = PaletteRGB::from(vec![
+    ///     ColorRGB([0, 0, 0]),
+    ///     ColorRGB([10, 10, 10]),
+    ///     ColorRGB([250, 250, 250]),
+    ///     ColorRGB([255, 255, 255]),
+    /// ]);
+    ///
+    /// let reduced_palette = palette.clone().try_reduce_agglomerative(2, &[]).unwrap();
+    /// assert_eq!(reduced_palette.len(), 2);
+    /// assert_eq!(reduced_palette, palette.try_reduce_agglomerative(2, &[]).unwrap());
+    /// ```
+    pub fn try_reduce_agglomerative(self, target_colors_count: usize, fixed: &[ColorRGB]) -> Result<Self, self::errors::PaletteError> {
+        self.try_reduce_with_strategy(target_colors_count, fixed, ReduceStrategy::Agglomerative, &Parallelism::default())
+    }
+
+    /// Reduces the palette using given clustering backend, see [`ReduceStrategy`].
+    ///
+    /// `parallelism` is used only by [`ReduceStrategy::KMeans`].
+    ///
+    /// # Errors
+    /// Same as [`PaletteRGB::try_reduce_with_fixed`].
+    pub fn try_reduce_with_strategy(self, target_colors_count: usize, fixed: &[ColorRGB], strategy: ReduceStrategy, parallelism: &Parallelism) -> Result<Self, self::errors::PaletteError> {
+        self.try_reduce_with_mapping(target_colors_count, fixed, strategy, parallelism)
+            .map(|(palette, _)| palette)
+    }
+
+    /// Attempts to reduce the palette keeping its colors perceptually apart.
+    ///
+    /// After reduction, centroids closer than `min_delta_e` are merged and freed slots are
+    /// refilled by clustering colors which are far enough from all kept ones. This repeats
+    /// until no colors get merged, so palettes do not waste entries on nearly identical colors.
+    /// Fixed colors are kept as in [`PaletteRGB::try_reduce_with_fixed`].
+    ///
+    /// Resulting palette can have fewer than `target_colors_count` colors if there are not
+    /// enough distinct colors to fill all slots.
+    ///
+    /// # Arguments
+    /// - `target_colors_count`: Maximal number of colors in resulting palette, including fixed ones.
+    /// - `fixed`: Colors guaranteed to be present in resulting palette.
+    /// - `min_delta_e`: Minimal CIEDE2000 difference between resulting colors, `0.0` disables spacing.
+    ///
+    /// # Errors
+    /// Same as [`PaletteRGB::try_reduce_with_fixed`].
+    ///
+    /// # Example
+    /// ```
+    /// use ditherum::{color::ColorRGB, palette::PaletteRGB};
+    ///
+    /// let mut palette = PaletteRGB::from((0..20).map(|g| ColorRGB([0, 180 + g, 0])).collect::<Vec<_>>());
+    /// palette.push(ColorRGB([255, 0, 0]));
+    ///
+    /// let reduced_palette = palette.try_reduce_spaced(4, &[], 10.0).unwrap();
+    /// assert!(reduced_palette.len() < 4);
+    /// ```
+    pub fn try_reduce_spaced(self, target_colors_count: usize, fixed: &[ColorRGB], min_delta_e: f32) -> Result<Self, self::errors::PaletteError> {
+        self.try_reduce_spaced_with_strategy(target_colors_count, fixed, min_delta_e, ReduceStrategy::KMeans, &Parallelism::default())
+    }
+
+    /// Same as [`PaletteRGB::try_reduce_spaced`], but using given clustering backend, see
+    /// [`PaletteRGB::try_reduce_with_strategy`].
+    ///
+    /// # Errors
+    /// Same as [`PaletteRGB::try_reduce_with_fixed`].
+    ///
+    /// # Example
+    /// ```
+    /// use ditherum::{algorithms::kmean::Parallelism, palette::{PaletteRGB, ReduceStrategy}};
+    ///
+    /// let palette = PaletteRGB::grayscale(64);
+    /// let reduced_palette = palette.try_reduce_spaced_with_strategy(4, &[], 0.0, ReduceStrategy::KMeans, &Parallelism::disabled()).unwrap();
+    /// assert_eq!(reduced_palette.len(), 4);
+    /// ```
+    pub fn try_reduce_spaced_with_strategy(self, target_colors_count: usize, fixed: &[ColorRGB], min_delta_e: f32, strategy: ReduceStrategy, parallelism: &Parallelism) -> Result<Self, self::errors::PaletteError> {
+        if min_delta_e <= 0.0 {
+            return self.try_reduce_with_strategy(target_colors_count, fixed, strategy, parallelism);
+        }
+
+        let source_palette = self.clone();
+        let fixed_palette = PaletteRGB::from(fixed.iter().copied().chain(self.locked_colors()).collect::<Vec<_>>());
+        let mut palette = self.try_reduce_with_strategy(target_colors_count, fixed, strategy, parallelism)?;
+
+        for _ in 0..SPACING_MAX_PASSES {
+            let spaced_palette = palette.merged_too_close(&fixed_palette, min_delta_e);
+            if spaced_palette.len() == palette.len() {
+                return Ok(palette.with_metadata_of(&source_palette.2));
+            }
+
+            // Refill freed slots with colors far from all kept ones
+            let mut candidates = source_palette.clone();
+            candidates.remove_within(&spaced_palette, min_delta_e);
+            if candidates.len() <= target_colors_count - spaced_palette.len() {
+                palette = spaced_palette;
+                palette.merge_within(candidates, 0.0);
+            } else {
+                palette = candidates.try_reduce_with_strategy(target_colors_count, &spaced_palette, strategy, parallelism)?;
+            }
+        }
+
+        Ok(palette.merged_too_close(&fixed_palette, min_delta_e).with_metadata_of(&source_palette.2))
+    }
+
+    /// Merges colors closer than `min_delta_e`, fixed colors stay unchanged and absorb colors close to them.
+    /// 
+    /// Distances between palette colors are read from cached distance matrix, see [`PaletteRGB::cluster_within`].
+    fn merged_too_close(&self, fixed_palette: &Self, min_delta_e: f32) -> Self {
+        if !self.has_colors_within(min_delta_e) {
+            return self.clone();
+        }
+
+        // Fixed colors are usually kept by reduction, so they are found in the matrix too
+        let distance = self.ciede2000_between();
+        let fixed_indices = (0..self.len())
+            .filter(|&idx| fixed_palette.contains(&self.0[idx]))
+            .collect::<Vec<_>>();
+        let missing_fixed_palette = PaletteRGB::from(fixed_palette.iter()
+            .filter(|color| !self.contains(color))
+            .copied()
+            .collect::<Vec<_>>()
+        );
+        let mut free_indices = (0..self.len())
+            .filter(|idx| !fixed_indices.contains(idx))
+            .filter(|&idx| fixed_indices.iter().all(|&fixed_idx| distance(fixed_idx, idx) > min_delta_e))
+            .filter(|&idx| !missing_fixed_palette.contains_within(&self.0[idx], min_delta_e))
+            .collect::<Vec<_>>();
+        free_indices.sort_by(|&a, &b| self.0[a].to_lab().l.total_cmp(&self.0[b].to_lab().l));
+
+        let mut free_palette = PaletteRGB::from(self.cluster_within(&free_indices, min_delta_e));
+        free_palette.remove_within(fixed_palette, min_delta_e);
+
+        let mut spaced_palette = fixed_palette.clone();
+        spaced_palette.merge_within(free_palette, 0.0);
+        spaced_palette
+    }
+
+    /// Attempts to find the smallest reduced palette reconstructing the image with acceptable quality.
+    ///
+    /// Image is thresholded with candidate palettes and compared with the original one using
+    /// mean CIEDE2000 difference. Colors count is doubled until quality target is met and then
+    /// bisected, so only a few reductions are needed. K-means clustering is randomized,
+    /// so resulting colors count can vary slightly between runs.
+    ///
+    /// If no reduced palette meets the target, the whole palette is returned.
+    ///
+    /// # Arguments
+    /// - `image`: An RGB image the palette is meant for, usually source of the palette colors.
+    /// - `max_mean_delta_e`: Maximal acceptable mean CIEDE2000 difference of thresholded image.
+    ///
+    /// # Errors
+    /// - `PaletteError::PaletteEmpty`: Returned when palette has no colors.
+    ///
+    /// # Example
+    /// ```
+    /// use ditherum::palette::PaletteRGB;
+    ///
+    /// let image = ditherum::image::generate_test_gradient_image(64, 4, image::Rgb([0, 0, 0]), image::Rgb([255, 255, 255]));
+    /// let palette = PaletteRGB::from_rgbu8_image(&image);
+    ///
+    /// let reduced_palette = palette.clone().reduce_to_quality(&image, 3.0).unwrap();
+    /// assert!(reduced_palette.len() < palette.len());
+    /// ```
+    pub fn reduce_to_quality(self, image: &image::RgbImage, max_mean_delta_e: f32) -> Result<Self, self::errors::PaletteError> {
+        if self.is_empty() {
+            return Err(self::errors::PaletteError::PaletteEmpty);
+        }
+
+        let meets_quality = |palette: &PaletteRGB| {
+            let thresholded_image = thresholding::thresholding_rgb(image.clone(), palette.clone());
+            metrics::mean_delta_e(image, &thresholded_image) <= max_mean_delta_e
+        };
+
+        // Find range by doubling colors count, `failed_count` never meets the target
+        let mut failed_count = 0;
+        let mut colors_count = 1;
+        let (mut best_count, mut best_palette) = loop {
+            let candidate = self.clone().try_reduce(colors_count)?;
+            if colors_count == self.len() || meets_quality(&candidate) {
+                break (colors_count, candidate);
+            }
+            failed_count = colors_count;
+            colors_count = (colors_count * 2).min(self.len());
+        };
+
+        // Bisect between failed and accepted colors count
+        while failed_count + 1 < best_count {
+            let colors_count = (failed_count + best_count) / 2;
+            let candidate = self.clone().try_reduce(colors_count)?;
+            if meets_quality(&candidate) {
+                best_count = colors_count;
+                best_palette = candidate;
+            } else {
+                failed_count = colors_count;
+            }
+        }
+
+        Ok(best_palette)
+    }
+
     /// Attempts to find a subset of the current palette that best matches the image content.
     /// 
     /// This is useful when the palette contains more colors than needed, and you'd like to reduce
@@ -283,11 +1917,28 @@ impl PaletteRGB {
     /// ```
     pub fn save_to_json<P>(&self, path: P) -> Result<(), PaletteError> 
     where 
-        P: AsRef<Path>
+        P: AsRef<Path>
+    {
+        let file = File::create(path)?;
+        self.save_to_json_writer(BufWriter::new(file))
+    }
+
+    /// Saves the palette as JSON to the writer, e.g. standard output.
+    ///
+    /// Written document is `{"version": 2, "colors": [...]}`, see [`PALETTE_JSON_VERSION`].
+    ///
+    /// # Parameters
+    /// - `writer`: Destination of the JSON data.
+    ///
+    /// # Errors
+    /// - Returns an error if there is an issue writing the data.
+    pub fn save_to_json_writer<W>(&self, mut writer: W) -> Result<(), PaletteError> 
+    where 
+        W: Write
     {
-        let file = File::create(path)?;
-        let writer = BufWriter::new(file);
-        serde_json::to_writer_pretty(writer, self)?;
+        let document = PaletteDocument { version: PALETTE_JSON_VERSION, colors: self };
+        serde_json::to_writer_pretty(&mut writer, &document)?;
+        writer.flush()?;
         Ok(())
     }
     
@@ -300,6 +1951,7 @@ impl PaletteRGB {
     /// - `Ok(PaletteRGB)`: If the JSON data is successfully parsed into a `PaletteRGB`.
     /// - `Err(io::Error)`: If there is an issue reading the file.
     /// - `Err(serde_json::Error)`: If there is an issue parsing the JSON data.
+    /// - `Err(PaletteError::UnsupportedPaletteVersion)`: If the file was written by newer version.
     ///
     /// # Example
     /// ```
@@ -313,8 +1965,113 @@ impl PaletteRGB {
         P: AsRef<Path>
     {
         let file = File::open(path)?;
-        let reader = BufReader::new(file);
-        let mut pallete: PaletteRGB = serde_json::from_reader(reader)?;
+        Self::load_from_json_reader(BufReader::new(file))
+    }
+
+    /// Loads the palette from file, JSON or palette image like swatch strip, chosen by extension.
+    ///
+    /// # Errors
+    /// Returns the same errors as [`PaletteRGB::load_from_json`] and [`PaletteRGB::from_palette_image`],
+    /// and [`PaletteError::ImageLoadingFailed`] if image could not be read.
+    pub fn load<P>(path: P) -> Result<Self, PaletteError>
+    where
+        P: AsRef<Path>
+    {
+        let path = path.as_ref();
+        if path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("json")) {
+            Self::load_from_json(path)
+        } else {
+            Self::from_palette_image(&crate::image::load_image(path)?)
+        }
+    }
+
+    /// Reads palette published as image, e.g. Lospec PNG strip, keeping colors in reading order.
+    ///
+    /// Swatch strips and grids are detected by the greatest common divisor of same color runs,
+    /// so enlarged swatches are read once per cell, left to right and top to bottom. Images
+    /// with separators between swatches fall back to unique colors of all pixels.
+    ///
+    /// # Errors
+    /// Returns [`PaletteError::NotPaletteImage`] if image has more than 256 colors, which is
+    /// rather a picture than a palette, or [`PaletteError::PaletteEmpty`] for empty image.
+    ///
+    /// # Example
+    /// ```
+    /// use ditherum::{color::ColorRGB, palette::PaletteRGB};
+    ///
+    /// // Strip of 3 swatches, 4x4 pixels each
+    /// let colors = [[255, 255, 255], [200, 0, 0], [0, 0, 0]];
+    /// let strip = image::RgbImage::from_fn(12, 4, |x, _| image::Rgb(colors[x as usize / 4]));
+    ///
+    /// let palette = PaletteRGB::from_palette_image(&strip).unwrap();
+    /// assert_eq!(palette.to_vec(), colors.map(ColorRGB).to_vec());
+    /// ```
+    pub fn from_palette_image(img: &image::RgbImage) -> Result<Self, PaletteError> {
+        let (width, height) = img.dimensions();
+        if width == 0 || height == 0 {
+            return Err(PaletteError::PaletteEmpty);
+        }
+
+        let gcd = |mut a: u32, mut b: u32| {
+            while b != 0 {
+                (a, b) = (b, a % b);
+            }
+            a
+        };
+        // Lengths of same color runs along rows and columns, swatch size divides all of them
+        let runs_gcd = |lines: u32, length: u32, pixel: &dyn Fn(u32, u32) -> image::Rgb<u8>| {
+            (0..lines).fold(0, |mut acc, line| {
+                let mut run_start = 0;
+                for position in 1..=length {
+                    if position == length || pixel(line, position) != pixel(line, run_start) {
+                        acc = gcd(acc, position - run_start);
+                        run_start = position;
+                    }
+                }
+                acc
+            })
+        };
+        let cell_width = runs_gcd(height, width, &|y, x| *img.get_pixel(x, y)).max(1);
+        let cell_height = runs_gcd(width, height, &|x, y| *img.get_pixel(x, y)).max(1);
+
+        let mut seen_colors = HashSet::new();
+        let mut colors = Vec::new();
+        for y in (0..height).step_by(cell_height as usize) {
+            for x in (0..width).step_by(cell_width as usize) {
+                let color = ColorRGB::from_rgbu8(*img.get_pixel(x, y));
+                if seen_colors.insert(color) {
+                    colors.push(color);
+                }
+                if colors.len() > PALETTE_IMAGE_MAX_COLORS {
+                    return Err(PaletteError::NotPaletteImage { width, height });
+                }
+            }
+        }
+
+        Ok(PaletteRGB(colors, DistancesCache::default(), HashMap::new()))
+    }
+
+    /// Loads the palette from JSON read from the reader, e.g. standard input.
+    ///
+    /// Documents of older versions are migrated, bare array of colors is version 1.
+    ///
+    /// # Example
+    /// ```
+    /// use ditherum::palette::{errors::PaletteError, PaletteRGB};
+    ///
+    /// let legacy_palette = PaletteRGB::load_from_json_reader(r#"[[0, 0, 0], [255, 255, 255]]"#.as_bytes()).unwrap();
+    /// let palette = PaletteRGB::load_from_json_reader(r#"{"version": 2, "colors": [[0, 0, 0], "white"]}"#.as_bytes()).unwrap();
+    /// assert_eq!(palette, legacy_palette);
+    ///
+    /// let future_palette = PaletteRGB::load_from_json_reader(r#"{"version": 99, "colors": []}"#.as_bytes());
+    /// assert!(matches!(future_palette, Err(PaletteError::UnsupportedPaletteVersion { version: 99, .. })));
+    /// ```
+    pub fn load_from_json_reader<R>(reader: R) -> Result<Self, PaletteError>
+    where
+        R: Read
+    {
+        let document: serde_json::Value = serde_json::from_reader(reader)?;
+        let mut pallete: PaletteRGB = serde_json::from_value(migrate_palette_document(document)?)?;
         pallete.sort();
         Ok(pallete)
     }
@@ -362,6 +2119,23 @@ impl PaletteRGB {
             .unwrap_or_default()
     }
 
+    /// Renders the palette as a horizontal strip of square swatches, in palette order.
+    /// 
+    /// # Example
+    /// ```
+    /// use ditherum::palette::PaletteRGB;
+    /// 
+    /// let palette = PaletteRGB::primary();
+    /// let swatch = palette.to_swatch_image(16);
+    /// assert_eq!(swatch.dimensions(), (48, 16));
+    /// assert_eq!(*swatch.get_pixel(40, 8), palette[2].to_rgbu8());
+    /// ```
+    pub fn to_swatch_image(&self, swatch_size: u32) -> image::RgbImage {
+        image::RgbImage::from_fn(swatch_size * self.len() as u32, swatch_size, |x, _| {
+            self[(x / swatch_size) as usize].to_rgbu8()
+        })
+    }
+
     /// Converts the palette to a vector of `image::Rgb<u8>`.
     /// 
     /// # Returns
@@ -442,13 +2216,93 @@ impl PaletteRGB {
 
     /// Combines another palette into this one, removes duplicates, and sorts it.
     /// 
+    /// Only exact duplicates are removed, use [`PaletteRGB::dedup_within`] afterwards 
+    /// to merge nearly identical colors as well.
+    /// 
     /// # Parameters
     /// 
     /// - `other`: Another `PaletteRGB` to merge.
-    pub fn combine(&mut self, mut other: Self) {
-        self.append(&mut other);
-        self.dedup();
+    pub fn combine(&mut self, other: Self) {
+        self.merge_within(other, 0.0);
+    }
+
+    /// Merges another palette into this one, skipping colors close to already present ones, and sorts it.
+    /// 
+    /// # Parameters
+    /// 
+    /// - `other`: Another `PaletteRGB` to merge.
+    /// - `delta_e`: Tolerance, colors with CIEDE2000 difference up to it are considered the same.
+    ///   `0.0` skips exact duplicates only.
+    /// 
+    /// # Example
+    /// ```
+    /// use ditherum::{color::ColorRGB, palette::PaletteRGB};
+    /// 
+    /// let mut palette = PaletteRGB::black_and_white();
+    /// palette.merge_within(PaletteRGB::from(vec![ColorRGB([2, 2, 2]), ColorRGB([255, 0, 0])]), 2.0);
+    /// assert_eq!(palette.len(), 3);
+    /// ```
+    pub fn merge_within(&mut self, other: Self, delta_e: f32) {
+        let Self(colors, _, mut metadata) = other;
+        for color in colors {
+            if !self.contains_within(&color, delta_e) {
+                if let Some(entry) = metadata.remove(&color) {
+                    self.2.entry(color).or_insert(entry);
+                }
+                self.push(color);
+            }
+        }
+        self.sort();
+    }
+
+    /// Removes colors close to any color of another palette.
+    /// 
+    /// # Parameters
+    /// 
+    /// - `other`: Palette of colors to be removed.
+    /// - `delta_e`: Tolerance, colors with CIEDE2000 difference up to it are considered the same.
+    ///   `0.0` removes exact matches only.
+    pub fn remove_within(&mut self, other: &Self, delta_e: f32) {
+        self.retain(|color| !other.contains_within(color, delta_e));
+    }
+
+    /// Clusters colors closer than tolerance and replaces each cluster with its centroid.
+    /// 
+    /// Colors are visited from darkest to lightest, each one joins the first cluster whose first
+    /// member is within tolerance or starts a new cluster, distances are read from cached
+    /// [`PaletteRGB::pairwise_distances`]. Centroids are Lab means of cluster members.
+    /// 
+    /// # Parameters
+    /// 
+    /// - `delta_e`: Tolerance, colors with CIEDE2000 difference up to it end up in the same cluster.
+    /// 
+    /// # Example
+    /// ```
+    /// use ditherum::{color::ColorRGB, palette::PaletteRGB};
+    /// 
+    /// let mut palette = PaletteRGB::from(vec![
+    ///     ColorRGB([0, 200, 0]), 
+    ///     ColorRGB([0, 202, 0]), 
+    ///     ColorRGB([2, 200, 2]), 
+    ///     ColorRGB([255, 0, 0]), 
+    /// ]);
+    /// palette.dedup_within(3.0);
+    /// assert_eq!(palette.len(), 2);
+    /// ```
+    pub fn dedup_within(&mut self, delta_e: f32) {
         self.sort();
+        if !self.has_colors_within(delta_e) {
+            return;
+        }
+
+        // Different centroids can round to the same color
+        let indices = (0..self.len()).collect::<Vec<_>>();
+        *self = Self::from(self.cluster_within(&indices, delta_e));
+    }
+
+    /// Checks if palette contains color with CIEDE2000 difference up to `delta_e` from the given one.
+    pub fn contains_within(&self, color: &ColorRGB, delta_e: f32) -> bool {
+        self.iter().any(|palette_color| palette_color == color || palette_color.dist_by_lab(color) <= delta_e)
     }
 }
 
@@ -484,7 +2338,9 @@ where
     fn from(value: HashSet<T>) -> Self {
         let mut result = Self(value.into_iter()
             .map(|v| v.into())
-            .collect()
+            .collect(), 
+            DistancesCache::default(),
+            HashMap::new()
         );
         result.sort();
         result
@@ -498,12 +2354,112 @@ where
 {
     fn from(value: Vec<T>) -> Self {
         let unique_colors: HashSet<ColorRGB> = value.into_iter().map(Into::into).collect();
-        let mut result = Self(unique_colors.into_iter().collect());
+        let mut result = Self(unique_colors.into_iter().collect(), DistancesCache::default(), HashMap::new());
         result.sort();
         result
     }
 }
 
+/// Single color of palette JSON as read, any form is accepted.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PaletteJsonEntry {
+    /// Plain `[r, g, b]` color.
+    Rgb(ColorRGB),
+
+    /// Hex or CSS color name, e.g. `"#4682b4"` or `"steelblue"`.
+    Text(String),
+
+    /// Color with metadata, value is looked up among CSS colors by name if missing.
+    Entry {
+        rgb: Option<ColorRGB>,
+        name: Option<String>,
+        count: Option<usize>,
+        #[serde(default)]
+        locked: bool,
+        order: Option<usize>
+    },
+}
+
+/// Single color of palette JSON as written, plain unless it has metadata.
+#[derive(Serialize)]
+#[serde(untagged)]
+enum PaletteJsonEntryRef<'a> {
+    Rgb(&'a ColorRGB),
+    Entry(&'a PaletteEntry),
+}
+
+impl Serialize for PaletteRGB {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer
+    {
+        serializer.collect_seq(self.iter().map(|color| match self.2.get(color) {
+            Some(entry) => PaletteJsonEntryRef::Entry(entry),
+            None => PaletteJsonEntryRef::Rgb(color),
+        }))
+    }
+}
+
+impl<'de> Deserialize<'de> for PaletteRGB {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>
+    {
+        let entries = Vec::<PaletteJsonEntry>::deserialize(deserializer)?
+            .into_iter()
+            .map(|entry| match entry {
+                PaletteJsonEntry::Rgb(color) => Ok(PaletteEntry::new(color)),
+                PaletteJsonEntry::Text(text) => {
+                    let color = text.parse::<ColorRGB>()?;
+                    Ok(match ColorRGB::from_hex(&text) {
+                        Ok(_) => PaletteEntry::new(color),
+                        Err(_) => PaletteEntry::new(color).with_name(text.trim()),
+                    })
+                },
+                PaletteJsonEntry::Entry { rgb, name, count, locked, order } => {
+                    let color = rgb
+                        .or_else(|| name.as_deref().and_then(color::named::lookup))
+                        .ok_or_else(|| color::ColorParseError::UnknownColor(name.clone().unwrap_or_default()))?;
+                    Ok(PaletteEntry { color, name, count, locked, order })
+                },
+            })
+            .collect::<Result<Vec<_>, color::ColorParseError>>()
+            .map_err(serde::de::Error::custom)?;
+
+        // Order of file is kept, loading functions sort on their own
+        let mut palette = Self(entries.iter().map(|entry| entry.color).collect(), DistancesCache::default(), HashMap::new());
+        entries.into_iter().for_each(|entry| palette.set_entry(entry));
+        Ok(palette)
+    }
+}
+
+/// Palette JSON file as written.
+#[derive(Serialize)]
+struct PaletteDocument<'a> {
+    version: u32,
+    colors: &'a PaletteRGB,
+}
+
+/// Upgrades palette JSON document of any supported version to colors array of the current one.
+fn migrate_palette_document(document: serde_json::Value) -> Result<serde_json::Value, PaletteError> {
+    match document {
+        // Version 1 was bare array of colors
+        serde_json::Value::Array(_) => Ok(document),
+        serde_json::Value::Object(mut fields) => {
+            let version = fields.get("version")
+                .and_then(serde_json::Value::as_u64)
+                .ok_or_else(|| PaletteError::InvalidPaletteDocument("missing 'version' number".to_string()))?;
+            match version {
+                2 => fields.remove("colors")
+                    .ok_or_else(|| PaletteError::InvalidPaletteDocument("missing 'colors' array".to_string())),
+                _ => Err(PaletteError::UnsupportedPaletteVersion { version, supported: PALETTE_JSON_VERSION }),
+            }
+        },
+        _ => Err(PaletteError::InvalidPaletteDocument("expected colors array or object with 'version' and 'colors'".to_string())),
+    }
+}
+
 /// Allows treating `PaletteRGB` as a vector of `ColorRGB`.
 impl Deref for PaletteRGB {
     type Target = Vec<ColorRGB>;
@@ -516,6 +2472,8 @@ impl Deref for PaletteRGB {
 /// Allows treating `PaletteRGB` as a mutable vector of `ColorRGB`.
 impl DerefMut for PaletteRGB {
     fn deref_mut(&mut self) -> &mut Self::Target {
+        // Colors can change, cached distances would be stale
+        self.1.clear();
         &mut self.0
     }
 }
@@ -535,30 +2493,138 @@ fn find_lab_colors_centroids(
     input: &[palette::Lab], 
     centroids_count: usize
 ) -> Result<Vec<palette::Lab>, kmean::CentroidsFindError> {
+    find_lab_colors_clusters_with_fixed(input, centroids_count, &[], &Parallelism::default())
+        .map(|result| result.centroids)
+}
+
+/// Clusters Lab colors like [`find_lab_colors_centroids`], keeping `fixed` centroids in place
+/// and returning also which centroid each input color belongs to.
+fn find_lab_colors_clusters_with_fixed(
+    input: &[palette::Lab], 
+    centroids_count: usize,
+    fixed: &[palette::Lab],
+    parallelism: &Parallelism
+) -> Result<kmean::KMeansResult<palette::Lab>, kmean::CentroidsFindError> {
     let lab_distance_measure = |a: &palette::Lab, b: &palette::Lab| {
         a.difference(*b)
     };
 
-    let calculate_lab_mean = |arr: &[palette::Lab]| {
-        let mut accumulator = arr.iter()
-            .fold(palette::Lab::new(0.0, 0.0, 0.0), |mut acc, item| {
-                color::manip::lab_mut_add(&mut acc, item);
-                acc
-            });
-        accumulator.l /= arr.len() as f32;
-        accumulator.a /= arr.len() as f32;
-        accumulator.b /= arr.len() as f32;
-        accumulator
-    };
-
-    kmean::find_centroids(
+    kmean::find_clusters(
         input, 
         centroids_count, 
+        fixed,
+        &mut rand::rng(),
+        &kmean::KMeansConfig::default().with_parallelism(*parallelism),
         lab_distance_measure, 
-        calculate_lab_mean
+        color::ops::lab_mean
     )
 }
 
+/// Merges the closest pair of colors until `centroids_count` colors are left.
+///
+/// Returns fixed colors followed by merged ones, like [`kmean::find_centroids_with_fixed`],
+/// together with index of the returned color each input color was merged into. Every step finds the pair with the smallest CIEDE2000 difference, nearest neighbour
+/// of each color is cached and recalculated only if it was merged.
+fn merge_lab_colors_agglomerative(
+    input: &[palette::Lab],
+    centroids_count: usize,
+    fixed: &[palette::Lab]
+) -> (Vec<palette::Lab>, Vec<usize>) {
+    // Centroid, number of merged source colors and whether it is fixed, merged ones are None
+    let mut clusters = fixed.iter()
+        .map(|&color| Some((color, 0usize, true)))
+        .chain(input.iter().map(|&color| Some((color, 1usize, false))))
+        .collect::<Vec<_>>();
+    let distance = |a: &(palette::Lab, usize, bool), b: &(palette::Lab, usize, bool)| {
+        if a.2 && b.2 { f32::INFINITY } else { a.0.difference(b.0) }
+    };
+    let nearest_of = |clusters: &[Option<(palette::Lab, usize, bool)>], idx: usize| {
+        let Some(cluster) = &clusters[idx] else {
+            return None;
+        };
+        clusters.iter()
+            .enumerate()
+            .filter(|&(other_idx, _)| other_idx != idx)
+            .filter_map(|(other_idx, other)| other.as_ref().map(|other| (other_idx, distance(cluster, other))))
+            .filter(|(_, other_distance)| other_distance.is_finite())
+            .min_by(|(_, a_dist), (_, b_dist)| a_dist.total_cmp(b_dist))
+    };
+
+    let mut nearest = (0..clusters.len())
+        .map(|idx| nearest_of(&clusters, idx))
+        .collect::<Vec<_>>();
+    let mut clusters_count = clusters.len();
+    // Cluster each merged cluster was absorbed by, followed until a kept one is found
+    let mut merged_into = (0..clusters.len()).collect::<Vec<_>>();
+
+    while clusters_count > centroids_count {
+        let closest_pair = nearest.iter()
+            .enumerate()
+            .filter_map(|(idx, neighbour)| neighbour.map(|(other_idx, pair_distance)| (idx, other_idx, pair_distance)))
+            .min_by(|(_, _, a_dist), (_, _, b_dist)| a_dist.total_cmp(b_dist));
+        let Some((idx, other_idx, _)) = closest_pair else {
+            break;
+        };
+
+        // Fixed cluster absorbs free one, free ones are merged into the first of them
+        let (kept_idx, merged_idx) = match clusters[other_idx] {
+            Some((_, _, true)) => (other_idx, idx),
+            _ => (idx.min(other_idx), idx.max(other_idx)),
+        };
+        let (merged_color, merged_count, _) = clusters[merged_idx].take().unwrap();
+        merged_into[merged_idx] = kept_idx;
+        if let Some((kept_color, kept_count, is_fixed)) = clusters[kept_idx].as_mut() {
+            if !*is_fixed {
+                let total = (*kept_count + merged_count) as f32;
+                let (kept_weight, merged_weight) = (*kept_count as f32 / total, merged_count as f32 / total);
+                *kept_color = palette::Lab::new(
+                    kept_color.l * kept_weight + merged_color.l * merged_weight,
+                    kept_color.a * kept_weight + merged_color.a * merged_weight,
+                    kept_color.b * kept_weight + merged_color.b * merged_weight,
+                );
+            }
+            *kept_count += merged_count;
+        }
+        clusters_count -= 1;
+
+        // Refresh neighbours which pointed to changed clusters or are now closer to the kept one
+        nearest[merged_idx] = None;
+        for neighbour_idx in 0..clusters.len() {
+            let points_to_changed = matches!(nearest[neighbour_idx], Some((target_idx, _)) if target_idx == kept_idx || target_idx == merged_idx);
+            let lacks_neighbour = clusters[neighbour_idx].is_some() && nearest[neighbour_idx].is_none();
+            if neighbour_idx == kept_idx || points_to_changed || lacks_neighbour {
+                nearest[neighbour_idx] = nearest_of(&clusters, neighbour_idx);
+            } else if let (Some(neighbour), Some(kept), Some((_, neighbour_distance))) = (&clusters[neighbour_idx], &clusters[kept_idx], nearest[neighbour_idx]) {
+                let kept_distance = distance(neighbour, kept);
+                if kept_distance < neighbour_distance {
+                    nearest[neighbour_idx] = Some((kept_idx, kept_distance));
+                }
+            }
+        }
+    }
+
+    let kept_positions = clusters.iter()
+        .scan(0, |position, cluster| {
+            let kept_position = cluster.as_ref().map(|_| *position);
+            *position += usize::from(cluster.is_some());
+            Some(kept_position)
+        })
+        .collect::<Vec<_>>();
+    let assignments = (fixed.len()..clusters.len())
+        .map(|mut idx| {
+            while merged_into[idx] != idx {
+                idx = merged_into[idx];
+            }
+            kept_positions[idx].unwrap()
+        })
+        .collect();
+    let colors = clusters.into_iter()
+        .flatten()
+        .map(|(color, _, _)| color)
+        .collect();
+    (colors, assignments)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -590,6 +2656,19 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_try_reduce_oscillating_clusters() {
+        // Reducing these 13 colors to 11 used to alternate between two clusterings until iterations ran out
+        let palette = PaletteRGB::from([
+            [68, 6, 2], [74, 34, 28], [176, 38, 152], [48, 123, 87], [199, 88, 149], [122, 131, 133], [246, 95, 45],
+            [118, 155, 98], [154, 143, 230], [181, 177, 97], [97, 190, 187], [110, 204, 215], [160, 227, 71],
+        ].into_iter().map(ColorRGB).collect::<Vec<_>>());
+
+        for _ in 0..50 {
+            assert_eq!(palette.clone().try_reduce(11).unwrap().len(), 11);
+        }
+    }
+
     #[test]
     fn test_reduce_bn_w_palette() {
         let palette = PaletteRGB::black_and_white();
@@ -602,6 +2681,219 @@ mod tests {
         assert_eq!(reduced_color, ColorRGB([119, 119, 119]));
     }
 
+    #[test]
+    fn test_reduce_with_fixed_colors() {
+        let palette = PaletteRGB::grayscale(32);
+        let fixed = [ColorRGB([0, 0, 0]), ColorRGB([255, 255, 255]), ColorRGB([0, 0, 255])];
+
+        let reduced_palette = palette.clone().try_reduce_with_fixed(5, &fixed).unwrap();
+        assert_eq!(reduced_palette.len(), 5);
+        assert!(fixed.iter().all(|color| reduced_palette.contains(color)));
+
+        let result = palette.clone().try_reduce_with_fixed(2, &fixed);
+        assert!(matches!(result, Err(errors::PaletteError::TooManyFixedColors { fixed: 3, target: 2 })));
+
+        let result = PaletteRGB::primary().try_reduce_with_fixed(5, &fixed[..1]);
+        assert!(matches!(result, Err(errors::PaletteError::NotEnoughColors(4))));
+    }
+
+    #[test]
+    fn test_reduce_agglomerative() {
+        // Two tight groups and fixed color absorbing its neighbours
+        let palette = PaletteRGB::from(vec![
+            ColorRGB([250, 0, 0]), ColorRGB([240, 10, 10]), ColorRGB([245, 5, 0]),
+            ColorRGB([0, 0, 250]), ColorRGB([10, 10, 240]),
+            ColorRGB([5, 5, 5]), ColorRGB([15, 15, 15]),
+        ]);
+        let fixed = [ColorRGB([0, 0, 0])];
+
+        let reduced_palette = palette.clone().try_reduce_agglomerative(3, &fixed).unwrap();
+        assert_eq!(reduced_palette.len(), 3);
+        assert!(reduced_palette.contains(&ColorRGB([0, 0, 0])));
+        assert!(reduced_palette.iter().any(|color| color.0[0] > 200 && color.0[2] < 30));
+        assert!(reduced_palette.iter().any(|color| color.0[2] > 200 && color.0[0] < 30));
+        assert_eq!(reduced_palette, palette.clone().try_reduce_agglomerative(3, &fixed).unwrap());
+
+        let result = palette.try_reduce_agglomerative(0, &fixed);
+        assert!(matches!(result, Err(errors::PaletteError::TooManyFixedColors { fixed: 1, target: 0 })));
+    }
+
+    #[test]
+    fn test_reduction_mapping() {
+        let palette = PaletteRGB::grayscale(16);
+        let fixed = [ColorRGB([255, 0, 0])];
+        let (reduced_palette, mapping) = palette.clone()
+            .try_reduce_with_mapping(4, &fixed, ReduceStrategy::KMeans, &Parallelism::default())
+            .unwrap();
+
+        assert_eq!(mapping.len(), palette.len());
+        assert!(mapping.values().all(|target| reduced_palette.contains(target)));
+
+        // Every reduced gray got some of the source colors, fixed red took none of them
+        let targets = mapping.values().collect::<HashSet<_>>();
+        assert_eq!(targets.len(), 3);
+        assert!(!targets.contains(&ColorRGB([255, 0, 0])));
+
+        // Merged colors are means of exactly the colors mapped to them
+        let (reduced_palette, mapping) = palette.clone()
+            .try_reduce_with_mapping(4, &fixed, ReduceStrategy::Agglomerative, &Parallelism::default())
+            .unwrap();
+        assert_eq!(mapping.len(), palette.len());
+        for &reduced_color in reduced_palette.iter().filter(|color| !fixed.contains(color)) {
+            let members = mapping.iter()
+                .filter(|(_, &target)| target == reduced_color)
+                .map(|(&source, _)| palette::Lab::from(source))
+                .collect::<Vec<_>>();
+            assert_eq!(ColorRGB::from(color::ops::lab_mean(&members)), reduced_color);
+        }
+
+        let nearest_mapping = palette.nearest_color_mapping(&reduced_palette);
+        assert_eq!(nearest_mapping[&ColorRGB([0, 0, 0])], reduced_palette.find_closest_by_lab(&ColorRGB([0, 0, 0])));
+    }
+
+    #[test]
+    fn test_reduce_spaced_merges_close_colors() {
+        let mut palette = PaletteRGB::from((0..40).map(|g| ColorRGB([0, 160 + g, 0])).collect::<Vec<_>>());
+        palette.merge_within(PaletteRGB::from(vec![ColorRGB([255, 0, 0]), ColorRGB([0, 0, 255]), ColorRGB([0, 0, 0])]), 0.0);
+
+        let min_delta_e = 8.0;
+        let reduced_palette = palette.try_reduce_spaced(8, &[ColorRGB([0, 0, 0])], min_delta_e).unwrap();
+        assert!(reduced_palette.len() <= 8);
+        assert!(reduced_palette.contains(&ColorRGB([0, 0, 0])));
+        for (idx, color) in reduced_palette.iter().enumerate() {
+            assert!(reduced_palette[idx + 1..].iter().all(|other| color.dist_by_lab(other) > min_delta_e));
+        }
+    }
+
+    #[test]
+    fn test_reduce_to_quality() {
+        let image = crate::image::generate_test_gradient_image(128, 4, image::Rgb([0, 0, 0]), image::Rgb([255, 128, 0]));
+        let palette = PaletteRGB::from_rgbu8_image(&image);
+
+        let reduced_palette = palette.clone().reduce_to_quality(&image, 100.0).unwrap();
+        assert_eq!(reduced_palette.len(), 1);
+
+        let max_mean_delta_e = 2.0;
+        let reduced_palette = palette.clone().reduce_to_quality(&image, max_mean_delta_e).unwrap();
+        assert!(reduced_palette.len() > 1 && reduced_palette.len() < palette.len());
+        let thresholded_image = thresholding::thresholding_rgb(image.clone(), reduced_palette);
+        assert!(metrics::mean_delta_e(&image, &thresholded_image) <= max_mean_delta_e);
+
+        assert!(matches!(
+            PaletteRGB::from(Vec::<ColorRGB>::new()).reduce_to_quality(&image, 1.0), 
+            Err(errors::PaletteError::PaletteEmpty)
+        ));
+    }
+
+    #[test]
+    fn test_sampled_palette_extraction() {
+        let img = crate::image::generate_test_gradient_image(256, 8, image::Rgb([0, 0, 0]), image::Rgb([255, 128, 64]));
+        let full_palette = PaletteRGB::from_rgbu8_image(&img);
+
+        let every_nth_palette = PaletteRGB::from_rgbu8_image_sampled(&img, PaletteSampling::EveryNth(4));
+        assert!(every_nth_palette.len() < full_palette.len());
+        assert!(every_nth_palette.iter().all(|color| full_palette.contains(color)));
+
+        let sampling = PaletteSampling::Random { samples: 20, seed: 3 };
+        let random_palette = PaletteRGB::from_rgbu8_image_sampled(&img, sampling);
+        assert!(random_palette.len() <= 20);
+        assert!(random_palette.iter().all(|color| full_palette.contains(color)));
+        assert_eq!(random_palette, PaletteRGB::from_rgbu8_image_sampled(&img, sampling));
+
+        let histogram_palette = PaletteRGB::from_rgbu8_image_sampled(&img, PaletteSampling::Histogram);
+        assert!(histogram_palette.len() < full_palette.len());
+        assert!(histogram_palette.iter().all(|color| full_palette.contains_within(color, 3.0)));
+    }
+
+    #[test]
+    fn test_parse_sampling() {
+        assert_eq!("all".parse::<PaletteSampling>().unwrap(), PaletteSampling::All);
+        assert_eq!("Histogram".parse::<PaletteSampling>().unwrap(), PaletteSampling::Histogram);
+        assert_eq!("random:10:7".parse::<PaletteSampling>().unwrap(), PaletteSampling::Random { samples: 10, seed: 7 });
+        for sampling in [PaletteSampling::All, PaletteSampling::EveryNth(3), PaletteSampling::Random { samples: 10, seed: 7 }, PaletteSampling::Histogram] {
+            assert_eq!(sampling.to_string().parse::<PaletteSampling>().unwrap(), sampling);
+        }
+        for invalid in ["", "every", "every:0", "every:x", "random:10:7:1", "some"] {
+            assert!(matches!(invalid.parse::<PaletteSampling>(), Err(errors::PaletteError::InvalidSampling(_))), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_parse_extract_options() {
+        let options = "#00ff00, #0000ff:12.5".parse::<PaletteExtractOptions>().unwrap();
+        assert_eq!(options.exclude, vec![ColorRGB([0, 255, 0]), ColorRGB([0, 0, 255])]);
+        assert_eq!(options.tolerance, 12.5);
+        assert_eq!("#00ff00".parse::<PaletteExtractOptions>().unwrap().tolerance, 0.0);
+        assert_eq!("lime:10".parse::<PaletteExtractOptions>().unwrap().exclude, vec![ColorRGB([0, 255, 0])]);
+        for invalid in ["", "#00ff00:", "#00ff00:-1", "greeen:10", "#00ff00:10:2"] {
+            assert!(matches!(invalid.parse::<PaletteExtractOptions>(), Err(errors::PaletteError::InvalidExtractOptions(_))), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_min_coverage_drops_rare_colors() {
+        for invalid in ["", "x", "-1", "101%", "%"] {
+            assert!(matches!(invalid.parse::<MinCoverage>(), Err(errors::PaletteError::InvalidCoverage(_))), "{invalid}");
+        }
+
+        // Two flat areas with a few antialiasing pixels in between
+        let mut img = image::RgbImage::from_fn(20, 10, |x, _| if x < 10 { image::Rgb([0, 0, 0]) } else { image::Rgb([255, 255, 255]) });
+        img.put_pixel(10, 0, image::Rgb([128, 128, 128]));
+        img.put_pixel(10, 1, image::Rgb([90, 90, 90]));
+
+        for sampling in [PaletteSampling::All, PaletteSampling::Histogram] {
+            for min_coverage in [MinCoverage::Pixels(2), MinCoverage::Percent(1.0)] {
+                let options = PaletteExtractOptions::default().with_min_coverage(min_coverage);
+                let palette = PaletteRGB::from_rgbu8_image_filtered(&img, sampling, &options);
+                assert_eq!(palette, PaletteRGB::black_and_white(), "{sampling}, {min_coverage}");
+            }
+        }
+        assert_eq!(PaletteRGB::from_rgbu8_image_filtered(&img, PaletteSampling::All, &PaletteExtractOptions::default()).len(), 4);
+    }
+
+    #[test]
+    fn test_region_is_clipped_to_image() {
+        let region = "8,4,100,100".parse::<ImageRegion>().unwrap();
+        assert_eq!(region.to_string().parse::<ImageRegion>().unwrap(), region);
+        for invalid in ["", "1,2,3", "1,2,0,4", "1,2,3,x", "1,2,3,4,5"] {
+            assert!(matches!(invalid.parse::<ImageRegion>(), Err(errors::PaletteError::InvalidRegion(_))), "{invalid}");
+        }
+
+        let img = image::RgbImage::new(16, 8);
+        assert_eq!(region.crop(&img).unwrap().dimensions(), (8, 4));
+        let outside_region = ImageRegion { x: 16, y: 0, width: 4, height: 4 };
+        assert!(matches!(PaletteRGB::from_image_region(&img, outside_region), Err(errors::PaletteError::RegionOutsideImage { .. })));
+    }
+
+    #[test]
+    fn test_pairwise_distances_cache() {
+        let mut palette = PaletteRGB::primary_bw();
+        let distances = palette.pairwise_distances(DistanceMetric::Ciede2000);
+        assert_eq!(distances.size(), palette.len());
+        assert!(Arc::ptr_eq(&distances, &palette.pairwise_distances(DistanceMetric::Ciede2000)));
+
+        for i in 0..palette.len() {
+            for j in 0..palette.len() {
+                assert_eq!(distances.get(i, j), distances.get(j, i));
+                assert!((distances.get(i, j) - palette[i].dist_by_lab(&palette[j])).abs() < 1e-3);
+            }
+        }
+
+        // Sorting reorders cached distances along with colors
+        let mut shuffled = PaletteRGB::from(vec![ColorRGB([0, 0, 0]), ColorRGB([255, 255, 0]), ColorRGB([0, 0, 255])]);
+        shuffled.0.reverse();
+        let unsorted_distances = shuffled.pairwise_distances(DistanceMetric::Ciede2000);
+        shuffled.sort();
+        let sorted_distances = shuffled.pairwise_distances(DistanceMetric::Ciede2000);
+        assert!(!Arc::ptr_eq(&unsorted_distances, &sorted_distances));
+        assert_eq!(sorted_distances, Arc::new(DistanceMatrix::new(&shuffled, DistanceMetric::Ciede2000)));
+
+        // Mutation invalidates cache
+        palette.push(ColorRGB([128, 128, 128]));
+        assert_eq!(palette.pairwise_distances(DistanceMetric::Ciede2000).size(), palette.len());
+        assert_eq!(PaletteRGB::from(vec![ColorRGB([0, 0, 0])]).pairwise_distances(DistanceMetric::Rgb).closest_pair(), None);
+    }
+
     #[test]
     fn test_convertion_to_lab_and_from() {
         let test_palette = PaletteRGB::primary_bw();
@@ -621,4 +2913,167 @@ mod tests {
         assert_eq!(combined_palette, expected_combined_palette)
 
     }
+
+    #[test]
+    fn test_combining_palettes_removes_all_duplicates() {
+        let mut palette = PaletteRGB::primary_bw();
+        palette.combine(PaletteRGB::primary_bw());
+        assert_eq!(palette, PaletteRGB::primary_bw());
+    }
+
+    #[test]
+    fn test_dedup_within_tolerance() {
+        let mut palette = PaletteRGB::primary_bw();
+        palette.combine(PaletteRGB::from(vec![ColorRGB([1, 1, 1]), ColorRGB([0, 0, 250])]));
+        assert_eq!(palette.len(), 7);
+
+        let mut exact_palette = palette.clone();
+        exact_palette.dedup_within(0.0);
+        assert_eq!(exact_palette, palette);
+
+        palette.dedup_within(2.0);
+        assert_eq!(palette.len(), 5);
+        assert!(palette.contains(&ColorRGB([255, 255, 255])));
+        assert!(palette.iter().all(|color| color.blue() < 10 || color.blue() >= 250));
+    }
+
+    #[test]
+    fn test_removing_colors_within_tolerance() {
+        let mut palette = PaletteRGB::primary_bw();
+        let almost_black_and_red = PaletteRGB::from(vec![ColorRGB([3, 3, 3]), ColorRGB([250, 0, 0])]);
+
+        palette.remove_within(&almost_black_and_red, 0.0);
+        assert_eq!(palette.len(), 5);
+
+        palette.remove_within(&almost_black_and_red, 3.0);
+        assert_eq!(palette.len(), 3);
+        assert!(!palette.contains(&ColorRGB([0, 0, 0])));
+        assert!(!palette.contains(&ColorRGB([255, 0, 0])));
+    }
+
+    #[test]
+    fn test_harmony_hues() {
+        let seed = ColorRGB([40, 90, 200]);
+        let seed_lch = palette::Lch::from_color(seed.to_lab());
+        let hue_distance = |color: &ColorRGB| {
+            let lch = palette::Lch::from_color(color.to_lab());
+            (lch.hue - seed_lch.hue).into_degrees().abs()
+        };
+
+        let complementary = PaletteRGB::harmony(seed, HarmonyKind::Complementary, 2);
+        assert_eq!(complementary.len(), 2);
+        assert!(complementary.contains(&seed));
+        let opposite = complementary.iter().find(|color| **color != seed).unwrap();
+        assert!(hue_distance(opposite) > 150.0, "{opposite}");
+
+        for kind in [HarmonyKind::Triadic, HarmonyKind::Analogous, HarmonyKind::SplitComplementary] {
+            let palette = PaletteRGB::harmony(seed, kind, 7);
+            assert_eq!(palette.len(), 7, "{kind}");
+            assert!(palette.contains(&seed));
+        }
+        assert_eq!(PaletteRGB::harmony(seed, HarmonyKind::Triadic, 1).len(), 1);
+    }
+
+    #[test]
+    fn test_random_palette_constraints() {
+        let options = RandomPaletteOptions::default()
+            .with_lightness(20.0, 60.0)
+            .with_min_delta_e(12.0)
+            .with_seed(3);
+        let palette = PaletteRGB::random(8, &options).unwrap();
+        assert_eq!(palette.len(), 8);
+        assert!(palette.iter().all(|color| (19.5..=60.5).contains(&color.to_lab().l)));
+        assert!(palette.pairwise_distances(DistanceMetric::Ciede2000).closest_pair().unwrap().2 >= 12.0);
+        assert_ne!(palette, PaletteRGB::random(8, &options.with_seed(4)).unwrap());
+
+        let impossible = PaletteRGB::random(50, &options.with_min_delta_e(60.0));
+        assert!(matches!(impossible, Err(PaletteError::RandomConstraintsUnsatisfied { requested: 50, .. })));
+    }
+
+    #[test]
+    fn test_color_names_round_trip() {
+        let json = r##"[[0, 0, 0], "steelblue", "#ff0000", {"name": "sky", "rgb": [135, 206, 235]}, {"name": "Rebecca Purple"}]"##;
+        let palette: PaletteRGB = serde_json::from_str(json).unwrap();
+        assert_eq!(palette.len(), 5);
+        assert_eq!(palette.color_name(&ColorRGB([70, 130, 180])), Some("steelblue"));
+        assert_eq!(palette.color_name(&ColorRGB([135, 206, 235])), Some("sky"));
+        assert_eq!(palette.color_name(&ColorRGB([102, 51, 153])), Some("Rebecca Purple"));
+        assert_eq!(palette.color_name(&ColorRGB([255, 0, 0])), None);
+
+        let loaded_palette: PaletteRGB = serde_json::from_str(&serde_json::to_string(&palette).unwrap()).unwrap();
+        assert_eq!(loaded_palette, palette);
+        assert!(palette.iter().all(|color| loaded_palette.color_name(color) == palette.color_name(color)));
+
+        // Palettes without names keep plain format
+        assert_eq!(serde_json::to_string(&PaletteRGB::black_and_white()).unwrap(), "[[0,0,0],[255,255,255]]");
+        assert!(serde_json::from_str::<PaletteRGB>(r#"["notacolor"]"#).is_err());
+    }
+
+    #[test]
+    fn test_entries_metadata_survives_reduction() {
+        let mut palette = PaletteRGB::grayscale(16);
+        palette.set_entry(PaletteEntry::new(ColorRGB([17, 17, 17])).with_name("ink").with_count(40).with_locked(true));
+        palette.set_entry(PaletteEntry::new(ColorRGB([255, 255, 255])).with_name("paper"));
+
+        let json = serde_json::to_string(&palette).unwrap();
+        assert!(json.contains(r#"{"rgb":[17,17,17],"name":"ink","count":40,"locked":true}"#), "{json}");
+        let loaded_palette: PaletteRGB = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded_palette.entries(), palette.entries());
+
+        for reduced_palette in [loaded_palette.clone().try_reduce(3).unwrap(), loaded_palette.try_reduce_spaced(3, &[], 20.0).unwrap()] {
+            assert!(reduced_palette.contains(&ColorRGB([17, 17, 17])));
+            assert_eq!(reduced_palette.color_name(&ColorRGB([17, 17, 17])), Some("ink"));
+            assert_eq!(reduced_palette.locked_colors(), vec![ColorRGB([17, 17, 17])]);
+        }
+    }
+
+    #[test]
+    fn test_debug_lists_colors_only() {
+        let mut palette = PaletteRGB::black_and_white();
+        palette.set_color_name(ColorRGB([0, 0, 0]), "ink");
+        palette.pairwise_distances(DistanceMetric::default());
+        assert_eq!(format!("{palette:?}"), "PaletteRGB([ColorRGB([0, 0, 0]), ColorRGB([255, 255, 255])])");
+    }
+
+    #[test]
+    fn test_pinned_order_survives_loading_and_reduction() {
+        let json = r##"{"version": 2, "colors": [
+            "#ffffff", "#000000", "#808080", "#ff0000",
+            {"rgb": [255, 0, 255], "name": "transparent", "locked": true, "order": 0},
+            {"rgb": [10, 10, 10], "locked": true, "order": 1},
+            {"rgb": [0, 0, 200], "order": 99}
+        ]}"##;
+        let palette = PaletteRGB::load_from_json_reader(json.as_bytes()).unwrap();
+        assert_eq!(palette[0], ColorRGB([255, 0, 255]));
+        assert_eq!(palette[1], ColorRGB([10, 10, 10]));
+        assert_eq!(palette[palette.len() - 1], ColorRGB([0, 0, 200]));
+
+        // Unpinned colors keep lightness order between pinned ones
+        let lightness = palette[2..palette.len() - 1].iter().map(|color| color.to_lab().l).collect::<Vec<_>>();
+        assert!(lightness.windows(2).all(|pair| pair[0] <= pair[1]), "{lightness:?}");
+
+        let reduced_palette = palette.try_reduce(4).unwrap();
+        assert_eq!(reduced_palette[0], ColorRGB([255, 0, 255]));
+        assert_eq!(reduced_palette[1], ColorRGB([10, 10, 10]));
+
+        let mut buffer = Vec::new();
+        reduced_palette.save_to_json_writer(&mut buffer).unwrap();
+        assert!(String::from_utf8(buffer).unwrap().contains(r#""order": 0"#));
+    }
+
+    #[test]
+    fn test_palette_image_grid() {
+        // 3x2 grid of 5x3 swatches, last cell repeats first color
+        let colors = [[10, 10, 10], [250, 0, 0], [0, 250, 0], [0, 0, 250], [240, 240, 240], [10, 10, 10]];
+        let grid = image::RgbImage::from_fn(15, 6, |x, y| image::Rgb(colors[(y / 3 * 3 + x / 5) as usize]));
+        let palette = PaletteRGB::from_palette_image(&grid).unwrap();
+        assert_eq!(palette.to_vec(), colors[..5].iter().copied().map(ColorRGB).collect::<Vec<_>>());
+
+        // Lospec style 1px strip
+        let strip = image::RgbImage::from_fn(5, 1, |x, _| image::Rgb(colors[x as usize]));
+        assert_eq!(PaletteRGB::from_palette_image(&strip).unwrap().len(), 5);
+
+        let picture = crate::image::generate_test_gradient_image(300, 2, image::Rgb([0, 0, 0]), image::Rgb([255, 128, 40]));
+        assert!(matches!(PaletteRGB::from_palette_image(&picture), Err(PaletteError::NotPaletteImage { .. })));
+    }
 }
\ No newline at end of file
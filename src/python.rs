@@ -0,0 +1,98 @@
+//! Python bindings exposed through PyO3, enabled with the `python` feature.
+//!
+//! Build the extension module with [maturin](https://www.maturin.rs/):
+//!
+//! ```sh
+//! maturin develop --features python
+//! ```
+//!
+//! ```python
+//! import numpy as np
+//! import ditherum
+//!
+//! img = np.asarray(PIL.Image.open("image.png").convert("RGB"))
+//! palette = ditherum.extract_palette(img, 8)
+//! dithered = ditherum.dither(img, palette, "fs-rgb")
+//! ```
+//!
+//! Images are passed as `uint8` arrays of shape `(height, width, 3)`,
+//! palettes as `uint8` arrays (or lists) of shape `(colors_count, 3)`.
+
+use numpy::{
+    ndarray::{Array2, ArrayView3},
+    IntoPyArray,
+    PyArray2,
+    PyArray3,
+    PyReadonlyArray3
+};
+use pyo3::{exceptions::PyValueError, prelude::*};
+
+use crate::{
+    color::ColorRGB,
+    image::{ImageProcessor, ProcessingAlgorithm},
+    interop::{array3_to_rgb_image, rgb_image_to_array3},
+    palette::PaletteRGB
+};
+
+/// Converts `(height, width, 3)` array view to `RgbImage`.
+fn array_to_rgb_image(array: ArrayView3<u8>) -> PyResult<image::RgbImage> {
+    let (height, width, _) = array.dim();
+    if width < 2 || height < 2 {
+        return Err(PyValueError::new_err(format!("Image too small, width={width}, height={height}")));
+    }
+
+    array3_to_rgb_image(array).map_err(|e| PyValueError::new_err(e.to_string()))
+}
+
+/// Dithers image using given palette and algorithm, same as `ditherum dither` does.
+///
+/// Returns a new `(height, width, 3)` `uint8` array.
+#[pyfunction]
+#[pyo3(signature = (image, palette, algorithm = "fs-rgb"))]
+fn dither<'py>(
+    py: Python<'py>,
+    image: PyReadonlyArray3<'py, u8>,
+    palette: Vec<[u8; 3]>,
+    algorithm: &str
+) -> PyResult<Bound<'py, PyArray3<u8>>> {
+    let algorithm = algorithm.parse::<ProcessingAlgorithm>()
+        .map_err(|e| PyValueError::new_err(e.to_string()))?;
+    if palette.is_empty() {
+        return Err(PyValueError::new_err("Palette is empty"));
+    }
+    let palette = PaletteRGB::from(palette.into_iter().map(ColorRGB).collect::<Vec<_>>());
+    let source_image = array_to_rgb_image(image.as_array())?;
+
+    let processed_image = py.detach(|| {
+        ImageProcessor::new(source_image, palette)
+            .with_algorithm(algorithm)
+            .run()
+    });
+
+    Ok(rgb_image_to_array3(processed_image).into_pyarray(py))
+}
+
+/// Extracts palette from image and reduces it to `colors_count` colors.
+///
+/// Returns `(colors_count, 3)` `uint8` array sorted by lightness.
+#[pyfunction]
+fn extract_palette<'py>(
+    py: Python<'py>,
+    image: PyReadonlyArray3<'py, u8>,
+    colors_count: usize
+) -> PyResult<Bound<'py, PyArray2<u8>>> {
+    let source_image = array_to_rgb_image(image.as_array())?;
+
+    let palette = py.detach(|| {
+        PaletteRGB::from_rgbu8_image(&source_image).try_reduce(colors_count)
+    }).map_err(|e| PyValueError::new_err(e.to_string()))?;
+
+    Ok(Array2::from(&palette).into_pyarray(py))
+}
+
+#[pymodule]
+fn ditherum(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(dither, m)?)?;
+    m.add_function(wrap_pyfunction!(extract_palette, m)?)?;
+    Ok(())
+}
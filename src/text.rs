@@ -0,0 +1,205 @@
+//! Minimal text rendering using embedded 5x7 bitmap font.
+//!
+//! Meant for short labels like algorithm names or timings, supports digits, latin letters
+//! (lowercase rendered as uppercase) and basic punctuation. Other characters are drawn as `?`.
+//! Text is split into lines at `\n`.
+
+use image::{Rgb, RgbImage};
+
+/// Glyph width in pixels, before scaling.
+pub const GLYPH_WIDTH: u32 = 5;
+
+/// Glyph height in pixels, before scaling.
+pub const GLYPH_HEIGHT: u32 = 7;
+
+/// Horizontal space between glyphs in pixels, before scaling.
+pub const GLYPH_SPACING: u32 = 1;
+
+/// Vertical space between lines in pixels, before scaling.
+pub const LINE_SPACING: u32 = 2;
+
+/// Margin between caption text and edges of its box in pixels, before scaling.
+const CAPTION_PADDING: u32 = 2;
+
+/// Returns rows of glyph, lowest 5 bits of each row are pixels with leftmost as most significant.
+fn glyph_rows(c: char) -> [u8; GLYPH_HEIGHT as usize] {
+    match c.to_ascii_uppercase() {
+        'A' => [0x0E, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],
+        'B' => [0x1E, 0x11, 0x11, 0x1E, 0x11, 0x11, 0x1E],
+        'C' => [0x0E, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0E],
+        'D' => [0x1E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x1E],
+        'E' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x1F],
+        'F' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x10],
+        'G' => [0x0E, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0F],
+        'H' => [0x11, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],
+        'I' => [0x0E, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        'J' => [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0C],
+        'K' => [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11],
+        'L' => [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1F],
+        'M' => [0x11, 0x1B, 0x15, 0x15, 0x11, 0x11, 0x11],
+        'N' => [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11],
+        'O' => [0x0E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        'P' => [0x1E, 0x11, 0x11, 0x1E, 0x10, 0x10, 0x10],
+        'Q' => [0x0E, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0D],
+        'R' => [0x1E, 0x11, 0x11, 0x1E, 0x14, 0x12, 0x11],
+        'S' => [0x0F, 0x10, 0x10, 0x0E, 0x01, 0x01, 0x1E],
+        'T' => [0x1F, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04],
+        'U' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        'V' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x0A, 0x04],
+        'W' => [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0A],
+        'X' => [0x11, 0x11, 0x0A, 0x04, 0x0A, 0x11, 0x11],
+        'Y' => [0x11, 0x11, 0x11, 0x0A, 0x04, 0x04, 0x04],
+        'Z' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1F],
+        '0' => [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E],
+        '1' => [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        '2' => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F],
+        '3' => [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E],
+        '4' => [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02],
+        '5' => [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E],
+        '6' => [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E],
+        '7' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08],
+        '8' => [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E],
+        '9' => [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C],
+        ' ' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+        '-' => [0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00],
+        '+' => [0x00, 0x04, 0x04, 0x1F, 0x04, 0x04, 0x00],
+        '=' => [0x00, 0x00, 0x1F, 0x00, 0x1F, 0x00, 0x00],
+        '.' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C],
+        ',' => [0x00, 0x00, 0x00, 0x00, 0x0C, 0x04, 0x08],
+        ':' => [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x0C, 0x00],
+        '_' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1F],
+        '/' => [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00],
+        '%' => [0x18, 0x19, 0x02, 0x04, 0x08, 0x13, 0x03],
+        '(' => [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02],
+        ')' => [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08],
+        '#' => [0x0A, 0x0A, 0x1F, 0x0A, 0x1F, 0x0A, 0x0A],
+        _ => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04],
+    }
+}
+
+/// Computes size of rendered text in pixels as `(width, height)`.
+///
+/// # Example
+/// ```
+/// use ditherum::text::text_size;
+///
+/// assert_eq!(text_size("ab", 1), (11, 7));
+/// assert_eq!(text_size("ab", 2), (22, 14));
+/// assert_eq!(text_size("ab\nabc", 1), (17, 16));
+/// ```
+pub fn text_size(text: &str, scale: u32) -> (u32, u32) {
+    let lines_count = text.split('\n').count() as u32;
+    let chars_count = text.split('\n').map(|line| line.chars().count()).max().unwrap_or(0) as u32;
+    let width = (chars_count * (GLYPH_WIDTH + GLYPH_SPACING)).saturating_sub(GLYPH_SPACING);
+    let height = lines_count * (GLYPH_HEIGHT + LINE_SPACING) - LINE_SPACING;
+    (width * scale, height * scale)
+}
+
+/// Draws text with its top-left corner at `(x, y)`, lines are separated by [`LINE_SPACING`].
+///
+/// # Parameters
+/// - `img`: Image to draw on, pixels outside of the image are skipped.
+/// - `x`, `y`: Position of the text top-left corner.
+/// - `text`: Text to be drawn.
+/// - `color`: Color of the text.
+/// - `scale`: Size of a single font pixel in image pixels.
+pub fn draw_text(img: &mut RgbImage, x: u32, y: u32, text: &str, color: Rgb<u8>, scale: u32) {
+    for (line_idx, line) in text.split('\n').enumerate() {
+        draw_line(img, x, y + line_idx as u32 * (GLYPH_HEIGHT + LINE_SPACING) * scale, line, color, scale);
+    }
+}
+
+/// Stamps text on a box filled with `background` in bottom-left corner of the image, so that it
+/// stays readable over any content. Box is clipped to the image.
+///
+/// # Example
+/// ```
+/// use ditherum::text::{draw_caption, text_size};
+///
+/// let mut img = image::RgbImage::from_pixel(100, 40, image::Rgb([255, 255, 255]));
+/// draw_caption(&mut img, "bayer4", image::Rgb([255, 255, 255]), image::Rgb([0, 0, 0]), 1);
+///
+/// // Box corner is filled with background, area outside of it is untouched
+/// assert_eq!(img.get_pixel(0, 39).0, [0, 0, 0]);
+/// assert_eq!(img.get_pixel(99, 0).0, [255, 255, 255]);
+/// ```
+pub fn draw_caption(img: &mut RgbImage, text: &str, color: Rgb<u8>, background: Rgb<u8>, scale: u32) {
+    let padding = CAPTION_PADDING * scale;
+    let (text_width, text_height) = text_size(text, scale);
+    let box_width = (text_width + 2 * padding).min(img.width());
+    let box_y = img.height().saturating_sub(text_height + 2 * padding);
+
+    for y in box_y..img.height() {
+        for x in 0..box_width {
+            img.put_pixel(x, y, background);
+        }
+    }
+    draw_text(img, padding, box_y + padding, text, color, scale);
+}
+
+/// Draws single line of text with its top-left corner at `(x, y)`.
+fn draw_line(img: &mut RgbImage, x: u32, y: u32, text: &str, color: Rgb<u8>, scale: u32) {
+    for (char_idx, c) in text.chars().enumerate() {
+        let glyph_x = x + char_idx as u32 * (GLYPH_WIDTH + GLYPH_SPACING) * scale;
+
+        for (row_idx, row) in glyph_rows(c).into_iter().enumerate() {
+            for column_idx in 0..GLYPH_WIDTH {
+                if row & (1 << (GLYPH_WIDTH - 1 - column_idx)) == 0 {
+                    continue;
+                }
+
+                let px_x = glyph_x + column_idx * scale;
+                let px_y = y + row_idx as u32 * scale;
+                for dy in 0..scale {
+                    for dx in 0..scale {
+                        if let Some(pixel) = img.get_pixel_mut_checked(px_x + dx, px_y + dy) {
+                            *pixel = color;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_draw_text_within_bounds() {
+        let (width, height) = text_size("fs-rgb 12%", 2);
+        let mut img = RgbImage::new(width + 10, height + 10);
+        draw_text(&mut img, 5, 5, "fs-rgb 12%", Rgb([255, 255, 255]), 2);
+
+        let drawn_pixels = img.enumerate_pixels()
+            .filter(|(_, _, px)| px.0 == [255, 255, 255])
+            .collect::<Vec<_>>();
+        assert!(!drawn_pixels.is_empty());
+        assert!(drawn_pixels.iter().all(|(x, y, _)| {
+            (5..5 + width).contains(x) && (5..5 + height).contains(y)
+        }));
+
+        // Clipped text does not panic
+        draw_text(&mut img, width, height, "clipped", Rgb([255, 0, 0]), 3);
+    }
+
+    #[test]
+    fn test_draw_caption_multiline() {
+        let mut img = RgbImage::from_pixel(80, 40, Rgb([255, 255, 255]));
+        draw_caption(&mut img, "bayer4\n8 colors", Rgb([255, 0, 0]), Rgb([0, 0, 0]), 1);
+
+        // Box hugs both lines with padding, text is drawn inside it
+        let (text_width, text_height) = text_size("bayer4\n8 colors", 1);
+        let box_top = 40 - text_height - 2 * CAPTION_PADDING;
+        assert_eq!(img.get_pixel(0, box_top).0, [0, 0, 0]);
+        assert_eq!(img.get_pixel(0, box_top - 1).0, [255, 255, 255]);
+        assert_eq!(img.get_pixel(text_width + 2 * CAPTION_PADDING, 39).0, [255, 255, 255]);
+        let second_line_top = box_top + CAPTION_PADDING + GLYPH_HEIGHT + LINE_SPACING;
+        assert!((0..text_width).any(|x| img.get_pixel(CAPTION_PADDING + x, second_line_top + 3).0 == [255, 0, 0]));
+
+        // Caption larger than image is clipped
+        let mut img = RgbImage::new(4, 4);
+        draw_caption(&mut img, "clipped", Rgb([255, 0, 0]), Rgb([0, 0, 255]), 2);
+    }
+}
@@ -0,0 +1,406 @@
+//! Dithering sequences of frames with a fixed palette, available with `video` feature.
+//!
+//! Dithering every frame independently makes static areas shimmer: error diffusion reacts
+//! to tiny changes, e.g. compression noise, and picks different patterns frame to frame.
+//! [`FrameDitherer`] keeps palette fixed for the whole sequence and can hold previous output
+//! of pixels whose source barely changed, see [`FrameDitherer::with_temporal_stability`].
+//!
+//! Frames come from any iterator of [`RgbImage`], [`ffmpeg`] decodes and encodes them
+//! using `ffmpeg` and `ffprobe` executables found in `PATH`.
+//!
+//! # Example
+//! ```
+//! use ditherum::{image::ProcessingAlgorithm, palette::PaletteRGB, video::FrameDitherer};
+//!
+//! let frames = (0..4).map(|_| image::RgbImage::from_pixel(8, 8, image::Rgb([100, 100, 100])));
+//! let dithered_frames = FrameDitherer::new(PaletteRGB::black_and_white())
+//!     .with_algorithm(ProcessingAlgorithm::FloydSteinbergRgb)
+//!     .with_temporal_stability(4)
+//!     .dither_frames(frames)
+//!     .collect::<Vec<_>>();
+//!
+//! assert_eq!(dithered_frames.len(), 4);
+//! assert!(dithered_frames.windows(2).all(|pair| pair[0] == pair[1]));
+//! ```
+
+use image::RgbImage;
+
+use crate::{image::ProcessingAlgorithm, palette::PaletteRGB};
+
+pub mod errors {
+    #[derive(Debug, thiserror::Error)]
+    pub enum VideoError {
+        #[error("IO error, reason={0}")]
+        IoError(std::io::Error),
+
+        #[error("Executable '{0}' not found, install FFmpeg and make sure it is in PATH")]
+        ExecutableNotFound(&'static str),
+
+        #[error("FFmpeg failed, reason={0}")]
+        FfmpegFailed(String),
+
+        #[error("Unexpected ffprobe output '{0}'")]
+        InvalidProbeOutput(String),
+
+        #[error("Frame dimensions {actual:?} differ from video dimensions {expected:?}")]
+        FrameSizeMismatch {
+            expected: (u32, u32),
+            actual: (u32, u32),
+        },
+    }
+
+    impl From<std::io::Error> for VideoError {
+        fn from(value: std::io::Error) -> Self {
+            Self::IoError(value)
+        }
+    }
+}
+
+/// Dithers consecutive frames using the same palette and reused buffers.
+#[derive(Debug, Clone)]
+pub struct FrameDitherer {
+    palette: PaletteRGB,
+    algorithm: ProcessingAlgorithm,
+    stability_threshold: Option<u8>,
+
+    /// Source pixels the current output was computed from, per pixel.
+    reference_frame: RgbImage,
+    output_frame: RgbImage,
+    scratch_frame: RgbImage,
+}
+
+impl FrameDitherer {
+    /// Creates ditherer using given palette for every frame and default algorithm.
+    pub fn new(palette: PaletteRGB) -> Self {
+        Self {
+            palette,
+            algorithm: ProcessingAlgorithm::default(),
+            stability_threshold: None,
+            reference_frame: RgbImage::new(0, 0),
+            output_frame: RgbImage::new(0, 0),
+            scratch_frame: RgbImage::new(0, 0),
+        }
+    }
+
+    /// Sets the processing algorithm.
+    pub fn with_algorithm(mut self, algorithm: ProcessingAlgorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    /// Keeps previous output of pixels whose every channel differs by at most `threshold`
+    /// from the source the output was computed from.
+    ///
+    /// Difference is measured against the last refreshed source, not the previous frame,
+    /// so slow fades still update once accumulated change exceeds the threshold.
+    pub fn with_temporal_stability(mut self, threshold: u8) -> Self {
+        self.stability_threshold = Some(threshold);
+        self
+    }
+
+    /// Returns palette used for every frame.
+    pub fn palette(&self) -> &PaletteRGB {
+        &self.palette
+    }
+
+    /// Dithers next frame of the sequence.
+    ///
+    /// Frame dimensions may change, e.g. on scene cut, then the frame is processed as the first one.
+    pub fn dither_frame(&mut self, frame: &RgbImage) -> &RgbImage {
+        self.algorithm.process_into(frame, &self.palette, None, &mut self.scratch_frame);
+
+        match self.stability_threshold {
+            Some(threshold) if self.reference_frame.dimensions() == frame.dimensions() => {
+                self.scratch_frame.pixels_mut()
+                    .zip(self.output_frame.pixels())
+                    .zip(self.reference_frame.pixels_mut().zip(frame.pixels()))
+                    .for_each(|((scratch_px, output_px), (reference_px, frame_px))| {
+                        let is_stable = reference_px.0.iter()
+                            .zip(frame_px.0)
+                            .all(|(&reference, current)| reference.abs_diff(current) <= threshold);
+                        if is_stable {
+                            *scratch_px = *output_px;
+                        } else {
+                            *reference_px = *frame_px;
+                        }
+                    });
+            },
+            Some(_) => self.reference_frame = frame.clone(),
+            None => {},
+        }
+
+        std::mem::swap(&mut self.output_frame, &mut self.scratch_frame);
+        &self.output_frame
+    }
+
+    /// Turns frames iterator into iterator of dithered frames.
+    pub fn dither_frames<I>(self, frames: I) -> DitheredFrames<I::IntoIter>
+    where
+        I: IntoIterator<Item = RgbImage>
+    {
+        DitheredFrames {
+            ditherer: self,
+            frames: frames.into_iter(),
+        }
+    }
+}
+
+/// Iterator returned by [`FrameDitherer::dither_frames`].
+#[derive(Debug)]
+pub struct DitheredFrames<I> {
+    ditherer: FrameDitherer,
+    frames: I,
+}
+
+impl<I: Iterator<Item = RgbImage>> Iterator for DitheredFrames<I> {
+    type Item = RgbImage;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let frame = self.frames.next()?;
+        Some(self.ditherer.dither_frame(&frame).clone())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.frames.size_hint()
+    }
+}
+
+/// Decoding and encoding video through `ffmpeg` and `ffprobe` executables.
+///
+/// Frames are exchanged as raw RGB24 through pipes. Output format follows output path
+/// extension, e.g. `.gif`, `.mkv` or `frames_%04d.png` pattern for separate images.
+/// Lossy codecs with chroma subsampling blur dithering patterns, prefer lossless ones.
+pub mod ffmpeg {
+    use std::{
+        io::{ErrorKind, Read, Write},
+        path::Path,
+        process::{Child, ChildStdin, ChildStdout, Command, Stdio}
+    };
+
+    use image::RgbImage;
+
+    use super::errors::VideoError;
+
+    const FFMPEG: &str = "ffmpeg";
+    const FFPROBE: &str = "ffprobe";
+
+    /// Properties of the first video stream.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct VideoInfo {
+        pub width: u32,
+        pub height: u32,
+
+        /// Frame rate as reported by ffprobe, e.g. `30/1` or `30000/1001`.
+        pub frame_rate: String,
+    }
+
+    /// Reads dimensions and frame rate of the first video stream.
+    pub fn probe(path: &Path) -> Result<VideoInfo, VideoError> {
+        let output = Command::new(FFPROBE)
+            .args(["-v", "error", "-select_streams", "v:0", "-show_entries", "stream=width,height,r_frame_rate", "-of", "csv=p=0"])
+            .arg(path)
+            .output()
+            .map_err(|e| not_found_or_io(e, FFPROBE))?;
+        if !output.status.success() {
+            return Err(VideoError::FfmpegFailed(String::from_utf8_lossy(&output.stderr).trim().to_string()));
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        parse_probe_output(&stdout).ok_or(VideoError::InvalidProbeOutput(stdout))
+    }
+
+    fn parse_probe_output(output: &str) -> Option<VideoInfo> {
+        let mut fields = output.lines().next()?.split(',');
+        let width = fields.next()?.trim().parse().ok()?;
+        let height = fields.next()?.trim().parse().ok()?;
+        let frame_rate = fields.next()?.trim().to_string();
+        Some(VideoInfo { width, height, frame_rate })
+    }
+
+    fn not_found_or_io(error: std::io::Error, executable: &'static str) -> VideoError {
+        match error.kind() {
+            ErrorKind::NotFound => VideoError::ExecutableNotFound(executable),
+            _ => VideoError::IoError(error),
+        }
+    }
+
+    fn check_exit(mut child: Child) -> Result<(), VideoError> {
+        let status = child.wait()?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(VideoError::FfmpegFailed(format!("ffmpeg exited with {status}")))
+        }
+    }
+
+    /// Iterator of decoded frames.
+    #[derive(Debug)]
+    pub struct FrameReader {
+        info: VideoInfo,
+        child: Option<Child>,
+        stdout: Option<ChildStdout>,
+    }
+
+    impl FrameReader {
+        /// Starts decoding video, ffmpeg errors are written to stderr.
+        pub fn open(path: &Path) -> Result<Self, VideoError> {
+            let info = probe(path)?;
+            let mut child = Command::new(FFMPEG)
+                .args(["-v", "error", "-i"])
+                .arg(path)
+                .args(["-map", "0:v:0", "-f", "rawvideo", "-pix_fmt", "rgb24", "-"])
+                .stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .spawn()
+                .map_err(|e| not_found_or_io(e, FFMPEG))?;
+            let stdout = child.stdout.take();
+
+            Ok(Self { info, child: Some(child), stdout })
+        }
+
+        pub fn info(&self) -> &VideoInfo {
+            &self.info
+        }
+
+        fn read_frame(&mut self) -> Result<Option<RgbImage>, VideoError> {
+            let Some(stdout) = self.stdout.as_mut() else {
+                return Ok(None);
+            };
+
+            let mut buffer = vec![0; self.info.width as usize * self.info.height as usize * 3];
+            let mut filled = 0;
+            while filled < buffer.len() {
+                match stdout.read(&mut buffer[filled..])? {
+                    0 => break,
+                    read => filled += read,
+                }
+            }
+
+            if filled == buffer.len() {
+                return Ok(RgbImage::from_raw(self.info.width, self.info.height, buffer));
+            }
+
+            // End of stream, truncated frame is dropped
+            self.stdout = None;
+            match self.child.take() {
+                Some(child) => check_exit(child).map(|_| None),
+                None => Ok(None),
+            }
+        }
+    }
+
+    impl Iterator for FrameReader {
+        type Item = Result<RgbImage, VideoError>;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            self.read_frame().transpose()
+        }
+    }
+
+    impl Drop for FrameReader {
+        fn drop(&mut self) {
+            if let Some(mut child) = self.child.take() {
+                let _ = child.kill();
+                let _ = child.wait();
+            }
+        }
+    }
+
+    /// Encoder of frames with fixed dimensions, call [`FrameWriter::finish`] to complete the file.
+    #[derive(Debug)]
+    pub struct FrameWriter {
+        dimensions: (u32, u32),
+        child: Child,
+        stdin: Option<ChildStdin>,
+    }
+
+    impl FrameWriter {
+        /// Starts encoding to given path, existing file is overwritten.
+        pub fn create(path: &Path, width: u32, height: u32, frame_rate: &str) -> Result<Self, VideoError> {
+            let mut child = Command::new(FFMPEG)
+                .args(["-v", "error", "-y", "-f", "rawvideo", "-pix_fmt", "rgb24"])
+                .args(["-s", &format!("{width}x{height}"), "-r", frame_rate, "-i", "-"])
+                .arg(path)
+                .stdin(Stdio::piped())
+                .stdout(Stdio::null())
+                .spawn()
+                .map_err(|e| not_found_or_io(e, FFMPEG))?;
+            let stdin = child.stdin.take();
+
+            Ok(Self { dimensions: (width, height), child, stdin })
+        }
+
+        pub fn write_frame(&mut self, frame: &RgbImage) -> Result<(), VideoError> {
+            if frame.dimensions() != self.dimensions {
+                return Err(VideoError::FrameSizeMismatch { expected: self.dimensions, actual: frame.dimensions() });
+            }
+            match self.stdin.as_mut() {
+                Some(stdin) => Ok(stdin.write_all(frame.as_raw())?),
+                None => Err(VideoError::FfmpegFailed("encoder input closed".to_string())),
+            }
+        }
+
+        /// Closes input and waits for ffmpeg to write the file.
+        pub fn finish(mut self) -> Result<(), VideoError> {
+            drop(self.stdin.take());
+            check_exit(self.child)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_parse_probe_output() {
+            assert_eq!(parse_probe_output("640,360,30000/1001\n"), Some(VideoInfo {
+                width: 640,
+                height: 360,
+                frame_rate: "30000/1001".to_string(),
+            }));
+            assert_eq!(parse_probe_output("N/A,360,30/1"), None);
+            assert_eq!(parse_probe_output(""), None);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_temporal_stability_holds_static_pixels() {
+        let first_frame = crate::image::generate_test_gradient_image(32, 8, image::Rgb([0, 0, 0]), image::Rgb([255, 255, 255]));
+        // Noise-like change of one level everywhere, large change in the left column
+        let second_frame = RgbImage::from_fn(32, 8, |x, y| {
+            let px = first_frame.get_pixel(x, y).0;
+            if x == 0 { image::Rgb([255, 255, 255]) } else { image::Rgb(px.map(|channel| channel.saturating_add(1))) }
+        });
+
+        let mut ditherer = FrameDitherer::new(PaletteRGB::black_and_white()).with_temporal_stability(2);
+        let first_output = ditherer.dither_frame(&first_frame).clone();
+        let second_output = ditherer.dither_frame(&second_frame).clone();
+
+        for (x, y, px) in second_output.enumerate_pixels() {
+            if x == 0 {
+                assert_eq!(px.0, [255, 255, 255]);
+            } else {
+                assert_eq!(px, first_output.get_pixel(x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn test_temporal_stability_follows_slow_fade() {
+        let mut ditherer = FrameDitherer::new(PaletteRGB::black_and_white())
+            .with_algorithm(ProcessingAlgorithm::ThresholdingRgb)
+            .with_temporal_stability(8);
+
+        // Each step is below threshold, accumulated change is not
+        let last_output = (0..=255u8).step_by(5)
+            .map(|level| ditherer.dither_frame(&RgbImage::from_pixel(4, 4, image::Rgb([level; 3]))).clone())
+            .last()
+            .unwrap();
+        assert!(last_output.pixels().all(|px| px.0 == [255, 255, 255]));
+    }
+}
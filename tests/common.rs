@@ -23,13 +23,15 @@ pub fn tests_setup() {
     // Initialize logger if the logging feature is enabled.
     LOGGER_INIT.get_or_init(|| {
         if cfg!(feature = "logging") {
-            env_logger::init();
+            tracing_subscriber::fmt()
+                .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+                .init();
         }
     });
 
     // Clear and recreate the results directory only once.
     RESOURCE_INIT.get_or_init(|| {
-        log::info!("Initializing test resources...");
+        tracing::info!("Initializing test resources...");
         let absolute_results_dir_path = Path::new(env!("CARGO_MANIFEST_DIR")).join(SAVE_TEST_IMAGE_DIR);
 
         if absolute_results_dir_path.exists() {
@@ -56,7 +58,7 @@ where
         .join(TEST_IMAGES_DIR)
         .join(&path);
     
-    log::debug!(
+    tracing::debug!(
         "Loading test file '{:?}' at absolute path '{:?}' ...", 
         path, 
         absolute_test_image_path
@@ -65,7 +67,7 @@ where
     let img = ditherum::image::load_image(absolute_test_image_path)
         .unwrap_or_else(|e| panic!("Failed to open test image, reason: {}", e));
 
-    log::debug!("Image loaded: width={}, height={}", img.width(), img.height());
+    tracing::debug!("Image loaded: width={}, height={}", img.width(), img.height());
     img
 }
 
@@ -0,0 +1,92 @@
+//! Golden image regression tests, every algorithm with every palette is rendered and compared
+//! to its reference image in `res/golden_images`.
+//!
+//! Output may differ from reference by perceived CIEDE2000 difference up to [`PERCEIVED_TOLERANCE`],
+//! so tiny floating point differences between platforms pass, while changed dithering patterns fail.
+//! Outputs of failed combinations are saved to `res/test_results/golden` for inspection.
+//!
+//! After intended change of an algorithm regenerate references and review them before committing:
+//! ```sh
+//! UPDATE_GOLDENS=1 cargo test --test golden_tests
+//! ```
+
+#[allow(dead_code)]
+mod common;
+
+use std::path::Path;
+
+use common::{load_test_image, COLOR_PINK300_IMAGE_FILENAME, SAVE_TEST_IMAGE_DIR};
+use ditherum::{
+    image::{self, manip, ImageProcessor, ProcessingAlgorithm, ResizeFilter, ResizeMode},
+    metrics::ImageQuality,
+    palette::PaletteRGB
+};
+
+const GOLDEN_IMAGES_DIR: &str = "./res/golden_images";
+
+/// Environment variable enabling regeneration of reference images.
+const UPDATE_GOLDENS_VAR: &str = "UPDATE_GOLDENS";
+
+/// Maximal perceived CIEDE2000 difference between output and reference image.
+const PERCEIVED_TOLERANCE: f32 = 0.5;
+
+/// Width of rendered images, small enough to keep references tiny.
+const GOLDEN_WIDTH: u32 = 64;
+
+fn golden_palettes() -> [(&'static str, PaletteRGB); 2] {
+    [
+        ("bw", PaletteRGB::black_and_white()),
+        ("primary", PaletteRGB::primary_bw()),
+    ]
+}
+
+#[test]
+fn test_golden_images() {
+    // cargo test --test golden_tests -- --nocapture
+    let source_image = manip::rgb_image_resize(
+        load_test_image(COLOR_PINK300_IMAGE_FILENAME),
+        Some(GOLDEN_WIDTH),
+        None,
+        ResizeMode::Fit,
+        ResizeFilter::Triangle
+    );
+    let update = std::env::var_os(UPDATE_GOLDENS_VAR).is_some();
+    let golden_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join(GOLDEN_IMAGES_DIR);
+    let failed_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join(SAVE_TEST_IMAGE_DIR).join("golden");
+
+    let mut failures = Vec::new();
+    for algorithm in ProcessingAlgorithm::all() {
+        for (palette_name, palette) in golden_palettes() {
+            let filename = format!("{}_{palette_name}.png", algorithm.name());
+            let golden_path = golden_dir.join(&filename);
+            let processed_image = ImageProcessor::new(source_image.clone(), palette)
+                .with_algorithm(*algorithm)
+                .run();
+
+            if update {
+                std::fs::create_dir_all(&golden_dir).unwrap();
+                image::save_image(&golden_path, &processed_image).unwrap();
+                continue;
+            }
+
+            let failure = match image::load_image(&golden_path) {
+                Err(e) => Some(format!("{filename}: missing reference ({e}), run with {UPDATE_GOLDENS_VAR}=1")),
+                Ok(golden_image) if golden_image.dimensions() != processed_image.dimensions() => {
+                    Some(format!("{filename}: dimensions {:?} differ from reference {:?}", processed_image.dimensions(), golden_image.dimensions()))
+                },
+                Ok(golden_image) => {
+                    let quality = ImageQuality::measure(&golden_image, &processed_image);
+                    (quality.perceived_mean_delta_e > PERCEIVED_TOLERANCE)
+                        .then(|| format!("{filename}: perceived difference {:.3} exceeds {PERCEIVED_TOLERANCE}", quality.perceived_mean_delta_e))
+                },
+            };
+            if let Some(failure) = failure {
+                std::fs::create_dir_all(&failed_dir).unwrap();
+                image::save_image(failed_dir.join(&filename), &processed_image).unwrap();
+                failures.push(failure);
+            }
+        }
+    }
+
+    assert!(failures.is_empty(), "{} golden images differ:\n{}", failures.len(), failures.join("\n"));
+}
@@ -24,7 +24,8 @@ use ditherum::{
     }, palette::{
         errors::PaletteError, 
         PaletteRGB
-    }
+    },
+    metadata::ImageMetadata
 };
 use ::image::Rgb;
 
@@ -77,7 +78,7 @@ fn test_reducing_color_palette() {
     let reduced_palette = palette.try_reduce(10);
     assert!(reduced_palette.is_ok(), "failed result={:?}", reduced_palette);
     let reduced_palette = reduced_palette.unwrap();
-    log::debug!(
+    tracing::debug!(
         "Reduced a palette of {} colors to {} colors: {:?}",
         original_len,
         reduced_palette.len(),
@@ -401,7 +402,7 @@ mod tests_cli {
         assert!(output.is_ok());
         
         let output = output.unwrap();
-        assert!(matches!(output.status.code(), Some(1)));
+        assert!(matches!(output.status.code(), Some(5)));
 
         let stderr_text = output.stderr.iter()
             .filter_map(|v| char::from_u32(*v as u32))
@@ -434,7 +435,7 @@ mod tests_cli {
         assert!(output.is_ok());
         
         let output = output.unwrap();
-        assert!(matches!(output.status.code(), Some(1)));
+        assert!(matches!(output.status.code(), Some(3)));
 
         let stderr_text = output.stderr.iter()
             .filter_map(|v| char::from_u32(*v as u32))
@@ -497,6 +498,44 @@ mod tests_cli {
         assert_eq!(palette_from_loaded_image.len(), loaded_palette.len());
         assert_eq!(palette_from_loaded_image, loaded_palette);
     }
+
+    #[test]
+    fn test_dither_16bit_input_keeps_precision() {
+        // cargo test --test integration_tests test_dither_16bit_input_keeps_precision -- --nocapture
+        tests_setup();
+        let deep_image = ::image::ImageBuffer::from_fn(64, 32, |x, y| Rgb([(x * 1000 + y * 7) as u16, 20000 + y as u16 * 300, 40000 - x as u16 * 97]));
+        let deep_image = ::image::DynamicImage::ImageRgb16(deep_image);
+        let absolute_deep_path = get_test_save_absolute_path("gradient_16bit.png");
+        let absolute_rounded_path = get_test_save_absolute_path("gradient_16bit_rounded.png");
+        deep_image.save(&absolute_deep_path).unwrap();
+        // Rounded the same way as library does, through floating point
+        ::image::DynamicImage::ImageRgb32F(deep_image.to_rgb32f()).to_rgb8().save(&absolute_rounded_path).unwrap();
+
+        let dither = |input_path: &std::path::Path, output_filename: &str| {
+            let absolute_output_path = get_test_save_absolute_path(output_filename);
+            let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+            cmd
+                .arg("dither")
+                .arg("-i")
+                .arg(input_path)
+                .arg("-a")
+                .arg("fs-rgb")
+                .arg("-c")
+                .arg("4")
+                .arg("-o")
+                .arg(&absolute_output_path);
+            let output = cmd.output().unwrap();
+            assert!(output.status.success(), "cmd output={output:?}.");
+            image::load_image(absolute_output_path).unwrap()
+        };
+
+        // Palette is picked from the same rounded pixels, only dithering sees the difference
+        let deep_result = dither(&absolute_deep_path, "gradient_16bit_dithered.png");
+        let rounded_result = dither(&absolute_rounded_path, "gradient_16bit_rounded_dithered.png");
+        assert_eq!(PaletteRGB::from_rgbu8_image(&deep_result).len(), 4);
+        assert_eq!(deep_result.dimensions(), rounded_result.dimensions());
+        assert_ne!(deep_result, rounded_result);
+    }
     
     #[test]
     fn test_dither_resize_width() {
@@ -614,5 +653,2392 @@ mod tests_cli {
             assert_eq!(expected_height, loaded_image.height());
         });
     }
+
+    #[test]
+    fn test_dither_batch_glob_with_palette() {
+        // cargo test --test integration_tests test_dither_batch_glob_with_palette -- --nocapture
+        tests_setup();
+        let input_pattern = get_test_image_absolute_path("test_*_300.*");
+        let absolute_palette_path = get_palette_absolute_path(PRIMARY_PALETTE_FILENAME);
+        let absolute_output_dir = get_test_save_absolute_path("batch_glob_dithered");
+
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("dither")
+            .arg("-i")
+            .arg(&input_pattern)
+            .arg("-p")
+            .arg(&absolute_palette_path)
+            .arg("-j")
+            .arg("2")
+            .arg("-o")
+            .arg(&absolute_output_dir);
+        let output = cmd.output();
+        assert!(output.is_ok());
+
+        let output = output.unwrap();
+        assert!(output.status.success(), "cmd output={output:?}.");
+
+        // Every matched image saved under its original filename
+        for filename in [COLOR_PINK300_IMAGE_FILENAME, COLOR_GRASS300_IMAGE_FILENAME, GRAY300_IMAGE_FILENAME] {
+            let loaded_image = image::load_image(absolute_output_dir.join(filename));
+            assert!(loaded_image.is_ok(), "Missing output for {filename}");
+        }
+    }
+
+    #[test]
+    fn test_dither_batch_no_matches() {
+        tests_setup();
+        let input_pattern = get_test_image_absolute_path("not_existing_*.png");
+        let absolute_output_dir = get_test_save_absolute_path("batch_no_matches_dithered");
+
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("dither")
+            .arg("-i")
+            .arg(&input_pattern)
+            .arg("-o")
+            .arg(&absolute_output_dir);
+        cmd.assert().failure();
+    }
+
+    #[test]
+    fn test_dither_batch_shared_palette() {
+        // cargo test --test integration_tests test_dither_batch_shared_palette -- --nocapture
+        tests_setup();
+        let colors_count = 3;
+        let absolute_input_dir = get_test_save_absolute_path("batch_shared_palette_frames");
+        let absolute_output_dir = get_test_save_absolute_path("batch_shared_palette_dithered");
+        let absolute_output_palette_path = get_test_save_absolute_path("batch_shared_palette.json");
+        std::fs::create_dir_all(&absolute_input_dir).unwrap();
+
+        // Frames with different colors content
+        let frames = [
+            ("frame_0.png", Rgb::<u8>([0,0,0]), Rgb::<u8>([255,0,0])),
+            ("frame_1.png", Rgb::<u8>([0,0,255]), Rgb::<u8>([255,255,255])),
+        ];
+        for (filename, from_color, to_color) in frames {
+            let frame = generate_test_gradient_image(64, 8, from_color, to_color);
+            image::save_image(absolute_input_dir.join(filename), &frame).unwrap();
+        }
+
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("dither")
+            .arg("-i")
+            .arg(&absolute_input_dir)
+            .arg("-c")
+            .arg(colors_count.to_string())
+            .arg("--shared-palette")
+            .arg("-r")
+            .arg(&absolute_output_palette_path)
+            .arg("-o")
+            .arg(&absolute_output_dir);
+        let output = cmd.output();
+        assert!(output.is_ok());
+
+        let output = output.unwrap();
+        assert!(output.status.success(), "cmd output={output:?}.");
+
+        let shared_palette = PaletteRGB::load_from_json(absolute_output_palette_path).unwrap();
+        assert_eq!(shared_palette.len(), colors_count);
+
+        // Every frame uses colors of the shared palette only
+        for (filename, _, _) in frames {
+            let loaded_image = image::load_image(absolute_output_dir.join(filename)).unwrap();
+            let frame_palette = PaletteRGB::from_rgbu8_image(&loaded_image);
+            assert!(frame_palette.iter().all(|color| shared_palette.contains(color)), "Frame {filename} colors {frame_palette:?} outside of {shared_palette:?}");
+        }
+    }
+
+    #[test]
+    fn test_dither_stdin_to_stdout() {
+        // cargo test --test integration_tests test_dither_stdin_to_stdout -- --nocapture
+        tests_setup();
+        let absolute_input_path = get_test_image_absolute_path(COLOR_GRASS300_IMAGE_FILENAME);
+        let absolute_palette_path = get_palette_absolute_path(PRIMARY_PALETTE_FILENAME);
+        let input_bytes = std::fs::read(&absolute_input_path).unwrap();
+
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("-v")
+            .arg("dither")
+            .arg("-i")
+            .arg("-")
+            .arg("-p")
+            .arg(&absolute_palette_path)
+            .arg("-o")
+            .arg("-")
+            .write_stdin(input_bytes);
+        let output = cmd.output();
+        assert!(output.is_ok());
+
+        let output = output.unwrap();
+        assert!(output.status.success(), "cmd output={output:?}.");
+
+        // Stdout contains only PNG image, verbose messages go to stderr
+        let processed_image = image::load_image_from_reader(output.stdout.as_slice());
+        assert!(processed_image.is_ok());
+        let processed_image = processed_image.unwrap();
+
+        let base_image = image::load_image(absolute_input_path).unwrap();
+        assert_eq!(base_image.dimensions(), processed_image.dimensions());
+
+        let palette_from_processed_image = PaletteRGB::from_rgbu8_image(&processed_image);
+        assert!(palette_from_processed_image.len() <= PaletteRGB::primary().len());
+    }
+
+    #[test]
+    fn test_palette_stdin_to_stdout() {
+        tests_setup();
+        let absolute_input_path = get_test_image_absolute_path(BNW_IMAGE_FILENAME);
+        let input_bytes = std::fs::read(&absolute_input_path).unwrap();
+
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("palette")
+            .arg("-i")
+            .arg("-")
+            .write_stdin(input_bytes);
+        let output = cmd.output().unwrap();
+        assert!(output.status.success(), "cmd output={output:?}.");
+
+        let palette = PaletteRGB::load_from_json_reader(output.stdout.as_slice()).unwrap();
+        assert_eq!(palette.len(), 2);
+    }
+
+    #[test]
+    fn test_algorithms_listing() {
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd.arg("algorithms");
+        let output = cmd.output().unwrap();
+        assert!(output.status.success());
+
+        let stdout_text = String::from_utf8_lossy(&output.stdout);
+        for algorithm in ProcessingAlgorithm::all() {
+            assert!(stdout_text.contains(algorithm.name()), "Missing {algorithm} in '{stdout_text}'");
+        }
+    }
+
+    #[test]
+    fn test_dither_algorithm_selection() {
+        // cargo test --test integration_tests test_dither_algorithm_selection -- --nocapture
+        tests_setup();
+        let absolute_input_path = get_test_image_absolute_path(COLOR_GRASS300_IMAGE_FILENAME);
+        let absolute_palette_path = get_palette_absolute_path(PRIMARY_PALETTE_FILENAME);
+        let palette = PaletteRGB::load_from_json(&absolute_palette_path).unwrap();
+
+        for algorithm in ProcessingAlgorithm::all() {
+            let absolute_output_path = get_test_save_absolute_path(format!("algorithm_{algorithm}_grass_image.png"));
+
+            let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+            cmd
+                .arg("dither")
+                .arg("-i")
+                .arg(&absolute_input_path)
+                .arg("-p")
+                .arg(&absolute_palette_path)
+                .arg("-a")
+                .arg(algorithm.name())
+                .arg("-o")
+                .arg(&absolute_output_path);
+            let output = cmd.output().unwrap();
+            assert!(output.status.success(), "cmd output={output:?}.");
+
+            let loaded_image = image::load_image(absolute_output_path).unwrap();
+            let palette_from_loaded_image = PaletteRGB::from_rgbu8_image(&loaded_image);
+            assert!(palette_from_loaded_image.iter().all(|color| palette.contains(color)), "{algorithm} produced colors outside palette");
+        }
+    }
+    
+
+    #[test]
+    fn test_compare_sheet_and_report() {
+        // cargo test --test integration_tests test_compare_sheet_and_report -- --nocapture
+        tests_setup();
+        let absolute_input_path = get_test_image_absolute_path(COLOR_GRASS300_IMAGE_FILENAME);
+        let absolute_palette_path = get_palette_absolute_path(PRIMARY_PALETTE_FILENAME);
+        let absolute_output_path = get_test_save_absolute_path("compare_grass_image.png");
+        let absolute_report_path = get_test_save_absolute_path("compare_grass_image.json");
+
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("compare")
+            .arg("-i")
+            .arg(&absolute_input_path)
+            .arg("-W")
+            .arg("64")
+            .arg("-H")
+            .arg("48")
+            .arg("-a")
+            .arg("fs-rgb,bayer4")
+            .arg("-p")
+            .arg(&absolute_palette_path)
+            .arg("-o")
+            .arg(&absolute_output_path);
+        let output = cmd.output().unwrap();
+        assert!(output.status.success(), "cmd output={output:?}.");
+
+        // Original and 2 algorithms in a single row
+        let sheet = image::load_image(&absolute_output_path).unwrap();
+        assert!(sheet.width() > 3 * 64);
+        assert!(sheet.height() > 48 && sheet.height() < 2 * 48);
+
+        let report: serde_json::Value = serde_json::from_reader(std::fs::File::open(&absolute_report_path).unwrap()).unwrap();
+        let results = report["results"].as_array().unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0]["algorithm"], "fs-rgb");
+        assert_eq!(results[1]["algorithm"], "bayer4");
+        assert!(results.iter().all(|result| result["duration_ms"].is_number() && result["mean_delta_e"].is_number()));
+    }
+    
+
+    #[test]
+    fn test_dither_resize_mode_fit_nearest() {
+        // cargo test --test integration_tests test_dither_resize_mode_fit_nearest -- --nocapture
+        tests_setup();
+        let absolute_input_path = get_test_image_absolute_path(COLOR_GRASS300_IMAGE_FILENAME);
+        let absolute_palette_path = get_palette_absolute_path(PRIMARY_PALETTE_FILENAME);
+        let source_image = load_test_image(COLOR_GRASS300_IMAGE_FILENAME);
+        // Source is roughly square, so it is fitted by height
+        let expected_width = (40.0 * source_image.width() as f32 / source_image.height() as f32).round() as u32;
+
+        for (resize_mode, expected_dimensions) in [("fit", (expected_width, 40)), ("stretch", (120, 40))] {
+            let absolute_output_path = get_test_save_absolute_path(format!("resize_{resize_mode}_grass_image.png"));
+
+            let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+            cmd
+                .arg("dither")
+                .arg("-i")
+                .arg(&absolute_input_path)
+                .arg("-p")
+                .arg(&absolute_palette_path)
+                .arg("-W")
+                .arg("120")
+                .arg("-H")
+                .arg("40")
+                .arg("--resize-mode")
+                .arg(resize_mode)
+                .arg("--filter")
+                .arg("nearest")
+                .arg("-o")
+                .arg(&absolute_output_path);
+            let output = cmd.output().unwrap();
+            assert!(output.status.success(), "cmd output={output:?}.");
+
+            let loaded_image = image::load_image(absolute_output_path).unwrap();
+            assert_eq!(loaded_image.dimensions(), expected_dimensions, "resize mode {resize_mode}");
+        }
+    }
+    
+
+    #[test]
+    fn test_dither_grayscale_preprocessing() {
+        // cargo test --test integration_tests test_dither_grayscale_preprocessing -- --nocapture
+        tests_setup();
+        let absolute_input_path = get_test_image_absolute_path(COLOR_PINK300_IMAGE_FILENAME);
+        let absolute_output_path = get_test_save_absolute_path("preprocessing_grayscale_pink_image.png");
+
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("dither")
+            .arg("-i")
+            .arg(&absolute_input_path)
+            .arg("-W")
+            .arg("100")
+            .arg("-c")
+            .arg("4")
+            .arg("--saturation")
+            .arg("0")
+            .arg("--contrast")
+            .arg("1.2")
+            .arg("--brightness")
+            .arg("-0.1")
+            .arg("--sharpen")
+            .arg("0.5")
+            .arg("-o")
+            .arg(&absolute_output_path);
+        let output = cmd.output().unwrap();
+        assert!(output.status.success(), "cmd output={output:?}.");
+
+        // Palette is reduced from adjusted image, so it is gray as well
+        let loaded_image = image::load_image(absolute_output_path).unwrap();
+        let palette_from_loaded_image = PaletteRGB::from_rgbu8_image(&loaded_image);
+        assert!(palette_from_loaded_image.len() <= 4);
+        assert!(palette_from_loaded_image.iter().all(|color| color.red().abs_diff(color.green()) <= 1 && color.green().abs_diff(color.blue()) <= 1));
+    }
+    
+
+    #[test]
+    fn test_dither_random_noise_seeded() {
+        // cargo test --test integration_tests test_dither_random_noise_seeded -- --nocapture
+        tests_setup();
+        let absolute_input_path = get_test_image_absolute_path(COLOR_PINK300_IMAGE_FILENAME);
+        // Fixed palette, reduced one could differ between runs
+        let absolute_palette_path = get_palette_absolute_path(PRIMARY_PALETTE_FILENAME);
+
+        let run_thresholding = |noise_args: &[&str], output_filename: &str| {
+            let absolute_output_path = get_test_save_absolute_path(output_filename);
+            let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+            cmd
+                .arg("dither")
+                .arg("-i")
+                .arg(&absolute_input_path)
+                .arg("-p")
+                .arg(&absolute_palette_path)
+                .arg("-a")
+                .arg("threshold-rgb")
+                .args(noise_args)
+                .arg("-o")
+                .arg(&absolute_output_path);
+            let output = cmd.output().unwrap();
+            assert!(output.status.success(), "cmd output={output:?}.");
+            image::load_image(absolute_output_path).unwrap()
+        };
+
+        let plain_image = run_thresholding(&[], "noise_none_pink_image.png");
+        let noisy_image = run_thresholding(&["--noise", "0.2", "--noise-seed", "3"], "noise_a_pink_image.png");
+        let noisy_again_image = run_thresholding(&["--noise", "0.2", "--noise-seed", "3"], "noise_b_pink_image.png");
+
+        assert!(plain_image != noisy_image, "Noise should change thresholding result");
+        assert!(noisy_image == noisy_again_image, "The same seed should give the same result");
+    }
+    
+
+    #[test]
+    fn test_dither_with_mask() {
+        // cargo test --test integration_tests test_dither_with_mask -- --nocapture
+        tests_setup();
+        let absolute_input_path = get_test_image_absolute_path(GRAY300_IMAGE_FILENAME);
+        let source_image = load_test_image(GRAY300_IMAGE_FILENAME);
+
+        // Gray levels are never equally distant from black and white, so thresholding is unambiguous
+        let absolute_palette_path = get_test_save_absolute_path("mask_black_and_white_palette.json");
+        PaletteRGB::black_and_white().save_to_json(&absolute_palette_path).unwrap();
+
+        // Left half is not dithered
+        let absolute_mask_path = get_test_save_absolute_path("mask_left_half_black.png");
+        let mask = ::image::GrayImage::from_fn(source_image.width(), source_image.height(), |x, _| {
+            if x < source_image.width() / 2 { ::image::Luma([0]) } else { ::image::Luma([255]) }
+        });
+        mask.save(&absolute_mask_path).unwrap();
+
+        let run_dithering = |algorithm: &str, mask_args: &[&std::path::Path], output_filename: &str| {
+            let absolute_output_path = get_test_save_absolute_path(output_filename);
+            let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+            cmd
+                .arg("dither")
+                .arg("-i")
+                .arg(&absolute_input_path)
+                .arg("-p")
+                .arg(&absolute_palette_path)
+                .arg("-a")
+                .arg(algorithm);
+            for mask_path in mask_args {
+                cmd.arg("--mask").arg(mask_path);
+            }
+            cmd.arg("-o").arg(&absolute_output_path);
+            let output = cmd.output().unwrap();
+            assert!(output.status.success(), "cmd output={output:?}.");
+            image::load_image(absolute_output_path).unwrap()
+        };
+
+        let masked_image = run_dithering("fs-rgb", &[&absolute_mask_path], "mask_fs_rgb_gray_image.png");
+        let thresholded_image = run_dithering("threshold-rgb", &[], "mask_threshold_rgb_gray_image.png");
+        let dithered_image = run_dithering("fs-rgb", &[], "mask_none_fs_rgb_gray_image.png");
+
+        let half_width = source_image.width() / 2;
+        let left_half_equal = |a: &::image::RgbImage, b: &::image::RgbImage| {
+            a.enumerate_pixels().filter(|(x, _, _)| *x < half_width).all(|(x, y, px)| px == b.get_pixel(x, y))
+        };
+        assert!(left_half_equal(&masked_image, &thresholded_image));
+        assert!(!left_half_equal(&dithered_image, &thresholded_image));
+    }
     
-}
\ No newline at end of file
+
+    #[test]
+    fn test_dither_transparent_key() {
+        // cargo test --test integration_tests test_dither_transparent_key -- --nocapture
+        tests_setup();
+        let key = Rgb([255, 0, 255]);
+
+        // Sprite on magenta background
+        let absolute_input_path = get_test_save_absolute_path("transparent_key_sprite.png");
+        let mut sprite = generate_test_gradient_image(32, 32, Rgb([20, 40, 60]), Rgb([240, 220, 200]));
+        sprite.enumerate_pixels_mut()
+            .filter(|(x, y, _)| *x < 8 || *y < 8)
+            .for_each(|(_, _, px)| *px = key);
+        sprite.save(&absolute_input_path).unwrap();
+
+        let absolute_output_path = get_test_save_absolute_path("transparent_key_sprite_dithered.png");
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("dither")
+            .arg("-i")
+            .arg(&absolute_input_path)
+            .arg("-c")
+            .arg("4")
+            .arg("--transparent-key")
+            .arg("#FF00ff")
+            .arg("-o")
+            .arg(&absolute_output_path);
+        let output = cmd.output().unwrap();
+        assert!(output.status.success(), "cmd output={output:?}.");
+
+        let loaded_image = image::load_image(absolute_output_path).unwrap();
+        for (x, y, px) in loaded_image.enumerate_pixels() {
+            assert_eq!(*px == key, x < 8 || y < 8, "Unexpected pixel {px:?} at x={x}, y={y}");
+        }
+    }
+
+    #[test]
+    fn test_dither_tiles_colors_limit() {
+        // cargo test --test integration_tests test_dither_tiles_colors_limit -- --nocapture
+        tests_setup();
+        let absolute_input_path = get_test_image_absolute_path(COLOR_PINK300_IMAGE_FILENAME);
+        let absolute_output_path = get_test_save_absolute_path("tiles_8x8_2.png");
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("dither")
+            .arg("-i")
+            .arg(&absolute_input_path)
+            .arg("-W")
+            .arg("100")
+            .arg("-c")
+            .arg("8")
+            .arg("--tiles")
+            .arg("8x8:2")
+            .arg("-o")
+            .arg(&absolute_output_path);
+        let output = cmd.output().unwrap();
+        assert!(output.status.success(), "cmd output={output:?}.");
+
+        let loaded_image = image::load_image(absolute_output_path).unwrap();
+        for tile_y in (0..loaded_image.height()).step_by(8) {
+            for tile_x in (0..loaded_image.width()).step_by(8) {
+                let tile = ::image::imageops::crop_imm(&loaded_image, tile_x, tile_y, 8, 8).to_image();
+                assert!(image::count_image_colors(&tile).len() <= 2, "Too many colors in tile at x={tile_x}, y={tile_y}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_dither_hardware_zx() {
+        // cargo test --test integration_tests test_dither_hardware_zx -- --nocapture
+        tests_setup();
+        let absolute_input_path = get_test_image_absolute_path(COLOR_PINK300_IMAGE_FILENAME);
+        let absolute_output_path = get_test_save_absolute_path("hardware_zx.png");
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("dither")
+            .arg("-i")
+            .arg(&absolute_input_path)
+            .arg("-W")
+            .arg("64")
+            .arg("-H")
+            .arg("48")
+            .arg("--hardware")
+            .arg("zx")
+            .arg("-o")
+            .arg(&absolute_output_path);
+        let output = cmd.output().unwrap();
+        assert!(output.status.success(), "cmd output={output:?}.");
+
+        let loaded_image = image::load_image(absolute_output_path).unwrap();
+        assert_eq!(loaded_image.dimensions(), (64, 48));
+        let zx_palette = ditherum::algorithms::hardware::HardwareProfile::ZxSpectrum.palette();
+        assert!(loaded_image.pixels().all(|px| zx_palette.contains(&ColorRGB::from_rgbu8(*px))));
+        for tile_y in (0..48).step_by(8) {
+            for tile_x in (0..64).step_by(8) {
+                let tile = ::image::imageops::crop_imm(&loaded_image, tile_x, tile_y, 8, 8).to_image();
+                assert!(image::count_image_colors(&tile).len() <= 2, "Too many colors in cell at x={tile_x}, y={tile_y}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_dither_levels_rgb332() {
+        // cargo test --test integration_tests test_dither_levels_rgb332 -- --nocapture
+        tests_setup();
+        let absolute_input_path = get_test_image_absolute_path(COLOR_PINK300_IMAGE_FILENAME);
+        let absolute_output_path = get_test_save_absolute_path("levels_rgb332.png");
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("dither")
+            .arg("-i")
+            .arg(&absolute_input_path)
+            .arg("-W")
+            .arg("64")
+            .arg("--levels")
+            .arg("rgb332")
+            .arg("-o")
+            .arg(&absolute_output_path);
+        let output = cmd.output().unwrap();
+        assert!(output.status.success(), "cmd output={output:?}.");
+
+        let loaded_image = image::load_image(absolute_output_path).unwrap();
+        let level_values = |levels: u32| (0..levels).map(|level| (level as f32 * 255.0 / (levels - 1) as f32).round() as u8).collect::<Vec<_>>();
+        let (red_green_values, blue_values) = (level_values(8), level_values(4));
+        assert!(loaded_image.pixels().all(|px| {
+            red_green_values.contains(&px.0[0]) && red_green_values.contains(&px.0[1]) && blue_values.contains(&px.0[2])
+        }));
+        assert!(image::count_image_colors(&loaded_image).len() > 8);
+    }
+
+    #[test]
+    fn test_dither_duotone() {
+        // cargo test --test integration_tests test_dither_duotone -- --nocapture
+        tests_setup();
+        let absolute_input_path = get_test_image_absolute_path(COLOR_PINK300_IMAGE_FILENAME);
+        let absolute_output_path = get_test_save_absolute_path("duotone_3_inks.png");
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("dither")
+            .arg("-i")
+            .arg(&absolute_input_path)
+            .arg("-W")
+            .arg("64")
+            .arg("-a")
+            .arg("duotone")
+            .arg("-c")
+            .arg("3")
+            .arg("-o")
+            .arg(&absolute_output_path);
+        let output = cmd.output().unwrap();
+        assert!(output.status.success(), "cmd output={output:?}.");
+
+        let loaded_image = image::load_image(absolute_output_path).unwrap();
+        let colors_count = image::count_image_colors(&loaded_image).len();
+        assert!((2..=3).contains(&colors_count), "colors_count={colors_count}");
+    }
+
+    #[test]
+    fn test_dither_bad_transparent_key() {
+        let absolute_input_path = get_test_image_absolute_path(BNW_IMAGE_FILENAME);
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("dither")
+            .arg("-i")
+            .arg(&absolute_input_path)
+            .arg("--transparent-key")
+            .arg("#ff00f");
+        let output = cmd.output().unwrap();
+        assert!(!output.status.success());
+        assert!(String::from_utf8_lossy(&output.stderr).contains("expected color like"));
+    }
+    
+
+    #[test]
+    fn test_dither_palette_image_strip() {
+        // cargo test --test integration_tests test_dither_palette_image_strip -- --nocapture
+        tests_setup();
+        let absolute_input_path = get_test_image_absolute_path(COLOR_PINK300_IMAGE_FILENAME);
+        let palette_path = get_test_save_absolute_path("palette_strip_8x.png");
+        let absolute_output_path = get_test_save_absolute_path("palette_strip_dithered.png");
+        let colors = [[20, 12, 28], [68, 36, 52], [208, 70, 72], [222, 238, 214]];
+        let strip = ::image::RgbImage::from_fn(32, 8, |x, _| ::image::Rgb(colors[x as usize / 8]));
+        image::save_image(&palette_path, &strip).unwrap();
+
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("dither")
+            .arg("-i")
+            .arg(&absolute_input_path)
+            .arg("-p")
+            .arg(&palette_path)
+            .arg("-o")
+            .arg(&absolute_output_path);
+        let output = cmd.output().unwrap();
+        assert!(output.status.success(), "cmd output={output:?}.");
+
+        let loaded_image = image::load_image(absolute_output_path).unwrap();
+        assert!(loaded_image.pixels().all(|px| colors.contains(&px.0)));
+    }
+
+    #[test]
+    fn test_dither_unsupported_palette_version() {
+        // cargo test --test integration_tests test_dither_unsupported_palette_version -- --nocapture
+        tests_setup();
+        let absolute_input_path = get_test_image_absolute_path(BNW_IMAGE_FILENAME);
+        let palette_path = get_test_save_absolute_path("future_version_palette.json");
+        std::fs::write(&palette_path, r#"{"version": 99, "colors": [[0, 0, 0]]}"#).unwrap();
+
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("dither")
+            .arg("-i")
+            .arg(&absolute_input_path)
+            .arg("-p")
+            .arg(&palette_path);
+        let output = cmd.output().unwrap();
+        assert_eq!(output.status.code(), Some(4), "cmd output={output:?}.");
+        assert!(String::from_utf8_lossy(&output.stderr).contains("version 99 is not supported"));
+    }
+
+    #[test]
+    fn test_palette_merge_and_remove() {
+        // cargo test --test integration_tests test_palette_merge_and_remove -- --nocapture
+        tests_setup();
+        let absolute_primary_path = get_palette_absolute_path(PRIMARY_PALETTE_FILENAME);
+        let absolute_almost_bw_path = get_test_save_absolute_path("merge_almost_bw_palette.json");
+        PaletteRGB::from(vec![ColorRGB([2, 2, 2]), ColorRGB([253, 253, 253])]).save_to_json(&absolute_almost_bw_path).unwrap();
+        let absolute_bw_path = get_test_save_absolute_path("merge_bw_palette.json");
+        PaletteRGB::black_and_white().save_to_json(&absolute_bw_path).unwrap();
+
+        let run_palette = |args: &[&str], output_filename: &str| {
+            let absolute_output_path = get_test_save_absolute_path(output_filename);
+            let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+            cmd
+                .arg("palette")
+                .arg("-i")
+                .arg(&absolute_primary_path)
+                .args(args)
+                .arg("-o")
+                .arg(&absolute_output_path);
+            let output = cmd.output().unwrap();
+            assert!(output.status.success(), "cmd output={output:?}.");
+            PaletteRGB::load_from_json(absolute_output_path).unwrap()
+        };
+
+        let bw_path = absolute_bw_path.to_str().unwrap();
+        let almost_bw_path = absolute_almost_bw_path.to_str().unwrap();
+
+        // Exact merge keeps nearly identical colors, tolerant one skips them
+        let merged_palette = run_palette(&["--merge", &format!("{bw_path},{almost_bw_path}")], "merge_exact_palette.json");
+        assert_eq!(merged_palette.len(), 7);
+        let merged_palette = run_palette(&["--merge", &format!("{bw_path},{almost_bw_path}"), "--tolerance", "2"], "merge_tolerant_palette.json");
+        assert_eq!(merged_palette, PaletteRGB::primary_bw());
+
+        // Clustering merges nearly identical colors into centroids
+        let merged_palette = run_palette(&["--merge", &format!("{bw_path},{almost_bw_path}"), "--dedup", "2"], "merge_dedup_palette.json");
+        assert_eq!(merged_palette.len(), 5);
+
+        // Removing everything from palette fails
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("palette")
+            .arg("-i")
+            .arg(&absolute_primary_path)
+            .arg("--remove")
+            .arg(&absolute_primary_path)
+            .arg("-o")
+            .arg(get_test_save_absolute_path("merge_empty_palette.json"));
+        let output = cmd.output().unwrap();
+        assert!(!output.status.success());
+    }
+    
+
+    #[test]
+    fn test_palette_keep_colors() {
+        // cargo test --test integration_tests test_palette_keep_colors -- --nocapture
+        tests_setup();
+        let absolute_input_path = get_test_image_absolute_path(COLOR_GRASS300_IMAGE_FILENAME);
+        let absolute_output_path = get_test_save_absolute_path("keep_colors_palette.json");
+
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("palette")
+            .arg("-i")
+            .arg(&absolute_input_path)
+            .arg("-c")
+            .arg("6")
+            .arg("--keep")
+            .arg("#000000,#ffffff,#ff00ff")
+            .arg("-o")
+            .arg(&absolute_output_path);
+        let output = cmd.output().unwrap();
+        assert!(output.status.success(), "cmd output={output:?}.");
+
+        let palette = PaletteRGB::load_from_json(absolute_output_path).unwrap();
+        assert_eq!(palette.len(), 6);
+        assert!(palette.contains(&ColorRGB([0, 0, 0])));
+        assert!(palette.contains(&ColorRGB([255, 255, 255])));
+        assert!(palette.contains(&ColorRGB([255, 0, 255])));
+    }
+    
+
+    #[test]
+    fn test_dither_named_colors_palette() {
+        // cargo test --test integration_tests test_dither_named_colors_palette -- --nocapture
+        tests_setup();
+        let absolute_input_path = get_test_image_absolute_path(COLOR_PINK300_IMAGE_FILENAME);
+        let palette_path = get_test_save_absolute_path("named_colors_palette.json");
+        let absolute_output_path = get_test_save_absolute_path("named_colors_dithered.png");
+        std::fs::write(&palette_path, r##"["rebeccapurple", "steelblue", {"name": "paper", "rgb": [250, 245, 230]}]"##).unwrap();
+
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("dither")
+            .arg("-i")
+            .arg(&absolute_input_path)
+            .arg("-p")
+            .arg(&palette_path)
+            .arg("--transparent-key")
+            .arg("magenta")
+            .arg("-o")
+            .arg(&absolute_output_path);
+        let output = cmd.output().unwrap();
+        assert!(output.status.success(), "cmd output={output:?}.");
+
+        let palette = PaletteRGB::load_from_json(&palette_path).unwrap();
+        assert_eq!(palette.color_name(&ColorRGB([70, 130, 180])), Some("steelblue"));
+        let loaded_image = image::load_image(absolute_output_path).unwrap();
+        assert!(loaded_image.pixels().all(|px| palette.contains(&ColorRGB(px.0))));
+    }
+
+    #[test]
+    fn test_dither_ostromoukhov() {
+        // cargo test --test integration_tests test_dither_ostromoukhov -- --nocapture
+        tests_setup();
+        let absolute_input_path = get_test_image_absolute_path(BNW_IMAGE_FILENAME);
+        let absolute_output_path = get_test_save_absolute_path("ostromoukhov_bnw.png");
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("dither")
+            .arg("-i")
+            .arg(&absolute_input_path)
+            .arg("-W")
+            .arg("64")
+            .arg("-a")
+            .arg("ostromoukhov")
+            .arg("-c")
+            .arg("2")
+            .arg("-o")
+            .arg(&absolute_output_path);
+        let output = cmd.output().unwrap();
+        assert!(output.status.success(), "cmd output={output:?}.");
+
+        let loaded_image = image::load_image(absolute_output_path).unwrap();
+        assert_eq!(loaded_image.width(), 64);
+        assert!(image::count_image_colors(&loaded_image).len() <= 2);
+    }
+
+    #[test]
+    fn test_dither_dot_diffusion() {
+        // cargo test --test integration_tests test_dither_dot_diffusion -- --nocapture
+        tests_setup();
+        let absolute_input_path = get_test_image_absolute_path(COLOR_PINK300_IMAGE_FILENAME);
+        let absolute_output_path = get_test_save_absolute_path("dot_diffusion_8.png");
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("dither")
+            .arg("-i")
+            .arg(&absolute_input_path)
+            .arg("-W")
+            .arg("64")
+            .arg("-a")
+            .arg("dot-diffusion")
+            .arg("-c")
+            .arg("8")
+            .arg("-o")
+            .arg(&absolute_output_path);
+        let output = cmd.output().unwrap();
+        assert!(output.status.success(), "cmd output={output:?}.");
+
+        let loaded_image = image::load_image(absolute_output_path).unwrap();
+        let colors_count = image::count_image_colors(&loaded_image).len();
+        assert!((2..=8).contains(&colors_count), "colors_count={colors_count}");
+    }
+
+    #[test]
+    fn test_dither_two_color_mix() {
+        // cargo test --test integration_tests test_dither_two_color_mix -- --nocapture
+        tests_setup();
+        let absolute_input_path = get_test_image_absolute_path(COLOR_PINK300_IMAGE_FILENAME);
+        let absolute_output_path = get_test_save_absolute_path("two_color_mix_4.png");
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("dither")
+            .arg("-i")
+            .arg(&absolute_input_path)
+            .arg("-W")
+            .arg("64")
+            .arg("-a")
+            .arg("mix")
+            .arg("-c")
+            .arg("4")
+            .arg("-o")
+            .arg(&absolute_output_path);
+        let output = cmd.output().unwrap();
+        assert!(output.status.success(), "cmd output={output:?}.");
+
+        let loaded_image = image::load_image(absolute_output_path).unwrap();
+        let colors_count = image::count_image_colors(&loaded_image).len();
+        assert!((2..=4).contains(&colors_count), "colors_count={colors_count}");
+    }
+
+    #[test]
+    fn test_dither_error_decay() {
+        // cargo test --test integration_tests test_dither_error_decay -- --nocapture
+        tests_setup();
+        let absolute_input_path = get_test_image_absolute_path(COLOR_PINK300_IMAGE_FILENAME);
+        let decayed_output_path = get_test_save_absolute_path("error_decay_zero.png");
+        let thresholded_output_path = get_test_save_absolute_path("error_decay_thresholded.png");
+        let palette_path = get_test_save_absolute_path("error_decay_palette.json");
+        std::fs::write(&palette_path, r#"["black", "white"]"#).unwrap();
+
+        let run_dither = |algorithm: &str, extra_args: &[&str], output_path: &std::path::Path| {
+            let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+            cmd
+                .arg("dither")
+                .arg("-i")
+                .arg(&absolute_input_path)
+                .arg("-W")
+                .arg("64")
+                .arg("-p")
+                .arg(&palette_path)
+                .arg("-a")
+                .arg(algorithm)
+                .args(extra_args)
+                .arg("-o")
+                .arg(output_path);
+            cmd.output().unwrap()
+        };
+
+        // Fully decayed error is not spread, so Floyd-Steinberg gives plain thresholding
+        let output = run_dither("fs-rgb", &["--error-decay", "0"], &decayed_output_path);
+        assert!(output.status.success(), "cmd output={output:?}.");
+        let output = run_dither("threshold-rgb", &[], &thresholded_output_path);
+        assert!(output.status.success(), "cmd output={output:?}.");
+        assert_eq!(image::load_image(decayed_output_path).unwrap(), image::load_image(thresholded_output_path).unwrap());
+
+        let output = run_dither("fs-rgb", &["--error-overshoot", "0.2", "--no-error-clamp"], &get_test_save_absolute_path("error_clamp_conflict.png"));
+        assert!(!output.status.success(), "cmd output={output:?}.");
+    }
+
+    #[test]
+    fn test_dither_edge_attenuation() {
+        // cargo test --test integration_tests test_dither_edge_attenuation -- --nocapture
+        tests_setup();
+        let absolute_input_path = get_test_save_absolute_path("edge_attenuation_input.png");
+        let absolute_output_path = get_test_save_absolute_path("edge_attenuation_dithered.png");
+        let palette_path = get_test_save_absolute_path("edge_attenuation_palette.json");
+        std::fs::write(&palette_path, r#"["black", "white"]"#).unwrap();
+
+        // Light gray page with black text line, error of the page must not leak into the line
+        let source_image = ::image::RgbImage::from_fn(32, 32, |_, y| if (12..14).contains(&y) { ::image::Rgb([0, 0, 0]) } else { ::image::Rgb([150, 150, 150]) });
+        image::save_image(&absolute_input_path, &source_image).unwrap();
+
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("dither")
+            .arg("-i")
+            .arg(&absolute_input_path)
+            .arg("-p")
+            .arg(&palette_path)
+            .arg("--edge-attenuation")
+            .arg("1")
+            .arg("-o")
+            .arg(&absolute_output_path);
+        let output = cmd.output().unwrap();
+        assert!(output.status.success(), "cmd output={output:?}.");
+
+        let loaded_image = image::load_image(absolute_output_path).unwrap();
+        assert!((0..32).all(|x| loaded_image.get_pixel(x, 12).0 == [0, 0, 0] && loaded_image.get_pixel(x, 13).0 == [0, 0, 0]));
+    }
+
+    #[test]
+    fn test_dither_hybrid() {
+        // cargo test --test integration_tests test_dither_hybrid -- --nocapture
+        tests_setup();
+        let absolute_input_path = get_test_save_absolute_path("hybrid_input.png");
+        let absolute_output_path = get_test_save_absolute_path("hybrid_dithered.png");
+        let palette_path = get_test_save_absolute_path("hybrid_palette.json");
+        std::fs::write(&palette_path, r#"["black", "white"]"#).unwrap();
+
+        // Flat gray panel next to noisy photo-like gradient
+        let source_image = ::image::RgbImage::from_fn(64, 32, |x, y| {
+            if x < 32 {
+                ::image::Rgb([150, 150, 150])
+            } else {
+                let level = 60 + (x - 32) as u8 * 4 + ((x * 37 + y * 91) % 23) as u8;
+                ::image::Rgb([level, level, level])
+            }
+        });
+        image::save_image(&absolute_input_path, &source_image).unwrap();
+
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("dither")
+            .arg("-i")
+            .arg(&absolute_input_path)
+            .arg("-p")
+            .arg(&palette_path)
+            .arg("--hybrid")
+            .arg("-o")
+            .arg(&absolute_output_path);
+        let output = cmd.output().unwrap();
+        assert!(output.status.success(), "cmd output={output:?}.");
+
+        let loaded_image = image::load_image(absolute_output_path).unwrap();
+        let panel_colors = (0..24).flat_map(|x| (0..32).map(move |y| (x, y)))
+            .map(|(x, y)| loaded_image.get_pixel(x, y).0)
+            .collect::<std::collections::HashSet<_>>();
+        let photo_colors = (40..64).flat_map(|x| (0..32).map(move |y| (x, y)))
+            .map(|(x, y)| loaded_image.get_pixel(x, y).0)
+            .collect::<std::collections::HashSet<_>>();
+        assert_eq!(panel_colors.len(), 1);
+        assert_eq!(photo_colors.len(), 2);
+    }
+
+    #[test]
+    fn test_dither_threads() {
+        // cargo test --test integration_tests test_dither_threads -- --nocapture
+        tests_setup();
+        let absolute_input_path = get_test_image_absolute_path(COLOR_PINK300_IMAGE_FILENAME);
+        let absolute_output_path = get_test_save_absolute_path("threads_dithered.png");
+
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("dither")
+            .arg("-i")
+            .arg(&absolute_input_path)
+            .arg("-c")
+            .arg("4")
+            .arg("--threads")
+            .arg("1")
+            .arg("-o")
+            .arg(&absolute_output_path);
+        let output = cmd.output().unwrap();
+        assert!(output.status.success(), "cmd output={output:?}.");
+
+        let loaded_image = image::load_image(absolute_output_path).unwrap();
+        assert!(image::count_image_colors(&loaded_image).len() <= 4);
+
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("dither")
+            .arg("-i")
+            .arg(&absolute_input_path)
+            .arg("--threads")
+            .arg("0");
+        cmd.assert().failure();
+    }
+
+    #[test]
+    fn test_dither_luminance_band() {
+        // cargo test --test integration_tests test_dither_luminance_band -- --nocapture
+        tests_setup();
+        let absolute_input_path = get_test_save_absolute_path("luminance_band_input.png");
+        let palette_path = get_test_save_absolute_path("luminance_band_palette.json");
+        let absolute_output_path = get_test_save_absolute_path("luminance_band_dithered.png");
+        image::save_image(&absolute_input_path, &::image::RgbImage::from_pixel(16, 16, ::image::Rgb([110, 10, 10]))).unwrap();
+        std::fs::write(&palette_path, r##"["black", [230, 90, 90], "white"]"##).unwrap();
+
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("dither")
+            .arg("-i")
+            .arg(&absolute_input_path)
+            .arg("-p")
+            .arg(&palette_path)
+            .arg("-a")
+            .arg("threshold-lab")
+            .arg("--luminance-band")
+            .arg("40")
+            .arg("-o")
+            .arg(&absolute_output_path);
+        let output = cmd.output().unwrap();
+        assert!(output.status.success(), "cmd output={output:?}.");
+
+        let loaded_image = image::load_image(absolute_output_path).unwrap();
+        assert!(loaded_image.pixels().all(|px| px.0 == [230, 90, 90]));
+    }
+
+    #[test]
+    fn test_palette_locked_entries() {
+        // cargo test --test integration_tests test_palette_locked_entries -- --nocapture
+        tests_setup();
+        let input_path = get_test_save_absolute_path("locked_entries_input_palette.json");
+        let absolute_output_path = get_test_save_absolute_path("locked_entries_palette.json");
+        let mut entries = (0..12u8).map(|idx| format!("[{}, {}, 90]", idx * 20, 250 - idx * 20)).collect::<Vec<_>>();
+        entries.push(r#"{"rgb": [250, 245, 230], "name": "paper", "count": 5, "locked": true}"#.to_string());
+        std::fs::write(&input_path, format!("[{}]", entries.join(", "))).unwrap();
+
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("palette")
+            .arg("-i")
+            .arg(&input_path)
+            .arg("-c")
+            .arg("3")
+            .arg("-o")
+            .arg(&absolute_output_path);
+        let output = cmd.output().unwrap();
+        assert!(output.status.success(), "cmd output={output:?}.");
+
+        let palette = PaletteRGB::load_from_json(absolute_output_path).unwrap();
+        assert_eq!(palette.len(), 3);
+        let paper = palette.entries().into_iter().find(|entry| entry.color == ColorRGB([250, 245, 230])).unwrap();
+        assert_eq!((paper.name.as_deref(), paper.count, paper.locked), (Some("paper"), Some(5), true));
+    }
+
+    #[test]
+    fn test_palette_min_delta_e() {
+        // cargo test --test integration_tests test_palette_min_delta_e -- --nocapture
+        tests_setup();
+        let absolute_input_path = get_test_image_absolute_path(COLOR_GRASS300_IMAGE_FILENAME);
+        let absolute_output_path = get_test_save_absolute_path("min_delta_e_palette.json");
+
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("palette")
+            .arg("-i")
+            .arg(&absolute_input_path)
+            .arg("-c")
+            .arg("16")
+            .arg("--min-delta-e")
+            .arg("10")
+            .arg("-o")
+            .arg(&absolute_output_path);
+        let output = cmd.output().unwrap();
+        assert!(output.status.success(), "cmd output={output:?}.");
+
+        let palette = PaletteRGB::load_from_json(absolute_output_path).unwrap();
+        assert!(!palette.is_empty() && palette.len() <= 16);
+        for (idx, color) in palette.iter().enumerate() {
+            assert!(palette[idx + 1..].iter().all(|other| color.dist_by_lab(other) > 10.0));
+        }
+    }
+    
+
+    #[test]
+    fn test_palette_agglomerative_reduction() {
+        // cargo test --test integration_tests test_palette_agglomerative_reduction -- --nocapture
+        tests_setup();
+        let absolute_input_path = get_test_save_absolute_path("agglomerative_input_palette.json");
+        PaletteRGB::grayscale(12).save_to_json(&absolute_input_path).unwrap();
+
+        let reduce = |output_filename: &str| {
+            let absolute_output_path = get_test_save_absolute_path(output_filename);
+            let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+            cmd
+                .arg("palette")
+                .arg("-i")
+                .arg(&absolute_input_path)
+                .arg("-c")
+                .arg("4")
+                .arg("--reduce-strategy")
+                .arg("agglomerative")
+                .arg("-o")
+                .arg(&absolute_output_path);
+            let output = cmd.output().unwrap();
+            assert!(output.status.success(), "cmd output={output:?}.");
+            PaletteRGB::load_from_json(absolute_output_path).unwrap()
+        };
+
+        // Reduction is deterministic
+        let palette = reduce("agglomerative_palette_a.json");
+        assert_eq!(palette.len(), 4);
+        assert_eq!(palette, reduce("agglomerative_palette_b.json"));
+    }
+
+    #[test]
+    fn test_palette_reduction_mapping() {
+        // cargo test --test integration_tests test_palette_reduction_mapping -- --nocapture
+        tests_setup();
+        let absolute_input_path = get_test_save_absolute_path("mapping_input_palette.json");
+        let absolute_output_path = get_test_save_absolute_path("mapping_palette.json");
+        let absolute_mapping_path = get_test_save_absolute_path("mapping.json");
+        PaletteRGB::grayscale(12).save_to_json(&absolute_input_path).unwrap();
+
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("palette")
+            .arg("-i")
+            .arg(&absolute_input_path)
+            .arg("-c")
+            .arg("3")
+            .arg("--mapping")
+            .arg(&absolute_mapping_path)
+            .arg("-o")
+            .arg(&absolute_output_path);
+        let output = cmd.output().unwrap();
+        assert!(output.status.success(), "cmd output={output:?}.");
+
+        let palette = PaletteRGB::load_from_json(absolute_output_path).unwrap();
+        let mapping: std::collections::HashMap<String, String> = serde_json::from_str(&std::fs::read_to_string(absolute_mapping_path).unwrap()).unwrap();
+        assert_eq!(mapping.len(), 12);
+        assert!(mapping.values().all(|target| palette.iter().any(|color| color.to_hex() == *target)));
+    }
+
+    #[test]
+    fn test_dither_pixelate() {
+        // cargo test --test integration_tests test_dither_pixelate -- --nocapture
+        tests_setup();
+        let absolute_input_path = get_test_image_absolute_path(COLOR_PINK300_IMAGE_FILENAME);
+        let absolute_output_path = get_test_save_absolute_path("pixelated.png");
+
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("dither")
+            .arg("-i")
+            .arg(&absolute_input_path)
+            .arg("-c")
+            .arg("6")
+            .arg("--pixelate")
+            .arg("10")
+            .arg("-o")
+            .arg(&absolute_output_path);
+        let output = cmd.output().unwrap();
+        assert!(output.status.success(), "cmd output={output:?}.");
+
+        let source = image::load_image(absolute_input_path).unwrap();
+        let pixelated = image::load_image(absolute_output_path).unwrap();
+        assert_eq!(pixelated.dimensions(), source.dimensions());
+        assert!(image::count_image_colors(&pixelated).len() <= 6);
+        assert!(pixelated.enumerate_pixels().all(|(x, y, px)| px == pixelated.get_pixel(x - x % 10, y - y % 10)));
+    }
+
+    #[test]
+    fn test_dither_despeckle() {
+        // cargo test --test integration_tests test_dither_despeckle -- --nocapture
+        tests_setup();
+        let absolute_input_path = get_test_image_absolute_path(COLOR_PINK300_IMAGE_FILENAME);
+        let absolute_output_path = get_test_save_absolute_path("despeckled.png");
+        let absolute_palette_path = get_test_save_absolute_path("despeckled_palette.json");
+        let min_size = 4;
+
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("dither")
+            .arg("-i")
+            .arg(&absolute_input_path)
+            .arg("-c")
+            .arg("4")
+            .arg("--despeckle")
+            .arg(min_size.to_string())
+            .arg("-r")
+            .arg(&absolute_palette_path)
+            .arg("-o")
+            .arg(&absolute_output_path);
+        let output = cmd.output().unwrap();
+        assert!(output.status.success(), "cmd output={output:?}.");
+
+        // Despeckling is idempotent only if no island below threshold is left
+        let despeckled = image::load_image(absolute_output_path).unwrap();
+        let palette = PaletteRGB::load_from_json(absolute_palette_path).unwrap();
+        assert!(image::count_image_colors(&despeckled).keys().all(|color| palette.contains(&ColorRGB(color.0))));
+        assert_eq!(ditherum::algorithms::despeckle::despeckle(&despeckled, min_size), despeckled);
+    }
+
+    #[test]
+    fn test_dither_outline() {
+        // cargo test --test integration_tests test_dither_outline -- --nocapture
+        tests_setup();
+        let absolute_input_path = get_test_image_absolute_path(COLOR_GRASS300_IMAGE_FILENAME);
+        let absolute_output_path = get_test_save_absolute_path("outlined.png");
+        let absolute_palette_path = get_test_save_absolute_path("outlined_palette.json");
+
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("dither")
+            .arg("-i")
+            .arg(&absolute_input_path)
+            .arg("-c")
+            .arg("4")
+            .arg("-a")
+            .arg("threshold-lab")
+            .arg("--outline")
+            .arg("#ff00ff")
+            .arg("-r")
+            .arg(&absolute_palette_path)
+            .arg("-o")
+            .arg(&absolute_output_path);
+        let output = cmd.output().unwrap();
+        assert!(output.status.success(), "cmd output={output:?}.");
+
+        let outlined = image::load_image(absolute_output_path).unwrap();
+        let colors = image::count_image_colors(&outlined);
+        assert!(colors.contains_key(&Rgb([255, 0, 255])));
+        assert!(colors.len() <= 5);
+        let palette = PaletteRGB::load_from_json(absolute_palette_path).unwrap();
+        assert!(palette.contains(&ColorRGB([255, 0, 255])));
+    }
+
+    #[test]
+    fn test_remap() {
+        // cargo test --test integration_tests test_remap -- --nocapture
+        tests_setup();
+        let absolute_dithered_path = get_test_save_absolute_path("remap_input.png");
+        let absolute_from_path = get_test_save_absolute_path("remap_from_palette.json");
+        let absolute_to_path = get_test_save_absolute_path("remap_to_palette.json");
+        let absolute_output_path = get_test_save_absolute_path("remap_output.png");
+        let from_palette = PaletteRGB::black_and_white();
+        let to_palette = PaletteRGB::from(vec![ColorRGB([20, 30, 90]), ColorRGB([240, 220, 120])]);
+        from_palette.save_to_json(&absolute_from_path).unwrap();
+        to_palette.save_to_json(&absolute_to_path).unwrap();
+
+        let dithered = ::image::RgbImage::from_fn(32, 32, |x, y| from_palette[((x + y) % 2) as usize].into());
+        image::save_image(&absolute_dithered_path, &dithered).unwrap();
+
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("remap")
+            .arg("-i")
+            .arg(&absolute_dithered_path)
+            .arg("--from")
+            .arg(&absolute_from_path)
+            .arg("--to")
+            .arg(&absolute_to_path)
+            .arg("--metric")
+            .arg("index")
+            .arg("-o")
+            .arg(&absolute_output_path);
+        let output = cmd.output().unwrap();
+        assert!(output.status.success(), "cmd output={output:?}.");
+
+        let remapped = image::load_image(absolute_output_path).unwrap();
+        let colors = image::count_image_colors(&remapped);
+        assert_eq!(colors.len(), 2);
+        assert!(colors.keys().all(|color| to_palette.iter().any(|target| target.0 == color.0)));
+    }
+
+    #[test]
+    fn test_auto_colors() {
+        // cargo test --test integration_tests test_auto_colors -- --nocapture
+        tests_setup();
+        let absolute_input_path = get_test_image_absolute_path(COLOR_PINK300_IMAGE_FILENAME);
+        let absolute_palette_path = get_test_save_absolute_path("auto_colors_palette.json");
+        let absolute_output_path = get_test_save_absolute_path("auto_colors_dithered.png");
+
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("palette")
+            .arg("-i")
+            .arg(&absolute_input_path)
+            .arg("--auto-colors")
+            .arg("--max-error")
+            .arg("100")
+            .arg("-o")
+            .arg(&absolute_palette_path);
+        let output = cmd.output().unwrap();
+        assert!(output.status.success(), "cmd output={output:?}.");
+        assert_eq!(PaletteRGB::load_from_json(&absolute_palette_path).unwrap().len(), 1);
+
+        // Quality cannot be measured without image
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("palette")
+            .arg("-i")
+            .arg(&absolute_palette_path)
+            .arg("--auto-colors");
+        let output = cmd.output().unwrap();
+        assert!(!output.status.success());
+        assert!(String::from_utf8_lossy(&output.stderr).contains("requires image input"));
+
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("dither")
+            .arg("-i")
+            .arg(&absolute_input_path)
+            .arg("-W")
+            .arg("32")
+            .arg("--auto-colors")
+            .arg("--max-error")
+            .arg("8")
+            .arg("-o")
+            .arg(&absolute_output_path);
+        let output = cmd.output().unwrap();
+        assert!(output.status.success(), "cmd output={output:?}.");
+        assert!(absolute_output_path.exists());
+    }
+    
+
+    #[test]
+    fn test_palette_sampling() {
+        // cargo test --test integration_tests test_palette_sampling -- --nocapture
+        tests_setup();
+        let absolute_input_path = get_test_image_absolute_path(COLOR_PINK300_IMAGE_FILENAME);
+        let full_palette = PaletteRGB::from_rgbu8_image(&ditherum::image::load_image(&absolute_input_path).unwrap());
+
+        for (sampling, output_filename) in [("histogram", "sampling_histogram_palette.json"), ("random:500:1", "sampling_random_palette.json")] {
+            let absolute_output_path = get_test_save_absolute_path(output_filename);
+            let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+            cmd
+                .arg("palette")
+                .arg("-i")
+                .arg(&absolute_input_path)
+                .arg("--sampling")
+                .arg(sampling)
+                .arg("-o")
+                .arg(&absolute_output_path);
+            let output = cmd.output().unwrap();
+            assert!(output.status.success(), "cmd output={output:?}.");
+
+            let palette = PaletteRGB::load_from_json(absolute_output_path).unwrap();
+            assert!(!palette.is_empty() && palette.len() < full_palette.len(), "{sampling} gave {} colors", palette.len());
+        }
+    }
+    
+
+    #[test]
+    fn test_dither_ignore_green_screen() {
+        // cargo test --test integration_tests test_dither_ignore_green_screen -- --nocapture
+        tests_setup();
+        let absolute_input_path = get_test_save_absolute_path("green_screen_input.png");
+        let absolute_output_path = get_test_save_absolute_path("green_screen_ignored.png");
+        let mut source_image = ditherum::image::generate_test_gradient_image(32, 16, Rgb([0, 0, 0]), Rgb([255, 255, 255]));
+        for (x, y, pixel) in source_image.enumerate_pixels_mut() {
+            if y < 8 {
+                *pixel = Rgb([(x % 3) as u8, 255 - (x % 4) as u8, 0]);
+            }
+        }
+        source_image.save(&absolute_input_path).unwrap();
+
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("dither")
+            .arg("-i")
+            .arg(&absolute_input_path)
+            .arg("--ignore")
+            .arg("#00ff00:5")
+            .arg("-c")
+            .arg("4")
+            .arg("-o")
+            .arg(&absolute_output_path);
+        let output = cmd.output().unwrap();
+        assert!(output.status.success(), "cmd output={output:?}.");
+
+        let processed_image = image::load_image(&absolute_output_path).unwrap();
+        assert!(processed_image.pixels().all(|px| px.0[1] < 200 || px.0[0] > 100), "green left in palette");
+    }
+    
+
+    #[test]
+    fn test_palette_region_of_interest() {
+        // cargo test --test integration_tests test_palette_region_of_interest -- --nocapture
+        tests_setup();
+        let absolute_input_path = get_test_image_absolute_path(COLOR_PINK300_IMAGE_FILENAME);
+        let source_image = ditherum::image::load_image(&absolute_input_path).unwrap();
+        let absolute_output_path = get_test_save_absolute_path("roi_palette.json");
+
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("palette")
+            .arg("-i")
+            .arg(&absolute_input_path)
+            .arg("--roi")
+            .arg("10,10,20,20")
+            .arg("-o")
+            .arg(&absolute_output_path);
+        let output = cmd.output().unwrap();
+        assert!(output.status.success(), "cmd output={output:?}.");
+
+        let palette = PaletteRGB::load_from_json(&absolute_output_path).unwrap();
+        let region_palette = PaletteRGB::from_rgbu8_image(&::image::imageops::crop_imm(&source_image, 10, 10, 20, 20).to_image());
+        assert_eq!(palette, region_palette);
+
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("palette")
+            .arg("-i")
+            .arg(&absolute_input_path)
+            .arg("--roi")
+            .arg(format!("{},0,10,10", source_image.width()))
+            .arg("-o")
+            .arg(&absolute_output_path);
+        let output = cmd.output().unwrap();
+        assert_eq!(output.status.code(), Some(3), "cmd output={output:?}.");
+    }
+    
+
+    #[test]
+    fn test_error_exit_codes_and_json_format() {
+        // cargo test --test integration_tests test_error_exit_codes_and_json_format -- --nocapture
+        tests_setup();
+        let absolute_unsupported_path = get_test_save_absolute_path("unsupported_format.xyz");
+        std::fs::write(&absolute_unsupported_path, "not an image").unwrap();
+
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("dither")
+            .arg("-i")
+            .arg(&absolute_unsupported_path);
+        let output = cmd.output().unwrap();
+        assert_eq!(output.status.code(), Some(4));
+
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("--error-format")
+            .arg("json")
+            .arg("dither")
+            .arg("-i")
+            .arg(get_test_image_absolute_path("not_existing_image.png"));
+        let output = cmd.output().unwrap();
+        assert_eq!(output.status.code(), Some(3));
+
+        let report: serde_json::Value = serde_json::from_slice(&output.stderr).unwrap();
+        assert_eq!(report["kind"], "bad_input");
+        assert_eq!(report["exit_code"], 3);
+        assert!(report["message"].as_str().unwrap().contains("os error 2"));
+    }
+    
+
+    #[test]
+    fn test_info_json() {
+        // cargo test --test integration_tests test_info_json -- --nocapture
+        let absolute_input_path = get_test_image_absolute_path(BNW_IMAGE_FILENAME);
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("info")
+            .arg("-i")
+            .arg(&absolute_input_path)
+            .arg("--json");
+        let output = cmd.output().unwrap();
+        assert!(output.status.success(), "cmd output={output:?}.");
+
+        let info: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(info["unique_colors"], 2);
+        assert_eq!(info["recommended_colors"], 2);
+        let dominant_colors = info["dominant_colors"].as_array().unwrap();
+        assert_eq!(dominant_colors.len(), 2);
+        let shares_sum = dominant_colors.iter().map(|dominant| dominant["share"].as_f64().unwrap()).sum::<f64>();
+        assert!((shares_sum - 100.0).abs() < 0.01);
+        assert!(dominant_colors.iter().any(|dominant| dominant["color"] == "#000000"));
+    }
+
+    #[test]
+    fn test_preview_renders_half_blocks() {
+        // cargo test --test integration_tests test_preview_renders_half_blocks -- --nocapture
+        let absolute_input_path = get_test_image_absolute_path(BNW_IMAGE_FILENAME);
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("preview")
+            .arg("-i")
+            .arg(&absolute_input_path)
+            .arg("-W")
+            .arg("16")
+            .arg("-c")
+            .arg("2");
+        let output = cmd.output().unwrap();
+        assert!(output.status.success(), "cmd output={output:?}.");
+
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let first_line = stdout.lines().next().unwrap();
+        assert_eq!(first_line.matches('\u{2580}').count(), 16);
+    }
+    
+
+    #[test]
+    fn test_run_pipeline_config() {
+        // cargo test --test integration_tests test_run_pipeline_config -- --nocapture
+        tests_setup();
+        let absolute_config_path = get_test_save_absolute_path("pipeline.toml");
+        let config = format!(r##"
+            algorithm = "bayer4"
+
+            [[images]]
+            input = {:?}
+            output = "pipeline_out/bnw.png"
+
+            [[images]]
+            input = {:?}
+            output = "pipeline_out/gray.png"
+
+            [resize]
+            width = 32
+
+            [palette]
+            source = "reduce"
+            colors = 2
+            keep = ["#000000"]
+            shared = true
+            output = "pipeline_palette.json"
+        "##,
+            get_test_image_absolute_path(BNW_IMAGE_FILENAME),
+            get_test_image_absolute_path(GRAY300_IMAGE_FILENAME)
+        );
+        std::fs::write(&absolute_config_path, config).unwrap();
+
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("run")
+            .arg(&absolute_config_path);
+        let output = cmd.output().unwrap();
+        assert!(output.status.success(), "cmd output={output:?}.");
+
+        // Relative paths are resolved against config directory
+        for output_filename in ["pipeline_out/bnw.png", "pipeline_out/gray.png"] {
+            let processed_image = ditherum::image::load_image(get_test_save_absolute_path(output_filename)).unwrap();
+            assert_eq!(processed_image.width(), 32);
+        }
+        let palette = PaletteRGB::load_from_json(get_test_save_absolute_path("pipeline_palette.json")).unwrap();
+        assert_eq!(palette.len(), 2);
+        assert!(palette.contains(&ColorRGB([0, 0, 0])));
+    }
+    
+
+    #[test]
+    fn test_dither_watch_reprocesses_changed_input() {
+        // cargo test --test integration_tests test_dither_watch_reprocesses_changed_input -- --nocapture
+        tests_setup();
+        let absolute_input_dir = get_test_save_absolute_path("watch_frames");
+        let absolute_output_dir = get_test_save_absolute_path("watch_dithered");
+        let absolute_palette_path = get_palette_absolute_path(PRIMARY_PALETTE_FILENAME);
+        std::fs::create_dir_all(&absolute_input_dir).unwrap();
+        let frame = generate_test_gradient_image(32, 8, Rgb::<u8>([0,0,0]), Rgb::<u8>([255,0,0]));
+        image::save_image(absolute_input_dir.join("frame.png"), &frame).unwrap();
+
+        let wait_for_file = |path: &std::path::Path| {
+            let started = std::time::Instant::now();
+            while !path.exists() {
+                assert!(started.elapsed() < std::time::Duration::from_secs(30), "{path:?} not created");
+                std::thread::sleep(std::time::Duration::from_millis(20));
+            }
+        };
+
+        let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin("ditherum"))
+            .arg("dither")
+            .arg("-i")
+            .arg(&absolute_input_dir)
+            .arg("-p")
+            .arg(&absolute_palette_path)
+            .arg("-o")
+            .arg(&absolute_output_dir)
+            .arg("--watch")
+            .arg("--watch-interval")
+            .arg("20")
+            .stdout(std::process::Stdio::null())
+            .spawn()
+            .unwrap();
+
+        // Existing image is processed at start, new one as soon as it appears
+        wait_for_file(&absolute_output_dir.join("frame.png"));
+        image::save_image(absolute_input_dir.join("frame_new.png"), &frame).unwrap();
+        wait_for_file(&absolute_output_dir.join("frame_new.png"));
+
+        let still_running = child.try_wait().unwrap().is_none();
+        child.kill().unwrap();
+        child.wait().unwrap();
+        assert!(still_running);
+    }
+    
+
+    #[test]
+    fn test_dither_multiple_outputs() {
+        // cargo test --test integration_tests test_dither_multiple_outputs -- --nocapture
+        tests_setup();
+        let colors_count = 4;
+        let absolute_input_path = get_test_image_absolute_path(COLOR_GRASS300_IMAGE_FILENAME);
+        let absolute_output_path = get_test_save_absolute_path("multi_output.png");
+        let absolute_header_path = get_test_save_absolute_path("multi_output.h");
+        let absolute_swatch_path = get_test_save_absolute_path("multi_output_swatch.png");
+        let absolute_report_path = get_test_save_absolute_path("multi_output_report.json");
+
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("dither")
+            .arg("-i")
+            .arg(&absolute_input_path)
+            .arg("-W")
+            .arg("40")
+            .arg("-c")
+            .arg(colors_count.to_string())
+            .arg("-o")
+            .arg(&absolute_output_path)
+            .arg("--export-c")
+            .arg(&absolute_header_path)
+            .arg("--swatch-out")
+            .arg(&absolute_swatch_path)
+            .arg("--report")
+            .arg(&absolute_report_path);
+        let output = cmd.output().unwrap();
+        assert!(output.status.success(), "cmd output={output:?}.");
+
+        let processed_image = image::load_image(&absolute_output_path).unwrap();
+        let header = std::fs::read_to_string(&absolute_header_path).unwrap();
+        assert!(header.contains("#define MULTI_OUTPUT_WIDTH 40"), "{header}");
+        assert!(header.contains(&format!("#define MULTI_OUTPUT_PALETTE_SIZE {colors_count}")), "{header}");
+
+        let swatch = image::load_image(&absolute_swatch_path).unwrap();
+        assert_eq!(swatch.height() * colors_count, swatch.width());
+
+        let report: serde_json::Value = serde_json::from_slice(&std::fs::read(&absolute_report_path).unwrap()).unwrap();
+        assert_eq!(report["width"], processed_image.width());
+        assert_eq!(report["palette"].as_array().unwrap().len(), colors_count as usize);
+        assert!(report["quality"]["mean_delta_e"].as_f64().unwrap() > 0.0);
+    }
+
+    #[test]
+    fn test_export_svg() {
+        // cargo test --test integration_tests test_export_svg -- --nocapture
+        tests_setup();
+        let colors_count = 3;
+        let absolute_input_path = get_test_image_absolute_path(COLOR_GRASS300_IMAGE_FILENAME);
+        let absolute_output_path = get_test_save_absolute_path("export_svg.png");
+        let absolute_svg_path = get_test_save_absolute_path("export_svg.svg");
+
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("dither")
+            .arg("-i")
+            .arg(&absolute_input_path)
+            .arg("-W")
+            .arg("60")
+            .arg("-c")
+            .arg(colors_count.to_string())
+            .arg("-a")
+            .arg("threshold-lab")
+            .arg("-o")
+            .arg(&absolute_output_path)
+            .arg("--export-svg")
+            .arg(&absolute_svg_path);
+        let output = cmd.output().unwrap();
+        assert!(output.status.success(), "cmd output={output:?}.");
+
+        let processed_image = image::load_image(&absolute_output_path).unwrap();
+        let svg = std::fs::read_to_string(&absolute_svg_path).unwrap();
+        assert!(svg.contains(&format!(r#"viewBox="0 0 60 {}""#, processed_image.height())), "{svg}");
+        assert_eq!(svg.matches("<path ").count(), image::count_image_colors(&processed_image).len());
+        assert!(svg.trim_end().ends_with("</svg>"));
+    }
+
+    #[test]
+    fn test_artifact_chart_and_csv() {
+        // cargo test --test integration_tests test_artifact_chart_and_csv -- --nocapture
+        tests_setup();
+        let colors_count = 4;
+        let chart_width = 30;
+        let absolute_input_path = get_test_image_absolute_path(COLOR_GRASS300_IMAGE_FILENAME);
+        let absolute_chart_path = get_test_save_absolute_path("artifact_chart.png");
+        let absolute_csv_path = get_test_save_absolute_path("artifact_chart.csv");
+        let absolute_counts_path = get_test_save_absolute_path("artifact_counts.csv");
+
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("artifact")
+            .arg("-i")
+            .arg(&absolute_input_path)
+            .arg("-W")
+            .arg(chart_width.to_string())
+            .arg("-c")
+            .arg(colors_count.to_string())
+            .arg("-o")
+            .arg(&absolute_chart_path)
+            .arg("--csv")
+            .arg(&absolute_csv_path)
+            .arg("--counts")
+            .arg(&absolute_counts_path);
+        let output = cmd.output().unwrap();
+        assert!(output.status.success(), "cmd output={output:?}.");
+
+        let chart = image::load_image(&absolute_chart_path).unwrap();
+        assert!(chart.width() > chart_width * 16);
+
+        let csv = std::fs::read_to_string(&absolute_csv_path).unwrap();
+        let rows = csv.lines().collect::<Vec<_>>();
+        assert!(rows.iter().all(|row| row.split(',').count() == chart_width as usize), "{csv}");
+
+        let counts = std::fs::read_to_string(&absolute_counts_path).unwrap();
+        let total_count = counts.lines()
+            .skip(1)
+            .map(|line| line.rsplit(',').next().unwrap().parse::<usize>().unwrap())
+            .sum::<usize>();
+        assert_eq!(counts.lines().count(), colors_count as usize + 1);
+        assert_eq!(total_count, rows.len() * chart_width as usize);
+    }
+
+    #[test]
+    fn test_artifact_rejects_oversized_chart() {
+        // cargo test --test integration_tests test_artifact_rejects_oversized_chart -- --nocapture
+        tests_setup();
+        let absolute_input_path = get_test_image_absolute_path(COLOR_GRASS300_IMAGE_FILENAME);
+        let absolute_chart_path = get_test_save_absolute_path("artifact_oversized_chart.png");
+
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("artifact")
+            .arg("-i")
+            .arg(&absolute_input_path)
+            .arg("-W")
+            .arg("2000")
+            .arg("-o")
+            .arg(&absolute_chart_path);
+        let output = cmd.output().unwrap();
+        assert!(!output.status.success(), "cmd output={output:?}.");
+        assert!(String::from_utf8_lossy(&output.stderr).contains("-W/-H"), "cmd output={output:?}.");
+        assert!(!absolute_chart_path.exists());
+    }
+
+    #[cfg(feature = "pdf")]
+    #[test]
+    fn test_artifact_print_chart() {
+        // cargo test --features pdf --test integration_tests test_artifact_print_chart -- --nocapture
+        tests_setup();
+        let absolute_input_path = get_test_image_absolute_path(COLOR_PINK300_IMAGE_FILENAME);
+        let absolute_chart_path = get_test_save_absolute_path("print_chart.png");
+        let absolute_pdf_path = get_test_save_absolute_path("print_chart.pdf");
+
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("artifact")
+            .arg("-i")
+            .arg(&absolute_input_path)
+            .arg("-W")
+            .arg("40")
+            .arg("-c")
+            .arg("6")
+            .arg("-o")
+            .arg(&absolute_chart_path)
+            .arg("--print-chart")
+            .arg(&absolute_pdf_path)
+            .arg("--page")
+            .arg("letter");
+        let output = cmd.output().unwrap();
+        assert!(output.status.success(), "cmd output={output:?}.");
+
+        // Chart page and single legend page, both portrait letter
+        let document = printpdf::lopdf::Document::load(&absolute_pdf_path).unwrap();
+        let pages = document.get_pages();
+        assert_eq!(pages.len(), 2);
+        for page_id in pages.values() {
+            let media_box = document.get_dictionary(*page_id).unwrap()
+                .get(b"MediaBox").unwrap()
+                .as_array().unwrap()
+                .iter()
+                .map(|value| value.as_float().unwrap())
+                .collect::<Vec<_>>();
+            assert!((media_box[2] - 612.0).abs() < 0.1 && (media_box[3] - 792.0).abs() < 0.1, "{media_box:?}");
+        }
+    }
+
+    #[test]
+    fn test_trace_json() {
+        // cargo test --test integration_tests test_trace_json -- --nocapture
+        tests_setup();
+        let absolute_input_path = get_test_image_absolute_path(COLOR_PINK300_IMAGE_FILENAME);
+        let absolute_output_path = get_test_save_absolute_path("trace_json.png");
+        let absolute_trace_path = get_test_save_absolute_path("trace_json.jsonl");
+
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("--trace-json")
+            .arg(&absolute_trace_path)
+            .arg("dither")
+            .arg("-i")
+            .arg(&absolute_input_path)
+            .arg("-o")
+            .arg(&absolute_output_path);
+        let output = cmd.output().unwrap();
+        assert!(output.status.success(), "cmd output={output:?}.");
+
+        let trace = std::fs::read_to_string(&absolute_trace_path).unwrap();
+        let events = trace.lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .collect::<Vec<_>>();
+        let closed_span = |name: &str| events.iter().find(|event| event["span"]["name"] == name);
+        for name in ["dither_image", "palette_extraction", "processing"] {
+            let event = closed_span(name).unwrap_or_else(|| panic!("span {name} missing"));
+            assert_eq!(event["fields"]["message"], "close");
+            assert!(event["fields"]["time.busy"].is_string());
+        }
+    }
+
+    #[test]
+    fn test_dither_verbose_report() {
+        // cargo test --test integration_tests test_dither_verbose_report -- --nocapture
+        tests_setup();
+        let absolute_input_path = get_test_image_absolute_path(COLOR_PINK300_IMAGE_FILENAME);
+        let absolute_output_path = get_test_save_absolute_path("verbose_report.png");
+
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("-v")
+            .arg("dither")
+            .arg("-i")
+            .arg(&absolute_input_path)
+            .arg("-W")
+            .arg("64")
+            .arg("-c")
+            .arg("5")
+            .arg("-o")
+            .arg(&absolute_output_path);
+        let output = cmd.output().unwrap();
+        assert!(output.status.success(), "cmd output={output:?}.");
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let processed_image = image::load_image(&absolute_output_path).unwrap();
+        assert!(stderr.contains("Palette size: 5"), "{stderr}");
+        assert!(stderr.contains(&format!("Unique output colors: {}", image::count_image_colors(&processed_image).len())), "{stderr}");
+        assert!(stderr.contains("Mean \u{0394}E: "), "{stderr}");
+        assert!(stderr.contains("Dithering: "), "{stderr}");
+    }
+
+    #[test]
+    fn test_dither_dry_run() {
+        // cargo test --test integration_tests test_dither_dry_run -- --nocapture
+        tests_setup();
+        let absolute_input_path = get_test_image_absolute_path(COLOR_PINK300_IMAGE_FILENAME);
+        let absolute_output_path = get_test_save_absolute_path("dry_run.png");
+        let absolute_report_path = get_test_save_absolute_path("dry_run.json");
+        let _ = std::fs::remove_file(&absolute_output_path);
+        let _ = std::fs::remove_file(&absolute_report_path);
+
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("dither")
+            .arg("-i")
+            .arg(&absolute_input_path)
+            .arg("-W")
+            .arg("100")
+            .arg("-o")
+            .arg(&absolute_output_path)
+            .arg("--report")
+            .arg(&absolute_report_path)
+            .arg("--dry-run");
+        let output = cmd.output().unwrap();
+        assert!(output.status.success(), "cmd output={output:?}.");
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.contains("300x326 -> "), "{stdout}");
+        assert!(stdout.contains(" 100x109"), "{stdout}");
+        assert!(stdout.contains("Estimated peak memory"), "{stdout}");
+        assert!(!absolute_output_path.exists());
+        assert!(!absolute_report_path.exists());
+
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("dither")
+            .arg("-i")
+            .arg(get_test_image_absolute_path("not_existing_image.png"))
+            .arg("--dry-run");
+        let output = cmd.output().unwrap();
+        assert_eq!(output.status.code(), Some(3));
+    }
+
+    #[test]
+    fn test_dither_output_template_no_clobber() {
+        // cargo test --test integration_tests test_dither_output_template_no_clobber -- --nocapture
+        tests_setup();
+        let absolute_input_path = get_test_image_absolute_path(COLOR_PINK300_IMAGE_FILENAME);
+        let absolute_template_path = get_test_save_absolute_path("template/{stem}_{algo}_{colors}_{width}.png");
+        let absolute_output_path = get_test_save_absolute_path("template/test_pink_300_bayer4_3_50.png");
+        let _ = std::fs::remove_file(&absolute_output_path);
+
+        let dither_command = |extra_args: &[&str]| {
+            let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+            cmd
+                .arg("dither")
+                .arg("-i")
+                .arg(&absolute_input_path)
+                .arg("-W")
+                .arg("50")
+                .arg("-c")
+                .arg("3")
+                .arg("-a")
+                .arg("bayer4")
+                .arg("-o")
+                .arg(&absolute_template_path)
+                .args(extra_args);
+            cmd.output().unwrap()
+        };
+
+        let output = dither_command(&["--no-clobber"]);
+        assert!(output.status.success(), "cmd output={output:?}.");
+        let processed_image = image::load_image(&absolute_output_path).unwrap();
+        assert_eq!(processed_image.width(), 50);
+        let modified = std::fs::metadata(&absolute_output_path).unwrap().modified().unwrap();
+
+        let output = dither_command(&["--no-clobber"]);
+        assert_eq!(output.status.code(), Some(1));
+        assert!(String::from_utf8_lossy(&output.stderr).contains("already exists"));
+        assert_eq!(std::fs::metadata(&absolute_output_path).unwrap().modified().unwrap(), modified);
+
+        let output = dither_command(&["--no-clobber", "--force"]);
+        assert!(output.status.success(), "cmd output={output:?}.");
+    }
+
+    #[test]
+    fn test_dither_keep_metadata() {
+        // cargo test --test integration_tests test_dither_keep_metadata -- --nocapture
+        tests_setup();
+        let source_image = image::load_image(get_test_image_absolute_path(COLOR_PINK300_IMAGE_FILENAME)).unwrap();
+        let absolute_input_path = get_test_save_absolute_path("test_keep_metadata_input.png");
+        let absolute_output_path = get_test_save_absolute_path("test_keep_metadata_output.png");
+
+        let mut encoded = Vec::new();
+        image::save_image_to_writer(&mut encoded, &source_image, ::image::ImageFormat::Png).unwrap();
+        let metadata = ImageMetadata {
+            icc_profile: Some(vec![7; 64]),
+            exif: None,
+            text: vec![("Author".to_string(), "Ditherum".to_string())],
+        };
+        std::fs::write(&absolute_input_path, metadata.embed(&encoded, ::image::ImageFormat::Png)).unwrap();
+
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        let output = cmd
+            .arg("dither")
+            .arg("-i")
+            .arg(&absolute_input_path)
+            .arg("-o")
+            .arg(&absolute_output_path)
+            .arg("-W")
+            .arg("50")
+            .arg("-c")
+            .arg("3")
+            .arg("--keep-metadata")
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "cmd output={output:?}.");
+
+        let output_metadata = ImageMetadata::read(&absolute_output_path).unwrap();
+        assert_eq!(output_metadata.icc_profile, metadata.icc_profile);
+        assert_eq!(output_metadata.text, metadata.text);
+        assert_eq!(image::load_image(&absolute_output_path).unwrap().width(), 50);
+    }
+
+    #[test]
+    fn test_dither_tileable() {
+        // cargo test --test integration_tests test_dither_tileable -- --nocapture
+        tests_setup();
+        let absolute_input_path = get_test_save_absolute_path("tileable_input.png");
+        let absolute_output_path = get_test_save_absolute_path("tileable_dithered.png");
+        let palette_path = get_test_save_absolute_path("tileable_palette.json");
+        std::fs::write(&palette_path, r#"["black", "white"]"#).unwrap();
+        image::save_image(&absolute_input_path, &::image::RgbImage::from_pixel(48, 32, ::image::Rgb([128, 128, 128]))).unwrap();
+
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("dither")
+            .arg("-i")
+            .arg(&absolute_input_path)
+            .arg("-p")
+            .arg(&palette_path)
+            .arg("-a")
+            .arg("fs-rgb")
+            .arg("--tileable")
+            .arg("-o")
+            .arg(&absolute_output_path);
+        let output = cmd.output().unwrap();
+        assert!(output.status.success(), "cmd output={output:?}.");
+
+        // Half of pixels across the seams are white, like anywhere inside
+        let loaded_image = image::load_image(absolute_output_path).unwrap();
+        let is_white = |x, y| loaded_image.get_pixel(x, y).0 == [255, 255, 255];
+        assert_eq!((0..32).flat_map(|y| [is_white(47, y), is_white(0, y)]).filter(|&white| white).count(), 32);
+        assert_eq!((0..48).flat_map(|x| [is_white(x, 31), is_white(x, 0)]).filter(|&white| white).count(), 48);
+    }
+
+    #[cfg(feature = "server")]
+    #[test]
+    fn test_ditherer_server() {
+        // cargo test --features server --test integration_tests test_ditherer_server -- --nocapture
+        use std::io::{BufRead, BufReader, Read, Write};
+
+        tests_setup();
+        let image_bytes = std::fs::read(get_test_image_absolute_path(COLOR_PINK300_IMAGE_FILENAME)).unwrap();
+        let mut server = std::process::Command::new(assert_cmd::cargo::cargo_bin("ditherer"))
+            .args(["--bind", "127.0.0.1:0", "--max-upload", "1000000"])
+            .stdout(std::process::Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut address_line = String::new();
+        BufReader::new(server.stdout.take().unwrap()).read_line(&mut address_line).unwrap();
+        let address = address_line.trim().trim_start_matches("Listening on http://").to_string();
+
+        // Sends multipart form, returns status code and response body
+        let post = |path: &str, fields: &[(&str, &[u8])]| {
+            let boundary = "ditherum-test-boundary";
+            let mut body = Vec::new();
+            for (name, value) in fields {
+                let filename = if *name == "image" { "; filename=\"upload\"" } else { "" };
+                write!(body, "--{boundary}\r\nContent-Disposition: form-data; name=\"{name}\"{filename}\r\n\r\n").unwrap();
+                body.extend_from_slice(value);
+                body.extend_from_slice(b"\r\n");
+            }
+            write!(body, "--{boundary}--\r\n").unwrap();
+
+            let mut stream = std::net::TcpStream::connect(&address).unwrap();
+            write!(stream, "POST {path} HTTP/1.1\r\nHost: {address}\r\nContent-Type: multipart/form-data; boundary={boundary}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", body.len()).unwrap();
+            stream.write_all(&body).unwrap();
+            let mut response = Vec::new();
+            stream.read_to_end(&mut response).unwrap();
+
+            let header_end = response.windows(4).position(|window| window == b"\r\n\r\n").unwrap();
+            let status = String::from_utf8_lossy(&response[9..12]).parse::<u16>().unwrap();
+            (status, response[header_end + 4..].to_vec())
+        };
+
+        let (status, body) = post("/dither", &[("image", &image_bytes), ("colors", b"3"), ("width", b"50")]);
+        assert_eq!(status, 200, "{}", String::from_utf8_lossy(&body));
+        let processed_image = ::image::load_from_memory(&body).unwrap().to_rgb8();
+        assert_eq!(processed_image.width(), 50);
+        assert!(image::count_image_colors(&processed_image).len() <= 3);
+
+        let (status, body) = post("/palette", &[("image", &image_bytes), ("colors", b"4")]);
+        assert_eq!(status, 200);
+        assert_eq!(PaletteRGB::load_from_json_reader(body.as_slice()).unwrap().len(), 4);
+
+        assert_eq!(post("/dither", &[("image", &image_bytes), ("colour", b"4")]).0, 400);
+        assert_eq!(post("/dither", &[("image", b"not an image")]).0, 415);
+        assert_eq!(post("/dither", &[("image", &vec![0; 1_100_000])]).0, 413);
+
+        server.kill().unwrap();
+        server.wait().unwrap();
+    }
+
+    #[test]
+    fn test_dither_batch_cache_dir() {
+        // cargo test --test integration_tests test_dither_batch_cache_dir -- --nocapture
+        tests_setup();
+        let absolute_input_dir = get_test_save_absolute_path("batch_cache_frames");
+        let absolute_output_dir = get_test_save_absolute_path("batch_cache_dithered");
+        let absolute_cache_dir = get_test_save_absolute_path("batch_cache");
+        let _ = std::fs::remove_dir_all(&absolute_cache_dir);
+        std::fs::create_dir_all(&absolute_input_dir).unwrap();
+        let frame = generate_test_gradient_image(64, 8, Rgb::<u8>([0,0,255]), Rgb::<u8>([255,255,0]));
+        image::save_image(absolute_input_dir.join("frame.png"), &frame).unwrap();
+
+        let run_batch = || {
+            let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+            cmd
+                .arg("dither")
+                .arg("-i")
+                .arg(&absolute_input_dir)
+                .arg("-c")
+                .arg("4")
+                .arg("--cache-dir")
+                .arg(&absolute_cache_dir)
+                .arg("-o")
+                .arg(&absolute_output_dir);
+            let output = cmd.output().unwrap();
+            assert!(output.status.success(), "cmd output={output:?}.");
+            let stdout = String::from_utf8(output.stdout).unwrap();
+            let dithered_image = image::load_image(absolute_output_dir.join("frame.png")).unwrap();
+            (stdout, dithered_image)
+        };
+
+        let (first_stdout, first_image) = run_batch();
+        assert!(first_stdout.contains("[ok]"), "stdout={first_stdout}");
+
+        // Unchanged input is not processed again, result is the same
+        let (second_stdout, second_image) = run_batch();
+        assert!(second_stdout.contains("[cached]"), "stdout={second_stdout}");
+        assert_eq!(first_image, second_image);
+    }
+
+    #[test]
+    fn test_dither_thumbnail() {
+        // cargo test --test integration_tests test_dither_thumbnail -- --nocapture
+        tests_setup();
+        let absolute_input_path = get_test_image_absolute_path(COLOR_PINK300_IMAGE_FILENAME);
+        let absolute_output_path = get_test_save_absolute_path("thumbnail_dithered.png");
+
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("dither")
+            .arg("-i")
+            .arg(&absolute_input_path)
+            .arg("-c")
+            .arg("4")
+            .arg("--thumbnail")
+            .arg("1200x630")
+            .arg("--pad-color")
+            .arg("white")
+            .arg("-o")
+            .arg(&absolute_output_path);
+        let output = cmd.output().unwrap();
+        assert!(output.status.success(), "cmd output={output:?}.");
+
+        // Input is smaller, so it is padded to exact dimensions
+        let loaded_image = image::load_image(&absolute_output_path).unwrap();
+        assert_eq!(loaded_image.dimensions(), (1200, 630));
+        assert!(image::count_image_colors(&loaded_image).len() <= 5);
+    }
+
+    #[test]
+    fn test_compare_stamp() {
+        // cargo test --test integration_tests test_compare_stamp -- --nocapture
+        tests_setup();
+        let absolute_input_path = get_test_image_absolute_path(COLOR_PINK300_IMAGE_FILENAME);
+        let absolute_palette_path = get_test_save_absolute_path("compare_stamp_palette.json");
+        let absolute_output_path = get_test_save_absolute_path("compare_stamp.png");
+        std::fs::write(&absolute_palette_path, r#"["red", "yellow"]"#).unwrap();
+
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("compare")
+            .arg("-i")
+            .arg(&absolute_input_path)
+            .arg("-W")
+            .arg("96")
+            .arg("-a")
+            .arg("bayer4")
+            .arg("-p")
+            .arg(&absolute_palette_path)
+            .arg("--stamp")
+            .arg("-o")
+            .arg(&absolute_output_path);
+        let output = cmd.output().unwrap();
+        assert!(output.status.success(), "cmd output={output:?}.");
+
+        // Black caption box is the only black area, palette has no black
+        let sheet = image::load_image(&absolute_output_path).unwrap();
+        assert!(image::count_image_colors(&sheet).contains_key(&Rgb([0, 0, 0])));
+    }
+
+    #[test]
+    fn test_dither_linear_area_filter() {
+        // cargo test --test integration_tests test_dither_linear_area_filter -- --nocapture
+        tests_setup();
+        let absolute_input_path = get_test_image_absolute_path(COLOR_PINK300_IMAGE_FILENAME);
+        let absolute_output_path = get_test_save_absolute_path("linear_area_dithered.png");
+
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("dither")
+            .arg("-i")
+            .arg(&absolute_input_path)
+            .arg("-W")
+            .arg("75")
+            .arg("--filter")
+            .arg("linear-area")
+            .arg("-c")
+            .arg("4")
+            .arg("-o")
+            .arg(&absolute_output_path);
+        let output = cmd.output().unwrap();
+        assert!(output.status.success(), "cmd output={output:?}.");
+
+        let loaded_image = image::load_image(&absolute_output_path).unwrap();
+        assert_eq!(loaded_image.width(), 75);
+        assert!(image::count_image_colors(&loaded_image).len() <= 4);
+    }
+
+    #[test]
+    fn test_dither_supersample() {
+        // cargo test --test integration_tests test_dither_supersample -- --nocapture
+        tests_setup();
+        let absolute_input_path = get_test_image_absolute_path(COLOR_PINK300_IMAGE_FILENAME);
+        let absolute_output_path = get_test_save_absolute_path("supersample_dithered.png");
+
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("dither")
+            .arg("-i")
+            .arg(&absolute_input_path)
+            .arg("-W")
+            .arg("60")
+            .arg("-c")
+            .arg("2")
+            .arg("--supersample")
+            .arg("2")
+            .arg("-o")
+            .arg(&absolute_output_path);
+        let output = cmd.output().unwrap();
+        assert!(output.status.success(), "cmd output={output:?}.");
+
+        // Dimensions kept, 2x2 blocks of 2 colors average to more tones
+        let loaded_image = image::load_image(&absolute_output_path).unwrap();
+        assert_eq!(loaded_image.width(), 60);
+        assert!(image::count_image_colors(&loaded_image).len() > 2);
+
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("dither")
+            .arg("-i")
+            .arg(&absolute_input_path)
+            .arg("--supersample")
+            .arg("2")
+            .arg("--pixelate")
+            .arg("4");
+        cmd.assert().failure();
+    }
+
+    #[test]
+    fn test_dither_chroma_subsample() {
+        // cargo test --test integration_tests test_dither_chroma_subsample -- --nocapture
+        tests_setup();
+        let absolute_input_path = get_test_image_absolute_path(COLOR_PINK300_IMAGE_FILENAME);
+        let absolute_output_path = get_test_save_absolute_path("chroma_subsample_dithered.png");
+
+        let mut cmd: Command = Command::cargo_bin("ditherum").unwrap();
+        cmd
+            .arg("dither")
+            .arg("-i")
+            .arg(&absolute_input_path)
+            .arg("-W")
+            .arg("64")
+            .arg("-c")
+            .arg("4")
+            .arg("--chroma-subsample")
+            .arg("-o")
+            .arg(&absolute_output_path);
+        let output = cmd.output().unwrap();
+        assert!(output.status.success(), "cmd output={output:?}.");
+
+        let loaded_image = image::load_image(&absolute_output_path).unwrap();
+        assert_eq!(loaded_image.width(), 64);
+        assert!(image::count_image_colors(&loaded_image).len() <= 4);
+    }
+
+    #[test]
+    fn test_dither_usage_report() {
+        tests_setup();
+        let input_path = get_test_image_absolute_path(COLOR_PINK300_IMAGE_FILENAME);
+        let output_path = get_test_save_absolute_path("usage_report_output.png");
+        let usage_path = get_test_save_absolute_path("usage_report.json");
+
+        let mut cmd = Command::cargo_bin("ditherum").unwrap();
+        cmd.arg("dither")
+            .arg("-i").arg(&input_path)
+            .arg("-o").arg(&output_path)
+            .arg("-c").arg("4")
+            .arg("--usage-report").arg(&usage_path);
+        cmd.assert().success();
+
+        let output_image = image::load_image(&output_path).unwrap();
+        let usage: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&usage_path).unwrap()).unwrap();
+        let colors = usage["colors"].as_array().unwrap();
+        assert!(!colors.is_empty() && colors.len() <= 4);
+        assert!(colors.iter().all(|usage| usage["color"].as_str().unwrap().starts_with('#')));
+
+        let counted = colors.iter().map(|usage| usage["pixels"].as_u64().unwrap()).sum::<u64>()
+            + usage["other_pixels"].as_u64().unwrap();
+        assert_eq!(counted, (output_image.width() * output_image.height()) as u64);
+    }
+
+    #[test]
+    fn test_dither_prune_palette() {
+        tests_setup();
+        let input_path = get_test_image_absolute_path(COLOR_PINK300_IMAGE_FILENAME);
+        let output_path = get_test_save_absolute_path("prune_palette_output.png");
+        let palette_path = get_test_save_absolute_path("prune_palette.json");
+
+        let mut cmd = Command::cargo_bin("ditherum").unwrap();
+        cmd.arg("dither")
+            .arg("-i").arg(&input_path)
+            .arg("-o").arg(&output_path)
+            .arg("-c").arg("8")
+            .arg("-r").arg(&palette_path)
+            .arg("--prune-palette").arg("10");
+        cmd.assert().success();
+
+        let output_image = image::load_image(&output_path).unwrap();
+        let palette = PaletteRGB::load_from_json(&palette_path).unwrap();
+        let colors = image::count_image_colors(&output_image);
+        let pixels_count = (output_image.width() * output_image.height()) as usize;
+        assert!(!palette.is_empty());
+        assert_eq!(colors.len(), palette.len());
+        for color in palette.iter() {
+            let pixels = colors.get(&color.to_rgbu8()).copied().unwrap_or(0);
+            assert!(pixels * 10 >= pixels_count, "{} covers {pixels} of {pixels_count} pixels", color.to_hex());
+        }
+    }
+
+    #[test]
+    fn test_verify() {
+        tests_setup();
+        let input_path = get_test_save_absolute_path("verify_input.png");
+        let palette_path = get_test_save_absolute_path("verify_palette.json");
+        let mut img = generate_test_gradient_image(8, 4, Rgb::<u8>([0, 0, 0]), Rgb::<u8>([0, 0, 0]));
+        img.put_pixel(3, 2, Rgb::<u8>([255, 0, 0]));
+        image::save_image(&input_path, &img).unwrap();
+        PaletteRGB::black_and_white().save_to_json(&palette_path).unwrap();
+
+        let mut cmd = Command::cargo_bin("ditherum").unwrap();
+        cmd.arg("verify")
+            .arg("-i").arg(&input_path)
+            .arg("-p").arg(&palette_path);
+        let output = cmd.assert().failure().code(6).get_output().stdout.clone();
+        let stdout = String::from_utf8(output).unwrap();
+        assert!(stdout.contains("#ff0000 1 pixels, first at (3, 2), closest"), "{stdout}");
+
+        let mut cmd = Command::cargo_bin("ditherum").unwrap();
+        cmd.arg("verify")
+            .arg("-i").arg(&input_path)
+            .arg("-p").arg(&palette_path)
+            .arg("--tolerance").arg("100");
+        cmd.assert().success();
+    }
+
+    #[test]
+    fn test_dither_pinned_palette_order() {
+        tests_setup();
+        let input_path = get_test_image_absolute_path(COLOR_GRASS300_IMAGE_FILENAME);
+        let palette_path = get_test_save_absolute_path("pinned_order_palette.json");
+        let output_path = get_test_save_absolute_path("pinned_order.png");
+        let header_path = get_test_save_absolute_path("pinned_order.h");
+        std::fs::write(&palette_path, r##"{"version": 2, "colors": [
+            "#000000", "#808080",
+            {"rgb": [255, 255, 255], "order": 0},
+            {"rgb": [0, 128, 0], "order": 1}
+        ]}"##).unwrap();
+
+        let mut cmd = Command::cargo_bin("ditherum").unwrap();
+        cmd.arg("dither")
+            .arg("-i").arg(&input_path)
+            .arg("-W").arg("20")
+            .arg("-p").arg(&palette_path)
+            .arg("-o").arg(&output_path)
+            .arg("--export-c").arg(&header_path);
+        cmd.assert().success();
+
+        let header = std::fs::read_to_string(&header_path).unwrap();
+        let palette_start = header.find("_palette[4][3] = {").unwrap();
+        let palette_lines = header[palette_start..].lines().skip(1).take(4).collect::<Vec<_>>();
+        assert_eq!(palette_lines, vec![
+            "    {0xff, 0xff, 0xff},",
+            "    {0x00, 0x80, 0x00},",
+            "    {0x00, 0x00, 0x00},",
+            "    {0x80, 0x80, 0x80},",
+        ], "{header}");
+    }
+
+    #[test]
+    fn test_dither_display_response() {
+        tests_setup();
+        let input_path = get_test_save_absolute_path("display_response_input.png");
+        let palette_path = get_test_save_absolute_path("display_response_palette.json");
+        let lut_path = get_test_save_absolute_path("display_response_lut.txt");
+        image::save_image(&input_path, &::image::RgbImage::from_pixel(4, 4, Rgb::<u8>([200, 200, 200]))).unwrap();
+        PaletteRGB::from(vec![ColorRGB([0, 0, 0]), ColorRGB([128, 128, 128]), ColorRGB([255, 255, 255])]).save_to_json(&palette_path).unwrap();
+        // Panel showing every driven value twice as bright
+        let lut = (0..256).map(|value| (value * 2).min(255).to_string()).collect::<Vec<_>>().join("\n");
+        std::fs::write(&lut_path, format!("# Measured panel\n{lut}\n")).unwrap();
+
+        let dither = |response_args: &[&str], output_name: &str| {
+            let output_path = get_test_save_absolute_path(output_name);
+            let mut cmd = Command::cargo_bin("ditherum").unwrap();
+            cmd.arg("dither")
+                .arg("-i").arg(&input_path)
+                .arg("-p").arg(&palette_path)
+                .arg("-a").arg("threshold-lab")
+                .arg("-o").arg(&output_path)
+                .args(response_args);
+            cmd.assert().success();
+            image::load_image(&output_path).unwrap().get_pixel(0, 0).0
+        };
+        assert_eq!(dither(&[], "display_response_srgb.png"), [255, 255, 255]);
+        assert_eq!(dither(&["--display-response", "led"], "display_response_led.png"), [128, 128, 128]);
+        assert_eq!(dither(&["--display-lut", lut_path.to_str().unwrap()], "display_response_lut.png"), [128, 128, 128]);
+
+        std::fs::write(&lut_path, "0\n255\n").unwrap();
+        let mut cmd = Command::cargo_bin("ditherum").unwrap();
+        cmd.arg("dither")
+            .arg("-i").arg(&input_path)
+            .arg("-p").arg(&palette_path)
+            .arg("--display-lut").arg(&lut_path);
+        cmd.assert().failure().code(3);
+    }
+
+    #[test]
+    fn test_dither_brightness_budget() {
+        tests_setup();
+        let input_path = get_test_image_absolute_path(COLOR_PINK300_IMAGE_FILENAME);
+        let output_path = get_test_save_absolute_path("brightness_budget_output.png");
+
+        let mut cmd = Command::cargo_bin("ditherum").unwrap();
+        cmd.arg("dither")
+            .arg("-i").arg(&input_path)
+            .arg("-o").arg(&output_path)
+            .arg("-c").arg("8")
+            .arg("--brightness-budget").arg("25");
+        cmd.assert().success();
+
+        let output_image = image::load_image(&output_path).unwrap();
+        let brightness = ditherum::metrics::mean_brightness(&output_image);
+        assert!(brightness <= 25.0, "brightness {brightness}");
+    }
+}
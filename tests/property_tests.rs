@@ -0,0 +1,66 @@
+//! Property based tests of invariants which are otherwise spot-checked with a few hand picked values.
+
+use ditherum::{
+    color::ColorRGB,
+    image::{ImageProcessor, ProcessingAlgorithm},
+    palette::PaletteRGB
+};
+use proptest::prelude::*;
+
+/// Maximal difference of each channel after RGB to Lab and back round trip.
+const ROUND_TRIP_CHANNEL_TOLERANCE: u8 = 1;
+
+fn color_strategy() -> impl Strategy<Value = ColorRGB> {
+    any::<[u8; 3]>().prop_map(ColorRGB)
+}
+
+fn image_strategy() -> impl Strategy<Value = image::RgbImage> {
+    (2u32..12, 2u32..12).prop_flat_map(|(width, height)| {
+        proptest::collection::vec(any::<[u8; 3]>(), (width * height) as usize)
+            .prop_map(move |pixels| image::RgbImage::from_fn(width, height, |x, y| image::Rgb(pixels[(y * width + x) as usize])))
+    })
+}
+
+fn palette_strategy(min_len: usize, max_len: usize) -> impl Strategy<Value = PaletteRGB> {
+    proptest::collection::hash_set(color_strategy(), min_len..=max_len)
+        .prop_map(|colors| PaletteRGB::from(colors.into_iter().collect::<Vec<_>>()))
+}
+
+proptest! {
+    #[test]
+    fn prop_rgb_lab_round_trip(color in color_strategy()) {
+        let round_trip = ColorRGB::from_lab(color.to_lab());
+        for (channel, round_trip_channel) in color.0.into_iter().zip(round_trip.0) {
+            prop_assert!(channel.abs_diff(round_trip_channel) <= ROUND_TRIP_CHANNEL_TOLERANCE, "{color:?} became {round_trip:?}");
+        }
+    }
+
+    #[test]
+    fn prop_rgb_srgb_round_trip(color in color_strategy()) {
+        prop_assert_eq!(ColorRGB::from_srgb(color.to_srgb()), color);
+    }
+
+    #[test]
+    fn prop_processing_uses_palette_colors_only(
+        source_image in image_strategy(),
+        palette in palette_strategy(2, 6),
+        algorithm_idx in 0..ProcessingAlgorithm::all().len()
+    ) {
+        let algorithm = ProcessingAlgorithm::all()[algorithm_idx];
+        let processed_image = ImageProcessor::new(source_image.clone(), palette.clone())
+            .with_algorithm(algorithm)
+            .run();
+
+        prop_assert_eq!(processed_image.dimensions(), source_image.dimensions());
+        for pixel in processed_image.pixels() {
+            prop_assert!(palette.contains(&ColorRGB(pixel.0)), "{algorithm} produced {pixel:?} outside of {palette:?}");
+        }
+    }
+
+    #[test]
+    fn prop_reduce_returns_exact_count(palette in palette_strategy(2, 40), target_ratio in 0.0f32..1.0) {
+        let target_count = 1 + (target_ratio * (palette.len() - 1) as f32) as usize;
+        let reduced = palette.try_reduce(target_count).unwrap();
+        prop_assert_eq!(reduced.len(), target_count);
+    }
+}